@@ -1,5 +1,7 @@
 #[cfg(feature = "frontend_abstr_erlang")]
 pub mod abstr_erlang;
+#[cfg(feature = "frontend_core_erlang")]
+pub mod core_erlang;
 #[cfg(feature = "frontend_eir")]
 pub mod eir;
 #[cfg(feature = "frontend_erlang")]
@@ -79,6 +81,8 @@ pub enum AnyFrontend {
     Erlang(erlang::ErlangFrontend),
     #[cfg(feature = "frontend_abstr_erlang")]
     AbstrErlang(abstr_erlang::AbstrErlangFrontend),
+    #[cfg(feature = "frontend_core_erlang")]
+    CoreErlang(core_erlang::CoreErlangFrontend),
     #[cfg(feature = "frontend_eir")]
     Eir(eir::EirFrontend),
 }
@@ -92,6 +96,8 @@ impl DynFrontend for AnyFrontend {
             AnyFrontend::Erlang(front) => front.parse_source_dyn(source),
             #[cfg(feature = "frontend_abstr_erlang")]
             AnyFrontend::AbstrErlang(front) => front.parse_source_dyn(source),
+            #[cfg(feature = "frontend_core_erlang")]
+            AnyFrontend::CoreErlang(front) => front.parse_source_dyn(source),
             #[cfg(feature = "frontend_eir")]
             AnyFrontend::Eir(front) => front.parse_source_dyn(source),
         }
@@ -103,6 +109,8 @@ impl DynFrontend for AnyFrontend {
             AnyFrontend::Erlang(front) => front.parse_string_dyn(source),
             #[cfg(feature = "frontend_abstr_erlang")]
             AnyFrontend::AbstrErlang(front) => front.parse_string_dyn(source),
+            #[cfg(feature = "frontend_core_erlang")]
+            AnyFrontend::CoreErlang(front) => front.parse_string_dyn(source),
             #[cfg(feature = "frontend_eir")]
             AnyFrontend::Eir(front) => front.parse_string_dyn(source),
         }
@@ -114,6 +122,8 @@ impl DynFrontend for AnyFrontend {
             AnyFrontend::Erlang(front) => front.parse_file_dyn(source),
             #[cfg(feature = "frontend_abstr_erlang")]
             AnyFrontend::AbstrErlang(front) => front.parse_file_dyn(source),
+            #[cfg(feature = "frontend_core_erlang")]
+            AnyFrontend::CoreErlang(front) => front.parse_file_dyn(source),
             #[cfg(feature = "frontend_eir")]
             AnyFrontend::Eir(front) => front.parse_file_dyn(source),
         }
@@ -129,6 +139,11 @@ impl From<abstr_erlang::AbstrErlangFrontend> for AnyFrontend {
         AnyFrontend::AbstrErlang(f)
     }
 }
+impl From<core_erlang::CoreErlangFrontend> for AnyFrontend {
+    fn from(f: core_erlang::CoreErlangFrontend) -> Self {
+        AnyFrontend::CoreErlang(f)
+    }
+}
 impl From<eir::EirFrontend> for AnyFrontend {
     fn from(f: eir::EirFrontend) -> Self {
         AnyFrontend::Eir(f)
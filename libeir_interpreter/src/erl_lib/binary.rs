@@ -0,0 +1,367 @@
+use std::rc::Rc;
+
+use libeir_intern::Symbol;
+use libeir_util_binary::{carrier_to_integer, integer_to_carrier, Endian};
+use libeir_util_binary::{BitCarrier, BitSlice, BitVec};
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::module::{NativeModule, NativeReturn};
+use crate::process::ProcessContext;
+use crate::term::Term;
+use crate::vm::VMState;
+
+use super::erlang::badarg;
+
+/// The buffer, bit offset and bit length of a binary term.
+pub(crate) fn binary_slice(term: &Term) -> Option<(Rc<BitVec>, usize, usize)> {
+    match term {
+        Term::Binary(buf) => Some((buf.clone(), 0, buf.bit_len())),
+        Term::BinarySlice {
+            buf,
+            bit_offset,
+            bit_length,
+        } => Some((buf.clone(), *bit_offset, *bit_length)),
+        _ => None,
+    }
+}
+
+/// Copies the bits of a binary term into a new `BitVec`.
+pub(crate) fn binary_bits(term: &Term) -> Option<BitVec> {
+    let (buf, bit_offset, bit_length) = binary_slice(term)?;
+    let slice = BitSlice::with_offset_length(&*buf, bit_offset, bit_length);
+    let mut new = BitVec::new();
+    new.push(slice);
+    Some(new)
+}
+
+/// Copies the bytes of a binary term. Bitstrings that are not a whole
+/// number of bytes are rejected.
+pub(crate) fn binary_bytes(term: &Term) -> Option<Vec<u8>> {
+    let bits = binary_bits(term)?;
+    bits.try_as_byte_aligned_slice().map(|b| b.to_vec())
+}
+
+pub(crate) fn bytes_to_binary(bytes: Vec<u8>) -> Rc<Term> {
+    Term::Binary(Rc::new(bytes.into())).into()
+}
+
+/// Creates a sub binary of `len` bytes from `start`. The new term shares
+/// the buffer of the original binary.
+pub(crate) fn sub_binary(term: &Term, start: usize, len: usize) -> Option<Rc<Term>> {
+    let (buf, bit_offset, bit_length) = binary_slice(term)?;
+    let end = start.checked_add(len)?;
+    if bit_length % 8 != 0 || end * 8 > bit_length {
+        return None;
+    }
+    Some(
+        Term::BinarySlice {
+            buf,
+            bit_offset: bit_offset + start * 8,
+            bit_length: len * 8,
+        }
+        .into(),
+    )
+}
+
+/// Appends the bytes of an iolist to `out`. An iolist is a possibly
+/// improper list of bytes, binaries and other iolists.
+pub(crate) fn iolist_bytes(term: &Rc<Term>, out: &mut Vec<u8>) -> Option<()> {
+    let mut curr = term.clone();
+    loop {
+        let next = match &*curr {
+            Term::Nil => return Some(()),
+            Term::ListCell(head, tail) => {
+                match &**head {
+                    Term::Integer(int) => out.push(int.to_u8()?),
+                    Term::Nil | Term::ListCell(_, _) => iolist_bytes(head, out)?,
+                    _ => out.extend(binary_bytes(head)?),
+                }
+                tail.clone()
+            }
+            _ => {
+                out.extend(binary_bytes(&curr)?);
+                return Some(());
+            }
+        };
+        curr = next;
+    }
+}
+
+/// Resolves a `{Start, Length}` part of a binary of `size` bytes to a
+/// start position and a positive length. A negative length counts
+/// backwards from the start.
+fn part_range(size: usize, start: &Term, len: &Term) -> Option<(usize, usize)> {
+    let start = start.as_integer()?.to_i64()?;
+    let len = len.as_integer()?.to_i64()?;
+    let (start, end) = if len < 0 {
+        (start.checked_add(len)?, start)
+    } else {
+        (start, start.checked_add(len)?)
+    };
+    if start < 0 || end as u64 > size as u64 {
+        return None;
+    }
+    Some((start as usize, (end - start) as usize))
+}
+
+fn byte_size(term: &Term) -> Option<usize> {
+    let (_, _, bit_length) = binary_slice(term)?;
+    if bit_length % 8 == 0 {
+        Some(bit_length / 8)
+    } else {
+        None
+    }
+}
+
+/// `binary:part/2,3` and `erlang:binary_part/2,3`.
+pub(crate) fn part(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    let (start, len) = match args {
+        [_bin, pos_len] => match pos_len.as_tuple() {
+            Some([start, len]) => (start, len),
+            _ => return badarg(),
+        },
+        [_bin, start, len] => (start, len),
+        _ => unreachable!(),
+    };
+
+    let res = byte_size(&args[0])
+        .and_then(|size| part_range(size, start, len))
+        .and_then(|(start, len)| sub_binary(&args[0], start, len));
+    if let Some(term) = res {
+        NativeReturn::Return { term }
+    } else {
+        badarg()
+    }
+}
+
+/// Reads a single pattern or a list of patterns. Empty patterns are not
+/// allowed.
+fn patterns(term: &Rc<Term>) -> Option<Vec<Vec<u8>>> {
+    let patterns = match &**term {
+        Term::ListCell(_, _) => Term::as_list(term)?
+            .iter()
+            .map(|p| binary_bytes(p))
+            .collect::<Option<Vec<_>>>()?,
+        _ => vec![binary_bytes(term)?],
+    };
+    if patterns.iter().any(|p| p.is_empty()) {
+        None
+    } else {
+        Some(patterns)
+    }
+}
+
+/// Finds the first match of any of the patterns at or after `from`. If
+/// several patterns match at the same position the longest one is taken.
+fn find_match(subject: &[u8], patterns: &[Vec<u8>], from: usize) -> Option<(usize, usize)> {
+    for pos in from..subject.len() {
+        let longest = patterns
+            .iter()
+            .filter(|p| subject[pos..].starts_with(p))
+            .map(|p| p.len())
+            .max();
+        if let Some(len) = longest {
+            return Some((pos, len));
+        }
+    }
+    None
+}
+
+fn match_2(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let (subject, patterns) = match (binary_bytes(&args[0]), patterns(&args[1])) {
+        (Some(subject), Some(patterns)) => (subject, patterns),
+        _ => return badarg(),
+    };
+
+    let term = match find_match(&subject, &patterns, 0) {
+        Some((pos, len)) => Term::Tuple(vec![
+            Term::new_usize(pos).into(),
+            Term::new_usize(len).into(),
+        ])
+        .into(),
+        None => Term::new_atom("nomatch").into(),
+    };
+    NativeReturn::Return { term }
+}
+
+fn split(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2 || args.len() == 3);
+    let (subject, patterns) = match (binary_bytes(&args[0]), patterns(&args[1])) {
+        (Some(subject), Some(patterns)) => (subject, patterns),
+        _ => return badarg(),
+    };
+
+    let mut global = false;
+    let mut trim = false;
+    let mut trim_all = false;
+    if let Some(options) = args.get(2) {
+        let options = match Term::as_list(options) {
+            Some(options) => options,
+            None => return badarg(),
+        };
+        for option in options.iter() {
+            match option.as_atom() {
+                Some(a) if a == "global" => global = true,
+                Some(a) if a == "trim" => trim = true,
+                Some(a) if a == "trim_all" => trim_all = true,
+                _ => return badarg(),
+            }
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut pos = 0;
+    while let Some((start, len)) = find_match(&subject, &patterns, pos) {
+        parts.push((pos, start - pos));
+        pos = start + len;
+        if !global {
+            break;
+        }
+    }
+    parts.push((pos, subject.len() - pos));
+
+    if trim_all {
+        parts.retain(|(_, len)| *len > 0);
+    } else if trim {
+        while parts.last().map(|(_, len)| *len == 0).unwrap_or(false) {
+            parts.pop();
+        }
+    }
+
+    let parts: Vec<_> = parts
+        .iter()
+        .map(|(start, len)| sub_binary(&args[0], *start, *len).unwrap())
+        .collect();
+    NativeReturn::Return {
+        term: Term::slice_to_list(&parts, Term::Nil.into()),
+    }
+}
+
+fn copy(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    let times = match args.get(1) {
+        Some(times) => times.as_integer().and_then(|t| t.to_usize()),
+        None => Some(1),
+    };
+    if let (Some(bytes), Some(times)) = (binary_bytes(&args[0]), times) {
+        NativeReturn::Return {
+            term: bytes_to_binary(bytes.repeat(times)),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn at(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let bytes = binary_bytes(&args[0]);
+    let pos = args[1].as_integer().and_then(|p| p.to_usize());
+    match (bytes, pos) {
+        (Some(bytes), Some(pos)) if pos < bytes.len() => NativeReturn::Return {
+            term: Term::new_i64(bytes[pos] as i64).into(),
+        },
+        _ => badarg(),
+    }
+}
+
+fn first(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    match binary_bytes(&args[0]).as_ref().and_then(|b| b.first()) {
+        Some(byte) => NativeReturn::Return {
+            term: Term::new_i64(*byte as i64).into(),
+        },
+        None => badarg(),
+    }
+}
+
+fn last(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    match binary_bytes(&args[0]).as_ref().and_then(|b| b.last()) {
+        Some(byte) => NativeReturn::Return {
+            term: Term::new_i64(*byte as i64).into(),
+        },
+        None => badarg(),
+    }
+}
+
+fn endianness(args: &[Rc<Term>]) -> Option<Endian> {
+    match args.get(1).map(|e| e.as_atom()) {
+        None => Some(Endian::Big),
+        Some(Some(a)) if a == "big" => Some(Endian::Big),
+        Some(Some(a)) if a == "little" => Some(Endian::Little),
+        _ => None,
+    }
+}
+
+fn decode_unsigned(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    let ((buf, bit_offset, bit_length), endian) = match (binary_slice(&args[0]), endianness(args)) {
+        (Some(slice), Some(endian)) if slice.2 % 8 == 0 => (slice, endian),
+        _ => return badarg(),
+    };
+
+    let int = if bit_length == 0 {
+        BigInt::from(0)
+    } else {
+        let slice = BitSlice::with_offset_length(&*buf, bit_offset, bit_length);
+        carrier_to_integer(slice, false, endian)
+    };
+    NativeReturn::Return {
+        term: Term::Integer(int).into(),
+    }
+}
+
+fn encode_unsigned(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    let (int, endian) = match (args[0].as_integer(), endianness(args)) {
+        (Some(int), Some(endian)) if !int.is_negative() => (int, endian),
+        _ => return badarg(),
+    };
+
+    // The smallest number of bytes that fit the integer, at least one
+    let num_bytes = int.to_bytes_be().1.len();
+    let carrier = integer_to_carrier(int.clone(), num_bytes * 8, endian);
+    let mut bits = BitVec::new();
+    bits.push(carrier);
+    NativeReturn::Return {
+        term: Term::Binary(Rc::new(bits)).into(),
+    }
+}
+
+pub fn make_binary() -> NativeModule {
+    let mut module = NativeModule::new(Symbol::intern("binary"));
+    module.add_fun(Symbol::intern("match"), 2, Box::new(match_2));
+    module.add_fun(Symbol::intern("split"), 2, Box::new(split));
+    module.add_fun(Symbol::intern("split"), 3, Box::new(split));
+    module.add_fun(Symbol::intern("part"), 2, Box::new(part));
+    module.add_fun(Symbol::intern("part"), 3, Box::new(part));
+    module.add_fun(Symbol::intern("copy"), 1, Box::new(copy));
+    module.add_fun(Symbol::intern("copy"), 2, Box::new(copy));
+    module.add_fun(Symbol::intern("at"), 2, Box::new(at));
+    module.add_fun(Symbol::intern("first"), 1, Box::new(first));
+    module.add_fun(Symbol::intern("last"), 1, Box::new(last));
+    module.add_fun(
+        Symbol::intern("decode_unsigned"),
+        1,
+        Box::new(decode_unsigned),
+    );
+    module.add_fun(
+        Symbol::intern("decode_unsigned"),
+        2,
+        Box::new(decode_unsigned),
+    );
+    module.add_fun(
+        Symbol::intern("encode_unsigned"),
+        1,
+        Box::new(encode_unsigned),
+    );
+    module.add_fun(
+        Symbol::intern("encode_unsigned"),
+        2,
+        Box::new(encode_unsigned),
+    );
+    module
+}
@@ -1,6 +1,10 @@
-use libeir_intern::Symbol;
+use libeir_intern::{Ident, Symbol};
+use libeir_ir::FunctionIdent;
+use libeir_util_binary::BitCarrier;
 use libeir_util_number::bigint_to_double;
 
+use super::binary::{self, binary_bits, binary_bytes, bytes_to_binary, iolist_bytes, sub_binary};
+use crate::etf;
 use crate::module::{NativeModule, NativeReturn};
 use crate::process::ProcessContext;
 use crate::vm::VMState;
@@ -9,10 +13,43 @@ use crate::term::ListIteratorItem;
 use crate::term::Term;
 use crate::term::{ErlEq, ErlExactEq, ErlOrd};
 
-use ::num_traits::Signed;
+use ::num_bigint::BigInt;
+use ::num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
 
 use std::rc::Rc;
 
+pub(crate) fn badarg() -> NativeReturn {
+    NativeReturn::Throw {
+        typ: Term::new_atom("error").into(),
+        reason: Term::new_atom("badarg").into(),
+    }
+}
+
+fn badarith() -> NativeReturn {
+    NativeReturn::Throw {
+        typ: Term::new_atom("error").into(),
+        reason: Term::new_atom("badarith").into(),
+    }
+}
+
+/// Applies an operation that is only defined on integers. A `None` result
+/// is a `badarith` error.
+fn integer_op<F>(args: &[Rc<Term>], op: F) -> NativeReturn
+where
+    F: FnOnce(&BigInt, &BigInt) -> Option<BigInt>,
+{
+    assert!(args.len() == 2);
+    match (&*args[0], &*args[1]) {
+        (Term::Integer(i1), Term::Integer(i2)) => match op(i1, i2) {
+            Some(res) => NativeReturn::Return {
+                term: Term::Integer(res).into(),
+            },
+            None => badarith(),
+        },
+        _ => badarith(),
+    }
+}
+
 fn abs(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     if args.len() != 1 {
         panic!()
@@ -122,7 +159,15 @@ fn mul(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeRe
         (Term::Integer(ref i1), Term::Integer(ref i2)) => NativeReturn::Return {
             term: Term::Integer(i1.clone() * i2).into(),
         },
-        _ => unimplemented!(),
+        (Term::Integer(ref int), Term::Float(flt)) | (Term::Float(flt), Term::Integer(ref int)) => {
+            NativeReturn::Return {
+                term: Term::Float((bigint_to_double(int) * flt.0).into()).into(),
+            }
+        }
+        (Term::Float(f1), Term::Float(f2)) => NativeReturn::Return {
+            term: Term::Float((f1.0 * f2.0).into()).into(),
+        },
+        _ => badarith(),
     }
 }
 
@@ -134,13 +179,16 @@ fn div(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeRe
     let a1 = match &*args[0] {
         Term::Integer(i1) => bigint_to_double(i1),
         Term::Float(flt) => flt.0,
-        _ => panic!(),
+        _ => return badarith(),
     };
     let a2 = match &*args[1] {
         Term::Integer(i1) => bigint_to_double(i1),
         Term::Float(flt) => flt.0,
-        _ => panic!(),
+        _ => return badarith(),
     };
+    if a2 == 0.0 {
+        return badarith();
+    }
 
     NativeReturn::Return {
         term: Term::Float((a1 / a2).into()).into(),
@@ -234,26 +282,16 @@ fn is_map(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> Nativ
     }
 }
 
-//fn list_append(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    // TODO: Validate semantics
-//    assert!(args.len() == 2);
-//    match (&*args[0], &*args[1]) {
-//        (Term::Nil, Term::Nil) => NativeReturn::Return { term: Term::Nil.into() },
-//        (Term::Nil, Term::List(_, _)) => NativeReturn::Return { term: args[1].clone() },
-//        (Term::List(_, ref tail), Term::Nil) if tail.erl_eq(&Term::Nil)
-//            => NativeReturn::Return { term: args[0].clone() },
-//        (Term::List(ref _f_head, ref _f_tail), Term::List(ref b_head, ref b_tail)) => {
-//            let (mut f_head_terms, f_tail_term) = args[0].as_inproper_list();
-//            if let Term::Nil = f_tail_term {
-//                f_head_terms.extend(b_head.iter().cloned());
-//                NativeReturn::Return { term: Term::List(f_head_terms, b_tail.clone()).into() }
-//            } else {
-//                NativeReturn::Throw
-//            }
-//        }
-//        _ => NativeReturn::Throw,
-//    }
-//}
+fn list_append(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if let Some(head) = Term::as_list(&args[0]) {
+        NativeReturn::Return {
+            term: Term::slice_to_list(&head, args[1].clone()),
+        }
+    } else {
+        badarg()
+    }
+}
 fn list_subtract(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     let (mut to_remove_vec, tail) = Term::as_inproper_list(&args[1]);
     assert!(tail.erl_eq(&Term::Nil));
@@ -361,115 +399,186 @@ fn is_function(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) ->
     }
 }
 
-//fn base_spawn(vm: &VMState, ident: &FunctionIdent, args: Vec<Term>) -> Pid {
-//    let new_pid = {
-//        let mut processes = vm.processes.borrow();
-//        Pid(processes.len())
-//    };
-//
-//    let process = ProcessContext::new(new_pid);
-//
-//    let orig_pid = crate::trace::get_pid();
-//    crate::trace::set_pid(new_pid);
-//    let frame = process.make_call_stackframe(
-//        vm,
-//        ident.module.clone(),
-//        ident.clone(),
-//        args
-//    );
-//    crate::trace::set_pid(orig_pid);
-//
-//    let stack_i = process.stack.clone();
-//    let mut stack = stack_i.borrow_mut();
-//    stack.push(frame);
-//
-//    {
-//        let mut processes = vm.processes.borrow_mut();
-//        processes.push(Rc::new(RefCell::new(process)));
-//    }
-//
-//    {
-//        let mut mailboxes = vm.mailboxes.borrow_mut();
-//        mailboxes.insert(new_pid, ::mailbox::Mailbox::new());
-//    }
-//
-//    new_pid
-//}
-//
-//fn base_spawn_term(vm: &VMState, callable: &Term, mut args: Vec<Term>) -> Pid {
-//    match callable {
-//        Term::CapturedFunction { module, fun_name, arity } => {
-//            let ident = FunctionIdent {
-//                module: module.clone(),
-//                name: fun_name.clone(),
-//                arity: *arity,
-//                lambda: None,
-//            };
-//            base_spawn(vm, &ident, args)
-//        }
-//        Term::BoundLambda { module, fun_name, arity, lambda, bound_env } => {
-//            let ident = FunctionIdent {
-//                module: module.clone(),
-//                name: fun_name.clone(),
-//                arity: *arity,
-//                lambda: Some(*lambda),
-//            };
-//            args.insert(0, Term::LambdaEnv(bound_env.clone()));
-//            base_spawn(vm, &ident, args)
-//        },
-//        _ => panic!(),
-//    }
-//}
-//
-//fn base_monitor(vm: &VMState, proc: &mut ProcessContext, other: Pid) -> Reference {
-//    let monitor_ref = vm.ref_gen.borrow_mut().next();
-//    let mut watches = vm.watches.borrow_mut();
-//
-//    if !watches.contains_key(&other) {
-//        watches.insert(other, Vec::new());
-//    }
-//    let for_proc = watches.get_mut(&other).unwrap();
-//
-//    for_proc.push((proc.pid, WatchType::Monitor(monitor_ref)));
-//
-//    monitor_ref
-//}
-//
-//fn spawn_1(vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 1);
-//    let fun_term = &*args[0];
-//
-//    let new_pid = base_spawn_term(vm, fun_term, vec![]);
-//    NativeReturn::Return { term: Term::Pid(new_pid) }
-//}
-//
-//fn spawn_monitor_1(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 1);
-//    let fun_term = &*args[0];
-//
-//    let new_pid = base_spawn_term(vm, fun_term, vec![]);
-//    let monitor_ref = base_monitor(vm, proc, new_pid);
-//
-//    let term = Term::Tuple(vec![
-//        Term::Pid(new_pid),
-//        Term::Reference(monitor_ref),
-//    ]);
-//    NativeReturn::Return { term: term }
-//}
-//
-//fn monitor_2(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 2);
-//    if args[0].erl_eq(&Term::new_atom("process")) {
-//        if let Term::Pid(pid) = args[1] {
-//            let monitor_ref = base_monitor(vm, proc, pid);
-//            NativeReturn::Return { term: Term::Reference(monitor_ref) }
-//        } else {
-//            NativeReturn::Throw
-//        }
-//    } else {
-//        unimplemented!()
-//    }
-//}
+/// Resolves the arguments of `spawn/1` and `spawn/3` into the function
+/// the new process calls and its arguments.
+fn spawn_target(args: &[Rc<Term>]) -> Option<(Rc<Term>, Vec<Rc<Term>>)> {
+    match args {
+        [fun] => match &**fun {
+            Term::BoundLambda { .. } => Some((fun.clone(), vec![])),
+            Term::CapturedFunction { ident } if ident.arity == 0 => Some((fun.clone(), vec![])),
+            _ => None,
+        },
+        [module, name, fun_args] => {
+            let module = module.as_atom()?;
+            let name = name.as_atom()?;
+            let fun_args = Term::as_list(fun_args)?;
+            let ident = FunctionIdent {
+                module: Ident::with_empty_span(module),
+                name: Ident::with_empty_span(name),
+                arity: fun_args.len(),
+            };
+            Some((Term::CapturedFunction { ident }.into(), fun_args))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn spawn(vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    if let Some((fun, fun_args)) = spawn_target(args) {
+        let pid = vm.spawn(fun, &fun_args);
+        NativeReturn::Return {
+            term: Term::Pid(pid).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn spawn_link(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    if let Some((fun, fun_args)) = spawn_target(args) {
+        let pid = vm.spawn(fun, &fun_args);
+        vm.link(proc.pid, pid);
+        NativeReturn::Return {
+            term: Term::Pid(pid).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn send(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if let Term::Pid(pid) = &*args[0] {
+        vm.send(proc, *pid, args[1].clone());
+        NativeReturn::Return {
+            term: args[1].clone(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn spawn_monitor(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    if let Some((fun, fun_args)) = spawn_target(args) {
+        let pid = vm.spawn(fun, &fun_args);
+        let monitor_ref = vm.monitor(proc, pid);
+        NativeReturn::Return {
+            term: Term::Tuple(vec![
+                Term::Pid(pid).into(),
+                Term::Reference(monitor_ref).into(),
+            ])
+            .into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn monitor(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    // Only processes can be monitored in the interpreter
+    if !args[0].erl_eq(&Term::new_atom("process")) {
+        return badarg();
+    }
+    if let Term::Pid(pid) = &*args[1] {
+        let monitor_ref = vm.monitor(proc, *pid);
+        NativeReturn::Return {
+            term: Term::Reference(monitor_ref).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn demonitor(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Term::Reference(monitor_ref) = &*args[0] {
+        vm.demonitor(proc.pid, *monitor_ref);
+        NativeReturn::Return {
+            term: Term::new_bool(true).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn link(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let pid = if let Term::Pid(pid) = &*args[0] {
+        *pid
+    } else {
+        return badarg();
+    };
+
+    if pid == proc.pid {
+        return NativeReturn::Return {
+            term: Term::new_bool(true).into(),
+        };
+    }
+
+    let alive = vm
+        .with_process(proc, pid, |p| p.state.is_alive())
+        .unwrap_or(false);
+    if alive {
+        vm.link(proc.pid, pid);
+    } else if proc.mailbox.get_trap_exits() {
+        proc.deliver(
+            Term::Tuple(vec![
+                Term::new_atom("EXIT").into(),
+                Term::Pid(pid).into(),
+                Term::new_atom("noproc").into(),
+            ])
+            .into(),
+        );
+    } else {
+        return NativeReturn::Throw {
+            typ: Term::new_atom("error").into(),
+            reason: Term::new_atom("noproc").into(),
+        };
+    }
+
+    NativeReturn::Return {
+        term: Term::new_bool(true).into(),
+    }
+}
+
+fn unlink(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Term::Pid(pid) = &*args[0] {
+        vm.unlink(proc.pid, *pid);
+        NativeReturn::Return {
+            term: Term::new_bool(true).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn exit_2(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if let Term::Pid(pid) = &*args[0] {
+        vm.exit_signal(proc, *pid, args[1].clone());
+        NativeReturn::Return {
+            term: Term::new_bool(true).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn is_process_alive(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Term::Pid(pid) = &*args[0] {
+        let alive = vm
+            .with_process(proc, *pid, |p| p.state.is_alive())
+            .unwrap_or(false);
+        NativeReturn::Return {
+            term: Term::new_bool(alive).into(),
+        }
+    } else {
+        badarg()
+    }
+}
 
 fn not(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     assert!(args.len() == 1);
@@ -612,18 +721,45 @@ fn erl_self(_vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> Nati
     }
 }
 
-//fn process_flag(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 2);
-//    if args[0].erl_eq(&Term::new_atom("trap_exit")) {
-//        let mut mailboxes = vm.mailboxes.borrow_mut();
-//        let mailbox = &mut mailboxes.get_mut(&proc.pid).unwrap();
-//        let old_trap_exits = mailbox.get_trap_exits();
-//        mailbox.set_trap_exits(args[1].as_boolean().unwrap());
-//        NativeReturn::Return { term: Term::new_bool(old_trap_exits).into() }
-//    } else {
-//        unimplemented!()
-//    }
-//}
+fn process_flag(_vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if args[0].erl_eq(&Term::new_atom("trap_exit")) {
+        if let Some(trap_exits) = args[1].as_boolean() {
+            let old_trap_exits = proc.mailbox.get_trap_exits();
+            proc.mailbox.set_trap_exits(trap_exits);
+            NativeReturn::Return {
+                term: Term::new_bool(old_trap_exits).into(),
+            }
+        } else {
+            badarg()
+        }
+    } else if let Some(default) = process_flag_default(&args[0]) {
+        let old = match proc.flags.iter_mut().find(|e| e.0.erl_exact_eq(&args[0])) {
+            Some(entry) => std::mem::replace(&mut entry.1, args[1].clone()),
+            None => {
+                proc.flags.push((args[0].clone(), args[1].clone()));
+                default.into()
+            }
+        };
+        NativeReturn::Return { term: old }
+    } else {
+        badarg()
+    }
+}
+
+/// Initial value of the process flags that are accepted, but have no
+/// effect in the interpreter.
+fn process_flag_default(flag: &Term) -> Option<Term> {
+    let name = flag.as_atom()?;
+    let default = match &*name.as_str() {
+        "priority" => Term::new_atom("normal"),
+        "sensitive" => Term::new_bool(false),
+        "save_calls" => Term::new_i64(0),
+        "message_queue_data" => Term::new_atom("on_heap"),
+        _ => return None,
+    };
+    Some(default)
+}
 
 fn put(_vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     assert!(args.len() == 2);
@@ -735,6 +871,437 @@ fn map_size(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> Nat
     }
 }
 
+fn rem(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    // Truncated, the result has the sign of the dividend
+    integer_op(
+        args,
+        |i1, i2| {
+            if i2.is_zero() {
+                None
+            } else {
+                Some(i1 % i2)
+            }
+        },
+    )
+}
+
+fn int_div(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    // Rounds towards zero
+    integer_op(
+        args,
+        |i1, i2| {
+            if i2.is_zero() {
+                None
+            } else {
+                Some(i1 / i2)
+            }
+        },
+    )
+}
+
+fn band(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    integer_op(args, |i1, i2| Some(i1 & i2))
+}
+
+fn bor(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    integer_op(args, |i1, i2| Some(i1 | i2))
+}
+
+fn bxor(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    integer_op(args, |i1, i2| Some(i1 ^ i2))
+}
+
+/// Shifts left by `shift` bits, negative shifts go right. Right shifts
+/// round towards negative infinity.
+fn shift_left(int: &BigInt, shift: &BigInt) -> Option<BigInt> {
+    let shift = shift.to_i64()?;
+    if shift >= 0 {
+        Some(int << (shift as usize))
+    } else {
+        Some(int >> ((-shift) as usize))
+    }
+}
+
+fn bsl(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    integer_op(args, shift_left)
+}
+
+fn bsr(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    integer_op(args, |i1, i2| shift_left(i1, &-i2))
+}
+
+fn bnot(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Term::Integer(int) = &*args[0] {
+        NativeReturn::Return {
+            term: Term::Integer(!int.clone()).into(),
+        }
+    } else {
+        badarith()
+    }
+}
+
+fn xor(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    match (args[0].as_boolean(), args[1].as_boolean()) {
+        (Some(b1), Some(b2)) => NativeReturn::Return {
+            term: Term::new_bool(b1 ^ b2).into(),
+        },
+        _ => badarg(),
+    }
+}
+
+fn equal(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    NativeReturn::Return {
+        term: Term::new_bool(args[0].erl_eq(&*args[1])).into(),
+    }
+}
+
+fn not_equal(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    NativeReturn::Return {
+        term: Term::new_bool(!args[0].erl_eq(&*args[1])).into(),
+    }
+}
+
+fn is_float(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let res = match &*args[0] {
+        Term::Float(_) => true,
+        _ => false,
+    };
+    NativeReturn::Return {
+        term: Term::new_bool(res).into(),
+    }
+}
+
+fn is_number(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let res = match &*args[0] {
+        Term::Integer(_) | Term::Float(_) => true,
+        _ => false,
+    };
+    NativeReturn::Return {
+        term: Term::new_bool(res).into(),
+    }
+}
+
+fn is_boolean(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    NativeReturn::Return {
+        term: Term::new_bool(args[0].as_boolean().is_some()).into(),
+    }
+}
+
+fn float(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    match &*args[0] {
+        Term::Integer(int) => NativeReturn::Return {
+            term: Term::Float(bigint_to_double(int).into()).into(),
+        },
+        Term::Float(_) => NativeReturn::Return {
+            term: args[0].clone(),
+        },
+        _ => badarg(),
+    }
+}
+
+/// Converts a float to an integer with `op`, integers are returned as is.
+fn float_to_integer<F>(args: &[Rc<Term>], op: F) -> NativeReturn
+where
+    F: FnOnce(f64) -> f64,
+{
+    assert!(args.len() == 1);
+    match &*args[0] {
+        Term::Integer(_) => NativeReturn::Return {
+            term: args[0].clone(),
+        },
+        Term::Float(flt) => NativeReturn::Return {
+            term: Term::Integer(BigInt::from_f64(op(flt.0)).unwrap()).into(),
+        },
+        _ => badarg(),
+    }
+}
+
+fn round(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    // Halfway cases are rounded away from zero
+    float_to_integer(args, f64::round)
+}
+
+fn trunc(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    float_to_integer(args, f64::trunc)
+}
+
+/// Reads a list of unicode code points into a string.
+fn list_to_string(term: &Rc<Term>) -> Option<String> {
+    Term::as_list(term)?
+        .iter()
+        .map(|c| c.as_integer()?.to_u32().and_then(std::char::from_u32))
+        .collect()
+}
+
+fn string_to_list(string: &str) -> Rc<Term> {
+    let chars: Vec<_> = string
+        .chars()
+        .map(|c| Term::new_i64(c as i64).into())
+        .collect();
+    Term::slice_to_list(&chars, Term::Nil.into())
+}
+
+fn list_to_atom(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(string) = list_to_string(&args[0]) {
+        NativeReturn::Return {
+            term: Term::Atom(Symbol::intern(&string)).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn integer_to_list(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Term::Integer(int) = &*args[0] {
+        NativeReturn::Return {
+            term: string_to_list(&int.to_string()),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn tuple_to_list(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(elems) = args[0].as_tuple() {
+        NativeReturn::Return {
+            term: Term::slice_to_list(elems, Term::Nil.into()),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn list_to_tuple(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(elems) = Term::as_list(&args[0]) {
+        NativeReturn::Return {
+            term: Term::Tuple(elems).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn make_ref(vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 0);
+    NativeReturn::Return {
+        term: Term::Reference(vm.ref_gen.borrow_mut().next()).into(),
+    }
+}
+
+fn throw(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    NativeReturn::Throw {
+        typ: Term::new_atom("throw").into(),
+        reason: args[0].clone(),
+    }
+}
+
+fn error(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    // error/2 takes the arguments of the failing function for the stack
+    // trace, which is not kept.
+    assert!(args.len() == 1 || args.len() == 2);
+    NativeReturn::Throw {
+        typ: Term::new_atom("error").into(),
+        reason: args[0].clone(),
+    }
+}
+
+fn exit_1(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    NativeReturn::Throw {
+        typ: Term::new_atom("exit").into(),
+        reason: args[0].clone(),
+    }
+}
+
+fn apply(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    let (fun, fun_args) = match args {
+        [fun, fun_args] => (fun.clone(), fun_args),
+        [module, name, fun_args] => {
+            let (module, name) = match (module.as_atom(), name.as_atom()) {
+                (Some(module), Some(name)) => (module, name),
+                _ => return badarg(),
+            };
+            let arity = match Term::as_list(fun_args) {
+                Some(list) => list.len(),
+                None => return badarg(),
+            };
+            let ident = FunctionIdent {
+                module: Ident::with_empty_span(module),
+                name: Ident::with_empty_span(name),
+                arity,
+            };
+            (Term::CapturedFunction { ident }.into(), fun_args)
+        }
+        _ => unreachable!(),
+    };
+
+    match &*fun {
+        Term::BoundLambda { .. } | Term::CapturedFunction { .. } => (),
+        _ => return badarg(),
+    }
+
+    if let Some(fun_args) = Term::as_list(fun_args) {
+        NativeReturn::Call {
+            fun,
+            args: fun_args,
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn byte_size(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(bits) = binary_bits(&args[0]) {
+        NativeReturn::Return {
+            term: Term::new_usize((bits.bit_len() + 7) / 8).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn bit_size(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(bits) = binary_bits(&args[0]) {
+        NativeReturn::Return {
+            term: Term::new_usize(bits.bit_len()).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn binary_to_list(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let bits = binary_bits(&args[0]);
+    if let Some(bytes) = bits.as_ref().and_then(|b| b.try_as_byte_aligned_slice()) {
+        let items: Vec<_> = bytes
+            .iter()
+            .map(|b| Term::new_i64(*b as i64).into())
+            .collect();
+        NativeReturn::Return {
+            term: Term::slice_to_list(&items, Term::Nil.into()),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn split_binary(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let pos = args[1].as_integer().and_then(|p| p.to_usize());
+    let size = binary_bytes(&args[0]).map(|b| b.len());
+    match (pos, size) {
+        (Some(pos), Some(size)) if pos <= size => NativeReturn::Return {
+            term: Term::Tuple(vec![
+                sub_binary(&args[0], 0, pos).unwrap(),
+                sub_binary(&args[0], pos, size - pos).unwrap(),
+            ])
+            .into(),
+        },
+        _ => badarg(),
+    }
+}
+
+fn list_to_binary(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    match &*args[0] {
+        Term::Nil | Term::ListCell(_, _) => (),
+        _ => return badarg(),
+    }
+    iolist_to_binary(vm, proc, args)
+}
+
+fn iolist_to_binary(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let mut bytes = Vec::new();
+    if iolist_bytes(&args[0], &mut bytes).is_some() {
+        NativeReturn::Return {
+            term: bytes_to_binary(bytes),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn iolist_size(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let mut bytes = Vec::new();
+    if iolist_bytes(&args[0], &mut bytes).is_some() {
+        NativeReturn::Return {
+            term: Term::new_usize(bytes.len()).into(),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn binary_to_integer(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let bytes = match binary_bytes(&args[0]) {
+        Some(bytes) => bytes,
+        None => return badarg(),
+    };
+
+    // An optional sign followed by at least one decimal digit
+    let digits = match bytes.first() {
+        Some(b'-') | Some(b'+') => &bytes[1..],
+        _ => &bytes[..],
+    };
+    if digits.is_empty() || !digits.iter().all(|b| b.is_ascii_digit()) {
+        return badarg();
+    }
+
+    let int: BigInt = std::str::from_utf8(&bytes).unwrap().parse().unwrap();
+    NativeReturn::Return {
+        term: Term::Integer(int).into(),
+    }
+}
+
+fn term_to_binary(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    // Options only affect compression and the float encoding, terms are
+    // always encoded uncompressed and with the newest float format.
+    if args.len() == 2 && Term::as_list(&args[1]).is_none() {
+        return badarg();
+    }
+    match etf::encode(&args[0]) {
+        Ok(bytes) => NativeReturn::Return {
+            term: bytes_to_binary(bytes),
+        },
+        Err(_) => badarg(),
+    }
+}
+
+fn binary_to_term(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    if let Some(options) = args.get(1) {
+        let safe = Symbol::intern("safe");
+        match Term::as_list(options) {
+            Some(options) if options.iter().all(|o| o.as_atom() == Some(safe)) => (),
+            _ => return badarg(),
+        }
+    }
+    match binary_bytes(&args[0]).map(|bytes| etf::decode(&bytes)) {
+        Some(Ok(term)) => NativeReturn::Return { term },
+        _ => badarg(),
+    }
+}
+
 pub fn make_erlang() -> NativeModule {
     let mut module = NativeModule::new(Symbol::intern("erlang"));
     module.add_fun(Symbol::intern("+"), 2, Box::new(add));
@@ -743,7 +1310,7 @@ pub fn make_erlang() -> NativeModule {
     module.add_fun(Symbol::intern("*"), 2, Box::new(mul));
     module.add_fun(Symbol::intern("/"), 2, Box::new(div));
     module.add_fun(Symbol::intern("abs"), 1, Box::new(abs));
-    //module.add_fun(Symbol::intern("++"), 2, Box::new(list_append));
+    module.add_fun(Symbol::intern("++"), 2, Box::new(list_append));
     module.add_fun(Symbol::intern("--"), 2, Box::new(list_subtract));
     module.add_fun(Symbol::intern("=:="), 2, Box::new(exact_eq));
     module.add_fun(Symbol::intern("=/="), 2, Box::new(exact_not_eq));
@@ -763,7 +1330,6 @@ pub fn make_erlang() -> NativeModule {
     module.add_fun(Symbol::intern("tuple_size"), 1, Box::new(tuple_size));
     module.add_fun(Symbol::intern("is_function"), 1, Box::new(is_function));
     module.add_fun(Symbol::intern("is_function"), 2, Box::new(is_function));
-    //module.add_fun(Symbol::intern("spawn_monitor"), 1, Box::new(spawn_monitor_1));
     module.add_fun(Symbol::intern("not"), 1, Box::new(not));
     module.add_fun(Symbol::intern("atom_to_list"), 1, Box::new(atom_to_list));
     module.add_fun(Symbol::intern("setelement"), 3, Box::new(setelement));
@@ -776,8 +1342,63 @@ pub fn make_erlang() -> NativeModule {
     module.add_fun(Symbol::intern("hd"), 1, Box::new(hd));
     module.add_fun(Symbol::intern("tl"), 1, Box::new(tl));
     module.add_fun(Symbol::intern("map_size"), 1, Box::new(map_size));
-    //module.add_fun(Symbol::intern("spawn"), 1, Box::new(spawn_1));
-    //module.add_fun(Symbol::intern("monitor"), 2, Box::new(monitor_2));
-    //module.add_fun(Symbol::intern("process_flag"), 2, Box::new(process_flag));
+    module.add_fun(Symbol::intern("spawn"), 1, Box::new(spawn));
+    module.add_fun(Symbol::intern("spawn"), 3, Box::new(spawn));
+    module.add_fun(Symbol::intern("spawn_link"), 1, Box::new(spawn_link));
+    module.add_fun(Symbol::intern("spawn_link"), 3, Box::new(spawn_link));
+    module.add_fun(Symbol::intern("!"), 2, Box::new(send));
+    module.add_fun(Symbol::intern("send"), 2, Box::new(send));
+    module.add_fun(Symbol::intern("spawn_monitor"), 1, Box::new(spawn_monitor));
+    module.add_fun(Symbol::intern("spawn_monitor"), 3, Box::new(spawn_monitor));
+    module.add_fun(Symbol::intern("monitor"), 2, Box::new(monitor));
+    module.add_fun(Symbol::intern("demonitor"), 1, Box::new(demonitor));
+    module.add_fun(Symbol::intern("link"), 1, Box::new(link));
+    module.add_fun(Symbol::intern("unlink"), 1, Box::new(unlink));
+    module.add_fun(Symbol::intern("exit"), 2, Box::new(exit_2));
+    module.add_fun(
+        Symbol::intern("is_process_alive"),
+        1,
+        Box::new(is_process_alive),
+    );
+    module.add_fun(Symbol::intern("process_flag"), 2, Box::new(process_flag));
+    module.add_fun(Symbol::intern("rem"), 2, Box::new(rem));
+    module.add_fun(Symbol::intern("div"), 2, Box::new(int_div));
+    module.add_fun(Symbol::intern("band"), 2, Box::new(band));
+    module.add_fun(Symbol::intern("bor"), 2, Box::new(bor));
+    module.add_fun(Symbol::intern("bxor"), 2, Box::new(bxor));
+    module.add_fun(Symbol::intern("bsl"), 2, Box::new(bsl));
+    module.add_fun(Symbol::intern("bsr"), 2, Box::new(bsr));
+    module.add_fun(Symbol::intern("bnot"), 1, Box::new(bnot));
+    module.add_fun(Symbol::intern("xor"), 2, Box::new(xor));
+    module.add_fun(Symbol::intern("=="), 2, Box::new(equal));
+    module.add_fun(Symbol::intern("/="), 2, Box::new(not_equal));
+    module.add_fun(Symbol::intern("is_float"), 1, Box::new(is_float));
+    module.add_fun(Symbol::intern("is_number"), 1, Box::new(is_number));
+    module.add_fun(Symbol::intern("is_boolean"), 1, Box::new(is_boolean));
+    module.add_fun(Symbol::intern("float"), 1, Box::new(float));
+    module.add_fun(Symbol::intern("round"), 1, Box::new(round));
+    module.add_fun(Symbol::intern("trunc"), 1, Box::new(trunc));
+    module.add_fun(Symbol::intern("list_to_atom"), 1, Box::new(list_to_atom));
+    module.add_fun(
+        Symbol::intern("integer_to_list"),
+        1,
+        Box::new(integer_to_list),
+    );
+    module.add_fun(Symbol::intern("tuple_to_list"), 1, Box::new(tuple_to_list));
+    module.add_fun(Symbol::intern("list_to_tuple"), 1, Box::new(list_to_tuple));
+    module.add_fun(Symbol::intern("make_ref"), 0, Box::new(make_ref));
+    module.add_fun(Symbol::intern("throw"), 1, Box::new(throw));
+    module.add_fun(Symbol::intern("error"), 1, Box::new(error));
+    module.add_fun(Symbol::intern("error"), 2, Box::new(error));
+    module.add_fun(Symbol::intern("exit"), 1, Box::new(exit_1));
+    module.add_fun(Symbol::intern("apply"), 2, Box::new(apply));
+    module.add_fun(Symbol::intern("apply"), 3, Box::new(apply));
+    module.add_fun(Symbol::intern("byte_size"), 1, Box::new(byte_size));
+    module.add_fun(Symbol::intern("bit_size"), 1, Box::new(bit_size));
+    module.add_fun(
+        Symbol::intern("binary_to_list"),
+        1,
+        Box::new(binary_to_list),
+    );
     module
 }
@@ -2,31 +2,33 @@ use std::rc::Rc;
 
 use crate::module::{NativeModule, NativeReturn};
 use crate::process::ProcessContext;
-use crate::term::{ErlEq, Term};
+use crate::term::{ErlEq, ErlExactEq, ErlOrd, Term};
 use crate::vm::VMState;
 
+use super::erlang::badarg;
+
 use libeir_intern::Symbol;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+fn is_fun(term: &Term) -> bool {
+    match term {
+        Term::BoundLambda { .. } | Term::CapturedFunction { .. } => true,
+        _ => false,
+    }
+}
+
+fn member(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if let Some(list) = Term::as_list(&args[1]) {
+        NativeReturn::Return {
+            term: Term::new_bool(list.iter().any(|i| i.erl_exact_eq(&*args[0]))).into(),
+        }
+    } else {
+        badarg()
+    }
+}
 
-//fn member_list(item: &Term, list: &Term) -> NativeReturn {
-//    if let Term::Nil = list {
-//        NativeReturn::Return { term: Term::new_bool(false).into() }
-//    } else if let Term::List(ref head, ref tail) = list {
-//        for l_item in head {
-//            if item.erl_exact_eq(l_item) {
-//                return NativeReturn::Return { term: Term::new_bool(true).into() };
-//            }
-//        }
-//        member_list(item, tail)
-//    } else {
-//        NativeReturn::Throw
-//    }
-//}
-//
-//fn member(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 2);
-//    member_list(&args[0], &args[1])
-//}
-//
 fn reverse_2(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     assert!(args.len() == 2);
 
@@ -44,37 +46,308 @@ fn reverse_1(vm: &VMState, proc: &mut ProcessContext, args: &[Rc<Term>]) -> Nati
     reverse_2(vm, proc, &[args[0].clone(), Term::Nil.into()])
 }
 
-//fn keyfind(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
-//    assert!(args.len() == 3);
-//    let key = &*args[0];
-//    let pos = if let Some(int) = args[1].as_i64() {
-//        int
-//    } else {
-//        return NativeReturn::Throw;
-//    };
-//    let list_term = &*args[2];
-//    let (list, list_tail) = list_term.as_inproper_list();
-//    for term in list.iter() {
-//        if let Term::Tuple(values) = &**term {
-//            if let Some(val_term) = values.get(pos as usize) {
-//                if val_term.erl_eq(key) {
-//                    return NativeReturn::Return { term: term.clone() };
-//                }
-//            }
-//        }
-//    }
-//    if let Term::Nil = list_tail {
-//        NativeReturn::Return { term: Term::new_bool(false).into() }
-//    } else {
-//        NativeReturn::Throw
-//    }
-//}
+fn as_position(term: &Term) -> Option<usize> {
+    term.as_integer()?.to_usize().filter(|pos| *pos > 0)
+}
+
+/// Returns the element at the 1 based `pos` of a tuple.
+fn tuple_element(term: &Term, pos: usize) -> Option<&Rc<Term>> {
+    if pos == 0 {
+        return None;
+    }
+    term.as_tuple()?.get(pos - 1)
+}
+
+fn keyfind(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    let key = &*args[0];
+    let (pos, list) = match (as_position(&args[1]), Term::as_list(&args[2])) {
+        (Some(pos), Some(list)) => (pos, list),
+        _ => return badarg(),
+    };
+
+    for term in list.iter() {
+        if let Some(val_term) = tuple_element(term, pos) {
+            if val_term.erl_eq(key) {
+                return NativeReturn::Return { term: term.clone() };
+            }
+        }
+    }
+    NativeReturn::Return {
+        term: Term::new_bool(false).into(),
+    }
+}
+
+fn map_step(fun: Rc<Term>, list: &Term, acc: Rc<Term>) -> NativeReturn {
+    match list {
+        Term::Nil => {
+            let (mut acc, _) = Term::as_inproper_list(&acc);
+            acc.reverse();
+            NativeReturn::Return {
+                term: Term::slice_to_list(&acc, Term::Nil.into()),
+            }
+        }
+        Term::ListCell(head, tail) => NativeReturn::CallThen {
+            fun: fun.clone(),
+            args: vec![head.clone()],
+            cont: Symbol::intern("-map/2-cont-"),
+            environment: vec![fun, tail.clone(), acc],
+        },
+        _ => badarg(),
+    }
+}
+
+fn map(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if !is_fun(&args[0]) {
+        return badarg();
+    }
+    map_step(args[0].clone(), &args[1], Term::Nil.into())
+}
+
+fn map_cont(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 4);
+    let acc = Term::ListCell(args[3].clone(), args[2].clone());
+    map_step(args[0].clone(), &args[1], acc.into())
+}
+
+fn foldl_step(fun: Rc<Term>, acc: Rc<Term>, list: &Term) -> NativeReturn {
+    match list {
+        Term::Nil => NativeReturn::Return { term: acc },
+        Term::ListCell(head, tail) => NativeReturn::CallThen {
+            fun: fun.clone(),
+            args: vec![head.clone(), acc],
+            cont: Symbol::intern("-foldl/3-cont-"),
+            environment: vec![fun, tail.clone()],
+        },
+        _ => badarg(),
+    }
+}
+
+fn foldl(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    if !is_fun(&args[0]) {
+        return badarg();
+    }
+    foldl_step(args[0].clone(), args[1].clone(), &args[2])
+}
+
+fn foldl_cont(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    foldl_step(args[0].clone(), args[2].clone(), &args[1])
+}
+
+fn filter_step(fun: Rc<Term>, list: &Term, acc: Rc<Term>) -> NativeReturn {
+    match list {
+        Term::Nil => {
+            let (mut acc, _) = Term::as_inproper_list(&acc);
+            acc.reverse();
+            NativeReturn::Return {
+                term: Term::slice_to_list(&acc, Term::Nil.into()),
+            }
+        }
+        Term::ListCell(head, tail) => NativeReturn::CallThen {
+            fun: fun.clone(),
+            args: vec![head.clone()],
+            cont: Symbol::intern("-filter/2-cont-"),
+            environment: vec![fun, head.clone(), tail.clone(), acc],
+        },
+        _ => badarg(),
+    }
+}
+
+fn filter(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if !is_fun(&args[0]) {
+        return badarg();
+    }
+    filter_step(args[0].clone(), &args[1], Term::Nil.into())
+}
+
+fn filter_cont(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 5);
+    let acc = match args[4].as_boolean() {
+        Some(true) => Term::ListCell(args[1].clone(), args[3].clone()).into(),
+        Some(false) => args[3].clone(),
+        None => return badarg(),
+    };
+    filter_step(args[0].clone(), &args[2], acc)
+}
+
+fn seq(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2 || args.len() == 3);
+    let one = BigInt::from(1);
+    let (from, to, incr) = match (
+        args[0].as_integer(),
+        args[1].as_integer(),
+        args.get(2).map(|i| i.as_integer()).unwrap_or(Some(&one)),
+    ) {
+        (Some(from), Some(to), Some(incr)) => (from, to, incr),
+        _ => return badarg(),
+    };
+
+    // The sequence may be empty only if `to` is one increment short
+    // of `from`.
+    let valid = if incr.is_positive() {
+        to >= &(from - incr)
+    } else if incr.is_negative() {
+        to <= &(from - incr)
+    } else {
+        from == to
+    };
+    if !valid {
+        return badarg();
+    }
+
+    let mut items = Vec::new();
+    let mut curr = from.clone();
+    if incr.is_zero() {
+        items.push(Term::Integer(curr).into());
+    } else {
+        while (incr.is_positive() && &curr <= to) || (incr.is_negative() && &curr >= to) {
+            items.push(Term::Integer(curr.clone()).into());
+            curr += incr;
+        }
+    }
+
+    NativeReturn::Return {
+        term: Term::slice_to_list(&items, Term::Nil.into()),
+    }
+}
+
+fn nth(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let list = Term::as_list(&args[1]);
+    let elem = as_position(&args[0]).and_then(|n| list?.get(n - 1).cloned());
+    if let Some(term) = elem {
+        NativeReturn::Return { term }
+    } else {
+        badarg()
+    }
+}
+
+fn sort(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    if let Some(mut list) = Term::as_list(&args[0]) {
+        list.sort_by(|l, r| l.erl_ord(r));
+        NativeReturn::Return {
+            term: Term::slice_to_list(&list, Term::Nil.into()),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn keysort(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let (pos, mut list) = match (as_position(&args[0]), Term::as_list(&args[1])) {
+        (Some(pos), Some(list)) => (pos, list),
+        _ => return badarg(),
+    };
+    if !list.iter().all(|t| tuple_element(t, pos).is_some()) {
+        return badarg();
+    }
+
+    // Stable, elements with equal keys keep their order
+    list.sort_by(|l, r| {
+        let l = tuple_element(l, pos).unwrap();
+        let r = tuple_element(r, pos).unwrap();
+        l.erl_ord(r)
+    });
+    NativeReturn::Return {
+        term: Term::slice_to_list(&list, Term::Nil.into()),
+    }
+}
+
+fn append_1(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let lists = match Term::as_list(&args[0]) {
+        Some(lists) => lists,
+        None => return badarg(),
+    };
+
+    // The last list is the tail of the result, and is not traversed
+    let mut acc = lists.last().cloned().unwrap_or_else(|| Term::Nil.into());
+    for list in lists.iter().rev().skip(1) {
+        match Term::as_list(list) {
+            Some(items) => acc = Term::slice_to_list(&items, acc),
+            None => return badarg(),
+        }
+    }
+    NativeReturn::Return { term: acc }
+}
+
+fn append_2(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    if let Some(head) = Term::as_list(&args[0]) {
+        NativeReturn::Return {
+            term: Term::slice_to_list(&head, args[1].clone()),
+        }
+    } else {
+        badarg()
+    }
+}
+
+fn flatten_into(list: &Rc<Term>, out: &mut Vec<Rc<Term>>) -> Option<()> {
+    for item in Term::as_list(list)? {
+        match &*item {
+            Term::Nil | Term::ListCell(_, _) => flatten_into(&item, out)?,
+            _ => out.push(item),
+        }
+    }
+    Some(())
+}
+
+fn flatten(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1 || args.len() == 2);
+    let mut items = Vec::new();
+    if flatten_into(&args[0], &mut items).is_none() {
+        return badarg();
+    }
+    let tail = args.get(1).cloned().unwrap_or_else(|| Term::Nil.into());
+    NativeReturn::Return {
+        term: Term::slice_to_list(&items, tail),
+    }
+}
+
+fn zip(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    match (Term::as_list(&args[0]), Term::as_list(&args[1])) {
+        (Some(l1), Some(l2)) if l1.len() == l2.len() => {
+            let items: Vec<_> = l1
+                .into_iter()
+                .zip(l2.into_iter())
+                .map(|(e1, e2)| Term::Tuple(vec![e1, e2]).into())
+                .collect();
+            NativeReturn::Return {
+                term: Term::slice_to_list(&items, Term::Nil.into()),
+            }
+        }
+        _ => badarg(),
+    }
+}
 
 pub fn make_lists() -> NativeModule {
     let mut module = NativeModule::new(Symbol::intern("lists"));
-    //module.add_fun(Symbol::intern("member"), 2, Box::new(member));
+    module.add_fun(Symbol::intern("member"), 2, Box::new(member));
     module.add_fun(Symbol::intern("reverse"), 1, Box::new(reverse_1));
     module.add_fun(Symbol::intern("reverse"), 2, Box::new(reverse_2));
-    //module.add_fun(Symbol::intern("keyfind"), 3, Box::new(keyfind));
+    module.add_fun(Symbol::intern("keyfind"), 3, Box::new(keyfind));
+    module.add_fun(Symbol::intern("map"), 2, Box::new(map));
+    module.add_fun(Symbol::intern("-map/2-cont-"), 4, Box::new(map_cont));
+    module.add_fun(Symbol::intern("foldl"), 3, Box::new(foldl));
+    module.add_fun(Symbol::intern("-foldl/3-cont-"), 3, Box::new(foldl_cont));
+    module.add_fun(Symbol::intern("filter"), 2, Box::new(filter));
+    module.add_fun(Symbol::intern("-filter/2-cont-"), 5, Box::new(filter_cont));
+    module.add_fun(Symbol::intern("seq"), 2, Box::new(seq));
+    module.add_fun(Symbol::intern("seq"), 3, Box::new(seq));
+    module.add_fun(Symbol::intern("nth"), 2, Box::new(nth));
+    module.add_fun(Symbol::intern("sort"), 1, Box::new(sort));
+    module.add_fun(Symbol::intern("keysort"), 2, Box::new(keysort));
+    module.add_fun(Symbol::intern("append"), 1, Box::new(append_1));
+    module.add_fun(Symbol::intern("append"), 2, Box::new(append_2));
+    module.add_fun(Symbol::intern("flatten"), 1, Box::new(flatten));
+    module.add_fun(Symbol::intern("flatten"), 2, Box::new(flatten));
+    module.add_fun(Symbol::intern("zip"), 2, Box::new(zip));
     module
 }
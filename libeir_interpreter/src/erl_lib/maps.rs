@@ -4,10 +4,28 @@ use crate::module::{NativeModule, NativeReturn};
 use crate::process::ProcessContext;
 use crate::vm::VMState;
 
+use super::erlang::badarg;
 use crate::term::{MapTerm, Term};
 
 use std::rc::Rc;
 
+fn error(reason: &str, term: &Rc<Term>) -> NativeReturn {
+    NativeReturn::Throw {
+        typ: Term::new_atom("error").into(),
+        reason: Term::Tuple(vec![Term::new_atom(reason).into(), term.clone()]).into(),
+    }
+}
+
+/// Gets the map argument at `idx`, or returns a `badmap` error.
+macro_rules! map_arg {
+    ($args:expr, $idx:expr) => {
+        match $args[$idx].as_map() {
+            Some(map) => map,
+            None => return error("badmap", &$args[$idx]),
+        }
+    };
+}
+
 fn new_0(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
     assert!(args.len() == 0);
     NativeReturn::Return {
@@ -15,8 +33,169 @@ fn new_0(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> Native
     }
 }
 
+fn get_2(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let map = map_arg!(args, 1);
+    if let Some(term) = map.get(&args[0]) {
+        NativeReturn::Return { term }
+    } else {
+        error("badkey", &args[0])
+    }
+}
+
+fn get_3(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    let map = map_arg!(args, 1);
+    NativeReturn::Return {
+        term: map.get(&args[0]).unwrap_or_else(|| args[2].clone()),
+    }
+}
+
+fn find(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let map = map_arg!(args, 1);
+    let term = if let Some(val) = map.get(&args[0]) {
+        Term::Tuple(vec![Term::new_atom("ok").into(), val]).into()
+    } else {
+        Term::new_atom("error").into()
+    };
+    NativeReturn::Return { term }
+}
+
+fn is_key(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let map = map_arg!(args, 1);
+    NativeReturn::Return {
+        term: Term::new_bool(map.get(&args[0]).is_some()).into(),
+    }
+}
+
+fn put(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    let mut map = map_arg!(args, 2).clone();
+    map.insert(args[0].clone(), args[1].clone());
+    NativeReturn::Return {
+        term: Term::Map(map).into(),
+    }
+}
+
+fn remove(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let mut map = map_arg!(args, 1).clone();
+    map.remove(&args[0]);
+    NativeReturn::Return {
+        term: Term::Map(map).into(),
+    }
+}
+
+fn keys(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let map = map_arg!(args, 0);
+    let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+    NativeReturn::Return {
+        term: Term::slice_to_list(&keys, Term::Nil.into()),
+    }
+}
+
+fn values(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let map = map_arg!(args, 0);
+    let values: Vec<_> = map.iter().map(|(_, v)| v.clone()).collect();
+    NativeReturn::Return {
+        term: Term::slice_to_list(&values, Term::Nil.into()),
+    }
+}
+
+fn to_list(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let map = map_arg!(args, 0);
+    let entries: Vec<_> = map
+        .iter()
+        .map(|(k, v)| Term::Tuple(vec![k.clone(), v.clone()]).into())
+        .collect();
+    NativeReturn::Return {
+        term: Term::slice_to_list(&entries, Term::Nil.into()),
+    }
+}
+
+fn from_list(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 1);
+    let list = match Term::as_list(&args[0]) {
+        Some(list) => list,
+        None => return badarg(),
+    };
+
+    // Later entries overwrite earlier ones with the same key
+    let mut map = MapTerm::new();
+    for entry in list.iter() {
+        match entry.as_tuple() {
+            Some([key, val]) => {
+                map.insert(key.clone(), val.clone());
+            }
+            _ => return badarg(),
+        }
+    }
+    NativeReturn::Return {
+        term: Term::Map(map).into(),
+    }
+}
+
+fn merge(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 2);
+    let mut map = map_arg!(args, 0).clone();
+    for (key, val) in map_arg!(args, 1).iter() {
+        map.insert(key.clone(), val.clone());
+    }
+    NativeReturn::Return {
+        term: Term::Map(map).into(),
+    }
+}
+
+fn fold_step(fun: Rc<Term>, acc: Rc<Term>, map: Rc<Term>, idx: usize) -> NativeReturn {
+    let entry = map.as_map().unwrap().iter().nth(idx);
+    if let Some((key, val)) = entry {
+        NativeReturn::CallThen {
+            fun: fun.clone(),
+            args: vec![key.clone(), val.clone(), acc],
+            cont: Symbol::intern("-fold/3-cont-"),
+            environment: vec![fun, map.clone(), Term::new_usize(idx + 1).into()],
+        }
+    } else {
+        NativeReturn::Return { term: acc }
+    }
+}
+
+fn fold(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 3);
+    map_arg!(args, 2);
+    match &*args[0] {
+        Term::BoundLambda { .. } | Term::CapturedFunction { .. } => (),
+        _ => return badarg(),
+    }
+    fold_step(args[0].clone(), args[1].clone(), args[2].clone(), 0)
+}
+
+fn fold_cont(_vm: &VMState, _proc: &mut ProcessContext, args: &[Rc<Term>]) -> NativeReturn {
+    assert!(args.len() == 4);
+    let idx = args[2].as_usize().unwrap();
+    fold_step(args[0].clone(), args[3].clone(), args[1].clone(), idx)
+}
+
 pub fn make_maps() -> NativeModule {
     let mut module = NativeModule::new(Symbol::intern("maps"));
     module.add_fun(Symbol::intern("new"), 0, Box::new(new_0));
+    module.add_fun(Symbol::intern("get"), 2, Box::new(get_2));
+    module.add_fun(Symbol::intern("get"), 3, Box::new(get_3));
+    module.add_fun(Symbol::intern("find"), 2, Box::new(find));
+    module.add_fun(Symbol::intern("is_key"), 2, Box::new(is_key));
+    module.add_fun(Symbol::intern("put"), 3, Box::new(put));
+    module.add_fun(Symbol::intern("remove"), 2, Box::new(remove));
+    module.add_fun(Symbol::intern("keys"), 1, Box::new(keys));
+    module.add_fun(Symbol::intern("values"), 1, Box::new(values));
+    module.add_fun(Symbol::intern("to_list"), 1, Box::new(to_list));
+    module.add_fun(Symbol::intern("from_list"), 1, Box::new(from_list));
+    module.add_fun(Symbol::intern("merge"), 2, Box::new(merge));
+    module.add_fun(Symbol::intern("fold"), 3, Box::new(fold));
+    module.add_fun(Symbol::intern("-fold/3-cont-"), 4, Box::new(fold_cont));
     module
 }
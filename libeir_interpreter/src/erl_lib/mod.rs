@@ -15,3 +15,7 @@ pub use self::math::make_math;
 
 mod maps;
 pub use self::maps::make_maps;
+
+mod binary;
+pub use self::binary::make_binary;
+pub(crate) use self::binary::{binary_bits, binary_slice};
@@ -0,0 +1,453 @@
+//! Encoding and decoding of terms in the External Term Format, as used by
+//! `term_to_binary/1` and `binary_to_term/1` on a BEAM node.
+//!
+//! The interpreter has no notion of nodes. Pids and references are encoded
+//! as belonging to the local node `nonode@nohost`, and decode back to the
+//! same local pid or reference regardless of the node they name. Captured
+//! functions are encoded as exports, other funs can not be encoded.
+//!
+//! Compressed terms are not supported.
+
+use std::convert::TryInto;
+use std::rc::Rc;
+
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
+use snafu::{OptionExt, Snafu};
+
+use libeir_intern::{Ident, Symbol};
+use libeir_ir::FunctionIdent;
+use libeir_util_binary::{BitCarrier, BitSlice, BitVec};
+
+use crate::erl_lib::binary_bits;
+use crate::term::{MapTerm, Pid, Reference, Term};
+
+/// The version byte every encoded term starts with.
+pub const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const BIT_BINARY_EXT: u8 = 77;
+const COMPRESSED: u8 = 80;
+const NEW_PID_EXT: u8 = 88;
+const NEWER_REFERENCE_EXT: u8 = 90;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const PID_EXT: u8 = 103;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const EXPORT_EXT: u8 = 113;
+const NEW_REFERENCE_EXT: u8 = 114;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+const LOCAL_NODE: &str = "nonode@nohost";
+
+#[derive(Debug, Snafu)]
+pub enum EtfError {
+    #[snafu(display("term can not be encoded: {:?}", term))]
+    Unencodable { term: Rc<Term> },
+
+    #[snafu(display("unsupported format version {}", version))]
+    UnsupportedVersion { version: u8 },
+
+    #[snafu(display("unknown tag {}", tag))]
+    UnknownTag { tag: u8 },
+
+    #[snafu(display("compressed terms are not supported"))]
+    Compressed,
+
+    #[snafu(display("unexpected end of data"))]
+    UnexpectedEnd,
+
+    #[snafu(display("malformed {}", what))]
+    Malformed { what: &'static str },
+}
+
+type Result<T> = std::result::Result<T, EtfError>;
+
+/// Encodes a term, including the leading version byte.
+pub fn encode(term: &Rc<Term>) -> Result<Vec<u8>> {
+    let mut out = vec![VERSION];
+    encode_term(term, &mut out)?;
+    Ok(out)
+}
+
+/// Decodes a term, starting with the version byte. Data after the end of
+/// the term is ignored.
+pub fn decode(data: &[u8]) -> Result<Rc<Term>> {
+    let mut decoder = Decoder { data, pos: 0 };
+    let version = decoder.u8()?;
+    if version != VERSION {
+        return UnsupportedVersion { version }.fail();
+    }
+    decoder.term()
+}
+
+fn encode_atom(atom: Symbol, out: &mut Vec<u8>) -> Result<()> {
+    let string = atom.as_str();
+    let bytes = string.as_bytes();
+    if bytes.len() < 256 {
+        out.push(SMALL_ATOM_UTF8_EXT);
+        out.push(bytes.len() as u8);
+    } else if bytes.len() < 65536 {
+        out.push(ATOM_UTF8_EXT);
+        out.extend(&(bytes.len() as u16).to_be_bytes());
+    } else {
+        return Unencodable {
+            term: Term::Atom(atom),
+        }
+        .fail();
+    }
+    out.extend(bytes);
+    Ok(())
+}
+
+fn encode_integer(int: &BigInt, out: &mut Vec<u8>) {
+    if let Some(small) = int.to_u8() {
+        out.push(SMALL_INTEGER_EXT);
+        out.push(small);
+    } else if let Some(int) = int.to_i32() {
+        out.push(INTEGER_EXT);
+        out.extend(&int.to_be_bytes());
+    } else {
+        let (sign, digits) = int.to_bytes_le();
+        if digits.len() < 256 {
+            out.push(SMALL_BIG_EXT);
+            out.push(digits.len() as u8);
+        } else {
+            out.push(LARGE_BIG_EXT);
+            out.extend(&(digits.len() as u32).to_be_bytes());
+        }
+        out.push(if sign == Sign::Minus { 1 } else { 0 });
+        out.extend(digits);
+    }
+}
+
+fn encode_binary(term: &Term, out: &mut Vec<u8>) {
+    let mut bits = binary_bits(term).unwrap();
+    let bit_len = bits.bit_len();
+
+    // Pad to a whole number of bytes, the padding bits are zero.
+    let tail_bits = bit_len % 8;
+    if tail_bits != 0 {
+        bits.push(BitSlice::with_offset_length(&[0u8][..], 0, 8 - tail_bits));
+    }
+    let bytes = bits.try_as_byte_aligned_slice().unwrap();
+
+    if tail_bits == 0 {
+        out.push(BINARY_EXT);
+        out.extend(&(bytes.len() as u32).to_be_bytes());
+    } else {
+        out.push(BIT_BINARY_EXT);
+        out.extend(&(bytes.len() as u32).to_be_bytes());
+        out.push(tail_bits as u8);
+    }
+    out.extend(bytes);
+}
+
+fn encode_term(term: &Rc<Term>, out: &mut Vec<u8>) -> Result<()> {
+    match &**term {
+        Term::Nil => out.push(NIL_EXT),
+        Term::Integer(int) => encode_integer(int, out),
+        Term::Float(flt) => {
+            out.push(NEW_FLOAT_EXT);
+            out.extend(&flt.0.to_bits().to_be_bytes());
+        }
+        Term::Atom(atom) => encode_atom(*atom, out)?,
+        Term::Tuple(elems) => {
+            if elems.len() < 256 {
+                out.push(SMALL_TUPLE_EXT);
+                out.push(elems.len() as u8);
+            } else {
+                out.push(LARGE_TUPLE_EXT);
+                out.extend(&(elems.len() as u32).to_be_bytes());
+            }
+            for elem in elems.iter() {
+                encode_term(elem, out)?;
+            }
+        }
+        Term::ListCell(_, _) => {
+            let (elems, tail) = Term::as_inproper_list(term);
+
+            // Proper lists of bytes are encoded as strings, like BEAM does
+            let bytes: Option<Vec<u8>> = elems
+                .iter()
+                .map(|e| e.as_integer().and_then(|i| i.to_u8()))
+                .collect();
+            match bytes {
+                Some(bytes) if *tail == Term::Nil && bytes.len() < 65536 => {
+                    out.push(STRING_EXT);
+                    out.extend(&(bytes.len() as u16).to_be_bytes());
+                    out.extend(bytes);
+                }
+                _ => {
+                    out.push(LIST_EXT);
+                    out.extend(&(elems.len() as u32).to_be_bytes());
+                    for elem in elems.iter() {
+                        encode_term(elem, out)?;
+                    }
+                    encode_term(&tail, out)?;
+                }
+            }
+        }
+        Term::Map(map) => {
+            out.push(MAP_EXT);
+            out.extend(&(map.len() as u32).to_be_bytes());
+            for (key, val) in map.iter() {
+                encode_term(key, out)?;
+                encode_term(val, out)?;
+            }
+        }
+        Term::Pid(pid) => {
+            out.push(NEW_PID_EXT);
+            encode_atom(Symbol::intern(LOCAL_NODE), out)?;
+            out.extend(&(pid.0 as u32).to_be_bytes());
+            out.extend(&0u32.to_be_bytes());
+            out.extend(&0u32.to_be_bytes());
+        }
+        Term::Reference(reference) => {
+            out.push(NEWER_REFERENCE_EXT);
+            out.extend(&2u16.to_be_bytes());
+            encode_atom(Symbol::intern(LOCAL_NODE), out)?;
+            out.extend(&0u32.to_be_bytes());
+            let id = reference.0 as u64;
+            out.extend(&(id as u32).to_be_bytes());
+            out.extend(&((id >> 32) as u32).to_be_bytes());
+        }
+        Term::Binary(_) | Term::BinarySlice { .. } => encode_binary(term, out),
+        Term::CapturedFunction { ident } => {
+            out.push(EXPORT_EXT);
+            encode_atom(ident.module.name, out)?;
+            encode_atom(ident.name.name, out)?;
+            if ident.arity > 255 {
+                return Unencodable { term: term.clone() }.fail();
+            }
+            out.push(SMALL_INTEGER_EXT);
+            out.push(ident.arity as u8);
+        }
+        _ => return Unencodable { term: term.clone() }.fail(),
+    }
+    Ok(())
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).context(UnexpectedEnd)?;
+        let bytes = self.data.get(self.pos..end).context(UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Reads a count of items that are each at least one byte long. Counts
+    /// larger than the remaining data are rejected up front.
+    fn count(&mut self, count: usize) -> Result<usize> {
+        if count > self.data.len() - self.pos {
+            UnexpectedEnd.fail()
+        } else {
+            Ok(count)
+        }
+    }
+
+    fn terms(&mut self, count: usize) -> Result<Vec<Rc<Term>>> {
+        let count = self.count(count)?;
+        let mut terms = Vec::with_capacity(count);
+        for _ in 0..count {
+            terms.push(self.term()?);
+        }
+        Ok(terms)
+    }
+
+    fn atom_text(&mut self, tag: u8) -> Result<Symbol> {
+        let len = match tag {
+            ATOM_EXT | ATOM_UTF8_EXT => self.u16()? as usize,
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => self.u8()? as usize,
+            _ => return Malformed { what: "atom" }.fail(),
+        };
+        let bytes = self.bytes(len)?;
+        let string = match tag {
+            // Latin-1 maps directly onto the first 256 code points
+            ATOM_EXT | SMALL_ATOM_EXT => bytes.iter().map(|b| *b as char).collect(),
+            _ => std::str::from_utf8(bytes)
+                .ok()
+                .context(Malformed { what: "atom" })?
+                .to_string(),
+        };
+        Ok(Symbol::intern(&string))
+    }
+
+    fn atom(&mut self) -> Result<Symbol> {
+        let tag = self.u8()?;
+        self.atom_text(tag)
+    }
+
+    fn term(&mut self) -> Result<Rc<Term>> {
+        let tag = self.u8()?;
+        let term = match tag {
+            SMALL_INTEGER_EXT => Term::new_i64(self.u8()? as i64),
+            INTEGER_EXT => Term::new_i64(self.u32()? as i32 as i64),
+            SMALL_BIG_EXT | LARGE_BIG_EXT => {
+                let len = if tag == SMALL_BIG_EXT {
+                    self.u8()? as usize
+                } else {
+                    self.u32()? as usize
+                };
+                let sign = match self.u8()? {
+                    0 => Sign::Plus,
+                    1 => Sign::Minus,
+                    _ => {
+                        return Malformed {
+                            what: "big integer",
+                        }
+                        .fail()
+                    }
+                };
+                Term::Integer(BigInt::from_bytes_le(sign, self.bytes(len)?))
+            }
+            NEW_FLOAT_EXT => {
+                let bits = u64::from_be_bytes(self.bytes(8)?.try_into().unwrap());
+                Term::Float(f64::from_bits(bits).into())
+            }
+            FLOAT_EXT => {
+                // Printed with `%.20e`, padded with zero bytes
+                let text = self.bytes(31)?;
+                let text = std::str::from_utf8(text)
+                    .ok()
+                    .context(Malformed { what: "float" })?;
+                let float: f64 = text
+                    .trim_end_matches('\0')
+                    .parse()
+                    .ok()
+                    .context(Malformed { what: "float" })?;
+                Term::Float(float.into())
+            }
+            ATOM_EXT | SMALL_ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT => {
+                Term::Atom(self.atom_text(tag)?)
+            }
+            SMALL_TUPLE_EXT => {
+                let arity = self.u8()? as usize;
+                Term::Tuple(self.terms(arity)?)
+            }
+            LARGE_TUPLE_EXT => {
+                let arity = self.u32()? as usize;
+                Term::Tuple(self.terms(arity)?)
+            }
+            NIL_EXT => Term::Nil,
+            STRING_EXT => {
+                let len = self.u16()? as usize;
+                let chars: Vec<_> = self
+                    .bytes(len)?
+                    .iter()
+                    .map(|b| Term::new_i64(*b as i64).into())
+                    .collect();
+                return Ok(Term::slice_to_list(&chars, Term::Nil.into()));
+            }
+            LIST_EXT => {
+                let len = self.u32()? as usize;
+                let elems = self.terms(len)?;
+                let tail = self.term()?;
+                return Ok(Term::slice_to_list(&elems, tail));
+            }
+            MAP_EXT => {
+                let arity = self.u32()? as usize;
+                let arity = self.count(arity)?;
+                let mut map = MapTerm::new();
+                for _ in 0..arity {
+                    let key = self.term()?;
+                    let val = self.term()?;
+                    map.insert(key, val);
+                }
+                Term::Map(map)
+            }
+            BINARY_EXT => {
+                let len = self.u32()? as usize;
+                let bytes = self.bytes(len)?.to_vec();
+                Term::Binary(Rc::new(bytes.into()))
+            }
+            BIT_BINARY_EXT => {
+                let len = self.u32()? as usize;
+                let tail_bits = self.u8()? as usize;
+                if tail_bits == 0 || tail_bits > 8 || len == 0 {
+                    return Malformed { what: "bitstring" }.fail();
+                }
+                let bytes = self.bytes(len)?;
+                let bit_len = (len - 1) * 8 + tail_bits;
+                let mut bits = BitVec::new();
+                bits.push(BitSlice::with_offset_length(bytes, 0, bit_len));
+                Term::Binary(Rc::new(bits))
+            }
+            PID_EXT | NEW_PID_EXT => {
+                self.atom()?;
+                let id = self.u32()?;
+                self.u32()?;
+                if tag == PID_EXT {
+                    self.u8()?;
+                } else {
+                    self.u32()?;
+                }
+                Term::Pid(Pid(id as usize))
+            }
+            NEW_REFERENCE_EXT | NEWER_REFERENCE_EXT => {
+                let len = self.u16()? as usize;
+                self.atom()?;
+                if tag == NEW_REFERENCE_EXT {
+                    self.u8()?;
+                } else {
+                    self.u32()?;
+                }
+                let mut id = 0u64;
+                for n in 0..len {
+                    let word = self.u32()? as u64;
+                    if n < 2 {
+                        id |= word << (32 * n);
+                    }
+                }
+                Term::Reference(Reference(id as usize))
+            }
+            EXPORT_EXT => {
+                let module = self.atom()?;
+                let name = self.atom()?;
+                if self.u8()? != SMALL_INTEGER_EXT {
+                    return Malformed { what: "export" }.fail();
+                }
+                let arity = self.u8()? as usize;
+                Term::CapturedFunction {
+                    ident: FunctionIdent {
+                        module: Ident::with_empty_span(module),
+                        name: Ident::with_empty_span(name),
+                        arity,
+                    },
+                }
+            }
+            COMPRESSED => return Compressed.fail(),
+            _ => return UnknownTag { tag }.fail(),
+        };
+        Ok(term.into())
+    }
+}
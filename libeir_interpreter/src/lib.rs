@@ -5,7 +5,7 @@
 //! details.
 
 mod term;
-pub use term::{ErlEq, ErlExactEq, ErlOrd, Pid, Reference, Term, TermType};
+pub use term::{ErlEq, ErlExactEq, ErlOrd, MapTerm, Pid, Reference, Term, TermType};
 
 pub mod erl_lib;
 
@@ -13,7 +13,13 @@ mod vm;
 pub use vm::{VMState, WatchType};
 
 mod process;
+pub use process::ProcessState;
 
 mod module;
 
-//mod trace;
+pub mod etf;
+
+mod mailbox;
+mod receive;
+
+pub mod trace;
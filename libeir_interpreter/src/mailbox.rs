@@ -1,9 +1,11 @@
-use ::term::{ Term, Pid };
+use std::rc::Rc;
+
+use crate::term::Term;
 
 #[derive(Debug)]
 pub struct Mailbox {
     trap_exits: bool,
-    messages: Vec<(Pid, Term)>,
+    messages: Vec<Rc<Term>>,
 }
 
 impl Mailbox {
@@ -19,4 +21,17 @@ impl Mailbox {
     pub fn set_trap_exits(&mut self, val: bool) {
         self.trap_exits = val;
     }
+
+    /// Appends a message to the end of the queue.
+    pub fn push(&mut self, message: Rc<Term>) {
+        self.messages.push(message);
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Rc<Term>> {
+        self.messages.get(idx)
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Rc<Term> {
+        self.messages.remove(idx)
+    }
 }
@@ -8,8 +8,32 @@ use libeir_intern::Symbol;
 use libeir_ir::{Function, FunctionIdent, LiveValues, Module};
 
 pub enum NativeReturn {
-    Return { term: Rc<Term> },
-    Throw { typ: Rc<Term>, reason: Rc<Term> },
+    Return {
+        term: Rc<Term>,
+    },
+    Throw {
+        typ: Rc<Term>,
+        reason: Rc<Term>,
+    },
+    /// Tail calls `fun` with `args`, with the return and throw
+    /// continuations of the native function.
+    Call {
+        fun: Rc<Term>,
+        args: Vec<Rc<Term>>,
+    },
+    /// Calls `fun` with `args`, and passes the result on to the native
+    /// function `cont` of the same module. `cont` is called with
+    /// `environment` followed by the result, and takes over the return and
+    /// throw continuations. Exceptions skip `cont`.
+    ///
+    /// This lets native functions call back into Erlang without recursing
+    /// on the Rust stack.
+    CallThen {
+        fun: Rc<Term>,
+        args: Vec<Rc<Term>>,
+        cont: Symbol,
+        environment: Vec<Rc<Term>>,
+    },
 }
 
 pub struct NativeModule {
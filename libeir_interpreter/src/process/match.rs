@@ -3,6 +3,7 @@ use libeir_ir::{BasicType, BinaryEntrySpecifier, Block, Endianness, MatchKind};
 use libeir_util_binary::BitCarrier;
 use libeir_util_binary::{carrier_to_integer, BitSlice, BitVec, Endian};
 
+use crate::erl_lib::binary_slice;
 use crate::module::ErlangFunction;
 use crate::term::ErlExactEq;
 use crate::Term;
@@ -154,12 +155,19 @@ pub fn match_op(
                 };
                 return ret;
             }
-            MatchKind::Binary(BinaryEntrySpecifier::Bytes { unit: 8 }) => match &*unpack_term {
-                Term::Binary(bin) => {
-                    if bin.bit_len() % 8 != 0 {
+            MatchKind::Binary(BinaryEntrySpecifier::Bytes { unit })
+            | MatchKind::Binary(BinaryEntrySpecifier::Bits { unit }) => {
+                let (buf, bit_offset, bit_length) = match binary_slice(&unpack_term) {
+                    Some(slice) => slice,
+                    None => continue,
+                };
+                let unit = *unit as usize;
+
+                // Without a size the entry takes the rest of the binary
+                if branch_args.len() == 0 {
+                    if bit_length % unit != 0 {
                         continue;
                     }
-
                     return TermCall {
                         fun: branches_elems[idx].clone(),
                         args: vec![
@@ -168,21 +176,33 @@ pub fn match_op(
                         ],
                     };
                 }
-                Term::BinarySlice { bit_length, .. } => {
-                    if *bit_length % 8 != 0 {
-                        continue;
-                    }
 
-                    return TermCall {
-                        fun: branches_elems[idx].clone(),
-                        args: vec![
-                            unpack_term.clone(),
-                            Term::Binary(BitVec::new().into()).into(),
-                        ],
-                    };
+                let take = match branch_args[0].as_i64() {
+                    Some(size) if size >= 0 => size as usize * unit,
+                    _ => continue,
+                };
+                if take > bit_length {
+                    continue;
                 }
-                _ => (),
-            },
+
+                return TermCall {
+                    fun: branches_elems[idx].clone(),
+                    args: vec![
+                        Term::BinarySlice {
+                            buf: buf.clone(),
+                            bit_offset,
+                            bit_length: take,
+                        }
+                        .into(),
+                        Term::BinarySlice {
+                            buf,
+                            bit_offset: bit_offset + take,
+                            bit_length: bit_length - take,
+                        }
+                        .into(),
+                    ],
+                };
+            }
             MatchKind::Wildcard => {
                 assert!(branch_args.len() == 0);
                 return TermCall {
@@ -4,23 +4,33 @@ use std::rc::Rc;
 
 use num_traits::cast::ToPrimitive;
 
-use libeir_intern::Ident;
+use libeir_intern::{Ident, Symbol};
 use libeir_ir::constant::{AtomicTerm, Const, ConstKind};
 use libeir_ir::operation::binary_construct::{
     BinaryConstructFinish, BinaryConstructPush, BinaryConstructStart,
 };
+use libeir_ir::operation::receive::{ReceiveDone, ReceiveStart, ReceiveWait};
 use libeir_ir::MapPutUpdate;
-use libeir_ir::{BinOp, Block, FunctionIdent, LogicOp, OpKind, PrimOpKind, Value, ValueKind};
+use libeir_ir::{
+    BinOp, Block, CallKind, FunctionIdent, LogicOp, OpKind, PrimOpKind, Value, ValueKind,
+};
 use libeir_ir::{BinaryEntrySpecifier, Endianness};
 
 use libeir_util_binary::{integer_to_carrier, BitSlice, BitVec, Endian};
 
+use crate::erl_lib::binary_slice;
+use crate::mailbox::Mailbox;
 use crate::module::{ErlangFunction, ErlangModule, ModuleType, NativeModule, NativeReturn};
+use crate::receive::ReceiveContext;
 use crate::term::{ErlEq, MapTerm, Pid, Term};
+use crate::trace;
 use crate::vm::VMState;
 
 mod r#match;
 
+mod stack;
+pub use self::stack::{construct_trace, format_exception, CallStack, Resumed, TraceFrame};
+
 #[derive(Debug)]
 pub struct TermCall {
     pub fun: Rc<Term>,
@@ -46,6 +56,18 @@ impl CallExecutor {
 
     pub fn run(&mut self, vm: &VMState, proc: &mut ProcessContext, call: TermCall) -> Continuation {
         self.binds.clear();
+        match proc.stack.resume(&call.fun) {
+            Some(Resumed::Return) => trace::ret(proc.pid, &call.args[0]),
+            Some(Resumed::Throw(unwound)) => {
+                trace::throw(proc.pid, &call.args[0], &call.args[1], unwound)
+            }
+            // Leaving the function the process was spawned with.
+            None => match &*call.fun {
+                Term::ReturnOk => trace::ret(proc.pid, &call.args[0]),
+                Term::ReturnThrow => trace::throw(proc.pid, &call.args[0], &call.args[1], 1),
+                _ => (),
+            },
+        }
         match &*call.fun {
             Term::BoundLambda {
                 ident,
@@ -55,35 +77,55 @@ impl CallExecutor {
                 let module = &vm.modules[&ident.module.name];
                 match module {
                     ModuleType::Erlang(erl, _overlay) => Continuation::Term(
-                        self.run_erlang(vm, erl, ident, Some((*block, &*environment)), &call.args)
-                            .unwrap(),
+                        self.run_erlang(
+                            vm,
+                            proc,
+                            erl,
+                            ident,
+                            Some((*block, &*environment)),
+                            &call.args,
+                        )
+                        .unwrap(),
                     ),
                     ModuleType::Native(_native) => unreachable!(),
                 }
             }
             Term::CapturedFunction { ident } => {
-                let module = &vm.modules[&ident.module.name];
-                match module {
-                    ModuleType::Erlang(erl, overlay) => {
-                        if let Some(native) = overlay {
-                            if let Some(res) = self.run_native(vm, proc, native, ident, &call.args)
-                            {
-                                return Continuation::Term(res);
-                            }
+                let res = match vm.modules.get(&ident.module.name) {
+                    Some(ModuleType::Erlang(erl, overlay)) => {
+                        let native_res = match overlay {
+                            Some(native) => self.run_native(vm, proc, native, ident, &call.args),
+                            None => None,
+                        };
+                        match native_res {
+                            Some(res) => Some(res),
+                            None => self.run_erlang(vm, proc, erl, ident, None, &call.args),
                         }
-                        println!("{}", ident);
-                        Continuation::Term(
-                            self.run_erlang(vm, erl, ident, None, &call.args).unwrap(),
-                        )
                     }
-                    ModuleType::Native(native) => Continuation::Term(
-                        if let Some(res) = self.run_native(vm, proc, native, ident, &call.args) {
-                            res
-                        } else {
-                            panic!("Could not find native function {}", ident);
-                        },
-                    ),
-                }
+                    Some(ModuleType::Native(native)) => {
+                        self.run_native(vm, proc, native, ident, &call.args)
+                    }
+                    None => None,
+                };
+                // Calling a function that does not exist raises `undef`
+                Continuation::Term(res.unwrap_or_else(|| TermCall {
+                    fun: call.args[1].clone(),
+                    args: vec![
+                        Term::new_atom("error").into(),
+                        Term::new_atom("undef").into(),
+                        proc.stack.capture(),
+                    ],
+                }))
+            }
+            Term::NativeContinuation { ident, environment } => {
+                let native = match &vm.modules[&ident.module.name] {
+                    ModuleType::Erlang(_erl, overlay) => overlay.as_ref().unwrap(),
+                    ModuleType::Native(native) => native,
+                };
+                proc.stack.enter_native();
+                let mut args = environment.clone();
+                args.extend(call.args.iter().cloned());
+                Continuation::Term(self.run_native(vm, proc, native, ident, &args).unwrap())
             }
             Term::ReturnOk => {
                 assert!(call.args.len() == 1);
@@ -118,8 +160,45 @@ impl CallExecutor {
                 }),
                 NativeReturn::Throw { typ, reason } => Some(TermCall {
                     fun: args[1].clone(),
-                    args: vec![typ, reason, Term::Nil.into()],
+                    args: vec![typ, reason, proc.stack.capture()],
                 }),
+                NativeReturn::Call {
+                    fun,
+                    args: call_args,
+                } => {
+                    let mut n_args = Vec::with_capacity(call_args.len() + 2);
+                    n_args.push(args[0].clone());
+                    n_args.push(args[1].clone());
+                    n_args.extend(call_args);
+                    trace::call(proc.pid, &fun, &n_args[2..], true);
+                    Some(TermCall { fun, args: n_args })
+                }
+                NativeReturn::CallThen {
+                    fun,
+                    args: call_args,
+                    cont,
+                    environment,
+                } => {
+                    let mut cont_env = Vec::with_capacity(environment.len() + 2);
+                    cont_env.push(args[0].clone());
+                    cont_env.push(args[1].clone());
+                    cont_env.extend(environment);
+
+                    let cont = Term::NativeContinuation {
+                        ident: FunctionIdent {
+                            module: ident.module,
+                            name: Ident::with_empty_span(cont),
+                            arity: cont_env.len() - 1,
+                        },
+                        environment: cont_env,
+                    };
+
+                    let mut n_args = Vec::with_capacity(call_args.len() + 2);
+                    n_args.push(cont.into());
+                    n_args.push(args[1].clone());
+                    n_args.extend(call_args);
+                    Some(TermCall { fun, args: n_args })
+                }
             }
         } else {
             None
@@ -129,6 +208,7 @@ impl CallExecutor {
     pub fn run_erlang(
         &mut self,
         vm: &VMState,
+        proc: &mut ProcessContext,
         module: &ErlangModule,
         ident: &FunctionIdent,
         state: Option<(Block, &[Rc<Term>])>,
@@ -148,6 +228,8 @@ impl CallExecutor {
             } else {
                 fun.fun.block_entry()
             };
+            proc.stack.enter(fun, block);
+            trace::block(proc.pid, ident, block);
 
             // Insert arguments
             let block_arg_vals = fun.fun.block_args(block);
@@ -157,7 +239,7 @@ impl CallExecutor {
             }
 
             // Execute operation
-            Some(self.run_erlang_op(vm, fun, block))
+            Some(self.run_erlang_op(vm, proc, fun, block))
         } else {
             None
         }
@@ -285,11 +367,32 @@ impl CallExecutor {
         }
     }
 
-    pub fn run_erlang_op(&mut self, _vm: &VMState, fun: &ErlangFunction, block: Block) -> TermCall {
+    pub fn run_erlang_op(
+        &mut self,
+        vm: &VMState,
+        proc: &mut ProcessContext,
+        fun: &ErlangFunction,
+        block: Block,
+    ) -> TermCall {
         let reads = fun.fun.block_reads(block);
-        println!("OP: {:?}", fun.fun.block_kind(block).unwrap());
         match fun.fun.block_kind(block).unwrap() {
-            OpKind::Call(_) => TermCall {
+            OpKind::Call(CallKind::Function) => {
+                let call = TermCall {
+                    fun: self.make_term(fun, reads[0]),
+                    args: reads
+                        .iter()
+                        .skip(1)
+                        .map(|r| self.make_term(fun, *r))
+                        .collect(),
+                };
+                // A return continuation that is a block of this function
+                // means we get control back, anything else is a tail call.
+                let pushed = fun.fun.value_block(reads[1]).is_some()
+                    && proc.stack.push_call(&call.args[0], &call.args[1]);
+                trace::call(proc.pid, &call.fun, &call.args[2..], !pushed);
+                call
+            }
+            OpKind::Call(CallKind::ControlFlow) => TermCall {
                 fun: self.make_term(fun, reads[0]),
                 args: reads
                     .iter()
@@ -340,8 +443,15 @@ impl CallExecutor {
             }
             OpKind::TraceCaptureRaw => TermCall {
                 fun: self.make_term(fun, reads[0]),
-                args: vec![Term::Nil.into()],
+                args: vec![proc.stack.capture()],
             },
+            OpKind::TraceConstruct => {
+                let raw = self.make_term(fun, reads[1]);
+                TermCall {
+                    fun: self.make_term(fun, reads[0]),
+                    args: vec![construct_trace(vm, &raw)],
+                }
+            }
             OpKind::Match { branches } => self::r#match::match_op(self, fun, branches, block),
             OpKind::Dyn(dyn_op) => {
                 let tid = dyn_op.type_id();
@@ -426,15 +536,24 @@ impl CallExecutor {
                                     _ => unreachable!(),
                                 }
                             }
-                            BinaryEntrySpecifier::Bytes { unit: 1 } => {
-                                let binary = val_term.as_binary().unwrap();
-
-                                if let Some(size_term) = size_term {
-                                    dbg!(&size_term, &binary);
-                                    assert!(size_term.as_usize().unwrap() == binary.len());
-                                }
+                            BinaryEntrySpecifier::Bytes { unit }
+                            | BinaryEntrySpecifier::Bits { unit } => {
+                                let (buf, bit_offset, bit_length) =
+                                    binary_slice(&val_term).unwrap();
+
+                                // Without a size the whole binary is pushed
+                                let bit_length = match size_term {
+                                    Some(size_term) => {
+                                        let size = size_term.as_usize().unwrap() * unit as usize;
+                                        assert!(size <= bit_length);
+                                        size
+                                    }
+                                    None => bit_length,
+                                };
 
-                                bin.push(binary);
+                                bin.push(BitSlice::with_offset_length(
+                                    &*buf, bit_offset, bit_length,
+                                ));
                             }
                             k => unimplemented!("{:?}", k),
                         }
@@ -448,6 +567,59 @@ impl CallExecutor {
                         fun: self.make_term(fun, reads[0]),
                         args: vec![self.make_term(fun, reads[1])],
                     },
+                    _ if tid == TypeId::of::<ReceiveStart>() => {
+                        let timeout = self.make_term(fun, reads[2]);
+                        if let Some(recv) = ReceiveContext::new(&timeout, vm.time()) {
+                            proc.receive = Some(recv);
+                            // Receive state is kept in the process, the
+                            // receive reference carries nothing.
+                            TermCall {
+                                fun: self.make_term(fun, reads[0]),
+                                args: vec![Term::Nil.into()],
+                            }
+                        } else {
+                            TermCall {
+                                fun: self.make_term(fun, reads[1]),
+                                args: vec![],
+                            }
+                        }
+                    }
+                    _ if tid == TypeId::of::<ReceiveWait>() => {
+                        let recv = proc.receive.as_mut().unwrap();
+                        if let Some(message) = recv.next_message(&proc.mailbox) {
+                            TermCall {
+                                fun: self.make_term(fun, reads[1]),
+                                args: vec![message],
+                            }
+                        } else if recv.timed_out(vm.time()) {
+                            proc.receive = None;
+                            TermCall {
+                                fun: self.make_term(fun, reads[0]),
+                                args: vec![],
+                            }
+                        } else {
+                            // Yield until a message arrives or the timeout
+                            // expires, and then enter this block again.
+                            proc.state = ProcessState::Waiting;
+                            TermCall {
+                                fun: self.make_term(fun, fun.fun.block_value(block)),
+                                args: fun
+                                    .fun
+                                    .block_args(block)
+                                    .iter()
+                                    .map(|a| self.binds[a].clone())
+                                    .collect(),
+                            }
+                        }
+                    }
+                    _ if tid == TypeId::of::<ReceiveDone>() => {
+                        let recv = proc.receive.take().unwrap();
+                        recv.done(&mut proc.mailbox);
+                        TermCall {
+                            fun: self.make_term(fun, reads[0]),
+                            args: reads[2..].iter().map(|r| self.make_term(fun, *r)).collect(),
+                        }
+                    }
                     _ => unimplemented!(),
                 }
             }
@@ -561,16 +733,146 @@ impl CallExecutor {
     }
 }
 
+/// Where a process is in its lifecycle.
+#[derive(Debug, Clone)]
+pub enum ProcessState {
+    /// The process has a continuation and will be run by the scheduler.
+    Runnable,
+    /// The process is blocked in a receive, waiting for a message or for
+    /// its timeout to expire.
+    Waiting,
+    /// The process called its return continuation with the given value.
+    Returned(Rc<Term>),
+    /// The process called its throw continuation, or was killed by an
+    /// exit signal.
+    Threw(Rc<Term>, Rc<Term>, Rc<Term>),
+}
+
+impl ProcessState {
+    pub fn is_runnable(&self) -> bool {
+        match self {
+            ProcessState::Runnable => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        match self {
+            ProcessState::Runnable | ProcessState::Waiting => true,
+            _ => false,
+        }
+    }
+
+    /// The reason the process exited with, `None` if it is still alive.
+    ///
+    /// Follows the conventions of the BEAM, an uncaught `exit` exits with
+    /// the reason as is, an `error` with `{Reason, Trace}` and a `throw`
+    /// with `{{nocatch, Reason}, Trace}`.
+    pub fn exit_reason(&self) -> Option<Rc<Term>> {
+        match self {
+            ProcessState::Runnable | ProcessState::Waiting => None,
+            ProcessState::Returned(_) => Some(Term::new_atom("normal").into()),
+            ProcessState::Threw(typ, reason, trace) => {
+                let reason = match typ.as_atom() {
+                    Some(typ) if typ == Symbol::intern("exit") => reason.clone(),
+                    Some(typ) if typ == Symbol::intern("throw") => Term::Tuple(vec![
+                        Term::Tuple(vec![Term::new_atom("nocatch").into(), reason.clone()]).into(),
+                        trace.clone(),
+                    ])
+                    .into(),
+                    _ => Term::Tuple(vec![reason.clone(), trace.clone()]).into(),
+                };
+                Some(reason)
+            }
+        }
+    }
+}
+
 pub struct ProcessContext {
     pub pid: Pid,
     pub dict: Vec<(Rc<Term>, Rc<Term>)>,
+    /// Process flags set with `process_flag/2`, other than `trap_exit`.
+    /// They are only stored, and have no effect on execution.
+    pub flags: Vec<(Rc<Term>, Rc<Term>)>,
+    pub state: ProcessState,
+    /// The next call the process will make when it is scheduled.
+    pub continuation: Option<TermCall>,
+    pub mailbox: Mailbox,
+    /// Set while the process is inside a receive construct.
+    pub receive: Option<ReceiveContext>,
+    pub stack: CallStack,
 }
 
 impl ProcessContext {
-    pub fn new(pid: Pid) -> Self {
+    pub fn new(pid: Pid, call: TermCall) -> Self {
         ProcessContext {
             pid,
             dict: Vec::new(),
+            flags: Vec::new(),
+            state: ProcessState::Runnable,
+            continuation: Some(call),
+            mailbox: Mailbox::new(),
+            receive: None,
+            stack: CallStack::new(),
+        }
+    }
+
+    /// Adds a message to the mailbox of the process, waking it up if it is
+    /// waiting in a receive. Messages to exited processes are dropped.
+    pub fn deliver(&mut self, message: Rc<Term>) {
+        if self.state.is_alive() {
+            self.mailbox.push(message);
+            if let ProcessState::Waiting = self.state {
+                self.state = ProcessState::Runnable;
+            }
+        }
+    }
+
+    /// Handles an exit signal from `from`. `linked` is set when the signal
+    /// comes from a linked process that exited, rather than from `exit/2`.
+    /// Returns the exit reason if the process exited because of it.
+    ///
+    /// A process trapping exits gets the signal as an `{'EXIT', From,
+    /// Reason}` message instead, unless the reason is `kill` sent through
+    /// `exit/2`, which can not be trapped.
+    pub fn exit_signal(&mut self, from: Pid, reason: Rc<Term>, linked: bool) -> Option<Rc<Term>> {
+        if !self.state.is_alive() {
+            return None;
+        }
+
+        let reason = if !linked && reason.erl_eq(&Term::new_atom("kill")) {
+            Term::new_atom("killed").into()
+        } else if self.mailbox.get_trap_exits() {
+            let message = Term::Tuple(vec![
+                Term::new_atom("EXIT").into(),
+                Term::Pid(from).into(),
+                reason,
+            ]);
+            self.deliver(message.into());
+            return None;
+        } else if reason.erl_eq(&Term::new_atom("normal")) && (linked || from != self.pid) {
+            return None;
+        } else {
+            reason
+        };
+
+        self.exit(ProcessState::Threw(
+            Term::new_atom("exit").into(),
+            reason.clone(),
+            Term::Nil.into(),
+        ));
+        Some(reason)
+    }
+
+    /// Stops the process. Does nothing if it has already exited.
+    pub fn exit(&mut self, state: ProcessState) {
+        if self.state.is_alive() {
+            self.state = state;
+            self.continuation = None;
+            self.receive = None;
+            if let Some(reason) = self.state.exit_reason() {
+                trace::exit(self.pid, &reason);
+            }
         }
     }
 }
@@ -0,0 +1,215 @@
+use std::rc::Rc;
+
+use libeir_ir::{Block, FunctionIdent, Location};
+
+use crate::module::{ErlangFunction, ModuleType};
+use crate::term::Term;
+use crate::vm::VMState;
+
+/// Number of frames kept in a stack trace, the same as the default
+/// `backtrace_depth` of the BEAM.
+pub const TRACE_DEPTH: usize = 8;
+
+/// A single entry of a stack trace. The location is the block that was
+/// executing in the function, for callers that is the call site.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
+pub struct TraceFrame {
+    pub ident: FunctionIdent,
+    pub location: Location,
+}
+
+/// A non-tail call that has not returned yet. `ret` and `thr` are the
+/// continuations the callee was given, the frame is popped when either of
+/// them is called.
+struct CallFrame {
+    frame: TraceFrame,
+    ret: Rc<Term>,
+    thr: Rc<Term>,
+}
+
+/// How the calls popped by `CallStack::resume` were left.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Resumed {
+    Return,
+    /// An exception, with the number of calls it unwound.
+    Throw(usize),
+}
+
+/// Tracks the call stack of a process.
+///
+/// Everything is a continuation in Eir, calls only push a frame when the
+/// return continuation is a block of the calling function, tail calls
+/// replace the current frame like they do on the BEAM.
+pub struct CallStack {
+    /// The Erlang function that is executing. `None` while a native function
+    /// runs.
+    current: Option<TraceFrame>,
+    frames: Vec<CallFrame>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        CallStack {
+            current: None,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Called whenever a block of an Erlang function is executed. Within
+    /// the same function the location is only updated for blocks that know
+    /// their line, most blocks only carry a span.
+    pub fn enter(&mut self, fun: &ErlangFunction, block: Block) {
+        let ident = fun.fun.ident();
+        let location = fun.fun.block_location(block);
+        match &mut self.current {
+            Some(current) if current.ident == *ident => {
+                if fun.fun.locations.file_line(&location).is_some() {
+                    current.location = location;
+                }
+            }
+            current => {
+                *current = Some(TraceFrame {
+                    ident: *ident,
+                    location,
+                });
+            }
+        }
+    }
+
+    /// Called for a non-tail call out of the current function. Returns
+    /// whether a frame was pushed.
+    pub fn push_call(&mut self, ret: &Rc<Term>, thr: &Rc<Term>) -> bool {
+        if let Some(frame) = self.current.take() {
+            self.frames.push(CallFrame {
+                frame,
+                ret: ret.clone(),
+                thr: thr.clone(),
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Called before a term is called. If it is the return or throw
+    /// continuation of the innermost call, the call is done.
+    ///
+    /// A throw continuation is passed on by every function without a
+    /// `try`, an exception unwinds all the frames that share it.
+    pub fn resume(&mut self, fun: &Rc<Term>) -> Option<Resumed> {
+        let mut resumed = None;
+        let mut unwound = 0;
+        while let Some(top) = self.frames.last() {
+            if Rc::ptr_eq(&top.ret, fun) {
+                resumed = Some(Resumed::Return);
+            } else if Rc::ptr_eq(&top.thr, fun) {
+                unwound += 1;
+                resumed = Some(Resumed::Throw(unwound));
+            } else {
+                break;
+            }
+            let call = self.frames.pop().unwrap();
+            self.current = Some(call.frame);
+        }
+        resumed
+    }
+
+    /// Leaves the current function for a native one.
+    pub fn enter_native(&mut self) {
+        self.current = None;
+    }
+
+    /// Captures the innermost `TRACE_DEPTH` frames as a raw trace term.
+    pub fn capture(&self) -> Rc<Term> {
+        let frames = self
+            .current
+            .iter()
+            .chain(self.frames.iter().rev().map(|call| &call.frame))
+            .take(TRACE_DEPTH)
+            .cloned()
+            .collect();
+        Term::RawTrace(Rc::new(frames)).into()
+    }
+}
+
+/// Builds the `[{M, F, A, [{file, File}, {line, Line}]}]` list from a raw
+/// trace. Anything that is not a raw trace is returned as is, so traces
+/// that have already been constructed pass through.
+pub fn construct_trace(vm: &VMState, raw: &Rc<Term>) -> Rc<Term> {
+    let frames = match &**raw {
+        Term::RawTrace(frames) => frames,
+        _ => return raw.clone(),
+    };
+
+    let entries: Vec<_> = frames
+        .iter()
+        .map(|frame| {
+            let ident = &frame.ident;
+
+            let mut info = Vec::new();
+            if let Some((file, line)) = frame_file_line(vm, frame) {
+                let file_chars: Vec<_> = file
+                    .chars()
+                    .map(|c| Term::new_i64(c as i64).into())
+                    .collect();
+                info.push(
+                    Term::Tuple(vec![
+                        Term::new_atom("file").into(),
+                        Term::slice_to_list(&file_chars, Term::Nil.into()),
+                    ])
+                    .into(),
+                );
+                info.push(
+                    Term::Tuple(vec![
+                        Term::new_atom("line").into(),
+                        Term::new_i64(line as i64).into(),
+                    ])
+                    .into(),
+                );
+            }
+
+            Term::Tuple(vec![
+                Term::Atom(ident.module.name).into(),
+                Term::Atom(ident.name.name).into(),
+                Term::new_usize(ident.arity).into(),
+                Term::slice_to_list(&info, Term::Nil.into()),
+            ])
+            .into()
+        })
+        .collect();
+
+    Term::slice_to_list(&entries, Term::Nil.into())
+}
+
+/// Formats an exception the way the Erlang shell prints them.
+pub fn format_exception(vm: &VMState, typ: &Term, reason: &Term, raw: &Rc<Term>) -> String {
+    let mut out = format!("** exception {}: {}\n", typ, reason);
+
+    let frames = match &**raw {
+        Term::RawTrace(frames) => frames,
+        _ => return out,
+    };
+    for (idx, frame) in frames.iter().enumerate() {
+        let prefix = if idx == 0 {
+            "in function "
+        } else {
+            "in call from"
+        };
+        out.push_str(&format!("     {} {}", prefix, frame.ident));
+        if let Some((file, line)) = frame_file_line(vm, frame) {
+            out.push_str(&format!(" ({}, line {})", file, line));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Looks up the file and line of a frame. Lines are one based in traces.
+fn frame_file_line(vm: &VMState, frame: &TraceFrame) -> Option<(String, u32)> {
+    let fun = match vm.modules.get(&frame.ident.module.name)? {
+        ModuleType::Erlang(erl, _) => erl.functions.get(&frame.ident)?,
+        ModuleType::Native(_) => return None,
+    };
+    let (file, line) = fun.fun.locations.file_line(&frame.location)?;
+    Some((file.to_string(), line + 1))
+}
@@ -1,15 +1,62 @@
-use ::term::Term;
+use std::rc::Rc;
 
+use libeir_intern::Symbol;
+
+use crate::mailbox::Mailbox;
+use crate::term::Term;
+
+/// State of a receive construct, from `receive_start` until either
+/// `receive_done` or the timeout branch of `receive_wait` is taken.
 #[derive(Debug)]
 pub struct ReceiveContext {
+    /// Number of messages at the front of the mailbox that have already
+    /// been checked. Messages that don't match stay in the mailbox, and
+    /// are skipped the next time `receive_wait` is entered.
+    save: usize,
+    /// Time on the virtual clock at which the receive times out. `None`
+    /// for `infinity`.
+    deadline: Option<u64>,
 }
 
 impl ReceiveContext {
+    /// Returns `None` if the timeout is not `infinity` or a non negative
+    /// integer.
+    pub fn new(timeout: &Term, now: u64) -> Option<Self> {
+        let deadline = match timeout {
+            Term::Atom(atom) if *atom == Symbol::intern("infinity") => None,
+            Term::Integer(_) => {
+                let timeout = timeout.as_i64()?;
+                if timeout < 0 {
+                    return None;
+                }
+                Some(now + timeout as u64)
+            }
+            _ => return None,
+        };
 
-    pub fn new(timeout: Term) -> Self {
-        // TODO: Timeout
-        ReceiveContext {
-        }
+        Some(ReceiveContext { save: 0, deadline })
     }
 
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    pub fn timed_out(&self, now: u64) -> bool {
+        self.deadline.map(|d| d <= now).unwrap_or(false)
+    }
+
+    /// Moves the save pointer past the next message in the mailbox, and
+    /// returns that message.
+    pub fn next_message(&mut self, mailbox: &Mailbox) -> Option<Rc<Term>> {
+        let message = mailbox.get(self.save)?.clone();
+        self.save += 1;
+        Some(message)
+    }
+
+    /// Removes the message that was last returned by `next_message` from
+    /// the mailbox.
+    pub fn done(self, mailbox: &mut Mailbox) -> Rc<Term> {
+        assert!(self.save > 0);
+        mailbox.remove(self.save - 1)
+    }
 }
@@ -1,13 +1,17 @@
 use ::std::rc::Rc;
 use std::cmp::{Ord, Ordering};
 use std::collections::HashMap;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
 use libeir_intern::{LocalInternedString, Symbol};
 
 use libeir_ir::{Block, FunctionIdent};
 
+use crate::process::TraceFrame;
+
 use libeir_util_binary::{BitSlice, BitVec};
+use libeir_util_number::bigint_to_double;
 
 use ::num_bigint::BigInt;
 use ::num_traits::cast::ToPrimitive;
@@ -39,6 +43,8 @@ pub enum TermType {
     ValueList,
     ReturnOk,
     ReturnThrow,
+    NativeContinuation,
+    RawTrace,
 }
 
 #[derive(Debug, Copy, Clone, PartialOrd)]
@@ -80,7 +86,7 @@ impl MapTerm {
 
     pub fn insert(&mut self, key: Rc<Term>, val: Rc<Term>) -> bool {
         self.map.insert(key.clone(), val.clone());
-        match self.sorted.binary_search_by(|(k, _)| k.cmp(&key)) {
+        match self.sorted.binary_search_by(|(k, _)| map_key_cmp(k, &key)) {
             Ok(idx) => {
                 self.sorted[idx] = (key, val);
                 true
@@ -96,10 +102,31 @@ impl MapTerm {
         self.map.get(key).cloned()
     }
 
+    pub fn remove(&mut self, key: &Rc<Term>) -> Option<Rc<Term>> {
+        let val = self.map.remove(key)?;
+        let idx = self
+            .sorted
+            .binary_search_by(|(k, _)| map_key_cmp(k, key))
+            .unwrap();
+        self.sorted.remove(idx);
+        Some(val)
+    }
+
+    /// Iterates over the entries of the map, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = &(Rc<Term>, Rc<Term>)> {
+        self.sorted.iter()
+    }
+
     pub fn len(&self) -> usize {
         self.map.len()
     }
 }
+/// Keys are kept in term order. Keys that are equal in term order, like
+/// `1` and `1.0`, are still distinct keys.
+fn map_key_cmp(l: &Term, r: &Term) -> Ordering {
+    l.erl_ord(r).then_with(|| l.cmp(r))
+}
+
 impl PartialEq for MapTerm {
     fn eq(&self, other: &MapTerm) -> bool {
         self.sorted == other.sorted
@@ -152,6 +179,17 @@ pub enum Term {
     ValueList(Vec<Rc<Term>>),
     ReturnOk,
     ReturnThrow,
+    /// Continuation back into the native function `ident`. When called,
+    /// the native function is run with `environment` followed by the call
+    /// arguments. The first two entries of the environment are the return
+    /// and throw continuations of the native function.
+    NativeContinuation {
+        ident: FunctionIdent,
+        environment: Vec<Rc<Term>>,
+    },
+    /// Stack trace captured by `TraceCaptureRaw`, turned into a list by
+    /// `TraceConstruct`.
+    RawTrace(Rc<Vec<TraceFrame>>),
 }
 
 impl Term {
@@ -247,6 +285,17 @@ impl PartialEq for Term {
             (ValueList(l), ValueList(r)) => l == r,
             (ReturnOk, ReturnOk) => true,
             (ReturnThrow, ReturnThrow) => true,
+            (
+                NativeContinuation {
+                    ident: li,
+                    environment: le,
+                },
+                NativeContinuation {
+                    ident: ri,
+                    environment: re,
+                },
+            ) => li == ri && le == re,
+            (RawTrace(l), RawTrace(r)) => l == r,
             _ => false,
         }
     }
@@ -335,6 +384,20 @@ impl PartialOrd for Term {
             (ValueList(l), ValueList(r)) => l.partial_cmp(r),
             (ReturnOk, ReturnOk) => Some(Ordering::Equal),
             (ReturnThrow, ReturnThrow) => Some(Ordering::Equal),
+            (
+                NativeContinuation {
+                    ident: li,
+                    environment: le,
+                },
+                NativeContinuation {
+                    ident: ri,
+                    environment: re,
+                },
+            ) => match li.partial_cmp(ri) {
+                Some(Ordering::Equal) | None => le.partial_cmp(re),
+                non_eq => non_eq,
+            },
+            (RawTrace(l), RawTrace(r)) => l.partial_cmp(r),
             (l, r) => l.order_idx().partial_cmp(&r.order_idx()),
         }
     }
@@ -380,6 +443,11 @@ impl Hash for Term {
             ValueList(i) => i.hash(state),
             ReturnOk => (),
             ReturnThrow => (),
+            NativeContinuation { ident, environment } => {
+                ident.hash(state);
+                environment.hash(state);
+            }
+            RawTrace(frames) => frames.hash(state),
         }
     }
 }
@@ -501,6 +569,8 @@ impl Term {
             Term::ValueList(_) => TermType::ValueList,
             Term::ReturnOk => TermType::ReturnOk,
             Term::ReturnThrow => TermType::ReturnThrow,
+            Term::NativeContinuation { .. } => TermType::NativeContinuation,
+            Term::RawTrace(_) => TermType::RawTrace,
         }
     }
 
@@ -736,6 +806,158 @@ impl Term {
     }
 }
 
+/// Atoms that need quotes even though they look like plain atoms.
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "not", "of", "or", "orelse", "receive",
+    "rem", "try", "when", "xor",
+];
+
+fn fmt_atom(f: &mut fmt::Formatter, atom: &str) -> fmt::Result {
+    let mut chars = atom.chars();
+    let plain = match chars.next() {
+        Some(c) if c.is_ascii_lowercase() => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+                && !RESERVED_WORDS.contains(&atom)
+        }
+        _ => false,
+    };
+
+    if plain {
+        write!(f, "{}", atom)
+    } else {
+        write!(f, "'")?;
+        for c in atom.chars() {
+            match c {
+                '\'' => write!(f, "\\'")?,
+                '\\' => write!(f, "\\\\")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "'")
+    }
+}
+
+/// Lists of printable characters are printed as strings, like the shell
+/// does.
+fn as_printable_string(elems: &[Rc<Term>]) -> Option<String> {
+    let mut string = String::new();
+    for elem in elems {
+        let c = match elem.as_i64() {
+            Some(c @ 32..=126) => c as u8 as char,
+            Some(9) => '\t',
+            Some(10) => '\n',
+            _ => return None,
+        };
+        string.push(c);
+    }
+    Some(string)
+}
+
+fn fmt_seq(f: &mut fmt::Formatter, elems: &[Rc<Term>]) -> fmt::Result {
+    for (idx, elem) in elems.iter().enumerate() {
+        if idx != 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", elem)?;
+    }
+    Ok(())
+}
+
+fn fmt_binary(f: &mut fmt::Formatter, term: &Term) -> fmt::Result {
+    let (buf, bit_offset, bit_length) = crate::erl_lib::binary_slice(term).unwrap();
+    let slice = BitSlice::with_offset_length(&*buf, bit_offset, bit_length);
+    let mut bits = BitVec::new();
+    bits.push(slice);
+
+    // Pad to a whole number of bytes, the padding bits are zero.
+    let tail_bits = bit_length % 8;
+    if tail_bits != 0 {
+        bits.push(BitSlice::with_offset_length(&[0u8][..], 0, 8 - tail_bits));
+    }
+    let bytes = bits.try_as_byte_aligned_slice().unwrap();
+
+    write!(f, "<<")?;
+    if tail_bits == 0 && !bytes.is_empty() {
+        let printable = bytes
+            .iter()
+            .all(|b| (32..=126).contains(b) && *b != b'"' && *b != b'\\');
+        if printable {
+            write!(f, "\"{}\"", std::str::from_utf8(bytes).unwrap())?;
+            return write!(f, ">>");
+        }
+    }
+    for (idx, byte) in bytes.iter().enumerate() {
+        if idx != 0 {
+            write!(f, ",")?;
+        }
+        if tail_bits != 0 && idx == bytes.len() - 1 {
+            write!(f, "{}:{}", byte >> (8 - tail_bits), tail_bits)?;
+        } else {
+            write!(f, "{}", byte)?;
+        }
+    }
+    write!(f, ">>")
+}
+
+/// Prints terms in Erlang syntax. Internal terms get a descriptive
+/// placeholder.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Nil => write!(f, "[]"),
+            Term::Integer(int) => write!(f, "{}", int),
+            Term::Float(flt) => write!(f, "{:?}", flt.0),
+            Term::Atom(atom) => fmt_atom(f, &atom.as_str()),
+            Term::Tuple(elems) => {
+                write!(f, "{{")?;
+                fmt_seq(f, elems)?;
+                write!(f, "}}")
+            }
+            Term::ListCell(head, tail) => {
+                let (elems, tail) =
+                    Term::as_inproper_list(&Term::ListCell(head.clone(), tail.clone()).into());
+                if let Term::Nil = &*tail {
+                    if let Some(string) = as_printable_string(&elems) {
+                        return write!(f, "{:?}", string);
+                    }
+                }
+                write!(f, "[")?;
+                fmt_seq(f, &elems)?;
+                match &*tail {
+                    Term::Nil => (),
+                    tail => write!(f, "|{}", tail)?,
+                }
+                write!(f, "]")
+            }
+            Term::Map(map) => {
+                write!(f, "#{{")?;
+                for (idx, (key, val)) in map.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{} => {}", key, val)?;
+                }
+                write!(f, "}}")
+            }
+            Term::Pid(pid) => write!(f, "<0.{}.0>", pid.0),
+            Term::Reference(reference) => write!(f, "#Ref<0.0.0.{}>", reference.0),
+            Term::Binary(_) | Term::BinarySlice { .. } => fmt_binary(f, self),
+            Term::BoundLambda { ident, block, .. } => write!(f, "#Fun<{}@{}>", ident, block),
+            Term::CapturedFunction { ident } => write!(f, "fun {}", ident),
+            Term::ValueList(elems) => {
+                write!(f, "<")?;
+                fmt_seq(f, elems)?;
+                write!(f, ">")
+            }
+            Term::ReturnOk => write!(f, "#ReturnOk"),
+            Term::ReturnThrow => write!(f, "#ReturnThrow"),
+            Term::NativeContinuation { ident, .. } => write!(f, "#Cont<{}>", ident),
+            Term::RawTrace(frames) => write!(f, "#RawTrace<{}>", frames.len()),
+        }
+    }
+}
+
 pub trait ErlEq<Rhs = Self> {
     fn erl_eq(&self, other: &Rhs) -> bool;
 }
@@ -757,70 +979,28 @@ impl ErlEq for f64 {
 impl ErlEq for Term {
     fn erl_eq(&self, other: &Term) -> bool {
         match (self, other) {
-            (Term::BoundLambda { .. }, _) => unreachable!(),
-            (_, Term::BoundLambda { .. }) => unreachable!(),
             (Term::ValueList(_), _) => unimplemented!(),
             (_, Term::ValueList(_)) => unimplemented!(),
 
-            (Term::Nil, Term::Nil) => true,
             (Term::ListCell(h1, t1), Term::ListCell(h2, t2)) => h1.erl_eq(h2) && t1.erl_eq(t2),
-            //(Term::List(h1, t1), Term::Nil) if h1.len() == 0 =>
-            //    if let Term::Nil = **t1 { true } else { false },
-            //(Term::Nil, Term::List(h2, t2)) if h2.len() == 0 =>
-            //    if let Term::Nil = **t2 { true } else { false },
-            //(Term::List(h1, t1), Term::List(h2, t2)) => {
-            //    let head_ok = h1.iter().zip(h2.iter())
-            //        .all(|(v1, v2)| v1.erl_eq(v2));
-            //    if !head_ok { return false; }
-            //    if h1.len() == h2.len() {
-            //        t1.erl_eq(t2)
-            //    } else if h1.len() > h2.len() {
-            //        let h1_new_head: Vec<_> = h1.iter().skip(h2.len())
-            //            .cloned().collect();
-            //        let h1_new = Term::List(h1_new_head, t1.clone());
-            //        h1_new.erl_eq(t2)
-            //    } else {
-            //        let h2_new_head: Vec<_> = h2.iter().skip(h1.len())
-            //            .cloned().collect();
-            //        let h2_new = Term::List(h2_new_head, t2.clone());
-            //        t1.erl_eq(&h2_new)
-            //    }
-            //}
-            (Term::Nil, _) => false,
-            (_, Term::Nil) => false,
-
-            (Term::Integer(ref i1), Term::Integer(ref i2)) => i1 == i2,
-            (Term::Float(ref f1), Term::Float(ref f2)) => f1 == f2,
-            (Term::Integer(_), Term::Float(_)) => unimplemented!(),
-            (Term::Float(_), Term::Integer(_)) => unimplemented!(),
-            (Term::Atom(ref a1), Term::Atom(ref a2)) => a1 == a2,
-            (Term::Tuple(ref v1), Term::Tuple(ref v2)) => {
-                v1.iter().zip(v2).all(|(e1, e2)| e1.erl_eq(e2))
+
+            // Integers and floats compare by value, everything else needs
+            // to be of the same type.
+            (Term::Integer(i), Term::Float(f)) | (Term::Float(f), Term::Integer(i)) => {
+                bigint_to_double(i) == f.0
             }
-            (
-                Term::CapturedFunction { ident: ref ident1 },
-                Term::CapturedFunction { ident: ref ident2 },
-            ) => ident1 == ident2,
-            _ => {
-                //crate::trace::warning_args(
-                //    "WARNING: ErlEq might be unimplemented".to_string(),
-                //    || {
-                //        let mut event_args = std::collections::HashMap::new();
-                //        event_args.insert(
-                //            "lhs".to_string(),
-                //            ::serde_json::Value::String(format!("{:?}", self))
-                //        );
-                //        event_args.insert(
-                //            "rhs".to_string(),
-                //            ::serde_json::Value::String(format!("{:?}", other))
-                //        );
-                //        event_args
-                //    }
-                //);
-                println!("WARNING: ErlEq might be unimplemented");
-                println!("  {:?} == {:?}", self, other);
-                false
+            (Term::Tuple(v1), Term::Tuple(v2)) => {
+                v1.len() == v2.len() && v1.iter().zip(v2).all(|(e1, e2)| e1.erl_eq(e2))
+            }
+            (Term::Map(m1), Term::Map(m2)) => {
+                m1.len() == m2.len()
+                    && m1
+                        .sorted
+                        .iter()
+                        .zip(m2.sorted.iter())
+                        .all(|((k1, v1), (k2, v2))| k1 == k2 && v1.erl_eq(v2))
             }
+            _ => self == other,
         }
     }
 }
@@ -832,12 +1012,50 @@ impl ErlExactEq for Term {
 }
 
 impl ErlOrd for Term {
+    /// Standard term order, `number < atom < reference < fun < port < pid
+    /// < tuple < map < nil < list < bit string`.
     fn erl_ord(&self, other: &Term) -> ::std::cmp::Ordering {
         match (self, other) {
             (Term::Integer(val1), Term::Integer(val2)) => val1.cmp(val2),
-            //(Term::Float(val1), Term::Float(val2)) =>
-            //    val1.cmp(val2),
-            (_, _) => unimplemented!(),
+            (Term::Float(val1), Term::Float(val2)) => val1.cmp(val2),
+            (Term::Integer(val1), Term::Float(val2)) => {
+                bigint_to_double(val1).partial_cmp(&val2.0).unwrap()
+            }
+            (Term::Float(val1), Term::Integer(val2)) => {
+                val1.0.partial_cmp(&bigint_to_double(val2)).unwrap()
+            }
+            (Term::Atom(val1), Term::Atom(val2)) => (&*val1.as_str()).cmp(&*val2.as_str()),
+            // Tuples are ordered by size first
+            (Term::Tuple(val1), Term::Tuple(val2)) => val1
+                .len()
+                .cmp(&val2.len())
+                .then_with(|| erl_ord_slice(val1, val2)),
+            (Term::ListCell(h1, t1), Term::ListCell(h2, t2)) => {
+                h1.erl_ord(h2).then_with(|| t1.erl_ord(t2))
+            }
+            // Maps are ordered by size, then by keys, then by values
+            (Term::Map(val1), Term::Map(val2)) => {
+                let keys1: Vec<_> = val1.sorted.iter().map(|(k, _)| k.clone()).collect();
+                let keys2: Vec<_> = val2.sorted.iter().map(|(k, _)| k.clone()).collect();
+                let vals1: Vec<_> = val1.sorted.iter().map(|(_, v)| v.clone()).collect();
+                let vals2: Vec<_> = val2.sorted.iter().map(|(_, v)| v.clone()).collect();
+                val1.len()
+                    .cmp(&val2.len())
+                    .then_with(|| erl_ord_slice(&keys1, &keys2))
+                    .then_with(|| erl_ord_slice(&vals1, &vals2))
+            }
+            (l, r) if l.order_idx() != r.order_idx() => l.order_idx().cmp(&r.order_idx()),
+            (l, r) => l.cmp(r),
+        }
+    }
+}
+
+fn erl_ord_slice(l: &[Rc<Term>], r: &[Rc<Term>]) -> ::std::cmp::Ordering {
+    for (l, r) in l.iter().zip(r.iter()) {
+        let ord = l.erl_ord(r);
+        if ord != ::std::cmp::Ordering::Equal {
+            return ord;
         }
     }
+    l.len().cmp(&r.len())
 }
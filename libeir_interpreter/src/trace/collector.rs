@@ -0,0 +1,315 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use libeir_ir::{Block, FunctionIdent};
+
+use crate::term::{Pid, Term};
+
+/// Entry of the Chrome trace-event format.
+#[derive(Serialize)]
+#[serde(tag = "ph")]
+enum TraceEntry {
+    #[serde(rename = "B")]
+    DurationStart {
+        name: String,
+        #[serde(rename = "cat")]
+        categories: String,
+        #[serde(rename = "ts")]
+        timestamp: u64,
+        pid: u64,
+        tid: u64,
+        args: HashMap<String, serde_json::Value>,
+    },
+    #[serde(rename = "E")]
+    DurationEnd {
+        #[serde(rename = "ts")]
+        timestamp: u64,
+        pid: u64,
+        tid: u64,
+        args: HashMap<String, serde_json::Value>,
+    },
+    #[serde(rename = "i")]
+    Instant {
+        name: String,
+        #[serde(rename = "ts")]
+        timestamp: u64,
+        pid: u64,
+        tid: u64,
+        #[serde(rename = "s")]
+        scope: &'static str,
+        args: HashMap<String, serde_json::Value>,
+    },
+}
+
+enum TraceEventType {
+    Call {
+        fun: Rc<Term>,
+        args: Vec<Rc<Term>>,
+        tail: bool,
+    },
+    Return {
+        value: Rc<Term>,
+    },
+    /// An exception that unwound `unwound` calls.
+    Throw {
+        typ: Rc<Term>,
+        reason: Rc<Term>,
+        unwound: usize,
+    },
+    Exit {
+        reason: Rc<Term>,
+    },
+    Block {
+        ident: FunctionIdent,
+        block: Block,
+    },
+    Send {
+        to: Pid,
+        message: Rc<Term>,
+    },
+}
+
+struct TraceEvent {
+    pid: Pid,
+    typ: TraceEventType,
+}
+
+std::thread_local! {
+    static ENABLED: Cell<bool> = Cell::new(false);
+    static EVENTS: RefCell<Vec<TraceEvent>> = RefCell::new(Vec::new());
+}
+
+/// Switches recording on or off for this thread. Off by default.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|e| e.set(enabled));
+}
+
+fn record(pid: Pid, typ: TraceEventType) {
+    if ENABLED.with(|e| e.get()) {
+        EVENTS.with(|events| events.borrow_mut().push(TraceEvent { pid, typ }));
+    }
+}
+
+pub(crate) fn call(pid: Pid, fun: &Rc<Term>, args: &[Rc<Term>], tail: bool) {
+    record(
+        pid,
+        TraceEventType::Call {
+            fun: fun.clone(),
+            args: args.to_vec(),
+            tail,
+        },
+    );
+}
+
+pub(crate) fn ret(pid: Pid, value: &Rc<Term>) {
+    record(
+        pid,
+        TraceEventType::Return {
+            value: value.clone(),
+        },
+    );
+}
+
+pub(crate) fn throw(pid: Pid, typ: &Rc<Term>, reason: &Rc<Term>, unwound: usize) {
+    record(
+        pid,
+        TraceEventType::Throw {
+            typ: typ.clone(),
+            reason: reason.clone(),
+            unwound,
+        },
+    );
+}
+
+pub(crate) fn exit(pid: Pid, reason: &Rc<Term>) {
+    record(
+        pid,
+        TraceEventType::Exit {
+            reason: reason.clone(),
+        },
+    );
+}
+
+pub(crate) fn block(pid: Pid, ident: &FunctionIdent, block: Block) {
+    record(
+        pid,
+        TraceEventType::Block {
+            ident: *ident,
+            block,
+        },
+    );
+}
+
+pub(crate) fn send(from: Pid, to: Pid, message: &Rc<Term>) {
+    record(
+        from,
+        TraceEventType::Send {
+            to,
+            message: message.clone(),
+        },
+    );
+}
+
+/// Throws away everything recorded so far on this thread.
+pub fn clear() {
+    EVENTS.with(|events| events.borrow_mut().clear());
+}
+
+fn fun_name(fun: &Term) -> String {
+    match fun {
+        Term::CapturedFunction { ident } => ident.to_string(),
+        Term::BoundLambda { ident, block, .. } => format!("{}@{}", ident, block),
+        other => other.to_string(),
+    }
+}
+
+fn join_terms(terms: &[Rc<Term>]) -> String {
+    let strings: Vec<_> = terms.iter().map(|t| t.to_string()).collect();
+    strings.join(", ")
+}
+
+/// Walks the recorded events, keeping track of the calls that are open in
+/// every process. `f` is called with the event, its depth, the number of
+/// calls it closes before it and whether it opens a call after it.
+fn walk<F>(mut f: F)
+where
+    F: FnMut(usize, &TraceEvent, usize, usize, bool),
+{
+    let mut depths: HashMap<Pid, usize> = HashMap::new();
+    EVENTS.with(|events| {
+        for (idx, event) in events.borrow().iter().enumerate() {
+            let depth = depths.entry(event.pid).or_insert(0);
+            let (closes, opens) = match &event.typ {
+                TraceEventType::Call { tail: false, .. } => (0, true),
+                TraceEventType::Call { tail: true, .. } => ((*depth).min(1), true),
+                TraceEventType::Return { .. } => ((*depth).min(1), false),
+                TraceEventType::Throw { unwound, .. } => ((*depth).min(*unwound), false),
+                TraceEventType::Exit { .. } => (*depth, false),
+                TraceEventType::Block { .. } | TraceEventType::Send { .. } => (0, false),
+            };
+            *depth -= closes;
+            f(idx, event, *depth, closes, opens);
+            if opens {
+                *depth += 1;
+            }
+        }
+    });
+}
+
+/// Formats everything recorded on this thread as a log with one line per
+/// event, indented by call depth.
+pub fn format_log() -> String {
+    let mut out = String::new();
+    walk(|_idx, event, depth, _closes, _opens| {
+        let text = match &event.typ {
+            TraceEventType::Call { fun, args, tail } => format!(
+                "{}call {}({})",
+                if *tail { "tail " } else { "" },
+                fun_name(fun),
+                join_terms(args)
+            ),
+            TraceEventType::Return { value } => format!("return {}", value),
+            TraceEventType::Throw { typ, reason, .. } => format!("throw {}:{}", typ, reason),
+            TraceEventType::Exit { reason } => format!("exit {}", reason),
+            TraceEventType::Block { ident, block } => format!("block {} {}", ident, block),
+            TraceEventType::Send { to, message } => {
+                format!("send {} to {}", message, Term::Pid(*to))
+            }
+        };
+        out.push_str(&format!(
+            "{} {}{}\n",
+            Term::Pid(event.pid),
+            "  ".repeat(depth),
+            text
+        ));
+    });
+    out
+}
+
+pub fn dump_log<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    std::fs::write(path, format_log())
+}
+
+/// Writes everything recorded on this thread in the Chrome trace-event
+/// format. Calls are durations, the rest are instant events. There are no
+/// real timestamps, every event takes one microsecond.
+pub fn dump_chrome_trace<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let string = |s: String| serde_json::Value::String(s);
+
+    let mut entries = Vec::new();
+    walk(|idx, event, _depth, closes, _opens| {
+        let timestamp = idx as u64;
+        let pid = (event.pid).0 as u64;
+        let instant = |name: String, tid: u64, args: HashMap<String, serde_json::Value>| {
+            TraceEntry::Instant {
+                name,
+                timestamp,
+                pid,
+                tid,
+                scope: "t",
+                args,
+            }
+        };
+
+        let mut end_args = HashMap::new();
+        match &event.typ {
+            TraceEventType::Return { value } => {
+                end_args.insert("return".to_string(), string(value.to_string()));
+            }
+            TraceEventType::Throw { typ, reason, .. } => {
+                entries.push(instant(
+                    format!("throw {}:{}", typ, reason),
+                    0,
+                    HashMap::new(),
+                ));
+            }
+            TraceEventType::Exit { reason } => {
+                entries.push(instant(format!("exit {}", reason), 0, HashMap::new()));
+            }
+            _ => (),
+        }
+        for _ in 0..closes {
+            entries.push(TraceEntry::DurationEnd {
+                timestamp,
+                pid,
+                tid: 0,
+                args: end_args.clone(),
+            });
+        }
+
+        match &event.typ {
+            TraceEventType::Call { fun, args, .. } => {
+                let mut call_args = HashMap::new();
+                let args = args.iter().map(|a| string(a.to_string())).collect();
+                call_args.insert("args".to_string(), serde_json::Value::Array(args));
+                entries.push(TraceEntry::DurationStart {
+                    name: fun_name(fun),
+                    categories: "call".to_string(),
+                    timestamp,
+                    pid,
+                    tid: 0,
+                    args: call_args,
+                });
+            }
+            TraceEventType::Block { ident, block } => {
+                entries.push(instant(format!("{} {}", ident, block), 1, HashMap::new()));
+            }
+            TraceEventType::Send { to, message } => {
+                let mut send_args = HashMap::new();
+                send_args.insert("to".to_string(), string(Term::Pid(*to).to_string()));
+                send_args.insert("message".to_string(), string(message.to_string()));
+                entries.push(instant("send".to_string(), 0, send_args));
+            }
+            _ => (),
+        }
+    });
+
+    let json =
+        serde_json::to_string(&entries).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    std::fs::write(path, json)
+}
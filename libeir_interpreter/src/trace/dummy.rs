@@ -1,14 +1,30 @@
+use std::io;
+use std::path::Path;
 use std::rc::Rc;
-use std::collections::HashMap;
 
-use crate::term::{ Term, Pid };
+use libeir_ir::{Block, FunctionIdent};
 
-use libeir_ir::{ FunctionIdent, Block };
+use crate::term::{Pid, Term};
 
-pub fn set_pid(_pid: Pid) {}
-pub fn enter_function(_ident: &FunctionIdent, _lambda: Option<Block>, _args: &[Rc<Term>]) {}
-//pub fn exit_function(ident: &FunctionIdent, ret: Option<&CallReturn>) {}
-//pub fn start_basic_block(module: &Atom, ident: &FunctionIdent, block: LabelN) {
-//pub fn end_basic_block() {
-pub fn warning(_text: String) {}
-pub fn warning_args<F>(_text: String, _make_args: F) where F: FnOnce() -> HashMap<String, ::serde_json::Value> {}
+pub(crate) fn call(_pid: Pid, _fun: &Rc<Term>, _args: &[Rc<Term>], _tail: bool) {}
+pub(crate) fn ret(_pid: Pid, _value: &Rc<Term>) {}
+pub(crate) fn throw(_pid: Pid, _typ: &Rc<Term>, _reason: &Rc<Term>, _unwound: usize) {}
+pub(crate) fn exit(_pid: Pid, _reason: &Rc<Term>) {}
+pub(crate) fn block(_pid: Pid, _ident: &FunctionIdent, _block: Block) {}
+pub(crate) fn send(_from: Pid, _to: Pid, _message: &Rc<Term>) {}
+
+pub fn set_enabled(_enabled: bool) {}
+
+pub fn clear() {}
+
+pub fn format_log() -> String {
+    String::new()
+}
+
+pub fn dump_log<P: AsRef<Path>>(_path: P) -> io::Result<()> {
+    Ok(())
+}
+
+pub fn dump_chrome_trace<P: AsRef<Path>>(_path: P) -> io::Result<()> {
+    Ok(())
+}
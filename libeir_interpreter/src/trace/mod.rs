@@ -1,12 +1,20 @@
+//! Execution tracing for debugging the interpreter.
+//!
+//! With the `trace` feature enabled, calls, returns, throws, block entries,
+//! message sends and process exits can be recorded for the current thread.
+//! Recording is off until switched on with `set_enabled`. The recorded
+//! events can be dumped as a human-readable log, or as a Chrome
+//! trace-event file that can be opened in `chrome://tracing`. Without the
+//! feature nothing is recorded and the dumps are empty.
 
-//#[cfg(not(feature = "trace"))]
-//#[macro_use]
-mod dummy;
-//#[cfg(not(feature = "trace"))]
-use self::dummy as trace;
+#[cfg(feature = "trace")]
+mod collector;
+#[cfg(feature = "trace")]
+use self::collector as imp;
 
-//#[cfg(feature = "trace")]
-//#[macro_use]
-//mod trace;
+#[cfg(not(feature = "trace"))]
+mod dummy;
+#[cfg(not(feature = "trace"))]
+use self::dummy as imp;
 
-pub use self::trace::*;
+pub use self::imp::*;
@@ -1,14 +1,20 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::module::{ErlangModule, ModuleType, NativeModule};
-use crate::process::{CallExecutor, Continuation, ProcessContext, TermCall};
+use crate::process::{construct_trace, format_exception};
+use crate::process::{CallExecutor, Continuation, ProcessContext, ProcessState, TermCall};
 use crate::term::{Pid, Reference, Term};
+use crate::trace;
 
 use libeir_intern::Symbol;
 use libeir_ir::{FunctionIdent, Module};
 
+/// Number of calls a process is allowed to make before the scheduler moves
+/// on to the next one.
+pub const REDUCTIONS: usize = 4000;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum WatchType {
     Link,
@@ -35,9 +41,12 @@ pub struct VMState {
 
     pub ref_gen: RefCell<ReferenceGenerator>,
     // Hashmap of all watches a process has placed on it.
-    //pub watches: RefCell<HashMap<Pid, Vec<(Pid, WatchType)>>>,
+    pub watches: RefCell<HashMap<Pid, Vec<(Pid, WatchType)>>>,
 
-    //pub mailboxes: RefCell<HashMap<Pid, ::mailbox::Mailbox>>,
+    /// Virtual clock in milliseconds, used for receive timeouts. Time
+    /// only moves when it is advanced explicitly, or when every process
+    /// is blocked in `run_all`.
+    pub time: Cell<u64>,
 }
 
 impl VMState {
@@ -46,8 +55,8 @@ impl VMState {
             modules: HashMap::new(),
             processes: RefCell::new(Vec::new()),
             ref_gen: RefCell::new(ReferenceGenerator::new()),
-            //watches: RefCell::new(HashMap::new()),
-            //mailboxes: RefCell::new(HashMap::new()),
+            watches: RefCell::new(HashMap::new()),
+            time: Cell::new(0),
         }
     }
 
@@ -94,101 +103,365 @@ impl VMState {
         self.add_native_module(crate::erl_lib::make_lists());
         self.add_native_module(crate::erl_lib::make_math());
         self.add_native_module(crate::erl_lib::make_maps());
+        self.add_native_module(crate::erl_lib::make_binary());
     }
 
+    /// Calls `fun` with `args` in a new process, running the scheduler
+    /// until the process exits.
+    ///
+    /// If the process ends up waiting for a message that can never arrive,
+    /// because no other process can run and there are no timeouts left,
+    /// this returns an `error:deadlock` exception.
     pub fn call(
         &mut self,
         fun: &FunctionIdent,
         args: &[Term],
     ) -> Result<Rc<Term>, (Rc<Term>, Rc<Term>, Rc<Term>)> {
-        let self_pid = {
-            let processes = self.processes.borrow();
-            Pid(processes.len())
-        };
+        let fun_term = Term::CapturedFunction { ident: fun.clone() };
+        let args: Vec<Rc<Term>> = args.iter().cloned().map(|v| v.into()).collect();
+        let pid = self.spawn(fun_term.into(), &args);
 
-        let mut process = ProcessContext::new(self_pid);
+        // Other processes keep running for as long as the called one does.
+        while self.process_state(pid).is_alive() {
+            if !self.run_round() && !self.advance_to_next_timeout() {
+                return Err((
+                    Term::new_atom("error").into(),
+                    Term::new_atom("deadlock").into(),
+                    Term::Nil.into(),
+                ));
+            }
+        }
 
-        let fun_term = Term::CapturedFunction { ident: fun.clone() };
+        match self.process_state(pid) {
+            ProcessState::Returned(ret) => Ok(ret),
+            ProcessState::Threw(r1, r2, r3) => Err((r1, r2, r3)),
+            ProcessState::Runnable | ProcessState::Waiting => unreachable!(),
+        }
+    }
+
+    /// Whether the function is defined in a loaded module, either in
+    /// Erlang or natively.
+    pub fn function_exists(&self, ident: &FunctionIdent) -> bool {
+        match self.modules.get(&ident.module.name) {
+            Some(ModuleType::Erlang(erl, overlay)) => {
+                erl.functions.contains_key(ident)
+                    || overlay.as_ref().map_or(false, |n| n.has_fun(ident))
+            }
+            Some(ModuleType::Native(native)) => native.has_fun(ident),
+            None => false,
+        }
+    }
+
+    /// Creates a new process that calls `fun` with `args`. The process does
+    /// not run until it is picked up by the scheduler.
+    pub fn spawn(&self, fun: Rc<Term>, args: &[Rc<Term>]) -> Pid {
+        let mut processes = self.processes.borrow_mut();
+        let pid = Pid(processes.len());
 
         let mut n_args = Vec::new();
         n_args.push(Term::ReturnOk.into());
         n_args.push(Term::ReturnThrow.into());
-        n_args.extend(args.iter().cloned().map(|v| v.into()));
+        n_args.extend(args.iter().cloned());
 
-        let mut continuation = TermCall {
-            fun: fun_term.into(),
-            args: n_args,
-        };
+        trace::call(pid, &fun, args, false);
+        let call = TermCall { fun, args: n_args };
+        processes.push(Rc::new(RefCell::new(ProcessContext::new(pid, call))));
 
-        let mut executor = CallExecutor::new();
+        pid
+    }
+
+    /// Links two processes. When one of them exits, the other one receives
+    /// an exit signal with the same reason. Linking processes that are
+    /// already linked does nothing.
+    pub fn link(&self, a: Pid, b: Pid) {
+        if self.is_linked(a, b) {
+            return;
+        }
+        let mut watches = self.watches.borrow_mut();
+        watches.entry(a).or_default().push((b, WatchType::Link));
+        watches.entry(b).or_default().push((a, WatchType::Link));
+    }
+
+    pub fn unlink(&self, a: Pid, b: Pid) {
+        let mut watches = self.watches.borrow_mut();
+        if let Some(a_watches) = watches.get_mut(&a) {
+            a_watches.retain(|(p, w)| !(*p == b && *w == WatchType::Link));
+        }
+        if let Some(b_watches) = watches.get_mut(&b) {
+            b_watches.retain(|(p, w)| !(*p == a && *w == WatchType::Link));
+        }
+    }
+
+    pub fn is_linked(&self, a: Pid, b: Pid) -> bool {
+        self.watches
+            .borrow()
+            .get(&a)
+            .map(|w| w.contains(&(b, WatchType::Link)))
+            .unwrap_or(false)
+    }
+
+    /// Places a monitor on `target`. When it exits, `current` receives a
+    /// `{'DOWN', Ref, process, Pid, Reason}` message. If `target` is not
+    /// alive the message is sent right away, with `noproc` as the reason.
+    pub fn monitor(&self, current: &mut ProcessContext, target: Pid) -> Reference {
+        let monitor_ref = self.ref_gen.borrow_mut().next();
+
+        let alive = self
+            .with_process(current, target, |p| p.state.is_alive())
+            .unwrap_or(false);
+        if alive {
+            self.watches
+                .borrow_mut()
+                .entry(target)
+                .or_default()
+                .push((current.pid, WatchType::Monitor(monitor_ref)));
+        } else {
+            current.deliver(down_message(
+                monitor_ref,
+                target,
+                Term::new_atom("noproc").into(),
+            ));
+        }
+
+        monitor_ref
+    }
+
+    /// Removes a monitor placed by `watcher`. Returns `false` if there was
+    /// no such monitor.
+    pub fn demonitor(&self, watcher: Pid, monitor_ref: Reference) -> bool {
+        let mut found = false;
+        for watches in self.watches.borrow_mut().values_mut() {
+            let len = watches.len();
+            watches.retain(|(p, w)| !(*p == watcher && *w == WatchType::Monitor(monitor_ref)));
+            found |= watches.len() != len;
+        }
+        found
+    }
+
+    /// Sends a message from the `from` process. Sending to a pid that does
+    /// not exist, or to a process that has exited, does nothing.
+    pub fn send(&self, from: &mut ProcessContext, to: Pid, message: Rc<Term>) {
+        trace::send(from.pid, to, &message);
+        self.with_process(from, to, |p| p.deliver(message));
+    }
+
+    /// Sends an exit signal to `to` on behalf of `current`, as done by
+    /// `exit/2`. If the signal makes the process exit, the exit is
+    /// propagated to the processes watching it.
+    pub fn exit_signal(&self, current: &mut ProcessContext, to: Pid, reason: Rc<Term>) {
+        let from = current.pid;
+        let exited = self
+            .with_process(current, to, |p| p.exit_signal(from, reason, false))
+            .and_then(|r| r);
+        if let Some(reason) = exited {
+            self.propagate_exit(current, to, reason);
+        }
+    }
+
+    /// Calls `f` with the process with the given pid, or returns `None` if
+    /// it does not exist. `current` is the process that is running, which
+    /// is already borrowed.
+    pub fn with_process<F, R>(&self, current: &mut ProcessContext, pid: Pid, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut ProcessContext) -> R,
+    {
+        if current.pid == pid {
+            return Some(f(current));
+        }
+
+        let process_rc = self.processes.borrow().get(pid.0).cloned()?;
+        let mut process = process_rc.borrow_mut();
+        Some(f(&mut process))
+    }
+
+    pub fn process_state(&self, pid: Pid) -> ProcessState {
+        self.process(pid).borrow().state.clone()
+    }
+
+    fn process(&self, pid: Pid) -> Rc<RefCell<ProcessContext>> {
+        self.processes.borrow()[pid.0].clone()
+    }
+
+    pub fn time(&self) -> u64 {
+        self.time.get()
+    }
+
+    /// Moves the virtual clock forward, waking up the processes with a
+    /// receive timeout that has expired.
+    pub fn advance_time(&self, millis: u64) {
+        let now = self.time.get() + millis;
+        self.time.set(now);
+
+        for process_rc in self.processes.borrow().iter() {
+            let mut process = process_rc.borrow_mut();
+            let expired = match (&process.state, &process.receive) {
+                (ProcessState::Waiting, Some(recv)) => recv.timed_out(now),
+                _ => false,
+            };
+            if expired {
+                process.state = ProcessState::Runnable;
+            }
+        }
+    }
+
+    /// Advances the clock to the earliest receive timeout of a waiting
+    /// process. Returns `false` if no process is waiting with a timeout.
+    fn advance_to_next_timeout(&self) -> bool {
+        let next = self
+            .processes
+            .borrow()
+            .iter()
+            .filter_map(|process_rc| {
+                let process = process_rc.borrow();
+                match process.state {
+                    ProcessState::Waiting => process.receive.as_ref()?.deadline(),
+                    _ => None,
+                }
+            })
+            .min();
+
+        if let Some(deadline) = next {
+            self.advance_time(deadline.saturating_sub(self.time.get()));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Runs the scheduler until no process is able to make progress
+    /// without the clock moving.
+    pub fn run(&self) {
+        while self.run_round() {}
+    }
+
+    /// Runs the scheduler until every process has either exited or is
+    /// waiting for a message without a timeout. Whenever all processes are
+    /// blocked, the clock skips ahead to the next timeout.
+    pub fn run_all(&self) {
         loop {
-            match executor.run(self, &mut process, continuation) {
-                Continuation::Term(call) => continuation = call,
-                Continuation::ReturnOk(ret) => return Ok(ret),
-                Continuation::ReturnThrow(r1, r2, r3) => return Err((r1, r2, r3)),
+            self.run();
+            if !self.advance_to_next_timeout() {
+                break;
+            }
+        }
+    }
+
+    /// Gives every runnable process a slice of `REDUCTIONS` calls, in order
+    /// of their pids. Processes spawned during the round are run in the
+    /// same round. Returns `false` if there was no process to run.
+    pub fn run_round(&self) -> bool {
+        let mut ran = false;
+
+        let mut idx = 0;
+        while idx < self.processes.borrow().len() {
+            let pid = Pid(idx);
+            if self.process_state(pid).is_runnable() {
+                self.run_reductions(pid, REDUCTIONS);
+                ran = true;
             }
+            idx += 1;
         }
+
+        ran
     }
 
-    //pub fn call(&mut self, module_name: &str, fun_name: &str, args: Vec<Term>)
-    //            -> CallReturn {
-    //    let fun_ident = FunctionIdent {
-    //        module: Atom::from_str(module_name),
-    //        name: Atom::from_str(fun_name),
-    //        arity: args.len(),
-    //        lambda: None,
-    //    };
-
-    //    let self_pid = {
-    //        let processes = self.processes.borrow();
-    //        Pid(processes.len())
-    //    };
-    //    ::trace::set_pid(self_pid);
-
-    //    let process = ProcessContext::new(self_pid);
-
-    //    let frame = process.make_call_stackframe(
-    //        self,
-    //        Atom::from_str(module_name),
-    //        fun_ident,
-    //        args
-    //    );
-
-    //    {
-    //        let mut processes = self.processes.borrow_mut();
-    //        {
-    //            let mut stack = process.stack.borrow_mut();
-    //            stack.push(frame);
-    //        }
-    //        processes.push(Rc::new(RefCell::new(process)));
-    //    }
-    //    {
-    //        let mut mailboxes = self.mailboxes.borrow_mut();
-    //        mailboxes.insert(self_pid, ::mailbox::Mailbox::new());
-    //    }
-
-    //    loop {
-    //        let processes_len = self.processes.borrow().len();
-    //        for process_num in 0..processes_len {
-    //            println!("=====================================");
-    //            println!("======== SWITCH TO PROCESS {} ========", process_num);
-    //            println!("=====================================");
-    //            ::trace::set_pid(Pid(process_num));
-
-    //            let process_rc = self.processes.borrow()[process_num].clone();
-    //            let mut process = process_rc.borrow_mut();
-    //            process.run_reductions(self, 4000);
-    //        }
-
-    //        let processes_borrow = self.processes.borrow();
-    //        let process_borrow = processes_borrow[self_pid.0].borrow();
-    //        if process_borrow.stack.borrow().len() == 0 {
-    //            break;
-    //        }
-    //    }
-
-    //    let processes_borrow = self.processes.borrow();
-    //    let mut process = processes_borrow[self_pid.0].borrow_mut();
-    //    process.return_val.take().unwrap()
-    //}
+    /// Runs a single process for at most `reductions` calls. Every call
+    /// made by the process counts as one reduction. Returns the number of
+    /// reductions that were used.
+    pub fn run_reductions(&self, pid: Pid, reductions: usize) -> usize {
+        let process_rc = self.process(pid);
+        let mut process = process_rc.borrow_mut();
+
+        let mut executor = CallExecutor::new();
+        let mut used = 0;
+        while used < reductions && process.state.is_runnable() {
+            let call = match process.continuation.take() {
+                Some(call) => call,
+                None => break,
+            };
+            used += 1;
+
+            match executor.run(self, &mut process, call) {
+                // The process may have been killed by an exit signal it
+                // sent to itself.
+                Continuation::Term(call) => {
+                    if process.state.is_alive() {
+                        process.continuation = Some(call);
+                    }
+                }
+                Continuation::ReturnOk(ret) => process.exit(ProcessState::Returned(ret)),
+                Continuation::ReturnThrow(typ, reason, trace) => {
+                    // Exits are the normal way for processes to stop, only
+                    // errors and throws are reported.
+                    if typ.as_atom() != Some(Symbol::intern("exit")) {
+                        eprint!(
+                            "Error in process {}:\n{}",
+                            Term::Pid(pid),
+                            format_exception(self, &typ, &reason, &trace)
+                        );
+                    }
+                    let trace = construct_trace(self, &trace);
+                    process.exit(ProcessState::Threw(typ, reason, trace))
+                }
+            }
+        }
+
+        let exit_reason = process.state.exit_reason();
+        if let Some(reason) = exit_reason {
+            self.propagate_exit(&mut process, pid, reason);
+        }
+
+        used
+    }
+
+    /// Sends the exit signal of a process that has exited to everything
+    /// watching it. Linked processes exiting as a result propagate their
+    /// own exit signal in turn.
+    fn propagate_exit(&self, current: &mut ProcessContext, pid: Pid, reason: Rc<Term>) {
+        let mut queue = vec![(pid, reason)];
+
+        while let Some((pid, reason)) = queue.pop() {
+            let watches = self
+                .watches
+                .borrow_mut()
+                .remove(&pid)
+                .unwrap_or_else(Vec::new);
+
+            // Monitors placed by the process go away with it.
+            for other_watches in self.watches.borrow_mut().values_mut() {
+                other_watches.retain(|(p, w)| !(*p == pid && *w != WatchType::Link));
+            }
+
+            for (other, watch) in watches {
+                match watch {
+                    WatchType::Link => {
+                        self.unlink(pid, other);
+                        let exited = self
+                            .with_process(current, other, |p| {
+                                p.exit_signal(pid, reason.clone(), true)
+                            })
+                            .and_then(|r| r);
+                        if let Some(other_reason) = exited {
+                            queue.push((other, other_reason));
+                        }
+                    }
+                    WatchType::Monitor(monitor_ref) => {
+                        let message = down_message(monitor_ref, pid, reason.clone());
+                        self.with_process(current, other, |p| p.deliver(message));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn down_message(monitor_ref: Reference, pid: Pid, reason: Rc<Term>) -> Rc<Term> {
+    Term::Tuple(vec![
+        Term::new_atom("DOWN").into(),
+        Term::Reference(monitor_ref).into(),
+        Term::new_atom("process").into(),
+        Term::Pid(pid).into(),
+        reason,
+    ])
+    .into()
 }
@@ -18,6 +18,8 @@ pub enum EqualityFail {
 
     PrimReadsLength { left: PrimOp, right: PrimOp },
 
+    MismatchingPrimOp { left: PrimOp, right: PrimOp },
+
     MismatchingConst { left: Const, right: Const },
 }
 
@@ -142,6 +144,13 @@ fn traverse_value<'a>(ctx: &mut EqCtx<'a>, l: Value, r: Value) -> Result<(), Equ
             Ok(())
         }
         (ValueKind::PrimOp(lp), ValueKind::PrimOp(rp)) => {
+            if ctx.lf.primop_kind(lp) != ctx.rf.primop_kind(rp) {
+                return Err(EqualityFail::MismatchingPrimOp {
+                    left: lp,
+                    right: rp,
+                });
+            }
+
             let l_reads = ctx.lf.primop_reads(lp);
             let r_reads = ctx.rf.primop_reads(rp);
             if l_reads.len() != r_reads.len() {
@@ -322,4 +331,31 @@ a'foo':a'bar'/1 {
             .graph_eq(ir1.block_entry(), &ir2, ir2.block_entry())
             .is_err());
     }
+
+    #[test]
+    fn prim_kind_inequality() {
+        let ir1 = parse_function_unwrap(
+            "
+a'foo':a'bar'/2 {
+    entry(%ret, %thr, %arg1, %arg2):
+        %cmp = %arg1 < %arg2;
+        %ret(%cmp);
+}
+",
+        );
+
+        let ir2 = parse_function_unwrap(
+            "
+a'foo':a'bar'/2 {
+    entry(%ret, %thr, %arg1, %arg2):
+        %cmp = %arg1 > %arg2;
+        %ret(%cmp);
+}
+",
+        );
+
+        assert!(ir1
+            .graph_eq(ir1.block_entry(), &ir2, ir2.block_entry())
+            .is_err());
+    }
 }
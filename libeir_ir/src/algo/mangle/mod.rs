@@ -292,7 +292,7 @@ impl Mangler {
         let copy_body =
             |mang: &mut Mangler, recv: &mut R, from_block: MangleBlock, to_block: ToBlock| {
                 let to_op = recv.map_block_op(from_block);
-                let loc = from_block.map_fun(recv, |f, b| f.block_location(b)).inner();
+                let loc = recv.map_block_location(from_block);
 
                 // Get and map reads to new values
                 mang.value_buf.clear();
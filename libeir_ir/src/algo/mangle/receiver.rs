@@ -1,7 +1,7 @@
-use crate::OpKind;
 use crate::{Function, FunctionBuilder};
+use crate::{Location, OpKind};
 
-use super::{MangleBlock, MangleValue, ToValue};
+use super::{MangleBlock, MangleTarget, MangleValue, ToT, ToValue};
 
 /// Trait used to generalize a single mangling implementation over
 /// both mangling within a single function container, and across
@@ -32,6 +32,10 @@ pub(super) trait MangleReceiver<'b> {
     /// Maps a block operation. This should return an OpKind that is
     /// usable in the destination function.
     fn map_block_op(&mut self, block: MangleBlock) -> OpKind;
+
+    /// Maps the location of a block. This should return a location that
+    /// is usable in the destination function.
+    fn map_block_location(&mut self, block: MangleBlock) -> Location;
 }
 
 /// This receiver performs a mangle within a single function container.
@@ -60,6 +64,10 @@ impl<'b, 'c> MangleReceiver<'b> for SingleMangleReceiver<'c, 'b> {
         let block = block.to().unwrap().inner();
         self.fun.fun().block_kind(block).unwrap().clone()
     }
+    fn map_block_location(&mut self, block: MangleBlock) -> Location {
+        let block = block.to().unwrap().inner();
+        self.fun.fun().block_location(block)
+    }
 }
 
 /// This receiver performs a mangle across to another function container.
@@ -78,13 +86,42 @@ impl<'b, 'c> MangleReceiver<'b> for CopyMangleReceiver<'c, 'b> {
     fn to_fun<'a>(&'a self) -> &'a Function {
         self.to.fun()
     }
-    fn map_const(&mut self, _val: MangleValue) -> ToValue {
-        unimplemented!()
+    fn map_const(&mut self, val: MangleValue) -> ToValue {
+        match val {
+            MangleTarget::From(from_val) => {
+                let cons = self.from.value_const(from_val.inner()).unwrap();
+                let new = self.to.cons_mut().copy_from(self.from.cons(), cons);
+                ToT(self.to.value(new))
+            }
+            MangleTarget::To(to_val) => to_val,
+        }
     }
-    fn map_free_value(&mut self, _val: MangleValue) -> ToValue {
-        panic!()
+    fn map_free_value(&mut self, val: MangleValue) -> ToValue {
+        match val {
+            MangleTarget::From(_) => panic!("free value in mangle across function containers"),
+            MangleTarget::To(to_val) => to_val,
+        }
+    }
+    fn map_block_op(&mut self, block: MangleBlock) -> OpKind {
+        match block {
+            MangleTarget::From(from_block) => {
+                self.from.block_kind(from_block.inner()).unwrap().clone()
+            }
+            MangleTarget::To(to_block) => {
+                self.to.fun().block_kind(to_block.inner()).unwrap().clone()
+            }
+        }
     }
-    fn map_block_op(&mut self, _block: MangleBlock) -> OpKind {
-        unimplemented!()
+    fn map_block_location(&mut self, block: MangleBlock) -> Location {
+        match block {
+            MangleTarget::From(from_block) => {
+                let loc = self.from.block_location(from_block.inner());
+                self.to
+                    .fun_mut()
+                    .locations
+                    .copy_from(&self.from.locations, loc)
+            }
+            MangleTarget::To(to_block) => self.to.fun().block_location(to_block.inner()),
+        }
     }
 }
@@ -1,7 +1,7 @@
 use crate::{NilTerm, StandardFormatConfig};
 
 use super::Mangler;
-use super::ToT;
+use super::{FromT, ToT};
 
 #[test]
 fn simple_mangle() {
@@ -228,3 +228,32 @@ a'foo':a'bar'/0 {
     //    ),
     //}
 }
+
+#[test]
+fn mangle_across() {
+    let from = crate::parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %tup = {%a, a'woo', [1, 2]};
+        b1(%tup);
+    b1(%b):
+        %ret(%b);
+}
+",
+    );
+
+    let mut to = crate::Function::new(from.span(), *from.ident());
+    let mut b = to.builder();
+
+    let mut mangler = Mangler::new();
+    mangler.start(FromT(from.block_entry()));
+    let new_entry = mangler.run_across(&from, &mut b);
+    b.block_set_entry(new_entry);
+
+    b.fun().graph_validate_global();
+    assert!(b
+        .fun()
+        .graph_eq(new_entry, &from, from.block_entry())
+        .is_ok());
+}
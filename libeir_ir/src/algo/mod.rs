@@ -3,4 +3,5 @@ pub mod func_tree;
 pub mod live;
 pub mod mangle;
 pub mod op_branches;
+pub mod types;
 pub mod validate;
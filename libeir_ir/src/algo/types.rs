@@ -0,0 +1,662 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use libeir_intern::Symbol;
+
+use crate::binary::BinaryEntrySpecifier;
+use crate::graph::BlockGraph;
+use crate::Function;
+use crate::{AtomicTerm, BasicType, ConstKind, MatchKind, OpKind, PrimOpKind};
+use crate::{Block, CallKind, Value, ValueKind};
+
+impl Function {
+    pub fn value_types(&self) -> ValueTypes {
+        calculate_value_types(self)
+    }
+}
+
+/// A pessimistic type from the type tree in `DOCS.md`.
+///
+/// Following `BasicType`, `list` contains both `nil` and list cells.
+/// Binaries and functions have no dedicated type, and are `any`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TermType {
+    /// No value can have this type. This is the type of block arguments
+    /// in blocks that are never called.
+    None,
+    Any,
+
+    Atom,
+    Boolean,
+
+    Number,
+    Integer,
+    SmallInteger,
+    BigInteger,
+    Float,
+
+    Pid,
+    Reference,
+    Map,
+
+    List,
+    ListCell,
+    Nil,
+
+    Tuple,
+    /// Tuple with a known arity.
+    NTuple(usize),
+}
+
+impl TermType {
+    /// The closest supertype. `any` has no parent.
+    pub fn parent(self) -> Option<TermType> {
+        match self {
+            TermType::None => Some(TermType::Any),
+            TermType::Any => None,
+            TermType::Boolean => Some(TermType::Atom),
+            TermType::Integer | TermType::Float => Some(TermType::Number),
+            TermType::SmallInteger | TermType::BigInteger => Some(TermType::Integer),
+            TermType::ListCell | TermType::Nil => Some(TermType::List),
+            TermType::NTuple(_) => Some(TermType::Tuple),
+            _ => Some(TermType::Any),
+        }
+    }
+
+    pub fn is_subtype_of(self, other: TermType) -> bool {
+        if self == TermType::None {
+            return true;
+        }
+        let mut current = Some(self);
+        while let Some(typ) = current {
+            if typ == other {
+                return true;
+            }
+            current = typ.parent();
+        }
+        false
+    }
+
+    /// The most specific type containing both types.
+    pub fn join(self, other: TermType) -> TermType {
+        let mut current = self;
+        loop {
+            if other.is_subtype_of(current) {
+                return current;
+            }
+            current = current.parent().unwrap();
+        }
+    }
+
+    /// The most specific type contained in both types. Since types form a
+    /// tree, this is `none` if neither is a subtype of the other.
+    pub fn meet(self, other: TermType) -> TermType {
+        if self.is_subtype_of(other) {
+            self
+        } else if other.is_subtype_of(self) {
+            other
+        } else {
+            TermType::None
+        }
+    }
+}
+
+impl From<BasicType> for TermType {
+    fn from(typ: BasicType) -> TermType {
+        match typ {
+            BasicType::List => TermType::List,
+            BasicType::ListCell => TermType::ListCell,
+            BasicType::Nil => TermType::Nil,
+            BasicType::Tuple(arity) => TermType::NTuple(arity),
+            BasicType::Map => TermType::Map,
+            BasicType::Number => TermType::Number,
+            BasicType::Float => TermType::Float,
+            BasicType::Integer => TermType::Integer,
+            BasicType::SmallInteger => TermType::SmallInteger,
+            BasicType::BigInteger => TermType::BigInteger,
+        }
+    }
+}
+
+impl Display for TermType {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            TermType::None => write!(f, "none"),
+            TermType::Any => write!(f, "any"),
+            TermType::Atom => write!(f, "atom"),
+            TermType::Boolean => write!(f, "boolean"),
+            TermType::Number => write!(f, "number"),
+            TermType::Integer => write!(f, "integer"),
+            TermType::SmallInteger => write!(f, "smallint"),
+            TermType::BigInteger => write!(f, "bigint"),
+            TermType::Float => write!(f, "float"),
+            TermType::Pid => write!(f, "pid"),
+            TermType::Reference => write!(f, "reference"),
+            TermType::Map => write!(f, "map"),
+            TermType::List => write!(f, "list"),
+            TermType::ListCell => write!(f, "list_cell"),
+            TermType::Nil => write!(f, "nil"),
+            TermType::Tuple => write!(f, "tuple"),
+            TermType::NTuple(arity) => write!(f, "{}-tuple", arity),
+        }
+    }
+}
+
+/// # Pessimistic type inference
+/// Assigns a `TermType` to every value in the reachable part of a
+/// function.
+///
+/// Block argument types are the join of the types passed along every
+/// branch to the block. Blocks that escape as values can be called with
+/// anything, their arguments are `any`.
+///
+/// Branches of a `Match` refine the type of the selector in the branch
+/// target. Refinements are inherited by blocks with a single predecessor,
+/// use `block_value_type` to get the type of a value at a specific block.
+#[derive(Debug, Clone)]
+pub struct ValueTypes {
+    types: HashMap<Value, TermType>,
+    refinements: HashMap<Block, BTreeMap<Value, TermType>>,
+}
+
+impl ValueTypes {
+    /// The type of the value anywhere in the function.
+    pub fn value_type(&self, value: Value) -> TermType {
+        self.types.get(&value).cloned().unwrap_or(TermType::Any)
+    }
+
+    /// The type of the value within the given block, taking refinements
+    /// from the branches leading to the block into account.
+    pub fn block_value_type(&self, block: Block, value: Value) -> TermType {
+        let typ = self.value_type(value);
+        match self.refinements.get(&block).and_then(|r| r.get(&value)) {
+            Some(refined) => typ.meet(*refined),
+            None => typ,
+        }
+    }
+
+    fn join_into(&mut self, value: Value, typ: TermType) -> bool {
+        let old = self.value_type(value);
+        let new = old.join(typ);
+        self.types.insert(value, new);
+        old != new
+    }
+}
+
+fn const_type(fun: &Function, value: Value) -> TermType {
+    let cons = fun.value_const(value).unwrap();
+    match fun.const_kind(cons) {
+        ConstKind::Atomic(AtomicTerm::Atom(atom)) if atom == "true" || atom == "false" => {
+            TermType::Boolean
+        }
+        ConstKind::Atomic(AtomicTerm::Atom(_)) => TermType::Atom,
+        ConstKind::Atomic(AtomicTerm::Int(_)) => TermType::SmallInteger,
+        ConstKind::Atomic(AtomicTerm::BigInt(_)) => TermType::BigInteger,
+        ConstKind::Atomic(AtomicTerm::Float(_)) => TermType::Float,
+        ConstKind::Atomic(AtomicTerm::Nil) => TermType::Nil,
+        ConstKind::Atomic(AtomicTerm::Binary(_)) => TermType::Any,
+        ConstKind::ListCell { .. } => TermType::ListCell,
+        ConstKind::Tuple { entries } => TermType::NTuple(fun.const_entries(entries).len()),
+        ConstKind::Map { .. } => TermType::Map,
+    }
+}
+
+fn primop_type(fun: &Function, value: Value) -> TermType {
+    let prim = fun.value_primop(value).unwrap();
+    match fun.primop_kind(prim) {
+        PrimOpKind::TypeTag => TermType::Atom,
+        PrimOpKind::IsType(_) => TermType::Boolean,
+        PrimOpKind::BinOp(_) => TermType::Boolean,
+        PrimOpKind::LogicOp(_) => TermType::Boolean,
+        PrimOpKind::Tuple => TermType::NTuple(fun.primop_reads(prim).len()),
+        PrimOpKind::ListCell => TermType::ListCell,
+        PrimOpKind::Map => TermType::Map,
+        PrimOpKind::ValueList => TermType::Any,
+        PrimOpKind::CaptureFunction => TermType::Any,
+    }
+}
+
+/// Return type of a function in the `erlang` module, given the types of
+/// the arguments.
+fn erlang_return_type(name: &str, args: &[TermType]) -> TermType {
+    let all = |typ: TermType| args.iter().all(|a| a.is_subtype_of(typ));
+    let any = |typ: TermType| args.iter().any(|a| a.is_subtype_of(typ));
+
+    match (name, args.len()) {
+        ("+", 2) | ("-", 2) | ("*", 2) => {
+            if all(TermType::Integer) {
+                TermType::Integer
+            } else if any(TermType::Float) {
+                TermType::Float
+            } else {
+                TermType::Number
+            }
+        }
+        ("-", 1) | ("+", 1) | ("abs", 1) if all(TermType::Integer) => TermType::Integer,
+        ("-", 1) | ("+", 1) | ("abs", 1) if all(TermType::Float) => TermType::Float,
+        ("-", 1) | ("+", 1) | ("abs", 1) => TermType::Number,
+        ("/", 2) | ("float", 1) => TermType::Float,
+        ("div", 2)
+        | ("rem", 2)
+        | ("band", 2)
+        | ("bor", 2)
+        | ("bxor", 2)
+        | ("bsl", 2)
+        | ("bsr", 2)
+        | ("bnot", 1) => TermType::Integer,
+        ("length", 1)
+        | ("tuple_size", 1)
+        | ("map_size", 1)
+        | ("byte_size", 1)
+        | ("bit_size", 1)
+        | ("size", 1)
+        | ("trunc", 1)
+        | ("round", 1) => TermType::Integer,
+
+        ("==", 2)
+        | ("/=", 2)
+        | ("=<", 2)
+        | ("<", 2)
+        | (">=", 2)
+        | (">", 2)
+        | ("=:=", 2)
+        | ("=/=", 2)
+        | ("and", 2)
+        | ("or", 2)
+        | ("xor", 2)
+        | ("not", 1) => TermType::Boolean,
+        (name, 1) if name.starts_with("is_") => TermType::Boolean,
+        ("is_function", 2) | ("is_record", 2) | ("is_record", 3) => TermType::Boolean,
+
+        ("self", 0) | ("spawn", _) | ("spawn_link", _) => TermType::Pid,
+        ("make_ref", 0) => TermType::Reference,
+        ("node", 0) | ("node", 1) | ("list_to_atom", 1) | ("binary_to_atom", 2) => TermType::Atom,
+        ("atom_to_list", 1)
+        | ("integer_to_list", 1)
+        | ("tuple_to_list", 1)
+        | ("binary_to_list", 1)
+        | ("float_to_list", 1)
+        | ("++", 2)
+        | ("--", 2) => TermType::List,
+        ("list_to_tuple", 1) | ("setelement", 3) => TermType::Tuple,
+        ("list_to_integer", 1) | ("binary_to_integer", 1) => TermType::Integer,
+        ("list_to_float", 1) | ("binary_to_float", 1) => TermType::Float,
+        _ => TermType::Any,
+    }
+}
+
+/// Types of the arguments passed to branch `n` of the operation in
+/// `block`. `None` if unknown.
+fn branch_arg_types(
+    fun: &Function,
+    types: &ValueTypes,
+    block: Block,
+    n: usize,
+) -> Option<Vec<TermType>> {
+    let reads = fun.block_reads(block);
+    let res = match fun.block_kind(block).unwrap() {
+        OpKind::Call(CallKind::ControlFlow) => reads[1..]
+            .iter()
+            .map(|v| types.block_value_type(block, *v))
+            .collect(),
+        OpKind::Call(CallKind::Function) if n == 0 => {
+            let ret = match capture_erlang(fun, reads[0]) {
+                Some(name) => {
+                    let args: Vec<_> = reads[3..]
+                        .iter()
+                        .map(|v| types.block_value_type(block, *v))
+                        .collect();
+                    erlang_return_type(&name.as_str(), &args)
+                }
+                None => TermType::Any,
+            };
+            vec![ret]
+        }
+        OpKind::Call(CallKind::Function) => vec![TermType::Atom, TermType::Any, TermType::Any],
+        OpKind::IfBool => vec![],
+        OpKind::MapPut { .. } if n == 0 => vec![TermType::Map],
+        OpKind::MapPut { .. } => vec![],
+        OpKind::UnpackValueList(num) => (0..*num)
+            .map(|idx| {
+                fun.value_list_get_n(reads[1], idx)
+                    .map(|v| types.block_value_type(block, v))
+                    .unwrap_or(TermType::Any)
+            })
+            .collect(),
+        OpKind::Match { branches } => match branches[n] {
+            MatchKind::Value | MatchKind::Type(_) | MatchKind::Wildcard => vec![],
+            MatchKind::Tuple(arity) => vec![TermType::Any; arity],
+            MatchKind::ListCell => vec![TermType::Any, TermType::Any],
+            MatchKind::MapItem => vec![TermType::Any],
+            MatchKind::Binary(spec) => {
+                let value = match spec {
+                    BinaryEntrySpecifier::Integer { .. } => TermType::Integer,
+                    BinaryEntrySpecifier::Float { .. } => TermType::Float,
+                    BinaryEntrySpecifier::Utf8
+                    | BinaryEntrySpecifier::Utf16 { .. }
+                    | BinaryEntrySpecifier::Utf32 { .. } => TermType::Integer,
+                    BinaryEntrySpecifier::Bytes { .. } | BinaryEntrySpecifier::Bits { .. } => {
+                        TermType::Any
+                    }
+                };
+                vec![value, TermType::Any]
+            }
+        },
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// If the value is a capture of a function in the `erlang` module, returns
+/// the name of the function.
+fn capture_erlang(fun: &Function, value: Value) -> Option<Symbol> {
+    let prim = fun.value_primop(value)?;
+    if *fun.primop_kind(prim) != PrimOpKind::CaptureFunction {
+        return None;
+    }
+    let reads = fun.primop_reads(prim);
+    let atom = |v: Value| match fun.const_kind(fun.value_const(v)?) {
+        ConstKind::Atomic(AtomicTerm::Atom(atom)) => Some(atom.0),
+        _ => None,
+    };
+    if atom(reads[0])? != "erlang" {
+        return None;
+    }
+    atom(reads[1])
+}
+
+/// Refinement of the selector of a match when branch `n` is taken.
+fn match_refinement(fun: &Function, types: &ValueTypes, block: Block, n: usize) -> TermType {
+    let reads = fun.block_reads(block);
+    let branches = match fun.block_kind(block) {
+        Some(OpKind::Match { branches }) => branches,
+        _ => unreachable!(),
+    };
+    match branches[n] {
+        MatchKind::Value => {
+            let value = fun.value_list_get_n(reads[2 + n], 0).unwrap();
+            types.value_type(value)
+        }
+        MatchKind::Type(typ) => typ.into(),
+        MatchKind::Tuple(arity) => TermType::NTuple(arity),
+        MatchKind::ListCell => TermType::ListCell,
+        MatchKind::MapItem => TermType::Map,
+        MatchKind::Binary(_) | MatchKind::Wildcard => TermType::Any,
+    }
+}
+
+/// Refinements of match selectors for every block. In DFS order, the
+/// single predecessor of a block is always visited before the block itself.
+fn calculate_refinements(
+    fun: &Function,
+    graph: &BlockGraph,
+    blocks: &[Block],
+    num_preds: &HashMap<Block, usize>,
+    types: &mut ValueTypes,
+) {
+    types.refinements.clear();
+
+    for block in blocks.iter().cloned() {
+        if let Some(OpKind::Match { branches }) = fun.block_kind(block) {
+            let reads = fun.block_reads(block);
+            let selector = reads[1];
+            for n in 0..branches.len() {
+                let target = fun.value_list_get_n(reads[0], n).unwrap();
+                let target_block = match fun.value_block(target) {
+                    Some(target_block) => target_block,
+                    None => continue,
+                };
+                let is_unique = (0..branches.len())
+                    .filter(|m| fun.value_list_get_n(reads[0], *m) == Some(target))
+                    .count()
+                    == 1;
+                if num_preds.get(&target_block) != Some(&1) || !is_unique {
+                    continue;
+                }
+
+                let refined = match_refinement(fun, types, block, n);
+                let mut refinements = types.refinements.get(&block).cloned().unwrap_or_default();
+                let current = refinements.get(&selector).cloned().unwrap_or(TermType::Any);
+                refinements.insert(selector, current.meet(refined));
+                types.refinements.insert(target_block, refinements);
+            }
+        }
+
+        for successor in graph.outgoing(block) {
+            if num_preds.get(&successor) != Some(&1) || types.refinements.contains_key(&successor) {
+                continue;
+            }
+            if let Some(refinements) = types.refinements.get(&block).cloned() {
+                types.refinements.insert(successor, refinements);
+            }
+        }
+    }
+}
+
+fn calculate_value_types(fun: &Function) -> ValueTypes {
+    let graph = fun.block_graph();
+    let entry = fun.block_entry();
+    let blocks: Vec<Block> = graph.dfs_iter().collect();
+
+    let mut types = ValueTypes {
+        types: HashMap::new(),
+        refinements: HashMap::new(),
+    };
+
+    // Values that do not depend on block arguments
+    let mut escaping = Vec::new();
+    let mut num_preds: HashMap<Block, usize> = HashMap::new();
+    for block in blocks.iter().cloned() {
+        for arg in fun.block_args(block) {
+            let typ = if block == entry {
+                TermType::Any
+            } else {
+                TermType::None
+            };
+            types.types.insert(*arg, typ);
+        }
+
+        let mut uses: HashMap<Value, usize> = HashMap::new();
+        fun.block_walk_nested_values::<_, ()>(block, &mut |val| {
+            match fun.value_kind(val) {
+                ValueKind::Const(_) => {
+                    types.types.insert(val, const_type(fun, val));
+                }
+                ValueKind::PrimOp(_) => {
+                    types.types.insert(val, primop_type(fun, val));
+                }
+                ValueKind::Block(_) => {
+                    *uses.entry(val).or_insert(0) += 1;
+                }
+                ValueKind::Argument(_, _) => (),
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        // Blocks that are used other than as a direct branch target can be
+        // called with anything.
+        if fun.block_kind(block).is_some() {
+            for target in fun.op_branch_iter(block) {
+                if let Some(count) = uses.get_mut(&target) {
+                    *count -= 1;
+                }
+            }
+        }
+        for (val, count) in uses.iter() {
+            if *count > 0 {
+                escaping.push(fun.value_block(*val).unwrap());
+            }
+        }
+
+        for successor in graph.outgoing(block) {
+            *num_preds.entry(successor).or_insert(0) += 1;
+        }
+    }
+
+    for block in escaping {
+        for arg in fun.block_args(block) {
+            types.types.insert(*arg, TermType::Any);
+        }
+    }
+
+    // Propagate types along branches until a fixpoint is reached. Types
+    // only ever grow, and the lattice has a finite height.
+    loop {
+        // Refinements by value depend on the types of block arguments,
+        // they are recomputed with the types of the previous iteration.
+        calculate_refinements(fun, &graph, &blocks, &num_preds, &mut types);
+
+        let mut changed = false;
+
+        for block in blocks.iter().cloned() {
+            if fun.block_kind(block).is_none() {
+                continue;
+            }
+
+            for n in 0..fun.op_branch_len(block).unwrap() {
+                let target = fun.op_branch_target(block, n);
+                let target_block = match fun.value_block(target) {
+                    Some(target_block) => target_block,
+                    None => continue,
+                };
+
+                let args = fun.block_args(target_block);
+                match branch_arg_types(fun, &types, block, n) {
+                    Some(arg_types) if arg_types.len() == args.len() => {
+                        for (arg, typ) in args.iter().zip(arg_types) {
+                            changed |= types.join_into(*arg, typ);
+                        }
+                    }
+                    _ => {
+                        for arg in args {
+                            changed |= types.join_into(*arg, TermType::Any);
+                        }
+                    }
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermType;
+
+    #[test]
+    fn lattice() {
+        assert!(TermType::SmallInteger.is_subtype_of(TermType::Number));
+        assert!(!TermType::Number.is_subtype_of(TermType::Integer));
+        assert!(TermType::None.is_subtype_of(TermType::Nil));
+
+        assert!(TermType::SmallInteger.join(TermType::BigInteger) == TermType::Integer);
+        assert!(TermType::Float.join(TermType::SmallInteger) == TermType::Number);
+        assert!(TermType::NTuple(2).join(TermType::NTuple(3)) == TermType::Tuple);
+        assert!(TermType::Nil.join(TermType::ListCell) == TermType::List);
+        assert!(TermType::Atom.join(TermType::Nil) == TermType::Any);
+        assert!(TermType::None.join(TermType::Float) == TermType::Float);
+
+        assert!(TermType::Number.meet(TermType::Float) == TermType::Float);
+        assert!(TermType::Atom.meet(TermType::Map) == TermType::None);
+    }
+
+    #[test]
+    fn infer_block_args() {
+        let (ir, map) = crate::parse_function_map_unwrap(
+            "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %add = a'erlang':a'+'/2;
+        %add(1, 2) => added except %thr;
+    added(%sum):
+        %cmp = a'erlang':a'<'/2;
+        %cmp(%sum, %a) => compared except %thr;
+    compared(%c):
+        if_bool %c yes no;
+    yes():
+        join(1.0, {%a});
+    no():
+        join(%sum, {%a, %a});
+    join(%num, %tup):
+        %ret([%num | %tup]);
+}
+",
+        );
+        let types = ir.value_types();
+
+        assert!(types.value_type(map.get_value("a")) == TermType::Any);
+        assert!(types.value_type(map.get_value("sum")) == TermType::Integer);
+        assert!(types.value_type(map.get_value("c")) == TermType::Boolean);
+        assert!(types.value_type(map.get_value("num")) == TermType::Number);
+        assert!(types.value_type(map.get_value("tup")) == TermType::Tuple);
+    }
+
+    #[test]
+    fn match_refinement() {
+        let (ir, map) = crate::parse_function_map_unwrap(
+            "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        match %a {
+            type %{} => is_map;
+            {} arity 2 => is_tuple;
+            _ => other;
+        };
+    is_map():
+        map_next();
+    map_next():
+        %ret(%a);
+    is_tuple(%x, %y):
+        %ret(%a);
+    other():
+        %ret(%a);
+}
+",
+        );
+        let types = ir.value_types();
+        let a = map.get_value("a");
+
+        assert!(types.value_type(a) == TermType::Any);
+        assert!(types.block_value_type(map.get_block("is_map"), a) == TermType::Map);
+        assert!(types.block_value_type(map.get_block("map_next"), a) == TermType::Map);
+        assert!(types.block_value_type(map.get_block("is_tuple"), a) == TermType::NTuple(2));
+        assert!(types.block_value_type(map.get_block("other"), a) == TermType::Any);
+        assert!(types.value_type(map.get_value("x")) == TermType::Any);
+    }
+
+    #[test]
+    fn match_value_refinement() {
+        let (ir, map) = crate::parse_function_map_unwrap(
+            "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %add = a'erlang':a'+'/2;
+        %add(1, 2) => added except %thr;
+    added(%sum):
+        match %a {
+            value %sum => same;
+            _ => other;
+        };
+    same():
+        next(%a);
+    other():
+        %ret(%a);
+    next(%b):
+        %ret(%b);
+}
+",
+        );
+        let types = ir.value_types();
+        let a = map.get_value("a");
+
+        assert!(types.block_value_type(map.get_block("same"), a) == TermType::Integer);
+        assert!(types.block_value_type(map.get_block("other"), a) == TermType::Any);
+        assert!(types.value_type(map.get_value("b")) == TermType::Integer);
+    }
+}
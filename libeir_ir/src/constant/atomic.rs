@@ -134,7 +134,9 @@ impl From<f64> for AtomicTerm {
 }
 impl Display for FloatTerm {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-        write!(fmt, "f{}", self.0)
+        // Debug formatting always includes a fractional part or exponent,
+        // which keeps floats distinct from integers in the text format.
+        write!(fmt, "{:?}", self.0)
     }
 }
 
@@ -168,7 +170,19 @@ impl PartialEq<str> for AtomTerm {
 }
 impl Display for AtomTerm {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-        write!(fmt, "a'{}'", self.0) // TODO escape
+        write!(fmt, "a'")?;
+        for c in self.0.as_str().chars() {
+            match c {
+                '\'' => write!(fmt, "\\'")?,
+                '\\' => write!(fmt, "\\\\")?,
+                '\n' => write!(fmt, "\\n")?,
+                '\r' => write!(fmt, "\\r")?,
+                '\t' => write!(fmt, "\\t")?,
+                c if c.is_control() => write!(fmt, "\\x{{{:x}}}", c as u32)?,
+                c => write!(fmt, "{}", c)?,
+            }
+        }
+        write!(fmt, "'")
     }
 }
 
@@ -180,6 +194,20 @@ impl BinaryTerm {
         &self.0
     }
 }
+impl Display for BinaryTerm {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "b\"")?;
+        for b in self.0.iter() {
+            match *b {
+                b'"' => write!(fmt, "\\\"")?,
+                b'\\' => write!(fmt, "\\\\")?,
+                b if b.is_ascii_graphic() || b == b' ' => write!(fmt, "{}", b as char)?,
+                b => write!(fmt, "\\x{:02x}", b)?,
+            }
+        }
+        write!(fmt, "\"")
+    }
+}
 impl From<BinaryTerm> for AtomicTerm {
     fn from(data: BinaryTerm) -> Self {
         AtomicTerm::Binary(data)
@@ -217,7 +245,7 @@ impl Display for AtomicTerm {
             AtomicTerm::Float(float) => write!(fmt, "{}", float),
             AtomicTerm::Atom(atom) => write!(fmt, "{}", atom),
             AtomicTerm::Nil => write!(fmt, "[]"),
-            AtomicTerm::Binary(_bin) => write!(fmt, "bin"),
+            AtomicTerm::Binary(bin) => write!(fmt, "{}", bin),
         }
     }
 }
@@ -10,6 +10,8 @@ use cranelift_entity::{entity_impl, EntityList, ListPool, PrimaryMap};
 mod atomic;
 pub use atomic::*;
 mod float;
+#[cfg(feature = "binary_serialization")]
+pub(crate) mod serialize;
 pub use libeir_util_number::{FromPrimitive, Integer, ToPrimitive};
 
 /// These entities has the property that if they are equal, they
@@ -206,6 +208,47 @@ impl ConstantContainer {
         TupleBuilder::new()
     }
 
+    /// Copies a constant from another container into this one.
+    pub fn copy_from(&mut self, from: &ConstantContainer, value: Const) -> Const {
+        match &from.const_values[value] {
+            ConstKind::Atomic(atomic) => self.from(ConstKind::Atomic(atomic.clone())),
+            ConstKind::ListCell { head, tail } => {
+                let head = self.copy_from(from, *head);
+                let tail = self.copy_from(from, *tail);
+                self.list_cell(head, tail)
+            }
+            ConstKind::Tuple { entries } => {
+                let mut builder = self.tuple_builder();
+                for entry in entries.as_slice(&from.const_pool) {
+                    let entry = self.copy_from(from, *entry);
+                    builder.push(entry, self);
+                }
+                builder.finish(self)
+            }
+            ConstKind::Map { keys, values } => {
+                let mut pairs = Vec::new();
+                for (key, value) in keys
+                    .as_slice(&from.const_pool)
+                    .iter()
+                    .zip(values.as_slice(&from.const_pool))
+                {
+                    pairs.push((self.copy_from(from, *key), self.copy_from(from, *value)));
+                }
+                // Keys are ordered by constant index, which differs between
+                // containers.
+                pairs.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+                let mut keys = EntityList::new();
+                let mut values = EntityList::new();
+                for (key, value) in pairs {
+                    keys.push(key, &mut self.const_pool);
+                    values.push(value, &mut self.const_pool);
+                }
+                self.from(ConstKind::Map { keys, values })
+            }
+        }
+    }
+
     pub fn eq_other(&self, l: Const, r_cont: &ConstantContainer, r: Const) -> bool {
         match (&self.const_values[l], &r_cont.const_values[r]) {
             (ConstKind::Atomic(la), ConstKind::Atomic(ra)) if la == ra => true,
@@ -223,7 +266,37 @@ impl ConstantContainer {
                 }
                 true
             }
-            l => unimplemented!("{:?}", l),
+            (
+                ConstKind::ListCell { head: h1, tail: t1 },
+                ConstKind::ListCell { head: h2, tail: t2 },
+            ) => self.eq_other(*h1, r_cont, *h2) && self.eq_other(*t1, r_cont, *t2),
+            (
+                ConstKind::Map {
+                    keys: k1,
+                    values: v1,
+                },
+                ConstKind::Map {
+                    keys: k2,
+                    values: v2,
+                },
+            ) => {
+                // Map entries are ordered by constant index, which is not
+                // comparable across containers.
+                let k1 = k1.as_slice(&self.const_pool);
+                let v1 = v1.as_slice(&self.const_pool);
+                let k2 = k2.as_slice(&r_cont.const_pool);
+                let v2 = v2.as_slice(&r_cont.const_pool);
+                if k1.len() != k2.len() {
+                    return false;
+                }
+                k1.iter().zip(v1.iter()).all(|(lk, lv)| {
+                    k2.iter()
+                        .position(|rk| self.eq_other(*lk, r_cont, *rk))
+                        .map(|idx| self.eq_other(*lv, r_cont, v2[idx]))
+                        .unwrap_or(false)
+                })
+            }
+            _ => false,
         }
     }
 }
@@ -0,0 +1,114 @@
+use cranelift_entity::EntityRef;
+use serde::{Deserialize, Serialize};
+
+use libeir_intern::Symbol;
+use libeir_util_number::BigInt;
+
+use super::{AtomTerm, AtomicTerm, BigIntTerm, BinaryTerm, FloatTerm, IntTerm};
+use super::{Const, ConstKind, ConstantContainer};
+use crate::serialize::{entity, entity_list, list_data};
+
+#[derive(Serialize, Deserialize)]
+enum AtomicData {
+    Int(i64),
+    /// Two's complement, little endian
+    BigInt(Vec<u8>),
+    Float(f64),
+    Atom(String),
+    Binary(Vec<u8>),
+    Nil,
+}
+
+#[derive(Serialize, Deserialize)]
+enum ConstData {
+    Atomic(AtomicData),
+    ListCell { head: u32, tail: u32 },
+    Tuple(Vec<u32>),
+    Map { keys: Vec<u32>, values: Vec<u32> },
+}
+
+/// Serialized form of a `ConstantContainer`.
+///
+/// Constants are stored in index order. Since a constant can only refer to
+/// constants created before it, they can be rebuilt in the same order,
+/// keeping all `Const` references valid.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ConstantContainerData {
+    constants: Vec<ConstData>,
+}
+
+impl ConstantContainerData {
+    pub fn new(c: &ConstantContainer) -> Self {
+        let constants = c
+            .const_values
+            .values()
+            .map(|kind| match kind {
+                ConstKind::Atomic(atomic) => ConstData::Atomic(match atomic {
+                    AtomicTerm::Int(IntTerm(int)) => AtomicData::Int(*int),
+                    AtomicTerm::BigInt(BigIntTerm(int)) => {
+                        AtomicData::BigInt(int.to_signed_bytes_le())
+                    }
+                    AtomicTerm::Float(FloatTerm(flt)) => AtomicData::Float(*flt),
+                    AtomicTerm::Atom(AtomTerm(sym)) => AtomicData::Atom(sym.as_str().to_string()),
+                    AtomicTerm::Binary(BinaryTerm(bin)) => AtomicData::Binary(bin.clone()),
+                    AtomicTerm::Nil => AtomicData::Nil,
+                }),
+                ConstKind::ListCell { head, tail } => ConstData::ListCell {
+                    head: head.index() as u32,
+                    tail: tail.index() as u32,
+                },
+                ConstKind::Tuple { entries } => ConstData::Tuple(list_data(entries, &c.const_pool)),
+                ConstKind::Map { keys, values } => ConstData::Map {
+                    keys: list_data(keys, &c.const_pool),
+                    values: list_data(values, &c.const_pool),
+                },
+            })
+            .collect();
+
+        ConstantContainerData { constants }
+    }
+
+    /// The number of constants in the container.
+    pub fn count(&self) -> usize {
+        self.constants.len()
+    }
+
+    /// Rebuilds the container, returning `None` if a constant refers to
+    /// one that comes after it, or if two constants are equal.
+    pub fn build(&self) -> Option<ConstantContainer> {
+        let mut c = ConstantContainer::new();
+
+        for (idx, data) in self.constants.iter().enumerate() {
+            let kind = match data {
+                ConstData::Atomic(atomic) => ConstKind::Atomic(match atomic {
+                    AtomicData::Int(int) => AtomicTerm::Int(IntTerm(*int)),
+                    AtomicData::BigInt(bytes) => {
+                        AtomicTerm::BigInt(BigIntTerm(BigInt::from_signed_bytes_le(bytes)))
+                    }
+                    AtomicData::Float(flt) => AtomicTerm::Float(FloatTerm(*flt)),
+                    AtomicData::Atom(name) => AtomicTerm::Atom(AtomTerm(Symbol::intern(name))),
+                    AtomicData::Binary(bin) => AtomicTerm::Binary(BinaryTerm(bin.clone())),
+                    AtomicData::Nil => AtomicTerm::Nil,
+                }),
+                ConstData::ListCell { head, tail } => ConstKind::ListCell {
+                    head: entity(*head, idx)?,
+                    tail: entity(*tail, idx)?,
+                },
+                ConstData::Tuple(entries) => ConstKind::Tuple {
+                    entries: entity_list(entries, idx, &mut c.const_pool)?,
+                },
+                ConstData::Map { keys, values } => ConstKind::Map {
+                    keys: entity_list(keys, idx, &mut c.const_pool)?,
+                    values: entity_list(values, idx, &mut c.const_pool)?,
+                },
+            };
+
+            let cons: Const = c.from(kind);
+            if cons.index() != idx {
+                return None;
+            }
+        }
+
+        Some(c)
+    }
+}
@@ -10,6 +10,9 @@ use meta_table::{MetaEntry, MetaTable};
 use crate::operation::{self as op, Op};
 use crate::traits::{OpBranches, OpParser, OpPrinter};
 
+#[cfg(feature = "binary_serialization")]
+use crate::traits::OpSerialize;
+
 lazy_static! {
     pub static ref NORMAL: ArcDialect = {
         let mut d = Dialect::new();
@@ -32,6 +35,12 @@ pub struct Dialect {
 
     op_printer: MetaTable<dyn OpPrinter>,
     op_parser: HashMap<Symbol, Box<dyn OpParser>>,
+
+    #[cfg(feature = "binary_serialization")]
+    op_serialize: MetaTable<dyn OpSerialize>,
+    /// Operations are looked up by name when decoding.
+    #[cfg(feature = "binary_serialization")]
+    op_deserialize: HashMap<Symbol, Box<dyn OpSerialize>>,
 }
 impl Debug for Dialect {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
@@ -46,6 +55,10 @@ impl Dialect {
             op_branches: MetaTable::new(),
             op_printer: MetaTable::new(),
             op_parser: HashMap::new(),
+            #[cfg(feature = "binary_serialization")]
+            op_serialize: MetaTable::new(),
+            #[cfg(feature = "binary_serialization")]
+            op_deserialize: HashMap::new(),
         }
     }
 
@@ -74,4 +87,33 @@ impl Dialect {
     pub fn get_op_printer<'a>(&self, obj: &'a dyn Op) -> Option<&'a dyn OpPrinter> {
         self.op_printer.get(obj.meta_entry())
     }
+
+    pub fn register_op_parser_impl<T: Op + OpParser + Clone>(&mut self, instance: &T) {
+        assert!(self.operations.contains(&TypeId::of::<T>()));
+        self.op_parser
+            .insert(Symbol::intern(instance.name()), Box::new(instance.clone()));
+    }
+
+    /// Operations are looked up by name when parsing.
+    pub fn get_op_parser(&self, name: Symbol) -> Option<&dyn OpParser> {
+        self.op_parser.get(&name).map(|b| &**b)
+    }
+
+    #[cfg(feature = "binary_serialization")]
+    pub fn register_op_serialize_impl<T: Op + OpSerialize + Clone>(&mut self, instance: &T) {
+        assert!(self.operations.contains(&TypeId::of::<T>()));
+        self.op_serialize.register(instance);
+        self.op_deserialize
+            .insert(Symbol::intern(instance.name()), Box::new(instance.clone()));
+    }
+
+    #[cfg(feature = "binary_serialization")]
+    pub fn get_op_serialize<'a>(&self, obj: &'a dyn Op) -> Option<&'a dyn OpSerialize> {
+        self.op_serialize.get(obj.meta_entry())
+    }
+
+    #[cfg(feature = "binary_serialization")]
+    pub fn get_op_deserialize(&self, name: Symbol) -> Option<&dyn OpSerialize> {
+        self.op_deserialize.get(&name).map(|b| &**b)
+    }
 }
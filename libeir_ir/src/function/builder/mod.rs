@@ -208,7 +208,7 @@ impl<'a> FunctionBuilder<'a> {
             let block_data = &mut self.fun.blocks[*successor];
             block_data
                 .predecessors
-                .remove(*successor, &mut self.fun.pool.block_set, &());
+                .remove(block, &mut self.fun.pool.block_set, &());
         }
 
         // 2. Add new successors to block
@@ -303,13 +303,19 @@ impl<'a> FunctionBuilder<'a> {
 
         let op;
 
+        // Usages are registered for every nested value in
+        // `graph_update_block`, so all of them need to be removed here.
+        self.fun
+            .block_walk_nested_values::<_, ()>(block, &mut |val| {
+                value_buf.push(val);
+                Ok(())
+            })
+            .unwrap();
+
         {
             let data = self.fun.blocks.get_mut(block).unwrap();
 
             op = data.op.take();
-            for read in data.reads.as_slice(&self.fun.pool.value) {
-                value_buf.push(*read);
-            }
             data.successors.clear(&mut self.fun.pool.block_set);
             data.reads.clear(&mut self.fun.pool.value);
         }
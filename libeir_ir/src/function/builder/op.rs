@@ -100,6 +100,34 @@ impl<'a> FunctionBuilder<'a> {
         cont
     }
 
+    pub fn op_trace_construct_next(
+        &mut self,
+        span: SourceSpan,
+        block: Block,
+        next: Value,
+        trace: Value,
+    ) {
+        let data = self.fun.blocks.get_mut(block).unwrap();
+        assert!(data.op.is_none());
+        assert!(data.reads.is_empty());
+
+        data.op = Some(OpKind::TraceConstruct);
+        data.reads.push(next, &mut self.fun.pool.value);
+        data.reads.push(trace, &mut self.fun.pool.value);
+        data.location = self.fun.locations.location(None, None, None, span);
+
+        self.graph_update_block(block);
+    }
+    pub fn op_trace_construct(&mut self, span: SourceSpan, block: Block, trace: Value) -> Block {
+        let cont = self.fun.block_insert();
+        let cont_val = self.value(cont);
+        self.fun.block_arg_insert(cont);
+
+        self.op_trace_construct_next(span, block, cont_val, trace);
+
+        cont
+    }
+
     pub fn op_intrinsic<'b, O: OpBuild>(
         &'b mut self,
         block: Block,
@@ -124,6 +152,33 @@ impl<'a> FunctionBuilder<'a> {
     pub fn op_map_put_build(&mut self, span: SourceSpan, value: Value) -> MapPutBuilder {
         MapPutBuilder::new(span, value, self)
     }
+    pub fn op_map_put_next(
+        &mut self,
+        span: SourceSpan,
+        block: Block,
+        ok: Value,
+        fail: Value,
+        value: Value,
+        entries: &[(Value, Value, MapPutUpdate)],
+    ) {
+        let data = self.fun.blocks.get_mut(block).unwrap();
+        assert!(data.op.is_none());
+        assert!(data.reads.is_empty());
+
+        data.op = Some(OpKind::MapPut {
+            action: entries.iter().map(|(_, _, action)| *action).collect(),
+        });
+        data.reads.push(ok, &mut self.fun.pool.value);
+        data.reads.push(fail, &mut self.fun.pool.value);
+        data.reads.push(value, &mut self.fun.pool.value);
+        for (key, val, _) in entries.iter() {
+            data.reads.push(*key, &mut self.fun.pool.value);
+            data.reads.push(*val, &mut self.fun.pool.value);
+        }
+        data.location = self.fun.locations.location(None, None, None, span);
+
+        self.graph_update_block(block);
+    }
 
     pub fn op_unpack_value_list_next(
         &mut self,
@@ -323,19 +378,16 @@ impl MatchBuilder {
         block
     }
 
-    pub fn push_binary(
+    pub fn push_binary_next(
         &mut self,
+        next: Value,
         specifier: BinaryEntrySpecifier,
         size: Option<Value>,
         b: &mut FunctionBuilder,
-    ) -> Block {
-        let (block, block_val) = b.block_insert_get_val();
-        b.block_arg_insert(block);
-        b.block_arg_insert(block);
-
+    ) {
         self.kinds.push(MatchKind::Binary(specifier));
 
-        self.branches.push(block_val, &mut b.fun.pool.value);
+        self.branches.push(next, &mut b.fun.pool.value);
 
         let args = if let Some(size) = size {
             b.prim_value_list(&[size])
@@ -343,6 +395,18 @@ impl MatchBuilder {
             b.prim_value_list(&[])
         };
         self.branch_args.push(args, &mut b.fun.pool.value);
+    }
+    pub fn push_binary(
+        &mut self,
+        specifier: BinaryEntrySpecifier,
+        size: Option<Value>,
+        b: &mut FunctionBuilder,
+    ) -> Block {
+        let (block, block_val) = b.block_insert_get_val();
+        b.block_arg_insert(block);
+        b.block_arg_insert(block);
+
+        self.push_binary_next(block_val, specifier, size, b);
 
         block
     }
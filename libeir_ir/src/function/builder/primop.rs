@@ -294,6 +294,25 @@ impl<'a> FunctionBuilder<'a> {
             .push_with_location(ValueKind::PrimOp(primop), Some(loc))
     }
 
+    /// Creates a primop exactly as given, without any of the folding or
+    /// canonicalization performed by the other constructors.
+    pub(crate) fn prim_raw(&mut self, span: SourceSpan, op: PrimOpKind, reads: &[Value]) -> Value {
+        let loc = self.fun.locations.location(None, None, None, span);
+        let mut entries_list = EntityList::new();
+        entries_list.extend(reads.iter().cloned(), &mut self.fun.pool.value);
+
+        let primop = self.fun.primops.push(
+            PrimOpData {
+                op,
+                reads: entries_list,
+            },
+            &self.fun.pool,
+        );
+        self.fun
+            .values
+            .push_with_location(ValueKind::PrimOp(primop), Some(loc))
+    }
+
     pub fn prim_from_kind(&mut self, span: SourceSpan, op: PrimOpKind, vals: &[Value]) -> Value {
         match op {
             PrimOpKind::ValueList => self.prim_value_list(vals),
@@ -18,20 +18,20 @@ pub struct LocationTerminal(u32);
 entity_impl!(LocationTerminal, "loc_terminal");
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct LocationTerminalData {
+pub(super) struct LocationTerminalData {
     /// Path to display for the origin file
-    file: Option<String>,
+    pub(super) file: Option<String>,
     /// Line number in origin file
-    line: Option<u32>,
+    pub(super) line: Option<u32>,
     /// Name of module and function/stack entity
-    names: Option<(String, String)>,
+    pub(super) names: Option<(String, String)>,
 
     /// Span in the file this was read from.
     /// While the `file`, `line` and `name` files are
     /// meant to be preserved when reading/writing
     /// to textual Eir, this span is meant to be the
     /// direct file it was read from.
-    span: SourceSpan,
+    pub(super) span: SourceSpan,
 }
 
 impl AuxHash<()> for LocationTerminalData {
@@ -46,8 +46,8 @@ impl AuxEq<()> for LocationTerminalData {
 }
 
 #[derive(Debug, Clone)]
-struct LocationData {
-    terminals: EntityList<LocationTerminal>,
+pub(super) struct LocationData {
+    pub(super) terminals: EntityList<LocationTerminal>,
 }
 impl AuxHash<ListPool<LocationTerminal>> for LocationData {
     fn aux_hash<H: Hasher>(&self, state: &mut H, container: &ListPool<LocationTerminal>) {
@@ -67,10 +67,10 @@ impl AuxEq<ListPool<LocationTerminal>> for LocationData {
 
 #[derive(Debug, Clone)]
 pub struct LocationContainer {
-    terminals: DedupPrimaryMap<LocationTerminal, LocationTerminalData>,
-    locations: DedupAuxPrimaryMap<Location, LocationData, ListPool<LocationTerminal>>,
+    pub(super) terminals: DedupPrimaryMap<LocationTerminal, LocationTerminalData>,
+    pub(super) locations: DedupAuxPrimaryMap<Location, LocationData, ListPool<LocationTerminal>>,
 
-    terminal_pool: ListPool<LocationTerminal>,
+    pub(super) terminal_pool: ListPool<LocationTerminal>,
 }
 
 impl LocationContainer {
@@ -98,6 +98,22 @@ impl LocationContainer {
         locs
     }
 
+    /// Gets the file and line of a location, if they are known. The last
+    /// terminal that has them wins, for inlined code that is the innermost
+    /// one.
+    pub fn file_line(&self, location: &Location) -> Option<(&str, u32)> {
+        let terminals = self.locations[*location]
+            .terminals
+            .as_slice(&self.terminal_pool);
+        terminals.iter().rev().find_map(|terminal| {
+            let terminal_data = &self.terminals[*terminal];
+            match (&terminal_data.file, terminal_data.line) {
+                (Some(file), Some(line)) => Some((file.as_str(), line)),
+                _ => None,
+            }
+        })
+    }
+
     pub fn location_empty(&mut self) -> Location {
         self.locations.push(
             LocationData {
@@ -172,6 +188,22 @@ impl LocationContainer {
         self.location(file, line, names, span)
     }
 
+    /// Copies a location from another container into this one.
+    pub fn copy_from(&mut self, from: &LocationContainer, location: Location) -> Location {
+        let mut terminals = EntityList::new();
+        for terminal in from.locations[location]
+            .terminals
+            .as_slice(&from.terminal_pool)
+        {
+            let data = from.terminals[*terminal].clone();
+            let new = self.terminals.push(data, &mut ());
+            terminals.push(new, &mut self.terminal_pool);
+        }
+
+        self.locations
+            .push(LocationData { terminals }, &mut self.terminal_pool)
+    }
+
     pub fn concat_locations(&mut self, bottom: Location, top: Location) -> Location {
         let mut terminals = Vec::new();
         terminals.extend(
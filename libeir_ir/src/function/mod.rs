@@ -37,7 +37,8 @@ pub use location::{Location, LocationContainer};
 mod format;
 pub use format::{ContainerDebug, ContainerDebugAdapter};
 
-//mod serialize;
+#[cfg(feature = "binary_serialization")]
+pub(crate) mod serialize;
 
 /// Block/continuation
 #[derive(Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -445,7 +446,7 @@ impl Function {
         match (self.block_kind(lb).unwrap(), r_fun.block_kind(rb).unwrap()) {
             (OpKind::Call(l), OpKind::Call(r)) => l == r,
             (OpKind::IfBool, OpKind::IfBool) => true,
-            (OpKind::Dyn(l), OpKind::Dyn(r)) => l.op_eq(&**r),
+            (OpKind::Dyn(l), OpKind::Dyn(r)) => l.op_eq(self, &**r, r_fun),
             (OpKind::TraceCaptureRaw, OpKind::TraceCaptureRaw) => true,
             (OpKind::TraceConstruct, OpKind::TraceConstruct) => true,
             (OpKind::MapPut { action: a1 }, OpKind::MapPut { action: a2 }) if a1 == a2 => true,
@@ -1,13 +1,401 @@
-use super::Function;
+use cranelift_bforest::Set;
+use cranelift_entity::{EntityList, EntityRef};
+use serde::{Deserialize, Serialize};
+use snafu::OptionExt;
 
-use serde::{ Serialize, Serializer, Deserialize };
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::{Ident, Symbol};
 
-impl Serialize for Function {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut value_n = 0;
-        let mut block_n = 0;
+use super::location::{LocationData, LocationTerminal, LocationTerminalData};
+use super::{Block, BlockData, Function, Location, PrimOp, PrimOpData, Value, ValueKind};
+use super::{CallKind, MapPutUpdate, MatchKind, OpKind, PrimOpKind};
+
+use crate::constant::serialize::ConstantContainerData;
+use crate::serialize::{entity, entity_list, list_data};
+use crate::serialize::{Malformed, SerializeError, UnknownOp, UnsupportedOp};
+use crate::{Const, FunctionIdent};
+
+#[derive(Serialize, Deserialize)]
+enum ValueKindData {
+    Argument(u32, u32),
+    Block(u32),
+    Const(u32),
+    PrimOp(u32),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ValueEntry {
+    kind: ValueKindData,
+    location: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrimOpEntry {
+    kind: PrimOpKind,
+    reads: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum OpEntry {
+    Call(CallKind),
+    IfBool,
+    TraceCaptureRaw,
+    TraceConstruct,
+    MapPut {
+        action: Vec<MapPutUpdate>,
+    },
+    UnpackValueList(usize),
+    Match {
+        branches: Vec<MatchKind>,
+    },
+    Unreachable,
+    /// Dialect operations are identified by name, the data is produced by
+    /// the `OpSerialize` implementation of the operation.
+    Dyn {
+        name: String,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockEntry {
+    location: u32,
+    op: Option<OpEntry>,
+    reads: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TerminalEntry {
+    file: Option<String>,
+    line: Option<u32>,
+    names: Option<(String, String)>,
+}
+
+/// Serialized form of a `Function`.
+///
+/// Values are stored in index order along with their kind. Blocks, block
+/// arguments and primops are created in the same order as values when
+/// building a function, so rebuilding them in value order restores the
+/// function with the exact same entity indices.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct FunctionData {
+    pub module: String,
+    pub name: String,
+    pub arity: usize,
+
+    entry_block: Option<u32>,
+
+    constants: ConstantContainerData,
+
+    terminals: Vec<TerminalEntry>,
+    locations: Vec<Vec<u32>>,
+
+    values: Vec<ValueEntry>,
+    primops: Vec<PrimOpEntry>,
+    blocks: Vec<BlockEntry>,
+}
+
+impl OpEntry {
+    fn new(fun: &Function, op: &OpKind) -> Result<Self, SerializeError> {
+        let entry = match op {
+            OpKind::Call(kind) => OpEntry::Call(*kind),
+            OpKind::IfBool => OpEntry::IfBool,
+            OpKind::TraceCaptureRaw => OpEntry::TraceCaptureRaw,
+            OpKind::TraceConstruct => OpEntry::TraceConstruct,
+            OpKind::MapPut { action } => OpEntry::MapPut {
+                action: action.clone(),
+            },
+            OpKind::UnpackValueList(num) => OpEntry::UnpackValueList(*num),
+            OpKind::Match { branches } => OpEntry::Match {
+                branches: branches.clone(),
+            },
+            OpKind::Unreachable => OpEntry::Unreachable,
+            OpKind::Dyn(dyn_op) => {
+                let ser = fun
+                    .dialect()
+                    .get_op_serialize(&**dyn_op)
+                    .context(UnsupportedOp {
+                        name: dyn_op.name().to_string(),
+                    })?;
+                OpEntry::Dyn {
+                    name: dyn_op.name().to_string(),
+                    data: ser.serialize(),
+                }
+            }
+        };
+        Ok(entry)
+    }
+
+    fn build(&self, fun: &Function) -> Result<OpKind, SerializeError> {
+        let op = match self {
+            OpEntry::Call(kind) => OpKind::Call(*kind),
+            OpEntry::IfBool => OpKind::IfBool,
+            OpEntry::TraceCaptureRaw => OpKind::TraceCaptureRaw,
+            OpEntry::TraceConstruct => OpKind::TraceConstruct,
+            OpEntry::MapPut { action } => OpKind::MapPut {
+                action: action.clone(),
+            },
+            OpEntry::UnpackValueList(num) => OpKind::UnpackValueList(*num),
+            OpEntry::Match { branches } => OpKind::Match {
+                branches: branches.clone(),
+            },
+            OpEntry::Unreachable => OpKind::Unreachable,
+            OpEntry::Dyn { name, data } => {
+                let de = fun
+                    .dialect()
+                    .get_op_deserialize(Symbol::intern(name))
+                    .context(UnknownOp { name: name.clone() })?;
+                OpKind::Dyn(
+                    de.deserialize(data)
+                        .context(Malformed { what: "operation" })?,
+                )
+            }
+        };
+        Ok(op)
+    }
+}
+
+impl FunctionData {
+    pub fn new(fun: &Function) -> Result<Self, SerializeError> {
+        let ident = fun.ident();
+
+        let terminals = (0..fun.locations.terminals.len())
+            .map(|n| {
+                let data = &fun.locations.terminals[LocationTerminal::new(n)];
+                TerminalEntry {
+                    file: data.file.clone(),
+                    line: data.line,
+                    names: data.names.clone(),
+                }
+            })
+            .collect();
+        let locations = (0..fun.locations.locations.len())
+            .map(|n| {
+                let data = &fun.locations.locations[Location::new(n)];
+                list_data(&data.terminals, &fun.locations.terminal_pool)
+            })
+            .collect();
+
+        let values = (0..fun.values.len())
+            .map(|n| {
+                let data = &fun.values[Value::new(n)];
+                let kind = match data.kind {
+                    ValueKind::Argument(block, num) => {
+                        ValueKindData::Argument(block.index() as u32, num as u32)
+                    }
+                    ValueKind::Block(block) => ValueKindData::Block(block.index() as u32),
+                    ValueKind::Const(cons) => ValueKindData::Const(cons.index() as u32),
+                    ValueKind::PrimOp(prim) => ValueKindData::PrimOp(prim.index() as u32),
+                };
+                ValueEntry {
+                    kind,
+                    location: data.location.map(|loc| loc.index() as u32),
+                }
+            })
+            .collect();
+
+        let primops = (0..fun.primops.len())
+            .map(|n| {
+                let data = &fun.primops[PrimOp::new(n)];
+                PrimOpEntry {
+                    kind: data.op,
+                    reads: list_data(&data.reads, &fun.pool.value),
+                }
+            })
+            .collect();
+
+        let mut blocks = Vec::with_capacity(fun.blocks.len());
+        for data in fun.blocks.values() {
+            let op = match &data.op {
+                Some(op) => Some(OpEntry::new(fun, op)?),
+                None => None,
+            };
+            blocks.push(BlockEntry {
+                location: data.location.index() as u32,
+                op,
+                reads: list_data(&data.reads, &fun.pool.value),
+            });
+        }
+
+        Ok(FunctionData {
+            module: ident.module.as_str().to_string(),
+            name: ident.name.as_str().to_string(),
+            arity: ident.arity,
+
+            entry_block: fun.entry_block.map(|block| block.index() as u32),
+
+            constants: ConstantContainerData::new(fun.cons()),
+
+            terminals,
+            locations,
+
+            values,
+            primops,
+            blocks,
+        })
+    }
+
+    pub fn build(&self) -> Result<Function, SerializeError> {
+        let ident = FunctionIdent {
+            module: Ident::from_str(&self.module),
+            name: Ident::from_str(&self.name),
+            arity: self.arity,
+        };
+        let mut fun = Function::new(SourceSpan::UNKNOWN, ident);
+
+        fun.constant_container = self
+            .constants
+            .build()
+            .context(Malformed { what: "constants" })?;
+        let num_consts = self.constants.count();
+
+        // Locations are deduplicated, and since spans are not preserved,
+        // several locations may be merged into one.
+        let mut terminals = Vec::with_capacity(self.terminals.len());
+        for entry in self.terminals.iter() {
+            let data = LocationTerminalData {
+                file: entry.file.clone(),
+                line: entry.line,
+                names: entry.names.clone(),
+                span: SourceSpan::UNKNOWN,
+            };
+            terminals.push(fun.locations.terminals.push(data, &()));
+        }
+        let mut locations: Vec<Location> = Vec::with_capacity(self.locations.len());
+        for entry in self.locations.iter() {
+            let mut list = EntityList::new();
+            for terminal in entry.iter() {
+                let terminal = terminals
+                    .get(*terminal as usize)
+                    .context(Malformed { what: "location" })?;
+                list.push(*terminal, &mut fun.locations.terminal_pool);
+            }
+            let data = LocationData { terminals: list };
+            locations.push(
+                fun.locations
+                    .locations
+                    .push(data, &fun.locations.terminal_pool),
+            );
+        }
+        let location = |n: u32| {
+            locations
+                .get(n as usize)
+                .cloned()
+                .context(Malformed { what: "location" })
+        };
+
+        for (idx, entry) in self.values.iter().enumerate() {
+            let kind = match entry.kind {
+                ValueKindData::Argument(block, num) => {
+                    let block: Block =
+                        entity(block, fun.blocks.len()).context(Malformed { what: "value" })?;
+                    let num = num as usize;
+                    if fun.blocks[block].arguments.len(&fun.pool.value) != num {
+                        return Malformed { what: "value" }.fail();
+                    }
+                    ValueKind::Argument(block, num)
+                }
+                ValueKindData::Block(block) => {
+                    let block_entry = self
+                        .blocks
+                        .get(block as usize)
+                        .context(Malformed { what: "block" })?;
+                    if block as usize != fun.blocks.len() {
+                        return Malformed { what: "block" }.fail();
+                    }
+                    let block = fun.blocks.push(BlockData {
+                        arguments: EntityList::new(),
+
+                        op: None,
+                        reads: EntityList::new(),
+
+                        predecessors: Set::new(),
+                        successors: Set::new(),
+
+                        location: location(block_entry.location)?,
+                    });
+                    ValueKind::Block(block)
+                }
+                ValueKindData::Const(cons) => {
+                    let cons: Const =
+                        entity(cons, num_consts).context(Malformed { what: "value" })?;
+                    ValueKind::Const(cons)
+                }
+                ValueKindData::PrimOp(prim) => {
+                    let prim_entry = self
+                        .primops
+                        .get(prim as usize)
+                        .context(Malformed { what: "primop" })?;
+                    let reads =
+                        entity_list(&prim_entry.reads, fun.values.len(), &mut fun.pool.value)
+                            .context(Malformed { what: "primop" })?;
+                    let data = PrimOpData {
+                        op: prim_entry.kind,
+                        reads,
+                    };
+                    let primop = fun.primops.push(data, &fun.pool);
+                    if primop.index() != prim as usize {
+                        return Malformed { what: "primop" }.fail();
+                    }
+                    ValueKind::PrimOp(primop)
+                }
+            };
+
+            let location = match entry.location {
+                Some(loc) => Some(location(loc)?),
+                None => None,
+            };
+            let value = fun.values.push_with_location(kind, location);
+            if value.index() != idx {
+                return Malformed { what: "value" }.fail();
+            }
+
+            match kind {
+                ValueKind::Argument(block, _) => {
+                    fun.blocks[block].arguments.push(value, &mut fun.pool.value);
+                }
+                ValueKind::Const(_) => {
+                    fun.constant_values.insert(value);
+                }
+                _ => (),
+            }
+        }
+
+        if fun.blocks.len() != self.blocks.len() {
+            return Malformed { what: "block" }.fail();
+        }
+        if fun.primops.len() != self.primops.len() {
+            return Malformed { what: "primop" }.fail();
+        }
+
+        for (idx, entry) in self.blocks.iter().enumerate() {
+            let op = match &entry.op {
+                Some(op) => Some(op.build(&fun)?),
+                None => None,
+            };
+            let reads = entity_list(&entry.reads, fun.values.len(), &mut fun.pool.value)
+                .context(Malformed { what: "block" })?;
+
+            let data = &mut fun.blocks[Block::new(idx)];
+            data.op = op;
+            data.reads = reads;
+        }
+
+        fun.entry_block = match self.entry_block {
+            Some(block) => {
+                Some(entity(block, fun.blocks.len()).context(Malformed { what: "block" })?)
+            }
+            None => None,
+        };
+
+        // Rebuild the graph auxiliary data from the reads
+        {
+            let mut b = fun.builder();
+            for idx in 0..self.blocks.len() {
+                b.graph_update_block(Block::new(idx));
+            }
+        }
+
+        Ok(fun)
     }
 }
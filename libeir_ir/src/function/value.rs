@@ -89,6 +89,14 @@ impl ValueMap {
     pub fn get(&self, kind: ValueKind) -> Option<Value> {
         self.back.get(&kind).cloned()
     }
+
+    pub fn len(&self) -> usize {
+        self.primary.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primary.is_empty()
+    }
 }
 
 impl Index<Value> for ValueMap {
@@ -20,6 +20,7 @@ mod algo;
 pub use algo::func_tree::{FunctionEntry, FunctionTree};
 pub use algo::live::LiveValues;
 pub use algo::mangle::{MangleFrom, MangleTarget, MangleTo, Mangler};
+pub use algo::types::{TermType, ValueTypes};
 pub use algo::validate::ValidationError;
 
 pub mod text;
@@ -48,7 +49,7 @@ pub use constant::{FromPrimitive, Integer, ToPrimitive};
 
 pub use pattern::{PatternClause, PatternContainer, PatternNode, PatternValue};
 
-pub use text::printer::{FormatConfig, StandardFormatConfig};
+pub use text::printer::{FormatConfig, StandardFormatConfig, TypeAnnotatedFormatConfig};
 pub use text::{
     parse_function, parse_function_map, parse_function_map_unwrap, parse_function_unwrap,
     parse_module, parse_module_unwrap,
@@ -58,7 +59,10 @@ pub mod binary;
 pub use binary::{BinaryEntrySpecifier, Endianness};
 
 mod module;
-pub use module::{FunctionDefinition, FunctionIndex, Module};
+pub use module::{Attribute, FunctionDefinition, FunctionIndex, Module};
+
+#[cfg(feature = "binary_serialization")]
+pub mod serialize;
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, PartialOrd)]
 pub struct FunctionIdent {
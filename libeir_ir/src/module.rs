@@ -3,12 +3,13 @@ use std::ops::{Index, IndexMut};
 
 use cranelift_entity::{entity_impl, PrimaryMap};
 
-use crate::{Function, FunctionIdent};
+use crate::{Const, ConstantContainer, Function, FunctionIdent};
 use libeir_diagnostics::SourceSpan;
 use libeir_intern::{Ident, Symbol};
 
 pub struct FunctionDefinition {
     index: FunctionIndex,
+    exported: bool,
     fun: Function,
 }
 impl FunctionDefinition {
@@ -16,6 +17,15 @@ impl FunctionDefinition {
         self.index
     }
 
+    /// Whether the function is callable from outside of the module.
+    pub fn is_exported(&self) -> bool {
+        self.exported
+    }
+
+    pub fn set_exported(&mut self, exported: bool) {
+        self.exported = exported;
+    }
+
     pub fn function(&self) -> &Function {
         &self.fun
     }
@@ -29,20 +39,30 @@ impl FunctionDefinition {
 pub struct FunctionIndex(u32);
 entity_impl!(FunctionIndex, "function_index");
 
+/// A module level attribute, like `vsn`, `behaviour` or any user
+/// defined attribute. The value is a constant in the constant
+/// container of the module.
+///
+/// The same attribute name may occur several times, attributes are
+/// kept in the order they were added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: Ident,
+    pub value: Const,
+}
+
 pub struct Module {
     name: Ident,
     span: SourceSpan,
     functions: PrimaryMap<FunctionIndex, FunctionDefinition>,
     name_map: BTreeMap<(Symbol, usize), FunctionIndex>,
+    attributes: Vec<Attribute>,
+    on_load: Option<FunctionIndex>,
+    constant_container: ConstantContainer,
 }
 impl Module {
     pub fn new(name: Ident) -> Self {
-        Self {
-            name,
-            span: SourceSpan::UNKNOWN,
-            functions: PrimaryMap::new(),
-            name_map: BTreeMap::new(),
-        }
+        Self::new_with_span(name, SourceSpan::UNKNOWN)
     }
 
     pub fn new_with_span(name: Ident, span: SourceSpan) -> Self {
@@ -51,6 +71,9 @@ impl Module {
             span,
             functions: PrimaryMap::new(),
             name_map: BTreeMap::new(),
+            attributes: Vec::new(),
+            on_load: None,
+            constant_container: ConstantContainer::new(),
         }
     }
 
@@ -78,6 +101,7 @@ impl Module {
         let fun = Function::new(span, ident);
         let def = FunctionDefinition {
             index: FunctionIndex(0),
+            exported: false,
             fun,
         };
 
@@ -106,6 +130,44 @@ impl Module {
     pub fn index_iter(&self) -> impl Iterator<Item = FunctionIndex> {
         self.functions.keys()
     }
+
+    pub fn exported_iter(&self) -> impl Iterator<Item = &FunctionDefinition> {
+        self.functions.values().filter(|def| def.exported)
+    }
+
+    /// The function that should be called when the module is loaded.
+    pub fn on_load(&self) -> Option<FunctionIndex> {
+        self.on_load
+    }
+
+    pub fn set_on_load(&mut self, on_load: Option<FunctionIndex>) {
+        self.on_load = on_load;
+    }
+
+    /// Constants used by module attributes.
+    pub fn cons(&self) -> &ConstantContainer {
+        &self.constant_container
+    }
+
+    pub fn cons_mut(&mut self) -> &mut ConstantContainer {
+        &mut self.constant_container
+    }
+
+    pub fn add_attribute(&mut self, name: Ident, value: Const) {
+        self.attributes.push(Attribute { name, value });
+    }
+
+    pub fn attribute_iter(&self) -> impl Iterator<Item = &Attribute> {
+        self.attributes.iter()
+    }
+
+    /// Returns the value of the first attribute with the given name.
+    pub fn get_attribute(&self, name: Symbol) -> Option<Const> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name.name == name)
+            .map(|attr| attr.value)
+    }
 }
 impl Clone for Module {
     fn clone(&self) -> Self {
@@ -114,11 +176,13 @@ impl Clone for Module {
         for def in self.function_iter() {
             let fun = def.function();
             let ident = fun.ident();
-            let def = FunctionDefinition {
+            let new_def = FunctionDefinition {
                 index: FunctionIndex(0),
+                exported: def.exported,
                 fun: fun.clone(),
             };
-            let index = functions.push(def);
+            let index = functions.push(new_def);
+            functions[index].index = index;
             name_map.insert((ident.name.name, ident.arity), index);
         }
         Self {
@@ -126,6 +190,10 @@ impl Clone for Module {
             span: self.span,
             functions,
             name_map,
+            attributes: self.attributes.clone(),
+            // Functions are cloned in index order, indices stay the same
+            on_load: self.on_load,
+            constant_container: self.constant_container.clone(),
         }
     }
 }
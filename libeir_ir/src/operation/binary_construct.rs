@@ -43,10 +43,15 @@ use std::any::TypeId;
 use std::default::Default;
 
 use meta_table::{impl_meta_entry, MetaEntry};
+use pretty::{DocAllocator, RefDoc};
 
-use super::{DynOp, Op, OpBuild};
+use super::{parse_generic_args, DynOp, Op, OpBuild};
 use crate::dialect::Dialect;
-use crate::traits::OpBranches;
+use crate::text::ast::{binary_specifier, DynToken, LowerContext};
+use crate::text::printer::binary_specifier_to_doc;
+#[cfg(feature = "binary_serialization")]
+use crate::traits::OpSerialize;
+use crate::traits::{FormatOpCtx, OpBranches, OpParser, OpPrinter};
 use crate::{BinaryEntrySpecifier, Block, Function, FunctionBuilder, Value};
 
 pub struct BinaryConstructToken(());
@@ -92,6 +97,17 @@ impl OpBuild for BinaryConstructStart {
     type Token = BinaryConstructToken;
 }
 
+impl OpParser for BinaryConstructStart {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let args = parse_generic_args(ctx, tokens, 1..=1)?;
+        ctx.builder()
+            .op_intrinsic(block, BinaryConstructStart, &args, BinaryConstructToken(()));
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl_op_serialize_unit!(BinaryConstructStart);
+
 /// ## `binary_construct_push`
 /// (ok: fn(bin_ref), fail: fn(), bin_ref, value)
 /// (ok: fn(bin_ref), fail: fn(), bin_ref, value, size)
@@ -114,7 +130,7 @@ impl Op for BinaryConstructPush {
     fn meta_entry(&self) -> &dyn MetaEntry {
         self
     }
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    fn op_eq(&self, _fun: &Function, other: &dyn Op, _other_fun: &Function) -> bool {
         if let Some(other_i) = other.downcast_ref::<Self>() {
             self == other_i
         } else {
@@ -186,6 +202,65 @@ impl OpBuild for BinaryConstructPush {
     type Token = BinaryConstructToken;
 }
 
+/// `@binary_construct_push <specifier> (ok, fail, bin_ref, value[, size])`
+impl OpPrinter for BinaryConstructPush {
+    fn to_doc<'doc>(&self, ctx: &mut dyn FormatOpCtx<'doc>, block: Block) -> RefDoc<'doc, ()> {
+        let arena = ctx.arena();
+        let reads = ctx.function().block_reads(block).to_vec();
+
+        let args = arena.intersperse(
+            reads.iter().map(|v| ctx.value_use_to_doc((*v).into())),
+            arena.text(",").append(arena.space()),
+        );
+
+        arena
+            .nil()
+            .append(arena.text("@binary_construct_push"))
+            .append(arena.space())
+            .append(binary_specifier_to_doc(arena, &self.specifier))
+            .append(arena.space())
+            .append(args.nest(1).parens())
+            .into_doc()
+    }
+}
+
+impl OpParser for BinaryConstructPush {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let specifier = match tokens {
+            [name, spec_args, _] => name
+                .ident()
+                .and_then(|name| binary_specifier(name, spec_args.parens()?)),
+            _ => None,
+        };
+        let specifier = match specifier {
+            Some(specifier) => specifier,
+            None => {
+                ctx.error("expected binary specifier");
+                return Err(());
+            }
+        };
+
+        let args = parse_generic_args(ctx, &tokens[2..], 4..=5)?;
+        ctx.builder().op_intrinsic(
+            block,
+            BinaryConstructPush { specifier },
+            &args,
+            BinaryConstructToken(()),
+        );
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl OpSerialize for BinaryConstructPush {
+    fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(&self.specifier).unwrap()
+    }
+    fn deserialize(&self, data: &[u8]) -> Option<DynOp> {
+        let specifier = bincode::deserialize(data).ok()?;
+        Some(DynOp::new(BinaryConstructPush { specifier }))
+    }
+}
+
 /// ## `binary_construct_finish`
 /// (cont: fn(result), ref)
 #[derive(Debug, Clone)]
@@ -246,13 +321,38 @@ impl OpBuild for BinaryConstructFinish {
     type Token = BinaryConstructToken;
 }
 
+impl OpParser for BinaryConstructFinish {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let args = parse_generic_args(ctx, tokens, 2..=2)?;
+        ctx.builder().op_intrinsic(
+            block,
+            BinaryConstructFinish,
+            &args,
+            BinaryConstructToken(()),
+        );
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl_op_serialize_unit!(BinaryConstructFinish);
+
 pub fn register(dialect: &mut Dialect) {
     dialect.register_op::<BinaryConstructStart>();
     dialect.register_op_branches_impl(&BinaryConstructStart);
+    dialect.register_op_parser_impl(&BinaryConstructStart);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&BinaryConstructStart);
 
     dialect.register_op::<BinaryConstructPush>();
     dialect.register_op_branches_impl(&BinaryConstructPush::default());
+    dialect.register_op_printer_impl(&BinaryConstructPush::default());
+    dialect.register_op_parser_impl(&BinaryConstructPush::default());
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&BinaryConstructPush::default());
 
     dialect.register_op::<BinaryConstructFinish>();
     dialect.register_op_branches_impl(&BinaryConstructFinish);
+    dialect.register_op_parser_impl(&BinaryConstructFinish);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&BinaryConstructFinish);
 }
@@ -1,14 +1,21 @@
 use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
 
-use cranelift_entity::EntityList;
 use libeir_diagnostics::SourceSpan;
+use libeir_intern::{Ident, Symbol};
 use meta_table::{impl_meta_entry, MetaEntry};
 use pretty::{DocAllocator, RefDoc};
 
 use super::{DynOp, Op, OpBuild};
-use crate::pattern::{PatternClause, PatternContainer};
-use crate::traits::{FormatOpCtx, OpBranches, OpPrinter};
-use crate::{Block, Dialect, Function, FunctionBuilder, Value};
+#[cfg(feature = "binary_serialization")]
+use crate::pattern::serialize::PatternContainerData;
+use crate::pattern::{PatternClause, PatternContainer, PatternNode, PatternNodeKind, PatternValue};
+use crate::text::ast::{binary_specifier, split_commas, DynToken, LowerContext};
+use crate::text::printer::binary_specifier_to_doc;
+#[cfg(feature = "binary_serialization")]
+use crate::traits::OpSerialize;
+use crate::traits::{FormatOpCtx, OpBranches, OpParser, OpPrinter};
+use crate::{Block, ConstKind, Dialect, DynValue, Function, FunctionBuilder, Value};
 
 pub struct CaseToken(());
 
@@ -64,9 +71,27 @@ impl Op for Case {
     fn meta_entry(&self) -> &dyn MetaEntry {
         self
     }
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    fn op_eq(&self, fun: &Function, other: &dyn Op, other_fun: &Function) -> bool {
         if let Some(other_i) = other.downcast_ref::<Self>() {
-            unimplemented!()
+            if self.inner.clauses.len() != other_i.inner.clauses.len() {
+                return false;
+            }
+            self.inner
+                .clauses
+                .iter()
+                .zip(other_i.inner.clauses.iter())
+                .all(|(l, r)| {
+                    let mut eq = PatternEq {
+                        l_fun: fun,
+                        l_pat: &self.inner.container,
+                        r_fun: other_fun,
+                        r_pat: &other_i.inner.container,
+                        nodes: HashMap::new(),
+                        values: HashMap::new(),
+                        deferred: Vec::new(),
+                    };
+                    eq.clause_eq(*l, *r)
+                })
         } else {
             false
         }
@@ -90,21 +115,807 @@ impl OpBranches for Case {
     }
 }
 
+#[cfg(feature = "binary_serialization")]
+impl OpSerialize for Case {
+    fn serialize(&self) -> Vec<u8> {
+        let data = PatternContainerData::new(&self.inner.container, &self.inner.clauses);
+        bincode::serialize(&data).unwrap()
+    }
+    fn deserialize(&self, data: &[u8]) -> Option<DynOp> {
+        let data: PatternContainerData = bincode::deserialize(data).ok()?;
+        let (container, clauses) = data.build()?;
+        Some(DynOp::new(Case {
+            inner: Box::new(Inner { container, clauses }),
+        }))
+    }
+}
+
+/// ```ignore
+/// @case %match_on {
+///   <n0 @ {v0, _}, []> values(%a) binds(n0) guard %guard => %body;
+///   _ => %no_match;
+/// };
+/// ```
+///
+/// `vN` refers to the Nth value of the clause. Nodes that are bound, used
+/// as the size of a binary entry or shared are named `nN`, and are written
+/// as `nN @ pattern` the first time they are printed.
 impl OpPrinter for Case {
     fn to_doc<'doc>(&self, ctx: &mut dyn FormatOpCtx<'doc>, block: Block) -> RefDoc<'doc, ()> {
         let arena = ctx.arena();
+        let reads = ctx.function().block_reads(block).to_vec();
+        let container = &self.inner.container;
+
+        let num_clauses = self.inner.clauses.len();
+        let mut values = reads[2 + num_clauses * 2..].iter().cloned();
+
+        let mut clauses = Vec::with_capacity(num_clauses + 1);
+        for (idx, clause) in self.inner.clauses.iter().enumerate() {
+            let mut names = ClauseNames::new(container, *clause);
 
-        let block = arena.nil();
+            let patterns: Vec<_> = container
+                .clause_root_nodes(*clause)
+                .iter()
+                .map(|node| self.node_to_doc(ctx, &mut names, *node))
+                .collect();
+            let mut doc = arena
+                .intersperse(patterns, arena.text(",").append(arena.space()))
+                .enclose("<", ">");
+
+            let num_values = container.clause_values(*clause).len();
+            if num_values > 0 {
+                let clause_values: Vec<_> = (&mut values)
+                    .take(num_values)
+                    .map(|v| ctx.value_use_to_doc(v.into()))
+                    .collect();
+                doc = doc.append(arena.space()).append(
+                    arena
+                        .intersperse(clause_values, arena.text(",").append(arena.space()))
+                        .enclose("values(", ")"),
+                );
+            }
+
+            let binds = container.clause_binds(*clause);
+            if !binds.is_empty() {
+                let binds: Vec<_> = binds
+                    .iter()
+                    .map(|node| arena.text(format!("n{}", names.nodes[node])))
+                    .collect();
+                doc = doc.append(arena.space()).append(
+                    arena
+                        .intersperse(binds, arena.text(",").append(arena.space()))
+                        .enclose("binds(", ")"),
+                );
+            }
+
+            let guard = ctx.value_use_to_doc(reads[1 + idx * 2].into());
+            let body = ctx.value_use_to_doc(reads[2 + idx * 2].into());
+            clauses.push(
+                doc.append(arena.space())
+                    .append(arena.text("guard"))
+                    .append(arena.space())
+                    .append(guard)
+                    .append(arena.space())
+                    .append(arena.text("=>"))
+                    .append(arena.space())
+                    .append(body)
+                    .append(arena.text(";")),
+            );
+        }
+
+        let no_match = ctx.value_use_to_doc(reads[0].into());
+        clauses.push(
+            arena
+                .text("_ =>")
+                .append(arena.space())
+                .append(no_match)
+                .append(arena.text(";")),
+        );
+
+        let match_on = ctx.value_use_to_doc(reads[1 + num_clauses * 2].into());
 
         arena
             .nil()
-            .append(arena.text("case"))
+            .append(arena.text("@case"))
             .append(arena.space())
-            .append(block.nest(1).braces())
+            .append(match_on)
+            .append(arena.space())
+            .append(
+                arena
+                    .hardline()
+                    .append(arena.intersperse(clauses, arena.hardline()))
+                    .nest(2)
+                    .append(arena.hardline())
+                    .braces(),
+            )
             .into_doc()
     }
 }
 
+impl Case {
+    fn node_to_doc<'doc>(
+        &self,
+        ctx: &mut dyn FormatOpCtx<'doc>,
+        names: &mut ClauseNames,
+        node: PatternNode,
+    ) -> RefDoc<'doc, ()> {
+        let arena = ctx.arena();
+        if let Some(name) = names.nodes.get(&node).cloned() {
+            if !names.printed.insert(node) {
+                return arena.text(format!("n{}", name)).into_doc();
+            }
+            let inner = self.node_kind_to_doc(ctx, names, node);
+            return arena
+                .text(format!("n{} @", name))
+                .append(arena.space())
+                .append(inner)
+                .into_doc();
+        }
+        self.node_kind_to_doc(ctx, names, node)
+    }
+
+    fn node_kind_to_doc<'doc>(
+        &self,
+        ctx: &mut dyn FormatOpCtx<'doc>,
+        names: &mut ClauseNames,
+        node: PatternNode,
+    ) -> RefDoc<'doc, ()> {
+        let arena = ctx.arena();
+        let container = &self.inner.container;
+        let sep = || arena.text(",").append(arena.space());
+
+        match container.node_kind(node) {
+            PatternNodeKind::Wildcard => arena.text("_").into_doc(),
+            PatternNodeKind::Const(cons) => {
+                let doc = ctx.value_use_to_doc(DynValue::Const(*cons));
+                match ctx.function().cons().const_kind(*cons) {
+                    ConstKind::Atomic(_) => doc,
+                    _ => arena.text("const").append(doc).into_doc(),
+                }
+            }
+            PatternNodeKind::Value(val) => arena.text(names.value(*val)).into_doc(),
+            PatternNodeKind::Binary {
+                specifier,
+                value,
+                size,
+                remaining,
+            } => {
+                let value = self.node_to_doc(ctx, names, *value);
+                let size = match size {
+                    Some(size) => arena.text(names.value(*size)),
+                    None => arena.text("_"),
+                };
+                let remaining = self.node_to_doc(ctx, names, *remaining);
+                let entries = vec![
+                    arena
+                        .nil()
+                        .append(binary_specifier_to_doc(arena, specifier)),
+                    arena.nil().append(value),
+                    size,
+                    arena.nil().append(remaining),
+                ];
+                arena
+                    .text("binary")
+                    .append(arena.intersperse(entries, sep()).parens())
+                    .into_doc()
+            }
+            PatternNodeKind::Tuple(elems) => {
+                let elems: Vec<_> = elems
+                    .as_slice(&container.node_pool)
+                    .iter()
+                    .map(|elem| self.node_to_doc(ctx, names, *elem))
+                    .collect();
+                arena.intersperse(elems, sep()).braces().into_doc()
+            }
+            PatternNodeKind::List { head, tail } => {
+                let head = self.node_to_doc(ctx, names, *head);
+                let tail = self.node_to_doc(ctx, names, *tail);
+                arena
+                    .text("[")
+                    .append(head)
+                    .append(arena.text(" | "))
+                    .append(tail)
+                    .append(arena.text("]"))
+                    .into_doc()
+            }
+            PatternNodeKind::Map { keys, values } => {
+                let entries: Vec<_> = keys
+                    .as_slice(&container.value_pool)
+                    .iter()
+                    .zip(values.as_slice(&container.node_pool).iter())
+                    .map(|(key, value)| {
+                        arena
+                            .text(names.value(*key))
+                            .append(arena.text(" => "))
+                            .append(self.node_to_doc(ctx, names, *value))
+                    })
+                    .collect();
+                arena
+                    .intersperse(entries, sep())
+                    .enclose("%{", "}")
+                    .into_doc()
+            }
+            PatternNodeKind::And { left, right } => {
+                let left = self.node_to_doc(ctx, names, *left);
+                let right = self.node_to_doc(ctx, names, *right);
+                arena
+                    .text("and")
+                    .append(arena.intersperse(vec![left, right], sep()).parens())
+                    .into_doc()
+            }
+        }
+    }
+}
+
+/// Names given to the nodes and values of a clause when printing.
+struct ClauseNames {
+    nodes: HashMap<PatternNode, usize>,
+    values: HashMap<PatternValue, String>,
+    printed: HashSet<PatternNode>,
+}
+
+impl ClauseNames {
+    fn new(container: &PatternContainer, clause: PatternClause) -> Self {
+        let mut named = Vec::new();
+        named.extend(container.clause_binds(clause).iter().cloned());
+        named.extend(
+            container
+                .clause_node_binds_iter(clause)
+                .map(|(_, node)| node),
+        );
+
+        // Nodes that occur more than once in the tree
+        let mut visited = HashSet::new();
+        let mut to_visit: Vec<_> = container.clause_root_nodes(clause).to_vec();
+        while let Some(node) = to_visit.pop() {
+            if !visited.insert(node) {
+                named.push(node);
+                continue;
+            }
+            match container.node_kind(node) {
+                PatternNodeKind::Binary {
+                    value, remaining, ..
+                } => {
+                    to_visit.push(*remaining);
+                    to_visit.push(*value);
+                }
+                PatternNodeKind::Tuple(elems) => {
+                    to_visit.extend(elems.as_slice(&container.node_pool).iter().rev());
+                }
+                PatternNodeKind::List { head, tail } => {
+                    to_visit.push(*tail);
+                    to_visit.push(*head);
+                }
+                PatternNodeKind::Map { values, .. } => {
+                    to_visit.extend(values.as_slice(&container.node_pool).iter().rev());
+                }
+                PatternNodeKind::And { left, right } => {
+                    to_visit.push(*right);
+                    to_visit.push(*left);
+                }
+                _ => (),
+            }
+        }
+
+        let mut nodes = HashMap::new();
+        for node in named {
+            let next = nodes.len();
+            nodes.entry(node).or_insert(next);
+        }
+
+        let mut values = HashMap::new();
+        for (idx, val) in container.clause_values(clause).iter().enumerate() {
+            values.insert(*val, format!("v{}", idx));
+        }
+        for (val, node) in container.clause_node_binds_iter(clause) {
+            values.insert(val, format!("n{}", nodes[&node]));
+        }
+
+        ClauseNames {
+            nodes,
+            values,
+            printed: HashSet::new(),
+        }
+    }
+
+    fn value(&self, val: PatternValue) -> String {
+        self.values[&val].clone()
+    }
+}
+
+impl OpParser for Case {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let (match_on, clauses) = match tokens {
+            [match_on, DynToken::Braces(clauses, _)] => (match_on, clauses),
+            _ => {
+                ctx.error("expected `@case <value> { <clauses> }`");
+                return Err(());
+            }
+        };
+
+        let mut case_b = Case::builder();
+        case_b.set_span(ctx.span());
+        case_b.match_on = Some(ctx.value(std::slice::from_ref(match_on))?);
+
+        let entries = clauses.split(|t| match t {
+            DynToken::Semicolon(_) => true,
+            _ => false,
+        });
+        for entry in entries {
+            match entry {
+                [] => (),
+                [DynToken::Underscore(_), DynToken::FatArrow(_), no_match] => {
+                    let no_match = ctx.value(std::slice::from_ref(no_match))?;
+                    case_b.no_match = Some(no_match);
+                }
+                _ => parse_clause(ctx, &mut case_b, entry)?,
+            }
+        }
+
+        if case_b.no_match.is_none() {
+            ctx.error("case has no `_ => <value>` branch");
+            return Err(());
+        }
+
+        case_b.finish(block, ctx.builder());
+        Ok(())
+    }
+}
+
+/// `<patterns..> [values(..)] [binds(..)] guard <value> => <value>`
+fn parse_clause(
+    ctx: &mut LowerContext,
+    case_b: &mut CaseBuilder,
+    tokens: &[DynToken],
+) -> Result<(), ()> {
+    let patterns = match tokens.first().and_then(|t| t.angle_brackets()) {
+        Some(patterns) => patterns,
+        None => {
+            ctx.error("expected clause patterns");
+            return Err(());
+        }
+    };
+
+    let guard_idx = tokens.iter().position(|t| t.is_ident("guard"));
+    let arrow_idx = tokens.iter().position(|t| match t {
+        DynToken::FatArrow(_) => true,
+        _ => false,
+    });
+    let (guard_idx, arrow_idx) = match (guard_idx, arrow_idx) {
+        (Some(guard), Some(arrow)) if guard < arrow => (guard, arrow),
+        _ => {
+            ctx.error("expected `guard <value> => <value>` in clause");
+            return Err(());
+        }
+    };
+
+    let mut values_tokens = None;
+    let mut binds_tokens = None;
+    let mut extra = &tokens[1..guard_idx];
+    while !extra.is_empty() {
+        let group = extra.get(1).and_then(|t| t.parens());
+        match (extra[0].ident(), group) {
+            (Some(name), Some(inner)) if name.name == "values" && values_tokens.is_none() => {
+                values_tokens = Some(inner)
+            }
+            (Some(name), Some(inner)) if name.name == "binds" && binds_tokens.is_none() => {
+                binds_tokens = Some(inner)
+            }
+            _ => {
+                ctx.error("expected `values(..)` or `binds(..)` in clause");
+                return Err(());
+            }
+        }
+        extra = &extra[2..];
+    }
+
+    let guard = ctx.value(&tokens[guard_idx + 1..arrow_idx])?;
+    let body = ctx.value(&tokens[arrow_idx + 1..])?;
+
+    let clause = case_b.container.clause_start(ctx.span());
+    let mut clause_p = ClauseParser {
+        clause,
+        values: HashMap::new(),
+        nodes: HashMap::new(),
+        node_values: HashMap::new(),
+        defined: HashSet::new(),
+    };
+
+    if let Some(values_tokens) = values_tokens {
+        for (idx, value) in ctx.values(values_tokens)?.iter().enumerate() {
+            let val = case_b.container.clause_value(clause);
+            clause_p
+                .values
+                .insert(Symbol::intern(&format!("v{}", idx)), val);
+            case_b.push_value(*value, ctx.builder());
+        }
+    }
+
+    for pattern in split_commas(patterns) {
+        let node = clause_p.pattern(ctx, &mut case_b.container, pattern)?;
+        case_b.container.clause_node_push(clause, node);
+    }
+
+    if let Some(binds_tokens) = binds_tokens {
+        for bind in split_commas(binds_tokens) {
+            let node = match bind {
+                [DynToken::Ident(name)] => clause_p.node(&mut case_b.container, *name),
+                _ => {
+                    ctx.error("expected node name in binds");
+                    return Err(());
+                }
+            };
+            case_b.container.clause_bind_push(clause, node);
+        }
+    }
+
+    if clause_p
+        .nodes
+        .values()
+        .any(|node| !clause_p.defined.contains(node))
+    {
+        ctx.error("pattern node is referenced but never defined");
+        return Err(());
+    }
+
+    case_b.container.clause_finish(clause);
+    case_b.push_clause(clause, guard, body, ctx.builder());
+
+    Ok(())
+}
+
+struct ClauseParser {
+    clause: PatternClause,
+    values: HashMap<Symbol, PatternValue>,
+    nodes: HashMap<Symbol, PatternNode>,
+    node_values: HashMap<PatternNode, PatternValue>,
+    defined: HashSet<PatternNode>,
+}
+
+impl ClauseParser {
+    fn node(&mut self, container: &mut PatternContainer, name: Ident) -> PatternNode {
+        *self
+            .nodes
+            .entry(name.name)
+            .or_insert_with(|| container.node_empty(Some(name.span)))
+    }
+
+    fn value(
+        &mut self,
+        ctx: &mut LowerContext,
+        container: &mut PatternContainer,
+        tokens: &[DynToken],
+    ) -> Result<PatternValue, ()> {
+        let name = match tokens {
+            [DynToken::Ident(name)] => *name,
+            _ => {
+                ctx.error("expected pattern value name");
+                return Err(());
+            }
+        };
+        if let Some(val) = self.values.get(&name.name) {
+            return Ok(*val);
+        }
+        if !name.as_str().starts_with('n') {
+            ctx.error("unknown pattern value");
+            return Err(());
+        }
+
+        let node = self.node(container, name);
+        let clause = self.clause;
+        Ok(*self
+            .node_values
+            .entry(node)
+            .or_insert_with(|| container.clause_node_value(clause, node)))
+    }
+
+    fn pattern(
+        &mut self,
+        ctx: &mut LowerContext,
+        container: &mut PatternContainer,
+        tokens: &[DynToken],
+    ) -> Result<PatternNode, ()> {
+        match tokens {
+            [DynToken::Ident(name)] if name.as_str().starts_with('n') => {
+                return Ok(self.node(container, *name));
+            }
+            _ => (),
+        }
+
+        let named = match (tokens.get(0), tokens.get(1)) {
+            (Some(DynToken::Ident(name)), Some(DynToken::At(_))) => Some(*name),
+            _ => None,
+        };
+        if let Some(name) = named {
+            let node = self.node(container, name);
+            if !self.defined.insert(node) {
+                ctx.error("pattern node defined twice");
+                return Err(());
+            }
+            self.pattern_kind(ctx, container, node, &tokens[2..])?;
+            Ok(node)
+        } else {
+            let span = tokens.first().map(|t| t.span());
+            let node = container.node_empty(span);
+            self.defined.insert(node);
+            self.pattern_kind(ctx, container, node, tokens)?;
+            Ok(node)
+        }
+    }
+
+    fn pattern_kind(
+        &mut self,
+        ctx: &mut LowerContext,
+        container: &mut PatternContainer,
+        node: PatternNode,
+        tokens: &[DynToken],
+    ) -> Result<(), ()> {
+        match tokens {
+            [DynToken::Underscore(_)] => container.wildcard(node),
+            [DynToken::Ident(name)] if name.as_str().starts_with('v') => {
+                let val = self.value(ctx, container, tokens)?;
+                container.value(node, val);
+            }
+            [DynToken::Ident(name), DynToken::Parens(inner, _)] if name.name == "const" => {
+                let cons = ctx.constant(inner)?;
+                container.constant(node, cons);
+            }
+            [DynToken::Ident(name), DynToken::Parens(inner, _)] if name.name == "binary" => {
+                let entries = split_commas(inner);
+                if entries.len() != 4 {
+                    ctx.error("expected `binary(<spec>, <value>, <size>, <remaining>)`");
+                    return Err(());
+                }
+
+                let specifier = match entries[0] {
+                    [DynToken::Ident(name), DynToken::Parens(args, _)] => {
+                        binary_specifier(*name, args)
+                    }
+                    _ => None,
+                };
+                let specifier = match specifier {
+                    Some(specifier) => specifier,
+                    None => {
+                        ctx.error("invalid binary specifier");
+                        return Err(());
+                    }
+                };
+
+                let value = self.pattern(ctx, container, entries[1])?;
+                let size = match entries[2] {
+                    [DynToken::Underscore(_)] => None,
+                    size => Some(self.value(ctx, container, size)?),
+                };
+                let remaining = self.pattern(ctx, container, entries[3])?;
+
+                container.binary(node, specifier, value, size, remaining);
+            }
+            [DynToken::Ident(name), DynToken::Parens(inner, _)] if name.name == "and" => {
+                let entries = split_commas(inner);
+                if entries.len() != 2 {
+                    ctx.error("expected `and(<left>, <right>)`");
+                    return Err(());
+                }
+
+                let left = self.pattern(ctx, container, entries[0])?;
+                let right = self.pattern(ctx, container, entries[1])?;
+
+                container.and(node, left, right);
+            }
+            [DynToken::Braces(inner, _)] => {
+                container.tuple(node);
+                for elem in split_commas(inner) {
+                    let elem = self.pattern(ctx, container, elem)?;
+                    container.tuple_elem_push(node, elem);
+                }
+                container.node_finish(node);
+            }
+            [DynToken::SquareBrackets(inner, _)] if !inner.is_empty() => {
+                let pipe = inner.iter().position(|t| match t {
+                    DynToken::Pipe(_) => true,
+                    _ => false,
+                });
+                let pipe = match pipe {
+                    Some(pipe) => pipe,
+                    None => {
+                        ctx.error("expected `[<head> | <tail>]`");
+                        return Err(());
+                    }
+                };
+                let head = self.pattern(ctx, container, &inner[..pipe])?;
+                let tail = self.pattern(ctx, container, &inner[pipe + 1..])?;
+                container.list(node, head, tail);
+            }
+            [DynToken::MapBraces(inner, _)] => {
+                container.map(node);
+                for entry in split_commas(inner) {
+                    let arrow = entry.iter().position(|t| match t {
+                        DynToken::FatArrow(_) => true,
+                        _ => false,
+                    });
+                    let arrow = match arrow {
+                        Some(arrow) => arrow,
+                        None => {
+                            ctx.error("expected `<key> => <pattern>` in map pattern");
+                            return Err(());
+                        }
+                    };
+                    let key = self.value(ctx, container, &entry[..arrow])?;
+                    let value = self.pattern(ctx, container, &entry[arrow + 1..])?;
+                    container.map_push(node, key, value);
+                }
+                container.node_finish(node);
+            }
+            _ => {
+                let cons = ctx.constant(tokens)?;
+                container.constant(node, cons);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Structural equality of two clauses, possibly in different containers.
+struct PatternEq<'a> {
+    l_fun: &'a Function,
+    l_pat: &'a PatternContainer,
+    r_fun: &'a Function,
+    r_pat: &'a PatternContainer,
+    nodes: HashMap<PatternNode, PatternNode>,
+    values: HashMap<PatternValue, PatternValue>,
+    /// Node values can only be compared once the nodes are mapped.
+    deferred: Vec<(PatternValue, PatternValue)>,
+}
+
+impl<'a> PatternEq<'a> {
+    fn clause_eq(&mut self, l: PatternClause, r: PatternClause) -> bool {
+        let (l_pat, r_pat) = (self.l_pat, self.r_pat);
+
+        let l_values = l_pat.clause_values(l);
+        let r_values = r_pat.clause_values(r);
+        if l_values.len() != r_values.len() {
+            return false;
+        }
+        for (lv, rv) in l_values.iter().zip(r_values.iter()) {
+            self.values.insert(*lv, *rv);
+        }
+
+        let l_roots = l_pat.clause_root_nodes(l);
+        let r_roots = r_pat.clause_root_nodes(r);
+        if l_roots.len() != r_roots.len() {
+            return false;
+        }
+        for (ln, rn) in l_roots.iter().zip(r_roots.iter()) {
+            if !self.node_eq(*ln, *rn) {
+                return false;
+            }
+        }
+
+        let l_binds = l_pat.clause_binds(l);
+        let r_binds = r_pat.clause_binds(r);
+        if l_binds.len() != r_binds.len() {
+            return false;
+        }
+        for (ln, rn) in l_binds.iter().zip(r_binds.iter()) {
+            if self.nodes.get(ln) != Some(rn) {
+                return false;
+            }
+        }
+
+        let l_node_values: HashMap<_, _> = l_pat.clause_node_binds_iter(l).collect();
+        let r_node_values: HashMap<_, _> = r_pat.clause_node_binds_iter(r).collect();
+        self.deferred.iter().all(
+            |(lv, rv)| match (l_node_values.get(lv), r_node_values.get(rv)) {
+                (Some(ln), Some(rn)) => self.nodes.get(ln) == Some(rn),
+                _ => false,
+            },
+        )
+    }
+
+    fn value_eq(&mut self, l: PatternValue, r: PatternValue) -> bool {
+        if let Some(mapped) = self.values.get(&l) {
+            *mapped == r
+        } else {
+            self.deferred.push((l, r));
+            true
+        }
+    }
+
+    fn node_eq(&mut self, l: PatternNode, r: PatternNode) -> bool {
+        if let Some(mapped) = self.nodes.get(&l) {
+            return *mapped == r;
+        }
+        self.nodes.insert(l, r);
+
+        let (l_pat, r_pat) = (self.l_pat, self.r_pat);
+        match (l_pat.node_kind(l), r_pat.node_kind(r)) {
+            (PatternNodeKind::Wildcard, PatternNodeKind::Wildcard) => true,
+            (PatternNodeKind::Const(lc), PatternNodeKind::Const(rc)) => {
+                self.l_fun.cons().eq_other(*lc, self.r_fun.cons(), *rc)
+            }
+            (PatternNodeKind::Value(lv), PatternNodeKind::Value(rv)) => self.value_eq(*lv, *rv),
+            (
+                PatternNodeKind::Binary {
+                    specifier: l_spec,
+                    value: l_value,
+                    size: l_size,
+                    remaining: l_rem,
+                },
+                PatternNodeKind::Binary {
+                    specifier: r_spec,
+                    value: r_value,
+                    size: r_size,
+                    remaining: r_rem,
+                },
+            ) => {
+                let size_eq = match (l_size, r_size) {
+                    (Some(ls), Some(rs)) => self.value_eq(*ls, *rs),
+                    (None, None) => true,
+                    _ => false,
+                };
+                l_spec == r_spec
+                    && size_eq
+                    && self.node_eq(*l_value, *r_value)
+                    && self.node_eq(*l_rem, *r_rem)
+            }
+            (PatternNodeKind::Tuple(l_elems), PatternNodeKind::Tuple(r_elems)) => {
+                let l_elems = l_elems.as_slice(&l_pat.node_pool);
+                let r_elems = r_elems.as_slice(&r_pat.node_pool);
+                l_elems.len() == r_elems.len()
+                    && l_elems
+                        .iter()
+                        .zip(r_elems.iter())
+                        .all(|(le, re)| self.node_eq(*le, *re))
+            }
+            (
+                PatternNodeKind::List {
+                    head: l_head,
+                    tail: l_tail,
+                },
+                PatternNodeKind::List {
+                    head: r_head,
+                    tail: r_tail,
+                },
+            ) => self.node_eq(*l_head, *r_head) && self.node_eq(*l_tail, *r_tail),
+            (
+                PatternNodeKind::Map {
+                    keys: l_keys,
+                    values: l_values,
+                },
+                PatternNodeKind::Map {
+                    keys: r_keys,
+                    values: r_values,
+                },
+            ) => {
+                let l_keys = l_keys.as_slice(&l_pat.value_pool);
+                let r_keys = r_keys.as_slice(&r_pat.value_pool);
+                let l_values = l_values.as_slice(&l_pat.node_pool);
+                let r_values = r_values.as_slice(&r_pat.node_pool);
+                l_keys.len() == r_keys.len()
+                    && l_keys
+                        .iter()
+                        .zip(r_keys.iter())
+                        .all(|(lk, rk)| self.value_eq(*lk, *rk))
+                    && l_values
+                        .iter()
+                        .zip(r_values.iter())
+                        .all(|(lv, rv)| self.node_eq(*lv, *rv))
+            }
+            (
+                PatternNodeKind::And {
+                    left: l_left,
+                    right: l_right,
+                },
+                PatternNodeKind::And {
+                    left: r_left,
+                    right: r_right,
+                },
+            ) => self.node_eq(*l_left, *r_left) && self.node_eq(*l_right, *r_right),
+            _ => false,
+        }
+    }
+}
+
 impl Case {
     pub fn builder() -> CaseBuilder {
         CaseBuilder::default()
@@ -243,11 +1054,17 @@ impl OpBuild for Case {
 }
 
 pub fn register(dialect: &mut Dialect) {
-    dialect.register_op::<Case>();
-    dialect.register_op_branches_impl(&Case {
+    let empty = Case {
         inner: Box::new(Inner {
             container: PatternContainer::new(),
             clauses: Vec::new(),
         }),
-    });
+    };
+
+    dialect.register_op::<Case>();
+    dialect.register_op_branches_impl(&empty);
+    dialect.register_op_printer_impl(&empty);
+    dialect.register_op_parser_impl(&empty);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&empty);
 }
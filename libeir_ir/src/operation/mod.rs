@@ -2,11 +2,15 @@ use std::ops::Deref;
 
 use std::any::TypeId;
 use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::ops::RangeBounds;
 use std::raw::TraitObject;
 
 use meta_table::MetaEntry;
 use stack_dst::Value;
 
+use crate::text::ast::{DynToken, LowerContext};
+use crate::Function;
+
 macro_rules! impl_op {
     ($typ:ident, $name:expr) => {
         impl Op for $typ {
@@ -26,6 +30,25 @@ macro_rules! impl_op {
     };
 }
 
+/// Implements `OpSerialize` for an operation without any inner state.
+#[cfg(feature = "binary_serialization")]
+macro_rules! impl_op_serialize_unit {
+    ($typ:ident) => {
+        impl crate::traits::OpSerialize for $typ {
+            fn serialize(&self) -> Vec<u8> {
+                Vec::new()
+            }
+            fn deserialize(&self, data: &[u8]) -> Option<DynOp> {
+                if data.is_empty() {
+                    Some(DynOp::new($typ))
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
 pub mod binary_construct;
 pub mod case;
 pub mod receive;
@@ -42,7 +65,10 @@ pub trait Op: MetaEntry + Send {
     /// Tests for semantic equality between the two operations.
     /// The default implementation assumes no inner state, and simply compares
     /// TypeIds.
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    ///
+    /// The functions containing the operations are given in order to
+    /// resolve constants referenced by the inner state.
+    fn op_eq(&self, _fun: &Function, other: &dyn Op, _other_fun: &Function) -> bool {
         self.type_id() == other.type_id()
     }
 
@@ -89,6 +115,34 @@ pub trait OpBuild: Op {
     type Token;
 }
 
+/// Reads the arguments of an operation written in the generic
+/// `@name(arg, ..)` form, as printed for operations without a custom
+/// printer.
+fn parse_generic_args<R: RangeBounds<usize>>(
+    ctx: &mut LowerContext,
+    tokens: &[DynToken],
+    num_args: R,
+) -> Result<Vec<crate::Value>, ()> {
+    let inner = match tokens {
+        [token] => token.parens(),
+        _ => None,
+    };
+    let inner = match inner {
+        Some(inner) => inner,
+        None => {
+            ctx.error("expected argument list");
+            return Err(());
+        }
+    };
+
+    let args = ctx.values(inner)?;
+    if !num_args.contains(&args.len()) {
+        ctx.error("wrong number of arguments");
+        return Err(());
+    }
+    Ok(args)
+}
+
 pub struct DynOp(Value<dyn Op>);
 
 impl DynOp {
@@ -29,15 +29,16 @@ use std::any::TypeId;
 
 use meta_table::{impl_meta_entry, MetaEntry};
 
-use super::{DynOp, Op, OpBuild};
+use super::{parse_generic_args, DynOp, Op, OpBuild};
 use crate::dialect::Dialect;
-use crate::traits::OpBranches;
+use crate::text::ast::{DynToken, LowerContext};
+use crate::traits::{OpBranches, OpParser};
 use crate::{Block, Function, FunctionBuilder, Value};
 
 pub struct ReceiveToken(());
 
 /// ## `receive_start`
-/// (cont: fn(recv_ref), timeout)
+/// (cont: fn(recv_ref), invalid_timeout: fn(), timeout)
 ///
 /// `recv_ref` is an opaque value that represents the current
 /// receive operation. It is up to the runtime implementor
@@ -47,7 +48,9 @@ pub struct ReceiveToken(());
 /// This value can only ever be passed to `receive_wait` or
 /// `receive_done`.
 ///
-/// `timeout` is either an atom, `infinity`, or a number.
+/// `timeout` is either an atom, `infinity`, or a number. If it is
+/// anything else, `invalid_timeout` is called instead of `cont`, and
+/// the receive construct is never entered.
 #[derive(Debug, Clone)]
 pub struct ReceiveStart;
 impl_meta_entry!(ReceiveStart);
@@ -60,34 +63,39 @@ impl Op for ReceiveStart {
         DynOp::new(self.clone())
     }
     fn type_id(&self) -> TypeId {
-        TypeId::of::<ReceiveStart>()
+        TypeId::of::<Self>()
     }
     fn meta_entry(&self) -> &dyn MetaEntry {
         self
     }
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    fn op_eq(&self, _fun: &Function, other: &dyn Op, _other_fun: &Function) -> bool {
         self.type_id() == other.type_id()
     }
 }
 
 impl OpBranches for ReceiveStart {
     fn branches_len(&self) -> usize {
-        1
+        2
     }
     fn branch_num(&self, fun: &Function, block: Block, branch_n: usize) -> Value {
         match branch_n {
             0 => fun.block_reads(block)[0],
+            1 => fun.block_reads(block)[1],
             _ => unreachable!(),
         }
     }
 }
 
 impl ReceiveStart {
-    pub fn build(builder: &mut FunctionBuilder, block: Block, timeout: Value) -> Block {
+    pub fn build(builder: &mut FunctionBuilder, block: Block, timeout: Value) -> (Block, Block) {
         let target = builder.block_insert();
         let _arg = builder.block_arg_insert(target);
-        Self::build_target(builder, block, timeout, target);
-        target
+
+        let invalid_timeout = builder.block_insert();
+
+        Self::build_target(builder, block, timeout, target, invalid_timeout);
+
+        (target, invalid_timeout)
     }
 
     pub fn build_target(
@@ -95,12 +103,14 @@ impl ReceiveStart {
         block: Block,
         timeout: Value,
         target: Block,
+        invalid_timeout: Block,
     ) {
         let target_val = builder.value(target);
+        let invalid_timeout_val = builder.value(invalid_timeout);
         builder.op_intrinsic(
             block,
             ReceiveStart,
-            &[target_val, timeout],
+            &[target_val, invalid_timeout_val, timeout],
             ReceiveToken(()),
         );
     }
@@ -109,6 +119,17 @@ impl OpBuild for ReceiveStart {
     type Token = ReceiveToken;
 }
 
+impl OpParser for ReceiveStart {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let args = parse_generic_args(ctx, tokens, 3..=3)?;
+        ctx.builder()
+            .op_intrinsic(block, ReceiveStart, &args, ReceiveToken(()));
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl_op_serialize_unit!(ReceiveStart);
+
 /// ## `receive_wait`
 /// (timeout: fn(), check_message: fn(msg), recv_ref)
 ///
@@ -136,12 +157,12 @@ impl Op for ReceiveWait {
         DynOp::new(self.clone())
     }
     fn type_id(&self) -> TypeId {
-        TypeId::of::<ReceiveStart>()
+        TypeId::of::<Self>()
     }
     fn meta_entry(&self) -> &dyn MetaEntry {
         self
     }
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    fn op_eq(&self, _fun: &Function, other: &dyn Op, _other_fun: &Function) -> bool {
         self.type_id() == other.type_id()
     }
 }
@@ -192,6 +213,17 @@ impl OpBuild for ReceiveWait {
     type Token = ReceiveToken;
 }
 
+impl OpParser for ReceiveWait {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let args = parse_generic_args(ctx, tokens, 3..=3)?;
+        ctx.builder()
+            .op_intrinsic(block, ReceiveWait, &args, ReceiveToken(()));
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl_op_serialize_unit!(ReceiveWait);
+
 /// ## `receive_done`
 /// (next: fn(...), recv_ref, ...)
 ///
@@ -220,12 +252,12 @@ impl Op for ReceiveDone {
         DynOp::new(self.clone())
     }
     fn type_id(&self) -> TypeId {
-        TypeId::of::<ReceiveStart>()
+        TypeId::of::<Self>()
     }
     fn meta_entry(&self) -> &dyn MetaEntry {
         self
     }
-    fn op_eq(&self, other: &dyn Op) -> bool {
+    fn op_eq(&self, _fun: &Function, other: &dyn Op, _other_fun: &Function) -> bool {
         self.type_id() == other.type_id()
     }
 }
@@ -280,13 +312,33 @@ impl OpBuild for ReceiveDone {
     type Token = ReceiveToken;
 }
 
+impl OpParser for ReceiveDone {
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()> {
+        let args = parse_generic_args(ctx, tokens, 2..)?;
+        ctx.builder()
+            .op_intrinsic(block, ReceiveDone, &args, ReceiveToken(()));
+        Ok(())
+    }
+}
+#[cfg(feature = "binary_serialization")]
+impl_op_serialize_unit!(ReceiveDone);
+
 pub fn register(dialect: &mut Dialect) {
     dialect.register_op::<ReceiveStart>();
     dialect.register_op_branches_impl(&ReceiveStart);
+    dialect.register_op_parser_impl(&ReceiveStart);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&ReceiveStart);
 
     dialect.register_op::<ReceiveWait>();
     dialect.register_op_branches_impl(&ReceiveWait);
+    dialect.register_op_parser_impl(&ReceiveWait);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&ReceiveWait);
 
     dialect.register_op::<ReceiveDone>();
     dialect.register_op_branches_impl(&ReceiveDone);
+    dialect.register_op_parser_impl(&ReceiveDone);
+    #[cfg(feature = "binary_serialization")]
+    dialect.register_op_serialize_impl(&ReceiveDone);
 }
@@ -8,6 +8,9 @@ use crate::binary::BinaryEntrySpecifier;
 use crate::constant::ConstantContainer;
 use crate::Const;
 
+#[cfg(feature = "binary_serialization")]
+pub(crate) mod serialize;
+
 #[derive(Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct PatternNode(u32);
 entity_impl!(PatternNode, "pattern_node");
@@ -102,6 +105,11 @@ pub enum PatternNodeKind {
         keys: EntityList<PatternValue>,
         values: EntityList<PatternNode>,
     },
+    /// The value is matched against both of the patterns in turn.
+    And {
+        left: PatternNode,
+        right: PatternNode,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -217,6 +225,13 @@ impl PatternContainer {
         data.finished = true;
     }
 
+    pub fn and(&mut self, node: PatternNode, left: PatternNode, right: PatternNode) {
+        let mut data = &mut self.nodes[node];
+        assert!(data.kind.is_none());
+        data.kind = Some(PatternNodeKind::And { left, right });
+        data.finished = true;
+    }
+
     pub fn map(&mut self, node: PatternNode) {
         let mut data = &mut self.nodes[node];
         assert!(data.kind.is_none());
@@ -437,6 +452,16 @@ fn copy_pattern_node(
             node_map.insert(node, new);
             new
         }
+        PatternNodeKind::And { left, right } => {
+            let left_copied = copy_pattern_node(value_map, node_map, *left, from, to);
+            let right_copied = copy_pattern_node(value_map, node_map, *right, from, to);
+
+            let new = to.node_empty(Some(data.span));
+            to.and(new, left_copied, right_copied);
+
+            node_map.insert(node, new);
+            new
+        }
         PatternNodeKind::Map { keys, values } => {
             let new = to.node_empty(Some(data.span));
             to.map(new);
@@ -473,6 +498,12 @@ impl PatternContainer {
         // TODO: Fuse locations
         let fused_span = self.nodes[lhs].span;
         match (lhs_kind, rhs_kind) {
+            (PatternNodeKind::And { .. }, _) | (_, PatternNodeKind::And { .. }) => {
+                Err(PatternMergeFail::Failure {
+                    left: Some(lhs),
+                    right: rhs,
+                })
+            }
             (PatternNodeKind::Value(l_val), PatternNodeKind::Value(r_val)) if l_val == r_val => {
                 map.insert(rhs, lhs);
                 Ok(lhs)
@@ -671,6 +702,10 @@ impl PatternContainer {
                 left: None,
                 right: lhs,
             }),
+            (PatternNodeKind::And { .. }, _) => Err(PatternMergeFail::Failure {
+                left: None,
+                right: lhs,
+            }),
             (a, b) => unimplemented!("{:?} {:?}", a, b),
         }
     }
@@ -0,0 +1,222 @@
+use cranelift_entity::EntityRef;
+use serde::{Deserialize, Serialize};
+
+use libeir_diagnostics::SourceSpan;
+
+use super::{PatternClause, PatternClauseData, PatternContainer};
+use super::{PatternNodeData, PatternNodeKind};
+use crate::binary::BinaryEntrySpecifier;
+use crate::serialize::{entity, entity_list, list_data};
+use crate::Const;
+
+#[derive(Serialize, Deserialize)]
+enum NodeKindData {
+    Wildcard,
+    Const(u32),
+    Value(u32),
+    Binary {
+        specifier: BinaryEntrySpecifier,
+        value: u32,
+        size: Option<u32>,
+        remaining: u32,
+    },
+    Tuple(Vec<u32>),
+    List {
+        head: u32,
+        tail: u32,
+    },
+    Map {
+        keys: Vec<u32>,
+        values: Vec<u32>,
+    },
+    And {
+        left: u32,
+        right: u32,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeData {
+    kind: Option<NodeKindData>,
+    finished: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClauseData {
+    root_nodes: Vec<u32>,
+    node_binds_keys: Vec<u32>,
+    node_binds_vals: Vec<u32>,
+    binds: Vec<u32>,
+    values: Vec<u32>,
+    finished: bool,
+}
+
+/// Serialized form of a `PatternContainer`, together with a list of clauses
+/// in it, as stored by the `case` operation.
+///
+/// Constants are referenced by index into the constant container of the
+/// function the pattern belongs to. Spans are not preserved.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PatternContainerData {
+    nodes: Vec<NodeData>,
+    num_values: u32,
+    clauses: Vec<ClauseData>,
+    selected: Vec<u32>,
+}
+
+impl PatternContainerData {
+    pub fn new(pat: &PatternContainer, selected: &[PatternClause]) -> Self {
+        let nodes = pat
+            .nodes
+            .values()
+            .map(|node| {
+                let kind = node.kind.as_ref().map(|kind| match kind {
+                    PatternNodeKind::Wildcard => NodeKindData::Wildcard,
+                    PatternNodeKind::Const(c) => NodeKindData::Const(c.index() as u32),
+                    PatternNodeKind::Value(v) => NodeKindData::Value(v.index() as u32),
+                    PatternNodeKind::Binary {
+                        specifier,
+                        value,
+                        size,
+                        remaining,
+                    } => NodeKindData::Binary {
+                        specifier: *specifier,
+                        value: value.index() as u32,
+                        size: size.map(|s| s.index() as u32),
+                        remaining: remaining.index() as u32,
+                    },
+                    PatternNodeKind::Tuple(elems) => {
+                        NodeKindData::Tuple(list_data(elems, &pat.node_pool))
+                    }
+                    PatternNodeKind::List { head, tail } => NodeKindData::List {
+                        head: head.index() as u32,
+                        tail: tail.index() as u32,
+                    },
+                    PatternNodeKind::Map { keys, values } => NodeKindData::Map {
+                        keys: list_data(keys, &pat.value_pool),
+                        values: list_data(values, &pat.node_pool),
+                    },
+                    PatternNodeKind::And { left, right } => NodeKindData::And {
+                        left: left.index() as u32,
+                        right: right.index() as u32,
+                    },
+                });
+                NodeData {
+                    kind,
+                    finished: node.finished,
+                }
+            })
+            .collect();
+
+        let clauses = pat
+            .clauses
+            .values()
+            .map(|clause| ClauseData {
+                root_nodes: list_data(&clause.root_nodes, &pat.node_pool),
+                node_binds_keys: list_data(&clause.node_binds_keys, &pat.node_pool),
+                node_binds_vals: list_data(&clause.node_binds_vals, &pat.value_pool),
+                binds: list_data(&clause.binds, &pat.node_pool),
+                values: list_data(&clause.values, &pat.value_pool),
+                finished: clause.finished,
+            })
+            .collect();
+
+        PatternContainerData {
+            nodes,
+            num_values: pat.values.len() as u32,
+            clauses,
+            selected: selected.iter().map(|c| c.index() as u32).collect(),
+        }
+    }
+
+    /// Rebuilds the container, returning `None` if any of the entity
+    /// references are out of bounds.
+    pub fn build(&self) -> Option<(PatternContainer, Vec<PatternClause>)> {
+        let mut pat = PatternContainer::new();
+
+        let num_nodes = self.nodes.len();
+        let num_values = self.num_values as usize;
+
+        for _ in 0..num_values {
+            pat.values.push(());
+        }
+
+        for node in self.nodes.iter() {
+            let kind = match &node.kind {
+                None => None,
+                Some(NodeKindData::Wildcard) => Some(PatternNodeKind::Wildcard),
+                Some(NodeKindData::Const(c)) => {
+                    Some(PatternNodeKind::Const(Const::new(*c as usize)))
+                }
+                Some(NodeKindData::Value(v)) => {
+                    Some(PatternNodeKind::Value(entity(*v, num_values)?))
+                }
+                Some(NodeKindData::Binary {
+                    specifier,
+                    value,
+                    size,
+                    remaining,
+                }) => Some(PatternNodeKind::Binary {
+                    specifier: *specifier,
+                    value: entity(*value, num_nodes)?,
+                    size: match size {
+                        Some(size) => Some(entity(*size, num_values)?),
+                        None => None,
+                    },
+                    remaining: entity(*remaining, num_nodes)?,
+                }),
+                Some(NodeKindData::Tuple(elems)) => Some(PatternNodeKind::Tuple(entity_list(
+                    elems,
+                    num_nodes,
+                    &mut pat.node_pool,
+                )?)),
+                Some(NodeKindData::List { head, tail }) => Some(PatternNodeKind::List {
+                    head: entity(*head, num_nodes)?,
+                    tail: entity(*tail, num_nodes)?,
+                }),
+                Some(NodeKindData::Map { keys, values }) => Some(PatternNodeKind::Map {
+                    keys: entity_list(keys, num_values, &mut pat.value_pool)?,
+                    values: entity_list(values, num_nodes, &mut pat.node_pool)?,
+                }),
+                Some(NodeKindData::And { left, right }) => Some(PatternNodeKind::And {
+                    left: entity(*left, num_nodes)?,
+                    right: entity(*right, num_nodes)?,
+                }),
+            };
+            pat.nodes.push(PatternNodeData {
+                kind,
+                finished: node.finished,
+                span: SourceSpan::UNKNOWN,
+            });
+        }
+
+        for clause in self.clauses.iter() {
+            let data = PatternClauseData {
+                span: SourceSpan::UNKNOWN,
+                root_nodes: entity_list(&clause.root_nodes, num_nodes, &mut pat.node_pool)?,
+                node_binds_keys: entity_list(
+                    &clause.node_binds_keys,
+                    num_nodes,
+                    &mut pat.node_pool,
+                )?,
+                node_binds_vals: entity_list(
+                    &clause.node_binds_vals,
+                    num_values,
+                    &mut pat.value_pool,
+                )?,
+                binds: entity_list(&clause.binds, num_nodes, &mut pat.node_pool)?,
+                values: entity_list(&clause.values, num_values, &mut pat.value_pool)?,
+                finished: clause.finished,
+            };
+            pat.clauses.push(data);
+        }
+
+        let selected = self
+            .selected
+            .iter()
+            .map(|c| entity(*c, self.clauses.len()))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some((pat, selected))
+    }
+}
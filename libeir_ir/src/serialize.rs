@@ -0,0 +1,437 @@
+//! Binary serialization of Eir modules and functions.
+//!
+//! Encoded data starts with a small header, containing a magic number and
+//! the format version, followed by the body encoded with `bincode`. The
+//! version is bumped on any change to the encoding, and data with a
+//! different version is rejected when decoding.
+//!
+//! Entity indices are preserved, a decoded function is equal to the
+//! original according to `Function::graph_eq`. Source spans refer to files
+//! in a `CodeMap` that only exists in the process that created them, and
+//! are not preserved. The file, line and names of locations are.
+//!
+//! Dialect operations are encoded through the `OpSerialize` trait, which
+//! needs to be registered with the dialect for every operation contained
+//! in a function.
+
+use cranelift_entity::packed_option::ReservedValue;
+use cranelift_entity::{EntityList, EntityRef, ListPool};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::Ident;
+
+use crate::constant::serialize::ConstantContainerData;
+use crate::function::serialize::FunctionData;
+use crate::{Function, Module};
+
+/// The current version of the binary format.
+pub const FORMAT_VERSION: u32 = 1;
+
+const MAGIC: [u8; 4] = *b"EIR\0";
+
+#[derive(Debug, Snafu)]
+pub enum SerializeError {
+    #[snafu(display("operation `{}` does not support serialization", name))]
+    UnsupportedOp { name: String },
+
+    #[snafu(display("data is not a serialized eir module"))]
+    InvalidMagic,
+
+    #[snafu(display("unsupported format version {}, expected {}", version, FORMAT_VERSION))]
+    UnsupportedVersion { version: u32 },
+
+    #[snafu(display("unknown operation `{}`", name))]
+    UnknownOp { name: String },
+
+    #[snafu(display("malformed {}", what))]
+    Malformed { what: &'static str },
+
+    #[snafu(display("{}", source))]
+    Bincode { source: bincode::Error },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    magic: [u8; 4],
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AttributeData {
+    name: String,
+    value: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FunctionDefinitionData {
+    exported: bool,
+    function: FunctionData,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModuleData {
+    name: String,
+    constants: ConstantContainerData,
+    attributes: Vec<AttributeData>,
+    on_load: Option<u32>,
+    functions: Vec<FunctionDefinitionData>,
+}
+
+fn encode<T: Serialize>(body: &T) -> Result<Vec<u8>, SerializeError> {
+    let header = Header {
+        magic: MAGIC,
+        version: FORMAT_VERSION,
+    };
+    let mut out = bincode::serialize(&header).context(Bincode)?;
+    bincode::serialize_into(&mut out, body).context(Bincode)?;
+    Ok(out)
+}
+
+fn decode<T: DeserializeOwned>(mut data: &[u8]) -> Result<T, SerializeError> {
+    let header: Header = bincode::deserialize_from(&mut data).context(Bincode)?;
+    if header.magic != MAGIC {
+        return InvalidMagic.fail();
+    }
+    if header.version != FORMAT_VERSION {
+        return UnsupportedVersion {
+            version: header.version,
+        }
+        .fail();
+    }
+    bincode::deserialize(data).context(Bincode)
+}
+
+impl Function {
+    /// Encodes the function in the binary format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializeError> {
+        encode(&FunctionData::new(self)?)
+    }
+
+    /// Decodes a function encoded with `to_bytes`. The function will use
+    /// the normal dialect.
+    pub fn from_bytes(data: &[u8]) -> Result<Function, SerializeError> {
+        let data: FunctionData = decode(data)?;
+        data.build()
+    }
+}
+
+impl Module {
+    /// Encodes the module in the binary format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializeError> {
+        let attributes = self
+            .attribute_iter()
+            .map(|attr| AttributeData {
+                name: attr.name.as_str().to_string(),
+                value: attr.value.index() as u32,
+            })
+            .collect();
+
+        let mut functions = Vec::new();
+        for def in self.function_iter() {
+            functions.push(FunctionDefinitionData {
+                exported: def.is_exported(),
+                function: FunctionData::new(def.function())?,
+            });
+        }
+
+        encode(&ModuleData {
+            name: self.name().as_str().to_string(),
+            constants: ConstantContainerData::new(self.cons()),
+            attributes,
+            on_load: self.on_load().map(|idx| idx.index() as u32),
+            functions,
+        })
+    }
+
+    /// Decodes a module encoded with `to_bytes`.
+    pub fn from_bytes(data: &[u8]) -> Result<Module, SerializeError> {
+        let data: ModuleData = decode(data)?;
+
+        let mut module = Module::new(Ident::from_str(&data.name));
+        *module.cons_mut() = data
+            .constants
+            .build()
+            .context(Malformed { what: "constants" })?;
+
+        for attr in data.attributes.iter() {
+            let value = entity(attr.value, data.constants.count())
+                .context(Malformed { what: "attribute" })?;
+            module.add_attribute(Ident::from_str(&attr.name), value);
+        }
+
+        for def_data in data.functions.iter() {
+            let fun_data = &def_data.function;
+            let name = Ident::from_str(&fun_data.name);
+            if fun_data.module != data.name
+                || module.name_arity_index(name.name, fun_data.arity).is_some()
+            {
+                return Malformed { what: "function" }.fail();
+            }
+
+            let fun = fun_data.build()?;
+            let def = module.add_function(SourceSpan::UNKNOWN, name, fun_data.arity);
+            def.set_exported(def_data.exported);
+            *def.function_mut() = fun;
+        }
+
+        let on_load = match data.on_load {
+            Some(idx) => {
+                Some(entity(idx, data.functions.len()).context(Malformed { what: "on_load" })?)
+            }
+            None => None,
+        };
+        module.set_on_load(on_load);
+
+        Ok(module)
+    }
+}
+
+pub(crate) fn entity<E: EntityRef>(n: u32, len: usize) -> Option<E> {
+    if (n as usize) < len {
+        Some(E::new(n as usize))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn entity_list<E: EntityRef + ReservedValue>(
+    data: &[u32],
+    len: usize,
+    pool: &mut ListPool<E>,
+) -> Option<EntityList<E>> {
+    let mut list = EntityList::new();
+    for n in data {
+        list.push(entity(*n, len)?, pool);
+    }
+    Some(list)
+}
+
+pub(crate) fn list_data<E: EntityRef + ReservedValue>(
+    list: &EntityList<E>,
+    pool: &ListPool<E>,
+) -> Vec<u32> {
+    list.as_slice(pool)
+        .iter()
+        .map(|e| e.index() as u32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use libeir_diagnostics::SourceSpan;
+    use libeir_intern::Ident;
+    use libeir_util_number::BigInt;
+
+    use super::SerializeError;
+    use crate::operation::binary_construct::{
+        BinaryConstructFinish, BinaryConstructPush, BinaryConstructStart,
+    };
+    use crate::operation::case::Case;
+    use crate::{parse_function_unwrap, parse_module_unwrap};
+    use crate::{BigIntTerm, BinaryEntrySpecifier, Function, FunctionIdent, Module};
+
+    fn roundtrip_bytes(fun: &Function) -> Function {
+        let bytes = fun.to_bytes().unwrap();
+        let decoded = Function::from_bytes(&bytes).unwrap();
+        // Entity indices are preserved, encoding again gives the same data
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+        decoded
+    }
+
+    fn roundtrip(fun: &Function) {
+        let decoded = roundtrip_bytes(fun);
+        assert_eq!(decoded.ident(), fun.ident());
+        assert!(fun
+            .graph_eq(fun.block_entry(), &decoded, decoded.block_entry())
+            .is_ok());
+    }
+
+    fn new_function() -> Function {
+        let ident = FunctionIdent {
+            module: Ident::from_str("foo"),
+            name: Ident::from_str("bar"),
+            arity: 1,
+        };
+        Function::new(SourceSpan::UNKNOWN, ident)
+    }
+
+    #[test]
+    fn basic_roundtrip() {
+        let fun = parse_function_unwrap(
+            "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %fun = a'erlang':a'+'/2;
+        %fun(block2, %thr, %a, 1);
+    block2(%b):
+        block3({%b, [1, 2, a'atom' | []]});
+    block3(%c):
+        %ret(%c);
+}
+",
+        );
+        roundtrip(&fun);
+    }
+
+    #[test]
+    fn match_roundtrip() {
+        let fun = parse_function_unwrap(
+            "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        match %a {
+            value 1 => one;
+            {} arity 2 => tup;
+            _ => other;
+        };
+    one():
+        %ret(a'one');
+    tup(%x, %y):
+        %ret(%y);
+    other():
+        unreachable;
+}
+",
+        );
+        roundtrip(&fun);
+    }
+
+    #[test]
+    fn constants_and_dialect_ops_roundtrip() {
+        let mut fun = new_function();
+        let mut b = fun.builder();
+
+        let entry = b.block_insert();
+        b.block_set_entry(entry);
+        let ret = b.block_arg_insert(entry);
+        let _thr = b.block_arg_insert(entry);
+        let arg = b.block_arg_insert(entry);
+
+        let start = BinaryConstructStart::build(&mut b, entry);
+        let bin_ref = b.block_args(start)[0];
+        let spec = BinaryEntrySpecifier::Bytes { unit: 8 };
+        let (ok, fail) = BinaryConstructPush::build(&mut b, start, bin_ref, arg, spec, None);
+        b.op_unreachable(SourceSpan::UNKNOWN, fail);
+
+        let bin_ref = b.block_args(ok)[0];
+        let done = BinaryConstructFinish::build(&mut b, ok, bin_ref);
+        let bin = b.block_args(done)[0];
+
+        let big = BigInt::parse_bytes(b"-123456789012345678901234567890", 10).unwrap();
+        let consts = [
+            b.value(2.5),
+            b.value(BigIntTerm(big)),
+            b.value(vec![1u8, 2, 3]),
+        ];
+        let tup = b.prim_tuple(SourceSpan::UNKNOWN, &[bin, consts[0], consts[1], consts[2]]);
+        b.op_call_flow(done, ret, &[tup]);
+
+        roundtrip(&fun);
+    }
+
+    #[test]
+    fn case_roundtrip() {
+        let mut fun = new_function();
+        let mut b = fun.builder();
+
+        let entry = b.block_insert();
+        b.block_set_entry(entry);
+        let ret = b.block_arg_insert(entry);
+        let _thr = b.block_arg_insert(entry);
+        let arg = b.block_arg_insert(entry);
+
+        let no_match = b.block_insert();
+        b.op_unreachable(SourceSpan::UNKNOWN, no_match);
+
+        let guard = b.block_insert();
+        let guard_ret = b.block_arg_insert(guard);
+        let _guard_thr = b.block_arg_insert(guard);
+        let true_val = b.value(true);
+        b.op_call_flow(guard, guard_ret, &[true_val]);
+
+        let body = b.block_insert();
+        let ok = b.value(Ident::from_str("ok"));
+        b.op_call_flow(body, ret, &[ok]);
+
+        let mut case_b = Case::builder();
+        case_b.match_on = Some(arg);
+        case_b.no_match = Some(b.value(no_match));
+
+        let clause = case_b.container.clause_start(SourceSpan::UNKNOWN);
+        let node = case_b.container.node_empty(None);
+        let cons = b.cons_mut().from(Ident::from_str("a"));
+        case_b.container.constant(node, cons);
+        case_b.container.clause_node_push(clause, node);
+        case_b.container.clause_finish(clause);
+
+        let guard_val = b.value(guard);
+        let body_val = b.value(body);
+        case_b.push_clause(clause, guard_val, body_val, &mut b);
+        case_b.finish(entry, &mut b);
+
+        let decoded = roundtrip_bytes(&fun);
+        let case = decoded
+            .block_kind(decoded.block_entry())
+            .unwrap()
+            .get_dyn::<Case>()
+            .unwrap();
+        assert_eq!(case.clauses().len(), 1);
+    }
+
+    #[test]
+    fn module_roundtrip() {
+        let module = parse_module_unwrap(
+            "
+a'woo' {
+    attribute a'vsn' = [1];
+    on_load a'init'/0;
+
+    export a'foo'/1 {
+        entry(%ret, %thr, %a):
+            %ret(%a);
+    }
+    a'init'/0 {
+        entry(%ret, %thr):
+            %ret(a'ok');
+    }
+}
+",
+        );
+
+        let bytes = module.to_bytes().unwrap();
+        let decoded = Module::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.name(), module.name());
+        assert_eq!(decoded.on_load(), module.on_load());
+        assert_eq!(decoded.attribute_iter().count(), 1);
+
+        for (l, r) in module.function_iter().zip(decoded.function_iter()) {
+            assert_eq!(l.is_exported(), r.is_exported());
+            let (lf, rf) = (l.function(), r.function());
+            assert!(lf.graph_eq(lf.block_entry(), rf, rf.block_entry()).is_ok());
+        }
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let fun = parse_function_unwrap(
+            "
+a'foo':a'bar'/0 {
+    entry(%ret, %thr):
+        %ret(a'ok');
+}
+",
+        );
+        let mut bytes = fun.to_bytes().unwrap();
+        bytes[4] = bytes[4].wrapping_add(1);
+
+        match Function::from_bytes(&bytes) {
+            Err(SerializeError::UnsupportedVersion { .. }) => (),
+            _ => panic!(),
+        }
+    }
+}
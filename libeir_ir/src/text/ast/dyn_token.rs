@@ -0,0 +1,231 @@
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::Ident;
+use libeir_util_number::ToPrimitive;
+
+use super::Value;
+use crate::constant::{FloatTerm, Integer};
+use crate::{BinaryEntrySpecifier, Endianness};
+
+/// Token tree making up the body of a dialect operation.
+///
+/// The grammar only makes sure groups are balanced, the meaning of the
+/// tokens is up to the `OpParser` registered for the operation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DynToken {
+    /// `( .. )`
+    Parens(Vec<DynToken>, SourceSpan),
+    /// `{ .. }`
+    Braces(Vec<DynToken>, SourceSpan),
+    /// `%{ .. }`
+    MapBraces(Vec<DynToken>, SourceSpan),
+    /// `[ .. ]`
+    SquareBrackets(Vec<DynToken>, SourceSpan),
+    /// `< .. >`
+    AngleBrackets(Vec<DynToken>, SourceSpan),
+
+    Ident(Ident),
+    Variable(Ident),
+
+    Atom(Ident),
+    Integer(Integer, SourceSpan),
+    Float(FloatTerm, SourceSpan),
+    Binary(Vec<u8>, SourceSpan),
+
+    Colon(SourceSpan),
+    ColonEquals(SourceSpan),
+    Semicolon(SourceSpan),
+    Comma(SourceSpan),
+    ForwardSlash(SourceSpan),
+    Equals(SourceSpan),
+    FatArrow(SourceSpan),
+    Underscore(SourceSpan),
+    Pipe(SourceSpan),
+    At(SourceSpan),
+}
+
+impl DynToken {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            DynToken::Parens(_, span) => *span,
+            DynToken::Braces(_, span) => *span,
+            DynToken::MapBraces(_, span) => *span,
+            DynToken::SquareBrackets(_, span) => *span,
+            DynToken::AngleBrackets(_, span) => *span,
+            DynToken::Ident(ident) => ident.span,
+            DynToken::Variable(ident) => ident.span,
+            DynToken::Atom(ident) => ident.span,
+            DynToken::Integer(_, span) => *span,
+            DynToken::Float(_, span) => *span,
+            DynToken::Binary(_, span) => *span,
+            DynToken::Colon(span) => *span,
+            DynToken::ColonEquals(span) => *span,
+            DynToken::Semicolon(span) => *span,
+            DynToken::Comma(span) => *span,
+            DynToken::ForwardSlash(span) => *span,
+            DynToken::Equals(span) => *span,
+            DynToken::FatArrow(span) => *span,
+            DynToken::Underscore(span) => *span,
+            DynToken::Pipe(span) => *span,
+            DynToken::At(span) => *span,
+        }
+    }
+
+    pub fn ident(&self) -> Option<Ident> {
+        match self {
+            DynToken::Ident(ident) => Some(*ident),
+            _ => None,
+        }
+    }
+
+    /// Tests if the token is the given identifier.
+    pub fn is_ident(&self, name: &str) -> bool {
+        self.ident().map(|i| i.name == name).unwrap_or(false)
+    }
+
+    pub fn parens(&self) -> Option<&[DynToken]> {
+        match self {
+            DynToken::Parens(inner, _) => Some(inner),
+            _ => None,
+        }
+    }
+
+    pub fn braces(&self) -> Option<&[DynToken]> {
+        match self {
+            DynToken::Braces(inner, _) => Some(inner),
+            _ => None,
+        }
+    }
+
+    pub fn angle_brackets(&self) -> Option<&[DynToken]> {
+        match self {
+            DynToken::AngleBrackets(inner, _) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a token sequence on its top level commas.
+/// An empty sequence has no entries.
+pub fn split_commas(tokens: &[DynToken]) -> Vec<&[DynToken]> {
+    if tokens.is_empty() {
+        Vec::new()
+    } else {
+        tokens
+            .split(|t| match t {
+                DynToken::Comma(_) => true,
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+/// Reads a single value from a token sequence. This accepts the same
+/// syntax as values in the rest of the text format, with the exception of
+/// function captures and operators, which are only allowed in assignments.
+pub fn tokens_to_value(tokens: &[DynToken]) -> Option<Value> {
+    match tokens {
+        [token] => token_to_value(token),
+        _ => None,
+    }
+}
+
+fn tokens_to_values(tokens: &[DynToken]) -> Option<Vec<Value>> {
+    split_commas(tokens)
+        .iter()
+        .map(|entry| tokens_to_value(entry))
+        .collect()
+}
+
+fn token_to_value(token: &DynToken) -> Option<Value> {
+    match token {
+        DynToken::Variable(ident) => Some(Value::Value(*ident)),
+        DynToken::Ident(ident) => Some(Value::Block(*ident)),
+        DynToken::Atom(ident) => Some(Value::Atom(*ident)),
+        DynToken::Integer(int, _) => Some(Value::Integer(int.clone())),
+        DynToken::Float(float, _) => Some(Value::Float(float.clone())),
+        DynToken::Binary(bin, _) => Some(Value::Binary(bin.clone())),
+        DynToken::Braces(inner, _) => Some(Value::Tuple(tokens_to_values(inner)?)),
+        DynToken::AngleBrackets(inner, _) => Some(Value::ValueList(tokens_to_values(inner)?)),
+        DynToken::SquareBrackets(inner, _) => {
+            let pipe = inner.iter().position(|t| match t {
+                DynToken::Pipe(_) => true,
+                _ => false,
+            });
+            let (head, tail) = match pipe {
+                Some(idx) => (&inner[..idx], Some(tokens_to_value(&inner[idx + 1..])?)),
+                None => (&inner[..], None),
+            };
+            let head = tokens_to_values(head)?;
+            if head.is_empty() {
+                Some(tail.unwrap_or(Value::Nil))
+            } else {
+                Some(Value::List(head, tail.map(Box::new)))
+            }
+        }
+        DynToken::MapBraces(inner, _) => {
+            let mut entries = Vec::new();
+            for entry in split_commas(inner) {
+                let arrow = entry.iter().position(|t| match t {
+                    DynToken::FatArrow(_) => true,
+                    _ => false,
+                })?;
+                let key = tokens_to_value(&entry[..arrow])?;
+                let value = tokens_to_value(&entry[arrow + 1..])?;
+                entries.push((key, value));
+            }
+            Some(Value::Map(entries))
+        }
+        _ => None,
+    }
+}
+
+/// Reads a binary entry specifier in the form printed by the text printer,
+/// `name(arg, ..)`, where `args` are the tokens within the parentheses.
+pub fn binary_specifier(name: Ident, args: &[DynToken]) -> Option<BinaryEntrySpecifier> {
+    let args: Vec<&DynToken> = split_commas(args)
+        .iter()
+        .map(|arg| match arg {
+            [token] => Some(token),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    let endianness = |token: &DynToken| match &*token.ident()?.as_str() {
+        "big" => Some(Endianness::Big),
+        "little" => Some(Endianness::Little),
+        "native" => Some(Endianness::Native),
+        _ => None,
+    };
+    let signed = |token: &DynToken| match &*token.ident()?.as_str() {
+        "signed" => Some(true),
+        "unsigned" => Some(false),
+        _ => None,
+    };
+    let unit = |token: &DynToken| match token {
+        DynToken::Integer(int, _) => int.to_i64(),
+        _ => None,
+    };
+
+    let spec = match (&*name.as_str(), args.as_slice()) {
+        ("integer", [s, e, u]) => BinaryEntrySpecifier::Integer {
+            signed: signed(s)?,
+            endianness: endianness(e)?,
+            unit: unit(u)?,
+        },
+        ("float", [e, u]) => BinaryEntrySpecifier::Float {
+            endianness: endianness(e)?,
+            unit: unit(u)?,
+        },
+        ("bytes", [u]) => BinaryEntrySpecifier::Bytes { unit: unit(u)? },
+        ("bits", [u]) => BinaryEntrySpecifier::Bits { unit: unit(u)? },
+        ("utf8", []) => BinaryEntrySpecifier::Utf8,
+        ("utf16", [e]) => BinaryEntrySpecifier::Utf16 {
+            endianness: endianness(e)?,
+        },
+        ("utf32", [e]) => BinaryEntrySpecifier::Utf32 {
+            endianness: endianness(e)?,
+        },
+        _ => return None,
+    };
+    Some(spec)
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use cranelift_entity::EntityList;
+
 use libeir_diagnostics::{Diagnostic, Label, SourceSpan, ToDiagnostic};
 use libeir_intern::Ident;
 use libeir_util_datastructures::hashmap_stack::HashMapStack;
@@ -8,10 +10,10 @@ use libeir_util_parse::ErrorReceiver;
 
 use snafu::Snafu;
 
-use crate::text::ast;
-use crate::{Block, Value};
+use crate::constant::{AtomTerm, BinaryTerm, NilTerm};
+use crate::text::ast::{self, tokens_to_value, DynToken};
+use crate::{Block, Const, ConstKind, ConstantContainer, PrimOpKind, Value};
 use crate::{Function, FunctionBuilder, FunctionIdent, Module};
-use crate::{PatternContainer, PatternNode};
 
 type ErrCollector<'a> = &'a mut dyn ErrorReceiver<E = LowerError, W = LowerError>;
 
@@ -39,6 +41,23 @@ pub enum LowerError {
     UndefinedBind {
         span: SourceSpan,
     },
+
+    UndefinedFunction {
+        span: SourceSpan,
+    },
+
+    NonConstantAttribute {
+        span: SourceSpan,
+    },
+
+    UnknownOperation {
+        span: SourceSpan,
+    },
+
+    MalformedOperation {
+        span: SourceSpan,
+        reason: &'static str,
+    },
 }
 
 impl ToDiagnostic for LowerError {
@@ -65,6 +84,24 @@ impl ToDiagnostic for LowerError {
                 .with_message("undefined block name")
                 .with_labels(vec![Label::primary(span.source_id(), *span)
                     .with_message("block name was not defined in the IR")]),
+            LowerError::UndefinedFunction { span } => Diagnostic::error()
+                .with_message("undefined function")
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("function was not defined in the module")]),
+            LowerError::NonConstantAttribute { span } => Diagnostic::error()
+                .with_message("attribute value is not a constant")
+                .with_labels(vec![Label::primary(span.source_id(), *span).with_message(
+                    "attribute values can only contain atoms, integers, tuples and lists",
+                )]),
+            LowerError::UnknownOperation { span } => Diagnostic::error()
+                .with_message("unknown operation")
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("operation has no parser in the dialect")]),
+            LowerError::MalformedOperation { span, reason } => Diagnostic::error()
+                .with_message("malformed operation")
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message(*reason)
+                ]),
             _ => Diagnostic::error().with_message(msg),
         }
     }
@@ -96,9 +133,35 @@ impl ast::Module {
                         fun.name,
                         fun.arity.to_usize().unwrap(),
                     );
+                    fun_ir.set_exported(fun.exported);
                     let mut b = fun_ir.function_mut().builder();
                     fun.lower_into(errors, &mut b)?;
                 }
+                ast::ModuleItem::Attribute(attr) => {
+                    let value = match lower_constant(module.cons_mut(), &attr.value) {
+                        Some(value) => value,
+                        None => {
+                            errors.error(LowerError::NonConstantAttribute { span: attr.span });
+                            return Err(());
+                        }
+                    };
+                    module.add_attribute(attr.name, value);
+                }
+                ast::ModuleItem::OnLoad(_) => (),
+            }
+        }
+
+        // The on_load function may be defined after it is referenced
+        for item in self.items.iter() {
+            if let ast::ModuleItem::OnLoad(on_load) = item {
+                let arity = on_load.arity.to_usize().unwrap();
+                match module.name_arity_index(on_load.name.name, arity) {
+                    Some(index) => module.set_on_load(Some(index)),
+                    None => {
+                        errors.error(LowerError::UndefinedFunction { span: on_load.span });
+                        return Err(());
+                    }
+                }
             }
         }
 
@@ -106,6 +169,58 @@ impl ast::Module {
     }
 }
 
+/// Lowers a value that is only made up of constant terms.
+fn lower_constant(c: &mut ConstantContainer, val: &ast::Value) -> Option<Const> {
+    match val {
+        ast::Value::Atom(atom) => Some(c.from(AtomTerm(atom.name))),
+        ast::Value::Integer(int) => Some(c.from(int.clone())),
+        ast::Value::Float(float) => Some(c.from(float.clone())),
+        ast::Value::Binary(bin) => Some(c.from(BinaryTerm(bin.clone()))),
+        ast::Value::Nil => Some(c.nil()),
+        ast::Value::Tuple(tup) => {
+            let mut builder = c.tuple_builder();
+            for elem in tup.iter() {
+                match lower_constant(c, elem) {
+                    Some(elem) => builder.push(elem, c),
+                    None => {
+                        builder.clear(c);
+                        return None;
+                    }
+                }
+            }
+            Some(builder.finish(c))
+        }
+        ast::Value::List(head, tail) => {
+            let mut acc = match tail {
+                Some(tail) => lower_constant(c, tail)?,
+                None => c.nil(),
+            };
+            for elem in head.iter().rev() {
+                let elem = lower_constant(c, elem)?;
+                acc = c.list_cell(elem, acc);
+            }
+            Some(acc)
+        }
+        ast::Value::Map(entries) => {
+            let mut pairs = Vec::with_capacity(entries.len());
+            for (key, value) in entries.iter() {
+                pairs.push((lower_constant(c, key)?, lower_constant(c, value)?));
+            }
+            // Same canonical ordering as `FunctionBuilder::prim_map`
+            pairs.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+            let mut keys = EntityList::new();
+            let mut values = EntityList::new();
+            for (key, value) in pairs {
+                keys.push(key, &mut c.const_pool);
+                values.push(value, &mut c.const_pool);
+            }
+            Some(c.from(ConstKind::Map { keys, values }))
+        }
+        _ => None,
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum Name {
     Value(Ident),
@@ -253,7 +368,26 @@ fn lower_operation(
     op: &ast::Op,
 ) -> Result<(), ()> {
     match op {
-        ast::Op::Dyn(ident, opts) => unimplemented!(),
+        ast::Op::Dyn(dyn_op) => {
+            let dialect = b.fun().dialect().clone();
+            let parser = match dialect.get_op_parser(dyn_op.name.name) {
+                Some(parser) => parser,
+                None => {
+                    errors.error(LowerError::UnknownOperation {
+                        span: dyn_op.name.span,
+                    });
+                    return Err(());
+                }
+            };
+
+            let mut ctx = LowerContext {
+                builder: b,
+                errors,
+                scope,
+                span: dyn_op.span,
+            };
+            parser.parse(&mut ctx, block, &dyn_op.tokens)?;
+        }
         ast::Op::CallControlFlow(call) => {
             let target = lower_value(errors, b, scope, &call.target)?;
             let args: Result<Vec<_>, _> = call
@@ -296,6 +430,11 @@ fn lower_operation(
             let then = lower_value(errors, b, scope, &trace_op.then)?;
             b.op_trace_capture_raw_next(SourceSpan::UNKNOWN, block, then);
         }
+        ast::Op::TraceConstruct(trace_op) => {
+            let then = lower_value(errors, b, scope, &trace_op.then)?;
+            let trace = lower_value(errors, b, scope, &trace_op.trace)?;
+            b.op_trace_construct_next(SourceSpan::UNKNOWN, block, then, trace);
+        }
         ast::Op::Match(match_op) => {
             let mut builder = b.op_match_build(SourceSpan::UNKNOWN);
             for entry in match_op.entries.iter() {
@@ -321,100 +460,41 @@ fn lower_operation(
                     ast::MatchKind::Tuple(n) => {
                         builder.push_tuple_next(next, *n, b);
                     }
-                    s => unimplemented!("{:?}", s),
+                    ast::MatchKind::Binary(spec, size) => {
+                        let size_v = size
+                            .as_ref()
+                            .map(|v| lower_value(errors, b, scope, v))
+                            .transpose()?;
+                        builder.push_binary_next(next, *spec, size_v, b);
+                    }
                 }
             }
 
             let match_val = lower_value(errors, b, scope, &match_op.value)?;
             builder.finish(block, match_val, b);
         }
-        ast::Op::Unreachable => {
-            b.op_unreachable(SourceSpan::UNKNOWN, block);
-        }
-        ast::Op::Case(case_op) => {
-            use crate::operation::case::Case;
-            let value = lower_value(errors, b, scope, &case_op.value)?;
-
-            let mut binds = HashMap::new();
-
-            let mut case_b = Case::builder();
-
-            for entry in case_op.entries.iter() {
-                binds.clear();
-                let clause = case_b.container.clause_start(SourceSpan::UNKNOWN);
-
-                for pattern in entry.patterns.iter() {
-                    let pat = lower_case_pattern(
-                        errors,
-                        &mut case_b.container,
-                        b,
-                        scope,
-                        &mut binds,
-                        pattern,
-                    )?;
-                    case_b.container.clause_node_push(clause, pat);
-                }
-
-                for bind in entry.args.iter() {
-                    if let Some(node) = binds.get(bind) {
-                        case_b.container.clause_bind_push(clause, node.1);
-                    } else {
-                        errors.error(LowerError::UndefinedBind { span: bind.span });
-                        return Err(());
-                    }
-                }
-
-                case_b.container.clause_finish(clause);
-
-                let guard = lower_value(errors, b, scope, &entry.guard)?;
-                let target = lower_value(errors, b, scope, &entry.target)?;
-
-                case_b.push_clause(clause, guard, target, b);
-            }
-
-            if let Some(no_match) = case_op.no_match.as_ref() {
-                let val = lower_value(errors, b, scope, no_match)?;
-                case_b.no_match = Some(val);
+        ast::Op::MapPut(map_put) => {
+            let value = lower_value(errors, b, scope, &map_put.value)?;
+            let ok = lower_value(errors, b, scope, &map_put.ok)?;
+            let fail = lower_value(errors, b, scope, &map_put.fail)?;
+
+            let mut entries = Vec::with_capacity(map_put.entries.len());
+            for entry in map_put.entries.iter() {
+                let key = lower_value(errors, b, scope, &entry.key)?;
+                let value = lower_value(errors, b, scope, &entry.value)?;
+                entries.push((key, value, entry.action));
             }
 
-            case_b.match_on = Some(value);
-            case_b.finish(block, b);
+            b.op_map_put_next(SourceSpan::UNKNOWN, block, ok, fail, value, &entries);
+        }
+        ast::Op::Unreachable => {
+            b.op_unreachable(SourceSpan::UNKNOWN, block);
         }
     }
 
     Ok(())
 }
 
-fn lower_case_pattern(
-    errors: ErrCollector,
-    pat: &mut PatternContainer,
-    b: &mut FunctionBuilder,
-    scope: &mut HashMapStack<Name, (SourceSpan, Value)>,
-    binds: &mut HashMap<Ident, (SourceSpan, PatternNode)>,
-    pattern: &ast::CasePattern,
-) -> Result<PatternNode, ()> {
-    match pattern {
-        ast::CasePattern::Binding { name, pattern } => {
-            let child = lower_case_pattern(errors, pat, b, scope, binds, pattern)?;
-            if binds.contains_key(name) {
-                errors.error(LowerError::DuplicateDefinititon {
-                    current: name.span,
-                    previous: binds[name].0,
-                });
-                return Err(());
-            }
-            binds.insert(*name, (name.span, child));
-            Ok(child)
-        }
-        ast::CasePattern::Wildcard => {
-            let node = pat.node_empty(Some(SourceSpan::UNKNOWN));
-            pat.wildcard(node);
-            Ok(node)
-        }
-        _ => unimplemented!(),
-    }
-}
-
 fn lower_value(
     errors: ErrCollector,
     b: &mut FunctionBuilder,
@@ -438,15 +518,12 @@ fn lower_value(
                 return Err(());
             }
         }
-        ast::Value::Atom(atom) => {
-            // TODO escapes
-            Ok(b.value(crate::constant::AtomTerm(atom.name)))
-        }
+        ast::Value::Atom(atom) => Ok(b.value(AtomTerm(atom.name))),
         ast::Value::Integer(int) => match int {
             crate::constant::Integer::Small(int) => Ok(b.value(*int)),
             crate::constant::Integer::Big(int) => Ok(b.value(int.clone())),
         },
-        ast::Value::Nil => Ok(b.value(crate::constant::NilTerm)),
+        ast::Value::Nil => Ok(b.value(NilTerm)),
         ast::Value::ValueList(list) => {
             let v_buf: Result<Vec<Value>, _> = list
                 .iter()
@@ -472,14 +549,48 @@ fn lower_value(
             let lhs_v = lower_value(errors, b, scope, &*lhs)?;
             let rhs_v = lower_value(errors, b, scope, &*rhs)?;
 
-            Ok(b.prim_binop(SourceSpan::UNKNOWN, *op, lhs_v, rhs_v))
+            // Operands are kept in the printed order, the builder would
+            // otherwise reorder symmetric operations.
+            Ok(b.prim_raw(SourceSpan::UNKNOWN, PrimOpKind::BinOp(*op), &[lhs_v, rhs_v]))
+        }
+        ast::Value::LogicOp(op, values) => {
+            let v_buf: Result<Vec<Value>, _> = values
+                .iter()
+                .map(|v| lower_value(errors, b, scope, v))
+                .collect();
+            Ok(b.prim_raw(SourceSpan::UNKNOWN, PrimOpKind::LogicOp(*op), &v_buf?))
         }
+        ast::Value::Map(entries) => {
+            let mut keys = Vec::with_capacity(entries.len());
+            let mut values = Vec::with_capacity(entries.len());
+            for (key, value) in entries.iter() {
+                keys.push(lower_value(errors, b, scope, key)?);
+                values.push(lower_value(errors, b, scope, value)?);
+            }
+
+            let all_const = keys
+                .iter()
+                .chain(values.iter())
+                .all(|v| b.fun().value_const(*v).is_some());
+            if all_const {
+                Ok(b.prim_map(SourceSpan::UNKNOWN, &keys, &values))
+            } else {
+                let reads: Vec<Value> = keys
+                    .iter()
+                    .zip(values.iter())
+                    .flat_map(|(k, v)| vec![*k, *v])
+                    .collect();
+                Ok(b.prim_raw(SourceSpan::UNKNOWN, PrimOpKind::Map, &reads))
+            }
+        }
+        ast::Value::Float(float) => Ok(b.value(float.clone())),
+        ast::Value::Binary(bin) => Ok(b.value(BinaryTerm(bin.clone()))),
         ast::Value::List(head, tail) => {
             let mut acc = tail
                 .as_ref()
                 .map(|v| lower_value(errors, b, scope, &*v))
                 .transpose()?
-                .unwrap_or(b.value(crate::constant::NilTerm));
+                .unwrap_or(b.value(NilTerm));
             for v in head.iter().rev() {
                 let new_val = lower_value(errors, b, scope, &*v)?;
                 acc = b.prim_list_cell(SourceSpan::UNKNOWN, new_val, acc);
@@ -488,3 +599,65 @@ fn lower_value(
         }
     }
 }
+
+/// Passed to `OpParser` implementations when lowering dialect operations.
+///
+/// Values are resolved in the scope of the block the operation is in, and
+/// errors are reported at the span of the operation.
+pub struct LowerContext<'a, 'b> {
+    builder: &'a mut FunctionBuilder<'b>,
+    errors: ErrCollector<'a>,
+    scope: &'a mut HashMapStack<Name, (SourceSpan, Value)>,
+    span: SourceSpan,
+}
+
+impl<'a, 'b> LowerContext<'a, 'b> {
+    pub fn builder(&mut self) -> &mut FunctionBuilder<'b> {
+        self.builder
+    }
+
+    /// Span of the whole operation.
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+
+    /// Reports the operation as malformed.
+    pub fn error(&mut self, reason: &'static str) {
+        self.errors.error(LowerError::MalformedOperation {
+            span: self.span,
+            reason,
+        });
+    }
+
+    /// Lowers a single value from the given tokens.
+    pub fn value(&mut self, tokens: &[DynToken]) -> Result<Value, ()> {
+        match tokens_to_value(tokens) {
+            Some(value) => lower_value(self.errors, self.builder, self.scope, &value),
+            None => {
+                self.error("expected value");
+                Err(())
+            }
+        }
+    }
+
+    /// Lowers a comma separated list of values from the given tokens.
+    pub fn values(&mut self, tokens: &[DynToken]) -> Result<Vec<Value>, ()> {
+        ast::split_commas(tokens)
+            .iter()
+            .map(|entry| self.value(entry))
+            .collect()
+    }
+
+    /// Lowers a constant term from the given tokens.
+    pub fn constant(&mut self, tokens: &[DynToken]) -> Result<Const, ()> {
+        let cons = tokens_to_value(tokens)
+            .and_then(|value| lower_constant(self.builder.cons_mut(), &value));
+        match cons {
+            Some(cons) => Ok(cons),
+            None => {
+                self.error("expected constant");
+                Err(())
+            }
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use libeir_diagnostics::SourceSpan;
 use libeir_intern::Ident;
 
-use crate::constant::Integer;
-use crate::{BasicType, BinOp, BinaryEntrySpecifier};
+use crate::constant::{FloatTerm, Integer};
+use crate::{BasicType, BinOp, BinaryEntrySpecifier, LogicOp, MapPutUpdate};
 
 mod lower;
-pub use lower::{LowerError, LowerMap};
+pub use lower::{LowerContext, LowerError, LowerMap};
+
+mod dyn_token;
+pub use dyn_token::{binary_specifier, split_commas, tokens_to_value, DynToken};
 
 //mod raise;
 
@@ -19,6 +22,22 @@ pub struct Module {
 #[derive(Debug, PartialEq, Eq)]
 pub enum ModuleItem {
     Function(Function),
+    Attribute(Attribute),
+    OnLoad(OnLoad),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Attribute {
+    pub span: SourceSpan,
+    pub name: Ident,
+    pub value: Value,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OnLoad {
+    pub span: SourceSpan,
+    pub name: Ident,
+    pub arity: Integer,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -26,6 +45,7 @@ pub struct Function {
     pub span: SourceSpan,
     pub name: Ident,
     pub arity: Integer,
+    pub exported: bool,
     pub items: Vec<FunctionItem>,
 }
 
@@ -51,57 +71,27 @@ pub struct Assignment {
     pub rhs: Value,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum DynOpt {
-    Parens(Vec<DynOpt>, SourceSpan),
-    Value(Value),
-}
-
 #[derive(Debug, PartialEq, Eq)]
 pub enum Op {
-    Dyn(Ident, Vec<DynOpt>),
+    Dyn(DynOp),
     UnpackValueList(UnpackValueListOp),
     CallControlFlow(CallControlFlowOp),
     CallFunction(CallFunctionOp),
     IfBool(IfBoolOp),
     TraceCaptureRaw(TraceCaptureRawOp),
+    TraceConstruct(TraceConstructOp),
     Match(MatchOp),
-    Case(CaseOp),
+    MapPut(MapPutOp),
     Unreachable,
 }
 
+/// A dialect operation, `@name <tokens..>`. The tokens are interpreted by
+/// the `OpParser` registered for the operation in the dialect.
 #[derive(Debug, PartialEq, Eq)]
-pub struct CaseOp {
-    pub span: SourceSpan,
-    pub value: Value,
-    pub entries: Vec<CaseEntry>,
-    pub no_match: Option<Value>,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct CaseEntry {
+pub struct DynOp {
     pub span: SourceSpan,
-    pub patterns: Vec<CasePattern>,
-    pub args: Vec<Ident>,
-    pub guard: Value,
-    pub target: Value,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum CasePattern {
-    Value(Value),
-    Binding {
-        name: Ident,
-        pattern: Box<CasePattern>,
-    },
-    ListCell {
-        head: Box<CasePattern>,
-        tail: Box<CasePattern>,
-    },
-    Tuple {
-        elements: Vec<CasePattern>,
-    },
-    Wildcard,
+    pub name: Ident,
+    pub tokens: Vec<DynToken>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -127,6 +117,21 @@ pub enum MatchKind {
     Wildcard,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct MapPutOp {
+    pub span: SourceSpan,
+    pub value: Value,
+    pub entries: Vec<MapPutEntry>,
+    pub ok: Value,
+    pub fail: Value,
+}
+#[derive(Debug, PartialEq, Eq)]
+pub struct MapPutEntry {
+    pub key: Value,
+    pub value: Value,
+    pub action: MapPutUpdate,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct UnpackValueListOp {
     pub span: SourceSpan,
@@ -165,6 +170,13 @@ pub struct TraceCaptureRawOp {
     pub then: Value,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceConstructOp {
+    pub span: SourceSpan,
+    pub then: Value,
+    pub trace: Value,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Value {
     // Atomics
@@ -172,14 +184,18 @@ pub enum Value {
     Block(Ident),
     Atom(Ident),
     Integer(Integer),
+    Float(FloatTerm),
+    Binary(Vec<u8>),
     Nil,
 
     // Composites
     ValueList(Vec<Value>),
     Tuple(Vec<Value>),
     List(Vec<Value>, Option<Box<Value>>),
+    Map(Vec<(Value, Value)>),
     CaptureFunction(Box<Value>, Box<Value>, Box<Value>),
     BinOp(Box<Value>, BinOp, Box<Value>),
+    LogicOp(LogicOp, Vec<Value>),
 }
 impl Value {
     pub fn value(&self) -> Option<Ident> {
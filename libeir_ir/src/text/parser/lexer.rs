@@ -85,6 +85,8 @@ pub enum Token {
     Integer(Integer),
     /// 12.2
     Float(Ident),
+    /// b"abc"
+    Binary(Vec<u8>),
 
     // Symbols
     ParenOpen,
@@ -97,6 +99,7 @@ pub enum Token {
     Greater,
     MapOpen,
     Colon,
+    ColonEquals,
     Semicolon,
     Comma,
     Question,
@@ -104,6 +107,11 @@ pub enum Token {
     Percent,
     Equals,
     EqualsEquals,
+    NotEquals,
+    LessEquals,
+    GreaterEquals,
+    ExactEquals,
+    ExactNotEquals,
     FatArrow,
     Underscore,
     Pipe,
@@ -117,12 +125,15 @@ pub enum Token {
     Tuple,
     Arity,
     TraceCaptureRaw,
+    TraceConstruct,
     Value,
     Match,
     Type,
-    Case,
-    Guard,
+    MapPut,
     Except,
+    Export,
+    Attribute,
+    OnLoad,
 }
 
 lazy_static! {
@@ -136,12 +147,15 @@ lazy_static! {
         map.insert(Symbol::intern("unpack"), Token::UnpackValueList);
         map.insert(Symbol::intern("arity"), Token::Arity);
         map.insert(Symbol::intern("trace_capture_raw"), Token::TraceCaptureRaw);
+        map.insert(Symbol::intern("trace_construct"), Token::TraceConstruct);
         map.insert(Symbol::intern("value"), Token::Value);
         map.insert(Symbol::intern("match"), Token::Match);
         map.insert(Symbol::intern("type"), Token::Type);
-        map.insert(Symbol::intern("case"), Token::Case);
+        map.insert(Symbol::intern("map_put"), Token::MapPut);
         map.insert(Symbol::intern("except"), Token::Except);
-        map.insert(Symbol::intern("guard"), Token::Guard);
+        map.insert(Symbol::intern("export"), Token::Export);
+        map.insert(Symbol::intern("attribute"), Token::Attribute);
+        map.insert(Symbol::intern("on_load"), Token::OnLoad);
         map
     };
 }
@@ -334,20 +348,32 @@ where
             '[' => pop!(self, Token::SquareOpen),
             ']' => pop!(self, Token::SquareClose),
             '<' => pop!(self, Token::Less),
-            '>' => pop!(self, Token::Greater),
+            '>' => match self.peek() {
+                '=' => pop2!(self, Token::GreaterEquals),
+                _ => pop!(self, Token::Greater),
+            },
             '%' => match self.peek() {
                 '{' => pop2!(self, Token::MapOpen),
                 c if c.is_alphanumeric() => self.lex_variable(),
                 _ => unimplemented!(),
             },
             ',' => pop!(self, Token::Comma),
-            ':' => pop!(self, Token::Colon),
+            ':' => match self.peek() {
+                '=' => pop2!(self, Token::ColonEquals),
+                _ => pop!(self, Token::Colon),
+            },
             ';' => pop!(self, Token::Semicolon),
-            '/' => pop!(self, Token::ForwardSlash),
+            '/' => match self.peek() {
+                '=' => pop2!(self, Token::NotEquals),
+                _ => pop!(self, Token::ForwardSlash),
+            },
             '|' => pop!(self, Token::Pipe),
             '=' => match self.peek() {
                 '>' => pop2!(self, Token::FatArrow),
                 '=' => pop2!(self, Token::EqualsEquals),
+                '<' => pop2!(self, Token::LessEquals),
+                ':' if self.peek_next() == '=' => pop3!(self, Token::ExactEquals),
+                '/' if self.peek_next() == '=' => pop3!(self, Token::ExactNotEquals),
                 _ => pop!(self, Token::Equals),
             },
             '_' => pop!(self, Token::Underscore),
@@ -356,8 +382,13 @@ where
                 '\'' => self.lex_atom(),
                 _ => self.lex_ident(),
             },
+            c if c == 'b' => match self.peek() {
+                '"' => self.lex_binary(),
+                _ => self.lex_ident(),
+            },
             c if c.is_alphabetic() => self.lex_ident(),
-            c if c.is_numeric() => self.lex_integer(),
+            '-' if self.peek().is_numeric() => self.lex_number(),
+            c if c.is_numeric() => self.lex_number(),
             c => unimplemented!("{}", c),
         }
     }
@@ -403,25 +434,50 @@ where
         Token::Variable(ident)
     }
 
-    fn lex_integer(&mut self) -> Token {
+    fn lex_number(&mut self) -> Token {
         let c = self.pop();
-        debug_assert!(c.is_numeric());
+        debug_assert!(c == '-' || c.is_numeric());
 
-        loop {
-            match self.read() {
-                c if c.is_numeric() => self.skip(),
-                _ => break,
+        self.skip_digits();
+
+        let mut is_float = false;
+        if self.read() == '.' && self.peek().is_numeric() {
+            is_float = true;
+            self.skip();
+            self.skip_digits();
+        }
+        if self.read() == 'e' || self.read() == 'E' {
+            let has_exponent = match self.peek() {
+                '+' | '-' => self.peek_next().is_numeric(),
+                c => c.is_numeric(),
+            };
+            if has_exponent {
+                is_float = true;
+                self.skip();
+                self.skip();
+                self.skip_digits();
             }
         }
 
-        let int = self.slice().parse().unwrap();
-        Token::Integer(int)
+        if is_float {
+            Token::Float(self.ident())
+        } else {
+            let int = self.slice().parse().unwrap();
+            Token::Integer(int)
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while self.read().is_numeric() {
+            self.skip();
+        }
     }
 
     fn lex_atom(&mut self) -> Token {
-        debug_assert!(self.pop() == 'a');
-        debug_assert!(self.pop() == '\'');
+        // a'
+        pop2!(self);
 
+        let mut buf = String::new();
         loop {
             match self.read() {
                 '\'' => {
@@ -430,21 +486,79 @@ where
                 }
                 '\\' => {
                     self.skip();
-                    self.skip();
+                    match self.pop() {
+                        'n' => buf.push('\n'),
+                        'r' => buf.push('\r'),
+                        't' => buf.push('\t'),
+                        'x' => buf.push(self.lex_hex_escape()),
+                        c => buf.push(c),
+                    }
                 }
+                '\0' => break,
                 c => {
                     self.skip();
+                    buf.push(c);
                 }
             }
         }
 
-        let slice = self.slice();
-        let inner_slice = &self.slice()[2..(slice.len() - 1)];
-
-        let ident = Ident::new(Symbol::intern(inner_slice), self.span());
+        let ident = Ident::new(Symbol::intern(&buf), self.span());
 
         Token::Atom(ident)
     }
+
+    /// Lexes the `{..}` part of a `\x{..}` escape
+    fn lex_hex_escape(&mut self) -> char {
+        let mut num = 0;
+        if self.read() == '{' {
+            self.skip();
+            while let Some(digit) = self.read().to_digit(16) {
+                self.skip();
+                num = num * 16 + digit;
+            }
+            if self.read() == '}' {
+                self.skip();
+            }
+        }
+        std::char::from_u32(num).unwrap_or('\u{fffd}')
+    }
+
+    fn lex_binary(&mut self) -> Token {
+        // b"
+        pop2!(self);
+
+        let mut buf = Vec::new();
+        loop {
+            match self.read() {
+                '"' => {
+                    self.skip();
+                    break;
+                }
+                '\\' => {
+                    self.skip();
+                    match self.pop() {
+                        'x' => {
+                            let high = self.pop().to_digit(16).unwrap_or(0);
+                            let low = self.pop().to_digit(16).unwrap_or(0);
+                            buf.push((high * 16 + low) as u8);
+                        }
+                        c => {
+                            let mut char_buf = [0; 4];
+                            buf.extend(c.encode_utf8(&mut char_buf).as_bytes());
+                        }
+                    }
+                }
+                '\0' => break,
+                c => {
+                    self.skip();
+                    let mut char_buf = [0; 4];
+                    buf.extend(c.encode_utf8(&mut char_buf).as_bytes());
+                }
+            }
+        }
+
+        Token::Binary(buf)
+    }
 }
 
 impl<S> Iterator for Lexer<S>
@@ -309,14 +309,16 @@ mod test {
 
     use std::sync::Arc;
 
-    use super::function_unwrap;
+    use super::{function_unwrap, module_unwrap};
     use super::{NamedFunction, Parser};
     use crate::text::ast;
 
     use libeir_diagnostics::{CodeMap, SourceSpan};
-    use libeir_intern::Ident;
+    use libeir_intern::{Ident, Symbol};
     use libeir_util_parse::Errors;
 
+    use crate::AtomTerm;
+
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -425,4 +427,50 @@ a'foo':a'add_one'/1 {
 ",
         );
     }
+
+    #[test]
+    fn module_attributes_roundtrip() {
+        let module = module_unwrap(
+            "
+a'woo' {
+    attribute a'vsn' = [1, 2];
+    attribute a'behaviour' = a'gen_server';
+    on_load a'init'/0;
+
+    export a'foo'/1 {
+        entry(%return, %throw, %num):
+            %return(%num);
+    }
+    a'init'/0 {
+        entry(%return, %throw):
+            %return(a'ok');
+    }
+}
+",
+        );
+
+        let check = |module: &crate::Module| {
+            let foo = module.name_arity_index(Symbol::intern("foo"), 1).unwrap();
+            let init = module.name_arity_index(Symbol::intern("init"), 0).unwrap();
+            assert!(module[foo].is_exported());
+            assert!(!module[init].is_exported());
+            assert_eq!(module.on_load(), Some(init));
+
+            let attrs: Vec<_> = module.attribute_iter().collect();
+            assert_eq!(attrs.len(), 2);
+            assert_eq!(attrs[0].name.name, Symbol::intern("vsn"));
+            assert_eq!(attrs[1].name.name, Symbol::intern("behaviour"));
+
+            let behaviour = module.get_attribute(Symbol::intern("behaviour")).unwrap();
+            assert_eq!(
+                module.cons().get(AtomTerm(Symbol::intern("gen_server"))),
+                Some(behaviour)
+            );
+        };
+        check(&module);
+
+        let text = module.to_text_standard();
+        let reparsed = module_unwrap(&text);
+        check(&reparsed);
+    }
 }
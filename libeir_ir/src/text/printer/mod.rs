@@ -1,5 +1,6 @@
 #![allow(unused)]
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::Write;
@@ -9,13 +10,16 @@ use cranelift_entity::EntityRef;
 use petgraph::visit::Dfs;
 use pretty::{Arena, DocAllocator, RefDoc};
 
+use crate::algo::types::ValueTypes;
 use crate::graph::EntityVisitMap;
 use crate::{
-    BinOp, Block, CallKind, Const, Function, LogicOp, Module, OpKind, PrimOpKind, Value, ValueKind,
+    AtomTerm, BinOp, Block, CallKind, Const, Function, FunctionIdent, LogicOp, Module, OpKind,
+    PrimOpKind, Value, ValueKind,
 };
 
 mod constant;
 mod operation;
+pub(crate) use self::operation::binary_specifier_to_doc;
 
 type DynError = Box<dyn Error>;
 
@@ -78,6 +82,22 @@ impl Default for StandardFormatConfig {
     }
 }
 
+pub type TypeAnnotatedFormatConfig = FormatConfig<
+    DfsBlockIteratorConfig,
+    TypeAnnotatingValueFormatter,
+    ReferencePrimopBlockValueLayout,
+>;
+impl Default for TypeAnnotatedFormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            width: 80,
+            block_iterator_config: DfsBlockIteratorConfig,
+            value_formatter: TypeAnnotatingValueFormatter::default(),
+            block_value_layout: ReferencePrimopBlockValueLayout::default(),
+        }
+    }
+}
+
 pub struct FormatState<'a> {
     pub function: &'a Function,
     pub nesting: usize,
@@ -136,6 +156,32 @@ impl ValueFormatter for StandardValueFormatter {
     }
 }
 
+/// This value formatter annotates every value declaration with the type
+/// inferred by `Function::value_types`. The output can not be parsed back.
+pub struct TypeAnnotatingValueFormatter {
+    types: RefCell<Option<(FunctionIdent, ValueTypes)>>,
+}
+impl Default for TypeAnnotatingValueFormatter {
+    fn default() -> Self {
+        TypeAnnotatingValueFormatter {
+            types: RefCell::new(None),
+        }
+    }
+}
+impl ValueFormatter for TypeAnnotatingValueFormatter {
+    fn value(&self, out: &mut String, fun: &Function, site: ValueSite, value: Value) {
+        StandardValueFormatter.value(out, fun, site, value);
+        if site == ValueSite::Decl {
+            let mut types = self.types.borrow_mut();
+            if types.as_ref().map(|(ident, _)| ident != fun.ident()) != Some(false) {
+                *types = Some((*fun.ident(), fun.value_types()));
+            }
+            let (_, types) = types.as_ref().unwrap();
+            write!(out, ": {}", types.value_type(value)).unwrap();
+        }
+    }
+}
+
 pub trait BlockValueLayout {
     /// Lays out the root scope for the module. This is called once
     /// at the beginning of processing a module.
@@ -376,32 +422,45 @@ where
                             .append(self.value_use(config, state, reads[1], Some(value)))
                             .append(arena.text("]"))
                     }
-                    PrimOpKind::BinOp(BinOp::Equal) => {
+                    PrimOpKind::BinOp(op) => {
                         assert!(reads.len() == 2);
                         arena
                             .nil()
                             .append(self.value_use(config, state, reads[0], Some(value)))
                             .append(arena.space())
-                            .append(arena.text("=="))
+                            .append(arena.text(binop_to_text(*op)))
                             .append(arena.space())
                             .append(self.value_use(config, state, reads[1], Some(value)))
                     }
-                    PrimOpKind::LogicOp(LogicOp::And) => arena
-                        .intersperse(
-                            reads
-                                .iter()
-                                .map(|r| self.value_use(config, state, *r, Some(value))),
-                            arena.text(",").append(arena.space()),
-                        )
-                        .enclose("and[", "]"),
-                    PrimOpKind::LogicOp(LogicOp::Or) => arena
+                    PrimOpKind::LogicOp(op) => {
+                        let name = match op {
+                            LogicOp::And => "and[",
+                            LogicOp::Or => "or[",
+                            LogicOp::Eq => "eq[",
+                        };
+                        arena
+                            .intersperse(
+                                reads
+                                    .iter()
+                                    .map(|r| self.value_use(config, state, *r, Some(value))),
+                                arena.text(",").append(arena.space()),
+                            )
+                            .enclose(name, "]")
+                    }
+                    PrimOpKind::Map => arena
                         .intersperse(
-                            reads
-                                .iter()
-                                .map(|r| self.value_use(config, state, *r, Some(value))),
+                            reads.chunks(2).map(|kv| {
+                                arena
+                                    .nil()
+                                    .append(self.value_use(config, state, kv[0], Some(value)))
+                                    .append(arena.space())
+                                    .append(arena.text("=>"))
+                                    .append(arena.space())
+                                    .append(self.value_use(config, state, kv[1], Some(value)))
+                            }),
                             arena.text(",").append(arena.space()),
                         )
-                        .enclose("or[", "]"),
+                        .enclose(arena.text("%{"), arena.text("}")),
                     _ => unimplemented!("{:?}", prim_kind),
                 }
             }
@@ -514,6 +573,19 @@ where
     }
 }
 
+fn binop_to_text(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Equal => "==",
+        BinOp::NotEqual => "/=",
+        BinOp::LessEqual => "=<",
+        BinOp::Less => "<",
+        BinOp::GreaterEqual => ">=",
+        BinOp::Greater => ">",
+        BinOp::ExactEqual => "=:=",
+        BinOp::ExactNotEqual => "=/=",
+    }
+}
+
 fn format_function_body_state<B, V, L, S>(
     config: &mut FormatConfig<B, V, L>,
     state: &mut FormatState,
@@ -591,26 +663,56 @@ where
     L: BlockValueLayout,
     S: BlockFormatSink,
 {
-    sink.write_str(&format!("{} {{\n", module.name().name.as_str().get()));
+    sink.write_str(&format!("{} {{\n", AtomTerm(module.name().name)))?;
+
+    let arena = Arena::new();
+    let mut buf = String::new();
+    for attribute in module.attribute_iter() {
+        let doc = self::constant::constant_to_doc(&arena, module.cons(), attribute.value);
+        buf.clear();
+        doc.render_fmt(config.width, &mut buf).unwrap();
+        sink.write_str(&format!(
+            "  attribute {} = {};\n",
+            AtomTerm(attribute.name.name),
+            buf
+        ))?;
+    }
+    if let Some(on_load) = module.on_load() {
+        let ident = module[on_load].function().ident();
+        sink.write_str(&format!(
+            "  on_load {}/{};\n",
+            AtomTerm(ident.name.name),
+            ident.arity
+        ))?;
+    }
+    if module.attribute_iter().next().is_some() || module.on_load().is_some() {
+        sink.write_str("\n")?;
+    }
 
     let num_functions = module.function_iter().count();
     for (i, fun) in module.function_iter().enumerate() {
         let function = fun.function();
         let ident = function.ident();
-        sink.write_str(&format!("  {}/{} {{\n", &ident.name, ident.arity));
+        let export = if fun.is_exported() { "export " } else { "" };
+        sink.write_str(&format!(
+            "  {}{}/{} {{\n",
+            export,
+            AtomTerm(ident.name.name),
+            ident.arity
+        ))?;
         let mut state = FormatState {
             function,
             nesting: 2,
         };
         format_function_body_state(config, &mut state, sink)?;
         if i + 1 < num_functions {
-            sink.write_str("  }\n\n");
+            sink.write_str("  }\n\n")?;
         } else {
-            sink.write_str("  }\n");
+            sink.write_str("  }\n")?;
         }
     }
 
-    sink.write_str("}\n");
+    sink.write_str("}\n")?;
 
     Ok(())
 }
@@ -679,6 +781,7 @@ impl Module {
 
 #[cfg(test)]
 mod tests {
+    use super::TypeAnnotatedFormatConfig;
     use super::{format_function_body, FormatConfig, StandardFormatConfig, StringSink};
 
     #[test]
@@ -698,4 +801,24 @@ a'woo':a'hoo'/1 {
         let text = ir.to_text(&mut StandardFormatConfig::default());
         println!("{}", text);
     }
+
+    #[test]
+    fn annotate_types() {
+        let ir = crate::parse_function_unwrap(
+            "
+a'woo':a'hoo'/1 {
+    entry(%ret, %thr, %a):
+        %f1 = a'erlang':a'+'/2;
+        %f1(%a, 2) => b2 except %thr;
+    b2(%b):
+        %ret({%b});
+}
+",
+        );
+        let text = ir.to_text(&mut TypeAnnotatedFormatConfig::default());
+        // Block arguments are printed by index, not by name
+        assert!(text.contains(": any, %"));
+        assert!(text.contains(": number):"));
+        assert!(text.contains(": 1-tuple = {"));
+    }
 }
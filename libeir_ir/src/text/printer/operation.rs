@@ -2,8 +2,11 @@ use pretty::{DocAllocator, RefDoc};
 
 use crate::binary::{BinaryEntrySpecifier, Endianness};
 use crate::traits::FormatOpCtx;
-use crate::{BasicType, Block, CallKind, DynValue, MatchKind, OpKind, Value};
+use crate::{
+    BasicType, Block, CallKind, DynValue, Function, MapPutUpdate, MatchKind, OpKind, Value,
+};
 
+use super::constant::constant_to_doc;
 use super::{
     get_value_list, BlockIteratorConfig, BlockValueLayout, FormatConfig, FormatState,
     FunctionFormatData, ValueFormatter,
@@ -30,14 +33,21 @@ where
         self.format_data.arena
     }
 
+    fn function(&self) -> &Function {
+        self.state.function
+    }
+
     fn value_use_to_doc(&mut self, value: DynValue) -> RefDoc<'doc, ()> {
+        if let DynValue::Const(cons) = value {
+            return constant_to_doc(self.format_data.arena, self.state.function.cons(), cons);
+        }
         let val = self.state.function.value_get(value).unwrap();
         self.format_data
             .value_use(self.config, self.state, val, None)
     }
 }
 
-fn binary_specifier_to_doc<'a>(
+pub(crate) fn binary_specifier_to_doc<'a>(
     arena: &'a pretty::Arena<'a>,
     spec: &BinaryEntrySpecifier,
 ) -> RefDoc<'a, ()> {
@@ -93,11 +103,11 @@ fn binary_specifier_to_doc<'a>(
         BinaryEntrySpecifier::Utf8 => arena.text("utf8").append(arena.nil().parens()).into_doc(),
         BinaryEntrySpecifier::Utf16 { endianness } => arena
             .text("utf16")
-            .append(f_endianness(endianness))
+            .append(f_endianness(endianness).parens())
             .into_doc(),
         BinaryEntrySpecifier::Utf32 { endianness } => arena
             .text("utf32")
-            .append(f_endianness(endianness))
+            .append(f_endianness(endianness).parens())
             .into_doc(),
     }
 }
@@ -127,7 +137,6 @@ where
         let op_doc = match op {
             OpKind::Match { branches } => {
                 let dests = reads[0];
-                let num_dests = state.function.value_list_length(dests);
                 let num_branches = branches.len();
                 let mut branches_formatted = Vec::with_capacity(num_branches);
                 for (i, kind) in branches.iter().enumerate() {
@@ -139,126 +148,45 @@ where
                     for n in 0..num_args {
                         args.push(state.function.value_list_get_n(args_vl, n).unwrap());
                     }
-                    let formatted = match kind {
-                        MatchKind::Value => {
-                            let val = self.value_use(config, state, args[0], None);
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter()
-                                        .skip(1)
-                                        .map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(arena.text("value"))
-                                .append(arena.space())
-                                .append(val)
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
-                        }
-                        MatchKind::Type(ty) => {
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter().map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(arena.text("is_type"))
-                                .append(arena.space())
-                                .append(arena.text(type_to_text(ty)))
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
-                        }
+                    let kind_doc = match kind {
+                        MatchKind::Value => arena
+                            .text("value")
+                            .append(arena.space())
+                            .append(self.value_use(config, state, args[0], None)),
+                        MatchKind::Type(ty) => arena
+                            .text("type")
+                            .append(arena.space())
+                            .append(arena.text(type_to_text(ty))),
                         MatchKind::Binary(ref spec) => {
-                            let doc = binary_specifier_to_doc(arena, spec);
-                            arena.nil().append(doc)
-                        }
-                        MatchKind::Tuple(arity) => {
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter().map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(arena.text("{}"))
-                                .append(arena.space())
-                                .append(arena.text(format!("arity {}", arity)))
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
-                        }
-                        MatchKind::ListCell => {
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter().map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(arena.text("[]"))
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
-                        }
-                        MatchKind::MapItem => {
-                            let val = self.value_use(config, state, args[0], None);
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter()
-                                        .skip(1)
-                                        .map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(val)
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
-                        }
-                        MatchKind::Wildcard => {
-                            let block_args = arena
-                                .intersperse(
-                                    args.iter().map(|v| self.value_use(config, state, *v, None)),
-                                    arena.text(",").append(arena.softline()),
-                                )
-                                .nest(1)
-                                .parens();
-                            let body = arena.nil().append(block_val).append(block_args);
-                            arena
-                                .nil()
-                                .append(arena.text("_"))
-                                .append(arena.space())
-                                .append(arena.text("=>"))
-                                .append(arena.space())
-                                .append(arena.nil().append(body))
+                            let doc = arena.nil().append(binary_specifier_to_doc(arena, spec));
+                            if let Some(size) = args.get(0) {
+                                doc.append(arena.space())
+                                    .append(self.value_use(config, state, *size, None))
+                            } else {
+                                doc
+                            }
                         }
+                        MatchKind::Tuple(arity) => arena
+                            .text("{}")
+                            .append(arena.space())
+                            .append(arena.text(format!("arity {}", arity))),
+                        MatchKind::ListCell => arena.text("[]"),
+                        MatchKind::MapItem => arena
+                            .text("%{")
+                            .append(arena.space())
+                            .append(self.value_use(config, state, args[0], None))
+                            .append(arena.space())
+                            .append(arena.text("}")),
+                        MatchKind::Wildcard => arena.text("_"),
                     };
+                    let formatted = arena
+                        .nil()
+                        .append(kind_doc)
+                        .append(arena.space())
+                        .append(arena.text("=>"))
+                        .append(arena.space())
+                        .append(block_val)
+                        .append(arena.text(";"));
                     branches_formatted.push(formatted.indent(2));
                 }
 
@@ -328,6 +256,18 @@ where
                     .append(arena.space())
                     .append(arg)
             }
+            OpKind::TraceConstruct => {
+                assert!(reads.len() == 2);
+                let then = self.value_use(config, state, reads[0], None);
+                let trace = self.value_use(config, state, reads[1], None);
+                arena
+                    .nil()
+                    .append(arena.text("trace_construct"))
+                    .append(arena.space())
+                    .append(then)
+                    .append(arena.space())
+                    .append(trace)
+            }
             OpKind::UnpackValueList(n) => {
                 assert!(reads.len() == 2);
                 let block = self.value_use(config, state, reads[0], None);
@@ -369,6 +309,40 @@ where
                     .append(self.value_use(config, state, reads[2], None)),
                 _ => panic!(),
             },
+            OpKind::MapPut { action } => {
+                let entries = action.iter().enumerate().map(|(n, action)| {
+                    let op = match action {
+                        MapPutUpdate::Put => "=>",
+                        MapPutUpdate::Update => ":=",
+                    };
+                    arena
+                        .nil()
+                        .append(self.value_use(config, state, reads[3 + n * 2], None))
+                        .append(arena.space())
+                        .append(arena.text(op))
+                        .append(arena.space())
+                        .append(self.value_use(config, state, reads[4 + n * 2], None))
+                });
+                let entries = arena
+                    .intersperse(entries, arena.text(",").append(arena.softline()))
+                    .nest(1)
+                    .enclose(arena.text("%{"), arena.text("}"));
+                arena
+                    .nil()
+                    .append(arena.text("map_put"))
+                    .append(arena.space())
+                    .append(self.value_use(config, state, reads[2], None))
+                    .append(arena.space())
+                    .append(entries)
+                    .append(arena.space())
+                    .append(arena.text("=>"))
+                    .append(arena.space())
+                    .append(self.value_use(config, state, reads[0], None))
+                    .append(arena.space())
+                    .append(arena.text("except"))
+                    .append(arena.space())
+                    .append(self.value_use(config, state, reads[1], None))
+            }
             OpKind::Unreachable => arena.text("unreachable"),
             OpKind::Dyn(op) => {
                 if let Some(printer) = state.function.dialect().get_op_printer(&**op) {
@@ -390,7 +364,10 @@ where
                         )
                         .nest(1)
                         .parens();
-                    arena.as_string(op.name()).append(call_args)
+                    arena
+                        .text("@")
+                        .append(arena.as_string(op.name()))
+                        .append(call_args)
                 }
             }
             _ => {
@@ -409,7 +386,7 @@ fn type_to_text(ty: &BasicType) -> String {
         BasicType::ListCell => "cons".to_owned(),
         BasicType::Nil => "nil".to_owned(),
         BasicType::Tuple(arity) => format!("tuple({})", arity),
-        BasicType::Map => "map".to_owned(),
+        BasicType::Map => "%{}".to_owned(),
         BasicType::Number => "number".to_owned(),
         BasicType::Float => "float".to_owned(),
         BasicType::Integer => "integer".to_owned(),
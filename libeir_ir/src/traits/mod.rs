@@ -6,3 +6,8 @@ pub use printer::{FormatOpCtx, OpPrinter};
 
 mod parser;
 pub use parser::OpParser;
+
+#[cfg(feature = "binary_serialization")]
+mod serialize;
+#[cfg(feature = "binary_serialization")]
+pub use serialize::OpSerialize;
@@ -1 +1,15 @@
-pub trait OpParser: Send + Sync {}
+use crate::text::ast::{DynToken, LowerContext};
+use crate::Block;
+
+/// When an operation implements this trait, it can be read from the Eir
+/// text format.
+///
+/// Dialect operations are written as `@name tokens..;`. The grammar only
+/// makes sure the tokens are balanced, the implementation is responsible
+/// for reading them and building the operation into `block`. This should
+/// accept the format produced by the `OpPrinter` of the operation.
+pub trait OpParser: Send + Sync {
+    /// Builds the operation into `block` from the tokens following the
+    /// operation name. Errors are reported through `ctx`.
+    fn parse(&self, ctx: &mut LowerContext, block: Block, tokens: &[DynToken]) -> Result<(), ()>;
+}
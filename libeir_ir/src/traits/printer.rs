@@ -1,9 +1,10 @@
-use crate::{Block, DynValue};
+use crate::{Block, DynValue, Function};
 use meta_table::impl_cast_from;
 use pretty::RefDoc;
 
 pub trait FormatOpCtx<'doc> {
     fn arena(&self) -> &'doc pretty::Arena<'doc>;
+    fn function(&self) -> &Function;
     fn value_use_to_doc(&mut self, value: DynValue) -> RefDoc<'doc, ()>;
 }
 
@@ -0,0 +1,18 @@
+use crate::operation::DynOp;
+use meta_table::impl_cast_from;
+
+/// When an operation implements this trait, it can be written to and read
+/// from the binary module format.
+///
+/// Only the inner state of the operation itself is encoded here, the reads
+/// of the block are handled by the function serializer.
+pub trait OpSerialize: Send + Sync {
+    /// Encodes the inner state of this operation.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Decodes an operation from data produced by `serialize`. This is
+    /// called on the instance registered with the dialect, and should
+    /// return `None` if the data is malformed.
+    fn deserialize(&self, data: &[u8]) -> Option<DynOp>;
+}
+impl_cast_from!(OpSerialize);
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use petgraph::algo::dominators::{simple_fast, Dominators};
+
+use libeir_ir::{Block, Function, FunctionIdent, FunctionTree, LiveValues, ValueTypes};
+
+/// Cached analyses for a single function.
+///
+/// Analyses are calculated lazily on first request, and stay cached until
+/// the function is invalidated. A pass that modifies the function and then
+/// requests an analysis again needs to call `invalidate` first. The
+/// `PassManager` invalidates the cache whenever a pass reports that it
+/// changed the function.
+#[derive(Default)]
+pub struct FunctionAnalysis {
+    live: Option<LiveValues>,
+    func_tree: Option<FunctionTree>,
+    dominators: Option<Dominators<Block>>,
+    value_types: Option<ValueTypes>,
+}
+
+impl FunctionAnalysis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn live_values(&mut self, fun: &Function) -> &LiveValues {
+        if self.live.is_none() {
+            self.live = Some(fun.live_values());
+        }
+        self.live.as_ref().unwrap()
+    }
+
+    /// Function tree with continuations resolved.
+    pub fn func_tree(&mut self, fun: &Function) -> &FunctionTree {
+        if self.func_tree.is_none() {
+            let tree = fun.func_tree(self.live_values(fun), true);
+            self.func_tree = Some(tree);
+        }
+        self.func_tree.as_ref().unwrap()
+    }
+
+    /// Dominator tree of the block graph, rooted at the entry block.
+    pub fn dominators(&mut self, fun: &Function) -> &Dominators<Block> {
+        if self.dominators.is_none() {
+            let graph = fun.block_graph();
+            self.dominators = Some(simple_fast(&graph, fun.block_entry()));
+        }
+        self.dominators.as_ref().unwrap()
+    }
+
+    /// Pessimistic types of all values in the function.
+    pub fn value_types(&mut self, fun: &Function) -> &ValueTypes {
+        if self.value_types.is_none() {
+            self.value_types = Some(fun.value_types());
+        }
+        self.value_types.as_ref().unwrap()
+    }
+
+    /// Drops all cached analyses.
+    pub fn invalidate(&mut self) {
+        self.live = None;
+        self.func_tree = None;
+        self.dominators = None;
+        self.value_types = None;
+    }
+}
+
+/// Cached analyses for all functions in a module.
+#[derive(Default)]
+pub struct AnalysisManager {
+    functions: HashMap<FunctionIdent, FunctionAnalysis>,
+}
+
+impl AnalysisManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn function(&mut self, ident: &FunctionIdent) -> &mut FunctionAnalysis {
+        self.functions.entry(*ident).or_default()
+    }
+
+    /// Invalidates the analyses of a single function. Module passes need to
+    /// call this for every function they modify.
+    pub fn invalidate(&mut self, ident: &FunctionIdent) {
+        self.functions.remove(ident);
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.functions.clear();
+    }
+}
@@ -57,6 +57,9 @@ pub enum NodeKind {
     /// A map is matched by an interned value.
     /// This matches only a single k=>v mapping in a map.
     MapItem(ValueOrConst),
+    /// Matches the value against two patterns. This does not test
+    /// anything by itself, it expands to the value in two columns.
+    And,
 
     // Meta
     ValueList,
@@ -74,6 +77,7 @@ impl NodeKind {
             NodeKind::ValueList => panic!(),
             NodeKind::Wildcard => 0,
             NodeKind::Binary { .. } => 2,
+            NodeKind::And => 2,
         }
     }
 }
@@ -188,6 +192,12 @@ impl<'a> PatternProvider for ErlangPatternProvider<'a> {
             return true;
         }
 
+        // Any pattern `P` is equivalent to `P = _`, so specializing on
+        // `And` includes every clause.
+        if kind == NodeKind::And && node_kind != NodeKind::Wildcard {
+            return true;
+        }
+
         //match (kind, node_kind) {
         //    (NodeKind::Value(l), NodeKind::Value(r)) =>
         //        if l
@@ -196,6 +206,10 @@ impl<'a> PatternProvider for ErlangPatternProvider<'a> {
         false
     }
 
+    fn kind_includes_all(&self, kind: NodeKind) -> bool {
+        kind == NodeKind::And
+    }
+
     fn get_kind(&self, key: Node) -> NodeKind {
         self.nodes[key].kind
     }
@@ -217,6 +231,28 @@ impl<'a> PatternProvider for ErlangPatternProvider<'a> {
             };
         }
 
+        if kind == NodeKind::And {
+            let mut exp = ExpandedClauseNodes {
+                clauses: clause_nodes.len(),
+                variables: vec![self.vars.push(()), self.vars.push(())],
+                nodes: vec![],
+            };
+
+            for node_id in clause_nodes {
+                let node = &self.nodes[node_id];
+                if node.kind == NodeKind::And {
+                    let children = node.children.as_slice(&self.node_pool);
+                    assert!(children.len() == 2);
+                    exp.nodes.extend(children.iter().cloned());
+                } else {
+                    exp.nodes.push(node_id);
+                    exp.nodes.push(self.wildcard());
+                }
+            }
+
+            return exp;
+        }
+
         for node in clause_nodes.iter() {
             let node_kind = self.nodes[*node].kind;
             assert!(node_kind == kind);
@@ -455,6 +491,12 @@ fn pattern_node_to_provider(
 
             binary_entry
         }
+        PatternNodeKind::And { left, right } => {
+            let and = provider.add_child(parent, NodeKind::And, node);
+            pattern_node_to_provider(fun, pat, value_map, provider, *left, and, n + 1);
+            pattern_node_to_provider(fun, pat, value_map, provider, *right, and, n + 1);
+            and
+        }
     }
 }
 
@@ -167,6 +167,26 @@ fn lower_cfg_rec(
         CfgNodeKind::Root => unreachable!(),
         CfgNodeKind::Match(var) => {
             let match_val = ctx.get_var_value(var);
+
+            // An `And` specialization includes every clause, so when
+            // present it is the only edge. It does not test the value,
+            // both sides are simply bound to it.
+            if let Some(and_edge) = cfg
+                .graph
+                .edges(node)
+                .find(|e| e.weight().kind == Some(NodeKind::And))
+            {
+                debug_assert!(cfg.graph.edges(node).count() == 1);
+
+                let weight = and_edge.weight();
+                assert!(weight.variable_binds.len() == 2);
+                ctx.bind(weight.variable_binds[0], match_val);
+                ctx.bind(weight.variable_binds[1], match_val);
+
+                lower_cfg_rec(bump, b, pat, ctx, cfg, clauses, block, and_edge.target());
+                return;
+            }
+
             let span = b
                 .fun()
                 .value_locations(match_val)
@@ -18,7 +18,7 @@ mod lower_cfg;
 use self::lower_cfg::lower_cfg;
 use self::lower_cfg::DecisionTreeDestinations;
 
-use super::FunctionPass;
+use super::{FunctionAnalysis, FunctionPass};
 
 #[cfg(test)]
 mod tests;
@@ -39,16 +39,21 @@ impl FunctionPass for CompilePatternPass {
     fn name(&self) -> &str {
         "compile_pattern"
     }
-    fn run_function_pass(&mut self, b: &mut FunctionBuilder) {
-        self.compile_pattern(b);
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        _analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        self.compile_pattern(b)
     }
 }
 
 impl CompilePatternPass {
-    pub fn compile_pattern(&mut self, b: &mut FunctionBuilder) {
+    /// Returns `true` if any case operation was compiled.
+    pub fn compile_pattern(&mut self, b: &mut FunctionBuilder) -> bool {
         let mut bump = self.bump.take().unwrap();
 
-        {
+        let changed = {
             // Find all pattern matching constructs
             let case_blocks = {
                 let fun = b.fun();
@@ -165,10 +170,14 @@ impl CompilePatternPass {
                 b.block_clear(block);
                 b.op_call_flow(block, cfg_entry, &[match_val]);
             }
-        }
+
+            !case_blocks.is_empty()
+        };
 
         bump.reset();
         self.bump = Some(bump);
+
+        changed
     }
 }
 
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use libeir_intern::Symbol;
+
+use libeir_ir::{Block, Const, ConstKind, FunctionBuilder, Value};
+use libeir_ir::{CallKind, MatchKind, OpKind};
+use libeir_ir::{MangleTo, Mangler};
+
+use super::{FunctionAnalysis, FunctionPass};
+use crate::util::capture_function;
+
+mod term;
+
+#[cfg(test)]
+mod tests;
+
+/// Evaluates operations on constant operands at compile time.
+///
+/// This will:
+/// - Fold primops on constants into new constants
+/// - Fold calls to pure `erlang` BIFs with constant arguments into a
+///   call to the return continuation
+/// - Replace `IfBool` and `Match` on constants with a call to the branch
+///   that is taken
+///
+/// Branches that become dead are left in place, `SimplifyCfgPass` should
+/// be run afterwards to remove them.
+pub struct ConstantFoldPass {
+    /// Result of folding every primop visited so far, `None` if it could
+    /// not be folded.
+    folded: BTreeMap<Value, Option<Const>>,
+    /// Blocks whose operation is replaced with a control flow call.
+    rewrites: Vec<(Block, Value, Vec<Const>)>,
+    mangler: Mangler,
+}
+
+impl ConstantFoldPass {
+    pub fn new() -> Self {
+        ConstantFoldPass {
+            folded: BTreeMap::new(),
+            rewrites: Vec::new(),
+            mangler: Mangler::new(),
+        }
+    }
+}
+
+impl FunctionPass for ConstantFoldPass {
+    fn name(&self) -> &str {
+        "constant_fold"
+    }
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        _analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        self.constant_fold(b)
+    }
+}
+
+impl ConstantFoldPass {
+    /// Returns `true` if anything was folded.
+    pub fn constant_fold(&mut self, b: &mut FunctionBuilder) -> bool {
+        self.folded.clear();
+        self.rewrites.clear();
+
+        let blocks: Vec<_> = b.fun().block_graph().dfs_iter().collect();
+
+        for block in blocks.iter().cloned() {
+            let mut values = Vec::new();
+            b.fun()
+                .block_walk_nested_values::<_, ()>(block, &mut |val| {
+                    values.push(val);
+                    Ok(())
+                })
+                .unwrap();
+            for value in values {
+                self.fold_value(b, value);
+            }
+
+            self.fold_op(b, block);
+        }
+
+        let mut renames = Vec::new();
+        for (value, folded) in self.folded.iter() {
+            if let Some(folded) = folded {
+                renames.push((*value, *folded));
+            }
+        }
+
+        let changed = !renames.is_empty() || !self.rewrites.is_empty();
+
+        for (block, target, args) in self.rewrites.drain(..) {
+            let args: Vec<_> = args.iter().map(|arg| b.value(*arg)).collect();
+            b.block_clear(block);
+            b.op_call_flow(block, target, &args);
+        }
+
+        if !renames.is_empty() {
+            let entry = b.fun().block_entry();
+            self.mangler.start(MangleTo(entry));
+            for (from, to) in renames {
+                let to = b.value(to);
+                self.mangler.add_rename(MangleTo(from), MangleTo(to));
+            }
+            let new_entry = self.mangler.run(b);
+            b.block_set_entry(new_entry);
+        }
+
+        changed
+    }
+
+    /// Returns the constant a value evaluates to, folding primops
+    /// recursively.
+    fn fold_value(&mut self, b: &mut FunctionBuilder, value: Value) -> Option<Const> {
+        if let Some(cons) = b.fun().value_const(value) {
+            return Some(cons);
+        }
+        let prim = b.fun().value_primop(value)?;
+        if let Some(folded) = self.folded.get(&value) {
+            return *folded;
+        }
+
+        let kind = *b.fun().primop_kind(prim);
+        let reads = b.fun().primop_reads(prim).to_vec();
+
+        let args: Option<Vec<_>> = reads.iter().map(|r| self.fold_value(b, *r)).collect();
+        let folded = args.and_then(|args| term::primop(b.cons_mut(), kind, &args));
+
+        self.folded.insert(value, folded);
+        folded
+    }
+
+    fn fold_op(&mut self, b: &mut FunctionBuilder, block: Block) {
+        let reads = b.fun().block_reads(block).to_vec();
+        match b.fun().block_kind(block).cloned() {
+            Some(OpKind::IfBool) => {
+                let value = *reads.last().unwrap();
+                if let Some(cons) = self.fold_value(b, value) {
+                    let target = match b.cons().as_bool(cons) {
+                        Some(true) => reads[0],
+                        Some(false) => reads[1],
+                        None if reads.len() == 4 => reads[2],
+                        None => return,
+                    };
+                    self.rewrites.push((block, target, Vec::new()));
+                }
+            }
+            Some(OpKind::Match { branches }) => {
+                self.fold_match(b, block, &branches, &reads);
+            }
+            Some(OpKind::Call(CallKind::Function)) => {
+                self.fold_bif_call(b, block, &reads);
+            }
+            _ => (),
+        }
+    }
+
+    fn fold_match(
+        &mut self,
+        b: &mut FunctionBuilder,
+        block: Block,
+        branches: &[MatchKind],
+        reads: &[Value],
+    ) {
+        let selector = match self.fold_value(b, reads[1]) {
+            Some(cons) => cons,
+            None => return,
+        };
+
+        for (idx, kind) in branches.iter().enumerate() {
+            let branch_args = reads[idx + 2];
+            let args = match kind {
+                MatchKind::Value => {
+                    let value = b.fun().value_list_get_n(branch_args, 0).unwrap();
+                    match self.fold_value(b, value) {
+                        Some(cons) if cons == selector => Some(Vec::new()),
+                        Some(_) => None,
+                        None => return,
+                    }
+                }
+                MatchKind::Type(typ) => match term::is_type(b.cons(), selector, *typ) {
+                    Some(true) => Some(Vec::new()),
+                    Some(false) => None,
+                    None => return,
+                },
+                MatchKind::Tuple(arity) => match b.cons().const_kind(selector) {
+                    ConstKind::Tuple { entries } => {
+                        let entries = entries.as_slice(&b.cons().const_pool);
+                        if entries.len() == *arity {
+                            Some(entries.to_vec())
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                },
+                MatchKind::ListCell => match b.cons().const_kind(selector) {
+                    ConstKind::ListCell { head, tail } => Some(vec![*head, *tail]),
+                    _ => None,
+                },
+                MatchKind::MapItem => {
+                    let key = b.fun().value_list_get_n(branch_args, 0).unwrap();
+                    let key = match self.fold_value(b, key) {
+                        Some(key) => key,
+                        None => return,
+                    };
+                    match b.cons().const_kind(selector) {
+                        ConstKind::Map { keys, values } => {
+                            let keys = keys.as_slice(&b.cons().const_pool);
+                            let values = values.as_slice(&b.cons().const_pool);
+                            keys.iter()
+                                .position(|k| *k == key)
+                                .map(|pos| vec![values[pos]])
+                        }
+                        _ => None,
+                    }
+                }
+                MatchKind::Binary(_) => {
+                    if term::is_binary(b.cons(), selector) {
+                        return;
+                    }
+                    None
+                }
+                MatchKind::Wildcard => Some(Vec::new()),
+            };
+
+            if let Some(args) = args {
+                let target = b.fun().value_list_get_n(reads[0], idx).unwrap();
+                self.rewrites.push((block, target, args));
+                return;
+            }
+        }
+    }
+
+    fn fold_bif_call(&mut self, b: &mut FunctionBuilder, block: Block, reads: &[Value]) {
+        let name = match erlang_capture(b, reads[0]) {
+            Some((name, arity)) if arity == reads.len() - 3 => name,
+            _ => return,
+        };
+
+        let args: Option<Vec<_>> = reads[3..].iter().map(|r| self.fold_value(b, *r)).collect();
+        let args = match args {
+            Some(args) => args,
+            None => return,
+        };
+
+        if let Some(result) = term::erlang_bif(b.cons_mut(), &name.as_str(), &args) {
+            self.rewrites.push((block, reads[1], vec![result]));
+        }
+    }
+}
+
+/// If the value is a capture of a function in the `erlang` module, returns
+/// the name and arity of the function.
+fn erlang_capture(b: &FunctionBuilder, value: Value) -> Option<(Symbol, usize)> {
+    match capture_function(b.fun(), value)? {
+        (module, name, arity) if module == "erlang" => Some((name, arity)),
+        _ => None,
+    }
+}
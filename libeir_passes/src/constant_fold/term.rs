@@ -0,0 +1,272 @@
+//! Evaluation of operations on constant terms.
+//!
+//! Every function here returns `None` when the result can not be
+//! determined at compile time, or when evaluating the operation would
+//! raise an error at runtime. In both cases the operation is left alone.
+
+use std::cmp::Ordering;
+
+use libeir_ir::{AtomicTerm, BasicType, BinOp, Const, ConstKind, ConstantContainer, Integer};
+use libeir_ir::{LogicOp, PrimOpKind};
+
+enum Number {
+    Integer(Integer),
+    Float(f64),
+}
+
+fn number(cons: &ConstantContainer, c: Const) -> Option<Number> {
+    match cons.const_kind(c) {
+        ConstKind::Atomic(AtomicTerm::Int(int)) => Some(Number::Integer(Integer::Small(int.0))),
+        ConstKind::Atomic(AtomicTerm::BigInt(int)) => {
+            Some(Number::Integer(Integer::Big(int.0.clone())))
+        }
+        ConstKind::Atomic(AtomicTerm::Float(float)) => Some(Number::Float(float.0)),
+        _ => None,
+    }
+}
+
+fn to_float(num: &Number) -> f64 {
+    match num {
+        Number::Integer(int) => int.to_float(),
+        Number::Float(float) => *float,
+    }
+}
+
+/// Position of the type of a term in the standard term order:
+/// number < atom < tuple < map < nil < list < bitstring
+fn type_rank(kind: &ConstKind) -> u8 {
+    match kind {
+        ConstKind::Atomic(AtomicTerm::Int(_)) => 0,
+        ConstKind::Atomic(AtomicTerm::BigInt(_)) => 0,
+        ConstKind::Atomic(AtomicTerm::Float(_)) => 0,
+        ConstKind::Atomic(AtomicTerm::Atom(_)) => 1,
+        ConstKind::Tuple { .. } => 2,
+        ConstKind::Map { .. } => 3,
+        ConstKind::Atomic(AtomicTerm::Nil) => 4,
+        ConstKind::ListCell { .. } => 5,
+        ConstKind::Atomic(AtomicTerm::Binary(_)) => 6,
+    }
+}
+
+/// Compares two terms in the standard term order. Integers and floats
+/// are compared by value, this is the ordering used by `==` and `<`, not
+/// by `=:=`.
+///
+/// Maps are only ordered against other types, or against themselves.
+pub fn compare(cons: &ConstantContainer, l: Const, r: Const) -> Option<Ordering> {
+    if l == r {
+        return Some(Ordering::Equal);
+    }
+
+    if let (Some(ln), Some(rn)) = (number(cons, l), number(cons, r)) {
+        return match (ln, rn) {
+            (Number::Integer(li), Number::Integer(ri)) => Some(li.cmp(&ri)),
+            (Number::Integer(li), Number::Float(rf)) => li.partial_cmp(&rf),
+            (Number::Float(lf), Number::Integer(ri)) => lf.partial_cmp(&ri),
+            (Number::Float(lf), Number::Float(rf)) => lf.partial_cmp(&rf),
+        };
+    }
+
+    match (cons.const_kind(l), cons.const_kind(r)) {
+        (ConstKind::Atomic(AtomicTerm::Atom(la)), ConstKind::Atomic(AtomicTerm::Atom(ra))) => {
+            Some(la.0.as_str().cmp(&ra.0.as_str()))
+        }
+        (ConstKind::Atomic(AtomicTerm::Binary(lb)), ConstKind::Atomic(AtomicTerm::Binary(rb))) => {
+            Some(lb.value().cmp(rb.value()))
+        }
+        (ConstKind::Tuple { entries: le }, ConstKind::Tuple { entries: re }) => {
+            let le = le.as_slice(&cons.const_pool);
+            let re = re.as_slice(&cons.const_pool);
+            if le.len() != re.len() {
+                return Some(le.len().cmp(&re.len()));
+            }
+            for (le, re) in le.iter().zip(re.iter()) {
+                match compare(cons, *le, *re)? {
+                    Ordering::Equal => (),
+                    ord => return Some(ord),
+                }
+            }
+            Some(Ordering::Equal)
+        }
+        (
+            ConstKind::ListCell { head: lh, tail: lt },
+            ConstKind::ListCell { head: rh, tail: rt },
+        ) => match compare(cons, *lh, *rh)? {
+            Ordering::Equal => compare(cons, *lt, *rt),
+            ord => Some(ord),
+        },
+        (ConstKind::Map { .. }, ConstKind::Map { .. }) => None,
+        (lk, rk) => Some(type_rank(lk).cmp(&type_rank(rk))),
+    }
+}
+
+pub fn is_type(cons: &ConstantContainer, c: Const, typ: BasicType) -> Option<bool> {
+    let kind = cons.const_kind(c);
+    let res = match typ {
+        BasicType::List => match kind {
+            ConstKind::ListCell { .. } => true,
+            ConstKind::Atomic(AtomicTerm::Nil) => true,
+            _ => false,
+        },
+        BasicType::ListCell => match kind {
+            ConstKind::ListCell { .. } => true,
+            _ => false,
+        },
+        BasicType::Nil => match kind {
+            ConstKind::Atomic(AtomicTerm::Nil) => true,
+            _ => false,
+        },
+        BasicType::Tuple(arity) => match kind {
+            ConstKind::Tuple { entries } => entries.len(&cons.const_pool) == arity,
+            _ => false,
+        },
+        BasicType::Map => match kind {
+            ConstKind::Map { .. } => true,
+            _ => false,
+        },
+        BasicType::Number => number(cons, c).is_some(),
+        BasicType::Float => match number(cons, c) {
+            Some(Number::Float(_)) => true,
+            _ => false,
+        },
+        BasicType::Integer => match number(cons, c) {
+            Some(Number::Integer(_)) => true,
+            _ => false,
+        },
+        // Where the line between small and big integers is drawn is up to
+        // the backend.
+        BasicType::SmallInteger | BasicType::BigInteger => return None,
+    };
+    Some(res)
+}
+
+pub fn binop(cons: &mut ConstantContainer, op: BinOp, l: Const, r: Const) -> Option<Const> {
+    let res = match op {
+        BinOp::ExactEqual => l == r,
+        BinOp::ExactNotEqual => l != r,
+        BinOp::Equal => compare(cons, l, r)? == Ordering::Equal,
+        BinOp::NotEqual => compare(cons, l, r)? != Ordering::Equal,
+        BinOp::Less => compare(cons, l, r)? == Ordering::Less,
+        BinOp::LessEqual => compare(cons, l, r)? != Ordering::Greater,
+        BinOp::Greater => compare(cons, l, r)? == Ordering::Greater,
+        BinOp::GreaterEqual => compare(cons, l, r)? != Ordering::Less,
+    };
+    Some(cons.from(res))
+}
+
+pub fn logic_op(cons: &mut ConstantContainer, op: LogicOp, args: &[Const]) -> Option<Const> {
+    let res = match op {
+        LogicOp::Eq => args.windows(2).all(|w| w[0] == w[1]),
+        LogicOp::And | LogicOp::Or => {
+            let mut bools = Vec::with_capacity(args.len());
+            for arg in args {
+                bools.push(cons.as_bool(*arg)?);
+            }
+            if op == LogicOp::And {
+                bools.iter().all(|b| *b)
+            } else {
+                bools.iter().any(|b| *b)
+            }
+        }
+    };
+    Some(cons.from(res))
+}
+
+pub fn primop(cons: &mut ConstantContainer, kind: PrimOpKind, args: &[Const]) -> Option<Const> {
+    match kind {
+        PrimOpKind::BinOp(op) => {
+            assert!(args.len() == 2);
+            binop(cons, op, args[0], args[1])
+        }
+        PrimOpKind::LogicOp(op) => logic_op(cons, op, args),
+        PrimOpKind::IsType(typ) => {
+            assert!(args.len() == 1);
+            let res = is_type(cons, args[0], typ)?;
+            Some(cons.from(res))
+        }
+        PrimOpKind::Tuple => {
+            let mut builder = cons.tuple_builder();
+            for arg in args {
+                builder.push(*arg, cons);
+            }
+            Some(builder.finish(cons))
+        }
+        PrimOpKind::ListCell => {
+            assert!(args.len() == 2);
+            Some(cons.list_cell(args[0], args[1]))
+        }
+        _ => None,
+    }
+}
+
+fn arith(cons: &mut ConstantContainer, name: &str, l: Const, r: Const) -> Option<Const> {
+    let (ln, rn) = (number(cons, l)?, number(cons, r)?);
+    match (ln, rn) {
+        (Number::Integer(li), Number::Integer(ri)) => {
+            let res = match name {
+                "+" => li + &ri,
+                "-" => li - &ri,
+                "*" => li * &ri,
+                _ => unreachable!(),
+            };
+            Some(cons.from(res))
+        }
+        (ln, rn) => {
+            let (lf, rf) = (to_float(&ln), to_float(&rn));
+            let res = match name {
+                "+" => lf + rf,
+                "-" => lf - rf,
+                "*" => lf * rf,
+                _ => unreachable!(),
+            };
+            // Overflowing float arithmetic raises `badarith`
+            if res.is_finite() {
+                Some(cons.from(res))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Evaluates a call to a pure function in the `erlang` module.
+pub fn erlang_bif(cons: &mut ConstantContainer, name: &str, args: &[Const]) -> Option<Const> {
+    match (name, args) {
+        ("+", &[l, r]) | ("-", &[l, r]) | ("*", &[l, r]) => arith(cons, name, l, r),
+        ("==", &[l, r]) => binop(cons, BinOp::Equal, l, r),
+        ("/=", &[l, r]) => binop(cons, BinOp::NotEqual, l, r),
+        ("=<", &[l, r]) => binop(cons, BinOp::LessEqual, l, r),
+        ("<", &[l, r]) => binop(cons, BinOp::Less, l, r),
+        (">=", &[l, r]) => binop(cons, BinOp::GreaterEqual, l, r),
+        (">", &[l, r]) => binop(cons, BinOp::Greater, l, r),
+        ("=:=", &[l, r]) => binop(cons, BinOp::ExactEqual, l, r),
+        ("=/=", &[l, r]) => binop(cons, BinOp::ExactNotEqual, l, r),
+        ("element", &[idx, tup]) => {
+            let idx = match cons.const_kind(idx) {
+                ConstKind::Atomic(AtomicTerm::Int(int)) => int.0,
+                _ => return None,
+            };
+            match cons.const_kind(tup) {
+                ConstKind::Tuple { entries } if idx >= 1 => {
+                    entries.get(idx as usize - 1, &cons.const_pool)
+                }
+                _ => None,
+            }
+        }
+        ("tuple_size", &[tup]) => match cons.const_kind(tup) {
+            ConstKind::Tuple { entries } => {
+                let size = entries.len(&cons.const_pool);
+                Some(cons.from(size))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn is_binary(cons: &ConstantContainer, c: Const) -> bool {
+    match cons.const_kind(c) {
+        ConstKind::Atomic(AtomicTerm::Binary(_)) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+
+use super::term;
+use super::ConstantFoldPass;
+use crate::{FunctionAnalysis, FunctionPass, SimplifyCfgPass};
+
+use libeir_intern::Symbol;
+use libeir_ir::{
+    parse_function_unwrap, AtomTerm, BinaryTerm, ConstantContainer, Function, NilTerm,
+};
+
+fn fold_and_simplify(fun: &mut Function) {
+    let mut b = fun.builder();
+
+    let mut constant_fold_pass = ConstantFoldPass::new();
+    let mut simplify_cfg_pass = SimplifyCfgPass::new();
+    while constant_fold_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()) {
+        simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
+    }
+}
+
+#[test]
+fn fold_if_bool() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %cond = a'foo' < {1};
+        if_bool %cond yes no;
+    yes():
+        %ret(%a);
+    no():
+        %thr(%a, %a, %a);
+}
+",
+    );
+    fold_and_simplify(&mut fun);
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %ret(%a);
+}
+",
+    );
+
+    assert!(fun
+        .graph_eq(fun.block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn fold_if_bool_else() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        if_bool a'woo' yes no other;
+    yes():
+        %ret(a'true');
+    no():
+        %ret(a'false');
+    other():
+        %ret(%a);
+}
+",
+    );
+    fold_and_simplify(&mut fun);
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %ret(%a);
+}
+",
+    );
+
+    assert!(fun
+        .graph_eq(fun.block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn fold_match() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %tup = {1, [2]};
+        match %tup {
+            value 1 => value_branch;
+            type %{} => map_branch;
+            {} arity 2 => tuple_branch;
+            _ => wildcard_branch;
+        };
+    value_branch():
+        %ret(a'value');
+    map_branch():
+        %ret(a'map');
+    tuple_branch(%x, %y):
+        match %y {
+            [] => list_branch;
+            _ => wildcard_branch;
+        };
+    list_branch(%head, %tail):
+        %ret(%head);
+    wildcard_branch():
+        %ret(%a);
+}
+",
+    );
+    fold_and_simplify(&mut fun);
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %ret(2);
+}
+",
+    );
+
+    assert!(fun
+        .graph_eq(fun.block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn fold_bif_calls() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %add = a'erlang':a'+'/2;
+        %add(1, 2) => added except %thr;
+    added(%sum):
+        %size = a'erlang':a'tuple_size'/1;
+        %size({1, 2, 3}) => sized except %thr;
+    sized(%s):
+        %element = a'erlang':a'element'/2;
+        %element(1, {%sum, %s}) => elem except %thr;
+    elem(%e):
+        %eq = a'erlang':a'=='/2;
+        %eq(%e, 3.0) => compared except %thr;
+    compared(%c):
+        %ret(%c);
+}
+",
+    );
+    fold_and_simplify(&mut fun);
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %ret(a'true');
+}
+",
+    );
+
+    assert!(fun
+        .graph_eq(fun.block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn no_fold_on_error() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        %element = a'erlang':a'element'/2;
+        %element(3, {1, 2}) => next except %thr;
+    next(%e):
+        %add = a'erlang':a'+'/2;
+        %add(%e, a'foo') => %ret except %thr;
+}
+",
+    );
+    let mut b = fun.builder();
+
+    let mut constant_fold_pass = ConstantFoldPass::new();
+    assert!(!constant_fold_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()));
+}
+
+#[test]
+fn term_order() {
+    let mut cons = ConstantContainer::new();
+
+    let one = cons.from(1);
+    let one_float = cons.from(1.0);
+    let atom_a = cons.from(AtomTerm(Symbol::intern("a")));
+    let atom_b = cons.from(AtomTerm(Symbol::intern("b")));
+    let empty_tuple = cons.tuple_builder().finish(&mut cons);
+    let mut builder = cons.tuple_builder();
+    builder.push(atom_b, &mut cons);
+    let tuple = builder.finish(&mut cons);
+    let nil = cons.from(NilTerm);
+    let list = cons.list_cell(one, nil);
+    let binary = cons.from(BinaryTerm(vec![1, 2]));
+
+    let ordered = [one, atom_a, atom_b, empty_tuple, tuple, nil, list, binary];
+    for pair in ordered.windows(2) {
+        assert!(term::compare(&cons, pair[0], pair[1]) == Some(Ordering::Less));
+        assert!(term::compare(&cons, pair[1], pair[0]) == Some(Ordering::Greater));
+    }
+
+    assert!(term::compare(&cons, one, one_float) == Some(Ordering::Equal));
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashSet};
+
+use libeir_ir::{Block, CallKind, FunctionBuilder, OpKind, Value};
+use libeir_ir::{MangleTo, Mangler};
+
+use super::{FunctionAnalysis, FunctionPass};
+
+#[cfg(test)]
+mod tests;
+
+/// Upper bound on the number of argument removal rounds in a single run.
+/// Every round can only make more arguments unused by dropping the values
+/// passed to the arguments removed in the previous one.
+const MAX_ROUNDS: usize = 16;
+
+/// Removes code that can never affect the result of the function:
+/// - Blocks that are unreachable from the entry are cleared, which removes
+///   them from the predecessors of live blocks in the `block_graph()` and
+///   from the `value_usages` of the values they read.
+/// - Block arguments that are never read are dropped, and all call sites
+///   are rewritten to no longer pass them. This only applies to blocks
+///   that are exclusively the target of control flow calls.
+/// - PrimOps are pure, the ones passed only to dropped arguments go away
+///   with the call site, and no longer count as a usage of the values they
+///   read.
+pub struct DeadCodeEliminationPass {
+    /// Blocks with unused arguments, and whether each argument is kept.
+    candidates: Vec<(Block, Vec<bool>)>,
+    map: BTreeMap<Value, Value>,
+    mangler: Mangler,
+}
+
+impl DeadCodeEliminationPass {
+    pub fn new() -> Self {
+        DeadCodeEliminationPass {
+            candidates: Vec::new(),
+            map: BTreeMap::new(),
+            mangler: Mangler::new(),
+        }
+    }
+}
+
+impl FunctionPass for DeadCodeEliminationPass {
+    fn name(&self) -> &str {
+        "dead_code_elimination"
+    }
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        self.eliminate_dead_code(b, analysis)
+    }
+}
+
+impl DeadCodeEliminationPass {
+    /// Clearing unreachable blocks does not change the live part of the
+    /// function, so the pass is only reported as changed when arguments are
+    /// removed. This keeps fixpoint iteration together with passes that
+    /// leave unreachable blocks behind from looping.
+    fn eliminate_dead_code(
+        &mut self,
+        b: &mut FunctionBuilder,
+        analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        let mut changed = false;
+        for _ in 0..MAX_ROUNDS {
+            if clear_unreachable(b) {
+                analysis.invalidate();
+            }
+            if !self.remove_unused_args(b) {
+                break;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    fn remove_unused_args(&mut self, b: &mut FunctionBuilder) -> bool {
+        self.candidates.clear();
+        self.map.clear();
+
+        let fun = b.fun();
+        let entry = fun.block_entry();
+        for block in fun.block_graph().dfs_iter() {
+            if block == entry {
+                continue;
+            }
+
+            let args = fun.block_args(block);
+            let keep: Vec<bool> = args
+                .iter()
+                .map(|arg| fun.value_usages(*arg).iter().next().is_some())
+                .collect();
+            if keep.iter().all(|k| *k) {
+                continue;
+            }
+
+            // Every usage of the block needs to be a control flow call we
+            // are able to rewrite.
+            let block_val = fun.block_value(block);
+            let only_called = fun.value_usages(block_val).iter().all(|caller| {
+                let reads = fun.block_reads(caller);
+                let mut captured = false;
+                for read in reads[1..].iter() {
+                    fun.value_walk_nested_values::<_, ()>(*read, &mut |val| {
+                        captured |= val == block_val;
+                        Ok(())
+                    })
+                    .unwrap();
+                }
+                match fun.block_kind(caller) {
+                    Some(OpKind::Call(CallKind::ControlFlow)) => {
+                        reads[0] == block_val && reads.len() == args.len() + 1 && !captured
+                    }
+                    _ => false,
+                }
+            });
+            if !only_called {
+                continue;
+            }
+
+            self.candidates.push((block, keep));
+        }
+
+        if self.candidates.is_empty() {
+            return false;
+        }
+
+        // Insert the replacement blocks with only the used arguments
+        let mut new_blocks = BTreeMap::new();
+        for (block, keep) in self.candidates.iter() {
+            let new_block = b.block_insert();
+            for (arg_num, _) in keep.iter().enumerate().filter(|(_, k)| **k) {
+                let arg = b.fun().block_args(*block)[arg_num];
+                let new_arg = b.block_arg_insert(new_block);
+                self.map.insert(arg, new_arg);
+            }
+            new_blocks.insert(*block, new_block);
+        }
+
+        // Rewrite the call sites to the new blocks
+        let mut args = Vec::new();
+        for (block, keep) in self.candidates.iter() {
+            let block_val = b.fun().block_value(*block);
+            let callers: HashSet<Block> = b.fun().value_usages(block_val).iter().collect();
+            for caller in callers {
+                args.clear();
+                args.extend(
+                    b.fun().block_reads(caller)[1..]
+                        .iter()
+                        .zip(keep.iter())
+                        .filter(|(_, k)| **k)
+                        .map(|(v, _)| *v),
+                );
+                b.block_clear(caller);
+                b.op_call_flow(caller, new_blocks[block], &args);
+            }
+        }
+
+        // Move the bodies over. This happens after the call sites are
+        // rewritten, since a candidate block can be a caller itself.
+        for (block, new_block) in new_blocks.iter() {
+            b.block_copy_body_map(*block, *new_block, |_| None);
+            b.block_clear(*block);
+        }
+
+        // Values in the scope of the new blocks still refer to the old
+        // arguments.
+        let entry = b.fun().block_entry();
+        self.mangler.start(MangleTo(entry));
+        for (from, to) in self.map.iter() {
+            self.mangler.add_rename(MangleTo(*from), MangleTo(*to));
+        }
+        let new_entry = self.mangler.run(b);
+        b.block_set_entry(new_entry);
+
+        true
+    }
+}
+
+/// Clears the operation of every block that can not be reached from the
+/// entry. Returns `true` if any block was cleared.
+fn clear_unreachable(b: &mut FunctionBuilder) -> bool {
+    let reachable: HashSet<Block> = b.fun().block_graph().dfs_iter().collect();
+    let unreachable: Vec<Block> = b
+        .fun()
+        .block_iter()
+        .filter(|block| !reachable.contains(block) && b.fun().block_kind(*block).is_some())
+        .collect();
+
+    for block in unreachable.iter() {
+        b.block_clear(*block);
+    }
+
+    !unreachable.is_empty()
+}
@@ -0,0 +1,166 @@
+use petgraph::visit::IntoNeighborsDirected;
+use petgraph::Direction;
+
+use super::DeadCodeEliminationPass;
+use crate::{FunctionAnalysis, FunctionPass};
+
+use libeir_ir::{parse_function_map_unwrap, parse_function_unwrap};
+
+#[test]
+fn unused_block_arg() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2(%a, {%a});
+    b2(%b, %c):
+        %ret(%b);
+}
+",
+    );
+    let mut b = fun.builder();
+
+    let mut dce_pass = DeadCodeEliminationPass::new();
+    assert!(dce_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()));
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2(%a);
+    b2(%b):
+        %ret(%b);
+}
+",
+    );
+
+    assert!(b
+        .fun()
+        .graph_eq(b.fun().block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn unused_arg_multiple_callers() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        if_bool %a b_true b_false;
+    b_true():
+        join(1, %a);
+    b_false():
+        join(2, %a);
+    join(%unused, %x):
+        %ret(inner);
+    inner(%y):
+        %ret(%x);
+}
+",
+    );
+    let mut b = fun.builder();
+
+    let mut dce_pass = DeadCodeEliminationPass::new();
+    assert!(dce_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()));
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        if_bool %a b_true b_false;
+    b_true():
+        join(%a);
+    b_false():
+        join(%a);
+    join(%x):
+        %ret(inner);
+    inner(%y):
+        %ret(%x);
+}
+",
+    );
+
+    assert!(b
+        .fun()
+        .graph_eq(b.fun().block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn unused_through_primop() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2(%a);
+    b2(%b):
+        b3({%b}, %a);
+    b3(%c, %d):
+        %ret(%d);
+}
+",
+    );
+    let mut b = fun.builder();
+
+    let mut dce_pass = DeadCodeEliminationPass::new();
+    assert!(dce_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()));
+
+    // Once `{%b}` is no longer passed to `b3`, `%b` is unused as well
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2();
+    b2():
+        b3(%a);
+    b3(%d):
+        %ret(%d);
+}
+",
+    );
+
+    assert!(b
+        .fun()
+        .graph_eq(b.fun().block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn unreachable_blocks() {
+    let _ = env_logger::try_init();
+
+    let (mut fun, map) = parse_function_map_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2(%a);
+    dead():
+        b2(1);
+    b2(%b):
+        %ret(%b);
+}
+",
+    );
+    let entry = map.get_block("entry");
+    let dead = map.get_block("dead");
+    let b2 = map.get_block("b2");
+
+    let mut b = fun.builder();
+
+    let mut dce_pass = DeadCodeEliminationPass::new();
+    assert!(!dce_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new()));
+
+    assert!(b.fun().block_kind(dead).is_none());
+
+    let graph = b.fun().block_graph();
+    let preds: Vec<_> = (&graph)
+        .neighbors_directed(b2, Direction::Incoming)
+        .collect();
+    assert!(preds == [entry]);
+}
@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use libeir_ir::{Block, CallKind, Function, FunctionBuilder, FunctionIndex, Module, OpKind};
+use libeir_ir::{MangleFrom, Mangler};
+
+use super::{AnalysisManager, FunctionSize, ModulePass};
+use crate::util::{capture_function, Walker};
+
+#[cfg(test)]
+mod tests;
+
+/// Default upper bound on the size of a function that is inlined, counted
+/// as the number of reachable blocks plus primops.
+const DEFAULT_MAX_SIZE: usize = 24;
+
+/// Inlines calls to small functions of the same module.
+///
+/// A call is inlined when it is a `CallKind::Function` call whose target
+/// is a constant capture of a function in the current module. This covers
+/// the small wrappers around `erlang` BIFs that are common in generated
+/// code.
+///
+/// Recursive functions, directly or through other functions in the module,
+/// are never inlined. Since the bodies that are inlined are taken from the
+/// module as it was before the pass ran, running the pass repeatedly will
+/// always terminate.
+///
+/// The call site location is kept below the location of every inlined
+/// block, diagnostics point to both the callee and the call.
+pub struct InlineFunctionsPass {
+    max_size: usize,
+    calls: BTreeMap<FunctionIndex, Vec<(Block, FunctionIndex)>>,
+    mangler: Mangler,
+}
+
+impl InlineFunctionsPass {
+    pub fn new() -> Self {
+        Self::with_max_size(DEFAULT_MAX_SIZE)
+    }
+
+    pub fn with_max_size(max_size: usize) -> Self {
+        InlineFunctionsPass {
+            max_size,
+            calls: BTreeMap::new(),
+            mangler: Mangler::new(),
+        }
+    }
+}
+
+impl ModulePass for InlineFunctionsPass {
+    fn name(&self) -> &str {
+        "inline_functions"
+    }
+    fn run_module_pass(&mut self, module: &mut Module, analysis: &mut AnalysisManager) -> bool {
+        self.inline_functions(module, analysis)
+    }
+}
+
+impl InlineFunctionsPass {
+    /// Returns `true` if any call was inlined.
+    pub fn inline_functions(
+        &mut self,
+        module: &mut Module,
+        analysis: &mut AnalysisManager,
+    ) -> bool {
+        self.calls.clear();
+
+        for fun_def in module.function_iter() {
+            let fun = fun_def.function();
+            let mut calls = Vec::new();
+            for block in fun.block_graph().dfs_iter() {
+                if let Some(callee) = local_callee(module, fun, block) {
+                    calls.push((block, callee));
+                }
+            }
+            self.calls.insert(fun_def.index(), calls);
+        }
+
+        let recursive = self.recursive_functions();
+
+        // Bodies are copied from the module as it was before any inlining
+        // was done.
+        let mut inlinable: BTreeMap<FunctionIndex, Function> = BTreeMap::new();
+        for (_, callee) in self.calls.values().flatten() {
+            if inlinable.contains_key(callee) || recursive.contains(callee) {
+                continue;
+            }
+            let fun = module[*callee].function();
+            let size = FunctionSize::of(fun);
+            if size.blocks + size.primops <= self.max_size {
+                inlinable.insert(*callee, fun.clone());
+            }
+        }
+
+        let mut changed = false;
+        for (caller, calls) in self.calls.iter() {
+            let fun = module[*caller].function_mut();
+            let ident = *fun.ident();
+            let mut b = fun.builder();
+
+            let mut caller_changed = false;
+            for (block, callee) in calls.iter() {
+                if let Some(callee_fun) = inlinable.get(callee) {
+                    inline_call(&mut self.mangler, &mut b, *block, callee_fun);
+                    caller_changed = true;
+                }
+            }
+
+            if caller_changed {
+                analysis.invalidate(&ident);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Functions that can reach themselves through local calls.
+    fn recursive_functions(&self) -> BTreeSet<FunctionIndex> {
+        let mut recursive = BTreeSet::new();
+        let mut walker = Walker::new();
+
+        for fun in self.calls.keys() {
+            walker.clear();
+            for (_, callee) in self.calls[fun].iter() {
+                walker.put(*callee);
+            }
+
+            while let Some(callee) = walker.next() {
+                walker.walked.insert(callee);
+                if callee == *fun {
+                    recursive.insert(*fun);
+                    break;
+                }
+                for (_, next) in self.calls[&callee].iter() {
+                    walker.put(*next);
+                }
+            }
+        }
+
+        recursive
+    }
+}
+
+/// If the block is a call to a function in the module, returns the index
+/// of that function.
+fn local_callee(module: &Module, fun: &Function, block: Block) -> Option<FunctionIndex> {
+    match fun.block_kind(block) {
+        Some(OpKind::Call(CallKind::Function)) => (),
+        _ => return None,
+    }
+
+    let reads = fun.block_reads(block);
+    let (callee_module, name, arity) = capture_function(fun, reads[0])?;
+    if callee_module != module.name().name || arity != reads.len() - 3 {
+        return None;
+    }
+
+    module.name_arity_index(name, arity)
+}
+
+/// Copies the body of `callee` into the function, and replaces the call in
+/// `block` with a control flow call to the copied entry block.
+fn inline_call(mangler: &mut Mangler, b: &mut FunctionBuilder, block: Block, callee: &Function) {
+    let call_location = b.fun().block_location(block);
+    let num_blocks = b.fun().block_iter().count();
+
+    mangler.start(MangleFrom(callee.block_entry()));
+    let new_entry = mangler.run_across(callee, b);
+
+    let new_blocks: Vec<Block> = b.fun().block_iter().skip(num_blocks).collect();
+    for new_block in new_blocks {
+        let location = b.fun().block_location(new_block);
+        let location = b
+            .fun_mut()
+            .locations
+            .concat_locations(call_location, location);
+        b.block_set_location(new_block, location);
+    }
+
+    // The entry block of the callee takes the return and throw
+    // continuations followed by the arguments, same as the call.
+    let args = b.fun().block_reads(block)[1..].to_vec();
+    b.block_clear(block);
+    b.op_call_flow(block, new_entry, &args);
+}
@@ -0,0 +1,151 @@
+use super::InlineFunctionsPass;
+use crate::{AnalysisManager, ModulePass};
+
+use libeir_intern::Symbol;
+use libeir_ir::{parse_function_unwrap, parse_module_unwrap, Function, Module};
+
+fn function<'a>(module: &'a Module, name: &str, arity: usize) -> &'a Function {
+    let idx = module
+        .name_arity_index(Symbol::intern(name), arity)
+        .unwrap();
+    module[idx].function()
+}
+
+#[test]
+fn inline_local_call() {
+    let _ = env_logger::try_init();
+
+    let mut module = parse_module_unwrap(
+        "
+a'foo' {
+    a'add_one'/1 {
+        entry(%ret, %thr, %a):
+            %add = a'erlang':a'+'/2;
+            %add(%a, 1) => %ret except %thr;
+    }
+    a'bar'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'add_one'/1;
+            %fun(%a) => ret except %thr;
+        ret(%r):
+            %ret({%r});
+    }
+}
+",
+    );
+
+    let mut pass = InlineFunctionsPass::new();
+    assert!(pass.run_module_pass(&mut module, &mut AnalysisManager::new()));
+
+    let after = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        inlined(ret, %thr, %a);
+    inlined(%iret, %ithr, %b):
+        %add = a'erlang':a'+'/2;
+        %add(%b, 1) => %iret except %ithr;
+    ret(%r):
+        %ret({%r});
+}
+",
+    );
+
+    let fun = function(&module, "bar", 1);
+    fun.graph_validate_global();
+    assert!(fun
+        .graph_eq(fun.block_entry(), &after, after.block_entry())
+        .is_ok());
+}
+
+#[test]
+fn inline_keeps_call_location() {
+    let _ = env_logger::try_init();
+
+    let mut module = parse_module_unwrap(
+        "
+a'foo' {
+    a'id'/1 {
+        entry(%ret, %thr, %a):
+            %ret(%a);
+    }
+    a'bar'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'id'/1;
+            %fun(%a) => %ret except %thr;
+    }
+}
+",
+    );
+    let call_locations = {
+        let fun = function(&module, "bar", 1);
+        fun.block_locations(fun.block_entry())
+    };
+
+    let mut pass = InlineFunctionsPass::new();
+    assert!(pass.run_module_pass(&mut module, &mut AnalysisManager::new()));
+
+    let fun = function(&module, "bar", 1);
+    let entry = fun.block_entry();
+    let inlined = fun.value_block(fun.block_reads(entry)[0]).unwrap();
+
+    let locations = fun.block_locations(inlined);
+    assert!(locations.len() == call_locations.len() + 1);
+    assert!(locations[..call_locations.len()] == call_locations[..]);
+}
+
+#[test]
+fn no_inline_recursive() {
+    let _ = env_logger::try_init();
+
+    let mut module = parse_module_unwrap(
+        "
+a'foo' {
+    a'ping'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'pong'/1;
+            %fun(%a) => %ret except %thr;
+    }
+    a'pong'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'ping'/1;
+            %fun(%a) => %ret except %thr;
+    }
+    a'loop'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'loop'/1;
+            %fun(%a) => %ret except %thr;
+    }
+}
+",
+    );
+
+    let mut pass = InlineFunctionsPass::new();
+    assert!(!pass.run_module_pass(&mut module, &mut AnalysisManager::new()));
+}
+
+#[test]
+fn no_inline_large() {
+    let _ = env_logger::try_init();
+
+    let mut module = parse_module_unwrap(
+        "
+a'foo' {
+    a'pair'/1 {
+        entry(%ret, %thr, %a):
+            b1({%a, %a});
+        b1(%b):
+            %ret({%b, %b});
+    }
+    a'bar'/1 {
+        entry(%ret, %thr, %a):
+            %fun = a'foo':a'pair'/1;
+            %fun(%a) => %ret except %thr;
+    }
+}
+",
+    );
+
+    let mut pass = InlineFunctionsPass::with_max_size(3);
+    assert!(!pass.run_module_pass(&mut module, &mut AnalysisManager::new()));
+}
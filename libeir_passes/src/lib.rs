@@ -1,14 +1,28 @@
 #![deny(warnings)]
 
-use log::{info, trace};
+use std::time::Instant;
+
+use log::{info, trace, warn};
 
 use libeir_ir::{FunctionBuilder, Module};
 
 pub mod util;
 
+mod analysis;
+pub use self::analysis::{AnalysisManager, FunctionAnalysis};
+
 mod compile_pattern;
 pub use self::compile_pattern::CompilePatternPass;
 
+mod constant_fold;
+pub use self::constant_fold::ConstantFoldPass;
+
+mod dead_code;
+pub use self::dead_code::DeadCodeEliminationPass;
+
+mod inline_functions;
+pub use self::inline_functions::InlineFunctionsPass;
+
 mod naive_inline_closures;
 pub use self::naive_inline_closures::NaiveInlineClosuresPass;
 
@@ -18,22 +32,59 @@ pub use self::simplify_cfg::SimplifyCfgPass;
 mod validate;
 pub use self::validate::ValidatePass;
 
+mod pipeline;
+pub use self::pipeline::{PassRegistry, PipelineError};
+
+mod stats;
+pub use self::stats::{FunctionSize, PassStats};
+
+#[cfg(test)]
+mod tests;
+
 pub trait FunctionPass {
     fn name(&self) -> &str;
-    fn run_function_pass(&mut self, b: &mut FunctionBuilder);
+
+    /// Runs the pass on a single function. Returns `true` if the function
+    /// was changed, in which case cached analyses for it are invalidated.
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        analysis: &mut FunctionAnalysis,
+    ) -> bool;
+}
+
+pub trait ModulePass {
+    fn name(&self) -> &str;
+
+    /// Runs the pass on the whole module. Returns `true` if the module was
+    /// changed.
+    ///
+    /// The pass is responsible for invalidating the analyses of every
+    /// function it modifies through the `AnalysisManager`.
+    fn run_module_pass(&mut self, module: &mut Module, analysis: &mut AnalysisManager) -> bool;
 }
 
+/// Upper bound on the number of iterations of a fixpoint group, in case
+/// the passes in it keep reporting changes.
+const MAX_FIXPOINT_ITERATIONS: usize = 32;
+
 enum PassType {
     Function(Box<dyn FunctionPass>),
+    Module(Box<dyn ModulePass>),
+    Fixpoint(Vec<PassType>),
 }
 
 pub struct PassManager {
     passes: Vec<PassType>,
+    stats: Option<Vec<PassStats>>,
 }
 
 impl PassManager {
     pub fn new() -> Self {
-        PassManager { passes: Vec::new() }
+        PassManager {
+            passes: Vec::new(),
+            stats: None,
+        }
     }
 
     pub fn push_function_pass<P>(&mut self, pass: P)
@@ -43,26 +94,214 @@ impl PassManager {
         self.passes.push(PassType::Function(Box::new(pass)));
     }
 
-    pub fn run(&mut self, module: &mut Module) {
-        for fun_def in module.function_iter_mut() {
-            let fun = fun_def.function_mut();
-            let ident = *fun.ident();
+    pub fn push_module_pass<P>(&mut self, pass: P)
+    where
+        P: ModulePass + 'static,
+    {
+        self.passes.push(PassType::Module(Box::new(pass)));
+    }
 
-            let mut b = FunctionBuilder::new(fun);
-            b.fun().graph_validate_global();
-            trace!("{}", b.fun().to_text_standard());
-            for pass in self.passes.iter_mut() {
-                match pass {
-                    PassType::Function(fun_pass) => {
-                        info!("======== {} FUNCTION_PASS: {}", ident, fun_pass.name());
-                        fun_pass.run_function_pass(&mut b);
-                        trace!("{}", b.fun().to_text_standard());
+    /// Runs the passes of `inner` repeatedly until none of them report a
+    /// change.
+    pub fn push_fixpoint(&mut self, inner: PassManager) {
+        self.passes.push(PassType::Fixpoint(inner.passes));
+    }
+
+    /// Enables collection of `PassStats` for every pass that is run.
+    pub fn enable_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(Vec::new());
+        }
+    }
+
+    /// Statistics collected so far, in the order the passes were run.
+    pub fn stats(&self) -> &[PassStats] {
+        self.stats.as_ref().map(|s| s.as_slice()).unwrap_or(&[])
+    }
+
+    /// Runs all passes on the module. Returns `true` if any pass reported
+    /// a change.
+    pub fn run(&mut self, module: &mut Module) -> bool {
+        let mut analysis = AnalysisManager::new();
+        self.run_with_analysis(module, &mut analysis)
+    }
+
+    /// Runs all passes on the module, reusing the given analysis cache.
+    ///
+    /// Consecutive function passes are run back to back on each function
+    /// before moving on to the next one.
+    pub fn run_with_analysis(
+        &mut self,
+        module: &mut Module,
+        analysis: &mut AnalysisManager,
+    ) -> bool {
+        run_passes(&mut self.passes, module, analysis, self.stats.as_mut())
+    }
+}
+
+impl PassType {
+    fn name(&self) -> &str {
+        match self {
+            PassType::Function(pass) => pass.name(),
+            PassType::Module(pass) => pass.name(),
+            PassType::Fixpoint(_) => "fixpoint",
+        }
+    }
+}
+
+fn module_size(module: &Module) -> FunctionSize {
+    let mut size = FunctionSize::default();
+    for fun_def in module.function_iter() {
+        size += FunctionSize::of(fun_def.function());
+    }
+    size
+}
+
+fn run_passes(
+    passes: &mut [PassType],
+    module: &mut Module,
+    analysis: &mut AnalysisManager,
+    mut stats: Option<&mut Vec<PassStats>>,
+) -> bool {
+    let mut changed = false;
+
+    let mut idx = 0;
+    while idx < passes.len() {
+        if let PassType::Function(_) = passes[idx] {
+            let end = passes[idx..]
+                .iter()
+                .position(|pass| match pass {
+                    PassType::Function(_) => false,
+                    _ => true,
+                })
+                .map(|n| idx + n)
+                .unwrap_or(passes.len());
+
+            changed |= run_function_passes(
+                &mut passes[idx..end],
+                module,
+                analysis,
+                stats.as_mut().map(|s| &mut **s),
+            );
+
+            idx = end;
+            continue;
+        }
+
+        match &mut passes[idx] {
+            PassType::Function(_) => unreachable!(),
+            PassType::Module(mod_pass) => {
+                info!("======== MODULE_PASS: {}", mod_pass.name());
+
+                let mut pass_stats = stats.as_ref().map(|_| PassStats::new(mod_pass.name()));
+                if let Some(pass_stats) = &mut pass_stats {
+                    pass_stats.before = module_size(module);
+                }
+
+                let start = Instant::now();
+                let pass_changed = mod_pass.run_module_pass(module, analysis);
+                let time = start.elapsed();
+
+                info!("changed: {}", pass_changed);
+                changed |= pass_changed;
+
+                if let (Some(stats), Some(mut pass_stats)) = (stats.as_mut(), pass_stats) {
+                    pass_stats.after = module_size(module);
+                    pass_stats.time = time;
+                    pass_stats.changed = pass_changed;
+                    stats.push(pass_stats);
+                }
+
+                for fun_def in module.function_iter() {
+                    fun_def.function().graph_validate_global();
+                }
+            }
+            PassType::Fixpoint(inner) => {
+                let mut iterations = 0;
+                loop {
+                    iterations += 1;
+                    info!("======== FIXPOINT ITERATION: {}", iterations);
+
+                    let iter_changed =
+                        run_passes(inner, module, analysis, stats.as_mut().map(|s| &mut **s));
+                    changed |= iter_changed;
+
+                    if !iter_changed {
+                        break;
+                    }
+                    if iterations == MAX_FIXPOINT_ITERATIONS {
+                        warn!("fixpoint not reached after {} iterations", iterations);
+                        break;
                     }
                 }
-                b.fun().graph_validate_global();
             }
         }
+        idx += 1;
     }
+
+    changed
+}
+
+fn run_function_passes(
+    passes: &mut [PassType],
+    module: &mut Module,
+    analysis: &mut AnalysisManager,
+    stats: Option<&mut Vec<PassStats>>,
+) -> bool {
+    let mut changed = false;
+
+    let mut group_stats: Option<Vec<PassStats>> = stats.as_ref().map(|_| {
+        passes
+            .iter()
+            .map(|pass| PassStats::new(pass.name()))
+            .collect()
+    });
+
+    for fun_def in module.function_iter_mut() {
+        let fun = fun_def.function_mut();
+        let ident = *fun.ident();
+        let fun_analysis = analysis.function(&ident);
+
+        let mut b = FunctionBuilder::new(fun);
+        b.fun().graph_validate_global();
+        trace!("{}", b.fun().to_text_standard());
+        for (n, pass) in passes.iter_mut().enumerate() {
+            let fun_pass = match pass {
+                PassType::Function(fun_pass) => fun_pass,
+                _ => unreachable!(),
+            };
+            info!("======== {} FUNCTION_PASS: {}", ident, fun_pass.name());
+
+            let before = group_stats.as_ref().map(|_| FunctionSize::of(b.fun()));
+
+            let start = Instant::now();
+            let pass_changed = fun_pass.run_function_pass(&mut b, fun_analysis);
+            let time = start.elapsed();
+
+            info!("changed: {}", pass_changed);
+            if pass_changed {
+                fun_analysis.invalidate();
+                changed = true;
+            }
+
+            if let Some(group_stats) = &mut group_stats {
+                let pass_stats = &mut group_stats[n];
+                pass_stats.before += before.unwrap();
+                pass_stats.after += FunctionSize::of(b.fun());
+                pass_stats.time += time;
+                pass_stats.changed |= pass_changed;
+            }
+
+            trace!("{}", b.fun().to_text_standard());
+            b.fun().graph_validate_global();
+        }
+    }
+
+    if let (Some(stats), Some(group_stats)) = (stats, group_stats) {
+        stats.extend(group_stats);
+    }
+
+    changed
 }
 
 impl Default for PassManager {
@@ -74,10 +313,14 @@ impl Default for PassManager {
         man.push_function_pass(ValidatePass::new());
         man.push_function_pass(NaiveInlineClosuresPass::new());
         man.push_function_pass(ValidatePass::new());
+        man.push_function_pass(ConstantFoldPass::new());
+        man.push_function_pass(ValidatePass::new());
         man.push_function_pass(SimplifyCfgPass::new());
         man.push_function_pass(ValidatePass::new());
         man.push_function_pass(NaiveInlineClosuresPass::new());
         man.push_function_pass(ValidatePass::new());
+        man.push_function_pass(DeadCodeEliminationPass::new());
+        man.push_function_pass(ValidatePass::new());
         man
     }
 }
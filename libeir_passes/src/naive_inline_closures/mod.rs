@@ -2,7 +2,7 @@ use libeir_ir::FunctionBuilder;
 use libeir_ir::{Block, OpKind};
 use libeir_ir::{MangleTo, Mangler};
 
-use super::FunctionPass;
+use super::{FunctionAnalysis, FunctionPass};
 
 #[cfg(test)]
 mod tests;
@@ -26,13 +26,18 @@ impl FunctionPass for NaiveInlineClosuresPass {
     fn name(&self) -> &str {
         "naive_inline_closures"
     }
-    fn run_function_pass(&mut self, b: &mut FunctionBuilder) {
-        self.inline_closures(b);
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        _analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        self.inline_closures(b)
     }
 }
 
 impl NaiveInlineClosuresPass {
-    pub fn inline_closures(&mut self, b: &mut FunctionBuilder) {
+    /// Returns `true` if any closure was inlined.
+    pub fn inline_closures(&mut self, b: &mut FunctionBuilder) -> bool {
         self.calls_buf.clear();
 
         for block in b.fun().block_graph().dfs_post_order_iter() {
@@ -91,5 +96,7 @@ impl NaiveInlineClosuresPass {
             b.block_clear(block);
             b.op_call_flow(block, new_block, &[]);
         }
+
+        !self.calls_buf.is_empty()
     }
 }
@@ -1,6 +1,6 @@
 use libeir_ir::{parse_function_unwrap, StandardFormatConfig};
 
-use crate::FunctionPass;
+use crate::{FunctionAnalysis, FunctionPass};
 
 #[test]
 fn inline_basic_function() {
@@ -25,7 +25,7 @@ a'foo':a'fun_shadowing'/1 {
     let mut b = fun.builder();
 
     let mut pass = super::NaiveInlineClosuresPass::new();
-    pass.run_function_pass(&mut b);
+    pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -83,7 +83,7 @@ a'foo':a'fun_shadowing'/1 {
     let mut b = fun.builder();
 
     let mut pass = super::NaiveInlineClosuresPass::new();
-    pass.run_function_pass(&mut b);
+    pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     println!("{}", b.fun().to_text(&mut StandardFormatConfig::default()));
 
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use snafu::{OptionExt, Snafu};
+
+use super::{CompilePatternPass, ConstantFoldPass, DeadCodeEliminationPass};
+use super::{FunctionPass, ModulePass, PassManager, PassType};
+use super::{InlineFunctionsPass, NaiveInlineClosuresPass, SimplifyCfgPass, ValidatePass};
+
+#[derive(Debug, Snafu)]
+pub enum PipelineError {
+    #[snafu(display("unknown pass `{}`", name))]
+    UnknownPass { name: String },
+
+    #[snafu(display("invalid pass pipeline at offset {}: {}", offset, reason))]
+    InvalidPipeline { offset: usize, reason: &'static str },
+}
+
+enum PassConstructor {
+    Function(Box<dyn Fn() -> Box<dyn FunctionPass>>),
+    Module(Box<dyn Fn() -> Box<dyn ModulePass>>),
+}
+
+/// Maps pass names to constructors, used to build a `PassManager` from a
+/// textual pipeline.
+///
+/// A pipeline is a comma separated list of pass names. Passes wrapped in
+/// `fixpoint(..)` are run repeatedly until none of them report a change:
+///
+/// ```ignore
+/// validate,compile_patterns,fixpoint(inline_closures,simplify_cfg)
+/// ```
+pub struct PassRegistry {
+    passes: BTreeMap<String, PassConstructor>,
+}
+
+impl PassRegistry {
+    pub fn new() -> Self {
+        PassRegistry {
+            passes: BTreeMap::new(),
+        }
+    }
+
+    pub fn register_function_pass<F, P>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn() -> P + 'static,
+        P: FunctionPass + 'static,
+    {
+        let constructor = move || Box::new(constructor()) as Box<dyn FunctionPass>;
+        self.passes.insert(
+            name.to_string(),
+            PassConstructor::Function(Box::new(constructor)),
+        );
+    }
+
+    pub fn register_module_pass<F, P>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn() -> P + 'static,
+        P: ModulePass + 'static,
+    {
+        let constructor = move || Box::new(constructor()) as Box<dyn ModulePass>;
+        self.passes.insert(
+            name.to_string(),
+            PassConstructor::Module(Box::new(constructor)),
+        );
+    }
+
+    /// Names of all registered passes, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.passes.keys().map(|name| name.as_str())
+    }
+
+    /// Appends a new instance of the named pass to the pass manager.
+    pub fn push_pass(&self, name: &str, manager: &mut PassManager) -> Result<(), PipelineError> {
+        let constructor = self.passes.get(name).context(UnknownPass { name })?;
+        let pass = match constructor {
+            PassConstructor::Function(fun) => PassType::Function(fun()),
+            PassConstructor::Module(fun) => PassType::Module(fun()),
+        };
+        manager.passes.push(pass);
+        Ok(())
+    }
+
+    pub fn parse_pipeline(&self, pipeline: &str) -> Result<PassManager, PipelineError> {
+        let mut parser = PipelineParser {
+            registry: self,
+            text: pipeline,
+            pos: 0,
+        };
+
+        let mut manager = PassManager::new();
+        parser.parse_list(&mut manager)?;
+        parser.skip_whitespace();
+        if parser.pos != pipeline.len() {
+            return parser.error("expected `,`");
+        }
+
+        Ok(manager)
+    }
+}
+
+impl Default for PassRegistry {
+    fn default() -> Self {
+        let mut registry = PassRegistry::new();
+        registry.register_function_pass("validate", ValidatePass::new);
+        registry.register_function_pass("compile_patterns", CompilePatternPass::new);
+        registry.register_function_pass("simplify_cfg", SimplifyCfgPass::new);
+        registry.register_function_pass("inline_closures", NaiveInlineClosuresPass::new);
+        registry.register_function_pass("constant_fold", ConstantFoldPass::new);
+        registry.register_function_pass("dead_code_elimination", DeadCodeEliminationPass::new);
+        registry.register_module_pass("inline_functions", InlineFunctionsPass::new);
+        registry
+    }
+}
+
+struct PipelineParser<'a> {
+    registry: &'a PassRegistry,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> PipelineParser<'a> {
+    fn error<T>(&self, reason: &'static str) -> Result<T, PipelineError> {
+        InvalidPipeline {
+            offset: self.pos,
+            reason,
+        }
+        .fail()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn name(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || c == '_') {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn parse_list(&mut self, manager: &mut PassManager) -> Result<(), PipelineError> {
+        loop {
+            self.skip_whitespace();
+            let name = self.name();
+            if name.is_empty() {
+                return self.error("expected pass name");
+            }
+
+            self.skip_whitespace();
+            if self.peek() == Some('(') {
+                if name != "fixpoint" {
+                    return self.error("only `fixpoint` takes a list of passes");
+                }
+                self.pos += 1;
+
+                let mut inner = PassManager::new();
+                self.parse_list(&mut inner)?;
+
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return self.error("expected `)`");
+                }
+                self.pos += 1;
+
+                manager.push_fixpoint(inner);
+            } else {
+                self.registry.push_pass(name, manager)?;
+            }
+
+            self.skip_whitespace();
+            if self.peek() == Some(',') {
+                self.pos += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -9,10 +9,10 @@ use fnv::FnvBuildHasher;
 use hashbrown::HashMap;
 type BFnvHashMap<'bump, K, V> = HashMap<K, V, FnvBuildHasher, &'bump Bump>;
 
-use libeir_ir::Value;
+use libeir_ir::{CallKind, OpKind, Value};
 use libeir_ir::{FunctionBuilder, MangleTo, Mangler, StandardFormatConfig};
 
-use super::FunctionPass;
+use super::{FunctionAnalysis, FunctionPass};
 
 mod analyze;
 mod chain_graph;
@@ -107,18 +107,26 @@ impl FunctionPass for SimplifyCfgPass {
     fn name(&self) -> &str {
         "simplify_cfg"
     }
-    fn run_function_pass(&mut self, b: &mut FunctionBuilder) {
-        self.simplify_cfg(b);
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        analysis: &mut FunctionAnalysis,
+    ) -> bool {
+        self.simplify_cfg(b, analysis)
     }
 }
 
 impl SimplifyCfgPass {
-    fn simplify_cfg(&mut self, b: &mut FunctionBuilder) {
+    /// The function is always rebuilt, so cached analyses are invalidated
+    /// unconditionally. It is reported as changed when any call chain was
+    /// merged into its target, or a statically resolved branch was replaced
+    /// by a plain call.
+    fn simplify_cfg(&mut self, b: &mut FunctionBuilder, analysis: &mut FunctionAnalysis) -> bool {
         let mut bump = self.bump.take().unwrap();
 
         let entry = b.fun().block_entry();
         let graph = b.fun().live_block_graph();
-        let live = b.fun().live_values();
+        let live = analysis.live_values(b.fun());
 
         //let func_tree = b.fun().func_tree(&live, false);
         //let func_order: Vec<_> = func_tree.dfs_post_order_iter().collect();
@@ -127,21 +135,40 @@ impl SimplifyCfgPass {
         let block_order: Vec<_> = graph.dfs_post_order_iter().collect();
         trace!("BLOCK ORDER {:?}", block_order);
 
+        let mut changed = false;
+
         trace!("{}", b.fun().to_text(&mut StandardFormatConfig::default()));
 
         {
-            let analysis = analyze::analyze_graph(&bump, b.fun(), &graph);
-            trace!("analysis = {:#?}", analysis);
+            let graph_analysis = analyze::analyze_graph(&bump, b.fun(), &graph);
+            trace!("analysis = {:#?}", graph_analysis);
             trace!("analysis done");
 
             for block in block_order.iter() {
-                if let Some(_blocks) = analysis.trees.get(block) {
+                if let Some(tree) = graph_analysis.trees.get(block) {
                     let target = block;
 
+                    // A tree without edges is a single block branching to a
+                    // value. Unless that branch was resolved from some other
+                    // operation, the rewrite reproduces the same block.
+                    changed |= !tree.edges.is_empty()
+                        || tree.blocks.keys().any(|block| {
+                            match b.fun().block_kind(*block).unwrap() {
+                                OpKind::Call(CallKind::ControlFlow) => false,
+                                _ => true,
+                            }
+                        });
+
                     // Synthesize CFG for chain
                     let graph = b.fun().live_block_graph();
-                    let chain_graph =
-                        analyze::analyze_chain(&bump, *target, &b.fun(), &graph, &live, &analysis);
+                    let chain_graph = analyze::analyze_chain(
+                        &bump,
+                        *target,
+                        &b.fun(),
+                        &graph,
+                        live,
+                        &graph_analysis,
+                    );
 
                     let synthesis_impl = chain_graph::synthesis::compound::CompoundStrategy;
                     let mut synthesis = synthesis_impl.try_run(&chain_graph, b.fun()).unwrap();
@@ -225,8 +252,12 @@ impl SimplifyCfgPass {
             //trace!("{}", b.fun().to_text());
         }
 
+        analysis.invalidate();
+
         self.map.clear();
         bump.reset();
         self.bump = Some(bump);
+
+        changed
     }
 }
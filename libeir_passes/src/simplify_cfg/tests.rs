@@ -1,5 +1,5 @@
 use super::SimplifyCfgPass;
-use crate::FunctionPass;
+use crate::{FunctionAnalysis, FunctionPass};
 
 use libeir_ir::{parse_function_map_unwrap, parse_function_unwrap};
 
@@ -23,7 +23,7 @@ a'foo':a'bar'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -61,7 +61,7 @@ a'foo':a'bar'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -108,7 +108,7 @@ a'foo':a'bar'/3 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -159,7 +159,7 @@ a'foo':a'bar'/5 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -199,7 +199,7 @@ a'foo':a'bar'/0 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -237,7 +237,7 @@ a'foo':a'bar'/0 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -275,7 +275,7 @@ a'foo':a'bar'/0 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -309,7 +309,7 @@ a'foo':a'bar'/2 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -352,7 +352,7 @@ a'foo':a'bar'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -399,7 +399,7 @@ a'foo':a'perms'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     //    let after = parse_function_unwrap("
     //a'foo':a'perms'/1 {
@@ -460,7 +460,7 @@ a'foo':a'do_map_vars_used'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -512,7 +512,7 @@ a'foo':a'do_map_vars_used'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -561,7 +561,7 @@ a'fib':a'fib'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let after = parse_function_unwrap(
         "
@@ -614,7 +614,7 @@ a'a':a'get_values'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     b.fun().live_values();
 }
@@ -671,7 +671,7 @@ a'a':a'a'/1 {
     println!("{}", b.fun().to_text(&mut StandardFormatConfig::default()));
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     println!("{}", b.fun().to_text(&mut StandardFormatConfig::default()));
 
@@ -831,7 +831,7 @@ a'a':a'a'/1 {
     println!("{}", dot);
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 }
 
 #[test]
@@ -992,10 +992,34 @@ a'a':a'underscore'/1 {
     let mut b = fun.builder();
 
     let mut simplify_cfg_pass = SimplifyCfgPass::new();
-    simplify_cfg_pass.run_function_pass(&mut b);
+    simplify_cfg_pass.run_function_pass(&mut b, &mut FunctionAnalysis::new());
 
     let mut errs = Vec::new();
     b.fun().validate(&mut errs);
     println!("{:?}", errs);
     assert!(errs.len() == 0)
 }
+
+#[test]
+fn reports_changes() {
+    let _ = env_logger::try_init();
+
+    let mut fun = parse_function_unwrap(
+        "
+a'foo':a'bar'/1 {
+    entry(%ret, %thr, %a):
+        b2(%a);
+    b2(%b):
+        %ret(%b);
+}
+",
+    );
+    let mut b = fun.builder();
+
+    let mut simplify_cfg_pass = SimplifyCfgPass::new();
+    let mut analysis = FunctionAnalysis::new();
+    assert!(simplify_cfg_pass.run_function_pass(&mut b, &mut analysis));
+
+    // The function is already simplified, a second run changes nothing
+    assert!(!simplify_cfg_pass.run_function_pass(&mut b, &mut analysis));
+}
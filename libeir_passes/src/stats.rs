@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+use libeir_ir::Function;
+
+/// Size of a function, only counting what is reachable from the entry
+/// block.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FunctionSize {
+    pub blocks: usize,
+    pub values: usize,
+    pub primops: usize,
+}
+
+impl FunctionSize {
+    pub fn of(fun: &Function) -> Self {
+        let mut blocks = 0;
+        let mut values = HashSet::new();
+
+        for block in fun.block_graph().dfs_iter() {
+            blocks += 1;
+            values.insert(fun.block_value(block));
+            values.extend(fun.block_args(block).iter().cloned());
+            fun.block_walk_nested_values::<_, ()>(block, &mut |val| {
+                values.insert(val);
+                Ok(())
+            })
+            .unwrap();
+        }
+
+        let primops = values
+            .iter()
+            .filter(|val| fun.value_primop(**val).is_some())
+            .count();
+
+        FunctionSize {
+            blocks,
+            values: values.len(),
+            primops,
+        }
+    }
+}
+
+impl AddAssign for FunctionSize {
+    fn add_assign(&mut self, rhs: FunctionSize) {
+        self.blocks += rhs.blocks;
+        self.values += rhs.values;
+        self.primops += rhs.primops;
+    }
+}
+
+/// Statistics for a single run of a pass over a module. Sizes are summed
+/// over all functions in the module.
+#[derive(Debug, Clone)]
+pub struct PassStats {
+    pub name: String,
+    pub before: FunctionSize,
+    pub after: FunctionSize,
+    pub time: Duration,
+    pub changed: bool,
+}
+
+impl PassStats {
+    pub(crate) fn new(name: &str) -> Self {
+        PassStats {
+            name: name.to_string(),
+            before: FunctionSize::default(),
+            after: FunctionSize::default(),
+            time: Duration::default(),
+            changed: false,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use libeir_ir::{parse_module_unwrap, Module};
+
+use crate::{AnalysisManager, ModulePass, NaiveInlineClosuresPass, PassManager, ValidatePass};
+use crate::{PassRegistry, PipelineError};
+
+struct CountFunctionsPass {
+    count: Rc<Cell<usize>>,
+}
+
+impl ModulePass for CountFunctionsPass {
+    fn name(&self) -> &str {
+        "count_functions"
+    }
+    fn run_module_pass(&mut self, module: &mut Module, _analysis: &mut AnalysisManager) -> bool {
+        self.count.set(module.function_iter().count());
+        false
+    }
+}
+
+fn module() -> Module {
+    parse_module_unwrap(
+        "
+a'foo' {
+    a'bar'/1 {
+        entry(%ret, %thr, %A):
+            b1();
+        b1():
+            inner(ret, thr, %A);
+        inner(%iret, %ithr, %B):
+            %iret(%B);
+
+        ret(%rv):
+            %ret(%rv);
+        thr(%rt1, %rt2, %rt3):
+            %thr(%rt1, %rt2, %rt3);
+    }
+    a'baz'/1 {
+        entry(%ret, %thr, %A):
+            %ret(%A);
+    }
+}
+",
+    )
+}
+
+#[test]
+fn reports_changes() {
+    let mut module = module();
+
+    let mut pass_manager = PassManager::new();
+    pass_manager.push_function_pass(NaiveInlineClosuresPass::new());
+    pass_manager.push_function_pass(ValidatePass::new());
+    assert!(pass_manager.run(&mut module));
+
+    let mut pass_manager = PassManager::new();
+    pass_manager.push_function_pass(ValidatePass::new());
+    assert!(!pass_manager.run(&mut module));
+}
+
+#[test]
+fn module_pass_between_function_passes() {
+    let mut module = module();
+    let count = Rc::new(Cell::new(0));
+
+    let mut pass_manager = PassManager::new();
+    pass_manager.push_function_pass(ValidatePass::new());
+    pass_manager.push_module_pass(CountFunctionsPass {
+        count: count.clone(),
+    });
+    pass_manager.push_function_pass(ValidatePass::new());
+
+    let mut analysis = AnalysisManager::new();
+    assert!(!pass_manager.run_with_analysis(&mut module, &mut analysis));
+    assert!(count.get() == 2);
+}
+
+#[test]
+fn pipeline_fixpoint_stats() {
+    let mut module = module();
+
+    let registry = PassRegistry::default();
+    let mut pass_manager = registry
+        .parse_pipeline("validate, compile_patterns, fixpoint(inline_closures, validate)")
+        .unwrap();
+    pass_manager.enable_stats();
+    assert!(pass_manager.run(&mut module));
+
+    // The second fixpoint iteration finds nothing left to inline
+    let names: Vec<_> = pass_manager.stats().iter().map(|s| &*s.name).collect();
+    assert!(
+        names
+            == [
+                "validate",
+                "compile_patterns",
+                "inline_closures",
+                "validate",
+                "inline_closures",
+                "validate"
+            ]
+    );
+
+    assert!(pass_manager.stats()[2].changed);
+    assert!(!pass_manager.stats()[4].changed);
+}
+
+#[test]
+fn pipeline_errors() {
+    let registry = PassRegistry::default();
+
+    match registry.parse_pipeline("validate,woo") {
+        Err(PipelineError::UnknownPass { name }) => assert!(name == "woo"),
+        _ => panic!(),
+    }
+    match registry.parse_pipeline("fixpoint(validate") {
+        Err(PipelineError::InvalidPipeline { offset, .. }) => assert!(offset == 17),
+        _ => panic!(),
+    }
+    match registry.parse_pipeline("validate(simplify_cfg)") {
+        Err(PipelineError::InvalidPipeline { offset, .. }) => assert!(offset == 8),
+        _ => panic!(),
+    }
+    assert!(registry.parse_pipeline("").is_err());
+}
@@ -1,5 +1,8 @@
 use std::collections::{BTreeMap, BTreeSet};
 
+use libeir_intern::Symbol;
+use libeir_ir::{AtomicTerm, ConstKind, Function, PrimOpKind, Value};
+
 #[derive(Debug)]
 pub struct EdgeSet<T: Copy + Ord>(pub BTreeMap<T, T>);
 impl<T: Copy + Ord> EdgeSet<T> {
@@ -65,3 +68,27 @@ where
         None
     }
 }
+
+/// If the value is a constant function capture, returns the module, name
+/// and arity of the captured function.
+pub fn capture_function(fun: &Function, value: Value) -> Option<(Symbol, Symbol, usize)> {
+    let prim = fun.value_primop(value)?;
+    if *fun.primop_kind(prim) != PrimOpKind::CaptureFunction {
+        return None;
+    }
+
+    let reads = fun.primop_reads(prim);
+    let atom = |v: Value| match fun.const_kind(fun.value_const(v)?) {
+        ConstKind::Atomic(AtomicTerm::Atom(atom)) => Some(atom.0),
+        _ => None,
+    };
+
+    let module = atom(reads[0])?;
+    let name = atom(reads[1])?;
+    let arity = match fun.const_kind(fun.value_const(reads[2])?) {
+        ConstKind::Atomic(AtomicTerm::Int(arity)) => arity.0 as usize,
+        _ => return None,
+    };
+
+    Some((module, name, arity))
+}
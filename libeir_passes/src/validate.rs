@@ -1,4 +1,4 @@
-use super::FunctionPass;
+use super::{FunctionAnalysis, FunctionPass};
 
 use libeir_ir::{FunctionBuilder, ValidationError};
 
@@ -20,7 +20,11 @@ impl FunctionPass for ValidatePass {
     fn name(&self) -> &str {
         "validate"
     }
-    fn run_function_pass(&mut self, b: &mut FunctionBuilder) {
+    fn run_function_pass(
+        &mut self,
+        b: &mut FunctionBuilder,
+        _analysis: &mut FunctionAnalysis,
+    ) -> bool {
         self.err_buf.clear();
         b.fun().validate(&mut self.err_buf);
 
@@ -29,5 +33,7 @@ impl FunctionPass for ValidatePass {
         }
 
         assert!(self.err_buf.len() == 0);
+
+        false
     }
 }
@@ -1,4 +1,6 @@
-use eir::{ Atom, Variable };
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::Ident;
+use libeir_util_number::Integer;
 
 #[derive(Debug, Copy, Clone)]
 pub enum MapExactAssoc {
@@ -7,73 +9,45 @@ pub enum MapExactAssoc {
 }
 
 #[derive(Debug, Clone)]
-pub struct Annotated<I>(pub I, pub Vec<()>);
+pub struct Annotated<I> {
+    pub inner: I,
+    pub span: SourceSpan,
+    pub annotations: Vec<Constant>,
+}
 impl<I> Annotated<I> {
-    pub fn empty(inner: I) -> Self {
-        Annotated(inner, Vec::new())
+    pub fn empty(inner: I, span: SourceSpan) -> Self {
+        Annotated {
+            inner,
+            span,
+            annotations: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Module {
-    pub name: Atom,
+    pub span: SourceSpan,
+    pub name: Ident,
     pub declarations: Vec<FunctionName>,
-    pub attributes: Vec<(Atom, Constant)>,
+    pub attributes: Vec<(Ident, Constant)>,
     pub definitions: Vec<FunctionDefinition>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct FunctionName {
-    pub name: Atom,
+    pub name: Ident,
     pub arity: usize,
 }
-//impl FunctionName {
-//    pub fn to_eir(&self, module: Atom) -> FunctionIdent {
-//        FunctionIdent {
-//            module: module,
-//            name: self.name.clone(),
-//            arity: self.arity,
-//            lambda: None,
-//        }
-//    }
-//}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Integer {
-    pub sign: bool,
-    pub digits: String,
-}
-impl Integer {
-    pub fn as_u32(&self) -> u32 {
-        assert!(self.sign);
-        self.digits.parse().unwrap()
-    }
-    pub fn as_usize(&self) -> usize {
-        assert!(self.sign);
-        self.digits.parse().unwrap()
-    }
-}
 
-use eir::{ AtomicTerm, ConstantTerm };
-type AtomicLiteral = AtomicTerm;
-
-//#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-//pub enum AtomicLiteral {
-//    Integer(Integer),
-//    Float,
-//    Atom(Atom),
-//    Nil,
-//    Char(char),
-//    String(String),
-//}
-//impl Display for AtomicLiteral {
-//    fn fmt(&self, f: &mut Formatter) -> Result<(), ::std::fmt::Error> {
-//        match self {
-//            &AtomicLiteral::Integer(ref int) => write!(f, "{}{}", int.sign, int.digits),
-//            _ => write!(f, "unimpl"),
-//        }
-//    }
-//}
+#[derive(Debug, Clone)]
+pub enum AtomicLiteral {
+    Integer(Integer),
+    Float(f64),
+    Atom(Ident),
+    Nil,
+    Char(char),
+    String(Ident),
+}
 
 #[derive(Debug, Clone)]
 pub enum Constant {
@@ -81,27 +55,6 @@ pub enum Constant {
     Tuple(Vec<Constant>),
     List(Vec<Constant>, Box<Constant>),
 }
-//impl Constant {
-//    pub fn to_eir(&self) -> ConstantTerm {
-//        match self {
-//            Constant::Atomic(atomic) => ConstantTerm::Atomic(atomic.clone()),
-//            Constant::List(head, tail) =>
-//                ConstantTerm::List(
-//                    head.iter().map(|c| c.to_eir()).collect(),
-//                    Box::new(tail.to_eir()),
-//                ),
-//            _ => unimplemented!(),
-//        }
-//    }
-//}
-//impl Display for Constant {
-//    fn fmt(&self, f: &mut Formatter) -> Result<(), ::std::fmt::Error> {
-//        match self {
-//            &Constant::Atomic(ref lit) => write!(f, "{}", lit),
-//            _ => write!(f, "unimpl"),
-//        }
-//    }
-//}
 
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
@@ -113,33 +66,67 @@ pub struct FunctionDefinition {
 pub enum SingleExpression {
     // Env reading
     FunctionName(FunctionName),
-    ExternalFunctionName { module: Atom, name: FunctionName },
-    Variable(Variable),
+    ExternalFunctionName {
+        module: Ident,
+        name: FunctionName,
+    },
+    Variable(Ident),
 
     // Control flow
-    Let { vars: Vec<Annotated<Variable>>, val: Box<Expression>, body: Box<Expression> },
+    Let {
+        vars: Vec<Annotated<Ident>>,
+        val: Box<Expression>,
+        body: Box<Expression>,
+    },
     Catch(Box<Expression>),
-    Case { val: Box<Expression>, clauses: Vec<Annotated<CaseClause>> },
+    Case {
+        val: Box<Expression>,
+        clauses: Vec<Annotated<CaseClause>>,
+    },
     Do(Box<Expression>, Box<Expression>),
-    Try { body: Box<Expression>, then_vars: Vec<Annotated<Variable>>, then: Box<Expression>,
-          catch_vars: Vec<Annotated<Variable>>, catch: Box<Expression> },
-    Receive { clauses: Vec<Annotated<CaseClause>>, timeout_time: Box<Expression>,
-              timeout_body: Box<Expression> },
+    Try {
+        body: Box<Expression>,
+        then_vars: Vec<Annotated<Ident>>,
+        then: Box<Expression>,
+        catch_vars: Vec<Annotated<Ident>>,
+        catch: Box<Expression>,
+    },
+    Receive {
+        clauses: Vec<Annotated<CaseClause>>,
+        timeout_time: Box<Expression>,
+        timeout_body: Box<Expression>,
+    },
 
     // Calling
     PrimOpCall(PrimOpCall),
-    ApplyCall { fun: Box<Expression>, args: Vec<Expression> },
-    InterModuleCall { module: Box<Expression>, name: Box<Expression>, args: Vec<Expression> },
+    ApplyCall {
+        fun: Box<Expression>,
+        args: Vec<Expression>,
+    },
+    InterModuleCall {
+        module: Box<Expression>,
+        name: Box<Expression>,
+        args: Vec<Expression>,
+    },
 
     // Lambda creation
     Fun(Box<Function>),
-    LetRec { funs: Vec<(FunctionName, Function)>, body: Box<Expression> },
+    LetRec {
+        funs: Vec<(FunctionName, Function)>,
+        body: Box<Expression>,
+    },
 
     // Term constructors
     AtomicLiteral(AtomicLiteral),
     Tuple(Vec<Expression>),
-    List { head: Vec<Expression>, tail: Box<Expression> },
-    Map(Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, Option<Expression>),
+    List {
+        head: Vec<Expression>,
+        tail: Box<Expression>,
+    },
+    Map(
+        Vec<Annotated<(Expression, MapExactAssoc, Expression)>>,
+        Option<Expression>,
+    ),
     Binary(Vec<(Expression, Vec<Expression>)>),
 }
 
@@ -153,7 +140,7 @@ pub struct CaseClause {
 #[derive(Debug, Clone)]
 pub enum Pattern {
     Wildcard,
-    BindVar(Annotated<Variable>, Box<Annotated<Pattern>>),
+    BindVar(Annotated<Ident>, Box<Annotated<Pattern>>),
     Atomic(AtomicLiteral),
     Binary(Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>),
     Tuple(Vec<Annotated<Pattern>>),
@@ -161,27 +148,32 @@ pub enum Pattern {
     Map(Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>),
 }
 impl Pattern {
-    pub fn nil() -> Annotated<Pattern> {
-        Annotated::empty(Pattern::Atomic(AtomicTerm::Nil))
+    pub fn nil(span: SourceSpan) -> Annotated<Pattern> {
+        Annotated::empty(Pattern::Atomic(AtomicLiteral::Nil), span)
     }
 }
 
 pub type Expression = Annotated<Vec<Annotated<SingleExpression>>>;
 impl Expression {
-    pub fn nil() -> Self {
-        Annotated::empty(vec![Annotated::empty(SingleExpression::AtomicLiteral(
-            AtomicTerm::Nil))])
+    pub fn nil(span: SourceSpan) -> Self {
+        Annotated::empty(
+            vec![Annotated::empty(
+                SingleExpression::AtomicLiteral(AtomicLiteral::Nil),
+                span,
+            )],
+            span,
+        )
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Function {
-    pub vars: Vec<Annotated<Variable>>,
+    pub vars: Vec<Annotated<Ident>>,
     pub body: Expression,
 }
 
 #[derive(Debug, Clone)]
 pub struct PrimOpCall {
-    pub name: Annotated<Atom>,
+    pub name: Annotated<Ident>,
     pub args: Vec<Expression>,
 }
@@ -1,8 +1,7 @@
-pub use eir::Atom;
-use eir::FunctionIdent;
+pub mod ast;
 
-mod lexer;
 mod parser;
-mod ast;
-mod lower_hir;
+pub use parser::{Parser, ParserError};
 
+mod lower;
+pub use lower::{lower_module, LowerError};
@@ -0,0 +1,125 @@
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::Ident;
+use libeir_ir::{BinaryEntrySpecifier, Endianness, ToPrimitive};
+
+use crate::ast::{Annotated, AtomicLiteral, SingleExpression};
+
+use super::LowerError;
+
+/// A parsed binary segment.
+///
+/// In Core Erlang every binary segment carries exactly four arguments,
+/// `#<Value>(Size, Unit, Type, Flags)`. The compiler always fills in
+/// all of them, so no defaults need to be computed here.
+pub(super) struct Segment<'a> {
+    pub specifier: BinaryEntrySpecifier,
+    /// `None` when the size is `'all'` or `'undefined'`.
+    pub size: Option<&'a Annotated<SingleExpression>>,
+}
+
+fn literal_atom(expr: &SingleExpression) -> Option<Ident> {
+    match expr {
+        SingleExpression::AtomicLiteral(AtomicLiteral::Atom(atom)) => Some(*atom),
+        _ => None,
+    }
+}
+
+/// Flattens a literal list of atoms, as used for segment flags.
+fn literal_atom_list(expr: &SingleExpression, out: &mut Vec<Ident>) -> bool {
+    match expr {
+        SingleExpression::AtomicLiteral(AtomicLiteral::Nil) => true,
+        SingleExpression::List { head, tail } => {
+            for elem in head.iter() {
+                if elem.inner.len() != 1 {
+                    return false;
+                }
+                match literal_atom(&elem.inner[0].inner) {
+                    Some(atom) => out.push(atom),
+                    None => return false,
+                }
+            }
+            if tail.inner.len() != 1 {
+                return false;
+            }
+            literal_atom_list(&tail.inner[0].inner, out)
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn segment<'a>(
+    span: SourceSpan,
+    args: &[&'a Annotated<SingleExpression>],
+) -> Result<Segment<'a>, LowerError> {
+    let err = LowerError::BinaryInvalidSpecifier { span };
+
+    if args.len() != 4 {
+        return Err(err);
+    }
+
+    let size = match literal_atom(&args[0].inner) {
+        Some(atom) if atom.name == "all" || atom.name == "undefined" => None,
+        Some(_) => return Err(err),
+        None => Some(args[0]),
+    };
+
+    let unit = match &args[1].inner {
+        SingleExpression::AtomicLiteral(AtomicLiteral::Integer(int)) => match int.to_i64() {
+            Some(unit) => Some(unit),
+            None => return Err(err),
+        },
+        SingleExpression::AtomicLiteral(AtomicLiteral::Atom(atom)) if atom.name == "undefined" => {
+            None
+        }
+        _ => return Err(err),
+    };
+
+    let typ = match literal_atom(&args[2].inner) {
+        Some(typ) => typ,
+        None => return Err(err),
+    };
+
+    let mut flags = Vec::new();
+    if !literal_atom_list(&args[3].inner, &mut flags) {
+        return Err(err);
+    }
+
+    let signed = flags.iter().any(|f| f.name == "signed");
+    let endianness = if flags.iter().any(|f| f.name == "little") {
+        Endianness::Little
+    } else if flags.iter().any(|f| f.name == "native") {
+        Endianness::Native
+    } else {
+        Endianness::Big
+    };
+
+    let specifier = match &*typ.as_str() {
+        "integer" => BinaryEntrySpecifier::Integer {
+            signed,
+            endianness,
+            unit: unit.unwrap_or(1),
+        },
+        "float" => BinaryEntrySpecifier::Float {
+            endianness,
+            unit: unit.unwrap_or(1),
+        },
+        "binary" => {
+            let unit = unit.unwrap_or(8);
+            if unit % 8 == 0 {
+                BinaryEntrySpecifier::Bytes { unit }
+            } else {
+                BinaryEntrySpecifier::Bits { unit }
+            }
+        }
+        "utf8" => BinaryEntrySpecifier::Utf8,
+        "utf16" => BinaryEntrySpecifier::Utf16 { endianness },
+        "utf32" => BinaryEntrySpecifier::Utf32 { endianness },
+        _ => return Err(err),
+    };
+
+    if size.is_some() && !specifier.has_size() {
+        return Err(err);
+    }
+
+    Ok(Segment { specifier, size })
+}
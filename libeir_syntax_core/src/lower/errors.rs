@@ -0,0 +1,74 @@
+use libeir_diagnostics::{Diagnostic, Label, SourceSpan, ToDiagnostic};
+
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum LowerError {
+    /// Unable to resolve a variable in scope.
+    #[snafu(display("could not resolve variable"))]
+    UnresolvedVariable { span: SourceSpan },
+
+    /// Unable to resolve a local function name in scope.
+    #[snafu(display("could not resolve local function"))]
+    UnresolvedFunction { span: SourceSpan },
+
+    /// The primop is not known to the lowering.
+    #[snafu(display("unsupported primop"))]
+    UnsupportedPrimop { span: SourceSpan },
+
+    /// The primop was called with the wrong number of arguments.
+    #[snafu(display("wrong number of arguments to primop"))]
+    PrimopArity { span: SourceSpan, expected: usize },
+
+    /// A value list was used where a single value is required.
+    #[snafu(display("value list of wrong arity"))]
+    ValueListArity { span: SourceSpan, expected: usize },
+
+    /// A binary segment had an invalid set of arguments.
+    #[snafu(display("invalid binary segment specifier"))]
+    BinaryInvalidSpecifier { span: SourceSpan },
+
+    /// An expression is not allowed at this place in a pattern.
+    #[snafu(display("an invalid expression occurred in a pattern"))]
+    NotAllowedInPattern { span: SourceSpan },
+}
+
+impl ToDiagnostic for LowerError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let msg = self.to_string();
+        match self {
+            LowerError::UnresolvedVariable { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("not bound in scope")
+                ]),
+            LowerError::UnresolvedFunction { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("not bound in scope")
+                ]),
+            LowerError::UnsupportedPrimop { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("unknown primop")
+                ]),
+            LowerError::PrimopArity { span, expected } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("expected {} arguments", expected))]),
+            LowerError::ValueListArity { span, expected } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("expected {} values", expected))]),
+            LowerError::BinaryInvalidSpecifier { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("invalid specifier")
+                ]),
+            LowerError::NotAllowedInPattern { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("disallowed expression in pattern")]),
+        }
+    }
+}
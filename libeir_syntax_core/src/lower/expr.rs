@@ -0,0 +1,742 @@
+use libeir_diagnostics::SourceSpan;
+use libeir_intern::{Ident, Symbol};
+use libeir_ir::operation::binary_construct::{
+    BinaryConstructFinish, BinaryConstructPush, BinaryConstructStart,
+};
+use libeir_ir::operation::case::{Case, CaseBuilder};
+use libeir_ir::operation::receive::{ReceiveDone, ReceiveStart, ReceiveWait};
+use libeir_ir::{
+    Block as IrBlock, Const, ConstantContainer, EmptyMap, FunctionBuilder, MapPutUpdate,
+    Value as IrValue,
+};
+
+use crate::ast::{
+    Annotated, AtomicLiteral, CaseClause, Constant, Expression, FunctionName, MapExactAssoc,
+    Pattern, PrimOpCall, SingleExpression,
+};
+
+use super::binary;
+use super::pattern::lower_clause;
+use super::{lower_function, lower_letrec_function, LowerCtx, LowerError};
+
+pub(super) fn lower_atomic(c: &mut ConstantContainer, literal: &AtomicLiteral) -> Const {
+    match literal {
+        AtomicLiteral::Integer(int) => c.from(int.clone()),
+        AtomicLiteral::Float(float) => c.from(*float),
+        AtomicLiteral::Atom(atom) => c.from(*atom),
+        AtomicLiteral::Nil => c.nil(),
+        AtomicLiteral::Char(chr) => c.from(*chr),
+        AtomicLiteral::String(string) => {
+            let chars: Vec<char> = string.as_str().chars().collect();
+            let mut acc = c.nil();
+            for chr in chars.iter().rev() {
+                let head = c.from(*chr);
+                acc = c.list_cell(head, acc);
+            }
+            acc
+        }
+    }
+}
+
+pub(super) fn lower_constant(c: &mut ConstantContainer, constant: &Constant) -> Const {
+    match constant {
+        Constant::Atomic(literal) => lower_atomic(c, literal),
+        Constant::Tuple(elems) => {
+            let mut builder = c.tuple_builder();
+            for elem in elems.iter() {
+                let elem = lower_constant(c, elem);
+                builder.push(elem, c);
+            }
+            builder.finish(c)
+        }
+        Constant::List(head, tail) => {
+            let mut acc = lower_constant(c, tail);
+            for elem in head.iter().rev() {
+                let elem = lower_constant(c, elem);
+                acc = c.list_cell(elem, acc);
+            }
+            acc
+        }
+    }
+}
+
+/// Lowers an expression that may evaluate to a value list.
+/// Value lists of more than one element are packed with
+/// `prim_value_list`.
+pub(super) fn lower_expr(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    expr: &Expression,
+) -> (IrBlock, IrValue) {
+    if expr.inner.len() == 1 {
+        return lower_single(ctx, b, block, &expr.inner[0]);
+    }
+
+    let mut values = Vec::with_capacity(expr.inner.len());
+    for single in expr.inner.iter() {
+        values.push(map_block!(block, lower_single(ctx, b, block, single)));
+    }
+    (block, b.prim_value_list(&values))
+}
+
+/// Lowers an expression that is required to evaluate to a single value.
+pub(super) fn lower_expr_single(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    block: IrBlock,
+    expr: &Expression,
+) -> (IrBlock, IrValue) {
+    if expr.inner.len() == 1 {
+        lower_single(ctx, b, block, &expr.inner[0])
+    } else {
+        ctx.error(LowerError::ValueListArity {
+            span: expr.span,
+            expected: 1,
+        });
+        (block, ctx.sentinel())
+    }
+}
+
+pub(super) fn lower_single(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    expr: &Annotated<SingleExpression>,
+) -> (IrBlock, IrValue) {
+    let span = expr.span;
+    match &expr.inner {
+        SingleExpression::FunctionName(name) => {
+            let val = lower_function_name(ctx, b, span, name);
+            (block, val)
+        }
+        SingleExpression::ExternalFunctionName { module, name } => {
+            let val = b.prim_capture_function(span, *module, name.name, name.arity);
+            (block, val)
+        }
+        SingleExpression::Variable(var) => (block, ctx.resolve(*var)),
+
+        SingleExpression::Let { vars, val, body } => {
+            ctx.scope_push();
+            if vars.len() > 1 && val.inner.len() == vars.len() {
+                // Values are bound directly when the arity is known
+                // syntactically, no need to go through a value list.
+                for (var, single) in vars.iter().zip(val.inner.iter()) {
+                    let val = map_block!(block, lower_single(ctx, b, block, single));
+                    ctx.bind(var.inner, val);
+                }
+            } else {
+                let val = map_block!(block, lower_expr(ctx, b, block, val));
+                block = bind_values(ctx, b, block, vars, val);
+            }
+            let ret = map_block!(block, lower_expr(ctx, b, block, body));
+            ctx.scope_pop();
+            (block, ret)
+        }
+        SingleExpression::Catch(body) => lower_catch(ctx, b, block, span, body),
+        SingleExpression::Case { val, clauses } => lower_case(ctx, b, block, span, val, clauses),
+        SingleExpression::Do(first, second) => {
+            let _ = map_block!(block, lower_expr(ctx, b, block, first));
+            lower_expr(ctx, b, block, second)
+        }
+        SingleExpression::Try {
+            body,
+            then_vars,
+            then,
+            catch_vars,
+            catch,
+        } => lower_try(
+            ctx, b, block, span, body, then_vars, then, catch_vars, catch,
+        ),
+        SingleExpression::Receive {
+            clauses,
+            timeout_time,
+            timeout_body,
+        } => lower_receive(ctx, b, block, span, clauses, timeout_time, timeout_body),
+
+        SingleExpression::PrimOpCall(call) => lower_primop(ctx, b, block, span, call),
+        SingleExpression::ApplyCall { fun, args } => {
+            let fun_val = map_block!(block, lower_expr_single(ctx, b, block, fun));
+            let args = map_block!(block, lower_args(ctx, b, block, args));
+            ctx.call_value(b, block, span, fun_val, &args)
+        }
+        SingleExpression::InterModuleCall { module, name, args } => {
+            let module_val = map_block!(block, lower_expr_single(ctx, b, block, module));
+            let name_val = map_block!(block, lower_expr_single(ctx, b, block, name));
+            let args = map_block!(block, lower_args(ctx, b, block, args));
+            ctx.call_function(b, block, span, module_val, name_val, &args)
+        }
+
+        SingleExpression::Fun(fun) => (block, lower_function(ctx, b, span, fun)),
+        SingleExpression::LetRec { funs, body } => {
+            ctx.scope_push();
+
+            // All functions in a letrec are in scope in each other's
+            // bodies, bind them all before lowering any of them.
+            let mut entries = Vec::with_capacity(funs.len());
+            for (name, _fun) in funs.iter() {
+                let entry = b.block_insert_with_span(Some(span));
+                let entry_val = b.value(entry);
+                ctx.bind_fun(name, entry_val);
+                entries.push(entry);
+            }
+            for ((name, fun), entry) in funs.iter().zip(entries.iter()) {
+                lower_letrec_function(ctx, b, *entry, name, fun);
+            }
+
+            let ret = map_block!(block, lower_expr(ctx, b, block, body));
+            ctx.scope_pop();
+            (block, ret)
+        }
+
+        SingleExpression::AtomicLiteral(literal) => {
+            let constant = lower_atomic(b.cons_mut(), literal);
+            (block, b.value(constant))
+        }
+        SingleExpression::Tuple(elems) => {
+            let elems = map_block!(block, lower_args(ctx, b, block, elems));
+            (block, b.prim_tuple(span, &elems))
+        }
+        SingleExpression::List { head, tail } => {
+            let head = map_block!(block, lower_args(ctx, b, block, head));
+            let mut acc = map_block!(block, lower_expr_single(ctx, b, block, tail));
+            for elem in head.iter().rev() {
+                acc = b.prim_list_cell(span, *elem, acc);
+            }
+            (block, acc)
+        }
+        SingleExpression::Map(entries, base) => lower_map(ctx, b, block, span, entries, base),
+        SingleExpression::Binary(segments) => lower_binary(ctx, b, block, span, segments),
+    }
+}
+
+fn lower_args(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    args: &[Expression],
+) -> (IrBlock, Vec<IrValue>) {
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args.iter() {
+        values.push(map_block!(block, lower_expr_single(ctx, b, block, arg)));
+    }
+    (block, values)
+}
+
+fn lower_function_name(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    span: SourceSpan,
+    name: &FunctionName,
+) -> IrValue {
+    if let Some(local) = ctx.resolve_fun(name) {
+        local
+    } else {
+        let module = ctx.module.name;
+        b.prim_capture_function(span, module, name.name, name.arity)
+    }
+}
+
+/// Binds the variables to the given value, unpacking it if it is a
+/// value list.
+fn bind_values(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    block: IrBlock,
+    vars: &[Annotated<Ident>],
+    val: IrValue,
+) -> IrBlock {
+    if vars.len() == 1 {
+        ctx.bind(vars[0].inner, val);
+        block
+    } else {
+        let cont = b.op_unpack_value_list(block, val, vars.len());
+        for (idx, var) in vars.iter().enumerate() {
+            let val = b.block_args(cont)[idx];
+            ctx.bind(var.inner, val);
+        }
+        cont
+    }
+}
+
+fn lower_case(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    val: &Expression,
+    clauses: &[Annotated<CaseClause>],
+) -> (IrBlock, IrValue) {
+    let mut values = Vec::with_capacity(val.inner.len());
+    for single in val.inner.iter() {
+        values.push(map_block!(block, lower_single(ctx, b, block, single)));
+    }
+    let match_val = if values.len() == 1 {
+        values[0]
+    } else {
+        b.prim_value_list(&values)
+    };
+
+    let join_block = b.block_insert();
+    let join_arg = b.block_arg_insert(join_block);
+
+    // The compiler always makes case expressions exhaustive by inserting
+    // a final clause that raises the appropriate error. This only covers
+    // hand written code.
+    let no_match = b.block_insert();
+    if values.len() == 1 {
+        let typ_val = b.value(Symbol::intern("error"));
+        let case_clause_val = b.value(Symbol::intern("case_clause"));
+        let err_val = b.prim_tuple(span, &[case_clause_val, match_val]);
+        ctx.exc_stack
+            .make_error_jump(b, span, no_match, typ_val, err_val);
+    } else {
+        b.op_unreachable(span, no_match);
+    }
+
+    let mut case_b = Case::builder();
+    case_b.set_span(span);
+    case_b.match_on = Some(match_val);
+    case_b.no_match = Some(b.value(no_match));
+
+    let entry_exc_height = ctx.exc_stack.len();
+
+    for clause in clauses.iter() {
+        let lowered = lower_clause(ctx, &mut case_b.container, b, &mut block, clause);
+        let body = lowered.make_body(ctx, b);
+
+        // Variables bound directly to a caught trace keep track of
+        // the exception class, so that a later `raise` can use it.
+        if clause.inner.patterns.len() == values.len() {
+            for (pattern, value) in clause.inner.patterns.iter().zip(values.iter()) {
+                if let Pattern::BindVar(var, _) = &pattern.inner {
+                    if let Some(class) = ctx.trace_classes.get(value).cloned() {
+                        let bound = ctx.resolve(var.inner);
+                        ctx.trace_classes.insert(bound, class);
+                    }
+                }
+            }
+        }
+
+        // Add to case
+        let body_val = b.value(body);
+        case_b.push_clause(lowered.clause, lowered.guard, body_val, b);
+        for value in lowered.values.iter() {
+            case_b.push_value(*value, b);
+        }
+
+        let (body_ret_block, body_ret) = lower_expr(ctx, b, body, &clause.inner.body);
+
+        // Call to join block
+        b.op_call_flow(body_ret_block, join_block, &[body_ret]);
+
+        // Pop scope pushed in make_body
+        ctx.scope_pop();
+
+        assert!(ctx.exc_stack.len() == entry_exc_height)
+    }
+
+    case_b.finish(block, b);
+
+    (join_block, join_arg)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lower_try(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    body: &Expression,
+    then_vars: &[Annotated<Ident>],
+    then: &Expression,
+    catch_vars: &[Annotated<Ident>],
+    catch: &Expression,
+) -> (IrBlock, IrValue) {
+    let exc_block = b.block_insert();
+
+    let exc_type = b.block_arg_insert(exc_block);
+    let exc_error = b.block_arg_insert(exc_block);
+    let exc_trace = b.block_arg_insert(exc_block);
+
+    // Lower body while catching exceptions
+    ctx.exc_stack.push_handler(b.value(exc_block));
+    let body_ret = map_block!(block, lower_expr(ctx, b, block, body));
+    ctx.exc_stack.pop_handler();
+
+    let join_block = b.block_insert();
+    let join_arg = b.block_arg_insert(join_block);
+
+    // Of branch
+    {
+        ctx.scope_push();
+        block = bind_values(ctx, b, block, then_vars, body_ret);
+        let ret = map_block!(block, lower_expr(ctx, b, block, then));
+        b.op_call_flow(block, join_block, &[ret]);
+        ctx.scope_pop();
+    }
+
+    // Catch branch
+    {
+        ctx.scope_push();
+        if catch_vars.len() == 3 {
+            ctx.bind(catch_vars[0].inner, exc_type);
+            ctx.bind(catch_vars[1].inner, exc_error);
+            ctx.bind(catch_vars[2].inner, exc_trace);
+        } else {
+            ctx.error(LowerError::ValueListArity { span, expected: 3 });
+        }
+        ctx.trace_classes.insert(exc_trace, exc_type);
+
+        let (catch_block, ret) = lower_expr(ctx, b, exc_block, catch);
+        b.op_call_flow(catch_block, join_block, &[ret]);
+        ctx.scope_pop();
+    }
+
+    (join_block, join_arg)
+}
+
+fn lower_catch(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    body: &Expression,
+) -> (IrBlock, IrValue) {
+    let exc_block = b.block_insert();
+
+    let exc_type = b.block_arg_insert(exc_block);
+    let exc_error = b.block_arg_insert(exc_block);
+    let exc_trace = b.block_arg_insert(exc_block);
+
+    let mut case_b = Case::builder();
+    case_b.set_span(span);
+
+    // Atoms
+    let big_exit_atom = b.value(Symbol::intern("EXIT"));
+
+    let make_value_clause = |b: &mut FunctionBuilder, case_b: &mut CaseBuilder, val: IrValue| {
+        let clause = case_b.container.clause_start(span);
+
+        // Value
+        case_b.push_value(val, b);
+        let pat_val = case_b.container.clause_value(clause);
+
+        let pat = case_b.container.node_empty(Some(span));
+        case_b.container.value(pat, pat_val);
+
+        case_b.container.clause_node_push(clause, pat);
+        case_b.container.clause_finish(clause);
+        clause
+    };
+
+    let error_atom = b.value(Symbol::intern("error"));
+    let error_clause = make_value_clause(b, &mut case_b, error_atom);
+
+    let exit_atom = b.value(Symbol::intern("exit"));
+    let exit_clause = make_value_clause(b, &mut case_b, exit_atom);
+
+    let throw_atom = b.value(Symbol::intern("throw"));
+    let throw_clause = make_value_clause(b, &mut case_b, throw_atom);
+
+    // Join block
+    let join_block = b.block_insert();
+    let join_arg = b.block_arg_insert(join_block);
+
+    // Lower body while catching exceptions
+    ctx.exc_stack.push_handler(b.value(exc_block));
+    let body_ret = map_block!(block, lower_expr(ctx, b, block, body));
+    ctx.exc_stack.pop_handler();
+    b.op_call_flow(block, join_block, &[body_ret]);
+
+    // no_match is unreachable
+    let no_match = b.block_insert();
+    b.op_unreachable(span, no_match);
+
+    // Guard lambda returning true
+    let guard = b.block_insert();
+    let guard_val = b.value(guard);
+    let guard_cont = b.block_arg_insert(guard);
+    let _guard_throw_cont = b.block_arg_insert(guard);
+    let true_val = b.value(true);
+    b.op_call_flow(guard, guard_cont, &[true_val]);
+
+    // Actual case
+    case_b.match_on = Some(exc_type);
+    case_b.no_match = Some(b.value(no_match));
+
+    // Error branch
+    {
+        let error_block = b.block_insert();
+        let error_block_val = b.value(error_block);
+        case_b.push_clause(error_clause, guard_val, error_block_val, b);
+
+        let inner_tup = b.prim_tuple(span, &[exc_error, exc_trace]);
+        let ret_tup = b.prim_tuple(span, &[big_exit_atom, inner_tup]);
+
+        b.op_call_flow(error_block, join_block, &[ret_tup]);
+    }
+
+    // Exit branch
+    {
+        let exit_block = b.block_insert();
+        let exit_block_val = b.value(exit_block);
+        case_b.push_clause(exit_clause, guard_val, exit_block_val, b);
+
+        let ret_tup = b.prim_tuple(span, &[big_exit_atom, exc_error]);
+
+        b.op_call_flow(exit_block, join_block, &[ret_tup]);
+    }
+
+    // Throw branch
+    {
+        let throw_block = b.block_insert();
+        let throw_block_val = b.value(throw_block);
+        case_b.push_clause(throw_clause, guard_val, throw_block_val, b);
+
+        b.op_call_flow(throw_block, join_block, &[exc_error]);
+    }
+
+    case_b.finish(exc_block, b);
+
+    (join_block, join_arg)
+}
+
+fn lower_receive(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    clauses: &[Annotated<CaseClause>],
+    timeout_time: &Expression,
+    timeout_body: &Expression,
+) -> (IrBlock, IrValue) {
+    let join_block = b.block_insert();
+    let join_arg = b.block_arg_insert(join_block);
+
+    // The timeout time
+    let timeout_val = map_block!(block, lower_expr_single(ctx, b, block, timeout_time));
+
+    // Receive start
+    let (recv_wait_block, invalid_timeout_block) = ReceiveStart::build(b, block, timeout_val);
+    let recv_ref_val = b.block_args(recv_wait_block)[0];
+
+    // Invalid timeout, raised in the enclosing scope
+    {
+        let typ_val = b.value(Symbol::intern("error"));
+        let timeout_value_val = b.value(Symbol::intern("timeout_value"));
+        ctx.exc_stack
+            .make_error_jump(b, span, invalid_timeout_block, typ_val, timeout_value_val);
+    }
+
+    // Receive wait
+    let (after_block, mut body_block) = ReceiveWait::build(b, recv_wait_block, recv_ref_val);
+    let body_message_arg = b.block_args(body_block)[0];
+
+    // Timeout body
+    let (after_ret_block, after_ret) = lower_expr(ctx, b, after_block, timeout_body);
+    b.op_call_flow(after_ret_block, join_block, &[after_ret]);
+
+    // When no clause matches, the message is left in the mailbox and
+    // we wait for the next one.
+    let no_match = b.block_insert();
+    b.op_call_flow(no_match, recv_wait_block, &[recv_ref_val]);
+
+    let mut case_b = Case::builder();
+    case_b.set_span(span);
+    case_b.match_on = Some(body_message_arg);
+    case_b.no_match = Some(b.value(no_match));
+
+    let entry_exc_height = ctx.exc_stack.len();
+
+    for clause in clauses.iter() {
+        let lowered = lower_clause(ctx, &mut case_b.container, b, &mut body_block, clause);
+
+        ctx.scope_push();
+        let body = b.block_insert();
+
+        // Map all matched values through receive_done.
+        // This enables us to do things like copy from
+        // a heap fragment to the main process heap.
+        let values: Vec<_> = lowered
+            .binds
+            .iter()
+            .map(|_bind| b.block_arg_insert(body))
+            .collect();
+        let body_mapped = ReceiveDone::build(b, body, recv_ref_val, &values);
+
+        for (idx, bind) in lowered.binds.iter().enumerate() {
+            let mapped_val = b.block_args(body_mapped)[idx];
+            ctx.bind(*bind, mapped_val);
+        }
+
+        // Add to case
+        let body_val = b.value(body);
+        case_b.push_clause(lowered.clause, lowered.guard, body_val, b);
+        for value in lowered.values.iter() {
+            case_b.push_value(*value, b);
+        }
+
+        let (body_ret_block, body_ret) = lower_expr(ctx, b, body_mapped, &clause.inner.body);
+
+        // Call to join block
+        b.op_call_flow(body_ret_block, join_block, &[body_ret]);
+
+        ctx.scope_pop();
+
+        assert!(ctx.exc_stack.len() == entry_exc_height)
+    }
+
+    case_b.finish(body_block, b);
+
+    (join_block, join_arg)
+}
+
+fn lower_primop(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    call: &PrimOpCall,
+) -> (IrBlock, IrValue) {
+    let args = map_block!(block, lower_args(ctx, b, block, &call.args));
+
+    let name = call.name.inner;
+    let expected = match &*name.as_str() {
+        "match_fail" => 1,
+        "raise" => 2,
+        "raw_raise" => 3,
+        _ => {
+            ctx.error(LowerError::UnsupportedPrimop {
+                span: call.name.span,
+            });
+            return (block, ctx.sentinel());
+        }
+    };
+    if args.len() != expected {
+        ctx.error(LowerError::PrimopArity { span, expected });
+        return (block, ctx.sentinel());
+    }
+
+    match &*name.as_str() {
+        "match_fail" => {
+            let typ_val = b.value(Symbol::intern("error"));
+            ctx.exc_stack
+                .make_error_jump(b, span, block, typ_val, args[0]);
+        }
+        "raise" => {
+            // If we don't know where the trace came from, the best we
+            // can do is to rethrow it as an error.
+            let class = match ctx.trace_classes.get(&args[0]) {
+                Some(class) => *class,
+                None => b.value(Symbol::intern("error")),
+            };
+            ctx.exc_stack
+                .make_error_jump_trace(b, block, class, args[1], args[0]);
+        }
+        "raw_raise" => {
+            ctx.exc_stack
+                .make_error_jump_trace(b, block, args[0], args[1], args[2]);
+        }
+        _ => unreachable!(),
+    }
+
+    // All the supported primops raise, control flow never continues.
+    let cont = b.block_insert();
+    let cont_val = b.block_arg_insert(cont);
+    (cont, cont_val)
+}
+
+fn lower_map(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    entries: &[Annotated<(Expression, MapExactAssoc, Expression)>],
+    base: &Option<Expression>,
+) -> (IrBlock, IrValue) {
+    let base_val = match base {
+        Some(base) => map_block!(block, lower_expr_single(ctx, b, block, base)),
+        None => b.value(EmptyMap),
+    };
+    let mut map_builder = b.op_map_put_build(span, base_val);
+
+    for entry in entries.iter() {
+        let (key, assoc, value) = &entry.inner;
+        let action = match assoc {
+            MapExactAssoc::Assoc => MapPutUpdate::Put,
+            MapExactAssoc::Exact => MapPutUpdate::Update,
+        };
+
+        let key_val = map_block!(block, lower_expr_single(ctx, b, block, key));
+        let value_val = map_block!(block, lower_expr_single(ctx, b, block, value));
+        map_builder.push_kv(key_val, value_val, action, b);
+    }
+
+    let loc = ctx.current_location(b, span);
+    b.block_set_location(block, loc);
+    let (ok, fail) = map_builder.finish(block, b);
+
+    let typ_val = b.value(Symbol::intern("error"));
+    let badkey_val = b.value(Symbol::intern("badkey"));
+    let failed_key = b.block_args(fail)[0];
+    let err_val = b.prim_tuple(span, &[badkey_val, failed_key]);
+    ctx.exc_stack
+        .make_error_jump(b, span, fail, typ_val, err_val);
+
+    (ok, b.block_args(ok)[0])
+}
+
+fn lower_binary(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    mut block: IrBlock,
+    span: SourceSpan,
+    segments: &[(Expression, Vec<Expression>)],
+) -> (IrBlock, IrValue) {
+    block = BinaryConstructStart::build(b, block);
+    let mut bin_ref = b.block_args(block)[0];
+
+    for (value, args) in segments.iter() {
+        let single_args: Vec<_> = args
+            .iter()
+            .filter(|arg| arg.inner.len() == 1)
+            .map(|arg| &arg.inner[0])
+            .collect();
+        if single_args.len() != args.len() {
+            ctx.error(LowerError::BinaryInvalidSpecifier { span: value.span });
+            continue;
+        }
+
+        let segment = match binary::segment(value.span, &single_args) {
+            Ok(segment) => segment,
+            Err(err) => {
+                ctx.error(err);
+                continue;
+            }
+        };
+
+        let value_val = map_block!(block, lower_expr_single(ctx, b, block, value));
+        let size_val = match segment.size {
+            Some(size) => Some(map_block!(block, lower_single(ctx, b, block, size))),
+            None => None,
+        };
+
+        let (ok, fail) =
+            BinaryConstructPush::build(b, block, bin_ref, value_val, segment.specifier, size_val);
+        bin_ref = b.block_args(ok)[0];
+        block = ok;
+
+        let typ_val = b.value(Symbol::intern("error"));
+        let badarg_val = b.value(Symbol::intern("badarg"));
+        ctx.exc_stack
+            .make_error_jump(b, value.span, fail, typ_val, badarg_val);
+    }
+
+    let loc = ctx.current_location(b, span);
+    b.block_set_location(block, loc);
+    block = BinaryConstructFinish::build(b, block, bin_ref);
+    let res = b.block_args(block)[0];
+
+    (block, res)
+}
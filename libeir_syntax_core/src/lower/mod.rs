@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use libeir_ir::{
+    AtomicTerm, Block as IrBlock, Const, ConstKind, ConstantContainer, FunctionBuilder, IntoValue,
+    Location, Module as IrModule, Value as IrValue,
+};
+
+use libeir_diagnostics::{CodeMap, SourceSpan};
+use libeir_intern::{Ident, Symbol};
+use libeir_util_datastructures::hashmap_stack::HashMapStack;
+use libeir_util_parse::ErrorReceiver;
+
+use crate::ast::{Function, FunctionDefinition, FunctionName, Module};
+
+macro_rules! map_block {
+    ($block:ident, $call:expr) => {{
+        let (block, val) = $call;
+        $block = block;
+        val
+    }};
+}
+
+mod expr;
+use expr::{lower_constant, lower_expr_single};
+
+mod binary;
+mod pattern;
+
+mod errors;
+pub use errors::LowerError;
+
+mod exception_handler_stack;
+use exception_handler_stack::ExceptionHandlerStack;
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) struct LowerCtx<'a> {
+    codemap: Arc<CodeMap>,
+    module: &'a Module,
+
+    /// Variables bound in the current scope.
+    scope: HashMapStack<Ident, IrValue>,
+    /// Local functions bound by `letrec` in the current scope.
+    fun_scope: HashMapStack<(Symbol, usize), IrValue>,
+
+    exc_stack: ExceptionHandlerStack,
+
+    /// Core Erlang passes around the class of an exception separately
+    /// from the raw trace, and `raise` only receives the trace. When
+    /// an exception is caught, we remember which class value belongs
+    /// to the trace so that it can be rethrown with the right class.
+    trace_classes: HashMap<IrValue, IrValue>,
+
+    sentinel_value: Option<IrValue>,
+
+    errors: &'a mut (dyn ErrorReceiver<E = LowerError, W = LowerError> + 'a),
+
+    fun_num: usize,
+    /// Top is current function name.
+    /// Used to generate debug info.
+    functions: Vec<String>,
+}
+
+impl<'a> LowerCtx<'a> {
+    /// Dummy value used in place of values that failed to lower.
+    /// If this value is used in the resulting IR, it is REQUIRED that
+    /// `error` be called at least once, which sets the error receiver
+    /// to the failed state.
+    pub fn sentinel(&self) -> IrValue {
+        self.sentinel_value.unwrap()
+    }
+
+    pub fn error(&mut self, err: LowerError) {
+        self.errors.error(err);
+    }
+
+    pub fn failed(&self) -> bool {
+        self.errors.is_failed()
+    }
+
+    pub fn scope_push(&mut self) {
+        self.scope.push();
+        self.fun_scope.push();
+    }
+
+    pub fn scope_pop(&mut self) {
+        self.scope.pop();
+        self.fun_scope.pop();
+    }
+
+    pub fn bind(&mut self, ident: Ident, val: IrValue) {
+        self.scope.insert(ident, val);
+    }
+
+    pub fn resolve(&mut self, ident: Ident) -> IrValue {
+        match self.scope.get(&ident) {
+            Some(val) => *val,
+            None => {
+                self.error(LowerError::UnresolvedVariable { span: ident.span });
+                self.sentinel()
+            }
+        }
+    }
+
+    pub fn bind_fun(&mut self, name: &FunctionName, val: IrValue) {
+        self.fun_scope.insert((name.name.name, name.arity), val);
+    }
+
+    /// Local functions bound by `letrec` shadow module level functions.
+    pub fn resolve_fun(&self, name: &FunctionName) -> Option<IrValue> {
+        self.fun_scope.get(&(name.name.name, name.arity)).cloned()
+    }
+
+    pub fn function_name(&self) -> String {
+        self.functions[self.functions.len() - 1].clone()
+    }
+    pub fn current_location(&self, b: &mut FunctionBuilder, span: SourceSpan) -> Location {
+        b.fun_mut().locations.from_bytespan(
+            &self.codemap,
+            span,
+            Some((self.module.name.as_str().to_string(), self.function_name())),
+        )
+    }
+
+    pub fn call_function<M, F>(
+        &mut self,
+        b: &mut FunctionBuilder,
+        block: IrBlock,
+        span: SourceSpan,
+        m: M,
+        f: F,
+        args: &[IrValue],
+    ) -> (IrBlock, IrValue)
+    where
+        M: IntoValue,
+        F: IntoValue,
+    {
+        let fun_val = b.prim_capture_function(span, m, f, args.len());
+        self.call_value(b, block, span, fun_val, args)
+    }
+
+    pub fn call_value(
+        &mut self,
+        b: &mut FunctionBuilder,
+        block: IrBlock,
+        span: SourceSpan,
+        fun_val: IrValue,
+        args: &[IrValue],
+    ) -> (IrBlock, IrValue) {
+        let loc = self.current_location(b, span);
+        b.block_set_location(block, loc);
+
+        let (ok_block, fail_block) = b.op_call_function(span, block, fun_val, args);
+
+        let fail_type = b.block_args(fail_block)[0];
+        let fail_error = b.block_args(fail_block)[1];
+        let fail_trace = b.block_args(fail_block)[2];
+        self.exc_stack
+            .make_error_jump_trace(b, fail_block, fail_type, fail_error, fail_trace);
+
+        let ok_res = b.block_args(ok_block)[0];
+
+        (ok_block, ok_res)
+    }
+}
+
+pub fn lower_module<'a>(
+    errors: &'a mut (dyn ErrorReceiver<E = LowerError, W = LowerError> + 'a),
+    codemap: Arc<CodeMap>,
+    module: &Module,
+) -> Result<IrModule, ()> {
+    let mut ir_module = IrModule::new_with_span(module.name, module.span);
+
+    let mut ctx = LowerCtx {
+        codemap,
+        module,
+
+        scope: HashMapStack::new(),
+        fun_scope: HashMapStack::new(),
+
+        exc_stack: ExceptionHandlerStack::new(),
+        trace_classes: HashMap::new(),
+
+        sentinel_value: None,
+
+        errors,
+
+        fun_num: 0,
+        functions: Vec::new(),
+    };
+
+    for definition in module.definitions.iter() {
+        assert!(ctx.scope.height() == 0);
+        ctx.fun_num = 0;
+
+        let name = &definition.name.inner;
+        let fun_def = ir_module.add_function(definition.fun.span, name.name, name.arity);
+        let mut fun = fun_def.function_mut();
+        let mut builder = FunctionBuilder::new(&mut fun);
+
+        // We do not want the sentinel value to be a constant,
+        // since that would interfere with potential constant
+        // comparisons while lowering. Insert an orphaned block
+        // with an argument that we use.
+        let sentinel_block = builder.block_insert();
+        let sentinel_value = builder.block_arg_insert(sentinel_block);
+        ctx.sentinel_value = Some(sentinel_value);
+
+        lower_top_function(&mut ctx, &mut builder, definition);
+
+        ctx.trace_classes.clear();
+    }
+
+    for export in module.declarations.iter() {
+        match ir_module.name_arity_index(export.name.name, export.arity) {
+            Some(index) => ir_module[index].set_exported(true),
+            None => ctx.error(LowerError::UnresolvedFunction {
+                span: export.name.span,
+            }),
+        }
+    }
+
+    for (name, value) in module.attributes.iter() {
+        let value = lower_constant(ir_module.cons_mut(), value);
+        if name.name.as_str() == "on_load" {
+            // The compiler writes this as `'on_load' = [{'init', 0}]`
+            let on_load = on_load_function(ir_module.cons(), value)
+                .and_then(|(fun, arity)| ir_module.name_arity_index(fun, arity));
+            ir_module.set_on_load(on_load);
+        }
+        ir_module.add_attribute(*name, value);
+    }
+
+    ctx.exc_stack.finish();
+
+    if ctx.failed() {
+        Err(())
+    } else {
+        Ok(ir_module)
+    }
+}
+
+fn on_load_function(c: &ConstantContainer, value: Const) -> Option<(Symbol, usize)> {
+    let entry = match c.const_kind(value) {
+        ConstKind::ListCell { head, .. } => *head,
+        _ => value,
+    };
+    match c.const_kind(entry) {
+        ConstKind::Tuple { entries } => match entries.as_slice(&c.const_pool) {
+            [name, arity] => match (c.const_kind(*name), c.const_kind(*arity)) {
+                (
+                    ConstKind::Atomic(AtomicTerm::Atom(name)),
+                    ConstKind::Atomic(AtomicTerm::Int(arity)),
+                ) => Some((name.0, arity.value() as usize)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Lowers an anonymous function, returning the value of its entry block.
+fn lower_function(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    span: SourceSpan,
+    fun: &Function,
+) -> IrValue {
+    let entry = b.block_insert_with_span(Some(span));
+
+    ctx.fun_num += 1;
+    let base_fun = &ctx.functions[0];
+    let new_fun = format!("{}-fun-{}", base_fun, ctx.fun_num);
+    ctx.functions.push(new_fun);
+
+    lower_function_base(ctx, b, entry, fun);
+
+    ctx.functions.pop().unwrap();
+
+    b.value(entry)
+}
+
+/// Lowers a `letrec` bound function into an already created entry block.
+fn lower_letrec_function(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    entry: IrBlock,
+    name: &FunctionName,
+    fun: &Function,
+) {
+    let base_fun = &ctx.functions[0];
+    let new_fun = format!("{}-{}/{}", base_fun, name.name, name.arity);
+    ctx.functions.push(new_fun);
+
+    lower_function_base(ctx, b, entry, fun);
+
+    ctx.functions.pop().unwrap();
+}
+
+fn lower_function_base(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    // The block the function should be lowered into
+    entry: IrBlock,
+    fun: &Function,
+) {
+    // Ok and Fail continuations
+    let ok_cont = b.block_arg_insert(entry);
+    let err_cont = b.block_arg_insert(entry);
+
+    ctx.exc_stack.push_handler(err_cont);
+    ctx.scope_push();
+
+    // Core Erlang functions only take plain variables as arguments,
+    // all matching is done explicitly by case expressions in the body.
+    for var in fun.vars.iter() {
+        let arg = b.block_arg_insert(entry);
+        ctx.bind(var.inner, arg);
+    }
+
+    let (ret_block, ret) = lower_expr_single(ctx, b, entry, &fun.body);
+    b.op_call_flow(ret_block, ok_cont, &[ret]);
+
+    ctx.scope_pop();
+    ctx.exc_stack.pop_handler();
+}
+
+fn lower_top_function(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    definition: &FunctionDefinition,
+) {
+    let entry = b.block_insert();
+    b.block_set_entry(entry);
+
+    let name = &definition.name.inner;
+    let fun_name = format!("{}/{}", name.name, name.arity);
+    assert!(ctx.functions.len() == 0);
+    ctx.functions.push(fun_name);
+
+    lower_function_base(ctx, b, entry, &definition.fun.inner);
+
+    ctx.functions.pop().unwrap();
+    assert!(ctx.functions.len() == 0);
+}
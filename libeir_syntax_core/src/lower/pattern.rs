@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use libeir_intern::Ident;
+use libeir_ir::pattern::{PatternClause, PatternContainer, PatternNode, PatternValue};
+use libeir_ir::{Block as IrBlock, FunctionBuilder, Value as IrValue};
+
+use crate::ast::{Annotated, CaseClause, Expression, Pattern, SingleExpression};
+
+use super::binary;
+use super::expr::{lower_atomic, lower_expr_single, lower_single};
+use super::LowerCtx;
+
+struct ClauseLowerCtx {
+    pat_clause: PatternClause,
+
+    /// Corresponds to PatternValues in the clause
+    values: Vec<IrValue>,
+
+    /// When values are bound when lowering patterns, they are added
+    /// here in the same order as they are referenced in the pattern.
+    binds: Vec<Ident>,
+
+    /// Binary segment sizes may refer to variables bound earlier in
+    /// the same pattern.
+    bound_nodes: HashMap<Ident, PatternNode>,
+}
+
+impl ClauseLowerCtx {
+    fn clause_value(&mut self, pat: &mut PatternContainer, val: IrValue) -> PatternValue {
+        self.values.push(val);
+        pat.clause_value(self.pat_clause)
+    }
+}
+
+pub(super) struct LoweredClause {
+    pub clause: PatternClause,
+    pub guard: IrValue,
+    pub values: Vec<IrValue>,
+    pub binds: Vec<Ident>,
+}
+impl LoweredClause {
+    /// Creates the body block of the clause. This pushes a scope
+    /// containing the variables bound by the pattern, which needs to be
+    /// popped by the caller after the body has been lowered.
+    pub fn make_body(&self, ctx: &mut LowerCtx, b: &mut FunctionBuilder) -> IrBlock {
+        ctx.scope_push();
+        let body_block = b.block_insert();
+
+        for bind in self.binds.iter() {
+            let val = b.block_arg_insert(body_block);
+            ctx.bind(*bind, val);
+        }
+
+        body_block
+    }
+}
+
+/// Lowers the patterns and guard of a clause into the pattern container.
+///
+/// Unlike Erlang, all variables in a Core Erlang pattern are fresh, and
+/// a variable never occurs twice in the same pattern. This means the
+/// patterns can be lowered directly without any merging of nodes.
+pub(super) fn lower_clause(
+    ctx: &mut LowerCtx,
+    pat: &mut PatternContainer,
+    b: &mut FunctionBuilder,
+    pre_case: &mut IrBlock,
+    clause: &Annotated<CaseClause>,
+) -> LoweredClause {
+    let pat_clause = pat.clause_start(clause.span);
+
+    let mut clause_ctx = ClauseLowerCtx {
+        pat_clause,
+        values: Vec::new(),
+        binds: Vec::new(),
+        bound_nodes: HashMap::new(),
+    };
+
+    for pattern in clause.inner.patterns.iter() {
+        let node = lower_pattern(ctx, b, pat, &mut clause_ctx, pre_case, pattern);
+        pat.clause_node_push(pat_clause, node);
+    }
+
+    pat.clause_finish(pat_clause);
+
+    let guard = lower_guard(ctx, b, &clause_ctx.binds, &clause.inner.guard);
+
+    LoweredClause {
+        clause: pat_clause,
+        guard: b.value(guard),
+        values: clause_ctx.values,
+        binds: clause_ctx.binds,
+    }
+}
+
+fn lower_guard(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    binds: &[Ident],
+    guard: &Expression,
+) -> IrBlock {
+    let guard_lambda_block = b.block_insert();
+
+    let ret_cont = b.block_arg_insert(guard_lambda_block);
+    let _throw_cont = b.block_arg_insert(guard_lambda_block);
+
+    ctx.scope_push();
+
+    // An exception in a guard makes the guard fail
+    {
+        let fail_handler_block = b.block_insert();
+        b.block_arg_insert(fail_handler_block);
+        b.block_arg_insert(fail_handler_block);
+        b.block_arg_insert(fail_handler_block);
+        let false_val = b.value(false);
+        b.op_call_flow(fail_handler_block, ret_cont, &[false_val]);
+        ctx.exc_stack.push_handler(b.value(fail_handler_block));
+    }
+
+    for bind in binds.iter() {
+        let val = b.block_arg_insert(guard_lambda_block);
+        ctx.bind(*bind, val);
+    }
+
+    let (block, result) = lower_expr_single(ctx, b, guard_lambda_block, guard);
+    b.op_call_flow(block, ret_cont, &[result]);
+
+    ctx.exc_stack.pop_handler();
+    ctx.scope_pop();
+
+    guard_lambda_block
+}
+
+fn lower_pattern(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    pat: &mut PatternContainer,
+    clause_ctx: &mut ClauseLowerCtx,
+    pre_case: &mut IrBlock,
+    pattern: &Annotated<Pattern>,
+) -> PatternNode {
+    let span = pattern.span;
+    match &pattern.inner {
+        Pattern::Wildcard => {
+            let node = pat.node_empty(Some(span));
+            pat.wildcard(node);
+            node
+        }
+        Pattern::BindVar(var, inner) => {
+            let node = lower_pattern(ctx, b, pat, clause_ctx, pre_case, inner);
+            pat.clause_bind_push(clause_ctx.pat_clause, node);
+            clause_ctx.binds.push(var.inner);
+            clause_ctx.bound_nodes.insert(var.inner, node);
+            node
+        }
+        Pattern::Atomic(literal) => {
+            let node = pat.node_empty(Some(span));
+            let constant = lower_atomic(b.cons_mut(), literal);
+            pat.constant(node, constant);
+            node
+        }
+        Pattern::Tuple(elems) => {
+            let node = pat.node_empty(Some(span));
+            pat.tuple(node);
+            for elem in elems.iter() {
+                let child = lower_pattern(ctx, b, pat, clause_ctx, pre_case, elem);
+                pat.tuple_elem_push(node, child);
+            }
+            pat.node_finish(node);
+            node
+        }
+        Pattern::List(head, tail) => {
+            let mut head_nodes = Vec::with_capacity(head.len());
+            for elem in head.iter() {
+                head_nodes.push(lower_pattern(ctx, b, pat, clause_ctx, pre_case, elem));
+            }
+
+            let mut acc = lower_pattern(ctx, b, pat, clause_ctx, pre_case, tail);
+            for head_node in head_nodes.iter().rev() {
+                let node = pat.node_empty(Some(span));
+                pat.list(node, *head_node, acc);
+                acc = node;
+            }
+            acc
+        }
+        Pattern::Map(entries) => {
+            let node = pat.node_empty(Some(span));
+            pat.map(node);
+            for entry in entries.iter() {
+                let (key, value) = &entry.inner;
+
+                // Map keys are expressions evaluated before the match.
+                let (block, key_val) = lower_single(ctx, b, *pre_case, key);
+                *pre_case = block;
+                let key_pat_val = clause_ctx.clause_value(pat, key_val);
+
+                let value_node = lower_pattern(ctx, b, pat, clause_ctx, pre_case, value);
+                pat.map_push(node, key_pat_val, value_node);
+            }
+            pat.node_finish(node);
+            node
+        }
+        Pattern::Binary(segments) => {
+            let mut entries = Vec::with_capacity(segments.len());
+            for (value, args) in segments.iter() {
+                let args: Vec<_> = args.iter().collect();
+                let segment = match binary::segment(value.span, &args) {
+                    Ok(segment) => segment,
+                    Err(err) => {
+                        ctx.error(err);
+                        continue;
+                    }
+                };
+
+                let size = match segment.size {
+                    None => None,
+                    Some(Annotated {
+                        inner: SingleExpression::Variable(var),
+                        ..
+                    }) if clause_ctx.bound_nodes.contains_key(var) => {
+                        let size_node = clause_ctx.bound_nodes[var];
+                        Some(pat.clause_node_value(clause_ctx.pat_clause, size_node))
+                    }
+                    Some(size) => {
+                        let (block, size_val) = lower_single(ctx, b, *pre_case, size);
+                        *pre_case = block;
+                        Some(clause_ctx.clause_value(pat, size_val))
+                    }
+                };
+
+                let value_node = lower_pattern(ctx, b, pat, clause_ctx, pre_case, value);
+                entries.push((value.span, segment.specifier, value_node, size));
+            }
+
+            let mut tail = pat.node_empty(Some(span));
+            let empty_bin = b.cons_mut().from(Vec::<u8>::new());
+            pat.constant(tail, empty_bin);
+
+            for (entry_span, specifier, value_node, size) in entries.into_iter().rev() {
+                let node = pat.node_empty(Some(entry_span));
+                pat.binary(node, specifier, value_node, size, tail);
+                tail = node;
+            }
+
+            tail
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use libeir_diagnostics::CodeMap;
+use libeir_intern::Symbol;
+use libeir_ir::{Module as IrModule, StandardFormatConfig};
+use libeir_util_parse::Errors;
+
+use crate::ast::Module;
+use crate::lower::lower_module;
+use crate::Parser;
+
+fn lower(input: &str) -> Result<IrModule, ()> {
+    let codemap = Arc::new(CodeMap::new());
+    let parser = Parser::new((), codemap.clone());
+
+    let mut errors = Errors::new();
+    let parsed: Module = match parser.parse_string(&mut errors, input) {
+        Ok(module) => module,
+        Err(()) => {
+            errors.print(&codemap);
+            panic!("parse failed");
+        }
+    };
+
+    let mut errors = Errors::new();
+    let res = lower_module(&mut errors, codemap.clone(), &parsed);
+    errors.print(&codemap);
+
+    res
+}
+
+#[test]
+fn factorial_lower() {
+    let input = std::fs::read_to_string("../test_data/factorial.core").unwrap();
+    let module = lower(&input).unwrap();
+
+    println!("{}", module.to_text(&mut StandardFormatConfig::default()));
+
+    assert!(module.name().name == Symbol::intern("factorial"));
+    let factorial = module
+        .name_arity_index(Symbol::intern("factorial"), 1)
+        .unwrap();
+    assert!(module[factorial].is_exported());
+    assert!(module
+        .name_arity_index(Symbol::intern("module_info"), 0)
+        .is_some());
+    assert!(module
+        .name_arity_index(Symbol::intern("module_info"), 1)
+        .is_some());
+}
+
+#[test]
+fn try_catch_lower() {
+    lower(
+        "
+module 'woo' ['foo'/1]
+    attributes []
+'foo'/1 =
+    fun (_0) ->
+        try
+            call 'erlang':'throw'(_0)
+        of <_1> ->
+            _1
+        catch <_4,_3,_2> ->
+            case <_4,_3,_2> of
+              <'throw',R,_5> when 'true' ->
+                  R
+              ( <_6,_7,_8> when 'true' ->
+                    primop 'raise'(_8, _7)
+                -| ['compiler_generated'] )
+            end
+end
+",
+    )
+    .unwrap();
+}
+
+#[test]
+fn catch_lower() {
+    lower(
+        "
+module 'woo' ['foo'/0]
+    attributes []
+'foo'/0 =
+    fun () ->
+        catch
+            call 'erlang':'error'('foo')
+end
+",
+    )
+    .unwrap();
+}
+
+#[test]
+fn letrec_lower() {
+    lower(
+        "
+module 'woo' ['foo'/1]
+    attributes []
+'foo'/1 =
+    fun (_0) ->
+        letrec
+            'lc$^0'/1 =
+                fun (_1) ->
+                    case _1 of
+                      <[H|T]> when 'true' ->
+                          let <_2> = apply 'lc$^0'/1(T)
+                          in [{H}|_2]
+                      <[]> when 'true' ->
+                          []
+                      ( <_3> when 'true' ->
+                            primop 'match_fail'({'function_clause',_3})
+                        -| ['compiler_generated'] )
+                    end
+        in apply 'lc$^0'/1(_0)
+end
+",
+    )
+    .unwrap();
+}
+
+#[test]
+fn receive_lower() {
+    lower(
+        "
+module 'woo' ['foo'/0]
+    attributes []
+'foo'/0 =
+    fun () ->
+        receive
+          <{'msg',A}> when 'true' ->
+              A
+          <B> when call 'erlang':'is_integer'(B) ->
+              B
+        after 'infinity' ->
+          'true'
+end
+",
+    )
+    .unwrap();
+}
+
+#[test]
+fn map_binary_lower() {
+    lower(
+        "
+module 'woo' ['foo'/2]
+    attributes []
+'foo'/2 =
+    fun (_0, _1) ->
+        case <_0, _1> of
+          <~{'a':=A}~, #{#<Size>(8,1,'integer',['unsigned'|['big']]),
+                         #<Data>(Size,8,'binary',['unsigned'|['big']]),
+                         #<_2>('all',8,'binary',['unsigned'|['big']])}#> when 'true' ->
+              let <M> = ~{'b'=>A}~
+              in {M, #{#<Data>('all',8,'binary',['unsigned'|['big']])}#}
+          ( <_3, _4> when 'true' ->
+                primop 'match_fail'({'function_clause',_3,_4})
+            -| ['compiler_generated'] )
+        end
+end
+",
+    )
+    .unwrap();
+}
+
+#[test]
+fn unsupported_primop() {
+    assert!(lower(
+        "
+module 'woo' ['foo'/0]
+    attributes []
+'foo'/0 =
+    fun () ->
+        primop 'not_a_primop'()
+end
+",
+    )
+    .is_err());
+}
+
+#[test]
+fn module_attributes_lower() {
+    let module = lower(
+        "
+module 'woo' ['foo'/0]
+    attributes ['on_load' = [{'init',0}],
+                'vsn' = [1]]
+'foo'/0 =
+    fun () ->
+        'ok'
+'init'/0 =
+    fun () ->
+        'ok'
+end
+",
+    )
+    .unwrap();
+
+    let foo = module.name_arity_index(Symbol::intern("foo"), 0).unwrap();
+    let init = module.name_arity_index(Symbol::intern("init"), 0).unwrap();
+    assert!(module[foo].is_exported());
+    assert!(!module[init].is_exported());
+    assert!(module.on_load() == Some(init));
+    assert!(module.attribute_iter().count() == 2);
+}
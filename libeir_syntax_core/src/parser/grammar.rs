@@ -1,16560 +0,0 @@
-// auto-generated: "lalrpop 0.16.3"
-// sha256: dc56de9177595febc2b12f8c47a9639b9cb9f823fc7527e3133dcd8cafc3efb
-use crate::ast::{ Module, Annotated, FunctionName, Constant,  
-Function, FunctionDefinition, Expression, SingleExpression, Pattern,
-CaseClause, PrimOpCall, MapExactAssoc };
-use crate::lexer::Tok;
-use eir::Atom;
-use eir::AtomicTerm;
-use ::num_bigint::BigInt;
-use ::num_traits::ToPrimitive;
-#[allow(unused_extern_crates)]
-extern crate lalrpop_util as __lalrpop_util;
-#[allow(unused_imports)]
-use self::__lalrpop_util::state_machine as __state_machine;
-
-#[cfg_attr(rustfmt, rustfmt_skip)]
-mod __parse__AnnotatedModule {
-    #![allow(non_snake_case, non_camel_case_types, unused_mut, unused_variables, unused_imports, unused_parens)]
-
-    use crate::ast::{ Module, Annotated, FunctionName, Constant,  
-Function, FunctionDefinition, Expression, SingleExpression, Pattern,
-CaseClause, PrimOpCall, MapExactAssoc };
-    use crate::lexer::Tok;
-    use eir::Atom;
-    use eir::AtomicTerm;
-    use ::num_bigint::BigInt;
-    use ::num_traits::ToPrimitive;
-    #[allow(unused_extern_crates)]
-    extern crate lalrpop_util as __lalrpop_util;
-    #[allow(unused_imports)]
-    use self::__lalrpop_util::state_machine as __state_machine;
-    use super::__ToTriple;
-    #[allow(dead_code)]
-    pub enum __Symbol<'input>
-     {
-        Variant0(Tok<'input>),
-        Variant1(&'input str),
-        Variant2(char),
-        Variant3((bool, &'input str)),
-        Variant4(Annotated<Pattern>),
-        Variant5(::std::option::Option<Annotated<Pattern>>),
-        Variant6(Expression),
-        Variant7(::std::option::Option<Expression>),
-        Variant8((Tok<'input>, Constant)),
-        Variant9(::std::option::Option<(Tok<'input>, Constant)>),
-        Variant10((Atom, Constant)),
-        Variant11(::std::vec::Vec<(Atom, Constant)>),
-        Variant12(Annotated<(Expression, MapExactAssoc, Expression)>),
-        Variant13(::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>),
-        Variant14(Annotated<SingleExpression>),
-        Variant15(::std::vec::Vec<Annotated<SingleExpression>>),
-        Variant16(Annotated<Atom>),
-        Variant17(::std::vec::Vec<Annotated<Atom>>),
-        Variant18(::std::vec::Vec<Annotated<Pattern>>),
-        Variant19(Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>),
-        Variant20(::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>),
-        Variant21(::std::option::Option<(Atom, Constant)>),
-        Variant22((Expression, Vec<Expression>)),
-        Variant23(::std::vec::Vec<(Expression, Vec<Expression>)>),
-        Variant24(Constant),
-        Variant25(::std::vec::Vec<Constant>),
-        Variant26(::std::vec::Vec<Expression>),
-        Variant27((Expression, MapExactAssoc, Expression)),
-        Variant28(FunctionName),
-        Variant29(::std::vec::Vec<FunctionName>),
-        Variant30((FunctionName, Function)),
-        Variant31(::std::vec::Vec<(FunctionName, Function)>),
-        Variant32((Annotated<Pattern>, Vec<Annotated<SingleExpression>>)),
-        Variant33(::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>),
-        Variant34(::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>),
-        Variant35(Annotated<CaseClause>),
-        Variant36(::std::vec::Vec<Annotated<CaseClause>>),
-        Variant37(Annotated<Function>),
-        Variant38(Annotated<FunctionName>),
-        Variant39(Annotated<Module>),
-        Variant40(::std::option::Option<Annotated<SingleExpression>>),
-        Variant41(Annotated<Vec<Annotated<SingleExpression>>>),
-        Variant42(::std::option::Option<Annotated<Atom>>),
-        Variant43(::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>),
-        Variant44(()),
-        Variant45(Atom),
-        Variant46(AtomicTerm),
-        Variant47(Vec<(Expression, Vec<Expression>)>),
-        Variant48(::std::option::Option<(Expression, Vec<Expression>)>),
-        Variant49(CaseClause),
-        Variant50(Vec<(Atom, Constant)>),
-        Variant51(Vec<Annotated<(Expression, MapExactAssoc, Expression)>>),
-        Variant52(Vec<Annotated<SingleExpression>>),
-        Variant53(Vec<Annotated<Atom>>),
-        Variant54(Vec<Annotated<Pattern>>),
-        Variant55(Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>),
-        Variant56(Vec<Constant>),
-        Variant57(Vec<Expression>),
-        Variant58(Vec<FunctionName>),
-        Variant59(Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>),
-        Variant60(::std::option::Option<Constant>),
-        Variant61(MapExactAssoc),
-        Variant62(Function),
-        Variant63(FunctionDefinition),
-        Variant64(::std::vec::Vec<FunctionDefinition>),
-        Variant65(::std::option::Option<FunctionName>),
-        Variant66(BigInt),
-        Variant67(Module),
-        Variant68(Pattern),
-        Variant69(::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>),
-        Variant70((Annotated<SingleExpression>, Annotated<Pattern>)),
-        Variant71(SingleExpression),
-    }
-    const __ACTION: &'static [i16] = &[
-        // State 0
-        0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 1
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 2
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 3
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 4
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 5
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 6
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 7
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 13, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 8
-        0, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, -126, 0, -126, -126, -126, -126, -126, -126, -126, 0, -126, -126, -126, -126, -126, -126, -126, -126, -126, 0, -126, -126, -126, -126, -126, -126, -126, -126, 0, -126, -126,
-        // State 9
-        0, 0, 0, 14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 10
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 11
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 12
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, -173, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 13
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 14
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, -165, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, 0, 0, 0, 0, 0,
-        // State 15
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 33, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 16
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, -175, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 17
-        0, 0, 0, 0, 0, 0, 0, 35, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 18
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 19
-        0, 0, 0, 0, 37, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -172, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 20
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, -167, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, -167, -167, 0, 0, 0,
-        // State 21
-        0, 0, 0, 0, -128, 0, -128, 0, 0, 0, 0, 0, 0, -128, -128, 0, 0, 0, 0, 0, 0, 0, -128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -128, 0, -128, -128, 0, -128, 0,
-        // State 22
-        0, 0, 0, 0, -183, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -183, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -183, -183, 0, 0, 0,
-        // State 23
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 39, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 24
-        0, 0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -164, -164, 0, 0, 0,
-        // State 25
-        0, -127, -127, -127, -127, -127, -127, 0, -127, -127, -127, 0, -127, -127, -127, -127, -127, 0, -127, -127, -127, -127, -127, -127, -127, 0, -127, -127, -127, -127, -127, -127, -127, -127, -127, 0, -127, -127, -127, -127, -127, -127, -127, -127, 0, -127, -127,
-        // State 26
-        0, -129, -129, -129, -129, -129, -129, 0, -129, -129, -129, 0, -129, -129, -129, -129, -129, 0, -129, -129, -129, -129, -129, -129, -129, 0, -129, -129, -129, -129, -129, -129, -129, -129, -129, 0, -129, -129, -129, -129, -129, -129, -129, -129, 0, -129, -129,
-        // State 27
-        0, -205, -205, -205, -205, -205, -205, 0, -205, -205, -205, -205, -205, -205, -205, -205, -205, 0, -205, -205, -205, -205, -205, -205, -205, 0, -205, -205, -205, -205, -205, -205, -205, -205, -205, 0, -205, -205, -205, -205, -205, -205, -205, -205, 0, -205, -205,
-        // State 28
-        0, -130, -130, -130, -130, -130, -130, 0, -130, -130, -130, 0, -130, -130, -130, -130, -130, 0, -130, -130, -130, -130, -130, -130, -130, 0, -130, -130, -130, -130, -130, -130, -130, -130, -130, 0, -130, -130, -130, -130, -130, -130, -130, -130, 0, -130, -130,
-        // State 29
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, -165, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, -165, 0, 0, 0, 0,
-        // State 30
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, 0, -165, 0, 0, 0,
-        // State 31
-        0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 48, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 32
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, -137, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 33
-        0, 0, 0, 0, 52, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -174, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 34
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 35
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -209, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 36
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -62, 0, 0, 0, 0, 0, 0, -62, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 37
-        0, 0, 0, 0, 54, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -166, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -166, -166, 0, 0, 0,
-        // State 38
-        0, 0, 0, -125, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 39
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -51, -51, 0, -51, -51, 0, -51, -51, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -51, -51, -51, 0, 0, 0,
-        // State 40
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 55, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 56, 0, 0, 0, 0,
-        // State 41
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 57, 0, 0, 0,
-        // State 42
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 58, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 43
-        0, 0, -199, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -199, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -199, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 44
-        0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 45
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -86, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 46
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 47
-        0, 0, 0, 0, 0, 0, -206, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 48
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, -139, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 49
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 50
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 64, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 51
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -63, 0, 0, 0, 0, 0, 0, -63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 52
-        0, -201, -201, -201, -201, -201, -201, 0, -201, -201, -201, -201, -201, -201, -201, -201, -201, 0, -201, -201, -201, -201, -201, -201, -201, 0, -201, -201, -201, -201, -201, -201, -201, -201, -201, 0, -201, -201, -201, -201, 0, -201, -201, -201, 0, -201, -201,
-        // State 53
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -52, -52, 0, -52, -52, 0, -52, -52, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -52, -52, -52, 0, 0, 0,
-        // State 54
-        0, 0, 0, 0, -182, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -182, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -182, -182, 0, 0, 0,
-        // State 55
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, 0, 0, 0, 0, 0,
-        // State 56
-        0, 0, 0, 0, -180, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -180, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -180, -180, 0, 0, 0,
-        // State 57
-        0, 0, 68, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 69, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 58
-        0, 0, -200, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -200, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -200, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 59
-        0, 0, 0, 0, 0, 0, -207, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 60
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 61
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 62
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, 0, 0, 0, 0, 0,
-        // State 63
-        0, 0, -208, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -208, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -208, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 64
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 73, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 65
-        0, 0, -196, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -196, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -196, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 66
-        0, 0, -84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 67
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 69, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 68
-        0, 0, 75, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 69
-        0, 0, 0, 76, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 70
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 31, 0, 0, 0, 0, 0,
-        // State 71
-        0, 0, 0, 0, 78, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -136, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 72
-        0, 0, 0, 0, -181, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -181, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -181, -181, 0, 0, 0,
-        // State 73
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 74
-        0, 0, 84, -149, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 75
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -87, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 76
-        0, 0, 0, 0, 86, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -138, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 77
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -13, 0, 0, 0, 0, 0, 0, -13, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 78
-        0, 0, 0, 87, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 79
-        0, 0, 84, -151, 0, 0, 0, 0, 0, 0, 0, 0, 0, -151, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 80
-        0, 0, 0, -148, 89, 0, 0, 0, 0, 0, 0, 0, 0, -148, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 81
-        0, 0, 0, 90, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 82
-        0, 0, 0, -114, -114, -114, 0, 0, 0, 0, 0, -114, 0, -114, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 83
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 84
-        0, -249, -249, -249, -249, -249, -249, 0, -249, -249, -249, -249, -249, -249, -249, -249, -249, 0, -249, -249, -249, -249, -249, -249, -249, 0, -249, -249, -249, -249, -249, -249, -249, -249, -249, 0, -249, -249, -249, -249, -249, -249, -249, -249, 0, -249, -249,
-        // State 85
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -14, 0, 0, 0, 0, 0, 0, -14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 86
-        0, 0, -85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 87
-        0, 0, 0, -150, 92, 0, 0, 0, 0, 0, 0, 0, 0, -150, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 88
-        0, 0, -28, -28, 0, 0, 0, 0, 0, 0, 0, 0, 0, -28, 0, 0, 0, 0, 0, 0, -28, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 89
-        0, 0, 0, 0, 0, 93, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 90
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 91
-        0, 0, -29, -29, 0, 0, 0, 0, 0, 0, 0, 0, 0, -29, 0, 0, 0, 0, 0, 0, -29, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 92
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 93
-        0, 0, 0, 123, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 94
-        0, -192, -192, -192, -192, -192, -192, 0, -192, -192, -192, 0, -192, -192, -192, -192, -192, 0, -192, -192, -192, -192, -192, -192, -192, 0, -192, -192, -192, -192, -192, -192, -192, -192, -192, 0, -192, -192, -192, -192, 0, -192, -192, -192, 0, -192, -192,
-        // State 95
-        0, -191, -191, -191, -191, -191, -191, 0, -191, -191, -191, 0, -191, -191, -191, -191, -191, 0, -191, -191, -191, -191, -191, -191, -191, 0, -191, -191, -191, -191, -191, -191, -191, -191, -191, 0, -191, -191, -191, -191, 0, -191, -191, -191, 0, -191, -191,
-        // State 96
-        0, -128, -128, -128, -128, -128, -128, 35, -128, -128, -128, 0, -128, -128, -128, -128, -128, 0, -128, -128, -128, -128, -128, -128, -128, 0, -128, -128, -128, -128, -128, -128, -128, -128, -128, 0, -128, -128, -128, -128, 0, -128, -128, -128, 0, -128, -128,
-        // State 97
-        0, -229, -229, -229, -229, -229, -229, 0, -229, -229, -229, 0, -229, -229, -229, -229, -229, 0, -229, -229, -229, -229, -229, -229, -229, 0, -229, -229, -229, -229, -229, -229, -229, -229, -229, 0, -229, -229, -229, -229, 0, -229, -229, -229, 0, -229, -229,
-        // State 98
-        0, -231, -231, -231, -231, -231, -231, 0, -231, -231, -231, 0, -231, -231, -231, -231, -231, 0, -231, -231, -231, -231, -231, -231, -231, 0, -231, -231, -231, -231, -231, -231, -231, -231, -231, 0, -231, -231, -231, -231, 0, -231, -231, -231, 0, -231, -231,
-        // State 99
-        0, -195, -195, -195, -195, -195, -195, 0, -195, -195, -195, 0, -195, -195, -195, -195, -195, 0, -195, -195, -195, -195, -195, -195, -195, 0, -195, -195, -195, -195, -195, -195, -195, -195, -195, 0, -195, -195, -195, -195, 0, -195, -195, -195, 0, -195, -195,
-        // State 100
-        0, -246, -246, -246, -246, -246, -246, 0, -246, -246, -246, 0, -246, -246, -246, -246, -246, 0, -246, -246, -246, -246, -246, -246, -246, 0, -246, -246, -246, -246, -246, -246, -246, -246, -246, 0, -246, -246, -246, -246, 0, -246, -246, -246, 0, -246, -246,
-        // State 101
-        0, -227, -227, -227, -227, -227, -227, 0, -227, -227, -227, 0, -227, -227, -227, -227, -227, 0, -227, -227, -227, -227, -227, -227, -227, 0, -227, -227, -227, -227, -227, -227, -227, -227, -227, 0, -227, -227, -227, -227, 0, -227, -227, -227, 0, -227, -227,
-        // State 102
-        0, -108, -108, -108, -108, -108, -108, 0, -108, -108, -108, 0, -108, -108, -108, -108, -108, 0, -108, -108, -108, -108, -108, -108, -108, 0, -108, -108, -108, -108, -108, -108, -108, -108, -108, 0, -108, -108, -108, -108, 0, -108, -108, -108, 0, -108, -108,
-        // State 103
-        0, -112, -112, -112, -112, -112, -112, 0, -112, -112, -112, 0, -112, -112, -112, -112, -112, 0, -112, -112, -112, -112, -112, -112, -112, 0, -112, -112, -112, -112, -112, -112, -112, -112, -112, 0, -112, -112, -112, -112, 0, -112, -112, -112, 0, -112, -112,
-        // State 104
-        0, -230, -230, -230, -230, -230, -230, 0, -230, -230, -230, 0, -230, -230, -230, -230, -230, 0, -230, -230, -230, -230, -230, -230, -230, 0, -230, -230, -230, -230, -230, -230, -230, -230, -230, 0, -230, -230, -230, -230, 0, -230, -230, -230, 0, -230, -230,
-        // State 105
-        127, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -161, 0, 0,
-        // State 106
-        0, 106, 0, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 107
-        0, 106, 133, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -145, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 108
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, -169, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, -169, 0, 0, 0, 122,
-        // State 109
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 110
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 111
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 112
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 113
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 114
-        0, 0, 75, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 115
-        0, 0, 84, 0, 0, 0, 0, 0, 0, 0, 145, 0, 0, 0, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 116
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 117
-        0, 0, 150, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 118
-        0, 161, 162, 0, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 165, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 119
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 120
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, -169, 0, 0, 122,
-        // State 121
-        0, 106, 174, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, -141, 0, 0, -141, 122,
-        // State 122
-        0, 0, 0, -115, -115, -115, 0, 0, 0, 0, 0, -115, 0, -115, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 123
-        127, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -163, 0, 0,
-        // State 124
-        0, 0, 0, 0, 176, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -160, 0, 0,
-        // State 125
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 177, 0, 0,
-        // State 126
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 127
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 128
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 129
-        0, 106, 133, -147, 0, 0, 0, 0, 0, 0, 0, 0, 0, -147, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 130
-        0, 0, 0, -144, 182, 0, 0, 0, 0, 0, 0, 0, 0, -144, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 131
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 183, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 132
-        0, 106, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 133
-        0, 106, 107, -171, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, -171, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, -171, -171, 0, 0, 122,
-        // State 134
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 185, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 186, 0, 0, 0, 0,
-        // State 135
-        0, 0, 0, -168, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -168, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -168, -168, 0, 0, 0,
-        // State 136
-        0, 0, 188, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 137
-        0, 0, 0, 0, 0, 0, 0, 0, 189, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 138
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 190, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 139
-        0, -237, -237, -237, -237, -237, -237, 0, -237, -237, -237, 0, -237, -237, -237, -237, -237, 0, -237, -237, -237, -237, -237, -237, -237, 0, -237, -237, -237, -237, -237, -237, -237, -237, -237, 0, -237, -237, -237, -237, 0, -237, -237, -237, 0, -237, -237,
-        // State 140
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 141
-        0, 0, 0, 0, 0, 0, 0, 0, 192, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 142
-        0, 0, 0, 0, 0, -250, 0, 0, 0, 0, 0, -250, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 143
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 193, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 144
-        0, 0, 84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -149, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 145
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 196, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 146
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 197, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 147
-        0, 0, 198, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 148
-        0, 0, -76, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 149
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 150
-        0, -82, -82, 0, 0, 0, 0, 0, 0, 0, -82, 0, 0, 0, 0, -82, -82, 0, -82, -82, -82, -82, 0, -82, 0, 0, 0, 0, 0, 0, -82, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -82, 0, 0, 0, 0, -82,
-        // State 151
-        0, 161, 162, 0, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 201, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 152
-        0, 0, 0, 0, -119, 0, -119, 0, 0, 0, 0, 0, 0, -119, -119, 0, 0, 0, 0, 0, 0, 0, -119, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -119, 0, -119, -119, 0, -119, 0,
-        // State 153
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 202, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 154
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -223, 0, 0, 0, 0, 0, 0,
-        // State 155
-        0, 0, 0, 0, -92, 0, -92, 0, 0, 0, 0, 0, 0, -92, -92, 0, 0, 0, 0, 0, 0, 0, -92, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -92, 0, -92, -92, 0, -92, 0,
-        // State 156
-        0, -78, -78, 0, 0, 0, 0, 0, 0, 0, -78, 0, 0, 0, 0, -78, -78, 0, -78, -78, -78, -78, 0, -78, 0, 0, 0, 0, 0, 0, -78, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -78, 0, 0, 0, 0, -78,
-        // State 157
-        0, 0, 0, 0, -93, 0, -93, 0, 0, 0, 0, 0, 0, -93, -93, 0, 0, 0, 0, 0, 0, 0, -93, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -93, 0, -93, -93, 0, -93, 0,
-        // State 158
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 204, 0, 0, 0, 0, 0, 0,
-        // State 159
-        0, 0, 0, 0, -91, 0, -91, 0, 0, 0, 0, -114, 0, -91, -91, 0, 0, 0, 0, 0, 0, 0, -91, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -91, 0, -91, -91, 0, -91, 0,
-        // State 160
-        208, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -177, 0, 0,
-        // State 161
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 215, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 216, 0, 0, 0, 0, 217,
-        // State 162
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -153, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 163
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, -153, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, -153, 0, 0, 0, 167,
-        // State 164
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 165
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, -153, 0, 0, 167,
-        // State 166
-        0, 106, 230, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, -157, 122,
-        // State 167
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 231, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 168
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 232, 0, 0, 0,
-        // State 169
-        0, 106, 174, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, -143, 0, 0, -143, 122,
-        // State 170
-        0, 0, 0, 0, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -140, 0, 0, -140, 0,
-        // State 171
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 235, 0, 0, 236, 0,
-        // State 172
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 238, 0, 0, 239, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 173
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 174
-        0, 0, 0, 0, 243, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -162, 0, 0,
-        // State 175
-        -46, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -46, 0, 0,
-        // State 176
-        0, -131, -131, -131, -131, -131, -131, 0, -131, -131, -131, 0, -131, -131, -131, -131, -131, 0, -131, -131, -131, -131, -131, -131, -131, 0, -131, -131, -131, -131, -131, -131, -131, -131, -131, 0, -131, -131, -131, -131, 0, -131, -131, -131, 0, -131, -131,
-        // State 177
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 244, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 178
-        0, 0, 0, 245, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 179
-        0, 0, 0, 246, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 180
-        0, 0, 0, -146, 247, 0, 0, 0, 0, 0, 0, 0, 0, -146, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 181
-        0, -23, -23, -23, 0, 0, 0, 0, 0, 0, 0, 0, 0, -23, 0, -23, -23, 0, -23, -23, -23, -23, 0, 0, -23, 0, -23, -23, -23, -23, 0, -23, 0, -23, -23, 0, 0, -23, -23, -23, 0, -23, 0, 0, 0, 0, -23,
-        // State 182
-        0, -248, -248, -248, -248, -248, -248, 0, -248, -248, -248, 0, -248, -248, -248, -248, -248, 0, -248, -248, -248, -248, -248, -248, -248, 0, -248, -248, -248, -248, -248, -248, -248, -248, -248, 0, -248, -248, -248, -248, 0, -248, -248, -248, 0, -248, -248,
-        // State 183
-        0, 0, 0, -170, 248, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -170, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -170, -170, 0, 0, 0,
-        // State 184
-        0, -226, -226, -226, -226, -226, -226, 0, -226, -226, -226, 0, -226, -226, -226, -226, -226, 0, -226, -226, -226, -226, -226, -226, -226, 0, -226, -226, -226, -226, -226, -226, -226, -226, -226, 0, -226, -226, -226, -226, 0, -226, -226, -226, 0, -226, -226,
-        // State 185
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 186
-        0, -56, -56, -56, 0, 0, 0, 0, 0, 0, -56, 0, 0, 0, 0, -56, -56, 0, -56, -56, -56, -56, -56, 0, -56, 0, -56, -56, -56, -56, 0, -56, 0, -56, -56, 0, 0, -56, -56, -56, 0, -56, -56, -56, 0, 0, -56,
-        // State 187
-        0, 106, 107, -169, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 188
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 189
-        0, 161, 162, 0, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 253, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 190
-        0, -241, -241, -241, -241, -241, -241, 0, -241, -241, -241, 0, -241, -241, -241, -241, -241, 0, -241, -241, -241, -241, -241, -241, -241, 0, -241, -241, -241, -241, -241, -241, -241, -241, -241, 0, -241, -241, -241, -241, 0, -241, -241, -241, 0, -241, -241,
-        // State 191
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 192
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 193
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 256, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 194
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 257, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 195
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 196
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 69, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 197
-        0, 106, 107, -169, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 198
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 199
-        0, -83, -83, 0, 0, 0, 0, 0, 0, 0, -83, 0, 0, 0, 0, -83, -83, 0, -83, -83, -83, -83, 0, -83, 0, 0, 0, 0, 0, 0, -83, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -83, 0, 0, 0, 0, -83,
-        // State 200
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 201
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 202
-        0, 0, 0, 0, 0, 264, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 203
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 204
-        208, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -179, 0, 0,
-        // State 205
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 267, 0, 0,
-        // State 206
-        0, 0, 0, 0, 268, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -176, 0, 0,
-        // State 207
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 208
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 270, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 209
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -92, 0, 0, 0, 0, 0, 0,
-        // State 210
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 211
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -93, 0, 0, 0, 0, 0, 0,
-        // State 212
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, -114, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -91, 0, 0, 0, 0, 0, 0,
-        // State 213
-        0, 161, 84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 279, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 280, 0, 0, 0, 0, 281,
-        // State 214
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, -153, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, -153, 0, 0, 0, 167,
-        // State 215
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, -153, 0, 0, 167,
-        // State 216
-        0, 106, 230, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, -157, 122,
-        // State 217
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -155, 0, 9, 27, 0, 28, 29, 85, 164, -155, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, -155, -155, 0, 0, 167,
-        // State 218
-        0, 0, 0, 0, 286, 0, 0, 0, 0, 0, 0, 0, 0, -152, 0, 0, 0, 0, 0, 0, 0, 0, -152, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -152, -152, 0, 0, 0,
-        // State 219
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 287, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 220
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 288, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 289, 0, 0, 0, 0,
-        // State 221
-        0, 0, 0, 0, 0, 290, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 222
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 291, 0, 0, 0,
-        // State 223
-        0, 106, 230, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, -159, 122,
-        // State 224
-        0, 0, 0, 0, -122, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -122, 0,
-        // State 225
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 293, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 226
-        0, 0, 0, 0, 294, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -156, 0,
-        // State 227
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 295, 0,
-        // State 228
-        0, 0, 0, 0, -106, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -106, 0,
-        // State 229
-        0, 106, 133, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 230
-        0, 0, 84, 0, 0, 0, 0, 0, 0, 0, 145, 0, 0, 0, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 231
-        0, -232, -232, -232, -232, -232, -232, 0, -232, -232, -232, 0, -232, -232, -232, -232, -232, 0, -232, -232, -232, -232, -232, -232, -232, 0, -232, -232, -232, -232, -232, -232, -232, -232, -232, 0, -232, -232, -232, -232, 0, -232, -232, -232, 0, -232, -232,
-        // State 232
-        0, 0, 0, 0, 298, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -142, 0, 0, -142, 0,
-        // State 233
-        0, -18, -18, 0, 0, 0, 0, 0, 0, 0, -18, 0, 0, 0, 0, -18, -18, 0, -18, -18, -18, -18, 0, 0, -18, 0, -18, -18, -18, -18, 0, -18, 0, -18, -18, 0, 0, -18, -18, -18, 0, -18, -18, 0, 0, -18, -18,
-        // State 234
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 235
-        0, -234, -234, -234, -234, -234, -234, 0, -234, -234, -234, 0, -234, -234, -234, -234, -234, 0, -234, -234, -234, -234, -234, -234, -234, 0, -234, -234, -234, -234, -234, -234, -234, -234, -234, 0, -234, -234, -234, -234, 0, -234, -234, -234, 0, -234, -234,
-        // State 236
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 237
-        0, -189, -189, 0, 0, 0, 0, 0, 0, 0, -189, 0, 0, 0, 0, -189, -189, 0, -189, -189, -189, -189, 0, 0, -189, 0, -189, -189, -189, -189, 0, -189, 0, -189, -189, 0, 0, -189, -189, -189, 0, -189, 0, 0, 0, 0, -189,
-        // State 238
-        0, -190, -190, 0, 0, 0, 0, 0, 0, 0, -190, 0, 0, 0, 0, -190, -190, 0, -190, -190, -190, -190, 0, 0, -190, 0, -190, -190, -190, -190, 0, -190, 0, -190, -190, 0, 0, -190, -190, -190, 0, -190, 0, 0, 0, 0, -190,
-        // State 239
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 238, 0, 0, 239, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 240
-        0, 0, 0, 0, 0, 0, 11, 0, 0, -108, 0, 0, -108, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 241
-        0, 0, 0, 0, 0, 0, 11, 0, 0, -112, 0, 0, -112, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 242
-        -47, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -47, 0, 0,
-        // State 243
-        0, 106, 107, -169, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 244
-        0, -109, -109, -109, -109, -109, -109, 0, -109, -109, -109, 0, -109, -109, -109, -109, -109, 0, -109, -109, -109, -109, -109, -109, -109, 0, -109, -109, -109, -109, -109, -109, -109, -109, -109, 0, -109, -109, -109, -109, 0, -109, -109, -109, 0, -109, -109,
-        // State 245
-        0, -113, -113, -113, -113, -113, -113, 0, -113, -113, -113, 0, -113, -113, -113, -113, -113, 0, -113, -113, -113, -113, -113, -113, -113, 0, -113, -113, -113, -113, -113, -113, -113, -113, -113, 0, -113, -113, -113, -113, 0, -113, -113, -113, 0, -113, -113,
-        // State 246
-        0, -24, -24, -24, 0, 0, 0, 0, 0, 0, 0, 0, 0, -24, 0, -24, -24, 0, -24, -24, -24, -24, 0, 0, -24, 0, -24, -24, -24, -24, 0, -24, 0, -24, -24, 0, 0, -24, -24, -24, 0, -24, 0, 0, 0, 0, -24,
-        // State 247
-        0, -57, -57, -57, 0, 0, 0, 0, 0, 0, -57, 0, 0, 0, 0, -57, -57, 0, -57, -57, -57, -57, -57, 0, -57, 0, -57, -57, -57, -57, 0, -57, 0, -57, -57, 0, 0, -57, -57, -57, 0, -57, -57, -57, 0, 0, -57,
-        // State 248
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 303, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 249
-        0, 0, 0, 304, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 250
-        0, 0, 305, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 251
-        0, 161, 162, 0, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 306, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 252
-        0, -238, -238, -238, -238, -238, -238, 0, -238, -238, -238, 0, -238, -238, -238, -238, -238, 0, -238, -238, -238, -238, -238, -238, -238, 0, -238, -238, -238, -238, -238, -238, -238, -238, -238, 0, -238, -238, -238, -238, 0, -238, -238, -238, 0, -238, -238,
-        // State 253
-        0, -228, -228, -228, -228, -228, -228, 0, -228, -228, -228, 0, -228, -228, -228, -228, -228, 0, -228, -228, -228, -228, -228, -228, -228, 0, -228, -228, -228, -228, -228, -228, -228, -228, -228, 0, -228, -228, -228, -228, 0, -228, -228, -228, 0, -228, -228,
-        // State 254
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 307, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 255
-        0, 0, 0, 0, 0, -251, 0, 0, 0, 0, 0, -251, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 256
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 69, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 257
-        0, -247, -247, -247, -247, -247, -247, 0, -247, -247, -247, 0, -247, -247, -247, -247, -247, 0, -247, -247, -247, -247, -247, -247, -247, 0, -247, -247, -247, -247, -247, -247, -247, -247, -247, 0, -247, -247, -247, -247, 0, -247, -247, -247, 0, -247, -247,
-        // State 258
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 259
-        0, 0, 0, 309, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 260
-        0, 0, 0, 310, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 261
-        0, 0, 0, 0, 0, 311, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 262
-        0, 0, 0, 0, -90, 0, -90, 0, 0, 0, 0, 0, 0, -90, -90, 0, 0, 0, 0, 0, 0, 0, -90, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -90, 0, -90, -90, 0, -90, 0,
-        // State 263
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 264
-        0, 0, 0, 0, 0, -204, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 265
-        0, 0, 0, 0, 313, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -178, 0, 0,
-        // State 266
-        0, 0, 0, 0, -218, 0, -218, 0, 0, 0, 0, 0, 0, -218, -218, 0, 0, 0, 0, 0, 0, 0, -218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -218, 0, -218, -218, 0, -218, 0,
-        // State 267
-        -70, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -70, 0, 0,
-        // State 268
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 314, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 269
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 270
-        0, 0, 0, 316, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 271
-        0, 0, 0, 317, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 272
-        0, 0, 0, 318, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 273
-        0, 0, 0, 319, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 274
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 320, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 275
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 276
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 277
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, -114, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 278
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, -153, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, -153, 0, 0, 0, 167,
-        // State 279
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, -153, 0, 0, 167,
-        // State 280
-        0, 106, 230, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, -157, 122,
-        // State 281
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 324, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 325, 0, 0, 0, 0,
-        // State 282
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 326, 0, 0, 0,
-        // State 283
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 327, 0,
-        // State 284
-        0, 0, 0, 0, 328, 0, 0, 0, 0, 0, 0, 0, 0, -154, 0, 0, 0, 0, 0, 0, 0, 0, -154, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -154, -154, 0, 0, 0,
-        // State 285
-        0, -33, -33, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -33, 0, -33, -33, 0, -33, -33, -33, -33, -33, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -33, -33, -33, 0, 0, -33,
-        // State 286
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -224, 0, 0, 0, 0, 0, 0,
-        // State 287
-        0, 0, 0, 0, -97, 0, -97, 0, 0, 0, 0, 0, 0, -97, -97, 0, 0, 0, 0, 0, 0, 0, -97, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -97, 0, -97, -97, 0, -97, 0,
-        // State 288
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 289
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 290
-        0, 0, 0, 0, -94, 0, -94, 0, 0, 0, 0, 0, 0, -94, -94, 0, 0, 0, 0, 0, 0, 0, -94, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -94, 0, -94, -94, 0, -94, 0,
-        // State 291
-        0, 0, 0, 0, 331, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -158, 0,
-        // State 292
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 293
-        0, -38, -38, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -38, -38, 0, -38, -38, -38, -38, 0, 0, -38, 0, -38, -38, -38, -38, 0, -38, 0, -38, -38, 0, 0, -38, -38, -38, 0, -38, 0, 0, 0, -38, -38,
-        // State 294
-        0, 0, 0, 0, -95, 0, -95, 0, 0, 0, 0, 0, 0, -95, -95, 0, 0, 0, 0, 0, 0, 0, -95, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -95, 0, -95, -95, 0, -95, 0,
-        // State 295
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 296
-        0, 0, 0, 0, 0, 334, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 297
-        0, -19, -19, 0, 0, 0, 0, 0, 0, 0, -19, 0, 0, 0, 0, -19, -19, 0, -19, -19, -19, -19, 0, 0, -19, 0, -19, -19, -19, -19, 0, -19, 0, -19, -19, 0, 0, -19, -19, -19, 0, -19, -19, 0, 0, -19, -19,
-        // State 298
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 335, 0,
-        // State 299
-        0, 0, 0, 0, -72, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -72, 0, 0, -72, 0,
-        // State 300
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 301
-        0, 0, 0, 337, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 302
-        0, -225, -225, -225, -225, -225, -225, 0, -225, -225, -225, 0, -225, -225, -225, -225, -225, 0, -225, -225, -225, -225, -225, -225, -225, 0, -225, -225, -225, -225, -225, -225, -225, -225, -225, 0, -225, -225, -225, -225, 0, -225, -225, -225, 0, -225, -225,
-        // State 303
-        0, -242, -242, -242, -242, -242, -242, 0, -242, -242, -242, 0, -242, -242, -242, -242, -242, 0, -242, -242, -242, -242, -242, -242, -242, 0, -242, -242, -242, -242, -242, -242, -242, -242, -242, 0, -242, -242, -242, -242, 0, -242, -242, -242, 0, -242, -242,
-        // State 304
-        0, 106, 107, -169, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 305
-        0, -239, -239, -239, -239, -239, -239, 0, -239, -239, -239, 0, -239, -239, -239, -239, -239, 0, -239, -239, -239, -239, -239, -239, -239, 0, -239, -239, -239, -239, -239, -239, -239, -239, -239, 0, -239, -239, -239, -239, 0, -239, -239, -239, 0, -239, -239,
-        // State 306
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 307
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -66, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -66, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 308
-        0, -240, -240, -240, -240, -240, -240, 0, -240, -240, -240, 0, -240, -240, -240, -240, -240, 0, -240, -240, -240, -240, -240, -240, -240, 0, -240, -240, -240, -240, -240, -240, -240, -240, -240, 0, -240, -240, -240, -240, 0, -240, -240, -240, 0, -240, -240,
-        // State 309
-        0, 0, -77, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 310
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 311
-        0, -135, -135, 0, 0, 0, -135, 0, 0, 0, -135, 0, 0, 0, 0, -135, -135, 0, -135, -135, -135, -135, 0, -135, 0, 0, 0, 0, 0, 0, -135, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -135, 0, 0, 0, 0, -135,
-        // State 312
-        -71, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -71, 0, 0,
-        // State 313
-        0, 106, 133, -145, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 314
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -90, 0, 0, 0, 0, 0, 0,
-        // State 315
-        0, 0, 0, 0, -100, 0, -100, 0, 0, 0, 0, 0, 0, -100, -100, 0, 0, 0, 0, 0, 0, 0, -100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -100, 0, -100, -100, 0, -100, 0,
-        // State 316
-        0, -79, -79, 0, 0, 0, 0, 0, 0, 0, -79, 0, 0, 0, 0, -79, -79, 0, -79, -79, -79, -79, 0, -79, 0, 0, 0, 0, 0, 0, -79, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -79, 0, 0, 0, 0, -79,
-        // State 317
-        0, 0, 0, 0, -101, 0, -101, 0, 0, 0, 0, 0, 0, -101, -101, 0, 0, 0, 0, 0, 0, 0, -101, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -101, 0, -101, -101, 0, -101, 0,
-        // State 318
-        0, 0, 0, 0, -99, 0, -99, 0, 0, 0, 0, -115, 0, -99, -99, 0, 0, 0, 0, 0, 0, 0, -99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -99, 0, -99, -99, 0, -99, 0,
-        // State 319
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 320
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 344, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 345, 0, 0, 0, 0,
-        // State 321
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 346, 0, 0, 0,
-        // State 322
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 347, 0,
-        // State 323
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -97, 0, 0, 0, 0, 0, 0,
-        // State 324
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 325
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -94, 0, 0, 0, 0, 0, 0,
-        // State 326
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -95, 0, 0, 0, 0, 0, 0,
-        // State 327
-        0, -34, -34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -34, 0, -34, -34, 0, -34, -34, -34, -34, -34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -34, -34, -34, 0, 0, -34,
-        // State 328
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 352, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 329
-        0, -244, -244, -244, -244, -244, -244, 0, -244, -244, -244, 0, -244, -244, -244, -244, -244, 0, -244, -244, -244, -244, -244, -244, -244, 0, -244, -244, -244, -244, -244, -244, -244, -244, -244, 0, -244, -244, -244, -244, 0, -244, -244, -244, 0, -244, -244,
-        // State 330
-        0, -39, -39, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -39, -39, 0, -39, -39, -39, -39, 0, 0, -39, 0, -39, -39, -39, -39, 0, -39, 0, -39, -39, 0, 0, -39, -39, -39, 0, -39, 0, 0, 0, -39, -39,
-        // State 331
-        0, 0, 0, 0, -222, 0, -222, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -222, 0,
-        // State 332
-        0, 0, 0, 353, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 333
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 334
-        0, -233, -233, -233, -233, -233, -233, 0, -233, -233, -233, 0, -233, -233, -233, -233, -233, 0, -233, -233, -233, -233, -233, -233, -233, 0, -233, -233, -233, -233, -233, -233, -233, -233, -233, 0, -233, -233, -233, -233, 0, -233, -233, -233, 0, -233, -233,
-        // State 335
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 336
-        0, 0, 0, 0, -132, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -132, 0, 0,
-        // State 337
-        0, 0, 0, 356, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 338
-        0, -235, -235, -235, -235, -235, -235, 0, -235, -235, -235, 0, -235, -235, -235, -235, -235, 0, -235, -235, -235, -235, -235, -235, -235, 0, -235, -235, -235, -235, -235, -235, -235, -235, -235, 0, -235, -235, -235, -235, 0, -235, -235, -235, 0, -235, -235,
-        // State 339
-        0, -245, -245, -245, -245, -245, -245, 0, -245, -245, -245, 0, -245, -245, -245, -245, -245, 0, -245, -245, -245, -245, -245, -245, -245, 0, -245, -245, -245, -245, -245, -245, -245, -245, -245, 0, -245, -245, -245, -245, 0, -245, -245, -245, 0, -245, -245,
-        // State 340
-        0, 0, 0, 357, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 341
-        0, 0, 0, 358, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 342
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 343
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 344
-        0, 161, 214, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 164, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 166, 0, 0, 0, 0, 167,
-        // State 345
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 346
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 347
-        0, 0, 0, 360, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 348
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 361, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 349
-        0, 0, 0, 362, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 350
-        0, 0, 0, 363, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 351
-        0, 0, 0, 0, -96, 0, -96, 0, 0, 0, 0, 0, 0, -96, -96, 0, 0, 0, 0, 0, 0, 0, -96, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -96, 0, -96, -96, 0, -96, 0,
-        // State 352
-        0, 0, 0, 0, -107, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -107, 0,
-        // State 353
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 364, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 354
-        0, 0, 0, 365, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 355
-        0, -236, -236, -236, -236, -236, -236, 0, -236, -236, -236, 0, -236, -236, -236, -236, -236, 0, -236, -236, -236, -236, -236, -236, -236, 0, -236, -236, -236, -236, -236, -236, -236, -236, -236, 0, -236, -236, -236, -236, 0, -236, -236, -236, 0, -236, -236,
-        // State 356
-        0, 0, 0, 0, -219, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -219, 0, 0,
-        // State 357
-        0, 0, 0, 0, -98, 0, -98, 0, 0, 0, 0, 0, 0, -98, -98, 0, 0, 0, 0, 0, 0, 0, -98, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -98, 0, -98, -98, 0, -98, 0,
-        // State 358
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 366, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 359
-        0, 0, 0, 0, -105, 0, -105, 0, 0, 0, 0, 0, 0, -105, -105, 0, 0, 0, 0, 0, 0, 0, -105, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -105, 0, -105, -105, 0, -105, 0,
-        // State 360
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -96, 0, 0, 0, 0, 0, 0,
-        // State 361
-        0, 0, 0, 0, -102, 0, -102, 0, 0, 0, 0, 0, 0, -102, -102, 0, 0, 0, 0, 0, 0, 0, -102, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -102, 0, -102, -102, 0, -102, 0,
-        // State 362
-        0, 0, 0, 0, -103, 0, -103, 0, 0, 0, 0, 0, 0, -103, -103, 0, 0, 0, 0, 0, 0, 0, -103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -103, 0, -103, -103, 0, -103, 0,
-        // State 363
-        0, 0, 84, 0, 0, 0, 0, 0, 0, 0, 145, 0, 0, 0, 0, 0, 0, 0, 0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 364
-        0, 0, 0, 0, -73, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -73, 0, 0, -73, 0,
-        // State 365
-        0, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 366
-        0, 0, 0, 369, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 367
-        0, 0, 0, 0, 0, 370, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 368
-        0, 0, 0, 0, -104, 0, -104, 0, 0, 0, 0, 0, 0, -104, -104, 0, 0, 0, 0, 0, 0, 0, -104, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -104, 0, -104, -104, 0, -104, 0,
-        // State 369
-        0, 106, 107, 0, 0, 0, 0, 0, 0, 0, 108, 0, 0, 0, 0, 9, 27, 0, 28, 29, 85, 109, 0, 0, 110, 0, 111, 112, 113, 114, 0, 115, 0, 116, 117, 0, 0, 118, 119, 120, 0, 121, 0, 0, 0, 0, 122,
-        // State 370
-        0, -243, -243, -243, -243, -243, -243, 0, -243, -243, -243, 0, -243, -243, -243, -243, -243, 0, -243, -243, -243, -243, -243, -243, -243, 0, -243, -243, -243, -243, -243, -243, -243, -243, -243, 0, -243, -243, -243, -243, 0, -243, -243, -243, 0, -243, -243,
-    ];
-    const __EOF_ACTION: &'static [i16] = &[
-        // State 0
-        0,
-        // State 1
-        -118,
-        // State 2
-        -252,
-        // State 3
-        -88,
-        // State 4
-        0,
-        // State 5
-        0,
-        // State 6
-        0,
-        // State 7
-        0,
-        // State 8
-        0,
-        // State 9
-        0,
-        // State 10
-        0,
-        // State 11
-        0,
-        // State 12
-        0,
-        // State 13
-        -89,
-        // State 14
-        0,
-        // State 15
-        0,
-        // State 16
-        0,
-        // State 17
-        0,
-        // State 18
-        0,
-        // State 19
-        0,
-        // State 20
-        0,
-        // State 21
-        0,
-        // State 22
-        0,
-        // State 23
-        0,
-        // State 24
-        0,
-        // State 25
-        0,
-        // State 26
-        0,
-        // State 27
-        0,
-        // State 28
-        0,
-        // State 29
-        0,
-        // State 30
-        0,
-        // State 31
-        0,
-        // State 32
-        0,
-        // State 33
-        0,
-        // State 34
-        0,
-        // State 35
-        0,
-        // State 36
-        0,
-        // State 37
-        0,
-        // State 38
-        0,
-        // State 39
-        0,
-        // State 40
-        0,
-        // State 41
-        0,
-        // State 42
-        0,
-        // State 43
-        0,
-        // State 44
-        0,
-        // State 45
-        0,
-        // State 46
-        0,
-        // State 47
-        -206,
-        // State 48
-        0,
-        // State 49
-        0,
-        // State 50
-        0,
-        // State 51
-        0,
-        // State 52
-        0,
-        // State 53
-        0,
-        // State 54
-        0,
-        // State 55
-        0,
-        // State 56
-        0,
-        // State 57
-        0,
-        // State 58
-        0,
-        // State 59
-        -207,
-        // State 60
-        0,
-        // State 61
-        0,
-        // State 62
-        0,
-        // State 63
-        0,
-        // State 64
-        0,
-        // State 65
-        0,
-        // State 66
-        0,
-        // State 67
-        0,
-        // State 68
-        0,
-        // State 69
-        0,
-        // State 70
-        0,
-        // State 71
-        0,
-        // State 72
-        0,
-        // State 73
-        0,
-        // State 74
-        0,
-        // State 75
-        0,
-        // State 76
-        0,
-        // State 77
-        0,
-        // State 78
-        0,
-        // State 79
-        0,
-        // State 80
-        0,
-        // State 81
-        0,
-        // State 82
-        0,
-        // State 83
-        0,
-        // State 84
-        0,
-        // State 85
-        0,
-        // State 86
-        0,
-        // State 87
-        0,
-        // State 88
-        0,
-        // State 89
-        0,
-        // State 90
-        0,
-        // State 91
-        0,
-        // State 92
-        0,
-        // State 93
-        0,
-        // State 94
-        0,
-        // State 95
-        0,
-        // State 96
-        0,
-        // State 97
-        0,
-        // State 98
-        0,
-        // State 99
-        0,
-        // State 100
-        0,
-        // State 101
-        0,
-        // State 102
-        0,
-        // State 103
-        0,
-        // State 104
-        0,
-        // State 105
-        0,
-        // State 106
-        0,
-        // State 107
-        0,
-        // State 108
-        0,
-        // State 109
-        0,
-        // State 110
-        0,
-        // State 111
-        0,
-        // State 112
-        0,
-        // State 113
-        0,
-        // State 114
-        0,
-        // State 115
-        0,
-        // State 116
-        0,
-        // State 117
-        0,
-        // State 118
-        0,
-        // State 119
-        0,
-        // State 120
-        0,
-        // State 121
-        0,
-        // State 122
-        0,
-        // State 123
-        0,
-        // State 124
-        0,
-        // State 125
-        0,
-        // State 126
-        0,
-        // State 127
-        0,
-        // State 128
-        0,
-        // State 129
-        0,
-        // State 130
-        0,
-        // State 131
-        0,
-        // State 132
-        0,
-        // State 133
-        0,
-        // State 134
-        0,
-        // State 135
-        0,
-        // State 136
-        0,
-        // State 137
-        0,
-        // State 138
-        0,
-        // State 139
-        0,
-        // State 140
-        0,
-        // State 141
-        0,
-        // State 142
-        0,
-        // State 143
-        0,
-        // State 144
-        0,
-        // State 145
-        0,
-        // State 146
-        0,
-        // State 147
-        0,
-        // State 148
-        0,
-        // State 149
-        0,
-        // State 150
-        0,
-        // State 151
-        0,
-        // State 152
-        0,
-        // State 153
-        0,
-        // State 154
-        0,
-        // State 155
-        0,
-        // State 156
-        0,
-        // State 157
-        0,
-        // State 158
-        0,
-        // State 159
-        0,
-        // State 160
-        0,
-        // State 161
-        0,
-        // State 162
-        0,
-        // State 163
-        0,
-        // State 164
-        0,
-        // State 165
-        0,
-        // State 166
-        0,
-        // State 167
-        0,
-        // State 168
-        0,
-        // State 169
-        0,
-        // State 170
-        0,
-        // State 171
-        0,
-        // State 172
-        0,
-        // State 173
-        0,
-        // State 174
-        0,
-        // State 175
-        0,
-        // State 176
-        0,
-        // State 177
-        0,
-        // State 178
-        0,
-        // State 179
-        0,
-        // State 180
-        0,
-        // State 181
-        0,
-        // State 182
-        0,
-        // State 183
-        0,
-        // State 184
-        0,
-        // State 185
-        0,
-        // State 186
-        0,
-        // State 187
-        0,
-        // State 188
-        0,
-        // State 189
-        0,
-        // State 190
-        0,
-        // State 191
-        0,
-        // State 192
-        0,
-        // State 193
-        0,
-        // State 194
-        0,
-        // State 195
-        0,
-        // State 196
-        0,
-        // State 197
-        0,
-        // State 198
-        0,
-        // State 199
-        0,
-        // State 200
-        0,
-        // State 201
-        0,
-        // State 202
-        0,
-        // State 203
-        0,
-        // State 204
-        0,
-        // State 205
-        0,
-        // State 206
-        0,
-        // State 207
-        0,
-        // State 208
-        0,
-        // State 209
-        0,
-        // State 210
-        0,
-        // State 211
-        0,
-        // State 212
-        0,
-        // State 213
-        0,
-        // State 214
-        0,
-        // State 215
-        0,
-        // State 216
-        0,
-        // State 217
-        0,
-        // State 218
-        0,
-        // State 219
-        0,
-        // State 220
-        0,
-        // State 221
-        0,
-        // State 222
-        0,
-        // State 223
-        0,
-        // State 224
-        0,
-        // State 225
-        0,
-        // State 226
-        0,
-        // State 227
-        0,
-        // State 228
-        0,
-        // State 229
-        0,
-        // State 230
-        0,
-        // State 231
-        0,
-        // State 232
-        0,
-        // State 233
-        0,
-        // State 234
-        0,
-        // State 235
-        0,
-        // State 236
-        0,
-        // State 237
-        0,
-        // State 238
-        0,
-        // State 239
-        0,
-        // State 240
-        0,
-        // State 241
-        0,
-        // State 242
-        0,
-        // State 243
-        0,
-        // State 244
-        0,
-        // State 245
-        0,
-        // State 246
-        0,
-        // State 247
-        0,
-        // State 248
-        0,
-        // State 249
-        0,
-        // State 250
-        0,
-        // State 251
-        0,
-        // State 252
-        0,
-        // State 253
-        0,
-        // State 254
-        0,
-        // State 255
-        0,
-        // State 256
-        0,
-        // State 257
-        0,
-        // State 258
-        0,
-        // State 259
-        0,
-        // State 260
-        0,
-        // State 261
-        0,
-        // State 262
-        0,
-        // State 263
-        0,
-        // State 264
-        0,
-        // State 265
-        0,
-        // State 266
-        0,
-        // State 267
-        0,
-        // State 268
-        0,
-        // State 269
-        0,
-        // State 270
-        0,
-        // State 271
-        0,
-        // State 272
-        0,
-        // State 273
-        0,
-        // State 274
-        0,
-        // State 275
-        0,
-        // State 276
-        0,
-        // State 277
-        0,
-        // State 278
-        0,
-        // State 279
-        0,
-        // State 280
-        0,
-        // State 281
-        0,
-        // State 282
-        0,
-        // State 283
-        0,
-        // State 284
-        0,
-        // State 285
-        0,
-        // State 286
-        0,
-        // State 287
-        0,
-        // State 288
-        0,
-        // State 289
-        0,
-        // State 290
-        0,
-        // State 291
-        0,
-        // State 292
-        0,
-        // State 293
-        0,
-        // State 294
-        0,
-        // State 295
-        0,
-        // State 296
-        0,
-        // State 297
-        0,
-        // State 298
-        0,
-        // State 299
-        0,
-        // State 300
-        0,
-        // State 301
-        0,
-        // State 302
-        0,
-        // State 303
-        0,
-        // State 304
-        0,
-        // State 305
-        0,
-        // State 306
-        0,
-        // State 307
-        0,
-        // State 308
-        0,
-        // State 309
-        0,
-        // State 310
-        0,
-        // State 311
-        0,
-        // State 312
-        0,
-        // State 313
-        0,
-        // State 314
-        0,
-        // State 315
-        0,
-        // State 316
-        0,
-        // State 317
-        0,
-        // State 318
-        0,
-        // State 319
-        0,
-        // State 320
-        0,
-        // State 321
-        0,
-        // State 322
-        0,
-        // State 323
-        0,
-        // State 324
-        0,
-        // State 325
-        0,
-        // State 326
-        0,
-        // State 327
-        0,
-        // State 328
-        0,
-        // State 329
-        0,
-        // State 330
-        0,
-        // State 331
-        0,
-        // State 332
-        0,
-        // State 333
-        0,
-        // State 334
-        0,
-        // State 335
-        0,
-        // State 336
-        0,
-        // State 337
-        0,
-        // State 338
-        0,
-        // State 339
-        0,
-        // State 340
-        0,
-        // State 341
-        0,
-        // State 342
-        0,
-        // State 343
-        0,
-        // State 344
-        0,
-        // State 345
-        0,
-        // State 346
-        0,
-        // State 347
-        0,
-        // State 348
-        0,
-        // State 349
-        0,
-        // State 350
-        0,
-        // State 351
-        0,
-        // State 352
-        0,
-        // State 353
-        0,
-        // State 354
-        0,
-        // State 355
-        0,
-        // State 356
-        0,
-        // State 357
-        0,
-        // State 358
-        0,
-        // State 359
-        0,
-        // State 360
-        0,
-        // State 361
-        0,
-        // State 362
-        0,
-        // State 363
-        0,
-        // State 364
-        0,
-        // State 365
-        0,
-        // State 366
-        0,
-        // State 367
-        0,
-        // State 368
-        0,
-        // State 369
-        0,
-        // State 370
-        0,
-    ];
-    const __GOTO: &'static [i16] = &[
-        // State 0
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 1
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 2
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 3
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 4
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 5
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 6
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 7
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 8
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 9
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 10
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 11
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 12
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 17, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 19, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 13
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 14
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 21, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 24, 0, 0, 0, 25, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 15
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 16
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 17
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 18
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 19
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 20
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 38, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 21
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 22
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 23
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 24
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 25
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 26
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 27
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 28
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 29
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 21, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 41, 0, 0, 0, 25, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 30
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 21, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 42, 0, 0, 0, 25, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 31
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 44, 0, 45, 46, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 32
-        0, 0, 0, 0, 0, 0, 0, 0, 49, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 50, 0, 0, 0, 0, 0, 51, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 33
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 34
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 53, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 35
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 36
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 37
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 38
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 39
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 40
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 41
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 42
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 43
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 44
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 59, 0, 0, 46, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 45
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 46
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 61, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 47
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 48
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 62, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 49
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 50
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 51
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 52
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 53
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 54
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 55
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 56
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 57
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 66, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 67, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 58
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 59
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 60
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 70, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 61
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 62
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 72, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 63
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 64
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 65
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 66
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 67
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 74, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 68
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 69
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 70
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22, 23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 77, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 71
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 72
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 73
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 79, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 74
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 81, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 82, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 0, 0,
-        // State 75
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 76
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 77
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 78
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 79
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 88, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 0, 0,
-        // State 80
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 81
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 82
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 83
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 91, 0, 0,
-        // State 84
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 85
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 86
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 87
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 88
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 89
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 90
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 94, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 91
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 92
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 100, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 93
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 94
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 95
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 96
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 97
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 98
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 99
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 100
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 101
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 102
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 103
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 104
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 105
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 124, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 125, 0, 0, 0, 0, 0, 0, 0, 0, 126, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 106
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 129, 105, 0, 0,
-        // State 107
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 130, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 131, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 132, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 0, 105, 0, 0,
-        // State 108
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 135, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 109
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 137, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 110
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 138, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 111
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 139, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 112
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 140, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 113
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 141, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 114
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 142, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 115
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 143, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 144, 0,
-        // State 116
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 146, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 147, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 117
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 148, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 149, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 118
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 151, 0, 152, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 155, 0, 0, 0, 0, 22, 156, 0, 0, 0, 157, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 159, 0, 0, 160, 0, 0,
-        // State 119
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 168, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 120
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 169, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 121
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 170, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 171, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 172, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 173, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 122
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 123
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 175, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 124
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 125
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 126
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 178, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 127
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 179, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 128
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 180, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 129
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 181, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 0, 105, 0, 0,
-        // State 130
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 131
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 132
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 0, 105, 0, 0,
-        // State 133
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 184, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 134
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 135
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 136
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 137
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 138
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 139
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 140
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 191, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 141
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 142
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 143
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 144
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 81, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 194, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 0, 0,
-        // State 145
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 195, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 146
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 147
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 148
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 149
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 199, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 150
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 151
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 200, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 155, 0, 0, 0, 0, 22, 156, 0, 0, 0, 157, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 159, 0, 0, 160, 0, 0,
-        // State 152
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 153
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 154
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 155
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 156
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 157
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 158
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 203, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 159
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 160
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 205, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 206, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 207, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 161
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 209, 0, 0, 155, 0, 0, 0, 0, 22, 210, 0, 0, 0, 211, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 212, 0, 0, 0, 159, 0, 0, 213, 0, 0,
-        // State 162
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 220, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 163
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 221, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 164
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 222, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 165
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 223, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 166
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 224, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 225, 226, 0, 0, 0, 0, 0, 0, 0, 227, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 228, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 229, 0, 103, 0, 105, 0, 0,
-        // State 167
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 168
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 169
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 233, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 173, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 170
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 171
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 172
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 237, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 173
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 240, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 241, 242, 105, 0, 0,
-        // State 174
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 175
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 176
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 177
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 178
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 179
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 180
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 181
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 182
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 183
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 184
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 185
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 249, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 186
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 187
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 250, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 188
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 251, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 189
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 151, 0, 252, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 155, 0, 0, 0, 0, 22, 156, 0, 0, 0, 157, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 159, 0, 0, 160, 0, 0,
-        // State 190
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 191
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 254, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 192
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 193
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 194
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 195
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 258, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 196
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 259, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 197
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 260, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 198
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 261, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 199
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 200
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 262, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 201
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 263, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 202
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 203
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 265, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 204
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 266, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 205
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 206
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 207
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 269, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 208
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 209
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 271, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 210
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 272, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 211
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 273, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 212
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 274, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 213
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 275, 0, 0, 0, 0, 0, 0, 0, 22, 276, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 277, 0, 0, 0, 0, 0, 0, 278, 0, 0,
-        // State 214
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 282, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 215
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 283, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 216
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 224, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 225, 226, 0, 0, 0, 0, 0, 0, 0, 227, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 284, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 229, 0, 103, 0, 105, 0, 0,
-        // State 217
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 285, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 218
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 219
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 220
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 221
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 222
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 223
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 225, 226, 0, 0, 0, 0, 0, 0, 0, 292, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 229, 0, 103, 0, 105, 0, 0,
-        // State 224
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 225
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 226
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 227
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 228
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 229
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 226, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 296, 0, 241, 0, 105, 0, 0,
-        // State 230
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 143, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 297, 0,
-        // State 231
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 232
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 233
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 234
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 299, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 235
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 236
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 300, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 237
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 238
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 239
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 301, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 240
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 179, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 241
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 180, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 242
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 243
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 302, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 244
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 245
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 246
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 247
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 248
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 249
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 250
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 251
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 200, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 155, 0, 0, 0, 0, 22, 156, 0, 0, 0, 157, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 159, 0, 0, 160, 0, 0,
-        // State 252
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 253
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 254
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 255
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 256
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 308, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 257
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 258
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 259
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 260
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 261
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 262
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 263
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 312, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 264
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 265
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 266
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 267
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 268
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 269
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 315, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 270
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 271
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 272
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 273
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 274
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 275
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 271, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 276
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 273, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 277
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 274, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 278
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 321, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 279
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 218, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 219, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 322, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 280
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 224, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 225, 226, 0, 0, 0, 0, 0, 0, 0, 227, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 323, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 229, 0, 103, 0, 105, 0, 0,
-        // State 281
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 282
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 283
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 284
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 285
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 286
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 287
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 288
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 329, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 289
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 330, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 290
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 291
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 292
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 332, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 293
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 294
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 295
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 333, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 296
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 297
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 298
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 299
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 300
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 336, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 301
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 302
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 303
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 304
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 338, 0, 0, 0, 0, 0, 0, 0, 136, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 305
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 306
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 339, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 307
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 308
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 309
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 310
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 340, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 311
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 312
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 313
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 130, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 131, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 341, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 0, 105, 0, 0,
-        // State 314
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 342, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 315
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 316
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 317
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 318
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 319
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 343, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 320
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 321
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 322
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 323
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 348, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 324
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 349, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 325
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 350, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 326
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 351, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 327
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 328
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 329
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 330
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 331
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 332
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 333
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 354, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 334
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 335
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 355, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 336
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 337
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 338
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 339
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 340
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 341
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 342
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 342, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 343
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 348, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 344
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 153, 0, 0, 0, 0, 154, 0, 0, 359, 0, 0, 0, 0, 22, 156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0, 158, 0, 0, 0, 0, 0, 0, 160, 0, 0,
-        // State 345
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 350, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 346
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 351, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 347
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 348
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 349
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 350
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 351
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 352
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 353
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 354
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 355
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 356
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 357
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 358
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 359
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 360
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 367, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 361
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 362
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 363
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 143, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 368, 0,
-        // State 364
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 365
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 367, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 366
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 367
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 368
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        // State 369
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 95, 0, 96, 0, 0, 0, 0, 0, 0, 0, 0, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 371, 0, 101, 0, 0, 0, 102, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 103, 104, 105, 0, 0,
-        // State 370
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    ];
-    fn __expected_tokens(__state: usize) -> Vec<::std::string::String> {
-        const __TERMINAL: &'static [&'static str] = &[
-            r###""#<""###,
-            r###""#{""###,
-            r###""(""###,
-            r###"")""###,
-            r###"",""###,
-            r###""->""###,
-            r###""-|""###,
-            r###""/""###,
-            r###"":""###,
-            r###"":=""###,
-            r###""<""###,
-            r###""=""###,
-            r###""=>""###,
-            r###"">""###,
-            r###"">(""###,
-            r###""Atom""###,
-            r###""Char""###,
-            r###""Float""###,
-            r###""Integer""###,
-            r###""String""###,
-            r###""Variable""###,
-            r###""[""###,
-            r###""]""###,
-            r###""after""###,
-            r###""apply""###,
-            r###""attributes""###,
-            r###""call""###,
-            r###""case""###,
-            r###""catch""###,
-            r###""do""###,
-            r###""end""###,
-            r###""fun""###,
-            r###""in""###,
-            r###""let""###,
-            r###""letrec""###,
-            r###""module""###,
-            r###""of""###,
-            r###""primop""###,
-            r###""receive""###,
-            r###""try""###,
-            r###""when""###,
-            r###""{""###,
-            r###""|""###,
-            r###""}""###,
-            r###""}#""###,
-            r###""}~""###,
-            r###""~{""###,
-        ];
-        __ACTION[(__state * 47)..].iter().zip(__TERMINAL).filter_map(|(&state, terminal)| {
-            if state == 0 {
-                None
-            } else {
-                Some(terminal.to_string())
-            }
-        }).collect()
-    }
-    pub struct __StateMachine<'input>
-    where 
-    {
-        text: &'input str,
-        __phantom: ::std::marker::PhantomData<(&'input ())>,
-    }
-    impl<'input> __state_machine::ParserDefinition for __StateMachine<'input>
-    where 
-    {
-        type Location = usize;
-        type Error = ();
-        type Token = Tok<'input>;
-        type TokenIndex = usize;
-        type Symbol = __Symbol<'input>;
-        type Success = Annotated<Module>;
-        type StateIndex = i16;
-        type Action = i16;
-        type ReduceIndex = i16;
-        type NonterminalIndex = usize;
-
-        #[inline]
-        fn start_location(&self) -> Self::Location {
-              Default::default()
-        }
-
-        #[inline]
-        fn start_state(&self) -> Self::StateIndex {
-              0
-        }
-
-        #[inline]
-        fn token_to_index(&self, token: &Self::Token) -> Option<usize> {
-            __token_to_integer(token, ::std::marker::PhantomData::<(&())>)
-        }
-
-        #[inline]
-        fn action(&self, state: i16, integer: usize) -> i16 {
-            __ACTION[(state as usize) * 47 + integer]
-        }
-
-        #[inline]
-        fn error_action(&self, state: i16) -> i16 {
-            __ACTION[(state as usize) * 47 + (47 - 1)]
-        }
-
-        #[inline]
-        fn eof_action(&self, state: i16) -> i16 {
-            __EOF_ACTION[state as usize]
-        }
-
-        #[inline]
-        fn goto(&self, state: i16, nt: usize) -> i16 {
-            __GOTO[(state as usize) * 112 + nt] - 1
-        }
-
-        fn token_to_symbol(&self, token_index: usize, token: Self::Token) -> Self::Symbol {
-            __token_to_symbol(token_index, token, ::std::marker::PhantomData::<(&())>)
-        }
-
-        fn expected_tokens(&self, state: i16) -> Vec<String> {
-            __expected_tokens(state as usize)
-        }
-
-        #[inline]
-        fn uses_error_recovery(&self) -> bool {
-            false
-        }
-
-        #[inline]
-        fn error_recovery_symbol(
-            &self,
-            recovery: __state_machine::ErrorRecovery<Self>,
-        ) -> Self::Symbol {
-            panic!("error recovery not enabled for this grammar")
-        }
-
-        fn reduce(
-            &mut self,
-            action: i16,
-            start_location: Option<&Self::Location>,
-            states: &mut Vec<i16>,
-            symbols: &mut Vec<__state_machine::SymbolTriple<Self>>,
-        ) -> Option<__state_machine::ParseResult<Self>> {
-            __reduce(
-                self.text,
-                action,
-                start_location,
-                states,
-                symbols,
-                ::std::marker::PhantomData::<(&())>,
-            )
-        }
-
-        fn simulate_reduce(&self, action: i16) -> __state_machine::SimulatedReduce<Self> {
-            __simulate_reduce(action, ::std::marker::PhantomData::<(&())>)
-        }
-    }
-    fn __token_to_integer<
-        'input,
-    >(
-        __token: &Tok<'input>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> Option<usize>
-    {
-        match *__token {
-            Tok::BitstringPatternOpen if true => Some(0),
-            Tok::BitstringOpen if true => Some(1),
-            Tok::ParenOpen if true => Some(2),
-            Tok::ParenClose if true => Some(3),
-            Tok::Comma if true => Some(4),
-            Tok::Arrow if true => Some(5),
-            Tok::Annotation if true => Some(6),
-            Tok::ForwardSlash if true => Some(7),
-            Tok::Colon if true => Some(8),
-            Tok::MapMatch if true => Some(9),
-            Tok::TriOpen if true => Some(10),
-            Tok::Equals if true => Some(11),
-            Tok::HashRocket if true => Some(12),
-            Tok::TriClose if true => Some(13),
-            Tok::BitstringPatternSep if true => Some(14),
-            Tok::Atom(_) if true => Some(15),
-            Tok::Char(_) if true => Some(16),
-            Tok::Float(_) if true => Some(17),
-            Tok::Integer(_) if true => Some(18),
-            Tok::String(_) if true => Some(19),
-            Tok::Variable(_) if true => Some(20),
-            Tok::SquareOpen if true => Some(21),
-            Tok::SquareClose if true => Some(22),
-            Tok::After if true => Some(23),
-            Tok::Apply if true => Some(24),
-            Tok::Attributes if true => Some(25),
-            Tok::Call if true => Some(26),
-            Tok::Case if true => Some(27),
-            Tok::Catch if true => Some(28),
-            Tok::Do if true => Some(29),
-            Tok::End if true => Some(30),
-            Tok::Fun if true => Some(31),
-            Tok::In if true => Some(32),
-            Tok::Let if true => Some(33),
-            Tok::Letrec if true => Some(34),
-            Tok::Module if true => Some(35),
-            Tok::Of if true => Some(36),
-            Tok::Primop if true => Some(37),
-            Tok::Receive if true => Some(38),
-            Tok::Try if true => Some(39),
-            Tok::When if true => Some(40),
-            Tok::CurlyOpen if true => Some(41),
-            Tok::Pipe if true => Some(42),
-            Tok::CurlyClose if true => Some(43),
-            Tok::BitstringClose if true => Some(44),
-            Tok::MapClose if true => Some(45),
-            Tok::MapOpen if true => Some(46),
-            _ => None,
-        }
-    }
-    fn __token_to_symbol<
-        'input,
-    >(
-        __token_index: usize,
-        __token: Tok<'input>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> __Symbol<'input>
-    {
-        match __token_index {
-            0 => match __token {
-                __tok @ Tok::BitstringPatternOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            1 => match __token {
-                __tok @ Tok::BitstringOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            2 => match __token {
-                __tok @ Tok::ParenOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            3 => match __token {
-                __tok @ Tok::ParenClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            4 => match __token {
-                __tok @ Tok::Comma => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            5 => match __token {
-                __tok @ Tok::Arrow => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            6 => match __token {
-                __tok @ Tok::Annotation => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            7 => match __token {
-                __tok @ Tok::ForwardSlash => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            8 => match __token {
-                __tok @ Tok::Colon => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            9 => match __token {
-                __tok @ Tok::MapMatch => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            10 => match __token {
-                __tok @ Tok::TriOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            11 => match __token {
-                __tok @ Tok::Equals => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            12 => match __token {
-                __tok @ Tok::HashRocket => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            13 => match __token {
-                __tok @ Tok::TriClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            14 => match __token {
-                __tok @ Tok::BitstringPatternSep => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            15 => match __token {
-                Tok::Atom(__tok0) => __Symbol::Variant1((__tok0)),
-                _ => unreachable!(),
-            },
-            16 => match __token {
-                Tok::Char(__tok0) => __Symbol::Variant2((__tok0)),
-                _ => unreachable!(),
-            },
-            17 => match __token {
-                Tok::Float(__tok0) => __Symbol::Variant1((__tok0)),
-                _ => unreachable!(),
-            },
-            18 => match __token {
-                Tok::Integer(__tok0) => __Symbol::Variant3((__tok0)),
-                _ => unreachable!(),
-            },
-            19 => match __token {
-                Tok::String(__tok0) => __Symbol::Variant1((__tok0)),
-                _ => unreachable!(),
-            },
-            20 => match __token {
-                Tok::Variable(__tok0) => __Symbol::Variant1((__tok0)),
-                _ => unreachable!(),
-            },
-            21 => match __token {
-                __tok @ Tok::SquareOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            22 => match __token {
-                __tok @ Tok::SquareClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            23 => match __token {
-                __tok @ Tok::After => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            24 => match __token {
-                __tok @ Tok::Apply => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            25 => match __token {
-                __tok @ Tok::Attributes => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            26 => match __token {
-                __tok @ Tok::Call => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            27 => match __token {
-                __tok @ Tok::Case => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            28 => match __token {
-                __tok @ Tok::Catch => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            29 => match __token {
-                __tok @ Tok::Do => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            30 => match __token {
-                __tok @ Tok::End => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            31 => match __token {
-                __tok @ Tok::Fun => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            32 => match __token {
-                __tok @ Tok::In => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            33 => match __token {
-                __tok @ Tok::Let => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            34 => match __token {
-                __tok @ Tok::Letrec => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            35 => match __token {
-                __tok @ Tok::Module => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            36 => match __token {
-                __tok @ Tok::Of => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            37 => match __token {
-                __tok @ Tok::Primop => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            38 => match __token {
-                __tok @ Tok::Receive => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            39 => match __token {
-                __tok @ Tok::Try => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            40 => match __token {
-                __tok @ Tok::When => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            41 => match __token {
-                __tok @ Tok::CurlyOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            42 => match __token {
-                __tok @ Tok::Pipe => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            43 => match __token {
-                __tok @ Tok::CurlyClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            44 => match __token {
-                __tok @ Tok::BitstringClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            45 => match __token {
-                __tok @ Tok::MapClose => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            46 => match __token {
-                __tok @ Tok::MapOpen => __Symbol::Variant0((__tok)),
-                _ => unreachable!(),
-            },
-            _ => unreachable!(),
-        }
-    }
-    fn __simulate_reduce<
-        'input,
-    >(
-        __reduce_index: i16,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> __state_machine::SimulatedReduce<__StateMachine<'input>>
-    {
-        match __reduce_index {
-            0 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 0,
-                }
-            }
-            1 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 1,
-                }
-            }
-            2 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 1,
-                }
-            }
-            3 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 2,
-                }
-            }
-            4 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 3,
-                }
-            }
-            5 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 3,
-                }
-            }
-            6 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 4,
-                }
-            }
-            7 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 5,
-                }
-            }
-            8 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 5,
-                }
-            }
-            9 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 6,
-                }
-            }
-            10 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 7,
-                }
-            }
-            11 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 7,
-                }
-            }
-            12 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 8,
-                }
-            }
-            13 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 8,
-                }
-            }
-            14 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 9,
-                }
-            }
-            15 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 10,
-                }
-            }
-            16 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 10,
-                }
-            }
-            17 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 11,
-                }
-            }
-            18 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 11,
-                }
-            }
-            19 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 12,
-                }
-            }
-            20 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 13,
-                }
-            }
-            21 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 13,
-                }
-            }
-            22 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 14,
-                }
-            }
-            23 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 14,
-                }
-            }
-            24 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 15,
-                }
-            }
-            25 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 16,
-                }
-            }
-            26 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 16,
-                }
-            }
-            27 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 17,
-                }
-            }
-            28 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 17,
-                }
-            }
-            29 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 18,
-                }
-            }
-            30 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 19,
-                }
-            }
-            31 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 19,
-                }
-            }
-            32 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 20,
-                }
-            }
-            33 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 20,
-                }
-            }
-            34 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 21,
-                }
-            }
-            35 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 22,
-                }
-            }
-            36 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 22,
-                }
-            }
-            37 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 23,
-                }
-            }
-            38 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 23,
-                }
-            }
-            39 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 24,
-                }
-            }
-            40 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 25,
-                }
-            }
-            41 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 25,
-                }
-            }
-            42 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 26,
-                }
-            }
-            43 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 27,
-                }
-            }
-            44 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 27,
-                }
-            }
-            45 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 28,
-                }
-            }
-            46 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 28,
-                }
-            }
-            47 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 29,
-                }
-            }
-            48 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 30,
-                }
-            }
-            49 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 30,
-                }
-            }
-            50 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 31,
-                }
-            }
-            51 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 31,
-                }
-            }
-            52 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 32,
-                }
-            }
-            53 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 33,
-                }
-            }
-            54 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 33,
-                }
-            }
-            55 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 34,
-                }
-            }
-            56 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 34,
-                }
-            }
-            57 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 35,
-                }
-            }
-            58 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 36,
-                }
-            }
-            59 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 37,
-                }
-            }
-            60 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 37,
-                }
-            }
-            61 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 38,
-                }
-            }
-            62 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 38,
-                }
-            }
-            63 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 39,
-                }
-            }
-            64 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 40,
-                }
-            }
-            65 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 40,
-                }
-            }
-            66 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 41,
-                }
-            }
-            67 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 42,
-                }
-            }
-            68 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 42,
-                }
-            }
-            69 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 43,
-                }
-            }
-            70 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 43,
-                }
-            }
-            71 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 44,
-                }
-            }
-            72 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 44,
-                }
-            }
-            73 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 45,
-                }
-            }
-            74 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 45,
-                }
-            }
-            75 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 46,
-                }
-            }
-            76 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 46,
-                }
-            }
-            77 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 47,
-                }
-            }
-            78 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 47,
-                }
-            }
-            79 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 48,
-                }
-            }
-            80 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 48,
-                }
-            }
-            81 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 49,
-                }
-            }
-            82 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 49,
-                }
-            }
-            83 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 50,
-                }
-            }
-            84 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 50,
-                }
-            }
-            85 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 51,
-                }
-            }
-            86 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 51,
-                }
-            }
-            87 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 52,
-                }
-            }
-            88 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 52,
-                }
-            }
-            89 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 53,
-                }
-            }
-            90 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 53,
-                }
-            }
-            91 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 53,
-                }
-            }
-            92 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 53,
-                }
-            }
-            93 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 53,
-                }
-            }
-            94 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 53,
-                }
-            }
-            95 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 53,
-                }
-            }
-            96 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 53,
-                }
-            }
-            97 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 53,
-                }
-            }
-            98 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 53,
-                }
-            }
-            99 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 53,
-                }
-            }
-            100 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 53,
-                }
-            }
-            101 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 53,
-                }
-            }
-            102 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 53,
-                }
-            }
-            103 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 8,
-                    nonterminal_produced: 53,
-                }
-            }
-            104 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 53,
-                }
-            }
-            105 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 54,
-                }
-            }
-            106 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 54,
-                }
-            }
-            107 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 55,
-                }
-            }
-            108 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 55,
-                }
-            }
-            109 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 56,
-                }
-            }
-            110 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 56,
-                }
-            }
-            111 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 57,
-                }
-            }
-            112 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 57,
-                }
-            }
-            113 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 58,
-                }
-            }
-            114 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 58,
-                }
-            }
-            115 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 59,
-                }
-            }
-            116 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 59,
-                }
-            }
-            117 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 60,
-                }
-            }
-            118 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 61,
-                }
-            }
-            119 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 62,
-                }
-            }
-            120 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 62,
-                }
-            }
-            121 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 63,
-                }
-            }
-            122 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 64,
-                }
-            }
-            123 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 64,
-                }
-            }
-            124 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 65,
-                }
-            }
-            125 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 66,
-                }
-            }
-            126 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 67,
-                }
-            }
-            127 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 67,
-                }
-            }
-            128 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 67,
-                }
-            }
-            129 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 67,
-                }
-            }
-            130 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 68,
-                }
-            }
-            131 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 69,
-                }
-            }
-            132 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 70,
-                }
-            }
-            133 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 70,
-                }
-            }
-            134 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 71,
-                }
-            }
-            135 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 72,
-                }
-            }
-            136 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 72,
-                }
-            }
-            137 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 72,
-                }
-            }
-            138 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 72,
-                }
-            }
-            139 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 73,
-                }
-            }
-            140 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 73,
-                }
-            }
-            141 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 73,
-                }
-            }
-            142 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 73,
-                }
-            }
-            143 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 74,
-                }
-            }
-            144 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 74,
-                }
-            }
-            145 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 74,
-                }
-            }
-            146 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 74,
-                }
-            }
-            147 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 75,
-                }
-            }
-            148 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 75,
-                }
-            }
-            149 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 75,
-                }
-            }
-            150 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 75,
-                }
-            }
-            151 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 76,
-                }
-            }
-            152 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 76,
-                }
-            }
-            153 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 76,
-                }
-            }
-            154 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 76,
-                }
-            }
-            155 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 77,
-                }
-            }
-            156 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 77,
-                }
-            }
-            157 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 77,
-                }
-            }
-            158 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 77,
-                }
-            }
-            159 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 78,
-                }
-            }
-            160 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 78,
-                }
-            }
-            161 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 78,
-                }
-            }
-            162 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 78,
-                }
-            }
-            163 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 79,
-                }
-            }
-            164 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 79,
-                }
-            }
-            165 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 79,
-                }
-            }
-            166 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 79,
-                }
-            }
-            167 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 80,
-                }
-            }
-            168 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 80,
-                }
-            }
-            169 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 80,
-                }
-            }
-            170 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 80,
-                }
-            }
-            171 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 81,
-                }
-            }
-            172 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 81,
-                }
-            }
-            173 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 81,
-                }
-            }
-            174 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 81,
-                }
-            }
-            175 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 82,
-                }
-            }
-            176 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 82,
-                }
-            }
-            177 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 82,
-                }
-            }
-            178 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 82,
-                }
-            }
-            179 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 83,
-                }
-            }
-            180 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 83,
-                }
-            }
-            181 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 83,
-                }
-            }
-            182 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 83,
-                }
-            }
-            183 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 84,
-                }
-            }
-            184 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 84,
-                }
-            }
-            185 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 85,
-                }
-            }
-            186 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 85,
-                }
-            }
-            187 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 86,
-                }
-            }
-            188 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 87,
-                }
-            }
-            189 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 87,
-                }
-            }
-            190 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 88,
-                }
-            }
-            191 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 88,
-                }
-            }
-            192 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 89,
-                }
-            }
-            193 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 89,
-                }
-            }
-            194 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 90,
-                }
-            }
-            195 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 91,
-                }
-            }
-            196 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 92,
-                }
-            }
-            197 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 92,
-                }
-            }
-            198 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 93,
-                }
-            }
-            199 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 93,
-                }
-            }
-            200 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 94,
-                }
-            }
-            201 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 95,
-                }
-            }
-            202 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 95,
-                }
-            }
-            203 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 96,
-                }
-            }
-            204 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 97,
-                }
-            }
-            205 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 98,
-                }
-            }
-            206 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 7,
-                    nonterminal_produced: 98,
-                }
-            }
-            207 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 99,
-                }
-            }
-            208 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 100,
-                }
-            }
-            209 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 101,
-                }
-            }
-            210 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 101,
-                }
-            }
-            211 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 101,
-                }
-            }
-            212 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 101,
-                }
-            }
-            213 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 101,
-                }
-            }
-            214 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 101,
-                }
-            }
-            215 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 101,
-                }
-            }
-            216 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 101,
-                }
-            }
-            217 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 102,
-                }
-            }
-            218 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 103,
-                }
-            }
-            219 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 104,
-                }
-            }
-            220 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 0,
-                    nonterminal_produced: 104,
-                }
-            }
-            221 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 105,
-                }
-            }
-            222 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 106,
-                }
-            }
-            223 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 106,
-                }
-            }
-            224 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            225 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 107,
-                }
-            }
-            226 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 107,
-                }
-            }
-            227 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 107,
-                }
-            }
-            228 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 107,
-                }
-            }
-            229 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 107,
-                }
-            }
-            230 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 107,
-                }
-            }
-            231 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 107,
-                }
-            }
-            232 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            233 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 107,
-                }
-            }
-            234 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 107,
-                }
-            }
-            235 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 7,
-                    nonterminal_produced: 107,
-                }
-            }
-            236 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 2,
-                    nonterminal_produced: 107,
-                }
-            }
-            237 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 107,
-                }
-            }
-            238 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            239 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            240 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 107,
-                }
-            }
-            241 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            242 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 10,
-                    nonterminal_produced: 107,
-                }
-            }
-            243 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 5,
-                    nonterminal_produced: 107,
-                }
-            }
-            244 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 6,
-                    nonterminal_produced: 107,
-                }
-            }
-            245 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 107,
-                }
-            }
-            246 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 4,
-                    nonterminal_produced: 107,
-                }
-            }
-            247 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 108,
-                }
-            }
-            248 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 109,
-                }
-            }
-            249 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 1,
-                    nonterminal_produced: 110,
-                }
-            }
-            250 => {
-                __state_machine::SimulatedReduce::Reduce {
-                    states_to_pop: 3,
-                    nonterminal_produced: 110,
-                }
-            }
-            251 => __state_machine::SimulatedReduce::Accept,
-            _ => panic!("invalid reduction index {}", __reduce_index)
-        }
-    }
-    pub struct AnnotatedModuleParser {
-        _priv: (),
-    }
-
-    impl AnnotatedModuleParser {
-        pub fn new() -> AnnotatedModuleParser {
-            AnnotatedModuleParser {
-                _priv: (),
-            }
-        }
-
-        #[allow(dead_code)]
-        pub fn parse<
-            'input,
-            __TOKEN: __ToTriple<'input, >,
-            __TOKENS: IntoIterator<Item=__TOKEN>,
-        >(
-            &self,
-            text: &'input str,
-            __tokens0: __TOKENS,
-        ) -> Result<Annotated<Module>, __lalrpop_util::ParseError<usize, Tok<'input>, ()>>
-        {
-            let __tokens = __tokens0.into_iter();
-            let mut __tokens = __tokens.map(|t| __ToTriple::to_triple(t));
-            let __r = __state_machine::Parser::drive(
-                __StateMachine {
-                    text,
-                    __phantom: ::std::marker::PhantomData::<(&())>,
-                },
-                __tokens,
-            );
-            __r
-        }
-    }
-    pub(crate) fn __reduce<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> Option<Result<Annotated<Module>,__lalrpop_util::ParseError<usize, Tok<'input>, ()>>>
-    {
-        let (__pop_states, __nonterminal) = match __action {
-            0 => {
-                __reduce0(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            1 => {
-                __reduce1(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            2 => {
-                __reduce2(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            3 => {
-                __reduce3(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            4 => {
-                __reduce4(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            5 => {
-                __reduce5(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            6 => {
-                __reduce6(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            7 => {
-                __reduce7(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            8 => {
-                __reduce8(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            9 => {
-                __reduce9(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            10 => {
-                __reduce10(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            11 => {
-                __reduce11(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            12 => {
-                __reduce12(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            13 => {
-                __reduce13(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            14 => {
-                __reduce14(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            15 => {
-                __reduce15(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            16 => {
-                __reduce16(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            17 => {
-                __reduce17(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            18 => {
-                __reduce18(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            19 => {
-                __reduce19(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            20 => {
-                __reduce20(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            21 => {
-                __reduce21(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            22 => {
-                __reduce22(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            23 => {
-                __reduce23(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            24 => {
-                __reduce24(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            25 => {
-                __reduce25(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            26 => {
-                __reduce26(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            27 => {
-                __reduce27(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            28 => {
-                __reduce28(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            29 => {
-                __reduce29(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            30 => {
-                __reduce30(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            31 => {
-                __reduce31(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            32 => {
-                __reduce32(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            33 => {
-                __reduce33(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            34 => {
-                __reduce34(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            35 => {
-                __reduce35(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            36 => {
-                __reduce36(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            37 => {
-                __reduce37(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            38 => {
-                __reduce38(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            39 => {
-                __reduce39(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            40 => {
-                __reduce40(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            41 => {
-                __reduce41(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            42 => {
-                __reduce42(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            43 => {
-                __reduce43(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            44 => {
-                __reduce44(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            45 => {
-                __reduce45(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            46 => {
-                __reduce46(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            47 => {
-                __reduce47(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            48 => {
-                __reduce48(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            49 => {
-                __reduce49(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            50 => {
-                __reduce50(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            51 => {
-                __reduce51(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            52 => {
-                __reduce52(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            53 => {
-                __reduce53(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            54 => {
-                __reduce54(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            55 => {
-                __reduce55(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            56 => {
-                __reduce56(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            57 => {
-                __reduce57(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            58 => {
-                __reduce58(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            59 => {
-                __reduce59(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            60 => {
-                __reduce60(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            61 => {
-                __reduce61(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            62 => {
-                __reduce62(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            63 => {
-                __reduce63(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            64 => {
-                __reduce64(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            65 => {
-                __reduce65(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            66 => {
-                __reduce66(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            67 => {
-                __reduce67(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            68 => {
-                __reduce68(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            69 => {
-                __reduce69(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            70 => {
-                __reduce70(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            71 => {
-                __reduce71(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            72 => {
-                __reduce72(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            73 => {
-                __reduce73(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            74 => {
-                __reduce74(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            75 => {
-                __reduce75(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            76 => {
-                __reduce76(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            77 => {
-                __reduce77(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            78 => {
-                __reduce78(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            79 => {
-                __reduce79(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            80 => {
-                __reduce80(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            81 => {
-                __reduce81(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            82 => {
-                __reduce82(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            83 => {
-                __reduce83(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            84 => {
-                __reduce84(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            85 => {
-                __reduce85(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            86 => {
-                __reduce86(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            87 => {
-                __reduce87(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            88 => {
-                __reduce88(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            89 => {
-                __reduce89(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            90 => {
-                __reduce90(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            91 => {
-                __reduce91(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            92 => {
-                __reduce92(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            93 => {
-                __reduce93(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            94 => {
-                __reduce94(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            95 => {
-                __reduce95(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            96 => {
-                __reduce96(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            97 => {
-                __reduce97(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            98 => {
-                __reduce98(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            99 => {
-                __reduce99(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            100 => {
-                __reduce100(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            101 => {
-                __reduce101(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            102 => {
-                __reduce102(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            103 => {
-                __reduce103(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            104 => {
-                __reduce104(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            105 => {
-                __reduce105(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            106 => {
-                __reduce106(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            107 => {
-                __reduce107(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            108 => {
-                __reduce108(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            109 => {
-                __reduce109(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            110 => {
-                __reduce110(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            111 => {
-                __reduce111(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            112 => {
-                __reduce112(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            113 => {
-                __reduce113(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            114 => {
-                __reduce114(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            115 => {
-                __reduce115(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            116 => {
-                __reduce116(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            117 => {
-                __reduce117(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            118 => {
-                __reduce118(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            119 => {
-                __reduce119(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            120 => {
-                __reduce120(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            121 => {
-                __reduce121(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            122 => {
-                __reduce122(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            123 => {
-                __reduce123(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            124 => {
-                __reduce124(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            125 => {
-                __reduce125(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            126 => {
-                __reduce126(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            127 => {
-                __reduce127(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            128 => {
-                __reduce128(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            129 => {
-                __reduce129(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            130 => {
-                __reduce130(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            131 => {
-                __reduce131(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            132 => {
-                __reduce132(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            133 => {
-                __reduce133(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            134 => {
-                __reduce134(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            135 => {
-                __reduce135(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            136 => {
-                __reduce136(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            137 => {
-                __reduce137(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            138 => {
-                __reduce138(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            139 => {
-                __reduce139(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            140 => {
-                __reduce140(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            141 => {
-                __reduce141(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            142 => {
-                __reduce142(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            143 => {
-                __reduce143(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            144 => {
-                __reduce144(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            145 => {
-                __reduce145(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            146 => {
-                __reduce146(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            147 => {
-                __reduce147(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            148 => {
-                __reduce148(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            149 => {
-                __reduce149(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            150 => {
-                __reduce150(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            151 => {
-                __reduce151(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            152 => {
-                __reduce152(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            153 => {
-                __reduce153(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            154 => {
-                __reduce154(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            155 => {
-                __reduce155(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            156 => {
-                __reduce156(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            157 => {
-                __reduce157(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            158 => {
-                __reduce158(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            159 => {
-                __reduce159(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            160 => {
-                __reduce160(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            161 => {
-                __reduce161(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            162 => {
-                __reduce162(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            163 => {
-                __reduce163(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            164 => {
-                __reduce164(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            165 => {
-                __reduce165(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            166 => {
-                __reduce166(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            167 => {
-                __reduce167(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            168 => {
-                __reduce168(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            169 => {
-                __reduce169(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            170 => {
-                __reduce170(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            171 => {
-                __reduce171(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            172 => {
-                __reduce172(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            173 => {
-                __reduce173(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            174 => {
-                __reduce174(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            175 => {
-                __reduce175(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            176 => {
-                __reduce176(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            177 => {
-                __reduce177(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            178 => {
-                __reduce178(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            179 => {
-                __reduce179(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            180 => {
-                __reduce180(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            181 => {
-                __reduce181(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            182 => {
-                __reduce182(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            183 => {
-                __reduce183(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            184 => {
-                __reduce184(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            185 => {
-                __reduce185(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            186 => {
-                __reduce186(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            187 => {
-                __reduce187(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            188 => {
-                __reduce188(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            189 => {
-                __reduce189(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            190 => {
-                __reduce190(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            191 => {
-                __reduce191(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            192 => {
-                __reduce192(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            193 => {
-                __reduce193(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            194 => {
-                __reduce194(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            195 => {
-                __reduce195(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            196 => {
-                __reduce196(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            197 => {
-                __reduce197(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            198 => {
-                __reduce198(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            199 => {
-                __reduce199(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            200 => {
-                __reduce200(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            201 => {
-                __reduce201(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            202 => {
-                __reduce202(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            203 => {
-                __reduce203(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            204 => {
-                __reduce204(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            205 => {
-                __reduce205(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            206 => {
-                __reduce206(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            207 => {
-                __reduce207(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            208 => {
-                __reduce208(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            209 => {
-                __reduce209(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            210 => {
-                __reduce210(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            211 => {
-                __reduce211(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            212 => {
-                __reduce212(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            213 => {
-                __reduce213(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            214 => {
-                __reduce214(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            215 => {
-                __reduce215(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            216 => {
-                __reduce216(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            217 => {
-                __reduce217(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            218 => {
-                __reduce218(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            219 => {
-                __reduce219(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            220 => {
-                __reduce220(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            221 => {
-                __reduce221(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            222 => {
-                __reduce222(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            223 => {
-                __reduce223(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            224 => {
-                __reduce224(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            225 => {
-                __reduce225(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            226 => {
-                __reduce226(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            227 => {
-                __reduce227(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            228 => {
-                __reduce228(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            229 => {
-                __reduce229(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            230 => {
-                __reduce230(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            231 => {
-                __reduce231(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            232 => {
-                __reduce232(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            233 => {
-                __reduce233(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            234 => {
-                __reduce234(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            235 => {
-                __reduce235(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            236 => {
-                __reduce236(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            237 => {
-                __reduce237(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            238 => {
-                __reduce238(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            239 => {
-                __reduce239(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            240 => {
-                __reduce240(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            241 => {
-                __reduce241(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            242 => {
-                __reduce242(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            243 => {
-                __reduce243(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            244 => {
-                __reduce244(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            245 => {
-                __reduce245(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            246 => {
-                __reduce246(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            247 => {
-                __reduce247(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            248 => {
-                __reduce248(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            249 => {
-                __reduce249(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            250 => {
-                __reduce250(text, __action, __lookahead_start, __states, __symbols, ::std::marker::PhantomData::<(&())>)
-            }
-            251 => {
-                // __AnnotatedModule = AnnotatedModule => ActionFn(0);
-                let __sym0 = __pop_Variant39(__symbols);
-                let __start = __sym0.0.clone();
-                let __end = __sym0.2.clone();
-                let __nt = super::__action0::<>(text, __sym0);
-                return Some(Ok(__nt));
-            }
-            _ => panic!("invalid action code {}", __action)
-        };
-        let __states_len = __states.len();
-        __states.truncate(__states_len - __pop_states);
-        let __state = *__states.last().unwrap() as usize;
-        let __next_state = __GOTO[__state * 112 + __nonterminal] - 1;
-        __states.push(__next_state);
-        None
-    }
-    fn __pop_Variant44<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant44(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant32<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant32(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant70<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Annotated<SingleExpression>, Annotated<Pattern>), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant70(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant10<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Atom, Constant), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant10(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant27<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Expression, MapExactAssoc, Expression), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant27(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant22<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Expression, Vec<Expression>), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant22(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant30<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (FunctionName, Function), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant30(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant8<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (Tok<'input>, Constant), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant8(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant3<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, (bool, &'input str), usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant3(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant19<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant19(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant12<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant12(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant16<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<Atom>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant16(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant35<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<CaseClause>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant35(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant37<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<Function>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant37(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant38<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<FunctionName>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant38(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant39<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<Module>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant39(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant4<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<Pattern>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant4(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant14<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<SingleExpression>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant14(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant41<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Annotated<Vec<Annotated<SingleExpression>>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant41(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant45<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Atom, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant45(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant46<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, AtomicTerm, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant46(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant66<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, BigInt, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant66(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant49<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, CaseClause, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant49(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant24<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Constant, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant24(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant6<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Expression, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant6(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant62<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Function, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant62(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant63<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, FunctionDefinition, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant63(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant28<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, FunctionName, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant28(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant61<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, MapExactAssoc, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant61(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant67<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Module, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant67(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant68<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Pattern, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant68(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant71<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, SingleExpression, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant71(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant0<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Tok<'input>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant0(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant59<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant59(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant50<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<(Atom, Constant)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant50(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant47<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<(Expression, Vec<Expression>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant47(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant55<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant55(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant51<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant51(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant53<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Annotated<Atom>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant53(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant54<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Annotated<Pattern>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant54(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant52<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Annotated<SingleExpression>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant52(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant56<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Constant>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant56(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant57<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<Expression>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant57(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant58<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, Vec<FunctionName>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant58(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant2<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, char, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant2(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant69<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant69(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant21<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<(Atom, Constant)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant21(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant48<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<(Expression, Vec<Expression>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant48(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant9<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<(Tok<'input>, Constant)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant9(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant43<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant43(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant34<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant34(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant42<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Annotated<Atom>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant42(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant5<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Annotated<Pattern>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant5(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant40<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Annotated<SingleExpression>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant40(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant60<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Constant>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant60(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant7<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<Expression>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant7(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant65<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::option::Option<FunctionName>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant65(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant33<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant33(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant11<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<(Atom, Constant)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant11(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant23<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant23(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant31<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<(FunctionName, Function)>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant31(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant20<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant20(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant13<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant13(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant17<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<Atom>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant17(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant36<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant36(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant18<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<Pattern>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant18(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant15<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant15(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant25<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Constant>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant25(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant26<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<Expression>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant26(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant64<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<FunctionDefinition>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant64(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant29<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, ::std::vec::Vec<FunctionName>, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant29(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    fn __pop_Variant1<
-      'input,
-    >(
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>
-    ) -> (usize, &'input str, usize)
-     {
-        match __symbols.pop().unwrap() {
-            (__l, __Symbol::Variant1(__v), __r) => (__l, __v, __r),
-            _ => panic!("symbol type mismatch")
-        }
-    }
-    pub(crate) fn __reduce0<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <AnnotatedPattern>) = "|", AnnotatedPattern => ActionFn(73);
-        let __sym1 = __pop_Variant4(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action73::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (2, 0)
-    }
-    pub(crate) fn __reduce1<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <AnnotatedPattern>)? = "|", AnnotatedPattern => ActionFn(197);
-        let __sym1 = __pop_Variant4(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action197::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant5(__nt), __end));
-        (2, 1)
-    }
-    pub(crate) fn __reduce2<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <AnnotatedPattern>)? =  => ActionFn(72);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action72::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant5(__nt), __end));
-        (0, 1)
-    }
-    pub(crate) fn __reduce3<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <Expression>) = "|", Expression => ActionFn(95);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action95::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant6(__nt), __end));
-        (2, 2)
-    }
-    pub(crate) fn __reduce4<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <Expression>)? = "|", Expression => ActionFn(200);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action200::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant7(__nt), __end));
-        (2, 3)
-    }
-    pub(crate) fn __reduce5<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" <Expression>)? =  => ActionFn(94);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action94::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant7(__nt), __end));
-        (0, 3)
-    }
-    pub(crate) fn __reduce6<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" Constant) = "|", Constant => ActionFn(67);
-        let __sym1 = __pop_Variant24(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action67::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant8(__nt), __end));
-        (2, 4)
-    }
-    pub(crate) fn __reduce7<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" Constant)? = "|", Constant => ActionFn(205);
-        let __sym1 = __pop_Variant24(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action205::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant9(__nt), __end));
-        (2, 5)
-    }
-    pub(crate) fn __reduce8<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ("|" Constant)? =  => ActionFn(66);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action66::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant9(__nt), __end));
-        (0, 5)
-    }
-    pub(crate) fn __reduce9<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<(<Atom> "=" <Constant>)> ",") = Atom, "=", Constant, "," => ActionFn(210);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant24(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action210::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant10(__nt), __end));
-        (4, 6)
-    }
-    pub(crate) fn __reduce10<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<(<Atom> "=" <Constant>)> ",")* =  => ActionFn(125);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action125::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant11(__nt), __end));
-        (0, 7)
-    }
-    pub(crate) fn __reduce11<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<(<Atom> "=" <Constant>)> ",")* = (<(<Atom> "=" <Constant>)> ",")+ => ActionFn(126);
-        let __sym0 = __pop_Variant11(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action126::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant11(__nt), __end));
-        (1, 7)
-    }
-    pub(crate) fn __reduce12<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<(<Atom> "=" <Constant>)> ",")+ = Atom, "=", Constant, "," => ActionFn(212);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant24(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action212::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant11(__nt), __end));
-        (4, 8)
-    }
-    pub(crate) fn __reduce13<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<(<Atom> "=" <Constant>)> ",")+ = (<(<Atom> "=" <Constant>)> ",")+, Atom, "=", Constant, "," => ActionFn(213);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant24(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant11(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action213::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant11(__nt), __end));
-        (5, 8)
-    }
-    pub(crate) fn __reduce14<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",") = Annotated<(<Expression> <ExactAssoc> <Expression>)>, "," => ActionFn(147);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant12(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action147::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant12(__nt), __end));
-        (2, 9)
-    }
-    pub(crate) fn __reduce15<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")* =  => ActionFn(145);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action145::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant13(__nt), __end));
-        (0, 10)
-    }
-    pub(crate) fn __reduce16<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")* = (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+ => ActionFn(146);
-        let __sym0 = __pop_Variant13(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action146::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant13(__nt), __end));
-        (1, 10)
-    }
-    pub(crate) fn __reduce17<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+ = Annotated<(<Expression> <ExactAssoc> <Expression>)>, "," => ActionFn(216);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant12(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action216::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant13(__nt), __end));
-        (2, 11)
-    }
-    pub(crate) fn __reduce18<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+ = (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+, Annotated<(<Expression> <ExactAssoc> <Expression>)>, "," => ActionFn(217);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant12(__symbols);
-        let __sym0 = __pop_Variant13(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action217::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant13(__nt), __end));
-        (3, 11)
-    }
-    pub(crate) fn __reduce19<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<SingleExpression>> ",") = Annotated<SingleExpression>, "," => ActionFn(137);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action137::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant14(__nt), __end));
-        (2, 12)
-    }
-    pub(crate) fn __reduce20<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<SingleExpression>> ",")* =  => ActionFn(135);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action135::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant15(__nt), __end));
-        (0, 13)
-    }
-    pub(crate) fn __reduce21<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<SingleExpression>> ",")* = (<Annotated<SingleExpression>> ",")+ => ActionFn(136);
-        let __sym0 = __pop_Variant15(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action136::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant15(__nt), __end));
-        (1, 13)
-    }
-    pub(crate) fn __reduce22<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<SingleExpression>> ",")+ = Annotated<SingleExpression>, "," => ActionFn(220);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action220::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant15(__nt), __end));
-        (2, 14)
-    }
-    pub(crate) fn __reduce23<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<SingleExpression>> ",")+ = (<Annotated<SingleExpression>> ",")+, Annotated<SingleExpression>, "," => ActionFn(221);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant14(__symbols);
-        let __sym0 = __pop_Variant15(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action221::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant15(__nt), __end));
-        (3, 14)
-    }
-    pub(crate) fn __reduce24<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<Variable>> ",") = Annotated<Variable>, "," => ActionFn(132);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action132::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant16(__nt), __end));
-        (2, 15)
-    }
-    pub(crate) fn __reduce25<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<Variable>> ",")* =  => ActionFn(130);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action130::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant17(__nt), __end));
-        (0, 16)
-    }
-    pub(crate) fn __reduce26<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<Variable>> ",")* = (<Annotated<Variable>> ",")+ => ActionFn(131);
-        let __sym0 = __pop_Variant17(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action131::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant17(__nt), __end));
-        (1, 16)
-    }
-    pub(crate) fn __reduce27<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<Variable>> ",")+ = Annotated<Variable>, "," => ActionFn(224);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action224::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant17(__nt), __end));
-        (2, 17)
-    }
-    pub(crate) fn __reduce28<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Annotated<Variable>> ",")+ = (<Annotated<Variable>> ",")+, Annotated<Variable>, "," => ActionFn(225);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant16(__symbols);
-        let __sym0 = __pop_Variant17(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action225::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant17(__nt), __end));
-        (3, 17)
-    }
-    pub(crate) fn __reduce29<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPattern> ",") = AnnotatedPattern, "," => ActionFn(154);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action154::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (2, 18)
-    }
-    pub(crate) fn __reduce30<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPattern> ",")* =  => ActionFn(152);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action152::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant18(__nt), __end));
-        (0, 19)
-    }
-    pub(crate) fn __reduce31<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPattern> ",")* = (<AnnotatedPattern> ",")+ => ActionFn(153);
-        let __sym0 = __pop_Variant18(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action153::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant18(__nt), __end));
-        (1, 19)
-    }
-    pub(crate) fn __reduce32<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPattern> ",")+ = AnnotatedPattern, "," => ActionFn(228);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action228::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant18(__nt), __end));
-        (2, 20)
-    }
-    pub(crate) fn __reduce33<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPattern> ",")+ = (<AnnotatedPattern> ",")+, AnnotatedPattern, "," => ActionFn(229);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant4(__symbols);
-        let __sym0 = __pop_Variant18(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action229::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant18(__nt), __end));
-        (3, 20)
-    }
-    pub(crate) fn __reduce34<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPatternMapEntry> ",") = AnnotatedPatternMapEntry, "," => ActionFn(159);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant19(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action159::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant19(__nt), __end));
-        (2, 21)
-    }
-    pub(crate) fn __reduce35<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPatternMapEntry> ",")* =  => ActionFn(157);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action157::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant20(__nt), __end));
-        (0, 22)
-    }
-    pub(crate) fn __reduce36<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPatternMapEntry> ",")* = (<AnnotatedPatternMapEntry> ",")+ => ActionFn(158);
-        let __sym0 = __pop_Variant20(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action158::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant20(__nt), __end));
-        (1, 22)
-    }
-    pub(crate) fn __reduce37<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPatternMapEntry> ",")+ = AnnotatedPatternMapEntry, "," => ActionFn(232);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant19(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action232::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant20(__nt), __end));
-        (2, 23)
-    }
-    pub(crate) fn __reduce38<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<AnnotatedPatternMapEntry> ",")+ = (<AnnotatedPatternMapEntry> ",")+, AnnotatedPatternMapEntry, "," => ActionFn(233);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant19(__symbols);
-        let __sym0 = __pop_Variant20(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action233::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant20(__nt), __end));
-        (3, 23)
-    }
-    pub(crate) fn __reduce39<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Atom> "=" <Constant>) = Atom, "=", Constant => ActionFn(110);
-        let __sym2 = __pop_Variant24(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action110::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant10(__nt), __end));
-        (3, 24)
-    }
-    pub(crate) fn __reduce40<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Atom> "=" <Constant>)? = Atom, "=", Constant => ActionFn(211);
-        let __sym2 = __pop_Variant24(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action211::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant21(__nt), __end));
-        (3, 25)
-    }
-    pub(crate) fn __reduce41<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Atom> "=" <Constant>)? =  => ActionFn(124);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action124::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant21(__nt), __end));
-        (0, 25)
-    }
-    pub(crate) fn __reduce42<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<BinaryElem> ",") = BinaryElem, "," => ActionFn(169);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant22(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action169::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant22(__nt), __end));
-        (2, 26)
-    }
-    pub(crate) fn __reduce43<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<BinaryElem> ",")* =  => ActionFn(167);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action167::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant23(__nt), __end));
-        (0, 27)
-    }
-    pub(crate) fn __reduce44<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<BinaryElem> ",")* = (<BinaryElem> ",")+ => ActionFn(168);
-        let __sym0 = __pop_Variant23(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action168::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant23(__nt), __end));
-        (1, 27)
-    }
-    pub(crate) fn __reduce45<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<BinaryElem> ",")+ = BinaryElem, "," => ActionFn(240);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant22(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action240::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant23(__nt), __end));
-        (2, 28)
-    }
-    pub(crate) fn __reduce46<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<BinaryElem> ",")+ = (<BinaryElem> ",")+, BinaryElem, "," => ActionFn(241);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant22(__symbols);
-        let __sym0 = __pop_Variant23(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action241::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant23(__nt), __end));
-        (3, 28)
-    }
-    pub(crate) fn __reduce47<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Constant> ",") = Constant, "," => ActionFn(174);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant24(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action174::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant24(__nt), __end));
-        (2, 29)
-    }
-    pub(crate) fn __reduce48<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Constant> ",")* =  => ActionFn(172);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action172::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant25(__nt), __end));
-        (0, 30)
-    }
-    pub(crate) fn __reduce49<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Constant> ",")* = (<Constant> ",")+ => ActionFn(173);
-        let __sym0 = __pop_Variant25(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action173::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant25(__nt), __end));
-        (1, 30)
-    }
-    pub(crate) fn __reduce50<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Constant> ",")+ = Constant, "," => ActionFn(244);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant24(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action244::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant25(__nt), __end));
-        (2, 31)
-    }
-    pub(crate) fn __reduce51<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Constant> ",")+ = (<Constant> ",")+, Constant, "," => ActionFn(245);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant24(__symbols);
-        let __sym0 = __pop_Variant25(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action245::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant25(__nt), __end));
-        (3, 31)
-    }
-    pub(crate) fn __reduce52<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> ",") = Expression, "," => ActionFn(142);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action142::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant6(__nt), __end));
-        (2, 32)
-    }
-    pub(crate) fn __reduce53<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> ",")* =  => ActionFn(140);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action140::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant26(__nt), __end));
-        (0, 33)
-    }
-    pub(crate) fn __reduce54<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> ",")* = (<Expression> ",")+ => ActionFn(141);
-        let __sym0 = __pop_Variant26(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action141::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant26(__nt), __end));
-        (1, 33)
-    }
-    pub(crate) fn __reduce55<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> ",")+ = Expression, "," => ActionFn(248);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action248::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant26(__nt), __end));
-        (2, 34)
-    }
-    pub(crate) fn __reduce56<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> ",")+ = (<Expression> ",")+, Expression, "," => ActionFn(249);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant26(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action249::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant26(__nt), __end));
-        (3, 34)
-    }
-    pub(crate) fn __reduce57<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<Expression> <ExactAssoc> <Expression>) = Expression, ExactAssoc, Expression => ActionFn(92);
-        let __sym2 = __pop_Variant6(__symbols);
-        let __sym1 = __pop_Variant61(__symbols);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action92::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant27(__nt), __end));
-        (3, 35)
-    }
-    pub(crate) fn __reduce58<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> ",") = FunctionName, "," => ActionFn(122);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action122::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant28(__nt), __end));
-        (2, 36)
-    }
-    pub(crate) fn __reduce59<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> ",")* =  => ActionFn(120);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action120::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant29(__nt), __end));
-        (0, 37)
-    }
-    pub(crate) fn __reduce60<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> ",")* = (<FunctionName> ",")+ => ActionFn(121);
-        let __sym0 = __pop_Variant29(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action121::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant29(__nt), __end));
-        (1, 37)
-    }
-    pub(crate) fn __reduce61<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> ",")+ = FunctionName, "," => ActionFn(254);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action254::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant29(__nt), __end));
-        (2, 38)
-    }
-    pub(crate) fn __reduce62<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> ",")+ = (<FunctionName> ",")+, FunctionName, "," => ActionFn(255);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant28(__symbols);
-        let __sym0 = __pop_Variant29(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action255::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant29(__nt), __end));
-        (3, 38)
-    }
-    pub(crate) fn __reduce63<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> "=" <Fun>) = FunctionName, "=", Fun => ActionFn(82);
-        let __sym2 = __pop_Variant62(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action82::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant30(__nt), __end));
-        (3, 39)
-    }
-    pub(crate) fn __reduce64<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> "=" <Fun>)+ = FunctionName, "=", Fun => ActionFn(258);
-        let __sym2 = __pop_Variant62(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action258::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant31(__nt), __end));
-        (3, 40)
-    }
-    pub(crate) fn __reduce65<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<FunctionName> "=" <Fun>)+ = (<FunctionName> "=" <Fun>)+, FunctionName, "=", Fun => ActionFn(259);
-        let __sym3 = __pop_Variant62(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant28(__symbols);
-        let __sym0 = __pop_Variant31(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action259::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant31(__nt), __end));
-        (4, 40)
-    }
-    pub(crate) fn __reduce66<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<PatternBinaryElem> ",") = PatternBinaryElem, "," => ActionFn(164);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant32(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action164::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant32(__nt), __end));
-        (2, 41)
-    }
-    pub(crate) fn __reduce67<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<PatternBinaryElem> ",")* =  => ActionFn(162);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action162::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant33(__nt), __end));
-        (0, 42)
-    }
-    pub(crate) fn __reduce68<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<PatternBinaryElem> ",")* = (<PatternBinaryElem> ",")+ => ActionFn(163);
-        let __sym0 = __pop_Variant33(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action163::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant33(__nt), __end));
-        (1, 42)
-    }
-    pub(crate) fn __reduce69<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<PatternBinaryElem> ",")+ = PatternBinaryElem, "," => ActionFn(260);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant32(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action260::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant33(__nt), __end));
-        (2, 43)
-    }
-    pub(crate) fn __reduce70<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // (<PatternBinaryElem> ",")+ = (<PatternBinaryElem> ",")+, PatternBinaryElem, "," => ActionFn(261);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant32(__symbols);
-        let __sym0 = __pop_Variant33(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action261::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant33(__nt), __end));
-        (3, 43)
-    }
-    pub(crate) fn __reduce71<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<(<Expression> <ExactAssoc> <Expression>)> = Expression, ExactAssoc, Expression => ActionFn(252);
-        let __sym2 = __pop_Variant6(__symbols);
-        let __sym1 = __pop_Variant61(__symbols);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action252::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant12(__nt), __end));
-        (3, 44)
-    }
-    pub(crate) fn __reduce72<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<(<Expression> <ExactAssoc> <Expression>)> = "(", Expression, ExactAssoc, Expression, Annotations, ")" => ActionFn(253);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant44(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant61(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action253::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant12(__nt), __end));
-        (6, 44)
-    }
-    pub(crate) fn __reduce73<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<(<Expression> <ExactAssoc> <Expression>)>? = Annotated<(<Expression> <ExactAssoc> <Expression>)> => ActionFn(143);
-        let __sym0 = __pop_Variant12(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action143::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant34(__nt), __end));
-        (1, 45)
-    }
-    pub(crate) fn __reduce74<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<(<Expression> <ExactAssoc> <Expression>)>? =  => ActionFn(144);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action144::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant34(__nt), __end));
-        (0, 45)
-    }
-    pub(crate) fn __reduce75<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Atom> = Atom => ActionFn(83);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action83::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant16(__nt), __end));
-        (1, 46)
-    }
-    pub(crate) fn __reduce76<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Atom> = "(", Atom, Annotations, ")" => ActionFn(84);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action84::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant16(__nt), __end));
-        (4, 46)
-    }
-    pub(crate) fn __reduce77<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause> = Clause => ActionFn(87);
-        let __sym0 = __pop_Variant49(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action87::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant35(__nt), __end));
-        (1, 47)
-    }
-    pub(crate) fn __reduce78<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause> = "(", Clause, Annotations, ")" => ActionFn(88);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant49(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action88::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant35(__nt), __end));
-        (4, 47)
-    }
-    pub(crate) fn __reduce79<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause>* =  => ActionFn(85);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action85::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant36(__nt), __end));
-        (0, 48)
-    }
-    pub(crate) fn __reduce80<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause>* = Annotated<Clause>+ => ActionFn(86);
-        let __sym0 = __pop_Variant36(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action86::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant36(__nt), __end));
-        (1, 48)
-    }
-    pub(crate) fn __reduce81<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause>+ = Annotated<Clause> => ActionFn(148);
-        let __sym0 = __pop_Variant35(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action148::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant36(__nt), __end));
-        (1, 49)
-    }
-    pub(crate) fn __reduce82<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Clause>+ = Annotated<Clause>+, Annotated<Clause> => ActionFn(149);
-        let __sym1 = __pop_Variant35(__symbols);
-        let __sym0 = __pop_Variant36(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action149::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant36(__nt), __end));
-        (2, 49)
-    }
-    pub(crate) fn __reduce83<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Fun> = Fun => ActionFn(105);
-        let __sym0 = __pop_Variant62(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action105::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant37(__nt), __end));
-        (1, 50)
-    }
-    pub(crate) fn __reduce84<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Fun> = "(", Fun, Annotations, ")" => ActionFn(106);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant62(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action106::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant37(__nt), __end));
-        (4, 50)
-    }
-    pub(crate) fn __reduce85<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<FunctionName> = FunctionName => ActionFn(107);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action107::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant38(__nt), __end));
-        (1, 51)
-    }
-    pub(crate) fn __reduce86<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<FunctionName> = "(", FunctionName, Annotations, ")" => ActionFn(108);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant28(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action108::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant38(__nt), __end));
-        (4, 51)
-    }
-    pub(crate) fn __reduce87<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Module> = Module => ActionFn(114);
-        let __sym0 = __pop_Variant67(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action114::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant39(__nt), __end));
-        (1, 52)
-    }
-    pub(crate) fn __reduce88<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Module> = "(", Module, Annotations, ")" => ActionFn(115);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant67(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action115::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant39(__nt), __end));
-        (4, 52)
-    }
-    pub(crate) fn __reduce89<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = Annotated<Variable>, "=", AnnotatedPattern => ActionFn(306);
-        let __sym2 = __pop_Variant4(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action306::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (3, 53)
-    }
-    pub(crate) fn __reduce90<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = Variable => ActionFn(307);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action307::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (1, 53)
-    }
-    pub(crate) fn __reduce91<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = AtomicTerm => ActionFn(308);
-        let __sym0 = __pop_Variant46(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action308::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (1, 53)
-    }
-    pub(crate) fn __reduce92<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = PatternBinary => ActionFn(309);
-        let __sym0 = __pop_Variant59(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action309::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (1, 53)
-    }
-    pub(crate) fn __reduce93<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "{", Comma<AnnotatedPattern>, "}" => ActionFn(310);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action310::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (3, 53)
-    }
-    pub(crate) fn __reduce94<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "~{", Comma<AnnotatedPatternMapEntry>, "}~" => ActionFn(311);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant55(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action311::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (3, 53)
-    }
-    pub(crate) fn __reduce95<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "[", Comma<AnnotatedPattern>, "|", AnnotatedPattern, "]" => ActionFn(312);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant4(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action312::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (5, 53)
-    }
-    pub(crate) fn __reduce96<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "[", Comma<AnnotatedPattern>, "]" => ActionFn(313);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action313::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (3, 53)
-    }
-    pub(crate) fn __reduce97<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", Annotated<Variable>, "=", AnnotatedPattern, Annotations, ")" => ActionFn(314);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant44(__symbols);
-        let __sym3 = __pop_Variant4(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant16(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action314::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (6, 53)
-    }
-    pub(crate) fn __reduce98<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", Variable, Annotations, ")" => ActionFn(315);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action315::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (4, 53)
-    }
-    pub(crate) fn __reduce99<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", AtomicTerm, Annotations, ")" => ActionFn(316);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant46(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action316::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (4, 53)
-    }
-    pub(crate) fn __reduce100<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", PatternBinary, Annotations, ")" => ActionFn(317);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant59(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action317::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (4, 53)
-    }
-    pub(crate) fn __reduce101<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", "{", Comma<AnnotatedPattern>, "}", Annotations, ")" => ActionFn(318);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant44(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant54(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action318::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (6, 53)
-    }
-    pub(crate) fn __reduce102<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", "~{", Comma<AnnotatedPatternMapEntry>, "}~", Annotations, ")" => ActionFn(319);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant44(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant55(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action319::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (6, 53)
-    }
-    pub(crate) fn __reduce103<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", "[", Comma<AnnotatedPattern>, "|", AnnotatedPattern, "]", Annotations, ")" => ActionFn(320);
-        let __sym7 = __pop_Variant0(__symbols);
-        let __sym6 = __pop_Variant44(__symbols);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant4(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant54(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym7.2.clone();
-        let __nt = super::__action320::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5, __sym6, __sym7);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (8, 53)
-    }
-    pub(crate) fn __reduce104<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Pattern> = "(", "[", Comma<AnnotatedPattern>, "]", Annotations, ")" => ActionFn(321);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant44(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant54(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action321::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (6, 53)
-    }
-    pub(crate) fn __reduce105<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<PatternMapEntry> = PatternMapEntry => ActionFn(75);
-        let __sym0 = __pop_Variant70(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action75::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant19(__nt), __end));
-        (1, 54)
-    }
-    pub(crate) fn __reduce106<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<PatternMapEntry> = "(", PatternMapEntry, Annotations, ")" => ActionFn(76);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant70(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action76::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant19(__nt), __end));
-        (4, 54)
-    }
-    pub(crate) fn __reduce107<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<SingleExpression> = SingleExpression => ActionFn(98);
-        let __sym0 = __pop_Variant71(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action98::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant14(__nt), __end));
-        (1, 55)
-    }
-    pub(crate) fn __reduce108<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<SingleExpression> = "(", SingleExpression, Annotations, ")" => ActionFn(99);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant71(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action99::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant14(__nt), __end));
-        (4, 55)
-    }
-    pub(crate) fn __reduce109<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<SingleExpression>? = Annotated<SingleExpression> => ActionFn(133);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action133::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant40(__nt), __end));
-        (1, 56)
-    }
-    pub(crate) fn __reduce110<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<SingleExpression>? =  => ActionFn(134);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action134::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant40(__nt), __end));
-        (0, 56)
-    }
-    pub(crate) fn __reduce111<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<ValueList> = ValueList => ActionFn(100);
-        let __sym0 = __pop_Variant52(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action100::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant41(__nt), __end));
-        (1, 57)
-    }
-    pub(crate) fn __reduce112<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<ValueList> = "(", ValueList, Annotations, ")" => ActionFn(101);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant52(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action101::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant41(__nt), __end));
-        (4, 57)
-    }
-    pub(crate) fn __reduce113<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Variable> = Variable => ActionFn(103);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action103::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant16(__nt), __end));
-        (1, 58)
-    }
-    pub(crate) fn __reduce114<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Variable> = "(", Variable, Annotations, ")" => ActionFn(104);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant44(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action104::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant16(__nt), __end));
-        (4, 58)
-    }
-    pub(crate) fn __reduce115<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Variable>? = Annotated<Variable> => ActionFn(128);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action128::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant42(__nt), __end));
-        (1, 59)
-    }
-    pub(crate) fn __reduce116<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotated<Variable>? =  => ActionFn(129);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action129::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant42(__nt), __end));
-        (0, 59)
-    }
-    pub(crate) fn __reduce117<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedModule = Annotated<Module> => ActionFn(4);
-        let __sym0 = __pop_Variant39(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action4::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant39(__nt), __end));
-        (1, 60)
-    }
-    pub(crate) fn __reduce118<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPattern = Annotated<Pattern> => ActionFn(41);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action41::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant4(__nt), __end));
-        (1, 61)
-    }
-    pub(crate) fn __reduce119<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPattern? = AnnotatedPattern => ActionFn(150);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action150::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant5(__nt), __end));
-        (1, 62)
-    }
-    pub(crate) fn __reduce120<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPattern? =  => ActionFn(151);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action151::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant5(__nt), __end));
-        (0, 62)
-    }
-    pub(crate) fn __reduce121<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPatternMapEntry = Annotated<PatternMapEntry> => ActionFn(43);
-        let __sym0 = __pop_Variant19(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action43::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant19(__nt), __end));
-        (1, 63)
-    }
-    pub(crate) fn __reduce122<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPatternMapEntry? = AnnotatedPatternMapEntry => ActionFn(155);
-        let __sym0 = __pop_Variant19(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action155::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant43(__nt), __end));
-        (1, 64)
-    }
-    pub(crate) fn __reduce123<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AnnotatedPatternMapEntry? =  => ActionFn(156);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action156::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant43(__nt), __end));
-        (0, 64)
-    }
-    pub(crate) fn __reduce124<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Annotations = "-|", "[", Comma<Constant>, "]" => ActionFn(64);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant56(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action64::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant44(__nt), __end));
-        (4, 65)
-    }
-    pub(crate) fn __reduce125<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Atom = "Atom" => ActionFn(2);
-        let __sym0 = __pop_Variant1(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action2::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant45(__nt), __end));
-        (1, 66)
-    }
-    pub(crate) fn __reduce126<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AtomicTerm = Integer => ActionFn(58);
-        let __sym0 = __pop_Variant66(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action58::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant46(__nt), __end));
-        (1, 67)
-    }
-    pub(crate) fn __reduce127<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AtomicTerm = Atom => ActionFn(59);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action59::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant46(__nt), __end));
-        (1, 67)
-    }
-    pub(crate) fn __reduce128<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AtomicTerm = "Char" => ActionFn(60);
-        let __sym0 = __pop_Variant2(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action60::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant46(__nt), __end));
-        (1, 67)
-    }
-    pub(crate) fn __reduce129<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // AtomicTerm = "String" => ActionFn(61);
-        let __sym0 = __pop_Variant1(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action61::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant46(__nt), __end));
-        (1, 67)
-    }
-    pub(crate) fn __reduce130<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Binary = "#{", Comma<BinaryElem>, "}#" => ActionFn(53);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant47(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action53::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant47(__nt), __end));
-        (3, 68)
-    }
-    pub(crate) fn __reduce131<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // BinaryElem = "#<", Expression, ">(", Comma<Expression>, ")" => ActionFn(54);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant57(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action54::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant22(__nt), __end));
-        (5, 69)
-    }
-    pub(crate) fn __reduce132<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // BinaryElem? = BinaryElem => ActionFn(165);
-        let __sym0 = __pop_Variant22(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action165::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant48(__nt), __end));
-        (1, 70)
-    }
-    pub(crate) fn __reduce133<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // BinaryElem? =  => ActionFn(166);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action166::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant48(__nt), __end));
-        (0, 70)
-    }
-    pub(crate) fn __reduce134<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Clause = Patterns, Guard, "->", Expression => ActionFn(35);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant54(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action35::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant49(__nt), __end));
-        (4, 71)
-    }
-    pub(crate) fn __reduce135<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<(<Atom> "=" <Constant>)> = Atom, "=", Constant => ActionFn(236);
-        let __sym2 = __pop_Variant24(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action236::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant50(__nt), __end));
-        (3, 72)
-    }
-    pub(crate) fn __reduce136<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<(<Atom> "=" <Constant>)> =  => ActionFn(237);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action237::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant50(__nt), __end));
-        (0, 72)
-    }
-    pub(crate) fn __reduce137<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<(<Atom> "=" <Constant>)> = (<(<Atom> "=" <Constant>)> ",")+, Atom, "=", Constant => ActionFn(238);
-        let __sym3 = __pop_Variant24(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant11(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action238::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant50(__nt), __end));
-        (4, 72)
-    }
-    pub(crate) fn __reduce138<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<(<Atom> "=" <Constant>)> = (<(<Atom> "=" <Constant>)> ",")+ => ActionFn(239);
-        let __sym0 = __pop_Variant11(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action239::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant50(__nt), __end));
-        (1, 72)
-    }
-    pub(crate) fn __reduce139<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>> = Annotated<(<Expression> <ExactAssoc> <Expression>)> => ActionFn(264);
-        let __sym0 = __pop_Variant12(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action264::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant51(__nt), __end));
-        (1, 73)
-    }
-    pub(crate) fn __reduce140<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>> =  => ActionFn(265);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action265::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant51(__nt), __end));
-        (0, 73)
-    }
-    pub(crate) fn __reduce141<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>> = (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+, Annotated<(<Expression> <ExactAssoc> <Expression>)> => ActionFn(266);
-        let __sym1 = __pop_Variant12(__symbols);
-        let __sym0 = __pop_Variant13(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action266::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant51(__nt), __end));
-        (2, 73)
-    }
-    pub(crate) fn __reduce142<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>> = (<Annotated<(<Expression> <ExactAssoc> <Expression>)>> ",")+ => ActionFn(267);
-        let __sym0 = __pop_Variant13(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action267::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant51(__nt), __end));
-        (1, 73)
-    }
-    pub(crate) fn __reduce143<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<SingleExpression>> = Annotated<SingleExpression> => ActionFn(272);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action272::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant52(__nt), __end));
-        (1, 74)
-    }
-    pub(crate) fn __reduce144<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<SingleExpression>> =  => ActionFn(273);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action273::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant52(__nt), __end));
-        (0, 74)
-    }
-    pub(crate) fn __reduce145<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<SingleExpression>> = (<Annotated<SingleExpression>> ",")+, Annotated<SingleExpression> => ActionFn(274);
-        let __sym1 = __pop_Variant14(__symbols);
-        let __sym0 = __pop_Variant15(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action274::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant52(__nt), __end));
-        (2, 74)
-    }
-    pub(crate) fn __reduce146<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<SingleExpression>> = (<Annotated<SingleExpression>> ",")+ => ActionFn(275);
-        let __sym0 = __pop_Variant15(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action275::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant52(__nt), __end));
-        (1, 74)
-    }
-    pub(crate) fn __reduce147<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<Variable>> = Annotated<Variable> => ActionFn(276);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action276::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (1, 75)
-    }
-    pub(crate) fn __reduce148<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<Variable>> =  => ActionFn(277);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action277::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (0, 75)
-    }
-    pub(crate) fn __reduce149<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<Variable>> = (<Annotated<Variable>> ",")+, Annotated<Variable> => ActionFn(278);
-        let __sym1 = __pop_Variant16(__symbols);
-        let __sym0 = __pop_Variant17(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action278::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (2, 75)
-    }
-    pub(crate) fn __reduce150<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Annotated<Variable>> = (<Annotated<Variable>> ",")+ => ActionFn(279);
-        let __sym0 = __pop_Variant17(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action279::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (1, 75)
-    }
-    pub(crate) fn __reduce151<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPattern> = AnnotatedPattern => ActionFn(280);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action280::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (1, 76)
-    }
-    pub(crate) fn __reduce152<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPattern> =  => ActionFn(281);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action281::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (0, 76)
-    }
-    pub(crate) fn __reduce153<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPattern> = (<AnnotatedPattern> ",")+, AnnotatedPattern => ActionFn(282);
-        let __sym1 = __pop_Variant4(__symbols);
-        let __sym0 = __pop_Variant18(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action282::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (2, 76)
-    }
-    pub(crate) fn __reduce154<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPattern> = (<AnnotatedPattern> ",")+ => ActionFn(283);
-        let __sym0 = __pop_Variant18(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action283::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (1, 76)
-    }
-    pub(crate) fn __reduce155<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPatternMapEntry> = AnnotatedPatternMapEntry => ActionFn(284);
-        let __sym0 = __pop_Variant19(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action284::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant55(__nt), __end));
-        (1, 77)
-    }
-    pub(crate) fn __reduce156<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPatternMapEntry> =  => ActionFn(285);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action285::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant55(__nt), __end));
-        (0, 77)
-    }
-    pub(crate) fn __reduce157<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPatternMapEntry> = (<AnnotatedPatternMapEntry> ",")+, AnnotatedPatternMapEntry => ActionFn(286);
-        let __sym1 = __pop_Variant19(__symbols);
-        let __sym0 = __pop_Variant20(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action286::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant55(__nt), __end));
-        (2, 77)
-    }
-    pub(crate) fn __reduce158<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<AnnotatedPatternMapEntry> = (<AnnotatedPatternMapEntry> ",")+ => ActionFn(287);
-        let __sym0 = __pop_Variant20(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action287::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant55(__nt), __end));
-        (1, 77)
-    }
-    pub(crate) fn __reduce159<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<BinaryElem> = BinaryElem => ActionFn(288);
-        let __sym0 = __pop_Variant22(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action288::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant47(__nt), __end));
-        (1, 78)
-    }
-    pub(crate) fn __reduce160<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<BinaryElem> =  => ActionFn(289);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action289::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant47(__nt), __end));
-        (0, 78)
-    }
-    pub(crate) fn __reduce161<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<BinaryElem> = (<BinaryElem> ",")+, BinaryElem => ActionFn(290);
-        let __sym1 = __pop_Variant22(__symbols);
-        let __sym0 = __pop_Variant23(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action290::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant47(__nt), __end));
-        (2, 78)
-    }
-    pub(crate) fn __reduce162<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<BinaryElem> = (<BinaryElem> ",")+ => ActionFn(291);
-        let __sym0 = __pop_Variant23(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action291::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant47(__nt), __end));
-        (1, 78)
-    }
-    pub(crate) fn __reduce163<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Constant> = Constant => ActionFn(292);
-        let __sym0 = __pop_Variant24(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action292::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant56(__nt), __end));
-        (1, 79)
-    }
-    pub(crate) fn __reduce164<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Constant> =  => ActionFn(293);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action293::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant56(__nt), __end));
-        (0, 79)
-    }
-    pub(crate) fn __reduce165<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Constant> = (<Constant> ",")+, Constant => ActionFn(294);
-        let __sym1 = __pop_Variant24(__symbols);
-        let __sym0 = __pop_Variant25(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action294::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant56(__nt), __end));
-        (2, 79)
-    }
-    pub(crate) fn __reduce166<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Constant> = (<Constant> ",")+ => ActionFn(295);
-        let __sym0 = __pop_Variant25(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action295::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant56(__nt), __end));
-        (1, 79)
-    }
-    pub(crate) fn __reduce167<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Expression> = Expression => ActionFn(296);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action296::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant57(__nt), __end));
-        (1, 80)
-    }
-    pub(crate) fn __reduce168<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Expression> =  => ActionFn(297);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action297::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant57(__nt), __end));
-        (0, 80)
-    }
-    pub(crate) fn __reduce169<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Expression> = (<Expression> ",")+, Expression => ActionFn(298);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant26(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action298::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant57(__nt), __end));
-        (2, 80)
-    }
-    pub(crate) fn __reduce170<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<Expression> = (<Expression> ",")+ => ActionFn(299);
-        let __sym0 = __pop_Variant26(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action299::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant57(__nt), __end));
-        (1, 80)
-    }
-    pub(crate) fn __reduce171<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<FunctionName> = FunctionName => ActionFn(302);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action302::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant58(__nt), __end));
-        (1, 81)
-    }
-    pub(crate) fn __reduce172<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<FunctionName> =  => ActionFn(303);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action303::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant58(__nt), __end));
-        (0, 81)
-    }
-    pub(crate) fn __reduce173<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<FunctionName> = (<FunctionName> ",")+, FunctionName => ActionFn(304);
-        let __sym1 = __pop_Variant28(__symbols);
-        let __sym0 = __pop_Variant29(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action304::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant58(__nt), __end));
-        (2, 81)
-    }
-    pub(crate) fn __reduce174<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<FunctionName> = (<FunctionName> ",")+ => ActionFn(305);
-        let __sym0 = __pop_Variant29(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action305::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant58(__nt), __end));
-        (1, 81)
-    }
-    pub(crate) fn __reduce175<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<PatternBinaryElem> = PatternBinaryElem => ActionFn(322);
-        let __sym0 = __pop_Variant32(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action322::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant59(__nt), __end));
-        (1, 82)
-    }
-    pub(crate) fn __reduce176<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<PatternBinaryElem> =  => ActionFn(323);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action323::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant59(__nt), __end));
-        (0, 82)
-    }
-    pub(crate) fn __reduce177<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<PatternBinaryElem> = (<PatternBinaryElem> ",")+, PatternBinaryElem => ActionFn(324);
-        let __sym1 = __pop_Variant32(__symbols);
-        let __sym0 = __pop_Variant33(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action324::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant59(__nt), __end));
-        (2, 82)
-    }
-    pub(crate) fn __reduce178<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Comma<PatternBinaryElem> = (<PatternBinaryElem> ",")+ => ActionFn(325);
-        let __sym0 = __pop_Variant33(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action325::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant59(__nt), __end));
-        (1, 82)
-    }
-    pub(crate) fn __reduce179<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant = "{", Comma<Constant>, "}" => ActionFn(55);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action55::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant24(__nt), __end));
-        (3, 83)
-    }
-    pub(crate) fn __reduce180<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant = "[", Comma<Constant>, "|", Constant, "]" => ActionFn(206);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant24(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action206::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant24(__nt), __end));
-        (5, 83)
-    }
-    pub(crate) fn __reduce181<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant = "[", Comma<Constant>, "]" => ActionFn(207);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action207::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant24(__nt), __end));
-        (3, 83)
-    }
-    pub(crate) fn __reduce182<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant = AtomicTerm => ActionFn(57);
-        let __sym0 = __pop_Variant46(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action57::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant24(__nt), __end));
-        (1, 83)
-    }
-    pub(crate) fn __reduce183<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant? = Constant => ActionFn(170);
-        let __sym0 = __pop_Variant24(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action170::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant60(__nt), __end));
-        (1, 84)
-    }
-    pub(crate) fn __reduce184<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Constant? =  => ActionFn(171);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action171::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant60(__nt), __end));
-        (0, 84)
-    }
-    pub(crate) fn __reduce185<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ConstantList = "[", Comma<Constant>, "|", Constant, "]" => ActionFn(208);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant24(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action208::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant44(__nt), __end));
-        (5, 85)
-    }
-    pub(crate) fn __reduce186<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ConstantList = "[", Comma<Constant>, "]" => ActionFn(209);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action209::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant44(__nt), __end));
-        (3, 85)
-    }
-    pub(crate) fn __reduce187<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ConstantTuple = "{", Comma<Constant>, "}" => ActionFn(62);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant56(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action62::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant44(__nt), __end));
-        (3, 86)
-    }
-    pub(crate) fn __reduce188<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ExactAssoc = ":=" => ActionFn(11);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action11::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant61(__nt), __end));
-        (1, 87)
-    }
-    pub(crate) fn __reduce189<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ExactAssoc = "=>" => ActionFn(12);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action12::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant61(__nt), __end));
-        (1, 87)
-    }
-    pub(crate) fn __reduce190<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Expression = Annotated<ValueList> => ActionFn(13);
-        let __sym0 = __pop_Variant41(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action13::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant6(__nt), __end));
-        (1, 88)
-    }
-    pub(crate) fn __reduce191<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Expression = Annotated<SingleExpression> => ActionFn(14);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action14::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant6(__nt), __end));
-        (1, 88)
-    }
-    pub(crate) fn __reduce192<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Expression? = Expression => ActionFn(138);
-        let __sym0 = __pop_Variant6(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action138::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant7(__nt), __end));
-        (1, 89)
-    }
-    pub(crate) fn __reduce193<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Expression? =  => ActionFn(139);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action139::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant7(__nt), __end));
-        (0, 89)
-    }
-    pub(crate) fn __reduce194<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Fun = "fun", "(", Comma<Annotated<Variable>>, ")", "->", Expression => ActionFn(10);
-        let __sym5 = __pop_Variant6(__symbols);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant53(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action10::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant62(__nt), __end));
-        (6, 90)
-    }
-    pub(crate) fn __reduce195<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionDefinition = Annotated<FunctionName>, "=", Annotated<Fun> => ActionFn(9);
-        let __sym2 = __pop_Variant37(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant38(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action9::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant63(__nt), __end));
-        (3, 91)
-    }
-    pub(crate) fn __reduce196<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionDefinition* =  => ActionFn(112);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action112::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant64(__nt), __end));
-        (0, 92)
-    }
-    pub(crate) fn __reduce197<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionDefinition* = FunctionDefinition+ => ActionFn(113);
-        let __sym0 = __pop_Variant64(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action113::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant64(__nt), __end));
-        (1, 92)
-    }
-    pub(crate) fn __reduce198<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionDefinition+ = FunctionDefinition => ActionFn(116);
-        let __sym0 = __pop_Variant63(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action116::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant64(__nt), __end));
-        (1, 93)
-    }
-    pub(crate) fn __reduce199<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionDefinition+ = FunctionDefinition+, FunctionDefinition => ActionFn(117);
-        let __sym1 = __pop_Variant63(__symbols);
-        let __sym0 = __pop_Variant64(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action117::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant64(__nt), __end));
-        (2, 93)
-    }
-    pub(crate) fn __reduce200<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionName = Atom, "/", Integer => ActionFn(8);
-        let __sym2 = __pop_Variant66(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action8::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant28(__nt), __end));
-        (3, 94)
-    }
-    pub(crate) fn __reduce201<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionName? = FunctionName => ActionFn(118);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action118::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant65(__nt), __end));
-        (1, 95)
-    }
-    pub(crate) fn __reduce202<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // FunctionName? =  => ActionFn(119);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action119::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant65(__nt), __end));
-        (0, 95)
-    }
-    pub(crate) fn __reduce203<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Guard = "when", Expression => ActionFn(36);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action36::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant6(__nt), __end));
-        (2, 96)
-    }
-    pub(crate) fn __reduce204<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Integer = "Integer" => ActionFn(1);
-        let __sym0 = __pop_Variant3(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action1::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant66(__nt), __end));
-        (1, 97)
-    }
-    pub(crate) fn __reduce205<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Module = "module", Atom, ModuleFunctions, "attributes", ModuleAttributes, "end" => ActionFn(300);
-        let __sym5 = __pop_Variant0(__symbols);
-        let __sym4 = __pop_Variant50(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant58(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action300::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant67(__nt), __end));
-        (6, 98)
-    }
-    pub(crate) fn __reduce206<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Module = "module", Atom, ModuleFunctions, "attributes", ModuleAttributes, FunctionDefinition+, "end" => ActionFn(301);
-        let __sym6 = __pop_Variant0(__symbols);
-        let __sym5 = __pop_Variant64(__symbols);
-        let __sym4 = __pop_Variant50(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant58(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym6.2.clone();
-        let __nt = super::__action301::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5, __sym6);
-        __symbols.push((__start, __Symbol::Variant67(__nt), __end));
-        (7, 98)
-    }
-    pub(crate) fn __reduce207<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ModuleAttributes = "[", Comma<(<Atom> "=" <Constant>)>, "]" => ActionFn(7);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant50(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action7::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant50(__nt), __end));
-        (3, 99)
-    }
-    pub(crate) fn __reduce208<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ModuleFunctions = "[", Comma<FunctionName>, "]" => ActionFn(6);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant58(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action6::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant58(__nt), __end));
-        (3, 100)
-    }
-    pub(crate) fn __reduce209<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = Annotated<Variable>, "=", AnnotatedPattern => ActionFn(44);
-        let __sym2 = __pop_Variant4(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action44::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (3, 101)
-    }
-    pub(crate) fn __reduce210<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = Variable => ActionFn(45);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action45::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (1, 101)
-    }
-    pub(crate) fn __reduce211<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = AtomicTerm => ActionFn(46);
-        let __sym0 = __pop_Variant46(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action46::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (1, 101)
-    }
-    pub(crate) fn __reduce212<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = PatternBinary => ActionFn(47);
-        let __sym0 = __pop_Variant59(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action47::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (1, 101)
-    }
-    pub(crate) fn __reduce213<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = "{", Comma<AnnotatedPattern>, "}" => ActionFn(48);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action48::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (3, 101)
-    }
-    pub(crate) fn __reduce214<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = "~{", Comma<AnnotatedPatternMapEntry>, "}~" => ActionFn(49);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant55(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action49::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (3, 101)
-    }
-    pub(crate) fn __reduce215<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = "[", Comma<AnnotatedPattern>, "|", AnnotatedPattern, "]" => ActionFn(198);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant4(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action198::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (5, 101)
-    }
-    pub(crate) fn __reduce216<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Pattern = "[", Comma<AnnotatedPattern>, "]" => ActionFn(199);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action199::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant68(__nt), __end));
-        (3, 101)
-    }
-    pub(crate) fn __reduce217<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // PatternBinary = "#{", Comma<PatternBinaryElem>, "}#" => ActionFn(51);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant59(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action51::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant59(__nt), __end));
-        (3, 102)
-    }
-    pub(crate) fn __reduce218<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // PatternBinaryElem = "#<", AnnotatedPattern, ">(", Comma<Annotated<SingleExpression>>, ")" => ActionFn(52);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant52(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant4(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action52::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant32(__nt), __end));
-        (5, 103)
-    }
-    pub(crate) fn __reduce219<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // PatternBinaryElem? = PatternBinaryElem => ActionFn(160);
-        let __sym0 = __pop_Variant32(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action160::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant69(__nt), __end));
-        (1, 104)
-    }
-    pub(crate) fn __reduce220<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // PatternBinaryElem? =  => ActionFn(161);
-        let __start = __symbols.last().map(|s| s.2.clone()).unwrap_or_default();
-        let __end = __lookahead_start.cloned().unwrap_or_else(|| __start.clone());
-        let __nt = super::__action161::<>(text, &__start, &__end);
-        __symbols.push((__start, __Symbol::Variant69(__nt), __end));
-        (0, 104)
-    }
-    pub(crate) fn __reduce221<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // PatternMapEntry = Annotated<SingleExpression>, ":=", AnnotatedPattern => ActionFn(42);
-        let __sym2 = __pop_Variant4(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant14(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action42::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant70(__nt), __end));
-        (3, 105)
-    }
-    pub(crate) fn __reduce222<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Patterns = AnnotatedPattern => ActionFn(39);
-        let __sym0 = __pop_Variant4(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action39::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (1, 106)
-    }
-    pub(crate) fn __reduce223<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Patterns = "<", Comma<AnnotatedPattern>, ">" => ActionFn(40);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant54(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action40::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant54(__nt), __end));
-        (3, 106)
-    }
-    pub(crate) fn __reduce224<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "[", Comma<Expression>, "|", Expression, "]" => ActionFn(201);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant57(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action201::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce225<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "[", Comma<Expression>, "]" => ActionFn(202);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant57(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action202::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (3, 107)
-    }
-    pub(crate) fn __reduce226<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = FunctionName => ActionFn(17);
-        let __sym0 = __pop_Variant28(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action17::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (1, 107)
-    }
-    pub(crate) fn __reduce227<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "fun", Atom, ":", FunctionName => ActionFn(18);
-        let __sym3 = __pop_Variant28(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant45(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action18::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (4, 107)
-    }
-    pub(crate) fn __reduce228<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = AtomicTerm => ActionFn(19);
-        let __sym0 = __pop_Variant46(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action19::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (1, 107)
-    }
-    pub(crate) fn __reduce229<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = Variable => ActionFn(20);
-        let __sym0 = __pop_Variant45(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action20::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (1, 107)
-    }
-    pub(crate) fn __reduce230<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = Binary => ActionFn(21);
-        let __sym0 = __pop_Variant47(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action21::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (1, 107)
-    }
-    pub(crate) fn __reduce231<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "{", Comma<Expression>, "}" => ActionFn(22);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant57(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action22::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (3, 107)
-    }
-    pub(crate) fn __reduce232<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "~{", Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>>, "|", Expression, "}~" => ActionFn(203);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant51(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action203::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce233<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "~{", Comma<Annotated<(<Expression> <ExactAssoc> <Expression>)>>, "}~" => ActionFn(204);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant51(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action204::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (3, 107)
-    }
-    pub(crate) fn __reduce234<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "let", Variables, "=", Expression, "in", Expression => ActionFn(24);
-        let __sym5 = __pop_Variant6(__symbols);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant53(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action24::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (6, 107)
-    }
-    pub(crate) fn __reduce235<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "call", Expression, ":", Expression, "(", Comma<Expression>, ")" => ActionFn(25);
-        let __sym6 = __pop_Variant0(__symbols);
-        let __sym5 = __pop_Variant57(__symbols);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym6.2.clone();
-        let __nt = super::__action25::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5, __sym6);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (7, 107)
-    }
-    pub(crate) fn __reduce236<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "catch", Expression => ActionFn(26);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym1.2.clone();
-        let __nt = super::__action26::<>(text, __sym0, __sym1);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (2, 107)
-    }
-    pub(crate) fn __reduce237<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "case", Expression, "of", "end" => ActionFn(268);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action268::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (4, 107)
-    }
-    pub(crate) fn __reduce238<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "case", Expression, "of", Annotated<Clause>+, "end" => ActionFn(269);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant36(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action269::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce239<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "primop", Annotated<Atom>, "(", Comma<Expression>, ")" => ActionFn(28);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant57(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant16(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action28::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce240<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "do", Expression, Expression => ActionFn(29);
-        let __sym2 = __pop_Variant6(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action29::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (3, 107)
-    }
-    pub(crate) fn __reduce241<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "apply", Expression, "(", Comma<Expression>, ")" => ActionFn(30);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant57(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action30::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce242<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "try", Expression, "of", Variables, "->", Expression, "catch", Variables, "->", Expression => ActionFn(31);
-        let __sym9 = __pop_Variant6(__symbols);
-        let __sym8 = __pop_Variant0(__symbols);
-        let __sym7 = __pop_Variant53(__symbols);
-        let __sym6 = __pop_Variant0(__symbols);
-        let __sym5 = __pop_Variant6(__symbols);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant53(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant6(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym9.2.clone();
-        let __nt = super::__action31::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5, __sym6, __sym7, __sym8, __sym9);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (10, 107)
-    }
-    pub(crate) fn __reduce243<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "receive", "after", Expression, "->", Expression => ActionFn(270);
-        let __sym4 = __pop_Variant6(__symbols);
-        let __sym3 = __pop_Variant0(__symbols);
-        let __sym2 = __pop_Variant6(__symbols);
-        let __sym1 = __pop_Variant0(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym4.2.clone();
-        let __nt = super::__action270::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (5, 107)
-    }
-    pub(crate) fn __reduce244<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "receive", Annotated<Clause>+, "after", Expression, "->", Expression => ActionFn(271);
-        let __sym5 = __pop_Variant6(__symbols);
-        let __sym4 = __pop_Variant0(__symbols);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant36(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym5.2.clone();
-        let __nt = super::__action271::<>(text, __sym0, __sym1, __sym2, __sym3, __sym4, __sym5);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (6, 107)
-    }
-    pub(crate) fn __reduce245<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = Fun => ActionFn(33);
-        let __sym0 = __pop_Variant62(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action33::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (1, 107)
-    }
-    pub(crate) fn __reduce246<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // SingleExpression = "letrec", (<FunctionName> "=" <Fun>)+, "in", Expression => ActionFn(34);
-        let __sym3 = __pop_Variant6(__symbols);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant31(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym3.2.clone();
-        let __nt = super::__action34::<>(text, __sym0, __sym1, __sym2, __sym3);
-        __symbols.push((__start, __Symbol::Variant71(__nt), __end));
-        (4, 107)
-    }
-    pub(crate) fn __reduce247<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // ValueList = "<", Comma<Annotated<SingleExpression>>, ">" => ActionFn(15);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant52(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action15::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant52(__nt), __end));
-        (3, 108)
-    }
-    pub(crate) fn __reduce248<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Variable = "Variable" => ActionFn(3);
-        let __sym0 = __pop_Variant1(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action3::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant45(__nt), __end));
-        (1, 109)
-    }
-    pub(crate) fn __reduce249<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Variables = Annotated<Variable> => ActionFn(37);
-        let __sym0 = __pop_Variant16(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym0.2.clone();
-        let __nt = super::__action37::<>(text, __sym0);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (1, 110)
-    }
-    pub(crate) fn __reduce250<
-        'input,
-    >(
-        text: &'input str,
-        __action: i16,
-        __lookahead_start: Option<&usize>,
-        __states: &mut ::std::vec::Vec<i16>,
-        __symbols: &mut ::std::vec::Vec<(usize,__Symbol<'input>,usize)>,
-        _: ::std::marker::PhantomData<(&'input ())>,
-    ) -> (usize, usize)
-    {
-        // Variables = "<", Comma<Annotated<Variable>>, ">" => ActionFn(38);
-        let __sym2 = __pop_Variant0(__symbols);
-        let __sym1 = __pop_Variant53(__symbols);
-        let __sym0 = __pop_Variant0(__symbols);
-        let __start = __sym0.0.clone();
-        let __end = __sym2.2.clone();
-        let __nt = super::__action38::<>(text, __sym0, __sym1, __sym2);
-        __symbols.push((__start, __Symbol::Variant53(__nt), __end));
-        (3, 110)
-    }
-}
-pub use self::__parse__AnnotatedModule::AnnotatedModuleParser;
-
-#[allow(unused_variables)]
-fn __action0<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Module>, usize),
-) -> Annotated<Module>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action1<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, (bool, &'input str), usize),
-) -> BigInt
-{
-    {
-    let mut num = BigInt::parse_bytes(i.1.as_bytes(), 10).unwrap();
-    if !i.0 {
-        num = -num;
-    }
-    num
-}
-}
-
-#[allow(unused_variables)]
-fn __action2<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, &'input str, usize),
-) -> Atom
-{
-    Atom::from_str(__0)
-}
-
-#[allow(unused_variables)]
-fn __action3<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, &'input str, usize),
-) -> Atom
-{
-    Atom::from_str(__0)
-}
-
-#[allow(unused_variables)]
-fn __action4<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Module>, usize),
-) -> Annotated<Module>
-{
-    __0
-}
-
-#[allow(unused_variables)]
-fn __action5<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, module_name, _): (usize, Atom, usize),
-    (_, functions, _): (usize, Vec<FunctionName>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, attributes, _): (usize, Vec<(Atom, Constant)>, usize),
-    (_, definitions, _): (usize, ::std::vec::Vec<FunctionDefinition>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Module
-{
-    Module {
-        name: module_name,
-        declarations: functions,
-        attributes: attributes,
-        definitions: definitions,
-    }
-}
-
-#[allow(unused_variables)]
-fn __action6<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, n, _): (usize, Vec<FunctionName>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<FunctionName>
-{
-    n
-}
-
-#[allow(unused_variables)]
-fn __action7<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __0, _): (usize, Vec<(Atom, Constant)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<(Atom, Constant)>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action8<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, Atom, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, BigInt, usize),
-) -> FunctionName
-{
-    FunctionName { name: a, arity: i.to_usize().unwrap() }
-}
-
-#[allow(unused_variables)]
-fn __action9<
-    'input,
->(
-    text: &'input str,
-    (_, n, _): (usize, Annotated<FunctionName>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, f, _): (usize, Annotated<Function>, usize),
-) -> FunctionDefinition
-{
-    FunctionDefinition { name: n, fun: f }
-}
-
-#[allow(unused_variables)]
-fn __action10<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Annotated<Atom>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Expression, usize),
-) -> Function
-{
-    Function { vars: a, body: e }
-}
-
-#[allow(unused_variables)]
-fn __action11<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-) -> MapExactAssoc
-{
-    MapExactAssoc::Exact
-}
-
-#[allow(unused_variables)]
-fn __action12<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-) -> MapExactAssoc
-{
-    MapExactAssoc::Assoc
-}
-
-#[allow(unused_variables)]
-fn __action13<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, Annotated<Vec<Annotated<SingleExpression>>>, usize),
-) -> Expression
-{
-    a
-}
-
-#[allow(unused_variables)]
-fn __action14<
-    'input,
->(
-    text: &'input str,
-    (_, s, _): (usize, Annotated<SingleExpression>, usize),
-) -> Expression
-{
-    Annotated::empty(vec![s])
-}
-
-#[allow(unused_variables)]
-fn __action15<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __0, _): (usize, Vec<Annotated<SingleExpression>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    __0
-}
-
-#[allow(unused_variables)]
-fn __action16<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Vec<Expression>, usize),
-    (_, t, _): (usize, ::std::option::Option<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::List { head: e, tail: Box::new(t.unwrap_or(Expression::nil())) }
-}
-
-#[allow(unused_variables)]
-fn __action17<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionName, usize),
-) -> SingleExpression
-{
-    SingleExpression::FunctionName(__0)
-}
-
-#[allow(unused_variables)]
-fn __action18<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, m, _): (usize, Atom, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, f, _): (usize, FunctionName, usize),
-) -> SingleExpression
-{
-    SingleExpression::ExternalFunctionName { module: m, name:f }
-}
-
-#[allow(unused_variables)]
-fn __action19<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, AtomicTerm, usize),
-) -> SingleExpression
-{
-    SingleExpression::AtomicLiteral(a)
-}
-
-#[allow(unused_variables)]
-fn __action20<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, Atom, usize),
-) -> SingleExpression
-{
-    SingleExpression::Variable(v)
-}
-
-#[allow(unused_variables)]
-fn __action21<
-    'input,
->(
-    text: &'input str,
-    (_, b, _): (usize, Vec<(Expression, Vec<Expression>)>, usize),
-) -> SingleExpression
-{
-    SingleExpression::Binary(b)
-}
-
-#[allow(unused_variables)]
-fn __action22<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, t, _): (usize, Vec<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::Tuple(t)
-}
-
-#[allow(unused_variables)]
-fn __action23<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, v, _): (usize, Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    (_, m, _): (usize, ::std::option::Option<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::Map(v, m)
-}
-
-#[allow(unused_variables)]
-fn __action24<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, v, _): (usize, Vec<Annotated<Atom>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::Let { vars: v, val: Box::new(e), body: Box::new(i) }
-}
-
-#[allow(unused_variables)]
-fn __action25<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, c, _): (usize, Vec<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::InterModuleCall {
-            module: Box::new(a), name: Box::new(b), args: c }
-}
-
-#[allow(unused_variables)]
-fn __action26<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::Catch(Box::new(e))
-}
-
-#[allow(unused_variables)]
-fn __action27<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::Case { val: Box::new(e), clauses: a }
-}
-
-#[allow(unused_variables)]
-fn __action28<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, n, _): (usize, Annotated<Atom>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::PrimOpCall(PrimOpCall { name: n, args: a })
-}
-
-#[allow(unused_variables)]
-fn __action29<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e1, _): (usize, Expression, usize),
-    (_, e2, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::Do(Box::new(e1), Box::new(e2))
-}
-
-#[allow(unused_variables)]
-fn __action30<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, f, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    SingleExpression::ApplyCall { fun: Box::new(f), args: a }
-}
-
-#[allow(unused_variables)]
-fn __action31<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, t, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, av, _): (usize, Vec<Annotated<Atom>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, cv, _): (usize, Vec<Annotated<Atom>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, c, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::Try { body: Box::new(t), then_vars: av, then: Box::new(a), 
-                catch_vars: cv, catch: Box::new(c) }
-}
-
-#[allow(unused_variables)]
-fn __action32<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, c, _): (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, t, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::Receive { clauses: c, timeout_time: Box::new(t),
-            timeout_body: Box::new(b) }
-}
-
-#[allow(unused_variables)]
-fn __action33<
-    'input,
->(
-    text: &'input str,
-    (_, f, _): (usize, Function, usize),
-) -> SingleExpression
-{
-    SingleExpression::Fun(Box::new(f))
-}
-
-#[allow(unused_variables)]
-fn __action34<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, f, _): (usize, ::std::vec::Vec<(FunctionName, Function)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, e, _): (usize, Expression, usize),
-) -> SingleExpression
-{
-    SingleExpression::LetRec { funs: f, body: Box::new(e) }
-}
-
-#[allow(unused_variables)]
-fn __action35<
-    'input,
->(
-    text: &'input str,
-    (_, p, _): (usize, Vec<Annotated<Pattern>>, usize),
-    (_, g, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Expression, usize),
-) -> CaseClause
-{
-    CaseClause { patterns: p, guard: g, body: b }
-}
-
-#[allow(unused_variables)]
-fn __action36<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __0, _): (usize, Expression, usize),
-) -> Expression
-{
-    __0
-}
-
-#[allow(unused_variables)]
-fn __action37<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, Annotated<Atom>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    vec![a]
-}
-
-#[allow(unused_variables)]
-fn __action38<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Annotated<Atom>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    a
-}
-
-#[allow(unused_variables)]
-fn __action39<
-    'input,
->(
-    text: &'input str,
-    (_, p, _): (usize, Annotated<Pattern>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    vec![p]
-}
-
-#[allow(unused_variables)]
-fn __action40<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, p, _): (usize, Vec<Annotated<Pattern>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    p
-}
-
-#[allow(unused_variables)]
-fn __action41<
-    'input,
->(
-    text: &'input str,
-    (_, p, _): (usize, Annotated<Pattern>, usize),
-) -> Annotated<Pattern>
-{
-    p
-}
-
-#[allow(unused_variables)]
-fn __action42<
-    'input,
->(
-    text: &'input str,
-    (_, k, _): (usize, Annotated<SingleExpression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, v, _): (usize, Annotated<Pattern>, usize),
-) -> (Annotated<SingleExpression>, Annotated<Pattern>)
-{
-    (k, v)
-}
-
-#[allow(unused_variables)]
-fn __action43<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>
-{
-    __0
-}
-
-#[allow(unused_variables)]
-fn __action44<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, Annotated<Atom>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, p, _): (usize, Annotated<Pattern>, usize),
-) -> Pattern
-{
-    Pattern::BindVar(v, Box::new(p))
-}
-
-#[allow(unused_variables)]
-fn __action45<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, Atom, usize),
-) -> Pattern
-{
-    Pattern::BindVar(Annotated::empty(v), Box::new(Annotated::empty(Pattern::Wildcard)))
-}
-
-#[allow(unused_variables)]
-fn __action46<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, AtomicTerm, usize),
-) -> Pattern
-{
-    Pattern::Atomic(a)
-}
-
-#[allow(unused_variables)]
-fn __action47<
-    'input,
->(
-    text: &'input str,
-    (_, b, _): (usize, Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Pattern
-{
-    Pattern::Binary(b)
-}
-
-#[allow(unused_variables)]
-fn __action48<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, t, _): (usize, Vec<Annotated<Pattern>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Pattern
-{
-    Pattern::Tuple(t)
-}
-
-#[allow(unused_variables)]
-fn __action49<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, m, _): (usize, Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Pattern
-{
-    Pattern::Map(m)
-}
-
-#[allow(unused_variables)]
-fn __action50<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, l, _): (usize, Vec<Annotated<Pattern>>, usize),
-    (_, t, _): (usize, ::std::option::Option<Annotated<Pattern>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Pattern
-{
-    Pattern::List(l, Box::new(t.unwrap_or(Pattern::nil())))
-}
-
-#[allow(unused_variables)]
-fn __action51<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    b
-}
-
-#[allow(unused_variables)]
-fn __action52<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, p, _): (usize, Annotated<Pattern>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Annotated<SingleExpression>>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> (Annotated<Pattern>, Vec<Annotated<SingleExpression>>)
-{
-    (p, a)
-}
-
-#[allow(unused_variables)]
-fn __action53<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Vec<(Expression, Vec<Expression>)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    b
-}
-
-#[allow(unused_variables)]
-fn __action54<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, b, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, a, _): (usize, Vec<Expression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> (Expression, Vec<Expression>)
-{
-    (b, a)
-}
-
-#[allow(unused_variables)]
-fn __action55<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Vec<Constant>, usize),
-    (_, __2, _): (usize, Tok<'input>, usize),
-) -> Constant
-{
-    Constant::Tuple(vec![])
-}
-
-#[allow(unused_variables)]
-fn __action56<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Vec<Constant>, usize),
-    (_, __2, _): (usize, ::std::option::Option<(Tok<'input>, Constant)>, usize),
-    (_, __3, _): (usize, Tok<'input>, usize),
-) -> Constant
-{
-    Constant::List(vec![], Box::new(Constant::Atomic(AtomicTerm::Nil)))
-}
-
-#[allow(unused_variables)]
-fn __action57<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, AtomicTerm, usize),
-) -> Constant
-{
-    Constant::Atomic(__0)
-}
-
-#[allow(unused_variables)]
-fn __action58<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, BigInt, usize),
-) -> AtomicTerm
-{
-    AtomicTerm::Integer(i)
-}
-
-#[allow(unused_variables)]
-fn __action59<
-    'input,
->(
-    text: &'input str,
-    (_, a, _): (usize, Atom, usize),
-) -> AtomicTerm
-{
-    AtomicTerm::Atom(a)
-}
-
-#[allow(unused_variables)]
-fn __action60<
-    'input,
->(
-    text: &'input str,
-    (_, c, _): (usize, char, usize),
-) -> AtomicTerm
-{
-    AtomicTerm::Char(c)
-}
-
-#[allow(unused_variables)]
-fn __action61<
-    'input,
->(
-    text: &'input str,
-    (_, s, _): (usize, &'input str, usize),
-) -> AtomicTerm
-{
-    AtomicTerm::String(s.to_string())
-}
-
-#[allow(unused_variables)]
-fn __action62<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Vec<Constant>, usize),
-    (_, __2, _): (usize, Tok<'input>, usize),
-) -> ()
-{
-    ()
-}
-
-#[allow(unused_variables)]
-fn __action63<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Vec<Constant>, usize),
-    (_, __2, _): (usize, ::std::option::Option<(Tok<'input>, Constant)>, usize),
-    (_, __3, _): (usize, Tok<'input>, usize),
-) -> ()
-{
-    ()
-}
-
-#[allow(unused_variables)]
-fn __action64<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, c, _): (usize, Vec<Constant>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> ()
-{
-    ()
-}
-
-#[allow(unused_variables)]
-fn __action65<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Tok<'input>, Constant), usize),
-) -> ::std::option::Option<(Tok<'input>, Constant)>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action66<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<(Tok<'input>, Constant)>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action67<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Constant, usize),
-) -> (Tok<'input>, Constant)
-{
-    (__0, __1)
-}
-
-#[allow(unused_variables)]
-fn __action68<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Constant>, usize),
-    (_, last, _): (usize, ::std::option::Option<Constant>, usize),
-) -> Vec<Constant>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action69<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-    (_, last, _): (usize, ::std::option::Option<(Expression, Vec<Expression>)>, usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action70<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    (_, last, _): (usize, ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action71<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Pattern>, usize),
-) -> ::std::option::Option<Annotated<Pattern>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action72<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<Pattern>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action73<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __0, _): (usize, Annotated<Pattern>, usize),
-) -> Annotated<Pattern>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action74<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    (_, last, _): (usize, ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action75<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, (Annotated<SingleExpression>, Annotated<Pattern>), usize),
-) -> Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action76<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, (Annotated<SingleExpression>, Annotated<Pattern>), usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action77<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Pattern, usize),
-) -> Annotated<Pattern>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action78<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Pattern, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action79<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-    (_, last, _): (usize, ::std::option::Option<Annotated<Pattern>>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action80<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (FunctionName, Function), usize),
-) -> ::std::vec::Vec<(FunctionName, Function)>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action81<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(FunctionName, Function)>, usize),
-    (_, e, _): (usize, (FunctionName, Function), usize),
-) -> ::std::vec::Vec<(FunctionName, Function)>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action82<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionName, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Function, usize),
-) -> (FunctionName, Function)
-{
-    (__0, __1)
-}
-
-#[allow(unused_variables)]
-fn __action83<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Atom, usize),
-) -> Annotated<Atom>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action84<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Atom, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Atom>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action85<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<CaseClause>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action86<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-) -> ::std::vec::Vec<Annotated<CaseClause>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action87<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, CaseClause, usize),
-) -> Annotated<CaseClause>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action88<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, CaseClause, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<CaseClause>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action89<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    (_, last, _): (usize, ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action90<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, (Expression, MapExactAssoc, Expression), usize),
-) -> Annotated<(Expression, MapExactAssoc, Expression)>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action91<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, (Expression, MapExactAssoc, Expression), usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<(Expression, MapExactAssoc, Expression)>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action92<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Expression, usize),
-    (_, __1, _): (usize, MapExactAssoc, usize),
-    (_, __2, _): (usize, Expression, usize),
-) -> (Expression, MapExactAssoc, Expression)
-{
-    (__0, __1, __2)
-}
-
-#[allow(unused_variables)]
-fn __action93<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Expression, usize),
-) -> ::std::option::Option<Expression>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action94<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Expression>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action95<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __0, _): (usize, Expression, usize),
-) -> Expression
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action96<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Expression>, usize),
-    (_, last, _): (usize, ::std::option::Option<Expression>, usize),
-) -> Vec<Expression>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action97<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-    (_, last, _): (usize, ::std::option::Option<Annotated<SingleExpression>>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action98<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, SingleExpression, usize),
-) -> Annotated<SingleExpression>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action99<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, SingleExpression, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<SingleExpression>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action100<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Vec<Annotated<SingleExpression>>, usize),
-) -> Annotated<Vec<Annotated<SingleExpression>>>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action101<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Vec<Annotated<SingleExpression>>, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Vec<Annotated<SingleExpression>>>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action102<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-    (_, last, _): (usize, ::std::option::Option<Annotated<Atom>>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action103<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Atom, usize),
-) -> Annotated<Atom>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action104<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Atom, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Atom>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action105<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Function, usize),
-) -> Annotated<Function>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action106<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Function, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Function>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action107<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, FunctionName, usize),
-) -> Annotated<FunctionName>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action108<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, FunctionName, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<FunctionName>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action109<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-    (_, last, _): (usize, ::std::option::Option<(Atom, Constant)>, usize),
-) -> Vec<(Atom, Constant)>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action110<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Atom, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, __1, _): (usize, Constant, usize),
-) -> (Atom, Constant)
-{
-    (__0, __1)
-}
-
-#[allow(unused_variables)]
-fn __action111<
-    'input,
->(
-    text: &'input str,
-    (_, rules, _): (usize, ::std::vec::Vec<FunctionName>, usize),
-    (_, last, _): (usize, ::std::option::Option<FunctionName>, usize),
-) -> Vec<FunctionName>
-{
-    {
-        let mut rules = rules;
-        rules.extend(last);
-        rules
-    }
-}
-
-#[allow(unused_variables)]
-fn __action112<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<FunctionDefinition>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action113<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<FunctionDefinition>, usize),
-) -> ::std::vec::Vec<FunctionDefinition>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action114<
-    'input,
->(
-    text: &'input str,
-    (_, i, _): (usize, Module, usize),
-) -> Annotated<Module>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action115<
-    'input,
->(
-    text: &'input str,
-    (_, _, _): (usize, Tok<'input>, usize),
-    (_, i, _): (usize, Module, usize),
-    (_, _, _): (usize, (), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Module>
-{
-    Annotated(i, vec![])
-}
-
-#[allow(unused_variables)]
-fn __action116<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionDefinition, usize),
-) -> ::std::vec::Vec<FunctionDefinition>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action117<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<FunctionDefinition>, usize),
-    (_, e, _): (usize, FunctionDefinition, usize),
-) -> ::std::vec::Vec<FunctionDefinition>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action118<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionName, usize),
-) -> ::std::option::Option<FunctionName>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action119<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<FunctionName>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action120<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<FunctionName>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action121<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<FunctionName>, usize),
-) -> ::std::vec::Vec<FunctionName>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action122<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionName, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> FunctionName
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action123<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Atom, Constant), usize),
-) -> ::std::option::Option<(Atom, Constant)>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action124<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<(Atom, Constant)>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action125<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action126<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action127<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Atom, Constant), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> (Atom, Constant)
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action128<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Atom>, usize),
-) -> ::std::option::Option<Annotated<Atom>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action129<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<Atom>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action130<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action131<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action132<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Atom>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Atom>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action133<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<SingleExpression>, usize),
-) -> ::std::option::Option<Annotated<SingleExpression>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action134<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<SingleExpression>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action135<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action136<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action137<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<SingleExpression>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<SingleExpression>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action138<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Expression, usize),
-) -> ::std::option::Option<Expression>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action139<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Expression>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action140<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Expression>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action141<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Expression>, usize),
-) -> ::std::vec::Vec<Expression>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action142<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Expression, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Expression
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action143<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-) -> ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action144<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action145<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action146<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action147<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<(Expression, MapExactAssoc, Expression)>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action148<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<CaseClause>, usize),
-) -> ::std::vec::Vec<Annotated<CaseClause>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action149<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-    (_, e, _): (usize, Annotated<CaseClause>, usize),
-) -> ::std::vec::Vec<Annotated<CaseClause>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action150<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Pattern>, usize),
-) -> ::std::option::Option<Annotated<Pattern>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action151<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<Pattern>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action152<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action153<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action154<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Pattern>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action155<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action156<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action157<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action158<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action159<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action160<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-) -> ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action161<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action162<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action163<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action164<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> (Annotated<Pattern>, Vec<Annotated<SingleExpression>>)
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action165<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Expression, Vec<Expression>), usize),
-) -> ::std::option::Option<(Expression, Vec<Expression>)>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action166<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<(Expression, Vec<Expression>)>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action167<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action168<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action169<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Expression, Vec<Expression>), usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> (Expression, Vec<Expression>)
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action170<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Constant, usize),
-) -> ::std::option::Option<Constant>
-{
-    Some(__0)
-}
-
-#[allow(unused_variables)]
-fn __action171<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::option::Option<Constant>
-{
-    None
-}
-
-#[allow(unused_variables)]
-fn __action172<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> ::std::vec::Vec<Constant>
-{
-    vec![]
-}
-
-#[allow(unused_variables)]
-fn __action173<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Constant>, usize),
-) -> ::std::vec::Vec<Constant>
-{
-    v
-}
-
-#[allow(unused_variables)]
-fn __action174<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Constant, usize),
-    (_, _, _): (usize, Tok<'input>, usize),
-) -> Constant
-{
-    (__0)
-}
-
-#[allow(unused_variables)]
-fn __action175<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Constant, usize),
-) -> ::std::vec::Vec<Constant>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action176<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Constant>, usize),
-    (_, e, _): (usize, Constant, usize),
-) -> ::std::vec::Vec<Constant>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action177<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Expression, Vec<Expression>), usize),
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action178<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-    (_, e, _): (usize, (Expression, Vec<Expression>), usize),
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action179<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action180<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    (_, e, _): (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action181<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action182<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    (_, e, _): (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action183<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Pattern>, usize),
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action184<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-    (_, e, _): (usize, Annotated<Pattern>, usize),
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action185<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action186<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    (_, e, _): (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action187<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Expression, usize),
-) -> ::std::vec::Vec<Expression>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action188<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Expression>, usize),
-    (_, e, _): (usize, Expression, usize),
-) -> ::std::vec::Vec<Expression>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action189<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<SingleExpression>, usize),
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action190<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-    (_, e, _): (usize, Annotated<SingleExpression>, usize),
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action191<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, Annotated<Atom>, usize),
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action192<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-    (_, e, _): (usize, Annotated<Atom>, usize),
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action193<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, (Atom, Constant), usize),
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action194<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-    (_, e, _): (usize, (Atom, Constant), usize),
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action195<
-    'input,
->(
-    text: &'input str,
-    (_, __0, _): (usize, FunctionName, usize),
-) -> ::std::vec::Vec<FunctionName>
-{
-    vec![__0]
-}
-
-#[allow(unused_variables)]
-fn __action196<
-    'input,
->(
-    text: &'input str,
-    (_, v, _): (usize, ::std::vec::Vec<FunctionName>, usize),
-    (_, e, _): (usize, FunctionName, usize),
-) -> ::std::vec::Vec<FunctionName>
-{
-    { let mut v = v; v.push(e); v }
-}
-
-#[allow(unused_variables)]
-fn __action197<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Annotated<Pattern>, usize),
-) -> ::std::option::Option<Annotated<Pattern>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action73(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action71(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action198<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<Pattern>>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Annotated<Pattern>, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> Pattern
-{
-    let __start0 = __2.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action197(
-        text,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action50(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action199<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<Pattern>>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> Pattern
-{
-    let __start0 = __1.2.clone();
-    let __end0 = __2.0.clone();
-    let __temp0 = __action72(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action50(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __2,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action200<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Expression, usize),
-) -> ::std::option::Option<Expression>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action95(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action93(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action201<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Expression>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Expression, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __2.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action200(
-        text,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action16(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action202<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Expression>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __1.2.clone();
-    let __end0 = __2.0.clone();
-    let __temp0 = __action94(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action16(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __2,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action203<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Expression, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __2.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action200(
-        text,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action23(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action204<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __1.2.clone();
-    let __end0 = __2.0.clone();
-    let __temp0 = __action94(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action23(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __2,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action205<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Constant, usize),
-) -> ::std::option::Option<(Tok<'input>, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action67(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action65(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action206<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Constant>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Constant, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> Constant
-{
-    let __start0 = __2.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action205(
-        text,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action56(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action207<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Constant>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> Constant
-{
-    let __start0 = __1.2.clone();
-    let __end0 = __2.0.clone();
-    let __temp0 = __action66(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action56(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __2,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action208<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Constant>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Constant, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> ()
-{
-    let __start0 = __2.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action205(
-        text,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action63(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action209<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Constant>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ()
-{
-    let __start0 = __1.2.clone();
-    let __end0 = __2.0.clone();
-    let __temp0 = __action66(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action63(
-        text,
-        __0,
-        __1,
-        __temp0,
-        __2,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action210<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Atom, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Constant, usize),
-    __3: (usize, Tok<'input>, usize),
-) -> (Atom, Constant)
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action110(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action127(
-        text,
-        __temp0,
-        __3,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action211<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Atom, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Constant, usize),
-) -> ::std::option::Option<(Atom, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action110(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action123(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action212<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Atom, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Constant, usize),
-    __3: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action210(
-        text,
-        __0,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action193(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action213<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-    __1: (usize, Atom, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Constant, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Atom, Constant)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __4.2.clone();
-    let __temp0 = __action210(
-        text,
-        __1,
-        __2,
-        __3,
-        __4,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action194(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action214<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<(Atom, Constant)>, usize),
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action125(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action109(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action215<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-    __1: (usize, ::std::option::Option<(Atom, Constant)>, usize),
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action126(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action109(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action216<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action147(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action185(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action217<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    __1: (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action147(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action186(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action218<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action145(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action89(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action219<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    __1: (usize, ::std::option::Option<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action146(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action89(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action220<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<SingleExpression>, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action137(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action189(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action221<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-    __1: (usize, Annotated<SingleExpression>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action137(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action190(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action222<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Annotated<SingleExpression>>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action135(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action97(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action223<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-    __1: (usize, ::std::option::Option<Annotated<SingleExpression>>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action136(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action97(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action224<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<Atom>, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action132(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action191(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action225<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-    __1: (usize, Annotated<Atom>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<Atom>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action132(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action192(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action226<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Annotated<Atom>>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action130(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action102(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action227<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-    __1: (usize, ::std::option::Option<Annotated<Atom>>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action131(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action102(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action228<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<Pattern>, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action154(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action183(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action229<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-    __1: (usize, Annotated<Pattern>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<Pattern>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action154(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action184(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action230<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Annotated<Pattern>>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action152(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action79(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action231<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-    __1: (usize, ::std::option::Option<Annotated<Pattern>>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action153(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action79(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action232<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action159(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action181(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action233<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    __1: (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action159(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action182(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action234<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action157(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action74(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action235<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    __1: (usize, ::std::option::Option<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action158(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action74(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action236<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Atom, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Constant, usize),
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action211(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action214(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action237<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action124(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action214(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action238<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-    __1: (usize, Atom, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Constant, usize),
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action211(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action215(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action239<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Atom, Constant)>, usize),
-) -> Vec<(Atom, Constant)>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action124(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action215(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action240<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, (Expression, Vec<Expression>), usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action169(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action177(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action241<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-    __1: (usize, (Expression, Vec<Expression>), usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action169(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action178(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action242<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<(Expression, Vec<Expression>)>, usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action167(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action69(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action243<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-    __1: (usize, ::std::option::Option<(Expression, Vec<Expression>)>, usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action168(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action69(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action244<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Constant, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Constant>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action174(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action175(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action245<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Constant>, usize),
-    __1: (usize, Constant, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Constant>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action174(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action176(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action246<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Constant>, usize),
-) -> Vec<Constant>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action172(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action68(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action247<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Constant>, usize),
-    __1: (usize, ::std::option::Option<Constant>, usize),
-) -> Vec<Constant>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action173(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action68(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action248<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Expression, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Expression>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action142(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action187(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action249<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Expression>, usize),
-    __1: (usize, Expression, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<Expression>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action142(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action188(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action250<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<Expression>, usize),
-) -> Vec<Expression>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action140(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action96(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action251<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Expression>, usize),
-    __1: (usize, ::std::option::Option<Expression>, usize),
-) -> Vec<Expression>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action141(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action96(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action252<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Expression, usize),
-    __1: (usize, MapExactAssoc, usize),
-    __2: (usize, Expression, usize),
-) -> Annotated<(Expression, MapExactAssoc, Expression)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action92(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action90(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action253<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Expression, usize),
-    __2: (usize, MapExactAssoc, usize),
-    __3: (usize, Expression, usize),
-    __4: (usize, (), usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Annotated<(Expression, MapExactAssoc, Expression)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action92(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action91(
-        text,
-        __0,
-        __temp0,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action254<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, FunctionName, usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<FunctionName>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action122(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action195(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action255<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<FunctionName>, usize),
-    __1: (usize, FunctionName, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<FunctionName>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action122(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action196(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action256<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<FunctionName>, usize),
-) -> Vec<FunctionName>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action120(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action111(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action257<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<FunctionName>, usize),
-    __1: (usize, ::std::option::Option<FunctionName>, usize),
-) -> Vec<FunctionName>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action121(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action111(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action258<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, FunctionName, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Function, usize),
-) -> ::std::vec::Vec<(FunctionName, Function)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action82(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action80(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action259<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(FunctionName, Function)>, usize),
-    __1: (usize, FunctionName, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Function, usize),
-) -> ::std::vec::Vec<(FunctionName, Function)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action82(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action81(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action260<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-    __1: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action164(
-        text,
-        __0,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action179(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action261<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    __1: (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-    __2: (usize, Tok<'input>, usize),
-) -> ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action164(
-        text,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action180(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action262<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.0.clone();
-    let __temp0 = __action162(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action70(
-        text,
-        __temp0,
-        __0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action263<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    __1: (usize, ::std::option::Option<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action163(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action70(
-        text,
-        __temp0,
-        __1,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action264<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action143(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action218(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action265<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action144(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action218(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action266<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-    __1: (usize, Annotated<(Expression, MapExactAssoc, Expression)>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action143(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action219(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action267<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Expression, MapExactAssoc, Expression)>>, usize),
-) -> Vec<Annotated<(Expression, MapExactAssoc, Expression)>>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action144(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action219(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action268<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Expression, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __2.2.clone();
-    let __end0 = __3.0.clone();
-    let __temp0 = __action85(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action27(
-        text,
-        __0,
-        __1,
-        __2,
-        __temp0,
-        __3,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action269<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Expression, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> SingleExpression
-{
-    let __start0 = __3.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action86(
-        text,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action27(
-        text,
-        __0,
-        __1,
-        __2,
-        __temp0,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action270<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Expression, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, Expression, usize),
-) -> SingleExpression
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __1.0.clone();
-    let __temp0 = __action85(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action32(
-        text,
-        __0,
-        __temp0,
-        __1,
-        __2,
-        __3,
-        __4,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action271<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, ::std::vec::Vec<Annotated<CaseClause>>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Expression, usize),
-    __4: (usize, Tok<'input>, usize),
-    __5: (usize, Expression, usize),
-) -> SingleExpression
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action86(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action32(
-        text,
-        __0,
-        __temp0,
-        __2,
-        __3,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action272<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<SingleExpression>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action133(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action222(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action273<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action134(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action222(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action274<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-    __1: (usize, Annotated<SingleExpression>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action133(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action223(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action275<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<SingleExpression>>, usize),
-) -> Vec<Annotated<SingleExpression>>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action134(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action223(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action276<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<Atom>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action128(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action226(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action277<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action129(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action226(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action278<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-    __1: (usize, Annotated<Atom>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action128(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action227(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action279<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Atom>>, usize),
-) -> Vec<Annotated<Atom>>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action129(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action227(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action280<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<Pattern>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action150(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action230(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action281<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action151(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action230(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action282<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-    __1: (usize, Annotated<Pattern>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action150(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action231(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action283<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<Pattern>>, usize),
-) -> Vec<Annotated<Pattern>>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action151(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action231(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action284<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action155(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action234(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action285<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action156(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action234(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action286<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    __1: (usize, Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action155(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action235(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action287<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-) -> Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action156(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action235(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action288<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, (Expression, Vec<Expression>), usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action165(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action242(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action289<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action166(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action242(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action290<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-    __1: (usize, (Expression, Vec<Expression>), usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action165(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action243(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action291<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Expression, Vec<Expression>)>, usize),
-) -> Vec<(Expression, Vec<Expression>)>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action166(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action243(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action292<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Constant, usize),
-) -> Vec<Constant>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action170(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action246(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action293<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Constant>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action171(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action246(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action294<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Constant>, usize),
-    __1: (usize, Constant, usize),
-) -> Vec<Constant>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action170(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action247(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action295<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Constant>, usize),
-) -> Vec<Constant>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action171(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action247(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action296<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Expression, usize),
-) -> Vec<Expression>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action138(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action250(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action297<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<Expression>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action139(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action250(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action298<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Expression>, usize),
-    __1: (usize, Expression, usize),
-) -> Vec<Expression>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action138(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action251(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action299<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<Expression>, usize),
-) -> Vec<Expression>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action139(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action251(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action300<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Atom, usize),
-    __2: (usize, Vec<FunctionName>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, Vec<(Atom, Constant)>, usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Module
-{
-    let __start0 = __4.2.clone();
-    let __end0 = __5.0.clone();
-    let __temp0 = __action112(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action5(
-        text,
-        __0,
-        __1,
-        __2,
-        __3,
-        __4,
-        __temp0,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action301<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Atom, usize),
-    __2: (usize, Vec<FunctionName>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, Vec<(Atom, Constant)>, usize),
-    __5: (usize, ::std::vec::Vec<FunctionDefinition>, usize),
-    __6: (usize, Tok<'input>, usize),
-) -> Module
-{
-    let __start0 = __5.0.clone();
-    let __end0 = __5.2.clone();
-    let __temp0 = __action113(
-        text,
-        __5,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action5(
-        text,
-        __0,
-        __1,
-        __2,
-        __3,
-        __4,
-        __temp0,
-        __6,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action302<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, FunctionName, usize),
-) -> Vec<FunctionName>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action118(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action256(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action303<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<FunctionName>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action119(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action256(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action304<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<FunctionName>, usize),
-    __1: (usize, FunctionName, usize),
-) -> Vec<FunctionName>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action118(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action257(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action305<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<FunctionName>, usize),
-) -> Vec<FunctionName>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action119(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action257(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action306<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Annotated<Atom>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Annotated<Pattern>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action44(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action307<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Atom, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action45(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action308<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, AtomicTerm, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action46(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action309<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action47(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action310<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<Pattern>>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action48(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action311<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action49(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action312<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<Pattern>>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Annotated<Pattern>, usize),
-    __4: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __4.2.clone();
-    let __temp0 = __action198(
-        text,
-        __0,
-        __1,
-        __2,
-        __3,
-        __4,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action313<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<Annotated<Pattern>>, usize),
-    __2: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __2.2.clone();
-    let __temp0 = __action199(
-        text,
-        __0,
-        __1,
-        __2,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action77(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action314<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Annotated<Atom>, usize),
-    __2: (usize, Tok<'input>, usize),
-    __3: (usize, Annotated<Pattern>, usize),
-    __4: (usize, (), usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action44(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action315<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Atom, usize),
-    __2: (usize, (), usize),
-    __3: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action45(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __2,
-        __3,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action316<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, AtomicTerm, usize),
-    __2: (usize, (), usize),
-    __3: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action46(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __2,
-        __3,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action317<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    __2: (usize, (), usize),
-    __3: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action47(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __2,
-        __3,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action318<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Vec<Annotated<Pattern>>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, (), usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action48(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action319<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Vec<Annotated<(Annotated<SingleExpression>, Annotated<Pattern>)>>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, (), usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action49(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action320<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Vec<Annotated<Pattern>>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, Annotated<Pattern>, usize),
-    __5: (usize, Tok<'input>, usize),
-    __6: (usize, (), usize),
-    __7: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __5.2.clone();
-    let __temp0 = __action198(
-        text,
-        __1,
-        __2,
-        __3,
-        __4,
-        __5,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __6,
-        __7,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action321<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, Tok<'input>, usize),
-    __1: (usize, Tok<'input>, usize),
-    __2: (usize, Vec<Annotated<Pattern>>, usize),
-    __3: (usize, Tok<'input>, usize),
-    __4: (usize, (), usize),
-    __5: (usize, Tok<'input>, usize),
-) -> Annotated<Pattern>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __3.2.clone();
-    let __temp0 = __action199(
-        text,
-        __1,
-        __2,
-        __3,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action78(
-        text,
-        __0,
-        __temp0,
-        __4,
-        __5,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action322<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __0.0.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action160(
-        text,
-        __0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action262(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action323<
-    'input,
->(
-    text: &'input str,
-    __lookbehind: &usize,
-    __lookahead: &usize,
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __lookbehind.clone();
-    let __end0 = __lookahead.clone();
-    let __temp0 = __action161(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action262(
-        text,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action324<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-    __1: (usize, (Annotated<Pattern>, Vec<Annotated<SingleExpression>>), usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __1.0.clone();
-    let __end0 = __1.2.clone();
-    let __temp0 = __action160(
-        text,
-        __1,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action263(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-#[allow(unused_variables)]
-fn __action325<
-    'input,
->(
-    text: &'input str,
-    __0: (usize, ::std::vec::Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>, usize),
-) -> Vec<(Annotated<Pattern>, Vec<Annotated<SingleExpression>>)>
-{
-    let __start0 = __0.2.clone();
-    let __end0 = __0.2.clone();
-    let __temp0 = __action161(
-        text,
-        &__start0,
-        &__end0,
-    );
-    let __temp0 = (__start0, __temp0, __end0);
-    __action263(
-        text,
-        __0,
-        __temp0,
-    )
-}
-
-pub trait __ToTriple<'input, > {
-    fn to_triple(value: Self) -> Result<(usize,Tok<'input>,usize), __lalrpop_util::ParseError<usize, Tok<'input>, ()>>;
-}
-
-impl<'input, > __ToTriple<'input, > for (usize, Tok<'input>, usize) {
-    fn to_triple(value: Self) -> Result<(usize,Tok<'input>,usize), __lalrpop_util::ParseError<usize, Tok<'input>, ()>> {
-        Ok(value)
-    }
-}
-impl<'input, > __ToTriple<'input, > for Result<(usize, Tok<'input>, usize), ()> {
-    fn to_triple(value: Self) -> Result<(usize,Tok<'input>,usize), __lalrpop_util::ParseError<usize, Tok<'input>, ()>> {
-        match value {
-            Ok(v) => Ok(v),
-            Err(error) => Err(__lalrpop_util::ParseError::User { error }),
-        }
-    }
-}
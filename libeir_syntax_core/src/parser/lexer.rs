@@ -0,0 +1,605 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use libeir_diagnostics::{ByteOffset, SourceIndex, SourceSpan};
+use libeir_intern::{Ident, Symbol};
+use libeir_util_number::Integer;
+use libeir_util_parse::{Scanner, Source};
+
+use super::errors::ParserError;
+
+macro_rules! pop {
+    ($lex:ident) => {{
+        $lex.skip();
+    }};
+    ($lex:ident, $code:expr) => {{
+        $lex.skip();
+        $code
+    }};
+}
+
+macro_rules! pop2 {
+    ($lex:ident) => {{
+        $lex.skip();
+        $lex.skip();
+    }};
+    ($lex:ident, $code:expr) => {{
+        $lex.skip();
+        $lex.skip();
+        $code
+    }};
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    EOF,
+
+    // Keywords
+    Module,
+    Attributes,
+    Fun,
+    Case,
+    Call,
+    Apply,
+    When,
+    End,
+    Catch,
+    Do,
+    Let,
+    In,
+    Of,
+    Primop,
+    Try,
+    Receive,
+    After,
+    Letrec,
+
+    // Identifiers/atomics
+    Atom(Ident),
+    Variable(Ident),
+    Integer(Integer),
+    Float(f64),
+    Char(char),
+    String(Ident),
+
+    // Symbols
+    ParenOpen,
+    ParenClose,
+    CurlyOpen,
+    CurlyClose,
+    SquareOpen,
+    SquareClose,
+    TriOpen,
+    TriClose,
+    MapOpen,
+    MapMatch,
+    MapClose,
+    BitstringOpen,
+    BitstringClose,
+    BitstringPatternOpen,
+    BitstringPatternSep,
+
+    Annotation,
+
+    Colon,
+    Comma,
+    ForwardSlash,
+    Equals,
+    Pipe,
+    Arrow,
+    HashRocket,
+}
+
+lazy_static! {
+    static ref KEYWORDS: HashMap<Symbol, Token> = {
+        let mut map = HashMap::new();
+        map.insert(Symbol::intern("module"), Token::Module);
+        map.insert(Symbol::intern("attributes"), Token::Attributes);
+        map.insert(Symbol::intern("fun"), Token::Fun);
+        map.insert(Symbol::intern("case"), Token::Case);
+        map.insert(Symbol::intern("call"), Token::Call);
+        map.insert(Symbol::intern("apply"), Token::Apply);
+        map.insert(Symbol::intern("when"), Token::When);
+        map.insert(Symbol::intern("end"), Token::End);
+        map.insert(Symbol::intern("catch"), Token::Catch);
+        map.insert(Symbol::intern("do"), Token::Do);
+        map.insert(Symbol::intern("let"), Token::Let);
+        map.insert(Symbol::intern("in"), Token::In);
+        map.insert(Symbol::intern("of"), Token::Of);
+        map.insert(Symbol::intern("primop"), Token::Primop);
+        map.insert(Symbol::intern("try"), Token::Try);
+        map.insert(Symbol::intern("receive"), Token::Receive);
+        map.insert(Symbol::intern("after"), Token::After);
+        map.insert(Symbol::intern("letrec"), Token::Letrec);
+        map
+    };
+}
+
+fn is_digit(c: char) -> bool {
+    c >= '0' && c <= '9'
+}
+fn is_octal(c: char) -> bool {
+    c >= '0' && c <= '7'
+}
+fn is_uppercase(c: char) -> bool {
+    (c >= 'A' && c <= 'Z')
+        || (c >= '\u{00c0}' && c <= '\u{00d6}')
+        || (c >= '\u{00d8}' && c <= '\u{00de}')
+}
+fn is_lowercase(c: char) -> bool {
+    (c >= 'a' && c <= 'z')
+        || (c >= '\u{00df}' && c <= '\u{00f6}')
+        || (c >= '\u{00f8}' && c <= '\u{00ff}')
+}
+fn is_namechar(c: char) -> bool {
+    is_uppercase(c) || is_lowercase(c) || is_digit(c) || (c == '@') || (c == '_')
+}
+
+/// Resolves the escape sequences in the body of an atom, string or
+/// character literal.
+fn unescape(raw: &str) -> Option<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'b' => out.push('\u{0008}'),
+            'd' => out.push('\u{007f}'),
+            'e' => out.push('\u{001b}'),
+            'f' => out.push('\u{000c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            's' => out.push(' '),
+            't' => out.push('\t'),
+            'v' => out.push('\u{000b}'),
+            '^' => {
+                let ctrl = chars.next()?;
+                out.push(std::char::from_u32(ctrl as u32 % 32)?);
+            }
+            c if is_octal(c) => {
+                let mut num = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek() {
+                        Some(c) if is_octal(*c) => {
+                            num = num * 8 + c.to_digit(8).unwrap();
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                out.push(std::char::from_u32(num)?);
+            }
+            c => out.push(c),
+        }
+    }
+
+    Some(out)
+}
+
+pub struct Lexer<S> {
+    scanner: Scanner<S>,
+    token: Result<Token, ParserError>,
+    token_start: SourceIndex,
+    token_end: SourceIndex,
+    eof: bool,
+}
+
+impl<S> Lexer<S>
+where
+    S: Source,
+{
+    pub fn new(scanner: Scanner<S>) -> Self {
+        let start = scanner.start();
+        let mut lexer = Lexer {
+            scanner,
+            token: Ok(Token::EOF),
+            token_start: start + ByteOffset(0),
+            token_end: start + ByteOffset(0),
+            eof: false,
+        };
+        lexer.advance();
+        lexer
+    }
+
+    pub fn lex(&mut self) -> Option<<Self as Iterator>::Item> {
+        if let Ok(Token::EOF) = self.token {
+            if self.eof {
+                return None;
+            }
+        }
+
+        let token = std::mem::replace(&mut self.token, Ok(Token::EOF));
+        let result = Some(token.map(|token| (self.token_start, token, self.token_end)));
+
+        self.advance();
+
+        result
+    }
+
+    fn advance(&mut self) {
+        self.advance_start();
+        self.token = self.tokenize();
+    }
+
+    fn advance_start(&mut self) {
+        let mut position: SourceIndex;
+        loop {
+            let (pos, c) = self.scanner.read();
+            position = pos;
+
+            if c == '\0' {
+                self.eof = true;
+                return;
+            }
+
+            if c.is_whitespace() {
+                self.scanner.advance();
+                continue;
+            }
+
+            // Comments run until the end of the line
+            if c == '%' {
+                'inner: loop {
+                    let (_pos, c) = self.scanner.read();
+
+                    if c == '\n' {
+                        break 'inner;
+                    }
+
+                    if c == '\0' {
+                        self.eof = true;
+                        return;
+                    }
+
+                    self.skip();
+                }
+                continue;
+            }
+
+            break;
+        }
+
+        self.token_start = position;
+        self.token_end = position;
+    }
+
+    fn pop(&mut self) -> char {
+        let (pos, c) = self.scanner.pop();
+        self.token_end = pos + ByteOffset::from_char_len(c);
+        c
+    }
+    fn peek(&mut self) -> char {
+        self.scanner.peek().1
+    }
+    fn read(&mut self) -> char {
+        self.scanner.read().1
+    }
+    fn skip(&mut self) {
+        self.pop();
+    }
+    pub fn span(&self) -> SourceSpan {
+        SourceSpan::new(self.token_start, self.token_end)
+    }
+    fn slice(&self) -> &str {
+        self.scanner.slice(self.span())
+    }
+    fn invalid(&self) -> Result<Token, ParserError> {
+        Err(ParserError::InvalidToken { span: self.span() })
+    }
+
+    fn tokenize(&mut self) -> Result<Token, ParserError> {
+        let c = self.read();
+
+        if c == '\0' {
+            self.eof = true;
+            return Ok(Token::EOF);
+        }
+
+        match c {
+            '(' => pop!(self, Ok(Token::ParenOpen)),
+            ')' => pop!(self, Ok(Token::ParenClose)),
+            '{' => pop!(self, Ok(Token::CurlyOpen)),
+            '}' => match self.peek() {
+                '#' => pop2!(self, Ok(Token::BitstringClose)),
+                '~' => pop2!(self, Ok(Token::MapClose)),
+                _ => pop!(self, Ok(Token::CurlyClose)),
+            },
+            '[' => pop!(self, Ok(Token::SquareOpen)),
+            ']' => pop!(self, Ok(Token::SquareClose)),
+            '~' => match self.peek() {
+                '{' => pop2!(self, Ok(Token::MapOpen)),
+                _ => pop!(self, self.invalid()),
+            },
+            '#' => match self.peek() {
+                '{' => pop2!(self, Ok(Token::BitstringOpen)),
+                '<' => pop2!(self, Ok(Token::BitstringPatternOpen)),
+                _ => pop!(self, self.invalid()),
+            },
+            '<' => pop!(self, Ok(Token::TriOpen)),
+            '>' => match self.peek() {
+                '(' => pop2!(self, Ok(Token::BitstringPatternSep)),
+                _ => pop!(self, Ok(Token::TriClose)),
+            },
+            ':' => match self.peek() {
+                '=' => pop2!(self, Ok(Token::MapMatch)),
+                _ => pop!(self, Ok(Token::Colon)),
+            },
+            '-' => match self.peek() {
+                '|' => pop2!(self, Ok(Token::Annotation)),
+                '>' => pop2!(self, Ok(Token::Arrow)),
+                c if is_digit(c) => self.lex_number(),
+                _ => pop!(self, self.invalid()),
+            },
+            '+' => match self.peek() {
+                c if is_digit(c) => self.lex_number(),
+                _ => pop!(self, self.invalid()),
+            },
+            ',' => pop!(self, Ok(Token::Comma)),
+            '/' => pop!(self, Ok(Token::ForwardSlash)),
+            '|' => pop!(self, Ok(Token::Pipe)),
+            '=' => match self.peek() {
+                '>' => pop2!(self, Ok(Token::HashRocket)),
+                _ => pop!(self, Ok(Token::Equals)),
+            },
+            '\'' => self.lex_quoted('\'').map(Token::Atom),
+            '"' => self.lex_quoted('"').map(Token::String),
+            '$' => self.lex_char(),
+            c if is_digit(c) => self.lex_number(),
+            c if is_uppercase(c) || c == '_' => self.lex_variable(),
+            c if is_lowercase(c) => self.lex_keyword(),
+            _ => pop!(self, self.invalid()),
+        }
+    }
+
+    fn skip_namechars(&mut self) {
+        while is_namechar(self.read()) {
+            self.skip();
+        }
+    }
+
+    fn lex_keyword(&mut self) -> Result<Token, ParserError> {
+        self.skip_namechars();
+
+        let symbol = Symbol::intern(self.slice());
+        if let Some(tok) = KEYWORDS.get(&symbol) {
+            Ok(tok.clone())
+        } else {
+            self.invalid()
+        }
+    }
+
+    fn lex_variable(&mut self) -> Result<Token, ParserError> {
+        self.skip_namechars();
+
+        let ident = Ident::new(Symbol::intern(self.slice()), self.span());
+        Ok(Token::Variable(ident))
+    }
+
+    fn lex_number(&mut self) -> Result<Token, ParserError> {
+        match self.read() {
+            '-' | '+' => self.skip(),
+            _ => (),
+        }
+
+        while is_digit(self.read()) {
+            self.skip();
+        }
+
+        if self.read() == '.' && is_digit(self.peek()) {
+            self.skip();
+            while is_digit(self.read()) {
+                self.skip();
+            }
+
+            if self.read() == 'e' || self.read() == 'E' {
+                self.skip();
+                if self.read() == '-' || self.read() == '+' {
+                    self.skip();
+                }
+                while is_digit(self.read()) {
+                    self.skip();
+                }
+            }
+
+            match self.slice().parse::<f64>() {
+                Ok(float) => Ok(Token::Float(float)),
+                Err(_) => self.invalid(),
+            }
+        } else {
+            match self.slice().parse::<Integer>() {
+                Ok(int) => Ok(Token::Integer(int)),
+                Err(_) => self.invalid(),
+            }
+        }
+    }
+
+    /// Lexes a quoted atom or string. Escapes are resolved.
+    fn lex_quoted(&mut self, quote: char) -> Result<Ident, ParserError> {
+        debug_assert!(self.read() == quote);
+        self.skip();
+
+        loop {
+            match self.read() {
+                '\0' | '\n' | '\r' => return Err(ParserError::InvalidToken { span: self.span() }),
+                '\\' => {
+                    self.skip();
+                    self.skip();
+                }
+                c if c == quote => {
+                    self.skip();
+                    break;
+                }
+                _ => self.skip(),
+            }
+        }
+
+        let slice = self.slice();
+        match unescape(&slice[1..(slice.len() - 1)]) {
+            Some(string) => Ok(Ident::new(Symbol::intern(&string), self.span())),
+            None => Err(ParserError::InvalidToken { span: self.span() }),
+        }
+    }
+
+    fn lex_char(&mut self) -> Result<Token, ParserError> {
+        debug_assert!(self.read() == '$');
+        self.skip();
+
+        match self.read() {
+            '\0' => return self.invalid(),
+            '\\' => {
+                self.skip();
+                match self.read() {
+                    '^' => pop2!(self),
+                    c if is_octal(c) => {
+                        for _ in 0..3 {
+                            if !is_octal(self.read()) {
+                                break;
+                            }
+                            self.skip();
+                        }
+                    }
+                    _ => self.skip(),
+                }
+            }
+            _ => self.skip(),
+        }
+
+        let unescaped = unescape(&self.slice()[1..]);
+        let mut chars = unescaped.as_ref().map(|s| s.chars());
+        match chars.as_mut().map(|c| (c.next(), c.next())) {
+            Some((Some(c), None)) => Ok(Token::Char(c)),
+            _ => self.invalid(),
+        }
+    }
+}
+
+impl<S> Iterator for Lexer<S>
+where
+    S: Source,
+{
+    type Item = Result<(SourceIndex, Token, SourceIndex), ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lex()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use libeir_diagnostics::CodeMap;
+    use libeir_intern::Ident;
+    use libeir_util_parse::{FileMapSource, Scanner, Source};
+
+    use super::{Lexer, Token};
+
+    fn lex(text: &str) -> Vec<Token> {
+        let codemap = Arc::new(CodeMap::new());
+        let id = codemap.add("nofile", text.to_string());
+        let file = codemap.get(id).unwrap();
+        let scanner = Scanner::new(FileMapSource::new(file));
+        Lexer::new(scanner).map(|t| t.unwrap().1).collect()
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(
+            lex("( ) { } [ ] #{ }# #< >( -| , / ="),
+            vec![
+                Token::ParenOpen,
+                Token::ParenClose,
+                Token::CurlyOpen,
+                Token::CurlyClose,
+                Token::SquareOpen,
+                Token::SquareClose,
+                Token::BitstringOpen,
+                Token::BitstringClose,
+                Token::BitstringPatternOpen,
+                Token::BitstringPatternSep,
+                Token::Annotation,
+                Token::Comma,
+                Token::ForwardSlash,
+                Token::Equals,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords() {
+        // Common path, don't bother testing all
+        assert_eq!(
+            lex("module attributes fun case call of primop"),
+            vec![
+                Token::Module,
+                Token::Attributes,
+                Token::Fun,
+                Token::Case,
+                Token::Call,
+                Token::Of,
+                Token::Primop,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_atoms() {
+        let atoms = |s: &[&str]| {
+            s.iter()
+                .map(|a| Token::Atom(Ident::from_str(a)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lex("'something' '\\'' '\\5' '\\55' '\\n' '\\^@' '\\^H'"),
+            atoms(&["something", "'", "\u{5}", "-", "\n", "\u{0}", "\u{8}"])
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let vars = |s: &[&str]| {
+            s.iter()
+                .map(|a| Token::Variable(Ident::from_str(a)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lex("Abc ABC _abcd A@_cD _@c1"),
+            vars(&["Abc", "ABC", "_abcd", "A@_cD", "_@c1"])
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            lex("12 -3 +4 1.5 -2.0e3 $a $\\n \"str\\\"\""),
+            vec![
+                Token::Integer(12.into()),
+                Token::Integer((-3).into()),
+                Token::Integer(4.into()),
+                Token::Float(1.5),
+                Token::Float(-2000.0),
+                Token::Char('a'),
+                Token::Char('\n'),
+                Token::String(Ident::from_str("str\"")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_compile_core_file() {
+        let codemap = Arc::new(CodeMap::new());
+        let path = std::path::Path::new("../test_data/compile.core");
+        let id = codemap.add(path, std::fs::read_to_string(path).unwrap());
+        let file = codemap.get(id).unwrap();
+        let scanner = Scanner::new(FileMapSource::new(file));
+        for token in Lexer::new(scanner) {
+            assert!(token.is_ok());
+        }
+    }
+}
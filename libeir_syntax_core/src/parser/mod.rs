@@ -1,12 +1,138 @@
-use crate::ast::{ Annotated, Module };
-use crate::lexer::{ Tokenizer, Tok };
+use libeir_diagnostics::SourceIndex;
+use libeir_util_parse::{self as parse, ErrorReceiver, Parse, Scanner, Source, SourceError};
 
-mod grammar;
+mod lexer;
+use lexer::{Lexer, Token};
 
-pub fn parse<'input>(text: &'input str) -> Result<Annotated<Module>,
-                                                  ::lalrpop_util::ParseError<usize, Tok<'input>, ()>> {
+mod errors;
+pub use self::errors::ParserError;
 
-    let tokenizer = Tokenizer::new(text);
-    let parser = grammar::AnnotatedModuleParser::new();
-    parser.parse(text, tokenizer)
+pub type Parser = parse::Parser<()>;
+
+type ParserErrorReceiver<'a> = dyn ErrorReceiver<E = ParserError, W = ParserError> + 'a;
+
+/// Used in the grammar for easy span creation
+macro_rules! span {
+    ($l:expr, $r:expr) => {
+        SourceSpan::new($l, $r)
+    };
+    ($i:expr) => {
+        SourceSpan::new($i, $i)
+    };
+}
+
+#[cfg_attr(rustfmt, rustfmt_skip)]
+#[allow(unknown_lints)]
+#[allow(clippy)]
+#[allow(unused_variables, dead_code, unused_imports, unused_parens)]
+pub(crate) mod grammar {
+    // During the build step, `build.rs` will output the generated parser to `OUT_DIR` to avoid
+    // adding it to the source directory, so we just directly include the generated parser here.
+    include!(concat!(env!("OUT_DIR"), "/parser/grammar.rs"));
+}
+
+impl Parse for crate::ast::Module {
+    type Parser = self::grammar::ModuleParser;
+    type Error = ParserError;
+    type Config = ();
+    type Token = std::result::Result<(SourceIndex, Token, SourceIndex), ParserError>;
+
+    fn file_map_error(err: SourceError) -> Self::Error {
+        ParserError::Source { source: err }
+    }
+
+    fn parse_tokens<S>(errors: &mut ParserErrorReceiver, tokens: S) -> Result<Self, ()>
+    where
+        S: IntoIterator<Item = std::result::Result<(SourceIndex, Token, SourceIndex), ParserError>>,
+    {
+        let result = self::grammar::ModuleParser::new().parse(tokens);
+
+        let ret;
+        match result {
+            Ok(ok) => ret = ok,
+            Err(err) => {
+                errors.error(err.into());
+                return Err(());
+            }
+        }
+
+        if (*errors).is_failed() {
+            Err(())
+        } else {
+            Ok(ret)
+        }
+    }
+
+    fn parse<S>(_parser: &Parser, errors: &mut ParserErrorReceiver, source: S) -> Result<Self, ()>
+    where
+        S: Source,
+    {
+        let scanner = Scanner::new(source);
+        let lexer = Lexer::new(scanner);
+        Self::parse_tokens(errors, lexer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use libeir_diagnostics::CodeMap;
+    use libeir_util_parse::Errors;
+
+    use super::Parser;
+    use crate::ast::Module;
+
+    fn parse(text: &str) -> Module {
+        let parser = Parser::new((), Arc::new(CodeMap::new()));
+        let mut errors = Errors::new();
+        match parser.parse_string::<Module, _>(&mut errors, text) {
+            Ok(module) => module,
+            Err(()) => {
+                errors.print(&parser.codemap);
+                panic!("parse failed");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_empty_module() {
+        let module = parse("module 'foo' [] attributes [] end");
+        assert_eq!(module.name.as_str(), "foo");
+        assert!(module.definitions.is_empty());
+    }
+
+    #[test]
+    fn parse_annotated_module() {
+        let module = parse(
+            "
+module 'foo' ['bar'/1]
+    attributes [%% Line 1
+                'file' =
+                    %% Line 1
+                    [{[102|[111|[111]]],1}]]
+'bar'/1 =
+    fun (_0) ->
+        ( case _0 of
+            <X> when 'true' -> {X, 1.5, $a, \"str\"}
+          end
+          -| [{'function',{'bar',1}}] )
+end
+",
+        );
+        assert_eq!(module.declarations.len(), 1);
+        assert_eq!(module.attributes.len(), 1);
+        assert_eq!(module.definitions.len(), 1);
+    }
+
+    #[test]
+    fn parse_compile_core_file() {
+        let parser = Parser::new((), Arc::new(CodeMap::new()));
+        let mut errors = Errors::new();
+        let res = parser.parse_file::<Module, _>(&mut errors, "../test_data/compile.core");
+        if res.is_err() {
+            errors.print(&parser.codemap);
+            panic!();
+        }
+    }
 }
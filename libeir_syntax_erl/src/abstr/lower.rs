@@ -1,380 +1,450 @@
-use libeir_diagnostics::SourceSpan;
+use libeir_diagnostics::{SourceSpan, ToDiagnostic};
 use libeir_intern::Ident;
 use libeir_ir::ToPrimitive;
-use libeir_util_parse::MessageIgnore;
+use libeir_util_parse::{ErrorOrWarning, ErrorReceiver, Errors};
 
 use std::convert::TryInto;
 
+use crate::lower::LowerError;
 use crate::parser::ast;
 use libeir_util_parse_listing::ast as aast;
 
-fn to_list_expr(
-    id_gen: &mut ast::NodeIdGenerator,
-    span: SourceSpan,
-    mut list: Vec<ast::Expr>,
-) -> ast::Expr {
-    let mut acc = ast::Expr::Nil(ast::Nil(span, id_gen.next()));
-    for elem in list.drain(..).rev() {
-        acc = ast::Expr::Cons(ast::Cons {
-            id: id_gen.next(),
-            span,
-            head: Box::new(elem),
-            tail: Box::new(acc),
-        });
-    }
-    acc
-}
-
-pub fn lower(root: &aast::Root) -> ast::Module {
-    let mut toplevel: Vec<ast::TopLevel> = Vec::new();
-
+type Result<T> = std::result::Result<T, LowerError>;
+
+/// Lowers a module in the abstract format to the Erlang AST.
+///
+/// A form that can not be lowered is reported and skipped, the
+/// remaining forms are still lowered to catch as many errors as
+/// possible.
+pub fn lower<'a>(
+    errors: &'a mut (dyn ErrorReceiver<E = LowerError, W = LowerError> + 'a),
+    root: &aast::Root,
+) -> std::result::Result<ast::Module, ()> {
     let mut id_gen = ast::NodeIdGenerator::new();
 
     let module_span = root.span();
+    let (module_name, toplevel) = lower_forms(errors, &mut id_gen, root);
+
+    let module_name = match module_name {
+        Some(name) => name,
+        None => {
+            errors.error(LowerError::MissingModuleAttribute { span: module_span });
+            return Err(());
+        }
+    };
+
+    let mut module_errors = Errors::new();
+    let module = ast::Module::new(
+        &mut module_errors,
+        module_span,
+        &mut id_gen,
+        module_name,
+        toplevel,
+    );
+    for err in module_errors.errors.iter() {
+        match err {
+            ErrorOrWarning::Error(err) => errors.error(LowerError::ShowDiagnostic {
+                diagnostic: err.to_diagnostic(),
+            }),
+            ErrorOrWarning::Warning(warn) => errors.warning(LowerError::ShowDiagnostic {
+                diagnostic: warn.to_diagnostic(),
+            }),
+        }
+    }
+
+    if errors.is_failed() {
+        Err(())
+    } else {
+        Ok(module)
+    }
+}
+
+/// Lowers every form of the module, reporting the ones that fail.
+fn lower_forms(
+    errors: &mut dyn ErrorReceiver<E = LowerError, W = LowerError>,
+    id_gen: &mut ast::NodeIdGenerator,
+    root: &aast::Root,
+) -> (Option<Ident>, Vec<ast::TopLevel>) {
+    let mut toplevel = Vec::new();
     let mut module_name = None;
-    let mut eof = false;
 
     for item in root.items.iter() {
-        let tuple = item.tuple().unwrap();
-        let name_ident = tuple.entries[0].atom().unwrap();
-        let name = name_ident.as_str();
-
-        let _line = tuple.entries[1].integer().unwrap();
+        match lower_form(id_gen, item) {
+            Ok(Form::Module(name)) => module_name = Some(name),
+            Ok(Form::TopLevel(top)) => toplevel.push(top),
+            Ok(Form::Ignored) => (),
+            Ok(Form::Eof) => break,
+            Err(err) => errors.error(err),
+        }
+    }
 
-        assert!(!eof);
+    (module_name, toplevel)
+}
 
-        match &*name {
-            "attribute" => {
-                let attr_ident = tuple.entries[2].atom().unwrap();
-                let attr = attr_ident.as_str();
+enum Form {
+    Module(Ident),
+    TopLevel(ast::TopLevel),
+    Ignored,
+    Eof,
+}
 
-                match &*attr {
-                    "file" => (),
-                    "module" => {
-                        module_name = Some(tuple.entries[3].atom().unwrap());
-                    }
-                    "export" => {
-                        let exports = tuple.entries[3]
-                            .list_iter()
-                            .unwrap()
-                            .map(|item| {
-                                let item_tup = item.tuple().unwrap();
-                                let name = item_tup.entries[0].atom().unwrap();
-                                let arity = item_tup.entries[1].integer().unwrap();
-                                ast::PartiallyResolvedFunctionName {
-                                    span: item_tup.span,
-                                    id: id_gen.next(),
-                                    function: name,
-                                    arity: arity.integer.to_usize().unwrap(),
-                                }
-                            })
-                            .collect();
-                        toplevel.push(ast::TopLevel::Attribute(ast::Attribute::Export(
-                            tuple.span, exports,
-                        )));
-                    }
-                    "compile" => {
-                        let opts = tuple.entries[3]
-                            .list_iter()
-                            .unwrap()
-                            .map(|item| match item {
-                                aast::Item::Atom(ident) => {
-                                    ast::Expr::Literal(ast::Literal::Atom(id_gen.next(), *ident))
-                                }
-                                _ => unimplemented!("{:?}", item),
-                            })
-                            .collect();
-                        toplevel.push(ast::TopLevel::Attribute(ast::Attribute::Compile(
-                            tuple.span,
-                            to_list_expr(&mut id_gen, tuple.span, opts),
-                        )))
-                    }
-                    "spec" => {
-                        continue;
-                    }
-                    "dialyzer" => {
-                        continue;
-                    }
-                    "export_type" => {
-                        continue;
-                    }
-                    "type" => {
-                        continue;
-                    }
-                    "opaque" => {
-                        continue;
-                        //let inner_tup = tuple.entries[3].tuple().unwrap();
-                        //let def = ast::TypeDef {
-                        //    span,
-                        //    opaque: true,
-                        //    name: inner_tup.entries[0].atom().unwrap(),
-
-                        //};
-                        //toplevel.push(ast::TopLevel::Attribute(
-                        //    ast::Attribute::Type(def)));
-                    }
-                    "record" => {
-                        let rec_tup = tuple.entries[3].tuple().unwrap();
-
-                        let name = rec_tup.entries[0].atom().unwrap();
-                        let fields = rec_tup.entries[1]
-                            .list_iter()
-                            .unwrap()
-                            .map(|v| lower_record_field(&mut id_gen, v))
-                            .collect();
-
-                        let record = ast::Record {
-                            span: rec_tup.span,
-                            id: id_gen.next(),
-                            name,
-                            fields,
-                        };
-                        toplevel.push(ast::TopLevel::Record(record));
-                    }
-                    "behaviour" => toplevel.push(ast::TopLevel::Attribute(
-                        ast::Attribute::Behaviour(tuple.span, tuple.entries[3].atom().unwrap()),
-                    )),
-                    n => unimplemented!("attribute {} {:?}", n, tuple),
+fn lower_form(id_gen: &mut ast::NodeIdGenerator, item: &aast::Item) -> Result<Form> {
+    let tuple = as_tuple(item)?;
+    let name_ident = as_atom(entry(tuple, 0)?)?;
+    let name = name_ident.as_str();
+
+    let _line = as_integer(entry(tuple, 1)?)?;
+
+    let top = match &*name {
+        "attribute" => {
+            let attr_ident = as_atom(entry(tuple, 2)?)?;
+            let attr = attr_ident.as_str();
+            let value = entry(tuple, 3)?;
+
+            match &*attr {
+                "file" => return Ok(Form::Ignored),
+                "module" => return Ok(Form::Module(as_atom(value)?)),
+                "export" => {
+                    let exports = as_list(value)?
+                        .map(|item| lower_function_name(id_gen, item))
+                        .collect::<Result<_>>()?;
+                    ast::TopLevel::Attribute(ast::Attribute::Export(tuple.span, exports))
                 }
-            }
-            "function" => {
-                let fun_name = tuple.entries[2].atom().unwrap();
-                let fun_arity = tuple.entries[3]
-                    .integer()
-                    .unwrap()
-                    .integer
-                    .to_usize()
-                    .unwrap();
-
-                let clauses = tuple.entries[4]
-                    .list_iter()
-                    .unwrap()
-                    .map(|clause| lower_function_clause(&mut id_gen, clause))
-                    .collect();
-
-                toplevel.push(ast::TopLevel::Function(ast::NamedFunction {
+                "import" => {
+                    let import_tup = as_tuple(value)?;
+                    let from_module = as_atom(entry(import_tup, 0)?)?;
+                    let imports = as_list(entry(import_tup, 1)?)?
+                        .map(|item| lower_function_name(id_gen, item))
+                        .collect::<Result<_>>()?;
+                    ast::TopLevel::Attribute(ast::Attribute::Import(
+                        tuple.span,
+                        from_module,
+                        imports,
+                    ))
+                }
+                "compile" => ast::TopLevel::Attribute(ast::Attribute::Compile(
+                    tuple.span,
+                    lower_term(id_gen, value),
+                )),
+                "vsn" => ast::TopLevel::Attribute(ast::Attribute::Vsn(
+                    tuple.span,
+                    lower_term(id_gen, value),
+                )),
+                "author" => ast::TopLevel::Attribute(ast::Attribute::Author(
+                    tuple.span,
+                    lower_term(id_gen, value),
+                )),
+                "on_load" => ast::TopLevel::Attribute(ast::Attribute::OnLoad(
+                    tuple.span,
+                    lower_function_name(id_gen, value)?,
+                )),
+                // Types and specs are not used by lowering
+                "spec" | "dialyzer" | "export_type" | "type" | "opaque" => {
+                    return Ok(Form::Ignored)
+                }
+                "record" => {
+                    let rec_tup = as_tuple(value)?;
+
+                    let name = as_atom(entry(rec_tup, 0)?)?;
+                    let fields = as_list(entry(rec_tup, 1)?)?
+                        .map(|v| lower_record_field(id_gen, v))
+                        .collect::<Result<_>>()?;
+
+                    let record = ast::Record {
+                        span: rec_tup.span,
+                        id: id_gen.next(),
+                        name,
+                        fields,
+                    };
+                    ast::TopLevel::Record(record)
+                }
+                "behaviour" | "behavior" => {
+                    ast::TopLevel::Attribute(ast::Attribute::Behaviour(tuple.span, as_atom(value)?))
+                }
+                _ => ast::TopLevel::Attribute(ast::Attribute::Custom(ast::UserAttribute {
                     span: tuple.span,
-                    id: id_gen.next(),
-                    name: fun_name,
-                    arity: fun_arity,
-                    clauses: clauses,
-                    spec: None,
-                }));
+                    name: attr_ident,
+                    value: lower_term(id_gen, value),
+                })),
             }
-            "eof" => {
-                eof = true;
+        }
+        "function" => {
+            let fun_name = as_atom(entry(tuple, 2)?)?;
+            let fun_arity = as_usize(entry(tuple, 3)?)?;
+
+            let clauses = as_list(entry(tuple, 4)?)?
+                .map(|clause| lower_function_clause(id_gen, clause))
+                .collect::<Result<_>>()?;
+
+            ast::TopLevel::Function(ast::NamedFunction {
+                span: tuple.span,
+                id: id_gen.next(),
+                name: fun_name,
+                arity: fun_arity,
+                clauses: clauses,
+                spec: None,
+            })
+        }
+        "eof" => return Ok(Form::Eof),
+        n => return Err(unsupported(tuple.span, n)),
+    };
+
+    Ok(Form::TopLevel(top))
+}
+
+fn lower_function_name(
+    gen: &mut ast::NodeIdGenerator,
+    item: &aast::Item,
+) -> Result<ast::PartiallyResolvedFunctionName> {
+    let item_tup = as_tuple(item)?;
+    let name = as_atom(entry(item_tup, 0)?)?;
+    let arity = as_usize(entry(item_tup, 1)?)?;
+    Ok(ast::PartiallyResolvedFunctionName {
+        span: item_tup.span,
+        id: gen.next(),
+        function: name,
+        arity,
+    })
+}
+
+/// Attribute values in the abstract format are plain terms, not
+/// abstract expressions.
+fn lower_term(gen: &mut ast::NodeIdGenerator, item: &aast::Item) -> ast::Expr {
+    match item {
+        aast::Item::Atom(ident) => ast::Expr::Literal(ast::Literal::Atom(gen.next(), *ident)),
+        aast::Item::String(ident) => ast::Expr::Literal(ast::Literal::String(gen.next(), *ident)),
+        aast::Item::Int(int) => ast::Expr::Literal(ast::Literal::Integer(
+            int.span,
+            gen.next(),
+            int.integer.clone(),
+        )),
+        aast::Item::Float(float) => {
+            ast::Expr::Literal(ast::Literal::Float(float.span, gen.next(), float.float))
+        }
+        aast::Item::Tuple(tup) => ast::Expr::Tuple(ast::Tuple {
+            span: tup.span,
+            id: gen.next(),
+            elements: tup.entries.iter().map(|e| lower_term(gen, e)).collect(),
+        }),
+        aast::Item::List(list) => {
+            let mut acc = match &list.tail {
+                Some(tail) => lower_term(gen, tail),
+                None => ast::Expr::Nil(ast::Nil(list.span, gen.next())),
+            };
+            for elem in list.heads.iter().rev() {
+                acc = ast::Expr::Cons(ast::Cons {
+                    span: list.span,
+                    id: gen.next(),
+                    head: Box::new(lower_term(gen, elem)),
+                    tail: Box::new(acc),
+                });
             }
-            n => unimplemented!("{}", n),
+            acc
         }
     }
-
-    let mut errors = MessageIgnore::new();
-    let module = ast::Module::new(
-        &mut errors,
-        module_span,
-        &mut id_gen,
-        module_name.unwrap(),
-        toplevel,
-    );
-    assert!(!errors.failed());
-    module
 }
 
-fn lower_record_field(gen: &mut ast::NodeIdGenerator, tup_item: &aast::Item) -> ast::RecordField {
-    let tup = tup_item.tuple().unwrap();
+fn lower_record_field(
+    gen: &mut ast::NodeIdGenerator,
+    tup_item: &aast::Item,
+) -> Result<ast::RecordField> {
+    let tup = as_tuple(tup_item)?;
 
-    assert!(&*tup.entries[0].atom().unwrap().as_str() == "record_field");
-    assert!(tup.entries.len() == 3 || tup.entries.len() == 4);
+    expect_form(tup, "record_field")?;
+    if tup.entries.len() != 3 && tup.entries.len() != 4 {
+        return Err(malformed(tup.span));
+    }
 
-    let name = atom(&tup.entries[2]);
+    let name = atom(&tup.entries[2])?;
 
-    let value = tup.entries.get(3).map(|v| lower_expr(gen, v));
+    let value = match tup.entries.get(3) {
+        Some(v) => Some(lower_expr(gen, v)?),
+        None => None,
+    };
 
-    ast::RecordField {
+    Ok(ast::RecordField {
         span: tup.span,
         id: gen.next(),
         name,
         value,
         ty: None,
-    }
+    })
 }
 
 fn lower_function_clause(
     gen: &mut ast::NodeIdGenerator,
     clause: &aast::Item,
-) -> ast::FunctionClause {
-    let tup = clause.tuple().unwrap();
+) -> Result<ast::FunctionClause> {
+    let tup = as_tuple(clause)?;
 
-    assert!(tup.entries[0].atom().unwrap().as_str() == "clause");
-    let _line = tup.entries[1].integer().unwrap();
+    expect_form(tup, "clause")?;
 
-    let params = &tup.entries[2];
-    let guard = &tup.entries[3];
-    let body = &tup.entries[4];
+    let params = entry(tup, 2)?;
+    let guard = entry(tup, 3)?;
+    let body = entry(tup, 4)?;
 
-    let params_n: Vec<_> = params
-        .list_iter()
-        .unwrap()
+    let params_n = as_list(params)?
         .map(|param| lower_expr(gen, param))
-        .collect();
+        .collect::<Result<_>>()?;
 
-    let guard_n = lower_guards(gen, guard);
+    let guard_n = lower_guards(gen, guard)?;
 
-    let body_n = lower_body(gen, body);
+    let body_n = lower_body(gen, body)?;
 
-    ast::FunctionClause {
+    Ok(ast::FunctionClause {
         span: clause.span(),
         name: None,
         params: params_n,
         guard: guard_n,
         body: body_n,
+    })
+}
+
+/// The single pattern of a case, receive or try clause.
+fn single_pattern(patterns: &aast::Item) -> Result<&aast::Item> {
+    let mut iter = as_list(patterns)?;
+    match (iter.next(), iter.next()) {
+        (Some(pattern), None) => Ok(pattern),
+        _ => Err(malformed(patterns.span())),
     }
 }
 
-fn lower_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> ast::Clause {
-    let tup = clause.tuple().unwrap();
+fn lower_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> Result<ast::Clause> {
+    let tup = as_tuple(clause)?;
 
-    assert!(tup.entries[0].atom().unwrap().as_str() == "clause");
-    let _line = tup.entries[1].integer().unwrap();
+    expect_form(tup, "clause")?;
 
-    let pattern = &tup.entries[2];
-    let guard = &tup.entries[3];
-    let body = &tup.entries[4];
+    let pattern = single_pattern(entry(tup, 2)?)?;
+    let guard = entry(tup, 3)?;
+    let body = entry(tup, 4)?;
 
-    let mut patterns = pattern.list_iter().unwrap();
-    let pattern_n = lower_expr(gen, patterns.next().unwrap());
-    assert!(patterns.next().is_none());
+    let pattern_n = lower_expr(gen, pattern)?;
 
-    let guard_n = lower_guards(gen, guard);
+    let guard_n = lower_guards(gen, guard)?;
 
-    let body_n = lower_body(gen, body);
+    let body_n = lower_body(gen, body)?;
 
-    ast::Clause {
+    Ok(ast::Clause {
         span: clause.span(),
         id: gen.next(),
         pattern: pattern_n,
         guard: guard_n,
         body: body_n,
-    }
+    })
 }
 
-fn lower_if_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> ast::IfClause {
-    let tup = clause.tuple().unwrap();
+fn lower_if_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> Result<ast::IfClause> {
+    let tup = as_tuple(clause)?;
 
-    assert!(tup.entries[0].atom().unwrap().as_str() == "clause");
-    let _line = tup.entries[1].integer().unwrap();
+    expect_form(tup, "clause")?;
 
-    assert!(tup.entries[2].list_iter().unwrap().count() == 0);
-    let guard = &tup.entries[3];
-    let body = &tup.entries[4];
+    if as_list(entry(tup, 2)?)?.count() != 0 {
+        return Err(malformed(tup.span));
+    }
+    let guard = entry(tup, 3)?;
+    let body = entry(tup, 4)?;
 
-    let guard_n = lower_guards(gen, guard);
-    let body_n = lower_body(gen, body);
+    let guard_n = match lower_guards(gen, guard)? {
+        Some(guard_n) => guard_n,
+        None => return Err(malformed(guard.span())),
+    };
+    let body_n = lower_body(gen, body)?;
 
-    ast::IfClause {
+    Ok(ast::IfClause {
         span: clause.span(),
         id: gen.next(),
-        guards: guard_n.unwrap(),
+        guards: guard_n,
         body: body_n,
-    }
+    })
 }
 
-fn lower_try_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> ast::TryClause {
-    println!("{:#?}", clause);
-    let tup = clause.tuple().unwrap();
-
-    assert!(tup.entries[0].atom().unwrap().as_str() == "clause");
-    let _line = tup.entries[1].integer().unwrap();
-
-    let pattern = &tup.entries[2];
-    let guard = &tup.entries[3];
-    let body = &tup.entries[4];
-
-    let mut patterns = pattern.list_iter().unwrap();
-    let pattern = patterns.next().unwrap();
-    assert!(patterns.next().is_none());
-
-    let patterns_tup_cont = pattern.tuple().unwrap();
-    assert!(patterns_tup_cont.entries.len() == 3);
-    assert!(patterns_tup_cont.entries[0].atom().unwrap().as_str() == "tuple");
-
-    let mut patterns_tup = patterns_tup_cont.entries[2].list_iter().unwrap();
-    let err_kind = patterns_tup.next().unwrap();
-    let err_error = patterns_tup.next().unwrap();
-    let err_trace = patterns_tup.next().unwrap();
-    assert!(patterns_tup.next().is_none());
-
-    println!("{:?}", pattern);
-    println!("{:?}", err_kind);
-    let err_kind_tup = err_kind.tuple().unwrap();
-    let err_kind_name = match &*err_kind_tup.entries[0].atom().unwrap().as_str() {
-        "var" => ast::Name::Var(err_kind_tup.entries[2].atom().unwrap()),
-        "atom" => ast::Name::Atom(err_kind_tup.entries[2].atom().unwrap()),
-        _ => panic!(),
+fn lower_try_clause(gen: &mut ast::NodeIdGenerator, clause: &aast::Item) -> Result<ast::TryClause> {
+    let tup = as_tuple(clause)?;
+
+    expect_form(tup, "clause")?;
+
+    let pattern = single_pattern(entry(tup, 2)?)?;
+    let guard = entry(tup, 3)?;
+    let body = entry(tup, 4)?;
+
+    // Catch patterns are always a `{Kind, Error, Trace}` tuple
+    let patterns_tup_cont = as_tuple(pattern)?;
+    expect_form(patterns_tup_cont, "tuple")?;
+
+    let patterns_tup: Vec<_> = as_list(entry(patterns_tup_cont, 2)?)?.collect();
+    let (err_kind, err_error, err_trace) = match patterns_tup.as_slice() {
+        [kind, error, trace] => (*kind, *error, *trace),
+        _ => return Err(malformed(patterns_tup_cont.span)),
     };
 
-    let err_trace_tup = err_trace.tuple().unwrap();
-    assert!(&*err_trace_tup.entries[0].atom().unwrap().as_str() == "var");
-    let err_trace_ident = err_trace_tup.entries[2].atom().unwrap();
+    let err_kind_tup = as_tuple(err_kind)?;
+    let err_kind_name = match &*as_atom(entry(err_kind_tup, 0)?)?.as_str() {
+        "var" => ast::Name::Var(as_atom(entry(err_kind_tup, 2)?)?),
+        "atom" => ast::Name::Atom(as_atom(entry(err_kind_tup, 2)?)?),
+        _ => return Err(malformed(err_kind_tup.span)),
+    };
 
-    let guard_n = lower_guards(gen, guard);
+    let err_trace_tup = as_tuple(err_trace)?;
+    expect_form(err_trace_tup, "var")?;
+    let err_trace_ident = as_atom(entry(err_trace_tup, 2)?)?;
 
-    let body_n = lower_body(gen, body);
+    let guard_n = lower_guards(gen, guard)?;
 
-    ast::TryClause {
+    let body_n = lower_body(gen, body)?;
+
+    Ok(ast::TryClause {
         span: clause.span(),
         id: gen.next(),
         kind: err_kind_name,
-        error: lower_expr(gen, err_error),
+        error: lower_expr(gen, err_error)?,
         trace: err_trace_ident,
         guard: guard_n,
         body: body_n,
-    }
+    })
 }
 
-fn lower_guards(gen: &mut ast::NodeIdGenerator, guard: &aast::Item) -> Option<Vec<ast::Guard>> {
-    let guard_n: Vec<_> = guard
-        .list_iter()
-        .unwrap()
-        .map(|guard| ast::Guard {
-            span: guard.span(),
-            conditions: guard
-                .list_iter()
-                .unwrap()
-                .map(|v| lower_expr(gen, v))
-                .collect(),
+fn lower_guards(
+    gen: &mut ast::NodeIdGenerator,
+    guard: &aast::Item,
+) -> Result<Option<Vec<ast::Guard>>> {
+    let guard_n = as_list(guard)?
+        .map(|guard| {
+            Ok(ast::Guard {
+                span: guard.span(),
+                conditions: as_list(guard)?
+                    .map(|v| lower_expr(gen, v))
+                    .collect::<Result<_>>()?,
+            })
         })
-        .collect();
+        .collect::<Result<Vec<_>>>()?;
     if guard_n.len() == 0 {
-        None
+        Ok(None)
     } else {
-        Some(guard_n)
+        Ok(Some(guard_n))
     }
 }
 
-fn lower_body(gen: &mut ast::NodeIdGenerator, body: &aast::Item) -> Vec<ast::Expr> {
-    let body_n: Vec<_> = body
-        .list_iter()
-        .unwrap()
-        .map(|expr| lower_expr(gen, expr))
-        .collect();
-    body_n
+fn lower_body(gen: &mut ast::NodeIdGenerator, body: &aast::Item) -> Result<Vec<ast::Expr>> {
+    as_list(body)?.map(|expr| lower_expr(gen, expr)).collect()
+}
+
+fn lower_exprs(gen: &mut ast::NodeIdGenerator, exprs: &aast::Item) -> Result<Vec<ast::Expr>> {
+    as_list(exprs)?.map(|expr| lower_expr(gen, expr)).collect()
 }
 
-fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
-    println!("{:#?}", expr);
-    let tup = expr.tuple().unwrap();
+fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> Result<ast::Expr> {
+    let tup = as_tuple(expr)?;
 
-    let name = tup.entries[0].atom().unwrap().as_str();
-    let _line = tup.entries[1].integer().unwrap();
+    let name = as_atom(entry(tup, 0)?)?.as_str();
+    let _line = as_integer(entry(tup, 1)?)?;
 
     let span = tup.span;
 
-    match &*name {
-        "var" => ast::Expr::Var(ast::Var(gen.next(), tup.entries[2].atom().unwrap())),
+    let expr = match &*name {
+        "var" => ast::Expr::Var(ast::Var(gen.next(), as_atom(entry(tup, 2)?)?)),
         "op" => {
             let tup_len = tup.entries.len();
-            let op = tup.entries[2].atom().unwrap().as_str();
+            let op = as_atom(entry(tup, 2)?)?.as_str();
 
             enum ExprKind {
                 Unary(ast::UnaryOp),
@@ -382,24 +452,40 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             }
 
             let expr_kind = match (&*op, tup_len) {
-                ("+", 5) => ExprKind::Binary(ast::BinaryOp::Add),
-                ("-", 5) => ExprKind::Binary(ast::BinaryOp::Sub),
-                ("*", 5) => ExprKind::Binary(ast::BinaryOp::Multiply),
+                ("!", 5) => ExprKind::Binary(ast::BinaryOp::Send),
+                ("orelse", 5) => ExprKind::Binary(ast::BinaryOp::OrElse),
+                ("andalso", 5) => ExprKind::Binary(ast::BinaryOp::AndAlso),
                 ("==", 5) => ExprKind::Binary(ast::BinaryOp::Equal),
+                ("/=", 5) => ExprKind::Binary(ast::BinaryOp::NotEqual),
+                ("=<", 5) => ExprKind::Binary(ast::BinaryOp::Lte),
+                ("<", 5) => ExprKind::Binary(ast::BinaryOp::Lt),
+                (">=", 5) => ExprKind::Binary(ast::BinaryOp::Gte),
+                (">", 5) => ExprKind::Binary(ast::BinaryOp::Gt),
                 ("=:=", 5) => ExprKind::Binary(ast::BinaryOp::StrictEqual),
                 ("=/=", 5) => ExprKind::Binary(ast::BinaryOp::StrictNotEqual),
-                ("andalso", 5) => ExprKind::Binary(ast::BinaryOp::AndAlso),
                 ("++", 5) => ExprKind::Binary(ast::BinaryOp::Append),
-                (">", 5) => ExprKind::Binary(ast::BinaryOp::Gt),
-                ("<", 5) => ExprKind::Binary(ast::BinaryOp::Lt),
+                ("--", 5) => ExprKind::Binary(ast::BinaryOp::Remove),
+                ("+", 5) => ExprKind::Binary(ast::BinaryOp::Add),
+                ("-", 5) => ExprKind::Binary(ast::BinaryOp::Sub),
+                ("bor", 5) => ExprKind::Binary(ast::BinaryOp::Bor),
+                ("bxor", 5) => ExprKind::Binary(ast::BinaryOp::Bxor),
+                ("bsl", 5) => ExprKind::Binary(ast::BinaryOp::Bsl),
+                ("bsr", 5) => ExprKind::Binary(ast::BinaryOp::Bsr),
+                ("or", 5) => ExprKind::Binary(ast::BinaryOp::Or),
+                ("xor", 5) => ExprKind::Binary(ast::BinaryOp::Xor),
+                ("/", 5) => ExprKind::Binary(ast::BinaryOp::Divide),
+                ("*", 5) => ExprKind::Binary(ast::BinaryOp::Multiply),
+                ("div", 5) => ExprKind::Binary(ast::BinaryOp::Div),
                 ("rem", 5) => ExprKind::Binary(ast::BinaryOp::Rem),
+                ("band", 5) => ExprKind::Binary(ast::BinaryOp::Band),
+                ("and", 5) => ExprKind::Binary(ast::BinaryOp::And),
 
                 ("+", 4) => ExprKind::Unary(ast::UnaryOp::Plus),
                 ("-", 4) => ExprKind::Unary(ast::UnaryOp::Minus),
                 ("bnot", 4) => ExprKind::Unary(ast::UnaryOp::Bnot),
                 ("not", 4) => ExprKind::Unary(ast::UnaryOp::Not),
 
-                (n, a) => unimplemented!("{} {}", n, a),
+                (n, a) => return Err(unsupported(span, &format!("op {}/{}", n, a - 3))),
             };
 
             match expr_kind {
@@ -407,33 +493,34 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                     span,
                     id: gen.next(),
                     op: op,
-                    operand: Box::new(lower_expr(gen, &tup.entries[3])),
+                    operand: Box::new(lower_expr(gen, &tup.entries[3])?),
                 }),
                 ExprKind::Binary(op) => ast::Expr::BinaryExpr(ast::BinaryExpr {
                     span,
                     id: gen.next(),
                     op: op,
-                    lhs: Box::new(lower_expr(gen, &tup.entries[3])),
-                    rhs: Box::new(lower_expr(gen, &tup.entries[4])),
+                    lhs: Box::new(lower_expr(gen, &tup.entries[3])?),
+                    rhs: Box::new(lower_expr(gen, &tup.entries[4])?),
                 }),
             }
         }
         "integer" => {
-            let int = tup.entries[2].integer().unwrap();
+            let int = as_integer(entry(tup, 2)?)?;
             let lit = ast::Literal::Integer(span, gen.next(), int.integer.clone());
             ast::Expr::Literal(lit)
         }
         "string" => {
-            if let Some(string) = tup.entries[2].string() {
+            let value = entry(tup, 2)?;
+            if let Some(string) = value.string() {
                 ast::Expr::Literal(ast::Literal::String(gen.next(), string))
             } else {
-                let elems: Vec<_> = tup.entries[2].list_iter().unwrap().collect();
+                let elems: Vec<_> = as_list(value)?.collect();
                 let mut acc = ast::Expr::Nil(ast::Nil(tup.span, gen.next()));
                 for elem in elems.iter().rev() {
                     acc = ast::Expr::Cons(ast::Cons {
                         span: elem.span(),
                         id: gen.next(),
-                        head: Box::new(lower_expr(gen, elem)),
+                        head: Box::new(lower_expr(gen, elem)?),
                         tail: Box::new(acc),
                     });
                 }
@@ -441,22 +528,18 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             }
         }
         "atom" => {
-            let atom = tup.entries[2].atom().unwrap();
+            let atom = as_atom(entry(tup, 2)?)?;
             ast::Expr::Literal(ast::Literal::Atom(gen.next(), atom))
         }
         "nil" => ast::Expr::Nil(ast::Nil(span, gen.next())),
         "tuple" => ast::Expr::Tuple(ast::Tuple {
             span,
             id: gen.next(),
-            elements: tup.entries[2]
-                .list_iter()
-                .unwrap()
-                .map(|e| lower_expr(gen, e))
-                .collect(),
+            elements: lower_exprs(gen, entry(tup, 2)?)?,
         }),
         "cons" => {
-            let head = lower_expr(gen, &tup.entries[2]);
-            let tail = lower_expr(gen, &tup.entries[3]);
+            let head = lower_expr(gen, entry(tup, 2)?)?;
+            let tail = lower_expr(gen, entry(tup, 3)?)?;
             ast::Expr::Cons(ast::Cons {
                 span,
                 id: gen.next(),
@@ -467,35 +550,33 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
         "map" => {
             let tup_len = tup.entries.len();
 
-            let fields = tup.entries[tup_len - 1]
-                .list_iter()
-                .unwrap()
+            let fields = as_list(&tup.entries[tup_len - 1])?
                 .map(|field| {
-                    let field_tup = field.tuple().unwrap();
+                    let field_tup = as_tuple(field)?;
                     let span = field_tup.span;
 
-                    let op_name = field_tup.entries[0].atom().unwrap().as_str();
+                    let op_name = as_atom(entry(field_tup, 0)?)?.as_str();
 
-                    let key = lower_expr(gen, &field_tup.entries[2]);
-                    let value = lower_expr(gen, &field_tup.entries[3]);
+                    let key = lower_expr(gen, entry(field_tup, 2)?)?;
+                    let value = lower_expr(gen, entry(field_tup, 3)?)?;
 
                     match &*op_name {
-                        "map_field_exact" => ast::MapField::Exact {
+                        "map_field_exact" => Ok(ast::MapField::Exact {
                             span,
                             id: gen.next(),
                             key,
                             value,
-                        },
-                        "map_field_assoc" => ast::MapField::Assoc {
+                        }),
+                        "map_field_assoc" => Ok(ast::MapField::Assoc {
                             span,
                             id: gen.next(),
                             key,
                             value,
-                        },
-                        r => panic!("{}", r),
+                        }),
+                        _ => Err(malformed(span)),
                     }
                 })
-                .collect();
+                .collect::<Result<_>>()?;
 
             match tup_len {
                 3 => ast::Expr::Map(ast::Map {
@@ -506,19 +587,17 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                 4 => ast::Expr::MapUpdate(ast::MapUpdate {
                     span,
                     id: gen.next(),
-                    map: Box::new(lower_expr(gen, &tup.entries[2])),
+                    map: Box::new(lower_expr(gen, &tup.entries[2])?),
                     updates: fields,
                 }),
-                _ => panic!(),
+                _ => return Err(malformed(span)),
             }
         }
         "case" => {
-            let expr = lower_expr(gen, &tup.entries[2]);
-            let clauses = tup.entries[3]
-                .list_iter()
-                .unwrap()
+            let expr = lower_expr(gen, entry(tup, 2)?)?;
+            let clauses = as_list(entry(tup, 3)?)?
                 .map(|c| lower_clause(gen, c))
-                .collect();
+                .collect::<Result<_>>()?;
             ast::Expr::Case(ast::Case {
                 span,
                 id: gen.next(),
@@ -527,13 +606,8 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             })
         }
         "call" => {
-            let target = lower_expr(gen, &tup.entries[2]);
-
-            let args = tup.entries[3]
-                .list_iter()
-                .unwrap()
-                .map(|v| lower_expr(gen, v))
-                .collect();
+            let target = lower_expr(gen, entry(tup, 2)?)?;
+            let args = lower_exprs(gen, entry(tup, 3)?)?;
 
             ast::Expr::Apply(ast::Apply {
                 span,
@@ -545,55 +619,49 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
         "remote" => ast::Expr::Remote(ast::Remote {
             span,
             id: gen.next(),
-            module: Box::new(lower_expr(gen, &tup.entries[2])),
-            function: Box::new(lower_expr(gen, &tup.entries[3])),
+            module: Box::new(lower_expr(gen, entry(tup, 2)?)?),
+            function: Box::new(lower_expr(gen, entry(tup, 3)?)?),
         }),
         "bin" => {
-            let elements = tup.entries[2]
-                .list_iter()
-                .unwrap()
+            let elements = as_list(entry(tup, 2)?)?
                 .map(|elem| {
-                    let tup = elem.tuple().unwrap();
-                    assert!(tup.entries[0].atom().unwrap().as_str() == "bin_element");
+                    let tup = as_tuple(elem)?;
+                    expect_form(tup, "bin_element")?;
 
-                    let bit_expr = lower_expr(gen, &tup.entries[2]);
+                    let bit_expr = lower_expr(gen, entry(tup, 2)?)?;
 
-                    let bit_size_v = &tup.entries[3];
-                    let bit_size = if let Some(atom) = bit_size_v.atom() {
-                        assert!(&*atom.as_str() == "default");
+                    let bit_size_v = entry(tup, 3)?;
+                    let bit_size = if bit_size_v.atom().is_some() {
+                        expect_default(bit_size_v)?;
                         None
                     } else {
-                        Some(lower_expr(gen, bit_size_v))
+                        Some(lower_expr(gen, bit_size_v)?)
                     };
 
-                    let bit_type_v = &tup.entries[4];
-                    let bit_type = if let Some(atom) = bit_type_v.atom() {
-                        assert!(&*atom.as_str() == "default");
+                    let bit_type_v = entry(tup, 4)?;
+                    let bit_type = if bit_type_v.atom().is_some() {
+                        expect_default(bit_type_v)?;
                         None
                     } else {
-                        let list = bit_type_v
-                            .list_iter()
-                            .unwrap()
-                            .map(|item| {
-                                if let Some(atom) = item.atom() {
-                                    ast::BitType::Name(span, gen.next(), atom)
-                                } else {
-                                    unimplemented!()
-                                }
+                        let list = as_list(bit_type_v)?
+                            .map(|item| match item.atom() {
+                                Some(atom) => Ok(ast::BitType::Name(span, gen.next(), atom)),
+                                // Type specifiers with a value, like `unit:8`
+                                None => Err(unsupported(item.span(), "bin_element type")),
                             })
-                            .collect();
+                            .collect::<Result<_>>()?;
                         Some(list)
                     };
 
-                    ast::BinaryElement {
+                    Ok(ast::BinaryElement {
                         span: elem.span(),
                         id: gen.next(),
                         bit_expr,
                         bit_size,
                         bit_type,
-                    }
+                    })
                 })
-                .collect();
+                .collect::<Result<_>>()?;
             ast::Expr::Binary(ast::Binary {
                 span,
                 id: gen.next(),
@@ -602,32 +670,36 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
         }
         "fun" => {
             // We expect either a M:F/A or a function definition.
-            let inner = tup.entries[2].tuple().unwrap();
-            let inner_name = inner.entries[0].atom().unwrap();
+            let inner = as_tuple(entry(tup, 2)?)?;
+            let inner_name = as_atom(entry(inner, 0)?)?;
             match &*inner_name.as_str() {
-                "function" => {
-                    let module = atom(&inner.entries[1]);
-                    let function = atom(&inner.entries[2]);
-                    let arity = integer(&inner.entries[3]);
+                "function" if inner.entries.len() == 4 => {
+                    let module = atom(&inner.entries[1])?;
+                    let function = atom(&inner.entries[2])?;
+                    let arity = integer(&inner.entries[3])?;
                     ast::Expr::FunctionName(ast::FunctionName::Resolved(
                         ast::ResolvedFunctionName {
                             span: inner.span,
                             id: gen.next(),
                             module,
                             function,
-                            arity: arity.integer.to_usize().unwrap(),
+                            arity: arity
+                                .integer
+                                .to_usize()
+                                .ok_or_else(|| malformed(arity.span))?,
                         },
                     ))
                 }
                 "clauses" => {
-                    let clauses: Vec<_> = inner.entries[1]
-                        .list_iter()
-                        .unwrap()
+                    let clauses: Vec<_> = as_list(entry(inner, 1)?)?
                         .map(|v| lower_function_clause(gen, v))
-                        .collect();
-                    let arity = clauses[0].params.len();
-                    for clause in clauses.iter() {
-                        assert!(clause.params.len() == arity);
+                        .collect::<Result<_>>()?;
+                    let arity = match clauses.first() {
+                        Some(clause) => clause.params.len(),
+                        None => return Err(malformed(inner.span)),
+                    };
+                    if clauses.iter().any(|clause| clause.params.len() != arity) {
+                        return Err(malformed(inner.span));
                     }
                     ast::Expr::Fun(ast::Function::Unnamed(ast::Lambda {
                         span: inner.span,
@@ -636,12 +708,12 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                         clauses,
                     }))
                 }
-                v => unimplemented!("{}", v),
+                v => return Err(unsupported(inner.span, &format!("fun {}", v))),
             }
         }
         "match" => {
-            let pattern = lower_expr(gen, &tup.entries[2]);
-            let expr = lower_expr(gen, &tup.entries[3]);
+            let pattern = lower_expr(gen, entry(tup, 2)?)?;
+            let expr = lower_expr(gen, entry(tup, 3)?)?;
             ast::Expr::Match(ast::Match {
                 span,
                 id: gen.next(),
@@ -650,64 +722,49 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             })
         }
         "char" => {
-            let int = tup.entries[2].integer().unwrap();
-            ast::Expr::Literal(ast::Literal::Char(
-                span,
-                gen.next(),
-                int.integer.to_u32().unwrap().try_into().unwrap(),
-            ))
+            let int = as_integer(entry(tup, 2)?)?;
+            let c = int
+                .integer
+                .to_u32()
+                .and_then(|c| c.try_into().ok())
+                .ok_or_else(|| malformed(int.span))?;
+            ast::Expr::Literal(ast::Literal::Char(span, gen.next(), c))
         }
         "float" => {
-            let float = tup.entries[2].float().unwrap();
+            let float = entry(tup, 2)?.float().ok_or_else(|| malformed(span))?;
             ast::Expr::Literal(ast::Literal::Float(span, gen.next(), float.float))
         }
         "catch" => ast::Expr::Catch(ast::Catch {
             span,
             id: gen.next(),
-            expr: Box::new(lower_expr(gen, &tup.entries[2])),
+            expr: Box::new(lower_expr(gen, entry(tup, 2)?)?),
         }),
         "generate" => ast::Expr::Generator(ast::Generator {
             span,
             id: gen.next(),
-            pattern: Box::new(lower_expr(gen, &tup.entries[2])),
-            expr: Box::new(lower_expr(gen, &tup.entries[3])),
+            pattern: Box::new(lower_expr(gen, entry(tup, 2)?)?),
+            expr: Box::new(lower_expr(gen, entry(tup, 3)?)?),
         }),
         "lc" => ast::Expr::ListComprehension(ast::ListComprehension {
             span,
             id: gen.next(),
-            body: Box::new(lower_expr(gen, &tup.entries[2])),
-            qualifiers: tup.entries[3]
-                .list_iter()
-                .unwrap()
-                .map(|v| lower_expr(gen, v))
-                .collect(),
+            body: Box::new(lower_expr(gen, entry(tup, 2)?)?),
+            qualifiers: lower_exprs(gen, entry(tup, 3)?)?,
         }),
         "receive" => {
-            let mut clauses = Vec::new();
-            let after = None;
-            for clause in tup.entries[2].list_iter().unwrap() {
-                let clause_tup = clause.tuple().unwrap();
-                match &*clause_tup.entries[0].atom().unwrap().as_str() {
-                    "clause" => {
-                        let mut arg_iter = clause_tup.entries[2].list_iter().unwrap();
-                        let arg = arg_iter.next().unwrap();
-                        assert!(arg_iter.next().is_none());
-
-                        let pattern = lower_expr(gen, arg);
-                        let guard = lower_guards(gen, &clause_tup.entries[3]);
-                        let body = lower_body(gen, &clause_tup.entries[4]);
-
-                        clauses.push(ast::Clause {
-                            span: clause_tup.span,
-                            id: gen.next(),
-                            pattern,
-                            guard: guard,
-                            body,
-                        });
-                    }
-                    _ => unimplemented!(),
-                }
-            }
+            let clauses = as_list(entry(tup, 2)?)?
+                .map(|c| lower_clause(gen, c))
+                .collect::<Result<Vec<_>>>()?;
+            let after = match tup.entries.len() {
+                3 => None,
+                5 => Some(ast::After {
+                    span,
+                    id: gen.next(),
+                    timeout: Box::new(lower_expr(gen, &tup.entries[3])?),
+                    body: lower_body(gen, &tup.entries[4])?,
+                }),
+                _ => return Err(malformed(span)),
+            };
             ast::Expr::Receive(ast::Receive {
                 span,
                 id: gen.next(),
@@ -720,7 +777,7 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             })
         }
         "block" => {
-            let body = lower_body(gen, &tup.entries[2]);
+            let body = lower_body(gen, entry(tup, 2)?)?;
             ast::Expr::Begin(ast::Begin {
                 span,
                 id: gen.next(),
@@ -728,11 +785,9 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
             })
         }
         "if" => {
-            let clauses = tup.entries[2]
-                .list_iter()
-                .unwrap()
+            let clauses = as_list(entry(tup, 2)?)?
                 .map(|v| lower_if_clause(gen, v))
-                .collect();
+                .collect::<Result<_>>()?;
             ast::Expr::If(ast::If {
                 span,
                 id: gen.next(),
@@ -741,12 +796,10 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
         }
         "record" => match tup.entries.len() {
             4 => {
-                let name = tup.entries[2].atom().unwrap();
-                let fields = tup.entries[3]
-                    .list_iter()
-                    .unwrap()
+                let name = as_atom(&tup.entries[2])?;
+                let fields = as_list(&tup.entries[3])?
                     .map(|v| lower_record_field(gen, v))
-                    .collect();
+                    .collect::<Result<_>>()?;
                 ast::Expr::Record(ast::Record {
                     span,
                     id: gen.next(),
@@ -755,13 +808,11 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                 })
             }
             5 => {
-                let old = lower_expr(gen, &tup.entries[2]);
-                let name = tup.entries[3].atom().unwrap();
-                let fields = tup.entries[4]
-                    .list_iter()
-                    .unwrap()
+                let old = lower_expr(gen, &tup.entries[2])?;
+                let name = as_atom(&tup.entries[3])?;
+                let fields = as_list(&tup.entries[4])?
                     .map(|v| lower_record_field(gen, v))
-                    .collect();
+                    .collect::<Result<_>>()?;
                 ast::Expr::RecordUpdate(ast::RecordUpdate {
                     span,
                     id: gen.next(),
@@ -770,21 +821,17 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                     updates: fields,
                 })
             }
-            _ => unimplemented!(),
+            _ => return Err(malformed(span)),
         },
         "try" => {
-            let exprs = lower_body(gen, &tup.entries[2]);
-            let clauses: Vec<_> = tup.entries[3]
-                .list_iter()
-                .unwrap()
+            let exprs = lower_body(gen, entry(tup, 2)?)?;
+            let clauses: Vec<_> = as_list(entry(tup, 3)?)?
                 .map(|v| lower_clause(gen, v))
-                .collect();
-            let catch_clauses: Vec<_> = tup.entries[4]
-                .list_iter()
-                .unwrap()
+                .collect::<Result<_>>()?;
+            let catch_clauses: Vec<_> = as_list(entry(tup, 4)?)?
                 .map(|v| lower_try_clause(gen, v))
-                .collect();
-            let after = lower_body(gen, &tup.entries[5]);
+                .collect::<Result<_>>()?;
+            let after = lower_body(gen, entry(tup, 5)?)?;
             ast::Expr::Try(ast::Try {
                 span,
                 id: gen.next(),
@@ -802,31 +849,89 @@ fn lower_expr(gen: &mut ast::NodeIdGenerator, expr: &aast::Item) -> ast::Expr {
                 after: if after.len() == 0 { None } else { Some(after) },
             })
         }
-        v => unimplemented!("{}", v),
+        v => return Err(unsupported(span, v)),
+    };
+
+    Ok(expr)
+}
+
+fn malformed(span: SourceSpan) -> LowerError {
+    LowerError::MalformedAbstractForm { span }
+}
+
+fn unsupported(span: SourceSpan, name: &str) -> LowerError {
+    LowerError::UnsupportedAbstractForm {
+        span,
+        name: name.to_string(),
     }
 }
 
-fn atom(item: &aast::Item) -> Ident {
-    let tup = item.tuple().unwrap();
-    assert!(&*tup.entries[0].atom().unwrap().as_str() == "atom");
-    tup.entries[2].atom().unwrap()
+fn entry(tup: &aast::Tuple, idx: usize) -> Result<&aast::Item> {
+    tup.entries.get(idx).ok_or_else(|| malformed(tup.span))
 }
 
-fn integer(item: &aast::Item) -> &aast::Int {
-    let tup = item.tuple().unwrap();
-    assert!(&*tup.entries[0].atom().unwrap().as_str() == "integer");
-    tup.entries[2].integer().unwrap()
+fn as_tuple(item: &aast::Item) -> Result<&aast::Tuple> {
+    item.tuple().ok_or_else(|| malformed(item.span()))
+}
+
+fn as_atom(item: &aast::Item) -> Result<Ident> {
+    item.atom().ok_or_else(|| malformed(item.span()))
+}
+
+fn as_integer(item: &aast::Item) -> Result<&aast::Int> {
+    item.integer().ok_or_else(|| malformed(item.span()))
+}
+
+fn as_usize(item: &aast::Item) -> Result<usize> {
+    as_integer(item)?
+        .integer
+        .to_usize()
+        .ok_or_else(|| malformed(item.span()))
+}
+
+fn as_list(item: &aast::Item) -> Result<impl Iterator<Item = &aast::Item>> {
+    item.list_iter().ok_or_else(|| malformed(item.span()))
+}
+
+/// Checks that a form is a tuple tagged with `name`.
+fn expect_form(tup: &aast::Tuple, name: &str) -> Result<()> {
+    if &*as_atom(entry(tup, 0)?)?.as_str() == name {
+        Ok(())
+    } else {
+        Err(malformed(tup.span))
+    }
+}
+
+fn expect_default(item: &aast::Item) -> Result<()> {
+    match item.atom() {
+        Some(atom) if &*atom.as_str() == "default" => Ok(()),
+        _ => Err(malformed(item.span())),
+    }
+}
+
+fn atom(item: &aast::Item) -> Result<Ident> {
+    let tup = as_tuple(item)?;
+    expect_form(tup, "atom")?;
+    as_atom(entry(tup, 2)?)
+}
+
+fn integer(item: &aast::Item) -> Result<&aast::Int> {
+    let tup = as_tuple(item)?;
+    expect_form(tup, "integer")?;
+    as_integer(entry(tup, 2)?)
 }
 
 #[cfg(test)]
 mod test {
     use libeir_diagnostics::{CodeMap, Diagnostic, ToDiagnostic};
-    use libeir_util_parse::{error_tee, Errors, Parse, Parser};
+    use libeir_intern::Symbol;
+    use libeir_util_parse::{error_tee, ErrorOrWarning, Errors, Parse, Parser};
     use libeir_util_parse_listing::ast::Root;
     use libeir_util_parse_listing::parser::ParseError;
     use std::path::Path;
     use std::sync::Arc;
 
+    use crate::ast::{NodeIdGenerator, TopLevel};
     use crate::LowerError;
 
     enum ParseOrLowerError {
@@ -870,6 +975,14 @@ mod test {
         };
     }
 
+    fn lower(root: &Root) -> crate::ast::Module {
+        let mut errors: Errors<LowerError, LowerError> = Errors::new();
+        match super::lower(&mut errors, root) {
+            Ok(module) => module,
+            Err(()) => panic!(),
+        }
+    }
+
     fn parse_file<T, S>(path: S) -> T
     where
         T: Parse<T, Config = (), Error = ParseError>,
@@ -914,13 +1027,71 @@ mod test {
 {eof,17}.
 ",
         );
-        super::lower(&root);
+        lower(&root);
     }
 
     #[test]
     fn maps() {
         let root: Root = parse_file("../test_data/maps.abstr");
-        super::lower(&root);
+        lower(&root);
+    }
+
+    #[test]
+    fn malformed_form() {
+        let root: Root = parse(
+            "
+{attribute,1,module,woo}.
+{function,3,foo,0,[{clause,3,[],[],[{unknown_expr,3}]}]}.
+{eof,4}.
+",
+        );
+        let mut errors: Errors<LowerError, LowerError> = Errors::new();
+        assert!(super::lower(&mut errors, &root).is_err());
+        assert!(errors.failed());
+    }
+
+    #[test]
+    fn malformed_forms_are_skipped() {
+        let root: Root = parse(
+            "
+{attribute,1,module,woo}.
+{function,3,foo,0,[{clause,3,[],[],[{unknown_expr,3}]}]}.
+{function,4,bar,0,[{clause,4,[],[],[{atom,4,ok}]}]}.
+{function,5,baz,0,not_a_list}.
+{function,6,qux,1,[{clause,6,[{var,6,'A'}],[],[{var,6,'A'}]}]}.
+{eof,7}.
+",
+        );
+
+        let mut errors: Errors<LowerError, LowerError> = Errors::new();
+        let mut id_gen = NodeIdGenerator::new();
+        let (module_name, toplevel) = super::lower_forms(&mut errors, &mut id_gen, &root);
+
+        assert!(module_name.map(|name| name.name) == Some(Symbol::intern("woo")));
+
+        let functions: Vec<_> = toplevel
+            .iter()
+            .map(|top| match top {
+                TopLevel::Function(fun) => (fun.name.name, fun.arity),
+                _ => panic!(),
+            })
+            .collect();
+        assert!(functions == vec![(Symbol::intern("bar"), 0), (Symbol::intern("qux"), 1)]);
+
+        let reported: Vec<_> = errors
+            .errors
+            .iter()
+            .map(|err| match err {
+                ErrorOrWarning::Error(LowerError::UnsupportedAbstractForm { name, .. }) => {
+                    name.clone()
+                }
+                ErrorOrWarning::Error(LowerError::MalformedAbstractForm { .. }) => {
+                    "malformed".to_string()
+                }
+                _ => panic!(),
+            })
+            .collect();
+        assert!(reported == vec!["unknown_expr".to_string(), "malformed".to_string()]);
     }
 
     #[test]
@@ -934,7 +1105,7 @@ mod test {
                 "../test_data/match_SUITE.abstr",
             ) {
                 Ok(ast) => {
-                    let module = super::lower(&ast);
+                    let module = super::lower(&mut errors.make_into_adapter(), &ast)?;
                     crate::lower_module(
                         &mut errors.make_into_adapter(),
                         parser.codemap.clone(),
@@ -0,0 +1,125 @@
+use libeir_intern::Ident;
+use libeir_ir::constant::{AtomTerm, Const, ConstantContainer, NilTerm};
+use libeir_ir::Module as IrModule;
+
+use crate::parser::ast::{Expr, Literal, Module, UnaryExpr, UnaryOp};
+
+use super::expr::literal::{intern_binary_const, intern_string_const};
+use super::{LowerCtx, LowerError};
+
+/// Carries the module level metadata collected by the parser over
+/// to the IR module. Must be called after all functions are lowered,
+/// since exports and `on_load` refer to function definitions.
+pub(super) fn lower_attributes(ctx: &mut LowerCtx, ir_module: &mut IrModule, module: &Module) {
+    let export_all = module
+        .compile
+        .as_ref()
+        .map(|opts| opts.export_all)
+        .unwrap_or(false);
+    for def in ir_module.function_iter_mut() {
+        let exported = {
+            let ident = def.function().ident();
+            export_all
+                || module
+                    .exports
+                    .iter()
+                    .any(|e| e.function.name == ident.name.name && e.arity == ident.arity)
+        };
+        def.set_exported(exported);
+    }
+
+    if let Some(on_load) = &module.on_load {
+        let index = ir_module.name_arity_index(on_load.function.name, on_load.arity);
+        ir_module.set_on_load(index);
+    }
+
+    if let Some(vsn) = &module.vsn {
+        add_attribute(ctx, ir_module, Ident::from_str("vsn"), vsn);
+    }
+    if let Some(author) = &module.author {
+        add_attribute(ctx, ir_module, Ident::from_str("author"), author);
+    }
+    if let Some(compile) = &module.compile {
+        for term in compile.terms.iter() {
+            add_attribute(ctx, ir_module, Ident::from_str("compile"), term);
+        }
+    }
+
+    // The parser collects these into unordered sets, sort them to keep
+    // the output deterministic.
+    let mut behaviours: Vec<_> = module.behaviours.iter().collect();
+    behaviours.sort_by(|a, b| a.name.as_str().cmp(&b.name.as_str()));
+    for behaviour in behaviours {
+        let value = ir_module.cons_mut().from(AtomTerm(behaviour.name));
+        ir_module.add_attribute(Ident::from_str("behaviour"), value);
+    }
+
+    let mut attributes: Vec<_> = module.attributes.values().collect();
+    attributes.sort_by(|a, b| a.name.name.as_str().cmp(&b.name.name.as_str()));
+    for attribute in attributes {
+        add_attribute(ctx, ir_module, attribute.name, &attribute.value);
+    }
+}
+
+fn add_attribute(ctx: &mut LowerCtx, ir_module: &mut IrModule, name: Ident, value: &Expr) {
+    match lower_const_expr(ctx, ir_module.cons_mut(), value) {
+        Some(value) => ir_module.add_attribute(name, value),
+        None => ctx.warn(LowerError::UnsupportedAttributeValue { span: value.span() }),
+    }
+}
+
+/// Attribute values are plain terms, this handles the subset of
+/// expressions that can occur in them.
+fn lower_const_expr(ctx: &mut LowerCtx, c: &mut ConstantContainer, expr: &Expr) -> Option<Const> {
+    match expr {
+        Expr::Literal(lit) => match lit {
+            Literal::Atom(_id, ident) => Some(c.from(AtomTerm(ident.name))),
+            Literal::Integer(_span, _id, int) => Some(c.from(int.clone())),
+            Literal::Float(_span, _id, flt) => Some(c.from(*flt)),
+            Literal::Char(_span, _id, chr) => Some(c.from(*chr)),
+            Literal::String(_id, ident) => match intern_string_const(*ident, c) {
+                Ok(cons) => Some(cons),
+                Err(err) => {
+                    ctx.error(err);
+                    None
+                }
+            },
+            Literal::Binary(_id, ident) => match intern_binary_const(*ident, c) {
+                Ok(bin) => Some(bin),
+                Err(err) => {
+                    ctx.error(err);
+                    None
+                }
+            },
+        },
+        Expr::UnaryExpr(UnaryExpr {
+            op: UnaryOp::Minus,
+            operand,
+            ..
+        }) => match &**operand {
+            Expr::Literal(Literal::Integer(_span, _id, int)) => Some(c.from(-int.clone())),
+            Expr::Literal(Literal::Float(_span, _id, flt)) => Some(c.from(-*flt)),
+            _ => None,
+        },
+        Expr::Nil(_) => Some(c.from(NilTerm)),
+        Expr::Cons(cons) => {
+            let head = lower_const_expr(ctx, c, &cons.head)?;
+            let tail = lower_const_expr(ctx, c, &cons.tail)?;
+            Some(c.list_cell(head, tail))
+        }
+        Expr::Tuple(tup) => {
+            let mut builder = c.tuple_builder();
+            for elem in tup.elements.iter() {
+                match lower_const_expr(ctx, c, elem) {
+                    Some(elem) => builder.push(elem, c),
+                    None => {
+                        builder.clear(c);
+                        return None;
+                    }
+                }
+            }
+            Some(builder.finish(c))
+        }
+        _ => None,
+    }
+}
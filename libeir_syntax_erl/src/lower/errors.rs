@@ -9,6 +9,10 @@ pub enum LowerError {
     /// An invalid expression occurred in a pattern
     #[snafu(display("an invalid expression occurred in a pattern"))]
     NotAllowedInPattern { span: SourceSpan },
+    /// An expression that is only valid in a specific context, like a
+    /// generator outside of a comprehension.
+    #[snafu(display("expression is not valid in this context"))]
+    IllegalExpression { span: SourceSpan },
 
     /// Equality in a pattern caused two nodes to be merged.
     /// It has been shown to be unmatchable.
@@ -27,7 +31,6 @@ pub enum LowerError {
 
     /// Equality in a pattern caused two nodes to be merged,
     /// but merging these two nodes is not supported.
-    /// Happens when trying to merge two binary patterns.
     #[snafu(display("patterns cannot be merged"))]
     UnsupportedPatternUnion {
         left: Option<SourceSpan>,
@@ -65,12 +68,34 @@ pub enum LowerError {
         span: SourceSpan,
         typ: BinaryTypeName,
     },
+    /// The pattern of a binary generator is not a binary.
+    #[snafu(display("binary generator pattern must be a binary"))]
+    BinaryGeneratorPattern { span: SourceSpan },
 
     // Records
     #[snafu(display("record field specified more than once"))]
     DuplicateRecordField { new: SourceSpan, old: SourceSpan },
     #[snafu(display("record is not defined"))]
     UndefinedRecord { span: SourceSpan },
+    #[snafu(display("record field is not defined"))]
+    UndefinedRecordField { span: SourceSpan },
+
+    /// A module attribute contained a value that is not a plain term.
+    #[snafu(display("unsupported value in module attribute"))]
+    UnsupportedAttributeValue { span: SourceSpan },
+
+    // Abstract format
+    /// A term in the abstract format did not have the expected shape.
+    #[snafu(display("malformed abstract format"))]
+    MalformedAbstractForm { span: SourceSpan },
+    /// A well formed abstract form that the lowering does not handle.
+    #[snafu(display("unsupported abstract form `{}`", name))]
+    UnsupportedAbstractForm { span: SourceSpan, name: String },
+    #[snafu(display("module is missing a module attribute"))]
+    MissingModuleAttribute { span: SourceSpan },
+    /// A diagnostic produced by a different stage, forwarded as is.
+    #[snafu(display("{}", diagnostic.message))]
+    ShowDiagnostic { diagnostic: Diagnostic },
 }
 
 impl ToDiagnostic for LowerError {
@@ -81,6 +106,11 @@ impl ToDiagnostic for LowerError {
                 .with_message(msg)
                 .with_labels(vec![Label::primary(span.source_id(), *span)
                     .with_message("disallowed expression in pattern")]),
+            LowerError::IllegalExpression { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("not allowed here")
+                ]),
             LowerError::DisjointPatternUnionWarning { left, right } => {
                 let dig = Diagnostic::warning().with_message(msg);
                 let mut labels = vec![];
@@ -95,9 +125,23 @@ impl ToDiagnostic for LowerError {
                 }
                 dig.with_labels(labels)
             }
-            LowerError::UnsupportedPatternUnion { left, right } => {
+            LowerError::UnmatchablePatternWarning { pat, reason } => {
                 let dig = Diagnostic::warning().with_message(msg);
                 let mut labels = vec![];
+                if let Some(pat) = pat {
+                    labels.push(Label::primary(pat.source_id(), *pat).with_message("pattern"));
+                }
+                if let Some(reason) = reason {
+                    labels.push(
+                        Label::secondary(reason.source_id(), *reason)
+                            .with_message("because of this"),
+                    );
+                }
+                dig.with_labels(labels)
+            }
+            LowerError::UnsupportedPatternUnion { left, right } => {
+                let dig = Diagnostic::error().with_message(msg);
+                let mut labels = vec![];
                 if let Some(left) = left {
                     labels
                         .push(Label::primary(left.source_id(), *left).with_message("left pattern"));
@@ -106,6 +150,11 @@ impl ToDiagnostic for LowerError {
                     .push(Label::primary(right.source_id(), *right).with_message("right pattern"));
                 dig.with_labels(labels)
             }
+            LowerError::BinaryGeneratorPattern { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("expected a binary")
+                ]),
             LowerError::InvalidStringEscape { span } => Diagnostic::error()
                 .with_message(msg)
                 .with_labels(vec![
@@ -130,7 +179,60 @@ impl ToDiagnostic for LowerError {
                     Label::secondary(old.source_id(), *old).with_message("previously bound here"),
                 ])
             }
-            _ => unimplemented!(),
+            LowerError::BinaryUnknownSpecifier { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("unknown specifier")
+                ]),
+            LowerError::BinaryConflictingSpecifier { new, old } => {
+                Diagnostic::error().with_message(msg).with_labels(vec![
+                    Label::primary(new.source_id(), *new).with_message("specifier"),
+                    Label::secondary(old.source_id(), *old).with_message("conflicts with this"),
+                ])
+            }
+            LowerError::BinaryInvalidSpecifier { span, typ } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("not valid for type {:?}", typ))]),
+            LowerError::BinaryInvalidSize { span, typ } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("type {:?} can not have a size", typ))]),
+            LowerError::DuplicateRecordField { new, old } => {
+                Diagnostic::error().with_message(msg).with_labels(vec![
+                    Label::primary(new.source_id(), *new).with_message("duplicate field"),
+                    Label::secondary(old.source_id(), *old).with_message("first specified here"),
+                ])
+            }
+            LowerError::UndefinedRecord { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("undefined record")
+                ]),
+            LowerError::UndefinedRecordField { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("undefined field")
+                ]),
+            LowerError::UnsupportedAttributeValue { span } => Diagnostic::warning()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("attribute is not a constant term, it will be ignored")]),
+            LowerError::MalformedAbstractForm { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("unexpected term")
+                ]),
+            LowerError::UnsupportedAbstractForm { span, .. } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![
+                    Label::primary(span.source_id(), *span).with_message("not supported")
+                ]),
+            LowerError::MissingModuleAttribute { span } => Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("expected a module attribute")]),
+            LowerError::ShowDiagnostic { diagnostic } => diagnostic.clone(),
         }
     }
 }
@@ -10,6 +10,7 @@ use crate::parser::ast::{Expr, Literal, Var};
 
 use crate::lower::expr::{lower_block, lower_single};
 use crate::lower::pattern::lower_clause;
+use crate::lower::scope::is_wildcard;
 use crate::lower::LowerCtx;
 
 pub(super) fn lower_try_expr(
@@ -125,7 +126,7 @@ pub(super) fn lower_try_expr(
                 clause.guard.as_ref(),
             ) {
                 Ok(lowered) => {
-                    let (scope_token, body) = lowered.make_body(ctx, b);
+                    let (scope_token, mut body) = lowered.make_body(ctx, b);
 
                     // Add to case
                     let body_val = b.value(body);
@@ -134,8 +135,13 @@ pub(super) fn lower_try_expr(
                         case_b.push_value(*value, b);
                     }
 
-                    // Bind stack trace in scope
-                    ctx.bind(clause.trace, exc_trace);
+                    // Bind stack trace in scope. The raw trace is only
+                    // turned into a list when it is used.
+                    if !is_wildcard(clause.trace) {
+                        body = b.op_trace_construct(clause.span, body, exc_trace);
+                        let trace = b.block_args(body)[0];
+                        ctx.bind(clause.trace, trace);
+                    }
 
                     let (body_ret_block, body_ret) = lower_block(ctx, b, body, &clause.body);
 
@@ -268,10 +274,13 @@ pub(super) fn lower_catch_expr(
         let error_block_val = b.value(error_block);
         case_b.push_clause(error_clause, guard_val, error_block_val, b);
 
-        let inner_tup = b.prim_tuple(span, &[exc_error, exc_trace]);
+        let trace_block = b.op_trace_construct(span, error_block, exc_trace);
+        let trace = b.block_args(trace_block)[0];
+
+        let inner_tup = b.prim_tuple(span, &[exc_error, trace]);
         let ret_tup = b.prim_tuple(span, &[big_exit_atom, inner_tup]);
 
-        b.op_call_flow(error_block, join_block, &[ret_tup]);
+        b.op_call_flow(trace_block, join_block, &[ret_tup]);
     }
 
     // Exit branch
@@ -5,12 +5,62 @@ use libeir_ir::{Block as IrBlock, FunctionBuilder, Value as IrValue};
 
 use libeir_intern::{Ident, Symbol};
 
-use crate::parser::ast::{BinaryComprehension, Expr, ListComprehension};
+use crate::parser::ast::{
+    Binary, BinaryComprehension, BinaryElement, BitType, Expr, ListComprehension, Var,
+};
 
 use crate::lower::expr::binary::lower_binary_expr;
 use crate::lower::expr::{lower_single, lower_single_same_scope};
 use crate::lower::pattern::lower_clause;
-use crate::lower::LowerCtx;
+use crate::lower::{LowerCtx, LowerError};
+
+/// Builds the pattern a binary generator matches on every iteration, the
+/// generator pattern followed by a segment binding the rest of the binary
+/// to `tail`.
+///
+/// The skip pattern is matched when the generator pattern does not match.
+/// It has the same segments with every value replaced by a wildcard, an
+/// element that does not match is skipped as long as there are enough bits
+/// left for it. Variables used as sizes are kept.
+fn binary_generator_pattern(bin: &Binary, tail: Ident, skip: bool) -> Expr {
+    let mut elements = Vec::with_capacity(bin.elements.len() + 1);
+
+    for elem in bin.elements.iter() {
+        let mut elem = elem.clone();
+        if skip {
+            let is_size = match &elem.bit_expr {
+                Expr::Var(Var(_, var)) => bin.elements.iter().any(|e| match &e.bit_size {
+                    Some(Expr::Var(Var(_, size))) => size == var,
+                    _ => false,
+                }),
+                _ => false,
+            };
+            if !is_size {
+                let span = elem.bit_expr.span();
+                elem.bit_expr = Expr::Var(Var(elem.id, Ident::new(Symbol::intern("_"), span)));
+            }
+        }
+        elements.push(elem);
+    }
+
+    elements.push(BinaryElement {
+        span: bin.span,
+        id: bin.id,
+        bit_expr: Expr::Var(Var(bin.id, tail)),
+        bit_size: None,
+        bit_type: Some(vec![BitType::Name(
+            bin.span,
+            bin.id,
+            Ident::new(Symbol::intern("bitstring"), bin.span),
+        )]),
+    });
+
+    Expr::Binary(Binary {
+        span: bin.span,
+        id: bin.id,
+        elements,
+    })
+}
 
 fn lower_qual<F>(
     ctx: &mut LowerCtx,
@@ -124,10 +174,109 @@ where
 
                         (ret_block, ret_val)
                     }
-                    Err(_) => unimplemented!(), // TODO warn/error unreachable pattern
+                    // The pattern can never match, every element goes to
+                    // the no match branch and is skipped.
+                    Err(_) => {
+                        case_b.finish(block, b);
+                        (ret_block, ret_val)
+                    }
+                }
+            }
+            Expr::BinaryGenerator(gen) => {
+                let gen_span = gen.span;
+
+                //     loop_block(bin_val, acc)
+                // loop_block(loop_bin_arg, loop_acc_arg):
+                //     case loop_bin_arg of
+                //         <<Pattern, Tail/bitstring>> -> do inner, loop_block(Tail, acc)
+                //         <<Skip, Tail/bitstring>> -> loop_block(Tail, loop_acc_arg)
+                //         _ -> done_block()
+                // done_block:
+                //     ret(loop_acc_arg)
+
+                let bin_pattern = match &*gen.pattern {
+                    Expr::Binary(bin) => bin,
+                    pattern => {
+                        ctx.error(LowerError::BinaryGeneratorPattern {
+                            span: pattern.span(),
+                        });
+                        return (block, acc);
+                    }
+                };
+
+                let bin_val = map_block!(block, lower_single(ctx, b, block, &gen.expr));
+
+                // Loop entry block
+                let loop_block = b.block_insert();
+                let loop_bin_arg = b.block_arg_insert(loop_block);
+                let loop_acc_arg = b.block_arg_insert(loop_block);
+
+                b.op_call_flow(block, loop_block, &[bin_val, acc]);
+
+                // When neither pattern matches, the generator is done
+                let done_block = b.block_insert();
+
+                // The rest of the binary is bound to a variable that can not
+                // be written in Erlang.
+                let tail = Ident::new(
+                    Symbol::intern(&format!("$bin_gen_tail_{}", gen.id.0)),
+                    gen_span,
+                );
+
+                let pattern_span = gen.pattern.span();
+                let pattern = binary_generator_pattern(bin_pattern, tail, false);
+                let skip_pattern = binary_generator_pattern(bin_pattern, tail, true);
+
+                block = loop_block;
+
+                let mut case_b = Case::builder();
+                case_b.set_span(pattern_span);
+                case_b.match_on = Some(loop_bin_arg);
+                case_b.no_match = Some(b.value(done_block));
+
+                for (pattern, is_skip) in [(&pattern, false), (&skip_pattern, true)].iter() {
+                    match lower_clause(
+                        ctx,
+                        &mut case_b.container,
+                        b,
+                        &mut block,
+                        false,
+                        pattern_span,
+                        [*pattern].iter().map(|i| *i),
+                        None,
+                    ) {
+                        Ok(lowered) => {
+                            let (scope_token, body) = lowered.make_body(ctx, b);
+
+                            // Add to case
+                            let body_val = b.value(body);
+                            case_b.push_clause(lowered.clause, lowered.guard, body_val, b);
+                            for value in lowered.values.iter() {
+                                case_b.push_value(*value, b);
+                            }
+
+                            let tail_val = ctx.resolve(tail);
+                            if *is_skip {
+                                b.op_call_flow(body, loop_block, &[tail_val, loop_acc_arg]);
+                            } else {
+                                let (cont, cont_val) =
+                                    lower_qual(ctx, b, inner, &quals[1..], body, loop_acc_arg);
+                                b.op_call_flow(cont, loop_block, &[tail_val, cont_val]);
+                            }
+
+                            // Pop scope pushed in lower_clause
+                            ctx.scope.pop(scope_token);
+                        }
+                        // A pattern that can never match is left out, the
+                        // skip pattern takes care of the element.
+                        Err(_) => (),
+                    }
                 }
+
+                case_b.finish(block, b);
+
+                (done_block, loop_acc_arg)
             }
-            Expr::BinaryGenerator(_gen) => unimplemented!(),
             expr => {
                 let bool_val = map_block!(block, lower_single_same_scope(ctx, b, block, expr));
                 let span = expr.span();
@@ -179,28 +328,41 @@ pub(super) fn lower_list_comprehension_expr(
 pub(super) fn lower_binary_comprehension_expr(
     ctx: &mut LowerCtx,
     b: &mut FunctionBuilder,
-    mut block: IrBlock,
+    block: IrBlock,
     compr: &BinaryComprehension,
 ) -> (IrBlock, IrValue) {
-    let inner =
-        |ctx: &mut LowerCtx, b: &mut FunctionBuilder, block: IrBlock, acc: IrValue| match &*compr
-            .body
-        {
-            Expr::Binary(bin) => lower_binary_expr(ctx, b, block, Some(acc), bin),
-            _ => panic!(),
-        };
-
-    let nil = b.value(Vec::<u8>::new());
-    let val = map_block!(
-        block,
-        lower_qual(ctx, b, &inner, &compr.qualifiers, block, nil)
-    );
-    ctx.call_function(
-        b,
-        block,
-        compr.span,
-        Ident::from_str("lists"),
-        Ident::from_str("reverse"),
-        &[val],
-    )
+    // Any other expression is appended as a whole, as if it was
+    // written as `<<Body/bitstring>>`.
+    let wrapped;
+    let body = match &*compr.body {
+        Expr::Binary(bin) => bin,
+        expr => {
+            let span = expr.span();
+            wrapped = Binary {
+                span,
+                id: compr.id,
+                elements: vec![BinaryElement {
+                    span,
+                    id: compr.id,
+                    bit_expr: expr.clone(),
+                    bit_size: None,
+                    bit_type: Some(vec![BitType::Name(
+                        span,
+                        compr.id,
+                        Ident::new(Symbol::intern("bitstring"), span),
+                    )]),
+                }],
+            };
+            &wrapped
+        }
+    };
+
+    let inner = |ctx: &mut LowerCtx, b: &mut FunctionBuilder, block: IrBlock, acc: IrValue| {
+        lower_binary_expr(ctx, b, block, Some(acc), body)
+    };
+
+    // Every element is appended to the accumulator, unlike the list
+    // comprehension the result does not need to be reversed.
+    let empty = b.value(Vec::<u8>::new());
+    lower_qual(ctx, b, &inner, &compr.qualifiers, block, empty)
 }
@@ -204,7 +204,12 @@ pub fn tokenize_string(ident: Ident) -> Result<Vec<u64>, LowerError> {
                 num,
             } => {
                 if !c.is_digit(16) {
-                    unimplemented!()
+                    // A single hex digit, the current character is not
+                    // part of the escape.
+                    let parsed = u64::from_str_radix(&full[digit_start..idx], 16).unwrap();
+                    out.push(parsed);
+                    *state = StringState::Norm;
+                    return process(state, out, ident, full, idx, c);
                 } else {
                     *state = StringState::Hex2 {
                         start,
@@ -213,21 +218,25 @@ pub fn tokenize_string(ident: Ident) -> Result<Vec<u64>, LowerError> {
                     };
                 }
             }
-            StringState::HexN { digit_start, .. } => {
+            StringState::HexN { start, digit_start } => {
                 if c == '}' {
-                    let parsed = u64::from_str_radix(&full[digit_start..idx], 16).unwrap();
-                    out.push(parsed);
+                    match u64::from_str_radix(&full[digit_start..idx], 16) {
+                        Ok(parsed) => out.push(parsed),
+                        Err(_) => return Err(err_until_current(start)),
+                    }
                     *state = StringState::Norm;
+                } else if !c.is_digit(16) {
+                    return Err(err_until_current(start));
                 }
             }
-            StringState::Control { .. } => {
+            StringState::Control { start } => {
                 let cl = c.to_ascii_lowercase();
                 if cl >= 'a' && cl <= 'z' {
                     let num = (cl as u64 - 'a' as u64) + 1;
                     out.push(num);
                     *state = StringState::Norm;
                 } else {
-                    unimplemented!()
+                    return Err(err_until_current(start));
                 }
             }
         }
@@ -261,7 +270,7 @@ pub fn tokenize_string(ident: Ident) -> Result<Vec<u64>, LowerError> {
             StringState::Hex2 {
                 digit_start, num, ..
             } => {
-                if num == 2 {
+                if num == 2 || c.is_none() {
                     let parsed = u64::from_str_radix(&full[digit_start..idx], 16).unwrap();
                     out.push(parsed);
                     *state = StringState::Norm;
@@ -281,6 +290,23 @@ pub fn tokenize_string(ident: Ident) -> Result<Vec<u64>, LowerError> {
     }
     post_process(&mut state, &mut chars, string, string.len(), None)?;
 
+    // The string ended in the middle of an escape
+    match state {
+        StringState::Norm => (),
+        StringState::Escape { start }
+        | StringState::Oct { start, .. }
+        | StringState::HexStart { start }
+        | StringState::HexN { start, .. }
+        | StringState::Hex2 { start, .. }
+        | StringState::Control { start } => {
+            let span = SourceSpan::new(
+                ident.span.start() + start,
+                ident.span.start() + string.len(),
+            );
+            return Err(LowerError::InvalidStringEscape { span });
+        }
+    }
+
     Ok(chars)
 }
 
@@ -349,5 +375,16 @@ mod tests {
         assert!(tokenize_string(Ident::from_str("\\x{ffff}")).unwrap() == vec![0xffff]);
 
         assert!(tokenize_string(Ident::from_str("\\^a\\^z")).unwrap() == vec![1, 26]);
+        assert!(tokenize_string(Ident::from_str("\\xfg")).unwrap() == vec![0xf, 'g' as u64]);
+        assert!(tokenize_string(Ident::from_str("\\xf")).unwrap() == vec![0xf]);
+    }
+
+    #[test]
+    fn string_literal_invalid_escape() {
+        assert!(tokenize_string(Ident::from_str("\\x{fg}")).is_err());
+        assert!(tokenize_string(Ident::from_str("\\x{}")).is_err());
+        assert!(tokenize_string(Ident::from_str("\\x{ff")).is_err());
+        assert!(tokenize_string(Ident::from_str("\\^1")).is_err());
+        assert!(tokenize_string(Ident::from_str("a\\")).is_err());
     }
 }
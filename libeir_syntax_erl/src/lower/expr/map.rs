@@ -27,9 +27,9 @@ pub(super) fn lower_map_update_expr(
         map_builder.push_kv(key_val, value_val, action, b);
     }
 
+    let (ok, fail) = map_builder.finish(block, b);
     let loc = ctx.current_location(b, map.span);
     b.block_set_location(block, loc);
-    let (ok, fail) = map_builder.finish(block, b);
 
     let typ_val = b.value(Symbol::intern("error"));
     let badmatch_val = b.value(Symbol::intern("badkey"));
@@ -61,9 +61,9 @@ pub(super) fn lower_map_expr(
         map_builder.push_kv(key_val, value_val, action, b);
     }
 
+    let (ok, fail) = map_builder.finish(block, b);
     let loc = ctx.current_location(b, map.span);
     b.block_set_location(block, loc);
-    let (ok, fail) = map_builder.finish(block, b);
 
     let typ_val = b.value(Symbol::intern("error"));
     let badmatch_val = b.value(Symbol::intern("badkey"));
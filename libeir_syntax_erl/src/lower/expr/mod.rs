@@ -7,7 +7,7 @@ use libeir_intern::{Ident, Symbol};
 use super::lower_function;
 
 use super::pattern::lower_clause;
-use super::LowerCtx;
+use super::{LowerCtx, LowerError};
 
 use crate::parser::ast::UnaryOp;
 use crate::parser::ast::{Apply, Remote, UnaryExpr};
@@ -15,6 +15,8 @@ use crate::parser::ast::{Arity, Name};
 use crate::parser::ast::{Expr, Literal, Var};
 use crate::parser::ast::{FunctionName, LocalFunctionName};
 
+use crate::lexer::DelayedSubstitution;
+
 pub mod literal;
 use literal::lower_literal;
 
@@ -163,11 +165,12 @@ fn lower_expr(
                 arg_vals.push(arg_val);
             }
 
+            let (ok_block, fail_block) = b.op_call_function(span, block, callee_val, &arg_vals);
+
+            // The operation sets a location of its own, replace it
             let loc = ctx.current_location(b, span);
             b.block_set_location(block, loc);
 
-            let (ok_block, fail_block) = b.op_call_function(span, block, callee_val, &arg_vals);
-
             let fail_type = b.block_args(fail_block)[0];
             let fail_error = b.block_args(fail_block)[1];
             let fail_trace = b.block_args(fail_block)[2];
@@ -208,7 +211,14 @@ fn lower_expr(
                     Symbol::intern("+"),
                     &[operand_val],
                 ),
-                op => unimplemented!("{:?}", op),
+                UnaryOp::Bnot => ctx.call_function(
+                    b,
+                    block,
+                    *span,
+                    Symbol::intern("erlang"),
+                    Symbol::intern("bnot"),
+                    &[operand_val],
+                ),
             };
 
             (block, val)
@@ -359,8 +369,23 @@ fn lower_expr(
             comprehension::lower_binary_comprehension_expr(ctx, b, block, compr)
         }
         Expr::Binary(bin) => binary::lower_binary_expr(ctx, b, block, None, bin),
-        _ => {
-            unimplemented!("{:?}", expr);
+        Expr::DelayedSubstitution(_span, _id, subs) => {
+            let (name, arity) = ctx.top_function.unwrap();
+            let val = match subs {
+                DelayedSubstitution::FunctionName => b.value(name),
+                DelayedSubstitution::FunctionArity => b.value(arity),
+            };
+            (block, val)
+        }
+        // Generators are only valid as qualifiers of comprehensions,
+        // remotes only as the callee of an apply, and map projections
+        // only in patterns.
+        Expr::Generator(_)
+        | Expr::BinaryGenerator(_)
+        | Expr::Remote(_)
+        | Expr::MapProjection(_) => {
+            ctx.error(LowerError::IllegalExpression { span: expr.span() });
+            (block, ctx.sentinel())
         }
     }
 }
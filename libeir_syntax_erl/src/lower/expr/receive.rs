@@ -1,4 +1,4 @@
-use libeir_intern::Ident;
+use libeir_intern::{Ident, Symbol};
 use libeir_ir::{
     operation::case::Case,
     operation::receive::{ReceiveDone, ReceiveStart, ReceiveWait},
@@ -27,9 +27,22 @@ pub(super) fn lower_receive(
     };
 
     // Receive start
-    let recv_wait_block = ReceiveStart::build(b, block, after_timeout_val);
+    let (recv_wait_block, invalid_timeout_block) = ReceiveStart::build(b, block, after_timeout_val);
     let recv_ref_val = b.block_args(recv_wait_block)[0];
 
+    // Invalid timeout, raised in the enclosing scope
+    {
+        let typ_val = b.value(Symbol::intern("error"));
+        let timeout_value_val = b.value(Symbol::intern("timeout_value"));
+        ctx.exc_stack.make_error_jump(
+            b,
+            recv.span,
+            invalid_timeout_block,
+            typ_val,
+            timeout_value_val,
+        );
+    }
+
     // Receive wait
     let (after_block, mut body_block) = ReceiveWait::build(b, recv_wait_block, recv_ref_val);
     let body_message_arg = b.block_args(body_block)[0];
@@ -35,11 +35,16 @@ pub(super) fn lower_record_access_expr(
     rec: &RecordAccess,
 ) -> (IrBlock, IrValue) {
     let span = rec.span;
-    let rec_def = &ctx.module.records[&rec.name.name];
+    let rec_def = match ctx.record(rec.name) {
+        Some(def) => def,
+        None => return (block, ctx.sentinel()),
+    };
+    let idx = match ctx.record_field_idx(rec_def, rec.field) {
+        Some(idx) => idx,
+        None => return (block, ctx.sentinel()),
+    };
     let recname_val = b.value(rec.name);
 
-    let idx = rec_def.field_idx_map[&rec.field];
-
     let fail_block = make_rec_fail(ctx, b, span, recname_val);
 
     let record_val = map_block!(block, lower_single(ctx, b, block, &rec.record));
@@ -70,7 +75,10 @@ pub(super) fn lower_record_update_expr(
 ) -> (IrBlock, IrValue) {
     let span = rec.span;
     // TODO Warn/error when updates overlap?
-    let rec_def = &ctx.module.records[&rec.name.name];
+    let rec_def = match ctx.record(rec.name) {
+        Some(def) => def,
+        None => return (block, ctx.sentinel()),
+    };
     let recname_val = b.value(rec.name);
 
     let num_fields = rec_def.record.fields.len();
@@ -102,7 +110,10 @@ pub(super) fn lower_record_update_expr(
 
     // Update fields
     for update in rec.updates.iter() {
-        let idx = rec_def.field_idx_map[&update.name];
+        let idx = match ctx.record_field_idx(rec_def, update.name) {
+            Some(idx) => idx,
+            None => continue,
+        };
         let new_val = map_block!(
             block,
             lower_single(ctx, b, block, update.value.as_ref().unwrap())
@@ -126,7 +137,10 @@ pub(super) fn lower_record_expr(
     rec: &Record,
 ) -> (IrBlock, IrValue) {
     let span = rec.span;
-    let rec_def = &ctx.module.records[&rec.name.name];
+    let rec_def = match ctx.record(rec.name) {
+        Some(def) => def,
+        None => return (block, ctx.sentinel()),
+    };
     let recname_val = b.value(rec.name);
 
     let num_fields = rec_def.record.fields.len();
@@ -135,7 +149,10 @@ pub(super) fn lower_record_expr(
 
     // Populate values from expression
     for field in rec.fields.iter() {
-        let idx = rec_def.field_idx_map[&field.name];
+        let idx = match ctx.record_field_idx(rec_def, field.name) {
+            Some(idx) => idx,
+            None => continue,
+        };
 
         if elems[idx].is_some() {
             ctx.error(LowerError::DuplicateRecordField {
@@ -184,8 +201,11 @@ pub(super) fn lower_record_index(
     block: IrBlock,
     rec: &RecordIndex,
 ) -> (IrBlock, IrValue) {
-    let rec_def = &ctx.module.records[&rec.name.name];
-    let index = rec_def.field_idx_map[&rec.field];
-    let val = b.value(index);
-    (block, val)
+    let index = ctx
+        .record(rec.name)
+        .and_then(|rec_def| ctx.record_field_idx(rec_def, rec.field));
+    match index {
+        Some(index) => (block, b.value(index)),
+        None => (block, ctx.sentinel()),
+    }
 }
@@ -9,7 +9,7 @@ use libeir_diagnostics::{CodeMap, SourceSpan};
 use libeir_intern::{Ident, Symbol};
 use libeir_util_parse::ErrorReceiver;
 
-use crate::parser::ast::{Function, FunctionClause, Module, NamedFunction};
+use crate::parser::ast::{DefinedRecord, Function, FunctionClause, Module, NamedFunction};
 
 macro_rules! map_block {
     ($block:ident, $call:expr) => {{
@@ -25,6 +25,9 @@ use pattern::lower_clause;
 mod expr;
 use expr::{lower_block, lower_single};
 
+mod attribute;
+use attribute::lower_attributes;
+
 mod errors;
 pub use errors::LowerError;
 
@@ -54,6 +57,9 @@ pub(crate) struct LowerCtx<'a> {
     /// Top is current function name.
     /// Used to generate debug info.
     functions: Vec<String>,
+    /// Name and arity of the top level function being lowered.
+    /// Used for `?FUNCTION_NAME` and `?FUNCTION_ARITY`.
+    top_function: Option<(Ident, usize)>,
 }
 
 impl<'a> LowerCtx<'a> {
@@ -109,6 +115,31 @@ impl<'a> LowerCtx<'a> {
         }
     }
 
+    /// Looks up the definition of a record, reports an error when it
+    /// is not defined.
+    pub fn record(&mut self, name: Ident) -> Option<&'a DefinedRecord> {
+        let module = self.module;
+        match module.records.get(&name.name) {
+            Some(def) => Some(def),
+            None => {
+                self.error(LowerError::UndefinedRecord { span: name.span });
+                None
+            }
+        }
+    }
+
+    /// Index of a field in a record definition, reports an error when
+    /// the record has no such field.
+    pub fn record_field_idx(&mut self, record: &DefinedRecord, field: Ident) -> Option<usize> {
+        match record.field_idx_map.get(&field) {
+            Some(idx) => Some(*idx),
+            None => {
+                self.error(LowerError::UndefinedRecordField { span: field.span });
+                None
+            }
+        }
+    }
+
     pub fn function_name(&self) -> String {
         self.functions[self.functions.len() - 1].clone()
     }
@@ -140,11 +171,12 @@ impl<'a> LowerCtx<'a> {
             self.val_buf.push(*arg);
         }
 
+        let (ok_block, fail_block) = b.op_call_function(span, block, fun_val, args);
+
+        // The operation sets a location of its own, replace it
         let loc = self.current_location(b, span);
         b.block_set_location(block, loc);
 
-        let (ok_block, fail_block) = b.op_call_function(span, block, fun_val, args);
-
         let fail_type = b.block_args(fail_block)[0];
         let fail_error = b.block_args(fail_block)[1];
         let fail_trace = b.block_args(fail_block)[2];
@@ -181,6 +213,7 @@ pub fn lower_module<'a>(
 
         fun_num: 0,
         functions: Vec::new(),
+        top_function: None,
     };
 
     for (ident, function) in module.functions.iter() {
@@ -204,6 +237,8 @@ pub fn lower_module<'a>(
         lower_top_function(&mut ctx, &mut builder, function);
     }
 
+    lower_attributes(&mut ctx, &mut ir_module, module);
+
     ctx.exc_stack.finish();
 
     if ctx.failed() {
@@ -217,7 +252,23 @@ fn lower_function(ctx: &mut LowerCtx, b: &mut FunctionBuilder, fun: &Function) -
     let entry = b.block_insert_with_span(Some(fun.span()));
 
     match fun {
-        Function::Named(_named) => unimplemented!(),
+        Function::Named(named) => {
+            ctx.fun_num += 1;
+            let base_fun = &ctx.functions[0];
+            let new_fun = format!("{}-{}-{}", base_fun, named.name, ctx.fun_num);
+            ctx.functions.push(new_fun);
+
+            // Within its body the name refers to the closure itself,
+            // which is what makes recursion possible.
+            let scope_token = ctx.scope.push();
+            let self_val = b.value(entry);
+            ctx.bind_shadow(named.name, self_val);
+
+            lower_function_base(ctx, b, entry, named.span, named.arity, &named.clauses);
+
+            ctx.scope.pop(scope_token);
+            ctx.functions.pop().unwrap();
+        }
         Function::Unnamed(lambda) => {
             ctx.fun_num += 1;
             let base_fun = &ctx.functions[0];
@@ -333,6 +384,7 @@ fn lower_top_function(ctx: &mut LowerCtx, b: &mut FunctionBuilder, function: &Na
     let fun_name = format!("{}/{}", function.name, function.arity);
     assert!(ctx.functions.len() == 0);
     ctx.functions.push(fun_name);
+    ctx.top_function = Some((function.name, function.arity));
 
     lower_function_base(
         ctx,
@@ -343,6 +395,7 @@ fn lower_top_function(ctx: &mut LowerCtx, b: &mut FunctionBuilder, function: &Na
         &function.clauses,
     );
 
+    ctx.top_function = None;
     ctx.functions.pop().unwrap();
     assert!(ctx.functions.len() == 0);
 }
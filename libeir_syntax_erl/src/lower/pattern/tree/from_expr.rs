@@ -1,13 +1,12 @@
 use either::Either;
-use std::convert::TryInto;
 
 use cranelift_entity::EntityList;
 
-use libeir_ir::{AtomicTerm, BigIntTerm, BinaryTerm, Block, FunctionBuilder, IntTerm, NilTerm};
+use libeir_ir::{AtomicTerm, BinaryTerm, Block, FunctionBuilder, NilTerm};
 
 use libeir_diagnostics::SourceSpan;
 
-use libeir_util_number::bigint::BigInt;
+use libeir_util_number::{Integer, ToPrimitive};
 
 use crate::lower::{lower_single, LowerCtx, LowerError};
 use crate::parser::ast::{Binary, BinaryExpr, BinaryOp, Expr, Literal, UnaryExpr, UnaryOp, Var};
@@ -59,58 +58,58 @@ fn pattern_to_tree_node_append_tail(
                 tail: tail,
             })
         }
-        _ => unimplemented!("{:?}", expr),
+        _ => {
+            ctx.error(LowerError::NotAllowedInPattern { span: expr.span() });
+            t.nodes.push(TreeNodeKind::Wildcard(expr.span()))
+        }
     }
 }
 
-fn eval_const_expr(
-    ctx: &mut LowerCtx,
-    b: &mut FunctionBuilder,
-    pre_block: &mut Block,
-    expr: &Expr,
-) -> AtomicTerm {
+/// Evaluates the constant expressions that are allowed in patterns.
+/// Returns `None` when the expression is not a constant number.
+fn eval_const_expr(expr: &Expr) -> Option<AtomicTerm> {
     match expr {
-        Expr::Literal(lit) => match lit {
-            Literal::Integer(_span, _id, int) => int.clone().into(),
-            val => unimplemented!("{:?}", val),
-        },
-        Expr::UnaryExpr(
-            unary @ UnaryExpr {
-                op: UnaryOp::Minus, ..
-            },
-        ) => match eval_const_expr(ctx, b, pre_block, &unary.operand) {
-            AtomicTerm::Int(IntTerm(int)) => AtomicTerm::Int(IntTerm(-int)),
-            val => unimplemented!("{:?}", val),
+        Expr::Literal(Literal::Float(_span, _id, num)) => Some((*num).into()),
+        Expr::UnaryExpr(UnaryExpr {
+            op: UnaryOp::Minus,
+            operand,
+            ..
+        }) => match &**operand {
+            Expr::Literal(Literal::Float(_span, _id, num)) => Some((-*num).into()),
+            operand => Some((Integer::Small(0) - &eval_const_int(operand)?).into()),
         },
-        Expr::BinaryExpr(
-            binary @ BinaryExpr {
-                op: BinaryOp::Add, ..
-            },
-        ) => {
-            let lhs = eval_const_expr(ctx, b, pre_block, &binary.lhs);
-            let rhs = eval_const_expr(ctx, b, pre_block, &binary.rhs);
-            match (lhs, rhs) {
-                (AtomicTerm::Int(IntTerm(l)), AtomicTerm::Int(IntTerm(r))) => {
-                    AtomicTerm::Int(IntTerm(l + r))
-                }
-                val => unimplemented!("{:?}", val),
-            }
-        }
-        Expr::BinaryExpr(
-            binary @ BinaryExpr {
-                op: BinaryOp::Bsl, ..
-            },
-        ) => {
-            let lhs = eval_const_expr(ctx, b, pre_block, &binary.lhs);
-            let rhs = eval_const_expr(ctx, b, pre_block, &binary.rhs);
-            match (lhs, rhs) {
-                (AtomicTerm::Int(IntTerm(l)), AtomicTerm::Int(IntTerm(r))) => {
-                    AtomicTerm::BigInt(BigIntTerm(BigInt::from(l) << r.try_into().unwrap()))
+        expr => eval_const_int(expr).map(Into::into),
+    }
+}
+
+fn eval_const_int(expr: &Expr) -> Option<Integer> {
+    match expr {
+        Expr::Literal(Literal::Integer(_span, _id, int)) => Some(int.clone()),
+        Expr::Literal(Literal::Char(_span, _id, c)) => Some(Integer::Small(*c as i64)),
+        Expr::UnaryExpr(UnaryExpr {
+            op: UnaryOp::Plus,
+            operand,
+            ..
+        }) => eval_const_int(operand),
+        Expr::UnaryExpr(UnaryExpr {
+            op: UnaryOp::Minus,
+            operand,
+            ..
+        }) => Some(Integer::Small(0) - &eval_const_int(operand)?),
+        Expr::BinaryExpr(BinaryExpr { op, lhs, rhs, .. }) => {
+            let lhs = eval_const_int(lhs)?;
+            let rhs = eval_const_int(rhs)?;
+            match op {
+                BinaryOp::Add => Some(lhs + &rhs),
+                BinaryOp::Sub => Some(lhs - &rhs),
+                BinaryOp::Bsl => {
+                    let shift = rhs.to_usize()?;
+                    Some(Integer::Big(lhs.to_bigint() << shift).shrink())
                 }
-                val => unimplemented!("{:?}", val),
+                _ => None,
             }
         }
-        _ => unimplemented!("{:?}", expr),
+        _ => None,
     }
 }
 
@@ -225,14 +224,10 @@ fn pattern_to_tree_node(
         }
         Expr::Record(rec) => {
             let span = expr.span();
-            if !ctx.module.records.contains_key(&rec.name.name) {
-                ctx.error(LowerError::UndefinedRecord {
-                    span: rec.name.span,
-                });
-                return t.nodes.push(TreeNodeKind::Wildcard(span));
-            }
-
-            let rec_def = &ctx.module.records[&rec.name.name];
+            let rec_def = match ctx.record(rec.name) {
+                Some(def) => def,
+                None => return t.nodes.push(TreeNodeKind::Wildcard(span)),
+            };
 
             let name = b.cons_mut().from(rec.name);
             let name_node = t.nodes.push(TreeNodeKind::Atomic(rec.name.span, name));
@@ -243,7 +238,10 @@ fn pattern_to_tree_node(
             }
 
             for field in rec.fields.iter() {
-                let idx = rec_def.field_idx_map[&field.name];
+                let idx = match ctx.record_field_idx(rec_def, field.name) {
+                    Some(idx) => idx,
+                    None => continue,
+                };
 
                 let node =
                     pattern_to_tree_node(ctx, b, pre_block, t, &field.value.as_ref().unwrap());
@@ -336,7 +334,6 @@ fn pattern_to_tree_node(
                         });
                         None
                     } else {
-                        println!("SIZE EXPR {:?}", size_expr);
                         let ret = match size_expr {
                             Expr::Var(Var(_id, var)) => Either::Left(*var),
                             _ => {
@@ -383,10 +380,15 @@ fn pattern_to_tree_node(
                 entries,
             })
         }
-        expr => {
-            let atomic_term = eval_const_expr(ctx, b, pre_block, expr);
-            let cons = b.cons_mut().from(atomic_term);
-            t.nodes.push(TreeNodeKind::Atomic(expr.span(), cons))
-        }
+        expr => match eval_const_expr(expr) {
+            Some(atomic_term) => {
+                let cons = b.cons_mut().from(atomic_term);
+                t.nodes.push(TreeNodeKind::Atomic(expr.span(), cons))
+            }
+            None => {
+                ctx.error(LowerError::NotAllowedInPattern { span: expr.span() });
+                t.nodes.push(TreeNodeKind::Wildcard(expr.span()))
+            }
+        },
     }
 }
@@ -116,7 +116,10 @@ fn create_nodes(
                 create_nodes(b, pat, t, map, *v);
             }
         }
-        _ => unreachable!(),
+        TreeNodeKind::And { left, right } => {
+            create_nodes(b, pat, t, map, *left);
+            create_nodes(b, pat, t, map, *right);
+        }
     }
 }
 
@@ -135,13 +138,12 @@ fn lower_tree_node(
             pat.constant(p_node, *cons);
             pat.node_set_span(p_node, *span);
         }
-        TreeNodeKind::Value(span, Either::Left(val)) => {
+        TreeNodeKind::Value(span, val) => {
             let cl_val = cl_ctx.clause_value(pat, *val);
 
             pat.value(p_node, cl_val);
             pat.node_set_span(p_node, *span);
         }
-        TreeNodeKind::Value(_, Either::Right(_node)) => unimplemented!(),
         TreeNodeKind::Wildcard(_) => {
             pat.wildcard(p_node);
         }
@@ -190,7 +192,11 @@ fn lower_tree_node(
             }
             pat.node_set_span(p_node, *span);
         }
-        _ => unreachable!(),
+        TreeNodeKind::And { left, right } => {
+            let left_n = lower_tree_node(b, pat, cl_ctx, map, t, *left);
+            let right_n = lower_tree_node(b, pat, cl_ctx, map, t, *right);
+            pat.and(p_node, left_n, right_n);
+        }
     }
 
     p_node
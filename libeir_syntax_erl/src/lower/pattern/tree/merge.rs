@@ -2,7 +2,9 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use libeir_ir::FunctionBuilder;
+use either::Either;
+
+use libeir_ir::{AtomicTerm, BinaryEntrySpecifier, Const, ConstKind, FunctionBuilder, Value};
 
 use cranelift_entity::EntityList;
 
@@ -168,7 +170,15 @@ fn merge_nodes(
                 tail: t_m,
             })
         }
-        (TreeNodeKind::Binary { .. }, TreeNodeKind::Binary { .. }) => unimplemented!(),
+        (TreeNodeKind::Binary { .. }, TreeNodeKind::Binary { .. }) => {
+            merge_binary(ctx, b, t, left, right)
+        }
+        (TreeNodeKind::Binary { .. }, TreeNodeKind::Atomic(_, c))
+        | (TreeNodeKind::Atomic(_, c), TreeNodeKind::Binary { .. })
+            if binary_const_len(b, c).is_some() =>
+        {
+            merge_binary(ctx, b, t, left, right)
+        }
         (TreeNodeKind::Map { entries: e_l, span }, TreeNodeKind::Map { entries: e_r, .. }) => {
             // Entries vectors should already be sorted
             debug_assert!(e_l.windows(2).all(|w| w[0].0 < w[1].0));
@@ -211,7 +221,11 @@ fn merge_nodes(
 
             t.nodes.push(TreeNodeKind::Map { span, entries: new })
         }
-        (TreeNodeKind::And { .. }, TreeNodeKind::And { .. }) => unreachable!(),
+        // One of the sides could not be merged into a single pattern,
+        // match the value against both of them in turn.
+        (TreeNodeKind::And { .. }, _) | (_, TreeNodeKind::And { .. }) => {
+            t.nodes.push(TreeNodeKind::And { left, right })
+        }
         _ => {
             ctx.warn(LowerError::DisjointPatternUnionWarning {
                 left: Some(t.node_span(left)),
@@ -227,3 +241,198 @@ fn merge_nodes(
 
     new
 }
+
+/// Merges two nodes of binary segment chains.
+///
+/// Segments are unified one by one for as long as their specifiers and
+/// sizes are identical. Past that point, a trailing unsized binary or
+/// bitstring segment on one side can still match whatever remains of
+/// the other side, so the other side is matched first and the segment
+/// value is bound to the rest of the binary. Anything else is left as
+/// an `And` node, and the value is matched against both sides in turn.
+fn merge_binary(
+    ctx: &mut LowerCtx,
+    b: &mut FunctionBuilder,
+    t: &mut Tree,
+    left: TreeNode,
+    right: TreeNode,
+) -> TreeNode {
+    if let (
+        TreeNodeKind::Binary {
+            span,
+            specifier: spec_l,
+            size: size_l,
+            value: value_l,
+            tail: tail_l,
+            ..
+        },
+        TreeNodeKind::Binary {
+            specifier: spec_r,
+            size: size_r,
+            value: value_r,
+            tail: tail_r,
+            ..
+        },
+    ) = (t.nodes[left].clone(), t.nodes[right].clone())
+    {
+        if spec_l == spec_r && size_l == size_r {
+            let value = merge_nodes(ctx, b, t, value_l, value_r);
+            let tail = merge_nodes(ctx, b, t, tail_l, tail_r);
+            return t.nodes.push(TreeNodeKind::Binary {
+                span,
+                specifier: spec_l,
+                size: size_l,
+                size_resolved: None,
+                value,
+                tail,
+            });
+        }
+    }
+
+    if let Some(value) = rest_segment_value(b, t, left, right) {
+        return merge_nodes(ctx, b, t, value, right);
+    }
+    if let Some(value) = rest_segment_value(b, t, right, left) {
+        return merge_nodes(ctx, b, t, left, value);
+    }
+
+    let l_span = t.node_span(left);
+    let r_span = t.node_span(right);
+
+    if (is_empty_binary(b, t, left) && matches_nonempty(b, t, right))
+        || (is_empty_binary(b, t, right) && matches_nonempty(b, t, left))
+    {
+        ctx.warn(LowerError::DisjointPatternUnionWarning {
+            left: Some(l_span),
+            right: Some(r_span),
+        });
+        t.unmatchable = true;
+        return t.nodes.push(TreeNodeKind::And { left, right });
+    }
+
+    t.nodes.push(TreeNodeKind::And { left, right })
+}
+
+/// If `rest` is a trailing unsized binary or bitstring segment that
+/// is able to match all of `other`, returns the value node of the segment.
+fn rest_segment_value(
+    b: &FunctionBuilder,
+    t: &Tree,
+    rest: TreeNode,
+    other: TreeNode,
+) -> Option<TreeNode> {
+    let (value, unit) = match &t.nodes[rest] {
+        TreeNodeKind::Binary {
+            specifier,
+            size: None,
+            value,
+            tail,
+            ..
+        } if is_empty_binary(b, t, *tail) => match specifier {
+            BinaryEntrySpecifier::Bytes { unit } | BinaryEntrySpecifier::Bits { unit }
+                if *unit > 0 =>
+            {
+                (*value, *unit)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    if chain_bits_mod(b, t, other, unit) == Some(0) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// The number of bits matched by the segment chain starting at `node`,
+/// modulo `unit`. `None` if this is not known at compile time.
+fn chain_bits_mod(b: &FunctionBuilder, t: &Tree, node: TreeNode, unit: i64) -> Option<i64> {
+    // Segments that match some multiple of `bits` bits.
+    let multiple_of = |bits: i64| if bits % unit == 0 { Some(0) } else { None };
+
+    match &t.nodes[node] {
+        TreeNodeKind::Atomic(_, cons) => {
+            binary_const_len(b, *cons).map(|len| (len as i64 * 8) % unit)
+        }
+        TreeNodeKind::Binary {
+            specifier,
+            size,
+            tail,
+            ..
+        } => {
+            let segment = match (specifier, size) {
+                (BinaryEntrySpecifier::Utf8, _) => multiple_of(8)?,
+                (BinaryEntrySpecifier::Utf16 { .. }, _) => multiple_of(16)?,
+                (BinaryEntrySpecifier::Utf32 { .. }, _) => multiple_of(32)?,
+                (spec, None) => multiple_of(specifier_unit(spec)?)?,
+                (spec, Some(Either::Right(val))) => {
+                    (const_int(b, *val)? * specifier_unit(spec)?) % unit
+                }
+                (_, Some(Either::Left(_))) => return None,
+            };
+            Some((segment + chain_bits_mod(b, t, *tail, unit)?) % unit)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the segment at `node` always matches at least one bit.
+fn matches_nonempty(b: &FunctionBuilder, t: &Tree, node: TreeNode) -> bool {
+    match &t.nodes[node] {
+        TreeNodeKind::Binary {
+            specifier: BinaryEntrySpecifier::Utf8,
+            ..
+        }
+        | TreeNodeKind::Binary {
+            specifier: BinaryEntrySpecifier::Utf16 { .. },
+            ..
+        }
+        | TreeNodeKind::Binary {
+            specifier: BinaryEntrySpecifier::Utf32 { .. },
+            ..
+        } => true,
+        TreeNodeKind::Binary {
+            specifier,
+            size: Some(Either::Right(val)),
+            ..
+        } => match (const_int(b, *val), specifier_unit(specifier)) {
+            (Some(size), Some(unit)) => size * unit > 0,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_empty_binary(b: &FunctionBuilder, t: &Tree, node: TreeNode) -> bool {
+    match &t.nodes[node] {
+        TreeNodeKind::Atomic(_, cons) => binary_const_len(b, *cons) == Some(0),
+        _ => false,
+    }
+}
+
+fn specifier_unit(spec: &BinaryEntrySpecifier) -> Option<i64> {
+    match spec {
+        BinaryEntrySpecifier::Integer { unit, .. } => Some(*unit),
+        BinaryEntrySpecifier::Float { unit, .. } => Some(*unit),
+        BinaryEntrySpecifier::Bytes { unit } => Some(*unit),
+        BinaryEntrySpecifier::Bits { unit } => Some(*unit),
+        _ => None,
+    }
+}
+
+fn binary_const_len(b: &FunctionBuilder, cons: Const) -> Option<usize> {
+    match b.cons().const_kind(cons) {
+        ConstKind::Atomic(AtomicTerm::Binary(bin)) => Some(bin.value().len()),
+        _ => None,
+    }
+}
+
+fn const_int(b: &FunctionBuilder, val: Value) -> Option<i64> {
+    let cons = b.fun().value_const(val)?;
+    match b.cons().const_kind(cons) {
+        ConstKind::Atomic(AtomicTerm::Int(int)) => Some(int.value()),
+        _ => None,
+    }
+}
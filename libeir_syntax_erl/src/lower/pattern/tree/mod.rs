@@ -94,7 +94,7 @@ pub(crate) enum TreeNodeKind {
     Atomic(SourceSpan, Const),
 
     // Promoted to in a late pass.
-    Value(SourceSpan, Either<IrValue, TreeNode>),
+    Value(SourceSpan, IrValue),
 
     Wildcard(SourceSpan),
     Tuple {
@@ -188,12 +188,12 @@ fn promote_values_node(
             // amount of information.
             if let Some(prim) = constraints.iter().flat_map(|c| c.primop()).nth(0) {
                 let val = b.value(prim);
-                t.nodes[node] = TreeNodeKind::Value(span, Either::Left(val));
+                t.nodes[node] = TreeNodeKind::Value(span, val);
                 return;
             }
             // Third choice is any other value
             if let Some(val) = constraints.iter().flat_map(|c| c.value()).nth(0) {
-                t.nodes[node] = TreeNodeKind::Value(span, Either::Left(val));
+                t.nodes[node] = TreeNodeKind::Value(span, val);
                 return;
             }
         }
@@ -259,10 +259,9 @@ fn promote_values_node(
             }
         }
 
-        // While this cannot occur in a matchable pattern, we still
-        // need to resolve scoping, so we handle it.
+        // Left when two binary patterns can not be merged, or when the
+        // pattern is unmatchable.
         TreeNodeKind::And { left, right } => {
-            assert!(t.unmatchable);
             process_constants_node(b, prom, t, left, false);
             process_constants_node(b, prom, t, right, false);
 
@@ -6,9 +6,9 @@ use crate::*;
 use crate::lower::lower_module;
 use crate::parser::ParseConfig;
 
-use libeir_diagnostics::CodeMap;
+use libeir_diagnostics::{CodeMap, SourceSpan};
 use libeir_ir::{Module as IrModule, StandardFormatConfig};
-use libeir_util_parse::Errors;
+use libeir_util_parse::{ErrorOrWarning, Errors};
 
 fn parse<T, S>(input: S, config: ParseConfig, codemap: Arc<CodeMap>) -> T
 where
@@ -36,6 +36,33 @@ fn lower(input: &str, config: ParseConfig) -> Result<IrModule, ()> {
     res
 }
 
+/// Lowers the module, returning the reported errors together with the
+/// codemap their spans point into.
+fn lower_errors(input: &str) -> (Arc<CodeMap>, Vec<LowerError>) {
+    let codemap = Arc::new(CodeMap::new());
+    let parsed: Module = parse(input, ParseConfig::default(), codemap.clone());
+
+    let mut errors: Errors<LowerError, LowerError> = Errors::new();
+    assert!(lower_module(&mut errors, codemap.clone(), &parsed).is_err());
+
+    let errors = errors
+        .errors
+        .into_iter()
+        .filter_map(|err| match err {
+            ErrorOrWarning::Error(err) => Some(err),
+            ErrorOrWarning::Warning(_) => None,
+        })
+        .collect();
+    (codemap, errors)
+}
+
+fn span_text(codemap: &CodeMap, span: SourceSpan) -> &str {
+    codemap
+        .source_slice(span.source_id(), span.as_span())
+        .unwrap()
+        .unwrap()
+}
+
 #[test]
 fn fib_lower() {
     let _result = lower(
@@ -62,6 +89,97 @@ pat(A, A) -> 1.
     println!("{}", fun.to_text(&mut StandardFormatConfig::default()));
 }
 
+#[test]
+fn module_attributes_lower() {
+    let module = lower(
+        "-module(attrs).
+-export([foo/1]).
+-on_load(init/0).
+-vsn(\"1.0\").
+-behaviour(gen_server).
+-my_attr({a, [1, 2]}).
+
+init() -> ok.
+foo(A) -> A.
+",
+        ParseConfig::default(),
+    )
+    .unwrap();
+
+    let foo = module.name_arity_index(Symbol::intern("foo"), 1).unwrap();
+    let init = module.name_arity_index(Symbol::intern("init"), 0).unwrap();
+    assert!(module[foo].is_exported());
+    assert!(!module[init].is_exported());
+    assert!(module.on_load() == Some(init));
+
+    let names: Vec<_> = module.attribute_iter().map(|a| a.name.name).collect();
+    assert!(
+        names
+            == vec![
+                Symbol::intern("vsn"),
+                Symbol::intern("behaviour"),
+                Symbol::intern("my_attr"),
+            ]
+    );
+
+    println!("{}", module.to_text(&mut StandardFormatConfig::default()));
+}
+
+#[test]
+fn undefined_record_diagnostics() {
+    let (codemap, errors) = lower_errors(
+        "-module(woo).
+
+-record(person, {name, phone, address}).
+
+get_1(A) -> A#person.age.
+get_2(A) -> A#animal.name.
+set_1(A) -> A#person{phone = 12, age = 12}.
+match_1(#person{age = A}) -> A.
+",
+    );
+
+    let mut reported: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            LowerError::UndefinedRecord { span } => ("record", span_text(&codemap, *span)),
+            LowerError::UndefinedRecordField { span } => ("field", span_text(&codemap, *span)),
+            err => panic!("unexpected error: {}", err),
+        })
+        .collect();
+    reported.sort();
+    assert_eq!(
+        reported,
+        vec![
+            ("field", "age"),
+            ("field", "age"),
+            ("field", "age"),
+            ("record", "animal"),
+        ]
+    );
+}
+
+#[test]
+fn not_allowed_in_pattern_diagnostics() {
+    let (codemap, errors) = lower_errors(
+        "-module(woo).
+
+foo(A + 1) -> ok.
+bar([H | T * 2]) -> H.
+",
+    );
+
+    let mut reported: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            LowerError::NotAllowedInPattern { span } => span_text(&codemap, *span),
+            err => panic!("unexpected error: {}", err),
+        })
+        .collect();
+    reported.sort();
+    assert_eq!(reported, vec!["A + 1", "T * 2"]);
+}
+
 //#[test]
 //fn compiler_lower() {
 //    let mut config = ParseConfig::default();
@@ -1075,6 +1075,8 @@ pub struct CompileOptions {
     pub warn_missing_spec: bool,
     // Inlines the given functions
     pub inline_functions: HashSet<ResolvedFunctionName>,
+    // The option terms as written, in order of declaration
+    pub terms: Vec<Expr>,
 }
 impl Default for CompileOptions {
     fn default() -> Self {
@@ -1096,6 +1098,7 @@ impl Default for CompileOptions {
             warn_unused_record: true,
             warn_missing_spec: false,
             inline_functions: HashSet::new(),
+            terms: Vec::new(),
         }
     }
 }
@@ -1109,6 +1112,7 @@ impl CompileOptions {
     }
 
     pub fn merge_from_expr(&mut self, module: &Ident, expr: &Expr) -> Result<(), Vec<Diagnostic>> {
+        self.terms.push(expr.clone());
         self.set_option(module, expr)
     }
 
@@ -0,0 +1,285 @@
+use crate::{atom, ident, vm_with_module};
+
+use libeir_interpreter::{ErlEq, Term};
+
+const BIFS: &str = "
+-module(bifs).
+
+big() -> 16#FFFFFFFFFFFFFFFF * 16#FFFFFFFFFFFFFFFF.
+bits() -> {1 bsl 70, (1 bsl 70) bsr 69, -8 bsr 1, 2#1100 band 2#1010,
+           2#1100 bor 2#1010}.
+
+int_div(A, B) -> {A div B, A rem B}.
+div_zero() ->
+    try 1 div 0
+    catch error:R -> R
+    end.
+
+compare() ->
+    {1 < a, a < {}, {2} < {1, 1}, [] < [1], 1 == 1.0, 1 =:= 1.0,
+     1 /= 1.0, 2 > 1.5, abc < abd, #{a => 1} < #{a => 2}}.
+
+convert() ->
+    {list_to_atom(\"hello\"), integer_to_list(-123), tuple_to_list({a, b}),
+     list_to_tuple([a, b]), float(2), round(2.5), trunc(-2.5)}.
+
+types() ->
+    {is_float(1.0), is_float(1), is_number(1), is_number(a),
+     is_boolean(true), is_boolean(a)}.
+
+append() -> [1, 2] ++ [3].
+
+binaries() ->
+    {byte_size(<<1, 2, 3>>), bit_size(<<1:4>>), byte_size(<<1:4>>),
+     binary_to_list(<<1, 2, 3>>)}.
+
+catch_all(Fun) ->
+    try Fun()
+    catch Type:Reason -> {Type, Reason}
+    end.
+exceptions() ->
+    {catch_all(fun() -> throw(a) end),
+     catch_all(fun() -> error(b) end),
+     catch_all(fun() -> exit(c) end)}.
+
+add(A, B) -> A + B.
+apply_funs() ->
+    {apply(fun add/2, [1, 2]), apply(bifs, add, [3, 4])}.
+
+refs() -> make_ref() == make_ref().
+
+binary_bifs() ->
+    {binary_part(<<\"hello\">>, 1, 3) =:= <<\"ell\">>,
+     binary_part(<<\"hello\">>, {5, -2}) =:= <<\"lo\">>,
+     split_binary(<<1, 2, 3>>, 1) =:= {<<1>>, <<2, 3>>},
+     list_to_binary([1, [2, <<3, 4>>], <<>> | <<5>>]) =:= <<1, 2, 3, 4, 5>>,
+     iolist_to_binary(<<1, 2>>) =:= <<1, 2>>,
+     iolist_size([1, [2, <<3, 4>>]]) =:= 4,
+     binary_to_integer(<<\"-123\">>) =:= -123,
+     binary_part(binary_part(<<\"abcdef\">>, 1, 4), 1, 2) =:= <<\"cd\">>}.
+
+binary_module() ->
+    {binary:match(<<\"abcabc\">>, <<\"ca\">>) =:= {2, 2},
+     binary:match(<<\"abc\">>, [<<\"b\">>, <<\"bc\">>]) =:= {1, 2},
+     binary:match(<<\"abc\">>, <<\"d\">>) =:= nomatch,
+     binary:split(<<\"a,b,c\">>, <<\",\">>) =:= [<<\"a\">>, <<\"b,c\">>],
+     binary:split(<<\"a,b,,\">>, <<\",\">>, [global, trim]) =:= [<<\"a\">>, <<\"b\">>],
+     binary:part(<<\"hello\">>, 0, 2) =:= <<\"he\">>,
+     binary:copy(<<1, 2>>, 2) =:= <<1, 2, 1, 2>>,
+     binary:at(<<1, 2, 3>>, 1) =:= 2,
+     binary:first(<<1, 2, 3>>) =:= 1,
+     binary:last(<<1, 2, 3>>) =:= 3,
+     binary:decode_unsigned(<<1, 0>>) =:= 256,
+     binary:decode_unsigned(<<1, 0>>, little) =:= 1,
+     binary:encode_unsigned(256) =:= <<1, 0>>,
+     binary:encode_unsigned(256, little) =:= <<0, 1>>,
+     binary:encode_unsigned(0) =:= <<0>>}.
+
+bad_binary_part() ->
+    try binary_part(<<1, 2>>, 1, 2)
+    catch error:R -> R
+    end.
+
+operators() ->
+    {(2#1100 bxor 2#1010) =:= 2#0110,
+     bnot 5 =:= -6,
+     (true xor false) =:= true,
+     (true xor true) =:= false,
+     ?FUNCTION_NAME =:= operators,
+     ?FUNCTION_ARITY =:= 0,
+     (fun() -> ?FUNCTION_NAME end)() =:= operators}.
+";
+
+#[test]
+fn bigint_promotion() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "big", 0), &[]).unwrap();
+    let expected = (2u128.pow(64) - 1).pow(2);
+    assert!(ret.as_integer().unwrap().to_string() == expected.to_string());
+
+    let ret = vm.call(&ident("bifs", "bits", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_integer().unwrap().to_string() == (1u128 << 70).to_string());
+    assert!(ret[1].as_i64() == Some(2));
+    assert!(ret[2].as_i64() == Some(-4));
+    assert!(ret[3].as_i64() == Some(0b1000));
+    assert!(ret[4].as_i64() == Some(0b1110));
+}
+
+#[test]
+fn integer_division() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    // Both round towards zero, the remainder takes the sign of the dividend
+    let cases = [
+        (7, 2, 3, 1),
+        (-7, 2, -3, -1),
+        (7, -2, -3, 1),
+        (-7, -2, 3, -1),
+    ];
+    for (a, b, div, rem) in cases.iter() {
+        let ret = vm
+            .call(
+                &ident("bifs", "int_div", 2),
+                &[Term::new_i64(*a), Term::new_i64(*b)],
+            )
+            .unwrap();
+        let ret = ret.as_tuple().unwrap();
+        assert!(ret[0].as_i64() == Some(*div));
+        assert!(ret[1].as_i64() == Some(*rem));
+    }
+
+    let ret = vm.call(&ident("bifs", "div_zero", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&atom("badarith")));
+}
+
+#[test]
+fn term_order() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "compare", 0), &[]).unwrap();
+    let ret: Vec<_> = ret
+        .as_tuple()
+        .unwrap()
+        .iter()
+        .map(|t| t.as_boolean().unwrap())
+        .collect();
+    assert!(ret == [true, true, true, true, true, false, false, true, true, true]);
+}
+
+#[test]
+fn conversions() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "convert", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].erl_eq(&atom("hello")));
+    let chars: Vec<_> = Term::as_list(&ret[1])
+        .unwrap()
+        .iter()
+        .map(|c| c.as_i64().unwrap() as u8)
+        .collect();
+    assert!(chars == b"-123");
+    assert!(ret[2].erl_eq(&*Term::slice_to_list(
+        &[atom("a").into(), atom("b").into()],
+        Term::Nil.into()
+    )));
+    assert!(ret[3].erl_eq(&Term::Tuple(vec![atom("a").into(), atom("b").into()])));
+    assert!(ret[4].erl_eq(&Term::Float(2.0.into())));
+    assert!(ret[5].as_i64() == Some(3));
+    assert!(ret[6].as_i64() == Some(-2));
+}
+
+#[test]
+fn type_tests() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "types", 0), &[]).unwrap();
+    let ret: Vec<_> = ret
+        .as_tuple()
+        .unwrap()
+        .iter()
+        .map(|t| t.as_boolean().unwrap())
+        .collect();
+    assert!(ret == [true, false, true, false, true, false]);
+}
+
+#[test]
+fn list_append() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "append", 0), &[]).unwrap();
+    let items: Vec<_> = Term::as_list(&ret)
+        .unwrap()
+        .iter()
+        .map(|t| t.as_i64().unwrap())
+        .collect();
+    assert!(items == [1, 2, 3]);
+}
+
+#[test]
+fn binary_sizes() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "binaries", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_i64() == Some(3));
+    assert!(ret[1].as_i64() == Some(4));
+    assert!(ret[2].as_i64() == Some(1));
+    let bytes: Vec<_> = Term::as_list(&ret[3])
+        .unwrap()
+        .iter()
+        .map(|t| t.as_i64().unwrap())
+        .collect();
+    assert!(bytes == [1, 2, 3]);
+}
+
+#[test]
+fn exceptions() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "exceptions", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    for (caught, (typ, reason)) in ret
+        .iter()
+        .zip([("throw", "a"), ("error", "b"), ("exit", "c")].iter())
+    {
+        let caught = caught.as_tuple().unwrap();
+        assert!(caught[0].erl_eq(&atom(typ)));
+        assert!(caught[1].erl_eq(&atom(reason)));
+    }
+}
+
+#[test]
+fn apply() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "apply_funs", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_i64() == Some(3));
+    assert!(ret[1].as_i64() == Some(7));
+
+    let ret = vm.call(&ident("bifs", "refs", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&atom("false")));
+}
+
+fn all_true(term: &Term) -> bool {
+    term.as_tuple()
+        .unwrap()
+        .iter()
+        .all(|t| t.as_boolean() == Some(true))
+}
+
+#[test]
+fn binaries() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "binary_bifs", 0), &[]).unwrap();
+    assert!(all_true(&ret), "{:?}", ret);
+
+    let ret = vm.call(&ident("bifs", "binary_module", 0), &[]).unwrap();
+    assert!(all_true(&ret), "{:?}", ret);
+
+    let ret = vm.call(&ident("bifs", "bad_binary_part", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&atom("badarg")));
+}
+
+#[test]
+fn operators() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(BIFS);
+
+    let ret = vm.call(&ident("bifs", "operators", 0), &[]).unwrap();
+    assert!(all_true(&ret), "{:?}", ret);
+}
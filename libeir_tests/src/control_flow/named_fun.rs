@@ -0,0 +1,73 @@
+use crate::{ident, vm_with_module};
+
+use libeir_interpreter::{ErlEq, Term};
+
+const NAMED_FUN: &str = "
+-module(named_fun).
+
+fact(N) ->
+    F = fun Fact(0) -> 1;
+            Fact(X) -> X * Fact(X - 1)
+        end,
+    F(N).
+
+count_down(N) ->
+    Loop = fun L(0, Acc) -> Acc;
+               L(X, Acc) -> L(X - 1, [X | Acc])
+           end,
+    Loop(N, []).
+
+captures(Step, N) ->
+    Sum = fun S(X) when X > N -> 0;
+              S(X) -> X + S(X + Step)
+          end,
+    Sum(0).
+
+returns_self() ->
+    F = fun Self(0) -> Self;
+            Self(X) -> X
+        end,
+    G = F(0),
+    G(42).
+";
+
+#[test]
+fn named_fun_recursion() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(NAMED_FUN);
+
+    let ret = vm
+        .call(&ident("named_fun", "fact", 1), &[Term::new_i64(5)])
+        .unwrap();
+    assert!(ret.as_i64() == Some(120));
+
+    // Tail recursion through the name
+    let ret = vm
+        .call(&ident("named_fun", "count_down", 1), &[Term::new_i64(3)])
+        .unwrap();
+    let list = Term::as_list(&ret).unwrap();
+    assert!(list.len() == 3);
+    assert!(list[0].as_i64() == Some(1));
+    assert!(list[2].as_i64() == Some(3));
+}
+
+#[test]
+fn named_fun_environment() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(NAMED_FUN);
+
+    // Variables captured by the fun are available in every recursive call
+    let ret = vm
+        .call(
+            &ident("named_fun", "captures", 2),
+            &[Term::new_i64(2), Term::new_i64(6)],
+        )
+        .unwrap();
+    assert!(ret.as_i64() == Some(12));
+
+    // The name can escape the fun like any other value
+    let ret = vm
+        .call(&ident("named_fun", "returns_self", 0), &[])
+        .unwrap();
+    assert!(ret.erl_eq(&Term::new_i64(42)));
+}
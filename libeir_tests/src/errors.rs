@@ -1,17 +1,16 @@
-use super::lower;
+use std::rc::Rc;
 
-use libeir_intern::{Ident, Symbol};
-use libeir_ir::FunctionIdent;
-use libeir_passes::PassManager;
-use libeir_syntax_erl::ParseConfig;
+use super::{ident, vm_with_module};
 
-use libeir_interpreter::{Term, VMState};
+use libeir_intern::Symbol;
+
+use libeir_interpreter::Term;
 
 #[test]
 fn test_basic_catch() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
@@ -22,22 +21,9 @@ woo(A) -> try foo(A) catch
         true
 end.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(vm.call(&fun, &[1.into()]).unwrap().as_boolean() == Some(true));
     assert!(
@@ -52,7 +38,7 @@ end.
 fn test_basic_catch_miss() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
@@ -63,22 +49,9 @@ woo(A) -> try foo(A) catch
         true
 end.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(
         vm.call(&fun, &[Term::Atom(Symbol::intern("foo")).into()])
@@ -88,3 +61,88 @@ end.
     );
     assert!(vm.call(&fun, &[1.into()]).is_err());
 }
+
+#[test]
+fn test_stack_trace() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(woo).
+
+inner(X) ->
+    X + 1.
+
+middle(X) ->
+    Y = inner(X),
+    {ok, Y}.
+
+outer(X) ->
+    try middle(X)
+    catch error:badarith:St -> St
+    end.
+
+old_catch(X) ->
+    catch middle(X).
+",
+    );
+
+    // Checks that the trace starts with `inner` called from `middle`
+    let check_trace = |trace: &Rc<Term>| {
+        let frames = Term::as_list(trace).unwrap();
+        let expected = [("inner", 5), ("middle", 8)];
+        for (frame, (name, line)) in frames.iter().zip(expected.iter()) {
+            let frame = frame.as_tuple().unwrap();
+            assert!(frame[0].as_atom() == Some(Symbol::intern("woo")));
+            assert!(frame[1].as_atom() == Some(Symbol::intern(name)));
+            assert!(frame[2].as_i64() == Some(1));
+
+            let info = Term::as_list(&frame[3]).unwrap();
+            let line_info = info
+                .iter()
+                .map(|i| i.as_tuple().unwrap())
+                .find(|i| i[0].as_atom() == Some(Symbol::intern("line")))
+                .unwrap();
+            assert!(line_info[1].as_i64() == Some(*line));
+        }
+    };
+
+    let arg = Term::new_atom("a");
+
+    let trace = vm.call(&ident("woo", "outer", 1), &[arg.clone()]).unwrap();
+    check_trace(&trace);
+
+    let ret = vm
+        .call(&ident("woo", "old_catch", 1), &[arg.clone()])
+        .unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_atom() == Some(Symbol::intern("EXIT")));
+    check_trace(&ret[1].as_tuple().unwrap()[1]);
+
+    // Uncaught errors carry the constructed trace too
+    let (_typ, _reason, trace) = vm.call(&ident("woo", "middle", 1), &[arg]).unwrap_err();
+    check_trace(&trace);
+}
+
+#[test]
+fn test_undef() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(woo).
+
+missing_module() -> try nomod:nofun() catch error:R -> R end.
+missing_function() -> try lists:nofun() catch error:R -> R end.
+",
+    );
+
+    assert!(!vm.function_exists(&ident("woo", "nofun", 0)));
+    assert!(vm.function_exists(&ident("woo", "missing_module", 0)));
+
+    let ret = vm.call(&ident("woo", "missing_module", 0), &[]).unwrap();
+    assert!(ret.as_atom() == Some(Symbol::intern("undef")));
+
+    let ret = vm.call(&ident("woo", "missing_function", 0), &[]).unwrap();
+    assert!(ret.as_atom() == Some(Symbol::intern("undef")));
+}
@@ -0,0 +1,197 @@
+use std::rc::Rc;
+
+use crate::{ident, vm_with_module};
+
+use libeir_intern::Symbol;
+
+use libeir_interpreter::etf::{decode, encode};
+use libeir_interpreter::{ErlEq, MapTerm, Pid, Reference, Term};
+
+/// Small deterministic xorshift generator, the tests need to be
+/// reproducible more than they need good randomness.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn bytes(&mut self, max_len: u64) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+const ATOMS: &[&str] = &["", "ok", "error", "Ünïcödé", "nonode@nohost", "with space"];
+
+fn random_term(rng: &mut Rng, depth: usize) -> Rc<Term> {
+    let max_kind = if depth == 0 { 9 } else { 13 };
+    let term = match rng.below(max_kind) {
+        0 => Term::Nil,
+        1 => Term::new_i64(rng.below(256) as i64),
+        2 => Term::new_i64(rng.next() as i64 >> rng.below(64)),
+        3 => {
+            let digits: String = (0..1 + rng.below(60))
+                .map(|_| (b'0' + rng.below(10) as u8) as char)
+                .collect();
+            let sign = if rng.below(2) == 0 { "-" } else { "" };
+            Term::Integer(format!("{}1{}", sign, digits).parse().unwrap())
+        }
+        4 => Term::Float(((rng.next() as i64) as f64 / 1000.0).into()),
+        5 => Term::new_atom(ATOMS[rng.below(ATOMS.len() as u64) as usize]),
+        6 => Term::Binary(Rc::new(rng.bytes(16).into())),
+        7 => {
+            let bytes = rng.bytes(8);
+            let bit_len = bytes.len() * 8;
+            let bit_offset = rng.below(bit_len as u64 + 1) as usize;
+            let bit_length = rng.below((bit_len - bit_offset) as u64 + 1) as usize;
+            Term::BinarySlice {
+                buf: Rc::new(bytes.into()),
+                bit_offset,
+                bit_length,
+            }
+        }
+        8 => match rng.below(3) {
+            0 => Term::Pid(Pid(rng.below(1 << 20) as usize)),
+            1 => Term::Reference(Reference(rng.below(1 << 40) as usize)),
+            _ => Term::CapturedFunction {
+                ident: ident("lists", "map", rng.below(256) as usize),
+            },
+        },
+        9 | 10 => {
+            // Large enough to need the large tuple encoding now and then
+            let len = if depth == 1 && rng.below(8) == 0 {
+                300
+            } else {
+                rng.below(5)
+            };
+            let elems = (0..len).map(|_| random_term(rng, depth - 1)).collect();
+            Term::Tuple(elems)
+        }
+        11 => {
+            let elems: Vec<_> = (0..rng.below(5))
+                .map(|_| random_term(rng, depth - 1))
+                .collect();
+            let tail = if rng.below(4) == 0 {
+                random_term(rng, 0)
+            } else {
+                Term::Nil.into()
+            };
+            return Term::slice_to_list(&elems, tail);
+        }
+        _ => {
+            let mut map = MapTerm::new();
+            for _ in 0..rng.below(5) {
+                map.insert(random_term(rng, depth - 1), random_term(rng, depth - 1));
+            }
+            Term::Map(map)
+        }
+    };
+    term.into()
+}
+
+#[test]
+fn round_trip_random_terms() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let term = random_term(&mut rng, 3);
+        let bytes = encode(&term).unwrap();
+        let decoded = decode(&bytes).unwrap();
+        assert!(term == decoded, "{:?} != {:?}", term, decoded);
+    }
+}
+
+#[test]
+fn decode_garbage() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    // Random data, and valid encodings with bytes flipped or cut off, can
+    // fail to decode but must never panic.
+    for _ in 0..2000 {
+        let mut bytes = rng.bytes(32);
+        bytes.insert(0, 131);
+        let _ = decode(&bytes);
+
+        let term = random_term(&mut rng, 2);
+        let mut bytes = encode(&term).unwrap();
+        let idx = rng.below(bytes.len() as u64) as usize;
+        bytes[idx] = rng.next() as u8;
+        let _ = decode(&bytes);
+        bytes.truncate(idx);
+        let _ = decode(&bytes);
+    }
+}
+
+#[test]
+fn known_encodings() {
+    let atom: Rc<Term> = Term::new_atom("foo").into();
+    assert!(encode(&atom).unwrap() == [131, 119, 3, b'f', b'o', b'o']);
+
+    // Latin-1 atoms from older nodes
+    let latin1 = decode(&[131, 100, 0, 1, 0xe9]).unwrap();
+    assert!(latin1.as_atom() == Some(Symbol::intern("é")));
+
+    let string = Term::slice_to_list(
+        &[Term::new_i64(1).into(), Term::new_i64(2).into()],
+        Term::Nil.into(),
+    );
+    assert!(encode(&string).unwrap() == [131, 107, 0, 2, 1, 2]);
+
+    let int: Rc<Term> = Term::new_i64(-1).into();
+    assert!(encode(&int).unwrap() == [131, 98, 255, 255, 255, 255]);
+
+    let big: Rc<Term> = Term::new_i64(1 << 40).into();
+    assert!(encode(&big).unwrap() == [131, 110, 6, 0, 0, 0, 0, 0, 0, 1]);
+
+    let bits: Rc<Term> = Term::BinarySlice {
+        buf: Rc::new(vec![0b1010_1111].into()),
+        bit_offset: 0,
+        bit_length: 3,
+    }
+    .into();
+    assert!(encode(&bits).unwrap() == [131, 77, 0, 0, 0, 1, 3, 0b1010_0000]);
+
+    assert!(decode(&[131]).is_err());
+    assert!(decode(&[130, 106]).is_err());
+    assert!(decode(&[131, 80, 0, 0, 0, 0]).is_err());
+}
+
+#[test]
+fn term_to_binary_bifs() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(etf).
+
+round_trip() ->
+    T = {ok, [1, 2 | 3], #{a => <<1, 2:3>>}, 1.5, -123456789012345678901234567890},
+    binary_to_term(term_to_binary(T)) =:= T.
+
+known() -> term_to_binary(foo).
+
+bad() ->
+    try binary_to_term(<<1, 2>>)
+    catch error:R -> R
+    end.
+",
+    );
+
+    let ret = vm.call(&ident("etf", "round_trip", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("true")));
+
+    let ret = vm.call(&ident("etf", "known", 0), &[]).unwrap();
+    let expected: Rc<Term> =
+        Term::Binary(Rc::new(vec![131, 119, 3, b'f', b'o', b'o'].into())).into();
+    assert!(ret == expected);
+
+    let ret = vm.call(&ident("etf", "bad", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("badarg")));
+}
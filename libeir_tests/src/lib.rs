@@ -5,18 +5,26 @@ use std::path::Path;
 use std::sync::Arc;
 
 use libeir_diagnostics::*;
+use libeir_intern::Ident;
+use libeir_interpreter::{Term, VMState};
 use libeir_ir::{FunctionIdent, Module};
+use libeir_passes::PassManager;
 use libeir_syntax_erl::lower_module;
 use libeir_syntax_erl::{ErlangError, Parse, ParseConfig, Parser, ParserError};
 use libeir_util_parse::{error_tee, Errors};
 
+mod bifs;
 mod control_flow;
 mod ct_runner;
 mod errors;
+mod etf;
 mod list_comprehensions;
 mod otp;
 mod patterns;
+mod processes;
 mod records;
+mod stdlib;
+mod text_roundtrip;
 
 fn lower_file<S>(path: S, config: ParseConfig) -> Result<Module, ()>
 where
@@ -54,6 +62,41 @@ where
     eir_res
 }
 
+/// Lowers the module, runs the default passes on it and loads it into a
+/// new VM together with the builtin modules.
+pub fn vm_with_module(source: &str) -> VMState {
+    let mut eir_mod = lower(source, ParseConfig::default()).unwrap();
+    run_passes(&mut eir_mod);
+    vm_with_eir_module(eir_mod)
+}
+
+/// Runs the default passes on the module.
+pub fn run_passes(eir_mod: &mut Module) {
+    let mut pass_manager = PassManager::default();
+    pass_manager.run(eir_mod);
+}
+
+/// Loads an already compiled module into a new VM together with the
+/// builtin modules.
+pub fn vm_with_eir_module(eir_mod: Module) -> VMState {
+    let mut vm = VMState::new();
+    vm.add_builtin_modules();
+    vm.add_erlang_module(eir_mod);
+    vm
+}
+
+pub fn ident(module: &str, name: &str, arity: usize) -> FunctionIdent {
+    FunctionIdent {
+        module: Ident::from_str(module),
+        name: Ident::from_str(name),
+        arity,
+    }
+}
+
+pub fn atom(name: &str) -> Term {
+    Term::new_atom(name)
+}
+
 pub fn write_dot(module: &Module, ident: Option<FunctionIdent>) {
     if let Some(ident) = ident {
         let idx = module.ident_index(&ident).unwrap();
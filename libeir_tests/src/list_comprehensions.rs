@@ -1,13 +1,10 @@
 use std::rc::Rc;
 
-use crate::lower;
+use crate::{ident, lower, run_passes, vm_with_eir_module, vm_with_module};
 
-use libeir_intern::Ident;
-use libeir_ir::FunctionIdent;
-use libeir_passes::PassManager;
 use libeir_syntax_erl::ParseConfig;
 
-use libeir_interpreter::{ErlEq, Term, VMState};
+use libeir_interpreter::{ErlEq, Term};
 
 #[test]
 fn test_list_comprehension_single_filter() {
@@ -30,8 +27,7 @@ woo(N) -> [1 || erlang:is_integer(N)].
         assert!(out.len() == 0);
     }
 
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
+    run_passes(&mut eir_mod);
 
     for fun_def in eir_mod.function_iter() {
         let fun = fun_def.function();
@@ -40,17 +36,11 @@ woo(N) -> [1 || erlang:is_integer(N)].
         assert!(out.len() == 0);
     }
 
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    let fun = ident("woo", "woo", 1);
 
     println!("{}", eir_mod.to_text_standard());
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let mut vm = vm_with_eir_module(eir_mod);
 
     assert!(vm.call(&fun, &[Term::Nil]).unwrap().erl_eq(&Term::Nil));
 
@@ -84,8 +74,7 @@ woo(L) -> [X*2 || X <- L].
         assert!(out.len() == 0);
     }
 
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
+    run_passes(&mut eir_mod);
 
     for fun_def in eir_mod.function_iter() {
         let fun = fun_def.function();
@@ -94,17 +83,11 @@ woo(L) -> [X*2 || X <- L].
         assert!(out.len() == 0);
     }
 
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    let fun = ident("woo", "woo", 1);
 
     println!("{}", eir_mod.to_text_standard());
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let mut vm = vm_with_eir_module(eir_mod);
 
     assert!(vm.call(&fun, &[Term::Nil]).unwrap().erl_eq(&Term::Nil));
 
@@ -163,8 +146,7 @@ comb(L) -> [[A, B] || A <- L, B <- L].
         assert!(out.len() == 0);
     }
 
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
+    run_passes(&mut eir_mod);
 
     for fun_def in eir_mod.function_iter() {
         let fun = fun_def.function();
@@ -174,15 +156,9 @@ comb(L) -> [[A, B] || A <- L, B <- L].
         assert!(out.len() == 0);
     }
 
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("comb"),
-        arity: 1,
-    };
+    let fun = ident("woo", "comb", 1);
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let mut vm = vm_with_eir_module(eir_mod);
 
     {
         let arg = Term::slice_to_list(
@@ -238,8 +214,7 @@ perms(L) -> [H || H <- L].
         assert!(out.len() == 0);
     }
 
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
+    run_passes(&mut eir_mod);
 
     for fun_def in eir_mod.function_iter() {
         let fun = fun_def.function();
@@ -278,8 +253,7 @@ perms(L) -> [[H|T] || H <- L, T <- perms(L--[H])].
         assert!(out.len() == 0);
     }
 
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
+    run_passes(&mut eir_mod);
 
     for fun_def in eir_mod.function_iter() {
         let fun = fun_def.function();
@@ -293,15 +267,9 @@ perms(L) -> [[H|T] || H <- L, T <- perms(L--[H])].
 
     println!("{}", eir_mod.to_text_standard());
 
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("perms"),
-        arity: 1,
-    };
+    let fun = ident("woo", "perms", 1);
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let mut vm = vm_with_eir_module(eir_mod);
 
     {
         let b: Rc<Term> = Term::new_atom("b").into();
@@ -330,3 +298,79 @@ perms(L) -> [[H|T] || H <- L, T <- perms(L--[H])].
         assert!(res.erl_eq(&out));
     }
 }
+
+#[test]
+fn test_binary_generators() {
+    let _ = env_logger::try_init();
+
+    let mut eir_mod = lower(
+        "
+-module(woo).
+
+bytes() -> [X || <<X:8>> <= <<1, 2, 3>>] =:= [1, 2, 3].
+nibbles() -> [X || <<X:4>> <= <<16#12, 16#34>>] =:= [1, 2, 3, 4].
+skip() -> [X || <<0, X>> <= <<0, 1, 5, 2, 0, 3>>] =:= [1, 3].
+leftover() -> [X || <<X:16>> <= <<1, 2, 3>>] =:= [258].
+filter(B) -> [X || <<X>> <= B, X > 1].
+nested() -> [{X, Y} || <<X>> <= <<1, 2>>, <<Y>> <= <<3, 4>>] =:= [{1, 3}, {1, 4}, {2, 3}, {2, 4}].
+sized() -> [X || <<S, X:S/binary>> <= <<1, 7, 2, 8, 9>>] =:= [<<7>>, <<8, 9>>].
+",
+        ParseConfig::default(),
+    )
+    .unwrap();
+
+    for fun_def in eir_mod.function_iter() {
+        let fun = fun_def.function();
+        let mut out = Vec::new();
+        fun.validate(&mut out);
+        assert!(out.len() == 0);
+    }
+
+    run_passes(&mut eir_mod);
+
+    for fun_def in eir_mod.function_iter() {
+        let fun = fun_def.function();
+        let mut out = Vec::new();
+        fun.validate(&mut out);
+        assert!(out.len() == 0);
+    }
+
+    let mut vm = vm_with_eir_module(eir_mod);
+
+    let t = Term::new_atom("true");
+
+    for name in &["bytes", "nibbles", "skip", "leftover", "nested", "sized"] {
+        let res = vm.call(&ident("woo", name, 0), &[]).unwrap();
+        assert!(res.erl_eq(&t), "{} failed", name);
+    }
+
+    let arg = Term::Binary(Rc::new(vec![1u8, 2, 3].into()));
+    let out = Term::slice_to_list(
+        &[Term::new_i64(2).into(), Term::new_i64(3).into()],
+        Term::Nil.into(),
+    );
+    let res = vm.call(&ident("woo", "filter", 1), &[arg]).unwrap();
+    assert!(res.erl_eq(&out));
+}
+
+#[test]
+fn test_binary_comprehension() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(woo).
+
+double() -> << <<(X * 2)>> || <<X>> <= <<1, 2, 3>> >> =:= <<2, 4, 6>>.
+nibbles() -> << <<X:4>> || <<X:4>> <= <<16#12, 16#34>> >> =:= <<16#12, 16#34>>.
+from_list() -> << <<X>> || X <- [1, 2, 3] >> =:= <<1, 2, 3>>.
+bits() -> << <<X:1>> || <<X>> <= <<1, 0, 1>> >> =:= <<5:3>>.
+",
+    );
+
+    let t = Term::new_atom("true");
+    for name in &["double", "nibbles", "from_list", "bits"] {
+        let res = vm.call(&ident("woo", name, 0), &[]).unwrap();
+        assert!(res.erl_eq(&t), "{} failed", name);
+    }
+}
@@ -1,3 +1,4 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::PathBuf;
 
 use crate::ct_runner::run_ct_suite;
@@ -242,6 +243,45 @@ fn xmerl_sax_parser_utf8() {
     pass_manager.run(&mut eir_mod);
 }
 
+/// Lowers every module in the source directories of a set of OTP
+/// applications. Lowering is allowed to fail with diagnostics, for
+/// example on unsupported constructs, but it should never panic.
+#[test]
+fn lower_corpus_no_panics() {
+    let _ = env_logger::try_init();
+
+    let apps = ["stdlib", "compiler", "kernel"];
+
+    let mut panicked = Vec::new();
+    for app in apps.iter() {
+        let src = PathBuf::from(format!("../otp/lib/{}/src/", app));
+        let mut files: Vec<_> = std::fs::read_dir(&src)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map(|e| e == "erl").unwrap_or(false))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let mut config = ParseConfig::default();
+            config.code_paths.push_front(PathBuf::from("../otp/lib/"));
+            config.include_paths.push_front(src.clone());
+            config
+                .include_paths
+                .push_front(PathBuf::from(format!("../otp/lib/{}/include/", app)));
+
+            let res = catch_unwind(AssertUnwindSafe(|| {
+                let _ = lower_file(&file, config);
+            }));
+            if res.is_err() {
+                panicked.push(file);
+            }
+        }
+    }
+
+    assert!(panicked.is_empty(), "lowering panicked on {:?}", panicked);
+}
+
 #[ignore]
 #[test]
 fn foo() {
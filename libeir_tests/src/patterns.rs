@@ -1,41 +1,25 @@
 use std::rc::Rc;
 
-use super::lower;
+use super::{ident, vm_with_module};
 
-use libeir_intern::{Ident, Symbol};
-use libeir_ir::FunctionIdent;
-use libeir_passes::PassManager;
-use libeir_syntax_erl::ParseConfig;
+use libeir_intern::Symbol;
 
-use libeir_interpreter::{ErlEq, Term, VMState};
+use libeir_interpreter::{ErlEq, Term};
 
 #[test]
 fn test_pattern_equality() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
 basic_pat(A, A) -> true;
 basic_pat(_, _) -> false.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("basic_pat"),
-        arity: 2,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "basic_pat", 2);
 
     assert!(vm.call(&fun, &[1.into(), 1.into()]).unwrap().as_boolean() == Some(true));
     assert!(vm.call(&fun, &[1.into(), 2.into()]).unwrap().as_boolean() == Some(false));
@@ -45,7 +29,7 @@ basic_pat(_, _) -> false.
 fn test_tuple_pattern() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
@@ -53,22 +37,9 @@ woo({1, 2}) -> 1;
 woo({1, 2, 3}) -> 2;
 woo(_) -> 3.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(vm.call(&fun, &[1.into()]).unwrap().as_i64() == Some(3));
 
@@ -111,7 +82,7 @@ woo(_) -> 3.
 fn test_list_pattern() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
@@ -120,22 +91,9 @@ woo([1, 2]) -> 2;
 woo([]) -> 3;
 woo(_) -> 4.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(vm.call(&fun, &[1.into()]).unwrap().as_i64() == Some(4));
 
@@ -160,29 +118,16 @@ woo(_) -> 4.
 fn test_fun_atom_pattern() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
 woo(abc) -> 1;
 woo(def) -> 2.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(
         vm.call(&fun, &[Term::Atom(Symbol::intern("abc")).into()])
@@ -211,7 +156,7 @@ woo(def) -> 2.
 fn test_case_atom_pattern() {
     let _ = env_logger::try_init();
 
-    let mut eir_mod = lower(
+    let mut vm = vm_with_module(
         "
 -module(woo).
 
@@ -220,22 +165,9 @@ woo(A) -> case A of
     def -> 2
 end.
 ",
-        ParseConfig::default(),
-    )
-    .unwrap();
-
-    let mut pass_manager = PassManager::default();
-    pass_manager.run(&mut eir_mod);
-
-    let fun = FunctionIdent {
-        module: Ident::from_str("woo"),
-        name: Ident::from_str("woo"),
-        arity: 1,
-    };
+    );
 
-    let mut vm = VMState::new();
-    vm.add_builtin_modules();
-    vm.add_erlang_module(eir_mod);
+    let fun = ident("woo", "woo", 1);
 
     assert!(
         vm.call(&fun, &[Term::Atom(Symbol::intern("abc")).into()])
@@ -263,3 +195,82 @@ end.
         ])));
     }
 }
+
+#[test]
+fn test_binary_alias_pattern() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(woo).
+
+alias(X) ->
+    case X of
+        <<A:8>> = <<B:8>> -> {one, A, B};
+        <<A:8, Rest/binary>> = <<B:8, C:8>> -> {two, A, B, C, Rest};
+        <<A:8, _/binary>> = <<B:8, C:16>> -> {three, A, B, C};
+        _ -> nomatch
+    end.
+
+bits(<<Rest/bitstring>> = <<A:4, B:4>>) -> {A, B, Rest}.
+
+run() ->
+    {alias(<<7>>) =:= {one, 7, 7},
+     alias(<<1, 2>>) =:= {two, 1, 1, 2, <<2>>},
+     alias(<<1, 2, 3>>) =:= {three, 1, 1, 515},
+     alias(<<1, 2, 3, 4>>) =:= nomatch,
+     bits(<<16#12>>) =:= {1, 2, <<16#12>>}}.
+",
+    );
+
+    let ret = vm.call(&ident("woo", "run", 0), &[]).unwrap();
+    assert!(ret
+        .as_tuple()
+        .unwrap()
+        .iter()
+        .all(|t| t.as_boolean() == Some(true)));
+}
+
+#[test]
+fn test_binary_alias_pattern_sequential() {
+    let _ = env_logger::try_init();
+
+    let mut vm = vm_with_module(
+        "
+-module(woo).
+
+woo(<<A:8, _/binary>> = <<B:16>>) -> {A, B};
+woo(_) -> nomatch.
+
+single(<<1>> = <<X:8>>) -> X;
+single(_) -> nomatch.
+
+triple(<<A:8, _/binary>> = <<B:16>> = <<C:4, _/bits>>) -> {A, B, C}.
+
+mixed(V) ->
+    case V of
+        <<A:4, _:4>> = <<B:8>> -> {a, A, B};
+        <<C:16>> -> {c, C};
+        _ -> other
+    end.
+
+run() ->
+    {woo(<<1, 2>>) =:= {1, 258},
+     woo(<<1, 2, 3>>) =:= nomatch,
+     woo(<<1>>) =:= nomatch,
+     single(<<1>>) =:= 1,
+     single(<<2>>) =:= nomatch,
+     triple(<<16#12, 2>>) =:= {16#12, 16#1202, 1},
+     mixed(<<16#12>>) =:= {a, 1, 16#12},
+     mixed(<<1, 2>>) =:= {c, 258},
+     mixed(<<>>) =:= other}.
+",
+    );
+
+    let ret = vm.call(&ident("woo", "run", 0), &[]).unwrap();
+    assert!(ret
+        .as_tuple()
+        .unwrap()
+        .iter()
+        .all(|t| t.as_boolean() == Some(true)));
+}
@@ -0,0 +1,388 @@
+use crate::{ident, vm_with_module};
+
+use libeir_intern::Symbol;
+
+use libeir_interpreter::{ErlEq, Pid, ProcessState, Term};
+
+fn pid_of(term: &Term) -> Pid {
+    match term {
+        Term::Pid(pid) => *pid,
+        other => panic!("expected pid, got {:?}", other),
+    }
+}
+
+const PROCS: &str = "
+-module(procs).
+
+spawn_self() -> spawn(fun() -> self() end).
+
+spawn_mfa(A) -> spawn(procs, add_one, [A]).
+add_one(A) -> A + 1.
+
+crash(foo) -> ok.
+spawn_crash() -> spawn(procs, crash, [bar]).
+link_crash() ->
+    spawn_link(procs, crash, [bar]),
+    loop().
+link_normal() ->
+    spawn_link(procs, add_one, [1]),
+    ok.
+
+loop() -> loop().
+
+flags() ->
+    Old = process_flag(priority, high),
+    New = process_flag(priority, low),
+    Monitor = try monitor(port, self()) catch error:R -> R end,
+    Unknown = try process_flag(no_such_flag, 1) catch error:R2 -> R2 end,
+    {Old, New, Monitor, Unknown}.
+
+echo() ->
+    receive
+        {From, Msg} -> From ! Msg
+    end.
+ping() ->
+    Pid = spawn(procs, echo, []),
+    Pid ! {self(), hello},
+    receive
+        Reply -> Reply
+    end.
+
+selective() ->
+    self() ! a,
+    self() ! b,
+    receive
+        b -> ok
+    end,
+    receive
+        Msg -> Msg
+    end.
+
+after_timeout() ->
+    receive
+        _ -> message
+    after 100 -> timeout
+    end.
+bad_timeout() ->
+    try
+        receive
+        after foo -> ok
+        end
+    catch
+        error:timeout_value -> caught
+    end.
+sleeper(Parent, Time) ->
+    receive
+    after Time -> Parent ! Time
+    end.
+timeouts() ->
+    Self = self(),
+    spawn(procs, sleeper, [Self, 200]),
+    spawn(procs, sleeper, [Self, 100]),
+    receive
+        First ->
+            receive
+                Second -> {First, Second}
+            end
+    end.
+
+wait_forever() ->
+    receive
+        stop -> ok
+    end.
+
+trap_link_crash() ->
+    process_flag(trap_exit, true),
+    Pid = spawn_link(procs, crash, [bar]),
+    receive
+        {'EXIT', Pid, {Reason, _}} -> Reason
+    end.
+
+monitor_crash() ->
+    {Pid, Ref} = spawn_monitor(procs, crash, [bar]),
+    receive
+        {'DOWN', Ref, process, Pid, {Reason, _}} -> Reason
+    end.
+demonitor_crash() ->
+    {_Pid, Ref} = spawn_monitor(procs, crash, [bar]),
+    demonitor(Ref),
+    receive
+        {'DOWN', _, _, _, _} -> down
+    after 10 -> no_down
+    end.
+
+trapper(Parent) ->
+    process_flag(trap_exit, true),
+    Parent ! ready,
+    receive
+        Msg -> Parent ! {trapped, Msg}
+    end.
+exit_trapped() ->
+    Pid = spawn(procs, trapper, [self()]),
+    receive
+        ready -> ok
+    end,
+    exit(Pid, shutdown),
+    receive
+        {trapped, Msg} -> Msg
+    end.
+kill_trapped() ->
+    Pid = spawn(procs, trapper, [self()]),
+    Ref = monitor(process, Pid),
+    receive
+        ready -> ok
+    end,
+    exit(Pid, kill),
+    receive
+        {'DOWN', Ref, process, Pid, Reason} -> Reason
+    end.
+";
+
+#[test]
+fn spawn_runs_process() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let pid = pid_of(&vm.call(&ident("procs", "spawn_self", 0), &[]).unwrap());
+    vm.run();
+
+    match vm.process_state(pid) {
+        ProcessState::Returned(ret) => assert!(pid_of(&ret) == pid),
+        state => panic!("{:?}", state),
+    }
+}
+
+#[test]
+fn spawn_mfa() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let pid = pid_of(
+        &vm.call(&ident("procs", "spawn_mfa", 1), &[Term::new_i64(1)])
+            .unwrap(),
+    );
+    vm.run();
+
+    match vm.process_state(pid) {
+        ProcessState::Returned(ret) => assert!(ret.as_i64() == Some(2)),
+        state => panic!("{:?}", state),
+    }
+}
+
+#[test]
+fn spawned_process_crash() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let pid = pid_of(&vm.call(&ident("procs", "spawn_crash", 0), &[]).unwrap());
+    vm.run();
+
+    let state = vm.process_state(pid);
+    assert!(!state.is_runnable());
+
+    let reason = state.exit_reason().unwrap();
+    let reason = reason.as_tuple().unwrap();
+    assert!(reason[0].erl_eq(&Term::new_atom("function_clause")));
+}
+
+#[test]
+fn link_propagates_crash() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    // Loops forever unless it is killed by the crash of the linked process
+    let (typ, reason, _trace) = vm.call(&ident("procs", "link_crash", 0), &[]).unwrap_err();
+    assert!(typ.as_atom() == Some(Symbol::intern("exit")));
+    let reason = reason.as_tuple().unwrap();
+    assert!(reason[0].erl_eq(&Term::new_atom("function_clause")));
+}
+
+#[test]
+fn link_normal_exit() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "link_normal", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("ok")));
+
+    vm.run();
+    let child = Pid(1);
+    assert!(vm
+        .process_state(child)
+        .exit_reason()
+        .unwrap()
+        .erl_eq(&Term::new_atom("normal")));
+}
+
+#[test]
+fn preempt_after_reductions() {
+    let _ = env_logger::try_init();
+    let vm = vm_with_module(PROCS);
+
+    let fun = Term::CapturedFunction {
+        ident: ident("procs", "loop", 0),
+    };
+    let pid = vm.spawn(fun.into(), &[]);
+
+    assert!(vm.run_reductions(pid, 10) == 10);
+    assert!(vm.process_state(pid).is_runnable());
+}
+
+#[test]
+fn send_and_receive() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "ping", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("hello")));
+}
+
+#[test]
+fn selective_receive() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    // `a` is skipped by the first receive and stays in the mailbox
+    let ret = vm.call(&ident("procs", "selective", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("a")));
+}
+
+#[test]
+fn receive_timeout() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "after_timeout", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("timeout")));
+    assert!(vm.time() == 100);
+}
+
+#[test]
+fn invalid_receive_timeout() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "bad_timeout", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("caught")));
+}
+
+#[test]
+fn timeouts_in_order() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "timeouts", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_i64() == Some(100));
+    assert!(ret[1].as_i64() == Some(200));
+    assert!(vm.time() == 200);
+}
+
+#[test]
+fn blocked_process() {
+    let _ = env_logger::try_init();
+    let vm = vm_with_module(PROCS);
+
+    let fun = Term::CapturedFunction {
+        ident: ident("procs", "wait_forever", 0),
+    };
+    let pid = vm.spawn(fun.into(), &[]);
+
+    vm.run_all();
+    match vm.process_state(pid) {
+        ProcessState::Waiting => (),
+        state => panic!("{:?}", state),
+    }
+}
+
+#[test]
+fn trap_exit_link() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "trap_link_crash", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("function_clause")));
+}
+
+#[test]
+fn monitor_down() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "monitor_crash", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("function_clause")));
+
+    let ret = vm.call(&ident("procs", "demonitor_crash", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("no_down")));
+}
+
+#[test]
+fn exit_signals() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    // A trapping process gets the signal as a message
+    let ret = vm.call(&ident("procs", "exit_trapped", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].erl_eq(&Term::new_atom("EXIT")));
+    assert!(ret[2].erl_eq(&Term::new_atom("shutdown")));
+
+    // `kill` can not be trapped
+    let ret = vm.call(&ident("procs", "kill_trapped", 0), &[]).unwrap();
+    assert!(ret.erl_eq(&Term::new_atom("killed")));
+}
+
+#[test]
+fn trace_ping() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    libeir_interpreter::trace::clear();
+    libeir_interpreter::trace::set_enabled(true);
+    let ret = vm.call(&ident("procs", "ping", 0), &[]).unwrap();
+    libeir_interpreter::trace::set_enabled(false);
+    assert!(ret.erl_eq(&Term::new_atom("hello")));
+
+    let log = libeir_interpreter::trace::format_log();
+    assert!(log.contains("<0.0.0> call procs:ping/0()"));
+    assert!(log.contains("<0.1.0> call procs:echo/0()"));
+    assert!(log.contains("block procs:ping/0"));
+    assert!(log.contains("send {<0.0.0>,hello} to <0.1.0>"));
+    assert!(log.contains("<0.1.0> send hello to <0.0.0>"));
+    assert!(log.contains("<0.0.0> return hello"));
+    assert!(log.contains("<0.0.0> exit normal"));
+
+    let path = std::env::temp_dir().join("libeir_trace_ping.json");
+    libeir_interpreter::trace::dump_chrome_trace(&path).unwrap();
+    let json = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(json.starts_with('['));
+    assert!(json.contains("\"ph\":\"B\""));
+    assert!(json.contains("\"ph\":\"E\""));
+}
+
+#[test]
+fn call_deadlock() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let err = vm
+        .call(&ident("procs", "wait_forever", 0), &[])
+        .err()
+        .unwrap();
+    assert!(err.0.erl_eq(&Term::new_atom("error")));
+    assert!(err.1.erl_eq(&Term::new_atom("deadlock")));
+}
+
+#[test]
+fn process_flags() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(PROCS);
+
+    let ret = vm.call(&ident("procs", "flags", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].erl_eq(&Term::new_atom("normal")));
+    assert!(ret[1].erl_eq(&Term::new_atom("high")));
+    assert!(ret[2].erl_eq(&Term::new_atom("badarg")));
+    assert!(ret[3].erl_eq(&Term::new_atom("badarg")));
+}
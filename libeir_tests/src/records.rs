@@ -163,3 +163,23 @@ create_1(A) -> #person{name=A}.
     vm.add_builtin_modules();
     vm.add_erlang_module(eir_mod);
 }
+
+#[test]
+fn undefined_record_field() {
+    let _ = env_logger::try_init();
+
+    let res = lower(
+        "
+-module(woo).
+
+-record(person, {name, phone, address}).
+
+get_1(A) -> A#person.age.
+get_2(A) -> A#animal.name.
+set_1(A) -> A#person{age = 12}.
+match_1(#person{age = A}) -> A.
+",
+        ParseConfig::default(),
+    );
+    assert!(res.is_err());
+}
@@ -0,0 +1,165 @@
+use crate::{atom, ident, vm_with_module};
+
+use libeir_interpreter::{ErlEq, Term};
+
+fn int_list(term: &std::rc::Rc<Term>) -> Vec<i64> {
+    Term::as_list(term)
+        .unwrap()
+        .iter()
+        .map(|t| t.as_i64().unwrap())
+        .collect()
+}
+
+const STDLIB: &str = "
+-module(stdlib).
+
+higher_order() ->
+    L = lists:seq(1, 5),
+    {lists:map(fun(X) -> X * 2 end, L),
+     lists:foldl(fun(X, Acc) -> X + Acc end, 0, L),
+     lists:filter(fun(X) -> X rem 2 == 0 end, L)}.
+
+long_fold(N) ->
+    lists:foldl(fun(X, Acc) -> X + Acc end, 0, lists:seq(1, N)).
+
+map_throws() ->
+    try lists:map(fun(3) -> throw(three); (X) -> X end, [1, 2, 3, 4])
+    catch throw:R -> {caught, R}
+    end.
+
+list_utils() ->
+    {lists:seq(5, 1, -2), lists:nth(2, [a, b, c]), lists:sort([3, a, 1.5, {}, 2]),
+     lists:append([[1], [2, 3], []]), lists:append([1], [2]),
+     lists:flatten([1, [2, [3, []]], 4]),
+     lists:keysort(2, [{a, 2}, {b, 1}, {c, 2}]),
+     lists:zip([1, 2], [a, b]),
+     lists:member(b, [a, b]), lists:keyfind(b, 1, [{a, 1}, {b, 2}])}.
+
+map_utils() ->
+    M = maps:from_list([{a, 1}, {b, 2}]),
+    M2 = maps:put(c, 3, M),
+    {maps:get(a, M2), maps:get(z, M2, default), maps:find(b, M2), maps:find(z, M2),
+     maps:keys(M2), maps:values(M2), maps:to_list(maps:remove(a, M)),
+     maps:to_list(maps:merge(M, #{a => 10, d => 4})),
+     maps:fold(fun(_K, V, Acc) -> V + Acc end, 0, M2)}.
+
+bad_key() ->
+    try maps:get(z, #{})
+    catch error:R -> R
+    end.
+";
+
+#[test]
+fn lists_higher_order() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(STDLIB);
+
+    let ret = vm.call(&ident("stdlib", "higher_order", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(int_list(&ret[0]) == [2, 4, 6, 8, 10]);
+    assert!(ret[1].as_i64() == Some(15));
+    assert!(int_list(&ret[2]) == [2, 4]);
+}
+
+#[test]
+fn lists_long_fold() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(STDLIB);
+
+    // Each element is a separate call through the executor, the Rust stack
+    // does not grow with the length of the list.
+    let ret = vm
+        .call(&ident("stdlib", "long_fold", 1), &[Term::new_i64(10000)])
+        .unwrap();
+    assert!(ret.as_i64() == Some(10000 * 10001 / 2));
+}
+
+#[test]
+fn lists_closure_throws() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(STDLIB);
+
+    let ret = vm.call(&ident("stdlib", "map_throws", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].erl_eq(&atom("caught")));
+    assert!(ret[1].erl_eq(&atom("three")));
+}
+
+#[test]
+fn lists_utils() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(STDLIB);
+
+    let ret = vm.call(&ident("stdlib", "list_utils", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(int_list(&ret[0]) == [5, 3, 1]);
+    assert!(ret[1].erl_eq(&atom("b")));
+
+    let sorted = Term::as_list(&ret[2]).unwrap();
+    assert!(sorted[0].as_i64() == Some(1));
+    assert!(sorted[1].erl_eq(&Term::Float(1.5.into())));
+    assert!(sorted[2].as_i64() == Some(2));
+    assert!(sorted[3].erl_eq(&atom("a")));
+    assert!(sorted[4].erl_eq(&Term::Tuple(vec![])));
+
+    assert!(int_list(&ret[3]) == [1, 2, 3]);
+    assert!(int_list(&ret[4]) == [1, 2]);
+    assert!(int_list(&ret[5]) == [1, 2, 3, 4]);
+
+    // Stable, `a` stays before `c`
+    let keys: Vec<_> = Term::as_list(&ret[6])
+        .unwrap()
+        .iter()
+        .map(|t| t.as_tuple().unwrap()[0].as_atom().unwrap())
+        .collect();
+    assert!(keys == ["b", "a", "c"]);
+
+    let zipped = Term::as_list(&ret[7]).unwrap();
+    assert!(zipped[1].erl_eq(&Term::Tuple(vec![
+        Term::new_i64(2).into(),
+        atom("b").into()
+    ])));
+
+    assert!(ret[8].erl_eq(&atom("true")));
+    assert!(ret[9].as_tuple().unwrap()[1].as_i64() == Some(2));
+}
+
+#[test]
+fn maps_utils() {
+    let _ = env_logger::try_init();
+    let mut vm = vm_with_module(STDLIB);
+
+    let ret = vm.call(&ident("stdlib", "map_utils", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].as_i64() == Some(1));
+    assert!(ret[1].erl_eq(&atom("default")));
+    assert!(ret[2].as_tuple().unwrap()[1].as_i64() == Some(2));
+    assert!(ret[3].erl_eq(&atom("error")));
+
+    let keys: Vec<_> = Term::as_list(&ret[4])
+        .unwrap()
+        .iter()
+        .map(|t| t.as_atom().unwrap())
+        .collect();
+    assert!(keys == ["a", "b", "c"]);
+    assert!(int_list(&ret[5]) == [1, 2, 3]);
+
+    let removed = Term::as_list(&ret[6]).unwrap();
+    assert!(removed.len() == 1);
+    assert!(removed[0].as_tuple().unwrap()[0].erl_eq(&atom("b")));
+
+    // Values of the second map take precedence
+    let merged: Vec<_> = Term::as_list(&ret[7])
+        .unwrap()
+        .iter()
+        .map(|t| t.as_tuple().unwrap()[1].as_i64().unwrap())
+        .collect();
+    assert!(merged == [10, 2, 4]);
+
+    assert!(ret[8].as_i64() == Some(6));
+
+    let ret = vm.call(&ident("stdlib", "bad_key", 0), &[]).unwrap();
+    let ret = ret.as_tuple().unwrap();
+    assert!(ret[0].erl_eq(&atom("badkey")));
+    assert!(ret[1].erl_eq(&atom("z")));
+}
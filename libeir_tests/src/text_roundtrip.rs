@@ -0,0 +1,109 @@
+use libeir_ir::{parse_module_unwrap, Module};
+use libeir_passes::PassManager;
+use libeir_syntax_erl::ParseConfig;
+
+use crate::lower;
+
+fn assert_roundtrip(module: &Module) {
+    let text = module.to_text_standard();
+
+    let parsed = parse_module_unwrap(&text);
+
+    for fun_def in module.function_iter() {
+        let fun = fun_def.function();
+        let ident = fun.ident();
+
+        let idx = parsed
+            .name_arity_index(ident.name.name, ident.arity)
+            .unwrap();
+        let parsed_fun = parsed[idx].function();
+
+        fun.graph_eq(fun.block_entry(), parsed_fun, parsed_fun.block_entry())
+            .unwrap();
+    }
+}
+
+fn roundtrip(source: &str) {
+    let mut eir_mod = lower(source, ParseConfig::default()).unwrap();
+    assert_roundtrip(&eir_mod);
+
+    let mut pass_manager = PassManager::default();
+    pass_manager.run(&mut eir_mod);
+    assert_roundtrip(&eir_mod);
+}
+
+#[test]
+fn roundtrip_case() {
+    roundtrip(
+        "
+-module(woo).
+
+woo(A, B) ->
+    case {A, B} of
+        {[H | T], _} when H > 1.5 -> {H, T};
+        {C, C} -> same;
+        {'quoted atom', \"str\"} -> 1.0e10;
+        _ when A =< B, A /= 2 -> A + B;
+        Other -> Other
+    end.
+",
+    );
+}
+
+#[test]
+fn roundtrip_receive() {
+    roundtrip(
+        "
+-module(woo).
+
+woo(Timeout) ->
+    receive
+        {msg, A} -> A;
+        other -> nil
+    after Timeout ->
+        timeout
+    end.
+",
+    );
+}
+
+#[test]
+fn roundtrip_binary() {
+    roundtrip(
+        "
+-module(woo).
+
+construct(A, B) -> <<A:8, B/binary, 1.0/float, \"abc\"/utf16-little>>.
+
+match(<<Size:8, Data:Size/binary, Rest/bits>>) -> {Data, Rest};
+match(<<C/utf8, _/binary>>) -> C.
+",
+    );
+}
+
+#[test]
+fn roundtrip_binary_alias() {
+    roundtrip(
+        "
+-module(woo).
+
+woo(<<A:8, _/binary>> = <<B:16>>) -> {A, B};
+woo(_) -> nomatch.
+",
+    );
+}
+
+#[test]
+fn roundtrip_map() {
+    roundtrip(
+        "
+-module(woo).
+
+woo(M, K) ->
+    case M of
+        #{K := V, a := 1} -> M#{K => V, b := 2};
+        _ -> #{K => M, 1 => 2}
+    end.
+",
+    );
+}
@@ -2,7 +2,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use clap::{arg_enum, value_t, values_t, App, Arg, ArgMatches};
+use clap::{arg_enum, value_t, App, Arg, ArgMatches};
 
 use libeir_diagnostics::term::{
     self,
@@ -10,11 +10,11 @@ use libeir_diagnostics::term::{
 };
 use libeir_diagnostics::CodeMap;
 use libeir_frontend::{
-    abstr_erlang::AbstrErlangFrontend, eir::EirFrontend, erlang::ErlangFrontend, AnyFrontend,
-    DynFrontend,
+    abstr_erlang::AbstrErlangFrontend, core_erlang::CoreErlangFrontend, eir::EirFrontend,
+    erlang::ErlangFrontend, AnyFrontend, DynFrontend,
 };
-use libeir_ir::FunctionIdent;
-use libeir_passes::PassManager;
+use libeir_ir::{FunctionIdent, TypeAnnotatedFormatConfig};
+use libeir_passes::{PassManager, PassRegistry, PassStats};
 
 arg_enum! {
     #[derive(Debug, PartialEq, Eq)]
@@ -29,6 +29,7 @@ arg_enum! {
     pub enum InputType {
         Eir,
         Abstr,
+        Core,
         Erl,
     }
 }
@@ -42,16 +43,6 @@ arg_enum! {
     }
 }
 
-arg_enum! {
-    #[derive(Debug)]
-    pub enum CompilePass {
-        CompilePatterns,
-        SimplifyCfg,
-        NaiveInlineClosures,
-        Validate,
-    }
-}
-
 arg_enum! {
     #[derive(Debug, Copy, Clone)]
     pub enum LogLevel {
@@ -97,6 +88,7 @@ fn make_frontend(codemap: Arc<CodeMap>, matches: &ArgMatches) -> AnyFrontend {
     match value_t!(matches, "IN_FORMAT", InputType).unwrap() {
         InputType::Erl => make_erlang_frontend(codemap, matches).into(),
         InputType::Abstr => AbstrErlangFrontend::new(codemap).into(),
+        InputType::Core => CoreErlangFrontend::new(codemap).into(),
         InputType::Eir => EirFrontend::new(codemap).into(),
     }
 }
@@ -117,7 +109,28 @@ fn setup_logger(level: log::LevelFilter) {
         .unwrap();
 }
 
+fn print_stats(stats: &[PassStats]) {
+    eprintln!(
+        "{:<24} {:>16} {:>16} {:>16} {:>12}",
+        "pass", "blocks", "values", "primops", "time"
+    );
+    for stat in stats {
+        eprintln!(
+            "{:<24} {:>16} {:>16} {:>16} {:>10.3}ms{}",
+            stat.name,
+            format!("{} -> {}", stat.before.blocks, stat.after.blocks),
+            format!("{} -> {}", stat.before.values, stat.after.values),
+            format!("{} -> {}", stat.before.primops, stat.after.primops),
+            stat.time.as_secs_f64() * 1000.0,
+            if stat.changed { "" } else { " (unchanged)" },
+        );
+    }
+}
+
 fn main() {
+    let registry = PassRegistry::default();
+    let pass_names: Vec<&str> = registry.names().collect();
+
     let matches = App::new("Eir Compiler CLI")
         .version("alpha")
         .author("Hans Elias B. Josephsen")
@@ -160,6 +173,9 @@ fn main() {
         .arg(Arg::from_usage(
             "[ANNOTATE_LIVE] --annotate-live 'annotate calculated live variables in ir",
         ))
+        .arg(Arg::from_usage(
+            "[ANNOTATE_TYPES] --annotate-types 'annotate inferred value types in ir'",
+        ))
         .arg(
             Arg::from_usage(
                 "<INCLUDE_PATHS> -I <INCLUDE_PATH> 'add include path for the erlang preprocessor'",
@@ -179,8 +195,18 @@ fn main() {
                 .required(false)
                 .multiple(true)
                 .number_of_values(1)
-                .possible_values(&CompilePass::variants()),
+                .possible_values(&pass_names),
         )
+        .arg(
+            Arg::from_usage(
+                "<PIPELINE> --pipeline <PIPELINE> 'run a pass pipeline, e.g. validate,fixpoint(simplify_cfg)'",
+            )
+            .required(false)
+            .conflicts_with("PASSES"),
+        )
+        .arg(Arg::from_usage(
+            "--stats 'print per pass statistics to stderr'",
+        ))
         .arg(
             Arg::from_usage("<LOG_LEVEL> -L,--log-level <LOG_LEVEL> 'log level'")
                 .default_value("info")
@@ -216,35 +242,38 @@ fn main() {
     }
     let mut eir = eir_res.unwrap();
 
-    match value_t!(matches, "COMPILE_LEVEL", CompileLevel).unwrap() {
-        CompileLevel::High => {}
-        CompileLevel::Normal => {
-            let mut pass_manager = PassManager::default();
-            pass_manager.run(&mut eir);
+    // An explicit pipeline takes precedence over the compile level
+    let pass_manager = if let Some(pipeline) = matches.value_of("PIPELINE") {
+        match registry.parse_pipeline(pipeline) {
+            Ok(pass_manager) => Some(pass_manager),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                std::process::exit(1);
+            }
         }
-        CompileLevel::Custom => {
-            let mut pass_manager = PassManager::new();
-            if matches.is_present("PASSES") {
-                for pass_type in values_t!(matches.values_of("PASSES"), CompilePass).unwrap() {
-                    match pass_type {
-                        CompilePass::CompilePatterns => {
-                            pass_manager
-                                .push_function_pass(libeir_passes::CompilePatternPass::new());
-                        }
-                        CompilePass::SimplifyCfg => {
-                            pass_manager.push_function_pass(libeir_passes::SimplifyCfgPass::new());
-                        }
-                        CompilePass::NaiveInlineClosures => {
-                            pass_manager
-                                .push_function_pass(libeir_passes::NaiveInlineClosuresPass::new());
-                        }
-                        CompilePass::Validate => {
-                            pass_manager.push_function_pass(libeir_passes::ValidatePass::new());
-                        }
+    } else {
+        match value_t!(matches, "COMPILE_LEVEL", CompileLevel).unwrap() {
+            CompileLevel::High => None,
+            CompileLevel::Normal => Some(PassManager::default()),
+            CompileLevel::Custom => {
+                let mut pass_manager = PassManager::new();
+                if let Some(passes) = matches.values_of("PASSES") {
+                    for pass in passes {
+                        registry.push_pass(pass, &mut pass_manager).unwrap();
                     }
                 }
+                Some(pass_manager)
             }
-            pass_manager.run(&mut eir);
+        }
+    };
+
+    if let Some(mut pass_manager) = pass_manager {
+        if matches.is_present("stats") {
+            pass_manager.enable_stats();
+        }
+        pass_manager.run(&mut eir);
+        if matches.is_present("stats") {
+            print_stats(pass_manager.stats());
         }
     }
 
@@ -261,7 +290,14 @@ fn main() {
     let out_type = value_t!(matches, "OUT_FORMAT", OutputType).unwrap();
     match out_type {
         OutputType::Eir => {
-            if let Some(selected) = selected_function {
+            if matches.is_present("ANNOTATE_TYPES") {
+                let mut config = TypeAnnotatedFormatConfig::default();
+                if let Some(selected) = selected_function {
+                    out_data = eir[&selected].function().to_text(&mut config);
+                } else {
+                    out_data = eir.to_text(&mut config);
+                }
+            } else if let Some(selected) = selected_function {
                 out_data = eir[&selected].function().to_text_standard();
             } else {
                 out_data = eir.to_text_standard();
@@ -0,0 +1,479 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use clap::{arg_enum, value_t, App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use libeir_diagnostics::term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+};
+use libeir_diagnostics::{CodeMap, Diagnostic};
+use libeir_frontend::{
+    abstr_erlang::AbstrErlangFrontend, core_erlang::CoreErlangFrontend, eir::EirFrontend,
+    erlang::ErlangFrontend, AnyFrontend, DynFrontend,
+};
+use libeir_intern::{Ident, Symbol};
+use libeir_interpreter::{Term, VMState};
+use libeir_ir::{FunctionIdent, Module};
+use libeir_passes::PassManager;
+use libeir_syntax_erl::{ast, ParseConfig, Parser, ParserError};
+use libeir_util_parse::Errors;
+
+arg_enum! {
+    #[derive(Debug, Copy, Clone)]
+    pub enum LogLevel {
+        Error,
+        Warn,
+        Info,
+        Debug,
+        Trace,
+    }
+}
+impl LogLevel {
+    pub fn to_filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+type CallResult = Result<Rc<Term>, (Rc<Term>, Rc<Term>, Rc<Term>)>;
+
+fn setup_logger(level: log::LevelFilter) {
+    fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{}][{}] {}",
+                record.target(),
+                record.level(),
+                message
+            ))
+        })
+        .level(level)
+        .chain(std::io::stderr())
+        .apply()
+        .unwrap();
+}
+
+fn emit_diagnostics(codemap: &CodeMap, diagnostics: &[Diagnostic]) {
+    let term_config = term::Config::default();
+    let mut out = StandardStream::stderr(ColorChoice::Auto);
+    for diag in diagnostics.iter() {
+        term::emit(&mut out, &term_config, codemap, diag).unwrap();
+    }
+}
+
+/// Evaluates expressions against the modules loaded into a VM. Every
+/// expression is lowered as the body of a function in a fresh module,
+/// which takes the variables bound so far as arguments.
+struct Shell {
+    vm: VMState,
+    codemap: Arc<CodeMap>,
+    config: ParseConfig,
+    evaluated: usize,
+    bindings: Vec<(Symbol, Rc<Term>)>,
+}
+
+impl Shell {
+    fn new(codemap: Arc<CodeMap>, config: ParseConfig) -> Self {
+        let mut vm = VMState::new();
+        vm.add_builtin_modules();
+        Shell {
+            vm,
+            codemap,
+            config,
+            evaluated: 0,
+            bindings: Vec::new(),
+        }
+    }
+
+    fn frontend_for(&self, path: &Path) -> Option<AnyFrontend> {
+        let codemap = self.codemap.clone();
+        match path.extension()?.to_str()? {
+            "erl" => Some(ErlangFrontend::new(self.config.clone(), codemap).into()),
+            "abstr" => Some(AbstrErlangFrontend::new(codemap).into()),
+            "core" => Some(CoreErlangFrontend::new(codemap).into()),
+            "eir" => Some(EirFrontend::new(codemap).into()),
+            _ => None,
+        }
+    }
+
+    fn add_module(&mut self, mut module: Module) {
+        let mut pass_manager = PassManager::default();
+        pass_manager.run(&mut module);
+        self.vm.add_erlang_module(module);
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<(), ()> {
+        let frontend = match self.frontend_for(path) {
+            Some(frontend) => frontend,
+            None => {
+                eprintln!("error: unknown file type: {}", path.display());
+                return Err(());
+            }
+        };
+
+        let (res, diagnostics) = frontend.parse_file_dyn(path);
+        emit_diagnostics(&self.codemap, &diagnostics);
+        self.add_module(res?);
+        Ok(())
+    }
+
+    /// Evaluates a sequence of Erlang expressions, with or without the
+    /// terminating `.`. Variables bound by a top level match are kept for
+    /// the following evaluations. Returns `Err` when they fail to compile.
+    fn eval(&mut self, exprs: &str) -> Result<CallResult, ()> {
+        let mut exprs = exprs.trim_end();
+        if exprs.ends_with('.') {
+            exprs = &exprs[..exprs.len() - 1];
+        }
+
+        // Find the variables the expressions bind, so that they can be
+        // returned from the function together with the value.
+        let mut errors: Errors<ParserError, ParserError> = Errors::new();
+        let parser = Parser::new(self.config.clone(), self.codemap.clone());
+        let block = format!("begin\n{}\nend", exprs);
+        let body = match parser.parse_string::<ast::Expr, _>(&mut errors, block) {
+            Ok(ast::Expr::Begin(begin)) => begin.body,
+            Ok(expr) => vec![expr],
+            Err(()) => {
+                errors.print(&self.codemap);
+                return Err(());
+            }
+        };
+        let mut vars: Vec<Symbol> = self.bindings.iter().map(|(var, _)| *var).collect();
+        for mut expr in body.iter() {
+            while let ast::Expr::Match(match_expr) = expr {
+                pattern_vars(&match_expr.pattern, &mut vars);
+                expr = &match_expr.expr;
+            }
+        }
+
+        let params: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(var, _)| var.to_string())
+            .collect();
+        let returned: Vec<_> = vars.iter().map(|var| var.to_string()).collect();
+
+        self.evaluated += 1;
+        let module = format!("eir_shell_{}", self.evaluated);
+        let source = format!(
+            "-module({}).\neval({}) ->\nEirShellValue = begin\n{}\nend,\n{{EirShellValue, {{{}}}}}.\n",
+            module,
+            params.join(", "),
+            exprs,
+            returned.join(", "),
+        );
+
+        let frontend = ErlangFrontend::new(self.config.clone(), self.codemap.clone());
+        let (res, diagnostics) = frontend.parse_string_dyn(&source);
+        let eir = match res {
+            Ok(eir) => eir,
+            Err(()) => {
+                emit_diagnostics(&self.codemap, &diagnostics);
+                return Err(());
+            }
+        };
+        self.add_module(eir);
+
+        let ident = FunctionIdent {
+            module: Ident::from_str(&module),
+            name: Ident::from_str("eval"),
+            arity: params.len(),
+        };
+        let args: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(_, value)| (**value).clone())
+            .collect();
+        let result = match self.vm.call(&ident, &args) {
+            Ok(result) => result,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        // The function returns `{Value, {Var1, Var2, ...}}`
+        let result = result.as_tuple().unwrap();
+        let values = result[1].as_tuple().unwrap();
+        self.bindings = vars.into_iter().zip(values.iter().cloned()).collect();
+
+        Ok(Ok(result[0].clone()))
+    }
+
+    /// Calls a function in a loaded module. Returns `Err` when there is
+    /// no such function.
+    fn call(&mut self, module: &str, function: &str, args: &[Term]) -> Result<CallResult, ()> {
+        let ident = FunctionIdent {
+            module: Ident::from_str(module),
+            name: Ident::from_str(function),
+            arity: args.len(),
+        };
+        if !self.vm.function_exists(&ident) {
+            eprintln!("error: undefined function {}", ident);
+            return Err(());
+        }
+        Ok(self.vm.call(&ident, args))
+    }
+}
+
+/// Collects the variables bound by a pattern, in order of appearance.
+fn pattern_vars(pattern: &ast::Expr, vars: &mut Vec<Symbol>) {
+    match pattern {
+        ast::Expr::Var(ast::Var(_, var)) => {
+            if var.name != Symbol::intern("_") && !vars.contains(&var.name) {
+                vars.push(var.name);
+            }
+        }
+        ast::Expr::Match(match_expr) => {
+            pattern_vars(&match_expr.pattern, vars);
+            pattern_vars(&match_expr.expr, vars);
+        }
+        ast::Expr::Cons(cons) => {
+            pattern_vars(&cons.head, vars);
+            pattern_vars(&cons.tail, vars);
+        }
+        ast::Expr::Tuple(tuple) => {
+            for elem in tuple.elements.iter() {
+                pattern_vars(elem, vars);
+            }
+        }
+        ast::Expr::Map(map) => {
+            for field in map.fields.iter() {
+                pattern_vars(&field.value(), vars);
+            }
+        }
+        ast::Expr::Binary(bin) => {
+            for elem in bin.elements.iter() {
+                pattern_vars(&elem.bit_expr, vars);
+            }
+        }
+        ast::Expr::Record(rec) => {
+            for field in rec.fields.iter() {
+                if let Some(value) = &field.value {
+                    pattern_vars(value, vars);
+                }
+            }
+        }
+        ast::Expr::BinaryExpr(bin_expr) => {
+            pattern_vars(&bin_expr.lhs, vars);
+            pattern_vars(&bin_expr.rhs, vars);
+        }
+        _ => (),
+    }
+}
+
+/// Prints the result of a call. Errors and throws have already been
+/// reported by the VM together with their stack trace, only exits and
+/// deadlocks of the called process are printed here.
+fn print_result(result: &CallResult) {
+    match result {
+        Ok(term) => println!("{}", term),
+        Err((typ, reason, _trace)) => {
+            let deadlock = typ.as_atom() == Some(Symbol::intern("error"))
+                && reason.as_atom() == Some(Symbol::intern("deadlock"));
+            if typ.as_atom() == Some(Symbol::intern("exit")) || deadlock {
+                eprintln!("** exception {}: {}", typ, reason);
+            }
+        }
+    }
+}
+
+fn repl(shell: &mut Shell) {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    let mut prompt = 1;
+    loop {
+        // Expressions may span several lines, they end with a `.`
+        let mut input = String::new();
+        loop {
+            if input.is_empty() {
+                print!("{}> ", prompt);
+            } else {
+                print!("{}  ", " ".repeat(prompt.to_string().len()));
+            }
+            std::io::stdout().flush().unwrap();
+
+            match lines.next() {
+                Some(Ok(line)) => {
+                    input.push_str(&line);
+                    input.push('\n');
+                }
+                _ => {
+                    println!();
+                    return;
+                }
+            }
+
+            let trimmed = input.trim();
+            if trimmed.is_empty() {
+                input.clear();
+            } else if trimmed.ends_with('.') {
+                break;
+            }
+        }
+
+        libeir_interpreter::trace::clear();
+        if let Ok(result) = shell.eval(&input) {
+            print_result(&result);
+            prompt += 1;
+        }
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), ()> {
+    let mut config = ParseConfig::default();
+    if let Some(includes) = matches.values_of("INCLUDE_PATHS") {
+        for include in includes {
+            config.include_paths.push_front(PathBuf::from(include));
+        }
+    }
+    if let Some(includes) = matches.values_of("CODE_PATHS") {
+        for include in includes {
+            config.code_paths.push_front(PathBuf::from(include));
+        }
+    }
+
+    let codemap = Arc::new(CodeMap::new());
+    let mut shell = Shell::new(codemap, config);
+
+    if let Some(files) = matches.values_of("FILES") {
+        for file in files {
+            shell.load_file(Path::new(file))?;
+        }
+    }
+
+    let call = match matches.value_of("CALL") {
+        Some(call) => call,
+        None => {
+            repl(&mut shell);
+            return Ok(());
+        }
+    };
+
+    let (module, function) = match call.find(':') {
+        Some(idx) => (&call[..idx], &call[idx + 1..]),
+        None => {
+            eprintln!("error: expected Module:function, got {}", call);
+            return Err(());
+        }
+    };
+
+    // Arguments are evaluated like shell expressions, which covers all
+    // of the term syntax.
+    let mut args = Vec::new();
+    if let Some(arg_exprs) = matches.values_of("ARGS") {
+        for arg_expr in arg_exprs {
+            match shell.eval(arg_expr)? {
+                Ok(term) => args.push((*term).clone()),
+                Err(err) => {
+                    print_result(&Err(err));
+                    return Err(());
+                }
+            }
+        }
+    }
+
+    let tracing = matches.is_present("TRACE_LOG") || matches.is_present("CHROME_TRACE");
+    libeir_interpreter::trace::clear();
+    libeir_interpreter::trace::set_enabled(tracing);
+    let result = shell.call(module, function, &args);
+    libeir_interpreter::trace::set_enabled(false);
+    let result = result?;
+    print_result(&result);
+
+    if let Some(path) = matches.value_of("TRACE_LOG") {
+        if let Err(err) = libeir_interpreter::trace::dump_log(path) {
+            eprintln!("error: failed to write trace log to {}: {}", path, err);
+            return Err(());
+        }
+    }
+    if let Some(path) = matches.value_of("CHROME_TRACE") {
+        if let Err(err) = libeir_interpreter::trace::dump_chrome_trace(path) {
+            eprintln!("error: failed to write Chrome trace to {}: {}", path, err);
+            return Err(());
+        }
+    }
+
+    result.map(|_| ()).map_err(|_| ())
+}
+
+fn main() {
+    let matches = App::new("Eir")
+        .version("alpha")
+        .author("Hans Elias B. Josephsen")
+        .about("Runs Erlang code in the Eir interpreter")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::from_usage("<LOG_LEVEL> -L,--log-level <LOG_LEVEL> 'log level'")
+                .default_value("warn")
+                .required(false)
+                .case_insensitive(true)
+                .possible_values(&LogLevel::variants()),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Loads modules and calls a function, or starts a shell without --call")
+                .arg(
+                    Arg::with_name("FILES")
+                        .help("Modules to load, as .erl, .abstr, .core or .eir files")
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::from_usage("<CALL> -c,--call <MOD:FUN> 'function to call'")
+                        .required(false),
+                )
+                .arg(
+                    Arg::from_usage("<ARGS> -a,--arg <TERM> 'argument to the called function'")
+                        .required(false)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires("CALL"),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "<INCLUDE_PATHS> -I <INCLUDE_PATH> 'add include path for the erlang preprocessor'",
+                    )
+                    .required(false)
+                    .multiple(true),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "<CODE_PATHS> -C <CODE_PATH> 'add code path for the erlang preprocessor'",
+                    )
+                    .required(false)
+                    .multiple(true),
+                )
+                .arg(
+                    Arg::from_usage("<TRACE_LOG> --trace-log <FILE> 'write a log of the execution'")
+                        .required(false)
+                        .requires("CALL"),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "<CHROME_TRACE> --chrome-trace <FILE> 'write a Chrome trace of the execution'",
+                    )
+                    .required(false)
+                    .requires("CALL"),
+                ),
+        )
+        .get_matches();
+
+    setup_logger(
+        value_t!(matches, "LOG_LEVEL", LogLevel)
+            .unwrap()
+            .to_filter(),
+    );
+
+    if let ("run", Some(run_matches)) = matches.subcommand() {
+        if run(run_matches).is_err() {
+            std::process::exit(1);
+        }
+    }
+}
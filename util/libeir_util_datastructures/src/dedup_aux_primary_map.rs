@@ -41,6 +41,14 @@ where
             k
         }
     }
+
+    pub fn len(&self) -> usize {
+        self.forward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
 }
 
 impl<K, V, C> Index<K> for DedupAuxPrimaryMap<K, V, C>
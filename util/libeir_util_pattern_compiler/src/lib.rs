@@ -130,6 +130,27 @@ fn matrix_to_decision_tree<P>(
     let specialization_types =
         matrix.collect_specialization_types(&ctx.pattern, specialize_variable);
 
+    // If one of the specializations includes every clause, there is
+    // nothing left for the others to match.
+    let includes_all = specialization_types
+        .iter()
+        .cloned()
+        .find(|kind| ctx.pattern.kind_includes_all(*kind));
+    if let Some(specialization) = includes_all {
+        let (introduced, specialized) = matrix.specialize(ctx, specialize_variable, specialization);
+
+        matrix_to_decision_tree(
+            cfg_node,
+            ctx,
+            Some(specialization),
+            &specialized,
+            introduced,
+            level + 1,
+        );
+
+        return;
+    }
+
     // Specialize on specific matrices
     for specialization in specialization_types.iter() {
         let (introduced, specialized) =
@@ -38,6 +38,14 @@ pub trait PatternProvider: Debug {
     /// `PatternNodeKind`.
     fn kind_includes(&self, kind: Self::PatternNodeKind, key: Self::PatternNodeKey) -> bool;
 
+    /// Used to determine if the specialization on `kind` includes every
+    /// clause. When a variable has such a kind, it is the only
+    /// specialization made on it, the other kinds and the default
+    /// matrix are never reached.
+    fn kind_includes_all(&self, _kind: Self::PatternNodeKind) -> bool {
+        false
+    }
+
     /// After clauses have been selected for specialization, this will
     /// be called with the set of all nodes that should be specialized on.
     ///